@@ -0,0 +1,868 @@
+// Copyright (c) 2025 air (https://yuanair.github.io).
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published by
+// the Free Software Foundation, version 3 of the License only.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+pub mod cfg;
+use crate::{
+    attr::cfg::CfgPredicate,
+    lexer::token::{Literal, OperatorKind, Token},
+    parser::{
+        Parser,
+        ast::{
+            AttrArgs,
+            AttrStyle,
+            Attribute,
+            Delimiter,
+            Expr,
+            ExprKind,
+            Item,
+            ItemKind,
+            Module,
+            Path,
+            Stmt,
+            StmtKind,
+            TokenTree,
+            VariantData,
+        },
+        visit::{self, MutVisitor},
+    },
+    utils::{
+        diagnostic::{Diagnostic, Level},
+        error::{Error, Result},
+        span::{Span, Spanned},
+    },
+};
+/// a built-in attribute, see [BUILTIN_ATTRIBUTES]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BuiltinAttribute {
+    pub name:     &'static str,
+    /// the accepted forms, e.g. `#[inline]` or `#[inline(always|never)]`
+    pub template: &'static str,
+    /// `None` if it may be written anywhere
+    pub targets:  Option<&'static [Target]>,
+    /// may be written as `#![...]`
+    pub inner:    bool,
+}
+/// what an attribute is written on
+#[derive(Debug, Clone, Copy, Hash, PartialEq, Eq)]
+pub enum Target {
+    Fn,
+    Struct,
+    Enum,
+    Union,
+    Trait,
+    Impl,
+    /// including the inner attributes of a file
+    Mod,
+    Use,
+    Const,
+    Static,
+    TypeAlias,
+    Extern,
+    MacroDef,
+    MacroCall,
+    Field,
+    Variant,
+    Stmt,
+    Expr,
+    Arm,
+}
+/// a checked built-in attribute, see [BuiltinAttr::parse]
+#[derive(Debug, Clone, PartialEq)]
+pub enum BuiltinAttr {
+    /// `#[test]`
+    Test,
+    /// e.g. `#[inline(always)]`
+    Inline(InlineHint),
+    /// e.g. `#[cfg(test)]`
+    Cfg(CfgPredicate),
+    /// e.g. `#[repr(C, u8)]`
+    Repr(Vec<Spanned<Repr>>),
+    /// e.g. `#[derive(Clone, Copy)]`
+    Derive(Vec<Path>),
+    /// e.g. `#[allow(unused)]`
+    Lint(LintLevel, Vec<Path>),
+    /// e.g. `#[deprecated(since = "0.2", note = "use b")]`
+    Deprecated {
+        since: Option<Spanned<String>>,
+        note:  Option<Spanned<String>>,
+    },
+}
+/// see [BuiltinAttr::Inline]
+#[derive(Debug, Clone, Copy, Hash, PartialEq, Eq)]
+pub enum InlineHint {
+    /// `#[inline]`
+    Hint,
+    /// `#[inline(always)]`
+    Always,
+    /// `#[inline(never)]`
+    Never,
+}
+/// see [BuiltinAttr::Repr]
+#[derive(Debug, Clone, Copy, Hash, PartialEq, Eq)]
+pub enum Repr {
+    C,
+    Transparent,
+    Packed,
+    /// the discriminant type of an enum, e.g. `u8`
+    Int(&'static str),
+}
+/// see [BuiltinAttr::Lint]
+#[derive(Debug, Clone, Copy, Hash, Ord, PartialOrd, PartialEq, Eq)]
+pub enum LintLevel {
+    Allow,
+    Warn,
+    Deny,
+}
+/// see [BuiltinAttr::Deprecated]
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct Deprecation {
+    pub since: Option<String>,
+    pub note:  Option<String>,
+}
+/// the levels `#[allow(...)]`, `#[warn(...)]` and `#[deny(...)]` set for
+/// the lints in the items, statements, expressions and arms they are
+/// written on, see [lint_levels]
+#[derive(Debug, Default, Clone)]
+pub struct LintLevels {
+    /// the outer first
+    scopes: Vec<LintScope>,
+}
+#[derive(Debug, Clone)]
+struct LintScope {
+    /// what the attribute is written on
+    span:  Span,
+    lint:  String,
+    level: LintLevel,
+    /// the lint in the attribute
+    attr:  Span,
+}
+const ADT: &[Target] = &[Target::Struct, Target::Enum, Target::Union];
+pub const BUILTIN_ATTRIBUTES: &[BuiltinAttribute] = &[
+    BuiltinAttribute {
+        name:     "test",
+        template: "`#[test]`",
+        targets:  Some(&[Target::Fn]),
+        inner:    false,
+    },
+    BuiltinAttribute {
+        name:     "inline",
+        template: "`#[inline]` or `#[inline(always|never)]`",
+        targets:  Some(&[Target::Fn]),
+        inner:    false,
+    },
+    BuiltinAttribute {
+        name:     "cfg",
+        template: "`#[cfg(predicate)]`",
+        targets:  None,
+        inner:    true,
+    },
+    BuiltinAttribute {
+        name:     "repr",
+        template: "`#[repr(C|transparent|packed|<integer type>, ...)]`",
+        targets:  Some(ADT),
+        inner:    false,
+    },
+    BuiltinAttribute {
+        name:     "derive",
+        template: "`#[derive(Copy)]`",
+        targets:  Some(ADT),
+        inner:    false,
+    },
+    BuiltinAttribute {
+        name:     "allow",
+        template: "`#[allow(lint, ...)]`",
+        targets:  None,
+        inner:    true,
+    },
+    BuiltinAttribute {
+        name:     "warn",
+        template: "`#[warn(lint, ...)]`",
+        targets:  None,
+        inner:    true,
+    },
+    BuiltinAttribute {
+        name:     "deny",
+        template: "`#[deny(lint, ...)]`",
+        targets:  None,
+        inner:    true,
+    },
+    BuiltinAttribute {
+        name:     "deprecated",
+        template: "`#[deprecated]`, `#[deprecated = \"note\"]` or \
+                   `#[deprecated(since = \"version\", note = \"note\")]`",
+        targets:  Some(&[
+            Target::Fn,
+            Target::Struct,
+            Target::Enum,
+            Target::Union,
+            Target::Trait,
+            Target::Mod,
+            Target::Const,
+            Target::Static,
+            Target::TypeAlias,
+            Target::MacroDef,
+            Target::Field,
+            Target::Variant,
+        ]),
+        inner:    false,
+    },
+];
+/// the lints of [Diagnostic::with_lint], `warnings` is every lint,
+/// `dead_code` and the `unused` lints of Rust are known but never emitted
+pub const LINTS: &[&str] = &[
+    "dead_code",
+    "deprecated",
+    "irrefutable_let_patterns",
+    "unknown_lints",
+    "unreachable_patterns",
+    "unused",
+    "unused_imports",
+    "unused_mut",
+    "unused_variables",
+    "warnings",
+];
+/// the traits of `#[derive(...)]`, `Copy` makes a struct, an enum or a union
+/// copied instead of moved
+pub const DERIVES: &[&str] = &["Copy"];
+const REPR_INTS: [&str; 12] = [
+    "i8", "i16", "i32", "i64", "i128", "isize", "u8", "u16", "u32", "u64",
+    "u128", "usize",
+];
+/// e.g. `builtin("inline")`
+pub fn builtin(name: &str) -> Option<&'static BuiltinAttribute> {
+    BUILTIN_ATTRIBUTES.iter().find(|builtin| builtin.name == name)
+}
+/// reports unknown, malformed and misplaced attributes
+pub fn check_attributes(module: &mut Module) -> Vec<Diagnostic> {
+    let mut checker = Checker { diagnostics: Vec::new() };
+    checker.visit_module(module);
+    checker.diagnostics
+}
+/// the [Deprecation] of an item, a field or a variant with `attrs`
+pub fn deprecation(attrs: &[Attribute]) -> Option<Deprecation> {
+    attrs.iter().find_map(|attr| match BuiltinAttr::parse(attr)? {
+        Ok(BuiltinAttr::Deprecated { since, note }) => Some(Deprecation {
+            since: since.map(|since| since.node),
+            note:  note.map(|note| note.node),
+        }),
+        _ => None,
+    })
+}
+/// the span of `trait_` in a `#[derive(...)]` of `attrs`, e.g. `Copy`
+pub fn derive(attrs: &[Attribute], trait_: &str) -> Option<Span> {
+    attrs.iter().find_map(|attr| match BuiltinAttr::parse(attr)? {
+        Ok(BuiltinAttr::Derive(paths)) => paths
+            .into_iter()
+            .find(|path| path_to_string(path) == trait_)
+            .map(|path| path.span),
+        _ => None,
+    })
+}
+/// the [LintLevels] of `module`, after expansion
+pub fn lint_levels(module: &mut Module) -> LintLevels {
+    let mut levels = LintLevels::default();
+    levels.visit_module(module);
+    levels
+}
+impl Target {
+    pub fn of(item: &ItemKind) -> Option<Self> {
+        Some(match item {
+            ItemKind::Fn(_) => Target::Fn,
+            ItemKind::Struct { .. } => Target::Struct,
+            ItemKind::Enum { .. } => Target::Enum,
+            ItemKind::Union { .. } => Target::Union,
+            ItemKind::Trait { .. } => Target::Trait,
+            ItemKind::Impl { .. } => Target::Impl,
+            ItemKind::Mod { .. } => Target::Mod,
+            ItemKind::Use(_) => Target::Use,
+            ItemKind::Const { .. } => Target::Const,
+            ItemKind::Static { .. } => Target::Static,
+            ItemKind::TypeAlias { .. } => Target::TypeAlias,
+            ItemKind::Extern { .. } => Target::Extern,
+            ItemKind::Marco(_) => Target::MacroDef,
+            ItemKind::MacCall(_) => Target::MacroCall,
+            ItemKind::Err => return None,
+        })
+    }
+
+    /// e.g. `a function`
+    pub fn description(&self) -> &'static str {
+        match self {
+            Target::Fn => "a function",
+            Target::Struct => "a struct",
+            Target::Enum => "an enum",
+            Target::Union => "a union",
+            Target::Trait => "a trait",
+            Target::Impl => "an impl block",
+            Target::Mod => "a module",
+            Target::Use => "a use item",
+            Target::Const => "a constant",
+            Target::Static => "a static",
+            Target::TypeAlias => "a type alias",
+            Target::Extern => "an extern block",
+            Target::MacroDef => "a macro definition",
+            Target::MacroCall => "a macro call",
+            Target::Field => "a field",
+            Target::Variant => "a variant",
+            Target::Stmt => "a statement",
+            Target::Expr => "an expression",
+            Target::Arm => "a match arm",
+        }
+    }
+}
+impl BuiltinAttr {
+    /// `None` if `attr` is not built-in
+    pub fn parse(attr: &Attribute) -> Option<Result<Self>> {
+        let name = attr.path.as_ident()?.node.as_str();
+        builtin(name).map(|builtin| Self::parse_builtin(attr, builtin))
+    }
+
+    fn parse_builtin(
+        attr: &Attribute, builtin: &BuiltinAttribute,
+    ) -> Result<Self> {
+        let malformed = || {
+            Error::new(
+                attr.span,
+                format!("malformed `{}` attribute input", builtin.name),
+            )
+        };
+        let list = || match &attr.args {
+            AttrArgs::Delimited { delim: Delimiter::Paren, tokens, .. } => {
+                Ok(split_list(tokens))
+            }
+            _ => Err(malformed()),
+        };
+        let ident = |tts: &[TokenTree]| match tts {
+            [
+                TokenTree::Token(Spanned {
+                    node: Token::Identifier(ident),
+                    span,
+                }),
+            ] => Ok(Spanned::new(ident.as_str().to_string(), *span)),
+            // the integer types of `#[repr(u8)]`
+            [
+                TokenTree::Token(Spanned {
+                    node: Token::Keyword(keyword),
+                    span,
+                }),
+            ] => Ok(Spanned::new(keyword.as_str().to_string(), *span)),
+            _ => Err(malformed()),
+        };
+        let paths = || {
+            list()?
+                .into_iter()
+                .map(|tts| {
+                    let mut parser = Parser::new(TokenTree::flatten(tts));
+                    match parser.parse_type_path() {
+                        Ok(path) if parser.is_eof() => Ok(path),
+                        _ => Err(malformed()),
+                    }
+                })
+                .collect::<Result<Vec<_>>>()
+        };
+        match builtin.name {
+            "test" => match attr.args {
+                AttrArgs::Empty => Ok(BuiltinAttr::Test),
+                _ => Err(malformed()),
+            },
+            "inline" => match attr.args {
+                AttrArgs::Empty => Ok(BuiltinAttr::Inline(InlineHint::Hint)),
+                _ => match list()?.as_slice() {
+                    [hint] => match ident(hint)?.node.as_str() {
+                        "always" => Ok(BuiltinAttr::Inline(InlineHint::Always)),
+                        "never" => Ok(BuiltinAttr::Inline(InlineHint::Never)),
+                        _ => Err(malformed()),
+                    },
+                    _ => Err(malformed()),
+                },
+            },
+            "cfg" => CfgPredicate::from_attr(attr).map(BuiltinAttr::Cfg),
+            "repr" => {
+                let reprs = list()?
+                    .into_iter()
+                    .map(|tts| {
+                        let repr = ident(tts)?;
+                        let node = match repr.node.as_str() {
+                            "C" => Repr::C,
+                            "transparent" => Repr::Transparent,
+                            "packed" => Repr::Packed,
+                            int => {
+                                match REPR_INTS.iter().find(|i| **i == int) {
+                                    Some(int) => Repr::Int(int),
+                                    None => {
+                                        return Err(Error::new(
+                                            repr.span,
+                                            format!(
+                                                "unrecognized representation \
+                                                 `{int}`"
+                                            ),
+                                        ));
+                                    }
+                                }
+                            }
+                        };
+                        Ok(Spanned::new(node, repr.span))
+                    })
+                    .collect::<Result<Vec<_>>>()?;
+                match reprs.is_empty() {
+                    true => Err(malformed()),
+                    false => Ok(BuiltinAttr::Repr(reprs)),
+                }
+            }
+            "derive" => paths().map(BuiltinAttr::Derive),
+            "allow" => {
+                paths().map(|paths| BuiltinAttr::Lint(LintLevel::Allow, paths))
+            }
+            "warn" => {
+                paths().map(|paths| BuiltinAttr::Lint(LintLevel::Warn, paths))
+            }
+            "deny" => {
+                paths().map(|paths| BuiltinAttr::Lint(LintLevel::Deny, paths))
+            }
+            "deprecated" => match &attr.args {
+                AttrArgs::Empty => {
+                    Ok(BuiltinAttr::Deprecated { since: None, note: None })
+                }
+                AttrArgs::Eq(Spanned { node: Literal::String(note), span }) => {
+                    Ok(BuiltinAttr::Deprecated {
+                        since: None,
+                        note:  Some(Spanned::new(note.clone(), *span)),
+                    })
+                }
+                AttrArgs::Eq(_) => Err(malformed()),
+                AttrArgs::Delimited { .. } => {
+                    let (mut since, mut note) = (None, None);
+                    for tts in list()? {
+                        let [
+                            key,
+                            TokenTree::Token(eq),
+                            TokenTree::Token(value),
+                        ] = tts
+                        else {
+                            return Err(malformed());
+                        };
+                        let (
+                            Token::Operator(eq),
+                            Token::Literal(Literal::String(value_text)),
+                        ) = (&eq.node, &value.node)
+                        else {
+                            return Err(malformed());
+                        };
+                        let slot = match ident(std::slice::from_ref(key))?
+                            .node
+                            .as_str()
+                        {
+                            "since" => &mut since,
+                            "note" => &mut note,
+                            _ => return Err(malformed()),
+                        };
+                        if eq.kind() != OperatorKind::Eq || slot.is_some() {
+                            return Err(malformed());
+                        }
+                        *slot =
+                            Some(Spanned::new(value_text.clone(), value.span));
+                    }
+                    Ok(BuiltinAttr::Deprecated { since, note })
+                }
+            },
+            _ => unreachable!("every built-in attribute is handled"),
+        }
+    }
+}
+impl Deprecation {
+    /// the warning for a use of the deprecated `descr` `name` at `span`,
+    /// e.g. the function `f`
+    pub fn warning(&self, descr: &str, name: &str, span: Span) -> Diagnostic {
+        let mut message = format!("use of deprecated {descr} `{name}`");
+        if let Some(note) = &self.note {
+            message += &format!(": {note}");
+        }
+        Diagnostic::warning(message)
+            .with_lint("deprecated")
+            .with_primary(span, "")
+    }
+}
+impl LintLevels {
+    /// `diagnostic` at the level of its lint where it is reported, the
+    /// innermost attribute wins, [None] if the lint is allowed there
+    pub fn apply(&self, diagnostic: Diagnostic) -> Option<Diagnostic> {
+        let (Some(lint), Some(span)) =
+            (diagnostic.lint(), diagnostic.primary_span())
+        else {
+            return Some(diagnostic);
+        };
+        let scope = self
+            .scopes
+            .iter()
+            .rev()
+            .filter(|scope| scope.lint == lint || scope.lint == "warnings")
+            .filter(|scope| scope.span.contains(span))
+            .min_by_key(|scope| scope.span.len());
+        let Some(scope) = scope else {
+            return Some(diagnostic);
+        };
+        let level = match scope.level {
+            LintLevel::Allow => return None,
+            LintLevel::Warn => Level::Warning,
+            LintLevel::Deny => Level::Error,
+        };
+        Some(
+            diagnostic
+                .with_level(level)
+                .with_secondary(scope.attr, "the lint level is defined here"),
+        )
+    }
+
+    fn push(&mut self, attrs: &[Attribute], span: Span) {
+        for attr in attrs {
+            let Some(Ok(BuiltinAttr::Lint(level, paths))) =
+                BuiltinAttr::parse(attr)
+            else {
+                continue;
+            };
+            for path in paths {
+                let lint = path_to_string(&path);
+                self.scopes.push(LintScope {
+                    span,
+                    lint,
+                    level,
+                    attr: path.span,
+                });
+            }
+        }
+    }
+}
+impl MutVisitor for LintLevels {
+    fn visit_module(&mut self, module: &mut Module) {
+        self.push(&module.attrs, module.span);
+        visit::walk_module(self, module);
+    }
+
+    fn visit_item(&mut self, item: &mut Item) {
+        self.push(&item.attrs, item.span);
+        visit::walk_item(self, item);
+    }
+
+    fn visit_stmt(&mut self, stmt: &mut Stmt) {
+        if let StmtKind::Let(local) = &stmt.kind {
+            self.push(&local.attrs, stmt.span);
+        }
+        visit::walk_stmt(self, stmt);
+    }
+
+    fn visit_expr(&mut self, expr: &mut Expr) {
+        self.push(&expr.attrs, expr.span);
+        if let ExprKind::Match { arms, .. } = &expr.kind {
+            for arm in arms {
+                self.push(&arm.attrs, arm.span);
+            }
+        }
+        visit::walk_expr(self, expr);
+    }
+}
+/// splits `a, b(c, d), e` at the top-level commas, a trailing comma is
+/// allowed
+pub(crate) fn split_list(tts: &[TokenTree]) -> Vec<&[TokenTree]> {
+    let mut list: Vec<_> = tts
+        .split(|tt| {
+            matches!(
+                tt,
+                TokenTree::Token(Spanned { node: Token::Operator(op), .. })
+                    if op.kind() == OperatorKind::Comma
+            )
+        })
+        .collect();
+    if list.last().is_some_and(|tts| tts.is_empty()) {
+        list.pop();
+    }
+    list
+}
+fn path_to_string(path: &Path) -> String {
+    path.segments
+        .iter()
+        .map(|segment| segment.ident.node.as_str())
+        .collect::<Vec<_>>()
+        .join("::")
+}
+struct Checker {
+    diagnostics: Vec<Diagnostic>,
+}
+impl Checker {
+    fn check(&mut self, attrs: &[Attribute], target: Target) {
+        for attr in attrs {
+            let Some(builtin) = attr
+                .path
+                .as_ident()
+                .and_then(|name| builtin(name.node.as_str()))
+            else {
+                self.diagnostics.push(
+                    Diagnostic::error(format!(
+                        "cannot find attribute `{}` in this scope",
+                        path_to_string(&attr.path)
+                    ))
+                    .with_primary(attr.path.span, ""),
+                );
+                continue;
+            };
+            let name = builtin.name;
+            if attr.style == AttrStyle::Inner && !builtin.inner {
+                self.diagnostics.push(
+                    Diagnostic::error(format!(
+                        "`{name}` cannot be used as an inner attribute"
+                    ))
+                    .with_primary(attr.span, "")
+                    .with_help(format!("use an outer attribute: `#[{name}]`")),
+                );
+            }
+            if let Some(targets) = builtin.targets
+                && !targets.contains(&target)
+            {
+                let expected = targets
+                    .iter()
+                    .map(Target::description)
+                    .collect::<Vec<_>>()
+                    .join(", ");
+                self.diagnostics.push(
+                    Diagnostic::error(format!(
+                        "`#[{name}]` cannot be applied to {}",
+                        target.description()
+                    ))
+                    .with_primary(attr.span, "")
+                    .with_note(format!("`#[{name}]` applies to: {expected}")),
+                );
+            }
+            match BuiltinAttr::parse(attr) {
+                Some(Err(error)) => {
+                    self.diagnostics.push(Diagnostic::from(error).with_help(
+                        format!("must be of the form: {}", builtin.template),
+                    ));
+                }
+                Some(Ok(BuiltinAttr::Lint(_, paths))) => {
+                    for path in paths {
+                        let lint = path_to_string(&path);
+                        if LINTS.contains(&lint.as_str()) {
+                            continue;
+                        }
+                        self.diagnostics.push(
+                            Diagnostic::warning(format!(
+                                "unknown lint: `{lint}`"
+                            ))
+                            .with_lint("unknown_lints")
+                            .with_primary(path.span, ""),
+                        );
+                    }
+                }
+                Some(Ok(BuiltinAttr::Derive(paths))) => {
+                    for path in paths {
+                        let name = path_to_string(&path);
+                        if DERIVES.contains(&name.as_str()) {
+                            continue;
+                        }
+                        self.diagnostics.push(
+                            Diagnostic::error(format!(
+                                "cannot find derive macro `{name}` in this \
+                                 scope"
+                            ))
+                            .with_primary(path.span, "")
+                            .with_note(format!(
+                                "the traits that can be derived are: {}",
+                                DERIVES.join(", ")
+                            )),
+                        );
+                    }
+                }
+                _ => {}
+            }
+        }
+    }
+
+    fn check_fields(&mut self, data: &VariantData) {
+        if let VariantData::Struct(fields) | VariantData::Tuple(fields) = data {
+            for field in fields {
+                self.check(&field.attrs, Target::Field);
+            }
+        }
+    }
+}
+impl MutVisitor for Checker {
+    fn visit_item(&mut self, item: &mut Item) {
+        if let Some(target) = Target::of(&item.kind) {
+            self.check(&item.attrs, target);
+        }
+        match &item.kind {
+            ItemKind::Struct { data, .. } => self.check_fields(data),
+            ItemKind::Enum { variants, .. } => {
+                for variant in variants {
+                    self.check(&variant.attrs, Target::Variant);
+                    self.check_fields(&variant.data);
+                }
+            }
+            ItemKind::Union { fields, .. } => {
+                for field in fields {
+                    self.check(&field.attrs, Target::Field);
+                }
+            }
+            _ => {}
+        }
+        visit::walk_item(self, item);
+    }
+
+    fn visit_module(&mut self, module: &mut Module) {
+        self.check(&module.attrs, Target::Mod);
+        visit::walk_module(self, module);
+    }
+
+    fn visit_stmt(&mut self, stmt: &mut Stmt) {
+        if let StmtKind::Let(local) = &stmt.kind {
+            self.check(&local.attrs, Target::Stmt);
+        }
+        visit::walk_stmt(self, stmt);
+    }
+
+    fn visit_expr(&mut self, expr: &mut Expr) {
+        self.check(&expr.attrs, Target::Expr);
+        if let ExprKind::Match { arms, .. } = &expr.kind {
+            for arm in arms {
+                self.check(&arm.attrs, Target::Arm);
+            }
+        }
+        visit::walk_expr(self, expr);
+    }
+}
+#[cfg(test)]
+mod tests {
+    use super::{cfg::Cfg, *};
+    use crate::{
+        expand::Expander,
+        lexer::scanner::{AnnasulScanner, Scanner},
+    };
+    fn expand(source: &str, cfg: Cfg) -> (Module, Vec<String>) {
+        let (tokens, errors) =
+            AnnasulScanner::new(source.as_bytes()).tokenize();
+        assert_eq!(errors, []);
+        let mut parser = Parser::new(tokens).with_source(source);
+        let mut module = parser.parse_module();
+        assert_eq!(parser.take_errors(), []);
+        let mut expander = Expander::new().with_source(source).with_cfg(cfg);
+        expander.expand_module(&mut module);
+        let mut diagnostics = expander.take_diagnostics();
+        diagnostics.extend(check_attributes(&mut module));
+        let messages = diagnostics
+            .iter()
+            .map(|diagnostic| diagnostic.message().to_string())
+            .collect();
+        (module, messages)
+    }
+    fn names(items: &[Item]) -> Vec<String> {
+        items
+            .iter()
+            .filter_map(|item| match &item.kind {
+                ItemKind::Fn(def) => Some(def.name.node.to_string()),
+                ItemKind::Struct { name, .. } | ItemKind::Mod { name, .. } => {
+                    Some(name.node.to_string())
+                }
+                _ => None,
+            })
+            .collect()
+    }
+    #[test]
+    fn cfg() {
+        assert_eq!(Cfg::parse_option("test"), Ok(("test".into(), None)));
+        assert_eq!(
+            Cfg::parse_option("feature = \"a\""),
+            Ok(("feature".into(), Some("a".into())))
+        );
+        assert!(Cfg::parse_option("feature = a").is_err());
+        let source = "
+            #[cfg(test)] fn only_test() {}
+            #[cfg(not(test))] fn not_test() {}
+            #[cfg(all(test, feature = \"a\"))] fn both() {}
+            #[cfg(any())] fn never() {}
+            mod m { #![cfg(not(test))] }
+            struct S { #[cfg(test)] a: i32, b: i32 }
+            marco m { () => { #[cfg(test)] fn expanded() {} } }
+            m!();
+            fn main() {
+                #[cfg(not(test))] let a = 1;
+                let v = [1, #[cfg(test)] 2, 3];
+            }";
+        let (module, messages) = expand(source, Cfg::new().with("test", None));
+        assert_eq!(messages, [""; 0]);
+        assert_eq!(names(&module.items), [
+            "only_test", "S", "expanded", "main"
+        ]);
+        let ItemKind::Struct { data: VariantData::Struct(fields), .. } =
+            &module.items[1].kind
+        else {
+            panic!("{:?}", module.items[1])
+        };
+        assert_eq!(fields.len(), 2);
+        let (module, _) = expand(source, Cfg::new().with("feature", Some("a")));
+        assert_eq!(names(&module.items), ["not_test", "m", "S", "main"]);
+        let ItemKind::Fn(def) = &module.items.last().unwrap().kind else {
+            panic!()
+        };
+        let stmts = &def.body.as_ref().unwrap().stmts;
+        assert_eq!(stmts.len(), 2);
+        let StmtKind::Let(local) = &stmts[1].kind else { panic!() };
+        let Some(ExprKind::Array(elements)) =
+            local.init.as_ref().map(|init| &init.kind)
+        else {
+            panic!("{local:?}")
+        };
+        assert_eq!(elements.len(), 2);
+        assert_eq!(
+            expand(
+                "fn main() { 1 + #[cfg(test)] 2; }
+                #[cfg(test = 1)] fn f() {}
+                #[cfg(a, b)] fn g() {}",
+                Cfg::new()
+            )
+            .1,
+            [
+                "expected a string literal",
+                "`cfg` takes exactly one predicate",
+                "removing an expression is not supported in this position",
+                "expected a string literal",
+                "`cfg` takes exactly one predicate",
+            ]
+        );
+    }
+    #[test]
+    fn builtin_attributes() {
+        let (_, messages) = expand(
+            "#![allow(unused, frobnicate)]
+            #![inline]
+            #[test] #[inline(always)] #[deprecated = \"use g\"] fn f() {}
+            #[inline(sometimes)] fn g() {}
+            #[derive(Copy, Debug)] #[repr(C, u8)] enum E { #[deprecated] A }
+            #[derive(Copy)] fn h() {}
+            #[repr(i7)] struct S;
+            #[deprecated(since = \"1\", note = \"x\", note = \"y\")] struct T;
+            #[frobnicate] #[tool::attr] fn i() { #[test] let a = 1; }",
+            Cfg::new(),
+        );
+        assert_eq!(messages, [
+            "unknown lint: `frobnicate`",
+            "`inline` cannot be used as an inner attribute",
+            "`#[inline]` cannot be applied to a module",
+            "malformed `inline` attribute input",
+            "cannot find derive macro `Debug` in this scope",
+            "`#[derive]` cannot be applied to a function",
+            "unrecognized representation `i7`",
+            "malformed `deprecated` attribute input",
+            "cannot find attribute `frobnicate` in this scope",
+            "cannot find attribute `tool::attr` in this scope",
+            "`#[test]` cannot be applied to a statement",
+        ]);
+    }
+}
@@ -0,0 +1,334 @@
+// Copyright (c) 2025 air (https://yuanair.github.io).
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published by
+// the Free Software Foundation, version 3 of the License only.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+use std::collections::BTreeSet;
+
+use crate::{
+    attr::split_list,
+    lexer::token::{Literal, OperatorKind, Token},
+    parser::{
+        ast::{
+            AttrArgs,
+            Attribute,
+            Delimiter,
+            Expr,
+            ExprKind,
+            Ident,
+            Item,
+            ItemKind,
+            Module,
+            Stmt,
+            StmtKind,
+            TokenTree,
+            VariantData,
+        },
+        visit::{self, MutVisitor},
+    },
+    utils::{
+        error::{Error, Result},
+        span::Spanned,
+    },
+};
+/// the options `#[cfg(...)]` is evaluated against, e.g. from `--cfg test`
+/// and `--cfg 'feature = "a"'`
+#[derive(Default, Debug, Clone, PartialEq, Eq)]
+pub struct Cfg {
+    /// e.g. `("test", None)` and `("feature", Some("a"))`
+    options: BTreeSet<(String, Option<String>)>,
+}
+/// e.g. `test`, `feature = "a"`, `all(unix, not(test))`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CfgPredicate {
+    /// e.g. `test`, `feature = "a"`
+    Option(Ident, Option<Spanned<String>>),
+    /// `all(...)`, true if empty
+    All(Vec<CfgPredicate>),
+    /// `any(...)`, false if empty
+    Any(Vec<CfgPredicate>),
+    /// `not(...)`
+    Not(Box<CfgPredicate>),
+}
+/// removes the items, fields, variants, statements, match arms and
+/// elements of lists whose `#[cfg(...)]` does not hold
+pub struct StripUnconfigured<'a> {
+    cfg:    &'a Cfg,
+    errors: Vec<Error>,
+}
+impl Cfg {
+    pub fn new() -> Self { Self::default() }
+
+    /// sets `name`, or `name = "value"`
+    pub fn insert(&mut self, name: impl Into<String>, value: Option<String>) {
+        self.options.insert((name.into(), value));
+    }
+
+    pub fn with(
+        mut self, name: impl Into<String>, value: Option<&str>,
+    ) -> Self {
+        self.insert(name, value.map(str::to_string));
+        self
+    }
+
+    pub fn is_set(&self, name: &str, value: Option<&str>) -> bool {
+        self.options.contains(&(name.to_string(), value.map(str::to_string)))
+    }
+
+    /// e.g. `test` or `feature = "a"` of `--cfg`
+    pub fn parse_option(
+        option: &str,
+    ) -> std::result::Result<(String, Option<String>), String> {
+        let invalid = || {
+            format!(
+                "invalid `--cfg` argument: `{option}` (expected `name` or \
+                 `name = \"value\"`)"
+            )
+        };
+        let (name, value) = match option.split_once('=') {
+            Some((name, value)) => {
+                let value = value.trim();
+                let value = value
+                    .strip_prefix('"')
+                    .and_then(|value| value.strip_suffix('"'))
+                    .filter(|value| !value.contains('"'))
+                    .ok_or_else(invalid)?;
+                (name.trim(), Some(value.to_string()))
+            }
+            None => (option.trim(), None),
+        };
+        let mut chars = name.chars();
+        let is_ident =
+            chars.next().is_some_and(|c| c == '_' || c.is_alphabetic())
+                && chars.all(|c| c == '_' || c.is_alphanumeric());
+        match is_ident {
+            true => Ok((name.to_string(), value)),
+            false => Err(invalid()),
+        }
+    }
+
+    /// every `#[cfg(...)]` of `attrs` holds
+    pub fn matches(&self, attrs: &[Attribute]) -> Result<bool> {
+        for attr in attrs {
+            if attr
+                .path
+                .as_ident()
+                .is_some_and(|name| name.node.as_str() == "cfg")
+                && !CfgPredicate::from_attr(attr)?.eval(self)
+            {
+                return Ok(false);
+            }
+        }
+        Ok(true)
+    }
+}
+impl CfgPredicate {
+    /// the predicate of `#[cfg(...)]`
+    pub fn from_attr(attr: &Attribute) -> Result<Self> {
+        match &attr.args {
+            AttrArgs::Delimited { delim: Delimiter::Paren, tokens, span } => {
+                match split_list(tokens).as_slice() {
+                    [predicate] => Self::parse(predicate),
+                    _ => Err(Error::new(
+                        *span,
+                        "`cfg` takes exactly one predicate",
+                    )),
+                }
+            }
+            _ => Err(Error::new(attr.span, "malformed `cfg` attribute input")),
+        }
+    }
+
+    /// e.g. the tokens of `all(a, not(b))`
+    pub fn parse(tts: &[TokenTree]) -> Result<Self> {
+        let expected = |span| Error::new(span, "expected a `cfg` predicate");
+        let (name, span) = match tts.first() {
+            Some(TokenTree::Token(Spanned {
+                node: Token::Identifier(name),
+                span,
+            })) => (name, *span),
+            Some(tt) => return Err(expected(tt.span())),
+            None => return Err(expected(Default::default())),
+        };
+        let ident = Spanned::new(name.clone(), span);
+        match &tts[1..] {
+            [] => Ok(CfgPredicate::Option(ident, None)),
+            [TokenTree::Token(eq), TokenTree::Token(value)]
+                if matches!(
+                    &eq.node,
+                    Token::Operator(op) if op.kind() == OperatorKind::Eq
+                ) =>
+            {
+                match &value.node {
+                    Token::Literal(Literal::String(text)) => {
+                        let value = Spanned::new(text.clone(), value.span);
+                        Ok(CfgPredicate::Option(ident, Some(value)))
+                    }
+                    _ => {
+                        Err(Error::new(value.span, "expected a string literal"))
+                    }
+                }
+            }
+            [TokenTree::Delimited { delim: Delimiter::Paren, tts, .. }] => {
+                let predicates = split_list(tts)
+                    .into_iter()
+                    .map(Self::parse)
+                    .collect::<Result<Vec<_>>>()?;
+                match name.as_str() {
+                    "all" => Ok(CfgPredicate::All(predicates)),
+                    "any" => Ok(CfgPredicate::Any(predicates)),
+                    "not" => match <[_; 1]>::try_from(predicates) {
+                        Ok([predicate]) => {
+                            Ok(CfgPredicate::Not(Box::new(predicate)))
+                        }
+                        Err(_) => Err(Error::new(
+                            span,
+                            "`not` takes exactly one predicate",
+                        )),
+                    },
+                    _ => Err(Error::new(
+                        span,
+                        format!("invalid predicate `{name}`"),
+                    )),
+                }
+            }
+            [tt, ..] => Err(Error::new(
+                tt.span(),
+                "expected `=`, `(` or the end of the predicate",
+            )),
+        }
+    }
+
+    pub fn eval(&self, cfg: &Cfg) -> bool {
+        match self {
+            CfgPredicate::Option(name, value) => cfg.is_set(
+                name.node.as_str(),
+                value.as_ref().map(|value| value.node.as_str()),
+            ),
+            CfgPredicate::All(predicates) => {
+                predicates.iter().all(|predicate| predicate.eval(cfg))
+            }
+            CfgPredicate::Any(predicates) => {
+                predicates.iter().any(|predicate| predicate.eval(cfg))
+            }
+            CfgPredicate::Not(predicate) => !predicate.eval(cfg),
+        }
+    }
+}
+impl<'a> StripUnconfigured<'a> {
+    pub fn new(cfg: &'a Cfg) -> Self { Self { cfg, errors: Vec::new() } }
+
+    /// the malformed `cfg` attributes, which are treated as true
+    pub fn take_errors(&mut self) -> Vec<Error> {
+        std::mem::take(&mut self.errors)
+    }
+
+    /// clears `module` if its `#![cfg(...)]` does not hold, e.g. for a
+    /// whole file
+    pub fn strip_module(&mut self, module: &mut Module) {
+        match self.keep(&module.attrs) {
+            true => self.visit_module(module),
+            false => module.items.clear(),
+        }
+    }
+
+    fn keep(&mut self, attrs: &[Attribute]) -> bool {
+        self.cfg.matches(attrs).unwrap_or_else(|error| {
+            self.errors.push(error);
+            true
+        })
+    }
+
+    fn keep_item(&mut self, item: &Item) -> bool {
+        match &item.kind {
+            // `#![cfg(...)]` at the start of an inline module
+            ItemKind::Mod { module: Some(module), .. } => {
+                self.keep(&item.attrs) && self.keep(&module.attrs)
+            }
+            _ => self.keep(&item.attrs),
+        }
+    }
+
+    fn keep_stmt(&mut self, stmt: &Stmt) -> bool {
+        match &stmt.kind {
+            StmtKind::Expr(expr) | StmtKind::Semi(expr) => {
+                self.keep(&expr.attrs)
+            }
+            StmtKind::Empty => true,
+            StmtKind::Item(item) => self.keep_item(item),
+            StmtKind::Let(local) => self.keep(&local.attrs),
+        }
+    }
+
+    fn strip_exprs(&mut self, exprs: &mut Vec<Expr>) {
+        exprs.retain(|expr| self.keep(&expr.attrs));
+    }
+}
+impl MutVisitor for StripUnconfigured<'_> {
+    fn visit_items(&mut self, items: &mut Vec<Item>) {
+        items.retain(|item| self.keep_item(item));
+        items.iter_mut().for_each(|item| self.visit_item(item));
+    }
+
+    fn visit_item(&mut self, item: &mut Item) {
+        match &mut item.kind {
+            ItemKind::Struct { data, .. } => match data {
+                VariantData::Struct(fields) | VariantData::Tuple(fields) => {
+                    fields.retain(|field| self.keep(&field.attrs))
+                }
+                VariantData::Unit => {}
+            },
+            ItemKind::Enum { variants, .. } => {
+                variants.retain(|variant| self.keep(&variant.attrs));
+                for variant in variants {
+                    if let VariantData::Struct(fields)
+                    | VariantData::Tuple(fields) = &mut variant.data
+                    {
+                        fields.retain(|field| self.keep(&field.attrs));
+                    }
+                }
+            }
+            ItemKind::Union { fields, .. } => {
+                fields.retain(|field| self.keep(&field.attrs))
+            }
+            _ => {}
+        }
+        visit::walk_item(self, item);
+    }
+
+    fn visit_stmts(&mut self, stmts: &mut Vec<Stmt>) {
+        stmts.retain(|stmt| self.keep_stmt(stmt));
+        stmts.iter_mut().for_each(|stmt| self.visit_stmt(stmt));
+    }
+
+    fn visit_expr(&mut self, expr: &mut Expr) {
+        match &mut expr.kind {
+            ExprKind::Tuple(exprs)
+            | ExprKind::Array(exprs)
+            | ExprKind::Call(_, exprs)
+            | ExprKind::MethodCall { args: exprs, .. } => {
+                self.strip_exprs(exprs)
+            }
+            ExprKind::Format(format) => self.strip_exprs(&mut format.args),
+            ExprKind::Match { arms, .. } => {
+                arms.retain(|arm| self.keep(&arm.attrs))
+            }
+            _ => {}
+        }
+        // the lists above are the only places an expression can be removed
+        if !self.keep(&expr.attrs) {
+            self.errors.push(Error::new(
+                expr.span,
+                "removing an expression is not supported in this position",
+            ));
+        }
+        visit::walk_expr(self, expr);
+    }
+}
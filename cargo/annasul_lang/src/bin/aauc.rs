@@ -10,25 +10,261 @@
 //
 // You should have received a copy of the GNU Affero General Public License
 // along with this program. If not, see <https://www.gnu.org/licenses/>.
-use std::{env::Args, fs::File, io::BufReader, path::PathBuf};
+use std::{
+    fs::File,
+    io::{self, IsTerminal, Write},
+    path::PathBuf,
+};
 
-use annasul_lang::lexer::scanner::{AnnasulScanner, Scanner};
-use clap::{Parser, ValueHint};
+use annasul_lang::{
+    attr::{self, LintLevels, cfg::Cfg},
+    interp::Interpreter,
+    lexer::scanner::{AnnasulScanner, Scanner},
+    semantic::{
+        self,
+        RealFileLoader,
+        borrowck::BorrowChecker,
+        collect::Collector,
+        matchck::MatchChecker,
+        mono::Monomorphizer,
+        resolve::Resolver,
+        typeck::TypeChecker,
+    },
+    types::{TyCtxt, TyKind, layout::Target},
+    utils::{
+        diagnostic::{Diagnostic, Diagnostics, Emitter},
+        span::SourceMap,
+    },
+};
+use clap::{Parser, ValueEnum, ValueHint};
+/// the stack of the thread running the interpreter, see
+/// [STACK_LIMIT](annasul_lang::interp::STACK_LIMIT)
+const INTERPRETER_STACK_SIZE: usize = 1 << 30;
 #[derive(Debug, Parser)]
 struct Cli {
-    #[clap(short, long, value_hint = ValueHint::AnyPath)]
-    output: Option<PathBuf>,
+    /// write the output of `--emit` to a file instead of stdout
+    #[clap(short, long, value_hint = ValueHint::FilePath)]
+    output:        Option<PathBuf>,
+    /// print an intermediate representation, see `--output`
+    #[clap(long, value_enum)]
+    emit:          Option<Emit>,
+    /// colorize diagnostics
+    #[clap(long, value_enum, default_value_t = ColorChoice::Auto)]
+    color:         ColorChoice,
+    /// e.g. `--cfg test` or `--cfg 'feature = "a"'`, see `#[cfg(...)]`
+    #[clap(long, value_name = "SPEC", value_parser = Cfg::parse_option)]
+    cfg:           Vec<(String, Option<String>)>,
+    /// the size of pointers in bits, the host's by default
+    #[clap(long, value_name = "BITS", value_parser = ["32", "64"])]
+    pointer_width: Option<String>,
+    /// run `main` of every crate after checking it
+    #[clap(long)]
+    run:           bool,
+    /// the root file of every crate, e.g. `main.aau`
     #[clap(value_hint = ValueHint::FilePath)]
-    inputs: Vec<PathBuf>,
+    inputs:        Vec<PathBuf>,
+}
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+enum Emit {
+    /// every token with its position
+    Tokens,
+    /// the syntax tree of every crate, after macro expansion
+    Ast,
+    /// the size, the alignment and the field offsets of every struct, enum
+    /// and union
+    Layouts,
+    /// the typed body of every function, constant and static
+    Hir,
+    /// the typed body of every instance of a function, constant and static
+    /// after monomorphization
+    Mono,
+}
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+enum ColorChoice {
+    /// color if stderr is a terminal
+    Auto,
+    Always,
+    Never,
 }
 fn main() {
     let args = Cli::parse();
+    let mut source_map = SourceMap::new();
+    let mut diagnostics = Diagnostics::new();
+    let mut cfg = Cfg::new();
+    for (name, value) in args.cfg {
+        cfg.insert(name, value);
+    }
+    let target = match args.pointer_width.as_deref() {
+        Some("32") => Target::BITS_32,
+        Some(_) => Target::BITS_64,
+        None => Target::host(),
+    };
+    let mut out: Box<dyn Write> = match &args.output {
+        Some(path) => match File::create(path) {
+            Ok(file) => Box::new(io::BufWriter::new(file)),
+            Err(error) => {
+                eprintln!("error: cannot create {}: {error}", path.display());
+                std::process::exit(1);
+            }
+        },
+        None => Box::new(io::stdout()),
+    };
+    let mut panicked = false;
     for input in args.inputs {
-        println!("-----{:?}-----", input);
-        let tokens: Vec<_> =
-            AnnasulScanner::new(BufReader::new(File::open(input).unwrap()))
-                .into_iter()
-                .collect();
-        println!("{:?}", tokens);
+        // the errors of earlier crates do not stop the stages of this one
+        let prior_errors = diagnostics.error_count();
+        let files = source_map.files().count();
+        let (mut module, errors) = semantic::parse_crate(
+            &input, &RealFileLoader, &mut source_map, &cfg,
+        );
+        let lints = match &mut module {
+            Some(module) => attr::lint_levels(module),
+            None => LintLevels::default(),
+        };
+        // the errors of expansion come with their backtraces
+        for diagnostic in errors.into_iter().filter_map(|d| lints.apply(d)) {
+            diagnostics.push(diagnostic);
+        }
+        if args.emit == Some(Emit::Tokens) {
+            for (file, source) in source_map.files().skip(files) {
+                let (tokens, _) =
+                    AnnasulScanner::new(source.source().as_bytes())
+                        .with_file(file)
+                        .tokenize();
+                let name = source.name().display();
+                for token in &tokens {
+                    // line comments end with a newline
+                    let text = token.node.to_string();
+                    writeln!(out, "{name}:{} {}", token.span, text.trim_end())
+                        .unwrap();
+                }
+            }
+        }
+        let Some(mut module) = module else { continue };
+        let mut resolver = Resolver::new();
+        let res = resolver.resolve_crate(&mut module);
+        let stage = resolver.take_diagnostics();
+        report(&mut diagnostics, &source_map, &lints, stage);
+        if args.emit == Some(Emit::Ast) {
+            writeln!(out, "{module:#?}").unwrap();
+        }
+        let mut tcx = TyCtxt::new();
+        let mut collector = Collector::new(&res, &mut tcx);
+        collector.collect_crate(&mut module);
+        let stage = collector.take_diagnostics();
+        report(&mut diagnostics, &source_map, &lints, stage);
+        if args.emit == Some(Emit::Layouts) {
+            let mut adts: Vec<_> = tcx.adts().map(|(id, _)| id).collect();
+            adts.sort();
+            // the layouts of generic ADTs depend on their arguments
+            adts.retain(|id| tcx.generics(*id).is_empty());
+            for id in adts {
+                let ty = tcx.intern(TyKind::Adt(id, Vec::new()));
+                let name = res.def_path(id);
+                match tcx.layout_of(ty, target) {
+                    Ok(layout) => writeln!(
+                        out,
+                        "{name}: size {}, align {}, offsets {:?}",
+                        layout.size, layout.align, layout.offsets
+                    ),
+                    Err(error) => {
+                        writeln!(out, "{name}: {}", error.message(&tcx))
+                    }
+                }
+                .unwrap();
+            }
+        }
+        let mut checker = TypeChecker::new(&res, &mut tcx).with_target(target);
+        let bodies = checker.check_crate(&mut module);
+        let stage = checker.take_diagnostics();
+        report(&mut diagnostics, &source_map, &lints, stage);
+        if args.emit == Some(Emit::Hir) {
+            for body in &bodies {
+                writeln!(out, "{}: {body:#?}", res.def_path(body.owner))
+                    .unwrap();
+            }
+        }
+        if diagnostics.error_count() > prior_errors {
+            continue;
+        }
+        let mut matchck = MatchChecker::new(&mut tcx).with_target(target);
+        matchck.check_crate(&bodies);
+        let stage = matchck.take_diagnostics();
+        report(&mut diagnostics, &source_map, &lints, stage);
+        if diagnostics.error_count() > prior_errors {
+            continue;
+        }
+        let mut borrowck = BorrowChecker::new(&mut tcx);
+        borrowck.check_crate(&bodies);
+        let stage = borrowck.take_diagnostics();
+        report(&mut diagnostics, &source_map, &lints, stage);
+        if diagnostics.error_count() > prior_errors {
+            continue;
+        }
+        let mut monomorphizer = Monomorphizer::new(&res, &mut tcx, &bodies);
+        let items = monomorphizer.collect_crate();
+        let stage = monomorphizer.take_diagnostics();
+        report(&mut diagnostics, &source_map, &lints, stage);
+        if args.emit == Some(Emit::Mono) {
+            for item in &items {
+                let name = item.instance.display(&res, &tcx);
+                writeln!(out, "{name}: {:#?}", item.body).unwrap();
+            }
+        }
+        if !args.run || diagnostics.error_count() > prior_errors {
+            continue;
+        }
+        let Some(main) = Interpreter::find_main(&res) else {
+            diagnostics.push(Diagnostic::error("`main` function not found"));
+            continue;
+        };
+        let run = || {
+            let (mut stdout, mut stderr) =
+                (std::io::stdout(), std::io::stderr());
+            Interpreter::new(&res, &mut tcx, &items, &mut stdout, &mut stderr)
+                .with_target(target)
+                .run(main)
+        };
+        // every interpreted call takes several frames of the host
+        let result = std::thread::scope(|scope| {
+            std::thread::Builder::new()
+                .stack_size(INTERPRETER_STACK_SIZE)
+                .spawn_scoped(scope, run)
+                .unwrap()
+                .join()
+                .unwrap()
+        });
+        if let Err(panic) = result {
+            report(&mut diagnostics, &source_map, &lints, vec![*panic]);
+            panicked = true;
+        }
+    }
+    out.flush().unwrap();
+    let color = match args.color {
+        ColorChoice::Auto => std::io::stderr().is_terminal(),
+        ColorChoice::Always => true,
+        ColorChoice::Never => false,
+    };
+    Emitter::new(color)
+        .emit(&source_map, &diagnostics, &mut std::io::stderr())
+        .unwrap();
+    if panicked {
+        // like a Rust program that panicked
+        std::process::exit(101);
+    }
+    if diagnostics.has_errors() {
+        std::process::exit(1);
+    }
+}
+/// adds the diagnostics of a stage with the backtraces of the expansions
+/// they originate in, at the levels of their lints
+fn report(
+    diagnostics: &mut Diagnostics, source_map: &SourceMap, lints: &LintLevels,
+    stage: Vec<Diagnostic>,
+) {
+    for diagnostic in stage {
+        if let Some(diagnostic) = lints.apply(diagnostic) {
+            diagnostics.push(diagnostic.with_expansions(source_map));
+        }
     }
 }
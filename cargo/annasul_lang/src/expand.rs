@@ -0,0 +1,1351 @@
+// Copyright (c) 2025 air (https://yuanair.github.io).
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published by
+// the Free Software Foundation, version 3 of the License only.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+use std::{
+    collections::{HashMap, HashSet},
+    rc::Rc,
+};
+
+use crate::{
+    attr::cfg::{Cfg, StripUnconfigured},
+    lexer::token::{Identifier, Operator, OperatorKind, Token},
+    parser::{
+        Parser,
+        ast::{
+            Delimiter,
+            Expr,
+            ExprKind,
+            FormatArg,
+            FormatPiece,
+            Ident,
+            Item,
+            ItemKind,
+            MacCall,
+            MacroDef,
+            MacroRule,
+            Module,
+            Pat,
+            PatKind,
+            Stmt,
+            StmtKind,
+            TokenTree,
+        },
+        visit::{self, MutVisitor},
+    },
+    utils::{
+        diagnostic::Diagnostic,
+        error::{Error, Result},
+        span::{Expansion, FileId, Span, Spanned},
+    },
+};
+/// how deep macro calls may expand into further macro calls
+const RECURSION_LIMIT: usize = 64;
+/// replaces the [MacCall]s of a crate by the expansions of its `marco`
+/// definitions, before name resolution
+///
+/// identifiers from a macro body are marked with their expansion, see
+/// [Identifier::mark], and keep the mark only where they bind or refer to
+/// a local binding of the same expansion, so the bindings a macro
+/// introduces are invisible to the code around the call
+#[derive(Debug, Default)]
+pub struct Expander {
+    /// `None` if a rule of the macro is malformed
+    macros:      HashMap<String, Rc<Macro>>,
+    /// see [Parser::with_file_source]
+    sources:     HashMap<FileId, Rc<str>>,
+    /// what `#[cfg(...)]` is evaluated against, see [Expander::with_cfg]
+    cfg:         Cfg,
+    /// the marks start after it, see [Expander::with_first_mark]
+    first_mark:  u32,
+    /// every expansion, by its mark minus `first_mark` minus one
+    expansions:  Vec<Expansion>,
+    /// the expansions in progress, the innermost last
+    stack:       Vec<Frame>,
+    diagnostics: Vec<Diagnostic>,
+}
+#[derive(Debug)]
+struct Macro {
+    name:  Ident,
+    /// the whole `marco` item
+    span:  Span,
+    /// [None] if a rule is malformed, which is already reported
+    rules: Option<Vec<Rule>>,
+}
+#[derive(Debug)]
+struct Rule {
+    matcher: Vec<Matcher>,
+    body:    Vec<Transcriber>,
+}
+/// the left side of a rule
+#[derive(Debug)]
+enum Matcher {
+    Token(Token),
+    Delimited(Delimiter, Vec<Matcher>),
+    /// e.g. `$x:expr`
+    Fragment(Ident, Fragment),
+    /// e.g. `$($x:expr),*`
+    Repeat(Repetition<Matcher>),
+}
+/// the right side of a rule
+#[derive(Debug)]
+enum Transcriber {
+    Token(Spanned<Token>),
+    Delimited {
+        delim: Delimiter,
+        open:  Span,
+        close: Span,
+        body:  Vec<Transcriber>,
+    },
+    /// e.g. `$x`
+    Var(Ident),
+    /// e.g. `$($x * 2),*`
+    Repeat(Repetition<Transcriber>),
+}
+/// e.g. `$(...),*`
+#[derive(Debug)]
+struct Repetition<T> {
+    body: Vec<T>,
+    sep:  Option<Spanned<Token>>,
+    op:   RepeatOp,
+    span: Span,
+}
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum RepeatOp {
+    /// `*`
+    ZeroOrMore,
+    /// `+`
+    OneOrMore,
+    /// `?`
+    ZeroOrOne,
+}
+/// the kind of a `$x:kind` fragment
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Fragment {
+    Expr,
+    Ident,
+    Ty,
+    Pat,
+    Path,
+    Block,
+    Literal,
+    Tt,
+    Item,
+    Lifetime,
+    Stmt,
+}
+/// what a fragment matched, nested once per repetition
+#[derive(Debug, Clone)]
+enum Binding {
+    One(Fragment, Vec<TokenTree>),
+    Seq(Vec<Binding>),
+}
+type Bindings = HashMap<String, Binding>;
+/// an expansion in progress
+#[derive(Debug, Clone)]
+struct Frame {
+    expansion: Expansion,
+    /// see [Macro::span]
+    def_span:  Span,
+    mark:      u32,
+}
+/// where the tokens being matched end
+enum End {
+    /// the end of the arguments, at the closing delimiter of the call
+    Invocation(Span),
+    /// a closing delimiter inside the arguments
+    Close(Spanned<Token>),
+}
+/// the state of matching a call against the rules of a macro
+struct Matching<'a> {
+    expander: &'a Expander,
+    /// the error furthest into the arguments, with its offset
+    furthest: Option<(usize, Error)>,
+}
+impl Expander {
+    pub fn new() -> Self { Self::default() }
+
+    /// see [Parser::with_source], for a crate of a single file
+    pub fn with_source(self, source: impl Into<Rc<str>>) -> Self {
+        self.with_file_source(FileId::default(), source)
+    }
+
+    /// see [Parser::with_file_source]
+    pub fn with_file_source(
+        mut self, file: FileId, source: impl Into<Rc<str>>,
+    ) -> Self {
+        self.sources.insert(file, source.into());
+        self
+    }
+
+    /// removes what `#[cfg(...)]` does not hold for, before collecting the
+    /// `marco` definitions and in every expansion
+    pub fn with_cfg(mut self, cfg: Cfg) -> Self {
+        self.cfg = cfg;
+        self
+    }
+
+    /// marks the expansions after `mark`, so the [Span::expansion]s of
+    /// several crates in one [SourceMap](crate::utils::span::SourceMap) are
+    /// distinct, see [Expander::take_expansions]
+    pub fn with_first_mark(mut self, mark: u32) -> Self {
+        self.first_mark = mark;
+        self
+    }
+
+    /// expands every macro call in `module` with the `marco` definitions
+    /// anywhere in it
+    pub fn expand_module(&mut self, module: &mut Module) {
+        self.strip(|strip| strip.strip_module(module));
+        let mut definitions = Definitions::default();
+        definitions.visit_module(module);
+        for (def, span) in definitions.0 {
+            self.define(def, span);
+        }
+        self.visit_module(module);
+        let mut bindings = MarkedBindings::default();
+        bindings.visit_module(module);
+        Unmark { bindings: &bindings.0 }.visit_module(module);
+    }
+
+    /// the errors of expansion, with a backtrace of the expansions they
+    /// originate in
+    pub fn take_diagnostics(&mut self) -> Vec<Diagnostic> {
+        std::mem::take(&mut self.diagnostics)
+    }
+
+    /// every expansion, by its mark, for
+    /// [SourceMap::add_expansion](crate::utils::span::SourceMap::add_expansion)
+    pub fn take_expansions(&mut self) -> Vec<Expansion> {
+        self.first_mark += self.expansions.len() as u32;
+        std::mem::take(&mut self.expansions)
+    }
+
+    fn define(&mut self, def: MacroDef, span: Span) {
+        let name = def.name.node.as_str().to_string();
+        if let Some(previous) = self.macros.get(&name) {
+            let previous = previous.name.span;
+            self.diagnostics.push(
+                Diagnostic::error(format!(
+                    "the macro `{name}` is defined multiple times"
+                ))
+                .with_primary(
+                    def.name.span,
+                    format!("`{name}!` redefined here"),
+                )
+                .with_secondary(
+                    previous,
+                    format!("previous definition of `{name}!` here"),
+                ),
+            );
+            return;
+        }
+        let rules: Result<Vec<_>> =
+            def.rules.iter().map(Rule::compile).collect();
+        let rules = rules
+            .inspect_err(|error| self.diagnostics.push(error.clone().into()))
+            .ok();
+        self.macros
+            .insert(name, Rc::new(Macro { name: def.name, span, rules }));
+    }
+
+    fn parser(&self, tokens: Vec<Spanned<Token>>) -> Parser {
+        self.sources.iter().fold(
+            Parser::new(tokens),
+            |parser, (file, source)| {
+                parser.with_file_source(*file, source.clone())
+            },
+        )
+    }
+
+    /// reports `error` with the expansions in progress
+    fn error(&mut self, error: Error) {
+        let backtrace: Vec<_> =
+            self.stack.iter().rev().map(|frame| &frame.expansion).collect();
+        let diagnostic = Diagnostic::from(error).with_backtrace(&backtrace);
+        self.diagnostics.push(diagnostic);
+    }
+
+    /// runs `f` with the [Cfg] of the expander, reporting malformed
+    /// `cfg` attributes
+    fn strip(&mut self, f: impl FnOnce(&mut StripUnconfigured)) {
+        let mut strip = StripUnconfigured::new(&self.cfg);
+        f(&mut strip);
+        for error in strip.take_errors() {
+            self.error(error);
+        }
+    }
+
+    /// reports the errors `parser` recovered from
+    fn parser_errors(&mut self, parser: &mut Parser) {
+        for error in parser.take_errors() {
+            self.error(error);
+        }
+    }
+
+    /// the tokens `mac` expands to, [None] if it cannot be expanded, which
+    /// is reported, otherwise the caller must [Expander::exit]
+    fn enter(&mut self, mac: &MacCall) -> Option<Vec<Spanned<Token>>> {
+        let Some(mac_def) = mac
+            .path
+            .as_ident()
+            .and_then(|name| self.macros.get(name.node.as_str()))
+            .cloned()
+        else {
+            let path: Vec<_> = mac
+                .path
+                .segments
+                .iter()
+                .map(|segment| segment.ident.node.to_string())
+                .collect();
+            self.error(Error::new(
+                mac.path.span,
+                format!(
+                    "cannot find macro `{}` in this scope",
+                    path.join("::")
+                ),
+            ));
+            return None;
+        };
+        let name = mac_def.name.node.to_string();
+        if self.stack.len() >= RECURSION_LIMIT {
+            self.error(Error::new(
+                mac.span,
+                format!("recursion limit reached while expanding `{name}!`"),
+            ));
+            return None;
+        }
+        let rules = mac_def.rules.as_ref()?;
+        let mut matching = Matching { expander: self, furthest: None };
+        let end = End::Invocation(mac.close);
+        let matched = rules.iter().find_map(|rule| {
+            let mut bindings = Bindings::new();
+            let pos = matching
+                .seq(&rule.matcher, &mac.tokens, 0, &mut bindings, &end)?;
+            if pos < mac.tokens.len() {
+                return matching.fail(mac.tokens.get(pos), &end);
+            }
+            Some((rule, bindings))
+        });
+        let Some((rule, bindings)) = matched else {
+            let error =
+                matching.furthest.map(|(_, error)| error).unwrap_or_else(
+                    || Error::new(mac.span, format!("`{name}!` has no rules")),
+                );
+            self.error(error);
+            return None;
+        };
+        let expansion = Expansion { name, call_site: mac.span };
+        self.expansions.push(expansion.clone());
+        let mark = self.first_mark + self.expansions.len() as u32;
+        self.stack.push(Frame { expansion, def_span: mac_def.span, mark });
+        let mut tts = Vec::new();
+        match transcribe(&rule.body, &bindings, &mut Vec::new(), mark, &mut tts)
+        {
+            Ok(()) => Some(TokenTree::flatten(&tts)),
+            Err(error) => {
+                self.error(error);
+                self.exit();
+                None
+            }
+        }
+    }
+
+    /// see [Expander::enter]
+    fn exit(&mut self) { self.stack.pop(); }
+
+    /// `mac` in expression position, e.g. `let a = square!(2);`
+    fn expand_expr(&mut self, mac: &MacCall) -> Expr {
+        let Some(tokens) = self.enter(mac) else {
+            return Expr::new(ExprKind::Err, mac.span);
+        };
+        let mut parser = self.parser(tokens.clone());
+        let mut expr = match parser.parse_expr() {
+            Ok(expr) => {
+                if !parser.is_eof() {
+                    let token = &tokens[parser.consumed()];
+                    self.error(Error::new(
+                        token.span,
+                        format!(
+                            "macro expansion ignores token `{}` and any \
+                             following",
+                            token.node
+                        ),
+                    ));
+                }
+                expr
+            }
+            Err(error) => {
+                self.error(error);
+                Expr::new(ExprKind::Err, mac.span)
+            }
+        };
+        self.parser_errors(&mut parser);
+        PlaceholderMarks { frames: &self.stack }.visit_expr(&mut expr);
+        self.strip(|strip| strip.visit_expr(&mut expr));
+        self.visit_expr(&mut expr);
+        self.exit();
+        expr
+    }
+
+    /// `mac` in statement position, e.g. `swap!(a, b);`, the last
+    /// statement gets the `;` of the call if `semi`
+    fn expand_stmts(&mut self, mac: &MacCall, semi: bool) -> Vec<Stmt> {
+        let Some(mut tokens) = self.enter(mac) else {
+            let expr = Expr::new(ExprKind::Err, mac.span);
+            return vec![Stmt { kind: StmtKind::Expr(expr), span: mac.span }];
+        };
+        let brace = |kind, span| {
+            Spanned::new(Token::Operator(Operator::new(kind)), span)
+        };
+        tokens
+            .insert(0, brace(OperatorKind::OpenBrace, mac.span.shrink_to_lo()));
+        tokens.push(brace(OperatorKind::CloseBrace, mac.close));
+        let mut parser = self.parser(tokens);
+        let mut stmts = match parser.parse_block() {
+            Ok(block) => block.stmts,
+            Err(error) => {
+                self.error(error);
+                Vec::new()
+            }
+        };
+        self.parser_errors(&mut parser);
+        if semi
+            && let Some(stmt) = stmts.last_mut()
+            && let StmtKind::Expr(expr) = &stmt.kind
+        {
+            stmt.kind = StmtKind::Semi(expr.clone());
+        }
+        let mut marks = PlaceholderMarks { frames: &self.stack };
+        stmts.iter_mut().for_each(|stmt| marks.visit_stmt(stmt));
+        self.strip(|strip| strip.visit_stmts(&mut stmts));
+        self.visit_stmts(&mut stmts);
+        self.exit();
+        stmts
+    }
+
+    /// `mac` in item position, e.g. `getters! { a, b }`
+    fn expand_items(&mut self, mac: &MacCall) -> Vec<Item> {
+        let Some(tokens) = self.enter(mac) else {
+            return Vec::new();
+        };
+        let mut parser = self.parser(tokens);
+        let mut items = parser.parse_items(false);
+        self.parser_errors(&mut parser);
+        let mut marks = PlaceholderMarks { frames: &self.stack };
+        items.iter_mut().for_each(|item| marks.visit_item(item));
+        self.strip(|strip| strip.visit_items(&mut items));
+        self.visit_items(&mut items);
+        self.exit();
+        items
+    }
+}
+impl MutVisitor for Expander {
+    fn visit_items(&mut self, items: &mut Vec<Item>) {
+        let mut i = 0;
+        while i < items.len() {
+            let ItemKind::MacCall(mac) = &items[i].kind else {
+                self.visit_item(&mut items[i]);
+                i += 1;
+                continue;
+            };
+            let expanded = self.expand_items(&mac.clone());
+            let len = expanded.len();
+            items.splice(i..=i, expanded);
+            i += len;
+        }
+    }
+
+    fn visit_stmts(&mut self, stmts: &mut Vec<Stmt>) {
+        let mut i = 0;
+        while i < stmts.len() {
+            let (mac, semi) = match &stmts[i].kind {
+                StmtKind::Expr(Expr {
+                    kind: ExprKind::MacCall(mac), ..
+                }) => (mac.clone(), false),
+                StmtKind::Semi(Expr {
+                    kind: ExprKind::MacCall(mac), ..
+                }) => (mac.clone(), true),
+                _ => {
+                    self.visit_stmt(&mut stmts[i]);
+                    i += 1;
+                    continue;
+                }
+            };
+            let expanded = self.expand_stmts(&mac, semi);
+            let len = expanded.len();
+            stmts.splice(i..=i, expanded);
+            i += len;
+        }
+    }
+
+    fn visit_expr(&mut self, expr: &mut Expr) {
+        match &expr.kind {
+            ExprKind::MacCall(mac) => *expr = self.expand_expr(&mac.clone()),
+            _ => visit::walk_expr(self, expr),
+        }
+    }
+}
+impl Rule {
+    /// checks the rule and splits it into fragments and repetitions
+    fn compile(rule: &MacroRule) -> Result<Self> {
+        let matcher = Matcher::compile(&rule.matcher)?;
+        let body = Transcriber::compile(&rule.body)?;
+        let mut vars = HashMap::new();
+        Matcher::vars(&matcher, &mut |name: &Ident| match vars
+            .insert(name.node.as_str().to_string(), name.span)
+        {
+            Some(_) => Err(Error::new(
+                name.span,
+                format!("duplicate matcher binding `{}`", name.node),
+            )),
+            None => Ok(()),
+        })?;
+        Transcriber::vars(&body, &mut |name: &Ident| match vars
+            .contains_key(name.node.as_str())
+        {
+            true => Ok(()),
+            false => Err(Error::new(
+                name.span,
+                format!("unknown macro variable `{}`", name.node),
+            )),
+        })?;
+        Ok(Self { matcher, body })
+    }
+}
+impl Matcher {
+    fn compile(tts: &[TokenTree]) -> Result<Vec<Self>> {
+        let mut matchers = Vec::new();
+        let mut iter = tts.iter();
+        while let Some(tt) = iter.next() {
+            let dollar = match tt {
+                TokenTree::Token(token) if is_dollar(&token.node) => token.span,
+                TokenTree::Token(token) => {
+                    matchers.push(Matcher::Token(token.node.clone()));
+                    continue;
+                }
+                TokenTree::Delimited { delim, tts, .. } => {
+                    matchers
+                        .push(Matcher::Delimited(*delim, Self::compile(tts)?));
+                    continue;
+                }
+            };
+            match iter.next() {
+                Some(TokenTree::Token(Spanned {
+                    node: Token::Identifier(name),
+                    span,
+                })) => {
+                    let name = Spanned::new(name.clone(), *span);
+                    let missing = || {
+                        Error::new(
+                            name.span,
+                            format!(
+                                "missing fragment specifier for `${}`",
+                                name.node
+                            ),
+                        )
+                    };
+                    match iter.next() {
+                        Some(TokenTree::Token(colon))
+                            if is_op(&colon.node, OperatorKind::Colon) => {}
+                        _ => return Err(missing()),
+                    }
+                    let Some(TokenTree::Token(Spanned {
+                        node: Token::Identifier(kind),
+                        span,
+                    })) = iter.next()
+                    else {
+                        return Err(missing());
+                    };
+                    let Some(fragment) = Fragment::from_name(kind.as_str())
+                    else {
+                        return Err(Error::new(
+                            *span,
+                            format!("invalid fragment specifier `{kind}`"),
+                        ));
+                    };
+                    matchers.push(Matcher::Fragment(name, fragment));
+                }
+                Some(TokenTree::Delimited {
+                    delim: Delimiter::Paren,
+                    tts,
+                    close,
+                    ..
+                }) => {
+                    let body = Self::compile(tts)?;
+                    let (sep, op) = repetition_op(&mut iter, *close)?;
+                    let span = dollar.to(*close);
+                    matchers.push(Matcher::Repeat(Repetition {
+                        body,
+                        sep,
+                        op,
+                        span,
+                    }));
+                }
+                _ => {
+                    return Err(Error::new(
+                        dollar,
+                        "expected identifier or `(` after `$`",
+                    ));
+                }
+            }
+        }
+        Ok(matchers)
+    }
+
+    /// calls `f` with the name of every fragment
+    fn vars(
+        matchers: &[Self], f: &mut impl FnMut(&Ident) -> Result<()>,
+    ) -> Result<()> {
+        for matcher in matchers {
+            match matcher {
+                Matcher::Token(_) => {}
+                Matcher::Delimited(_, matchers)
+                | Matcher::Repeat(Repetition { body: matchers, .. }) => {
+                    Self::vars(matchers, f)?
+                }
+                Matcher::Fragment(name, _) => f(name)?,
+            }
+        }
+        Ok(())
+    }
+}
+impl Transcriber {
+    fn compile(tts: &[TokenTree]) -> Result<Vec<Self>> {
+        let mut transcribers = Vec::new();
+        let mut iter = tts.iter();
+        while let Some(tt) = iter.next() {
+            let dollar = match tt {
+                TokenTree::Token(token) if is_dollar(&token.node) => token.span,
+                TokenTree::Token(token) => {
+                    transcribers.push(Transcriber::Token(token.clone()));
+                    continue;
+                }
+                TokenTree::Delimited { delim, open, close, tts } => {
+                    transcribers.push(Transcriber::Delimited {
+                        delim: *delim,
+                        open:  *open,
+                        close: *close,
+                        body:  Self::compile(tts)?,
+                    });
+                    continue;
+                }
+            };
+            match iter.next() {
+                Some(TokenTree::Token(Spanned {
+                    node: Token::Identifier(name),
+                    span,
+                })) => transcribers
+                    .push(Transcriber::Var(Spanned::new(name.clone(), *span))),
+                Some(TokenTree::Delimited {
+                    delim: Delimiter::Paren,
+                    tts,
+                    close,
+                    ..
+                }) => {
+                    let body = Self::compile(tts)?;
+                    let (sep, op) = repetition_op(&mut iter, *close)?;
+                    let span = dollar.to(*close);
+                    transcribers.push(Transcriber::Repeat(Repetition {
+                        body,
+                        sep,
+                        op,
+                        span,
+                    }));
+                }
+                _ => {
+                    return Err(Error::new(
+                        dollar,
+                        "expected identifier or `(` after `$`",
+                    ));
+                }
+            }
+        }
+        Ok(transcribers)
+    }
+
+    /// calls `f` with every variable
+    fn vars(
+        transcribers: &[Self], f: &mut impl FnMut(&Ident) -> Result<()>,
+    ) -> Result<()> {
+        for transcriber in transcribers {
+            match transcriber {
+                Transcriber::Token(_) => {}
+                Transcriber::Delimited { body, .. }
+                | Transcriber::Repeat(Repetition { body, .. }) => {
+                    Self::vars(body, f)?
+                }
+                Transcriber::Var(name) => f(name)?,
+            }
+        }
+        Ok(())
+    }
+}
+impl Fragment {
+    /// e.g. `expr` of `$x:expr`
+    fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "expr" => Some(Fragment::Expr),
+            "ident" => Some(Fragment::Ident),
+            "ty" => Some(Fragment::Ty),
+            "pat" => Some(Fragment::Pat),
+            "path" => Some(Fragment::Path),
+            "block" => Some(Fragment::Block),
+            "literal" => Some(Fragment::Literal),
+            "tt" => Some(Fragment::Tt),
+            "item" => Some(Fragment::Item),
+            "lifetime" => Some(Fragment::Lifetime),
+            "stmt" => Some(Fragment::Stmt),
+            _ => None,
+        }
+    }
+}
+impl RepeatOp {
+    fn from_token(token: &Token) -> Option<Self> {
+        match token {
+            Token::Operator(op) => match op.kind() {
+                OperatorKind::Star => Some(RepeatOp::ZeroOrMore),
+                OperatorKind::Plus => Some(RepeatOp::OneOrMore),
+                OperatorKind::Question => Some(RepeatOp::ZeroOrOne),
+                _ => None,
+            },
+            _ => None,
+        }
+    }
+}
+impl Matching<'_> {
+    /// matches `matchers` from `tts[pos]`, the position after the match
+    /// on success
+    fn seq(
+        &mut self, matchers: &[Matcher], tts: &[TokenTree], mut pos: usize,
+        bindings: &mut Bindings, end: &End,
+    ) -> Option<usize> {
+        for matcher in matchers {
+            match matcher {
+                Matcher::Token(expected) => match tts.get(pos) {
+                    Some(TokenTree::Token(token))
+                        if same_token(&token.node, expected) =>
+                    {
+                        pos += 1
+                    }
+                    tt => return self.fail(tt, end),
+                },
+                Matcher::Delimited(expected, matchers) => match tts.get(pos) {
+                    Some(TokenTree::Delimited {
+                        delim,
+                        tts: inner,
+                        close,
+                        ..
+                    }) if delim == expected => {
+                        let close_kind = delim.operators().1;
+                        let end = End::Close(Spanned::new(
+                            Token::Operator(Operator::new(close_kind)),
+                            *close,
+                        ));
+                        let inner_pos =
+                            self.seq(matchers, inner, 0, bindings, &end)?;
+                        if inner_pos < inner.len() {
+                            return self.fail(inner.get(inner_pos), &end);
+                        }
+                        pos += 1;
+                    }
+                    tt => return self.fail(tt, end),
+                },
+                Matcher::Fragment(name, fragment) => {
+                    let Some(len) = self.fragment(*fragment, &tts[pos..])
+                    else {
+                        return self.fail(tts.get(pos), end);
+                    };
+                    let matched = tts[pos..pos + len].to_vec();
+                    bindings.insert(
+                        name.node.as_str().to_string(),
+                        Binding::One(*fragment, matched),
+                    );
+                    pos += len;
+                }
+                Matcher::Repeat(repetition) => {
+                    pos = self.repeat(repetition, tts, pos, bindings, end)?
+                }
+            }
+        }
+        Some(pos)
+    }
+
+    /// matches as many repetitions as possible
+    fn repeat(
+        &mut self, repetition: &Repetition<Matcher>, tts: &[TokenTree],
+        mut pos: usize, bindings: &mut Bindings, end: &End,
+    ) -> Option<usize> {
+        let mut iterations = Vec::new();
+        while repetition.op != RepeatOp::ZeroOrOne || iterations.is_empty() {
+            let mut start = pos;
+            if !iterations.is_empty()
+                && let Some(sep) = &repetition.sep
+            {
+                match tts.get(pos) {
+                    Some(TokenTree::Token(token))
+                        if same_token(&token.node, &sep.node) =>
+                    {
+                        start += 1
+                    }
+                    _ => break,
+                }
+            }
+            let mut iteration = Bindings::new();
+            match self.seq(&repetition.body, tts, start, &mut iteration, end) {
+                Some(next) if next > start => {
+                    pos = next;
+                    iterations.push(iteration);
+                }
+                _ => break,
+            }
+        }
+        if repetition.op == RepeatOp::OneOrMore && iterations.is_empty() {
+            return self.fail(tts.get(pos), end);
+        }
+        let _ = Matcher::vars(&repetition.body, &mut |name: &Ident| {
+            let name = name.node.as_str();
+            let seq = iterations
+                .iter_mut()
+                .map(|iteration| {
+                    iteration.remove(name).unwrap_or(Binding::Seq(Vec::new()))
+                })
+                .collect();
+            bindings.insert(name.to_string(), Binding::Seq(seq));
+            Ok(())
+        });
+        Some(pos)
+    }
+
+    /// the number of trees at the start of `tts` that `fragment` matches
+    fn fragment(&self, fragment: Fragment, tts: &[TokenTree]) -> Option<usize> {
+        let token = |i: usize| match tts.get(i) {
+            Some(TokenTree::Token(token)) => Some(&token.node),
+            _ => None,
+        };
+        match fragment {
+            Fragment::Tt => (!tts.is_empty()).then_some(1),
+            Fragment::Ident => {
+                matches!(token(0), Some(Token::Identifier(_))).then_some(1)
+            }
+            Fragment::Lifetime => {
+                matches!(token(0), Some(Token::Lifetime(_))).then_some(1)
+            }
+            Fragment::Literal => match (token(0), token(1)) {
+                (Some(Token::Literal(_)), _) => Some(1),
+                (Some(minus), Some(Token::Literal(_)))
+                    if is_op(minus, OperatorKind::Minus) =>
+                {
+                    Some(2)
+                }
+                _ => None,
+            },
+            _ => self.parse_fragment(fragment, tts),
+        }
+    }
+
+    /// parses `fragment` speculatively, it must end at a tree boundary
+    fn parse_fragment(
+        &self, fragment: Fragment, tts: &[TokenTree],
+    ) -> Option<usize> {
+        let mut parser = self.expander.parser(TokenTree::flatten(tts));
+        let parsed = match fragment {
+            Fragment::Expr => parser.parse_expr().is_ok(),
+            Fragment::Ty => parser.parse_type().is_ok(),
+            Fragment::Pat => parser.parse_pat().is_ok(),
+            Fragment::Path => parser.parse_type_path().is_ok(),
+            Fragment::Block => parser.parse_block().is_ok(),
+            Fragment::Item => parser.parse_item().is_ok(),
+            Fragment::Stmt => parser.parse_stmt().is_ok(),
+            _ => false,
+        };
+        let consumed = parser.consumed();
+        if !parsed || consumed == 0 || !parser.take_errors().is_empty() {
+            return None;
+        }
+        let mut count = 0;
+        for (i, tt) in tts.iter().enumerate() {
+            if count == consumed {
+                return Some(i);
+            }
+            count += token_count(tt);
+        }
+        (count == consumed).then_some(tts.len())
+    }
+
+    /// records a mismatch at `tt`, or at `end` if there are no more trees
+    fn fail<T>(&mut self, tt: Option<&TokenTree>, end: &End) -> Option<T> {
+        let (offset, error) = match (tt, end) {
+            (Some(tt), _) => {
+                let token = match tt {
+                    TokenTree::Token(token) => token.node.to_string(),
+                    TokenTree::Delimited { delim, .. } => {
+                        delim.operators().0.to_string()
+                    }
+                };
+                let message = format!("no rules expected the token `{token}`");
+                (tt.span().lo(), Error::new(tt.span(), message))
+            }
+            (None, End::Close(token)) => {
+                let message =
+                    format!("no rules expected the token `{}`", token.node);
+                (token.span.lo(), Error::new(token.span, message))
+            }
+            (None, End::Invocation(span)) => (
+                usize::MAX,
+                Error::new(*span, "unexpected end of macro invocation"),
+            ),
+        };
+        if self.furthest.as_ref().is_none_or(|(furthest, _)| offset > *furthest)
+        {
+            self.furthest = Some((offset, error));
+        }
+        None
+    }
+}
+/// the tokens of `body` with the fragments of `bindings` substituted,
+/// `indices` are the iterations of the enclosing repetitions
+fn transcribe(
+    body: &[Transcriber], bindings: &Bindings, indices: &mut Vec<usize>,
+    mark: u32, out: &mut Vec<TokenTree>,
+) -> Result<()> {
+    for transcriber in body {
+        match transcriber {
+            Transcriber::Token(token) => out.push(marked(token, mark)),
+            Transcriber::Delimited { delim, open, close, body } => {
+                let mut tts = Vec::new();
+                transcribe(body, bindings, indices, mark, &mut tts)?;
+                out.push(TokenTree::Delimited {
+                    delim: *delim,
+                    open: open.with_expansion(mark),
+                    close: close.with_expansion(mark),
+                    tts,
+                });
+            }
+            Transcriber::Var(name) => match lookup(bindings, name, indices) {
+                // keeps the precedence, e.g. `$x * 2` of `1 + 1`
+                Some(Binding::One(Fragment::Expr, tts)) => {
+                    out.push(TokenTree::Delimited {
+                        delim: Delimiter::Paren,
+                        open:  tts[0].span().shrink_to_lo(),
+                        close: tts[tts.len() - 1].span().shrink_to_hi(),
+                        tts:   tts.clone(),
+                    })
+                }
+                Some(Binding::One(_, tts)) => out.extend(tts.iter().cloned()),
+                Some(Binding::Seq(_)) => {
+                    return Err(Error::new(
+                        name.span,
+                        format!(
+                            "variable `{}` is still repeating at this depth",
+                            name.node
+                        ),
+                    ));
+                }
+                None => {
+                    return Err(Error::new(
+                        name.span,
+                        format!("unknown macro variable `{}`", name.node),
+                    ));
+                }
+            },
+            Transcriber::Repeat(repetition) => {
+                let mut count: Option<(usize, Identifier)> = None;
+                Transcriber::vars(&repetition.body, &mut |name: &Ident| {
+                    let Some(Binding::Seq(seq)) =
+                        lookup(bindings, name, indices)
+                    else {
+                        return Ok(());
+                    };
+                    match &count {
+                        None => count = Some((seq.len(), name.node.clone())),
+                        Some((len, other)) if *len != seq.len() => {
+                            return Err(Error::new(
+                                name.span,
+                                format!(
+                                    "meta-variable `{other}` repeats {len} \
+                                     times, but `{}` repeats {} times",
+                                    name.node,
+                                    seq.len()
+                                ),
+                            ));
+                        }
+                        Some(_) => {}
+                    }
+                    Ok(())
+                })?;
+                let Some((count, _)) = count else {
+                    return Err(Error::new(
+                        repetition.span,
+                        "attempted to repeat an expression containing no \
+                         syntax variables matched as repeating at this depth",
+                    ));
+                };
+                for i in 0..count {
+                    if i > 0
+                        && let Some(sep) = &repetition.sep
+                    {
+                        out.push(marked(sep, mark));
+                    }
+                    indices.push(i);
+                    transcribe(&repetition.body, bindings, indices, mark, out)?;
+                    indices.pop();
+                }
+            }
+        }
+    }
+    Ok(())
+}
+/// the binding of `name` in the iterations `indices`
+fn lookup<'a>(
+    bindings: &'a Bindings, name: &Ident, indices: &[usize],
+) -> Option<&'a Binding> {
+    let mut binding = bindings.get(name.node.as_str())?;
+    for &i in indices {
+        match binding {
+            Binding::Seq(seq) => binding = seq.get(i)?,
+            Binding::One(..) => break,
+        }
+    }
+    Some(binding)
+}
+/// `token` of a macro body, its span and identifiers get the `mark` of the
+/// expansion
+fn marked(token: &Spanned<Token>, mark: u32) -> TokenTree {
+    let mut token = token.clone();
+    token.span = token.span.with_expansion(mark);
+    if let Token::Identifier(ident) = &mut token.node {
+        *ident = ident.clone().with_mark(mark);
+    }
+    TokenTree::Token(token)
+}
+/// e.g. `,*` after `$(...)`
+fn repetition_op<'a>(
+    iter: &mut impl Iterator<Item = &'a TokenTree>, close: Span,
+) -> Result<(Option<Spanned<Token>>, RepeatOp)> {
+    let expected = |span| Error::new(span, "expected one of: `*`, `+`, or `?`");
+    let sep = match iter.next() {
+        Some(TokenTree::Token(token)) => {
+            match RepeatOp::from_token(&token.node) {
+                Some(op) => return Ok((None, op)),
+                None => token.clone(),
+            }
+        }
+        Some(tt) => return Err(expected(tt.span())),
+        None => return Err(expected(close)),
+    };
+    match iter.next() {
+        Some(TokenTree::Token(token)) => RepeatOp::from_token(&token.node)
+            .map(|op| (Some(sep), op))
+            .ok_or_else(|| expected(token.span)),
+        Some(tt) => Err(expected(tt.span())),
+        None => Err(expected(sep.span)),
+    }
+}
+fn is_op(token: &Token, kind: OperatorKind) -> bool {
+    matches!(token, Token::Operator(op) if op.kind() == kind)
+}
+fn is_dollar(token: &Token) -> bool { is_op(token, OperatorKind::Dollar) }
+/// identifiers are equal whatever their marks
+fn same_token(a: &Token, b: &Token) -> bool {
+    match (a, b) {
+        (Token::Identifier(a), Token::Identifier(b)) => {
+            a.as_str() == b.as_str() && a.is_raw() == b.is_raw()
+        }
+        _ => a == b,
+    }
+}
+/// the number of tokens of `tt` with its delimiters
+fn token_count(tt: &TokenTree) -> usize {
+    match tt {
+        TokenTree::Token(_) => 1,
+        TokenTree::Delimited { tts, .. } => {
+            2 + tts.iter().map(token_count).sum::<usize>()
+        }
+    }
+}
+/// every `marco` definition, with the span of its item
+#[derive(Default)]
+struct Definitions(Vec<(MacroDef, Span)>);
+impl MutVisitor for Definitions {
+    fn visit_item(&mut self, item: &mut Item) {
+        if let ItemKind::Marco(def) = &item.kind {
+            self.0.push((def.clone(), item.span));
+        }
+        visit::walk_item(self, item);
+    }
+}
+/// marks the identifiers in the placeholders of format strings of a macro
+/// body, which are not identifier tokens when transcribed
+struct PlaceholderMarks<'a> {
+    frames: &'a [Frame],
+}
+impl MutVisitor for PlaceholderMarks<'_> {
+    fn visit_expr(&mut self, expr: &mut Expr) {
+        if let ExprKind::Format(format) = &mut expr.kind
+            && let Some(frame) = self
+                .frames
+                .iter()
+                .rev()
+                .find(|frame| frame.def_span.contains(format.template))
+        {
+            for piece in &mut format.pieces {
+                if let FormatPiece::Placeholder(placeholder) = piece
+                    && let FormatArg::Expr(expr) = &mut placeholder.arg
+                {
+                    Mark(frame.mark).visit_expr(expr);
+                }
+            }
+        }
+        visit::walk_expr(self, expr);
+    }
+}
+/// gives every unmarked identifier the mark
+struct Mark(u32);
+impl MutVisitor for Mark {
+    fn visit_ident(&mut self, ident: &mut Ident) {
+        if ident.node.mark() == 0 {
+            ident.node = ident.node.clone().with_mark(self.0);
+        }
+    }
+}
+/// the marked identifiers that bind a local
+#[derive(Default)]
+struct MarkedBindings(HashSet<Identifier>);
+impl MutVisitor for MarkedBindings {
+    fn visit_pat(&mut self, pat: &mut Pat) {
+        if let PatKind::Ident { name, .. } = &pat.kind
+            && name.node.mark() != 0
+        {
+            self.0.insert(name.node.clone());
+        }
+        visit::walk_pat(self, pat);
+    }
+}
+/// removes the marks of identifiers other than local bindings and their
+/// uses, e.g. items and fields stay visible to the code around a call
+struct Unmark<'a> {
+    bindings: &'a HashSet<Identifier>,
+}
+impl MutVisitor for Unmark<'_> {
+    fn visit_expr(&mut self, expr: &mut Expr) {
+        if let ExprKind::Path(path) = &expr.kind
+            && let Some(ident) = path.as_ident()
+            && self.bindings.contains(&ident.node)
+        {
+            return;
+        }
+        visit::walk_expr(self, expr);
+    }
+
+    fn visit_pat(&mut self, pat: &mut Pat) {
+        match &mut pat.kind {
+            PatKind::Ident { sub, .. } => {
+                if let Some(sub) = sub {
+                    self.visit_pat(sub);
+                }
+            }
+            _ => visit::walk_pat(self, pat),
+        }
+    }
+
+    fn visit_ident(&mut self, ident: &mut Ident) {
+        if ident.node.mark() != 0 {
+            ident.node = ident.node.clone().with_mark(0);
+        }
+    }
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        lexer::scanner::{AnnasulScanner, Scanner},
+        parser::ast::{BinOp, Block, Local},
+    };
+    fn expand(source: &str) -> (Module, Vec<Diagnostic>) {
+        let (tokens, errors) =
+            AnnasulScanner::new(source.as_bytes()).tokenize();
+        assert_eq!(errors, []);
+        let mut parser = Parser::new(tokens).with_source(source);
+        let mut module = parser.parse_module();
+        assert_eq!(parser.take_errors(), []);
+        let mut expander = Expander::new().with_source(source);
+        expander.expand_module(&mut module);
+        (module, expander.take_diagnostics())
+    }
+    /// the body of the last function
+    fn body(module: &Module) -> &Block {
+        module
+            .items
+            .iter()
+            .rev()
+            .find_map(|item| match &item.kind {
+                ItemKind::Fn(def) => def.body.as_ref(),
+                _ => None,
+            })
+            .unwrap()
+    }
+    fn local(stmt: &Stmt) -> &Local {
+        let StmtKind::Let(local) = &stmt.kind else { panic!("{stmt:?}") };
+        local
+    }
+    fn ident(expr: &Expr) -> &Identifier {
+        let ExprKind::Path(path) = &expr.kind else { panic!("{expr:?}") };
+        &path.as_ident().unwrap().node
+    }
+    #[test]
+    fn expansion_and_repetition() {
+        let (module, diagnostics) = expand(
+            "marco square { ($x:expr) => { $x * $x }; }
+            marco sum { ($($x:expr),* $(,)?) => { 0 $(+ $x)* } }
+            marco getters { ($($name:ident: $ty:ty),+) => {
+                $(fn $name() -> $ty { 0 })+
+            } }
+            getters! { a: i32, b: u8 }
+            fn main() {
+                let a = square!(1 + 2);
+                let b = sum![1, 2, 3,];
+                sum!();
+            }",
+        );
+        assert_eq!(diagnostics, []);
+        let names: Vec<_> =
+            module.items.iter().filter_map(|item| item.name()).collect();
+        let names: Vec<_> =
+            names.iter().map(|name| name.node.as_str()).collect();
+        assert_eq!(names, ["square", "sum", "getters", "a", "b", "main"]);
+        let stmts = &body(&module).stmts;
+        let square = local(&stmts[0]).init.as_ref().unwrap();
+        let ExprKind::Binary(op, l, r) = &square.kind else { panic!() };
+        assert_eq!(op.node, BinOp::Mul);
+        assert!(matches!(l.kind, ExprKind::Paren(_)) && l == r);
+        // `((0 + 1) + 2) + 3`
+        let mut sum = local(&stmts[1]).init.as_ref().unwrap();
+        let mut terms = 0;
+        while let ExprKind::Binary(_, l, _) = &sum.kind {
+            terms += 1;
+            sum = l;
+        }
+        assert_eq!(terms, 3);
+        assert!(matches!(&stmts[2].kind, StmtKind::Semi(e)
+            if matches!(e.kind, ExprKind::Literal(_))));
+    }
+    #[test]
+    fn hygiene() {
+        let (module, diagnostics) = expand(
+            "marco double { ($e:expr) => {{
+                let tmp = $e;
+                println!(\"{tmp}\");
+                tmp * 2
+            }} }
+            fn main() { let tmp = 1; let a = double!(tmp); }",
+        );
+        assert_eq!(diagnostics, []);
+        let stmts = &body(&module).stmts;
+        let outer = &local(&stmts[0]).pat;
+        let PatKind::Ident { name: outer, .. } = &outer.kind else { panic!() };
+        assert_eq!(outer.node.mark(), 0);
+        let ExprKind::Block(block) =
+            &local(&stmts[1]).init.as_ref().unwrap().kind
+        else {
+            panic!()
+        };
+        let inner = local(&block.stmts[0]);
+        let PatKind::Ident { name: inner, .. } = &inner.pat.kind else {
+            panic!()
+        };
+        assert_ne!(inner.node.mark(), 0);
+        // the argument is the outer `tmp`
+        let ExprKind::Paren(arg) =
+            &local(&block.stmts[0]).init.as_ref().unwrap().kind
+        else {
+            panic!()
+        };
+        assert_eq!(ident(arg), &outer.node);
+        let StmtKind::Semi(Expr { kind: ExprKind::Format(format), .. }) =
+            &block.stmts[1].kind
+        else {
+            panic!()
+        };
+        let FormatPiece::Placeholder(placeholder) = &format.pieces[0] else {
+            panic!()
+        };
+        let FormatArg::Expr(arg) = &placeholder.arg else { panic!() };
+        assert_eq!(ident(arg), &inner.node);
+        let StmtKind::Expr(Expr { kind: ExprKind::Binary(_, tmp, _), .. }) =
+            &block.stmts[2].kind
+        else {
+            panic!()
+        };
+        assert_eq!(ident(tmp), &inner.node);
+    }
+    #[test]
+    fn errors_with_backtrace() {
+        let messages = |source: &str| -> Vec<String> {
+            expand(source)
+                .1
+                .iter()
+                .map(|diagnostic| {
+                    let labels: Vec<_> = diagnostic
+                        .labels()
+                        .iter()
+                        .filter(|label| !label.is_primary())
+                        .map(|label| label.message().to_string())
+                        .collect();
+                    let mut message = diagnostic.message().to_string();
+                    for label in labels.iter().chain(diagnostic.notes()) {
+                        message += &format!("; {label}");
+                    }
+                    message
+                })
+                .collect()
+        };
+        assert_eq!(
+            messages(
+                "marco inner { ($x:ident) => { $x } }
+                marco outer { () => { inner!(1) } }
+                fn main() { outer!(); missing!(); }"
+            ),
+            [
+                "no rules expected the token `1`; in this expansion of \
+                 `outer!`; this error originates in the macro `outer`",
+                "cannot find macro `missing` in this scope",
+            ]
+        );
+        assert_eq!(
+            messages(
+                "marco forever { () => { forever!() } }
+                fn main() { forever!(); }"
+            ),
+            [
+                "recursion limit reached while expanding `forever!`; in this \
+                 expansion of `forever!` (×63); in this expansion of \
+                 `forever!`; this error originates in the macro `forever`"
+            ]
+        );
+        assert_eq!(
+            messages(
+                "marco a { ($x:expr) => { $y } }
+                marco b { ($x:thing) => {} }
+                marco c { ($($x:expr)) => {} }
+                marco d { ($x:expr, $y:expr) => {} }
+                fn main() { d!(1); d!(1, 2 3); }"
+            ),
+            [
+                "unknown macro variable `y`",
+                "invalid fragment specifier `thing`",
+                "expected one of: `*`, `+`, or `?`",
+                "unexpected end of macro invocation",
+                "no rules expected the token `3`",
+            ]
+        );
+    }
+}
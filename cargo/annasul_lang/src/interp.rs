@@ -0,0 +1,1845 @@
+// Copyright (c) 2025 air (https://yuanair.github.io).
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published by
+// the Free Software Foundation, version 3 of the License only.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+//! runs a crate after [monomorphization](crate::semantic::mono)
+use std::{cmp::Ordering, collections::HashMap, io::Write, mem};
+
+use crate::{
+    lexer::token::{IntLit, Literal},
+    parser::ast::{
+        BinOp,
+        FormatAlign,
+        FormatMacro,
+        FormatSpec,
+        FormatTrait,
+        UnOp,
+    },
+    semantic::{
+        hir::{
+            Block,
+            Body,
+            Expr,
+            ExprKind,
+            FormatPiece,
+            LocalId,
+            LoopId,
+            Pat,
+            PatKind,
+            StmtKind,
+        },
+        mono::{Instance, MonoItem},
+        resolve::{DefId, DefKind, Resolutions},
+    },
+    types::{AdtKind, FloatTy, Ty, TyCtxt, TyKind, layout::Target},
+    utils::{diagnostic::Diagnostic, span::Span},
+};
+/// how many calls may be nested, e.g. `fn f() { f() }` never returns
+pub const STACK_LIMIT: usize = 2048;
+/// runs the instances of a crate by walking their typed bodies
+///
+/// every local, temporary and `box` is an allocation of its own: a local
+/// is freed at the end of its block, and a `box` when its owner is dropped
+/// unless it was moved out before
+pub struct Interpreter<'a> {
+    res:        &'a Resolutions,
+    tcx:        &'a mut TyCtxt,
+    target:     Target,
+    items:      HashMap<&'a Instance, &'a Body>,
+    memory:     Vec<Option<Allocation>>,
+    /// the freed indices of `memory`, reused by the next allocations
+    free:       Vec<usize>,
+    /// the allocations of the statics, once they are used
+    statics:    HashMap<DefId, usize>,
+    consts:     HashMap<DefId, Value>,
+    /// the allocations of the string literals, by their spans
+    literals:   HashMap<Span, usize>,
+    drop_cache: HashMap<Ty, bool>,
+    frames:     Vec<Frame<'a>>,
+    /// the expression being evaluated, where a panic is reported
+    span:       Span,
+    stdout:     &'a mut dyn Write,
+    stderr:     &'a mut dyn Write,
+}
+/// a value in memory, see [Interpreter]
+#[derive(Debug, Clone, PartialEq)]
+enum Value {
+    Bool(bool),
+    /// an integer or a character, truncated to the width of its type
+    Int(u128),
+    /// an `f32` is rounded after every operation
+    Float(f64),
+    /// the contents of a `str`
+    Str(String),
+    /// a tuple, a struct, a union, an array or a slice
+    Fields(Vec<Value>),
+    /// a variant of an enum by its index
+    Variant(usize, Vec<Value>),
+    /// a reference, a raw pointer or a box
+    Ptr(Pointer),
+    Fn(Instance),
+    /// not yet initialized, or moved out
+    Uninit,
+}
+/// a field of an allocation, by the indices of the nested fields
+#[derive(Debug, Clone, PartialEq)]
+struct Pointer {
+    alloc:  usize,
+    path:   Vec<usize>,
+    /// the type behind a pointer to `dyn A`
+    dyn_ty: Option<Ty>,
+}
+#[derive(Debug, Clone, PartialEq)]
+struct Allocation {
+    value: Value,
+    /// made by `box` or `format!`, freed by dropping its box
+    heap:  bool,
+}
+/// a call of a body
+struct Frame<'a> {
+    body:   &'a Body,
+    locals: Vec<Option<usize>>,
+    /// the locals and the temporaries of every block being evaluated,
+    /// dropped in the reverse order at its end
+    scopes: Vec<Vec<(usize, Ty)>>,
+}
+/// how the evaluation of an expression ends early
+enum Flow {
+    Break(LoopId, Value),
+    Continue(LoopId),
+    Return(Value),
+    /// a panic, or a program the interpreter cannot run
+    Abort(Box<Diagnostic>),
+}
+type Eval<T> = Result<T, Flow>;
+impl Pointer {
+    fn new(alloc: usize) -> Self {
+        Self { alloc, path: Vec::new(), dyn_ty: None }
+    }
+
+    fn field(&self, index: usize) -> Self {
+        let mut path = self.path.clone();
+        path.push(index);
+        Self { alloc: self.alloc, path, dyn_ty: None }
+    }
+}
+impl<'a> Interpreter<'a> {
+    pub fn new(
+        res: &'a Resolutions, tcx: &'a mut TyCtxt, items: &'a [MonoItem],
+        stdout: &'a mut dyn Write, stderr: &'a mut dyn Write,
+    ) -> Self {
+        Self {
+            res,
+            tcx,
+            target: Target::host(),
+            items: items
+                .iter()
+                .map(|item| (&item.instance, &item.body))
+                .collect(),
+            memory: Vec::new(),
+            free: Vec::new(),
+            statics: HashMap::new(),
+            consts: HashMap::new(),
+            literals: HashMap::new(),
+            drop_cache: HashMap::new(),
+            frames: Vec::new(),
+            span: Span::default(),
+            stdout,
+            stderr,
+        }
+    }
+
+    /// the widths of `isize` and `usize`, the host's by default
+    pub fn with_target(mut self, target: Target) -> Self {
+        self.target = target;
+        self
+    }
+
+    /// the function `main` of the root module
+    pub fn find_main(res: &Resolutions) -> Option<DefId> {
+        res.def(res.root()).children.iter().copied().find(|id| {
+            let def = res.def(*id);
+            def.kind == DefKind::Fn && def.name == "main"
+        })
+    }
+
+    /// calls `main`, the error is the panic that ended the program
+    pub fn run(&mut self, main: DefId) -> Result<(), Box<Diagnostic>> {
+        let instance = Instance { def: main, args: Vec::new() };
+        let result = match self.call(instance, Vec::new()) {
+            Ok(_) => Ok(()),
+            Err(Flow::Abort(diagnostic)) => Err(diagnostic),
+            Err(_) => unreachable!("`break` and `return` end in a body"),
+        };
+        let _ = self.stdout.flush();
+        let _ = self.stderr.flush();
+        result
+    }
+
+    /// the boxes not yet freed, none after [Interpreter::run] unless they
+    /// are owned by statics
+    pub fn heap_allocations(&self) -> usize {
+        self.memory.iter().flatten().filter(|alloc| alloc.heap).count()
+    }
+
+    fn call(&mut self, instance: Instance, args: Vec<Value>) -> Eval<Value> {
+        let Some(body) = self.items.get(&instance).copied() else {
+            return self.call_without_body(instance, args);
+        };
+        if self.frames.len() >= STACK_LIMIT {
+            return Err(self.abort(format!(
+                "reached the limit of {STACK_LIMIT} nested calls"
+            )));
+        }
+        let span = self.span;
+        self.frames.push(Frame {
+            body,
+            locals: vec![None; body.locals.len()],
+            scopes: vec![Vec::new()],
+        });
+        let result = self.run_body(body, args);
+        let scope = self.frame_mut().scopes.pop().unwrap();
+        self.drop_scope(scope);
+        self.frames.pop();
+        self.span = span;
+        match result {
+            Ok(value) | Err(Flow::Return(value)) => Ok(value),
+            Err(flow) => Err(flow),
+        }
+    }
+
+    fn run_body(&mut self, body: &'a Body, args: Vec<Value>) -> Eval<Value> {
+        for (pat, arg) in body.params.iter().zip(args) {
+            let pointer = self.temp(arg, pat.ty);
+            self.bind(pat, &pointer, false)?;
+        }
+        self.expr(&body.value)
+    }
+
+    /// a constructor of a tuple struct or variant, or a method called
+    /// through a pointer to `dyn A`
+    fn call_without_body(
+        &mut self, instance: Instance, args: Vec<Value>,
+    ) -> Eval<Value> {
+        let def = self.res.def(instance.def);
+        match def.kind {
+            DefKind::Struct => return Ok(Value::Fields(args)),
+            DefKind::Variant => {
+                let parent = self.res.def(def.parent.unwrap());
+                let index =
+                    parent.children.iter().position(|id| *id == instance.def);
+                return Ok(Value::Variant(index.unwrap(), args));
+            }
+            _ => {}
+        }
+        if let Some(trait_) = def.parent.filter(|id| self.tcx.is_trait(*id))
+            && let Some(Value::Ptr(Pointer { dyn_ty: Some(self_ty), .. })) =
+                args.first()
+            && let Some(resolved) =
+                self.resolve_dyn(trait_, &instance, *self_ty)
+        {
+            return self.call(resolved, args);
+        }
+        let name = instance.display(self.res, self.tcx);
+        Err(self.abort(format!("`{name}` has no body to run")))
+    }
+
+    /// the instance of a trait method for the type behind `dyn A`, like
+    /// [Monomorphizer](crate::semantic::mono::Monomorphizer) resolves it for
+    /// a known type
+    fn resolve_dyn(
+        &self, trait_: DefId, instance: &Instance, self_ty: Ty,
+    ) -> Option<Instance> {
+        let (impl_, mut args) = self.tcx.find_impl(trait_, self_ty)?;
+        let resolved = match self.tcx.impl_item(impl_, instance.def) {
+            Some(def) if def != instance.def => {
+                args.extend_from_slice(instance.args.get(1..)?);
+                Instance { def, args }
+            }
+            _ => {
+                let mut args = instance.args.clone();
+                *args.first_mut()? = self_ty;
+                Instance { def: instance.def, args }
+            }
+        };
+        self.items.contains_key(&resolved).then_some(resolved)
+    }
+}
+impl<'a> Interpreter<'a> {
+    fn expr(&mut self, expr: &'a Expr) -> Eval<Value> {
+        self.span = expr.span;
+        match &expr.kind {
+            ExprKind::Local(_)
+            | ExprKind::Field(..)
+            | ExprKind::Index(..)
+            | ExprKind::Unary(UnOp::Deref, _) => {
+                let place = self.place(expr)?;
+                self.read(&place, expr.ty)
+            }
+            ExprKind::Def(id) => match self.tcx.kind(expr.ty) {
+                TyKind::FnDef(def, args) => {
+                    Ok(Value::Fn(Instance { def: *def, args: args.clone() }))
+                }
+                _ if self.res.def(*id).kind == DefKind::Static => {
+                    let place = self.place(expr)?;
+                    self.read(&place, expr.ty)
+                }
+                _ => self.const_value(*id),
+            },
+            ExprKind::Literal(lit) => Ok(self.literal(lit, expr.ty, expr.span)),
+            ExprKind::Tuple(exprs) | ExprKind::Array(exprs) => {
+                let values = exprs.iter().map(|expr| self.expr(expr));
+                Ok(Value::Fields(values.collect::<Eval<_>>()?))
+            }
+            ExprKind::Repeat(expr, len) => {
+                let value = self.expr(expr)?;
+                Ok(Value::Fields(vec![value; *len as usize]))
+            }
+            ExprKind::Adt { adt, variant, fields, rest } => {
+                let Some(def) = self.tcx.adt(*adt) else {
+                    return Err(self.abort("a type with errors"));
+                };
+                let kind = def.kind;
+                let mut values =
+                    vec![Value::Uninit; def.variants[*variant].fields.len()];
+                for (index, field) in fields {
+                    values[*index] = self.expr(field)?;
+                }
+                if let Some(rest) = rest {
+                    let base = self.place(rest)?;
+                    for (index, value) in values.iter_mut().enumerate() {
+                        if fields.iter().all(|(given, _)| *given != index) {
+                            let ty =
+                                self.tcx.field_ty(rest.ty, *variant, index);
+                            *value = self.read(&base.field(index), ty)?;
+                        }
+                    }
+                }
+                Ok(match kind {
+                    AdtKind::Enum => Value::Variant(*variant, values),
+                    AdtKind::Struct | AdtKind::Union => Value::Fields(values),
+                })
+            }
+            // e.g. `-128i8`, which does not overflow
+            ExprKind::Unary(UnOp::Neg, operand)
+                if let ExprKind::Literal(Literal::Int(lit)) = &operand.kind =>
+            {
+                let lit = IntLit { negative: !lit.negative, ..lit.clone() };
+                Ok(self.literal(&Literal::Int(lit), expr.ty, expr.span))
+            }
+            ExprKind::Unary(op, operand) => {
+                let value = self.expr(operand)?;
+                self.span = expr.span;
+                self.unary(*op, value, operand.ty)
+            }
+            ExprKind::Ref { expr: inner, .. } => {
+                Ok(Value::Ptr(self.place(inner)?))
+            }
+            ExprKind::Box(inner) => {
+                let value = self.expr(inner)?;
+                Ok(Value::Ptr(Pointer::new(self.allocate(value, true))))
+            }
+            ExprKind::Binary(op @ (BinOp::And | BinOp::Or), lhs, rhs) => {
+                let lhs = self.expr(lhs)?;
+                if lhs == Value::Bool(*op == BinOp::Or) {
+                    return Ok(lhs);
+                }
+                self.expr(rhs)
+            }
+            ExprKind::Binary(op, lhs, rhs) => {
+                let (ty, rhs_ty) = (lhs.ty, rhs.ty);
+                let lhs = self.expr(lhs)?;
+                let rhs = self.expr(rhs)?;
+                self.span = expr.span;
+                self.binary(*op, lhs, rhs, ty, rhs_ty)
+            }
+            ExprKind::Assign(lhs, rhs) => {
+                let value = self.expr(rhs)?;
+                let place = self.place(lhs)?;
+                let old = mem::replace(self.slot(&place)?, value);
+                self.drop_value(old, lhs.ty);
+                Ok(Value::Fields(Vec::new()))
+            }
+            ExprKind::AssignOp(op, lhs, rhs) => {
+                let rhs_value = self.expr(rhs)?;
+                let place = self.place(lhs)?;
+                let lhs_value = self.slot(&place)?.clone();
+                self.span = expr.span;
+                let value =
+                    self.binary(*op, lhs_value, rhs_value, lhs.ty, rhs.ty)?;
+                *self.slot(&place)? = value;
+                Ok(Value::Fields(Vec::new()))
+            }
+            ExprKind::Cast(inner) => {
+                let value = self.expr(inner)?;
+                Ok(self.cast(value, inner.ty, expr.ty))
+            }
+            ExprKind::Call(callee, args) => {
+                let callee = self.expr(callee)?;
+                let args = args.iter().map(|arg| self.expr(arg));
+                let args = args.collect::<Eval<Vec<_>>>()?;
+                self.span = expr.span;
+                match callee {
+                    Value::Fn(instance) => self.call(instance, args),
+                    _ => {
+                        Err(self.abort("called a value that is not a function"))
+                    }
+                }
+            }
+            ExprKind::Block(block) => self.block(block),
+            ExprKind::If { cond, then_branch, else_branch } => {
+                if self.expr(cond)? == Value::Bool(true) {
+                    self.block(then_branch)
+                } else if let Some(else_branch) = else_branch {
+                    self.expr(else_branch)
+                } else {
+                    Ok(Value::Fields(Vec::new()))
+                }
+            }
+            ExprKind::Match { scrutinee, arms } => {
+                let place = self.place(scrutinee)?;
+                for arm in arms {
+                    if !self.matches(&arm.pat, &place)? {
+                        continue;
+                    }
+                    if let Some(guard) = &arm.guard {
+                        // the bindings are copies until the arm is taken
+                        let taken = self.scoped(false, |this| {
+                            this.bind(&arm.pat, &place, true)?;
+                            this.expr(guard)
+                        })?;
+                        if taken != Value::Bool(true) {
+                            continue;
+                        }
+                    }
+                    return self.scoped(true, |this| {
+                        this.bind(&arm.pat, &place, false)?;
+                        this.expr(&arm.body)
+                    });
+                }
+                self.span = expr.span;
+                Err(self.abort("no arm matched"))
+            }
+            ExprKind::Loop { id, body } => loop {
+                match self.block(body) {
+                    Ok(_) => {}
+                    Err(Flow::Break(target, value)) if target == *id => {
+                        return Ok(value);
+                    }
+                    Err(Flow::Continue(target)) if target == *id => {}
+                    Err(flow) => return Err(flow),
+                }
+            },
+            ExprKind::For { id, pat, iter, body } => {
+                self.for_loop(*id, pat, iter, body)?;
+                Ok(Value::Fields(Vec::new()))
+            }
+            ExprKind::Break { target, value } => {
+                let value = match value {
+                    Some(value) => self.expr(value)?,
+                    None => Value::Fields(Vec::new()),
+                };
+                Err(Flow::Break(*target, value))
+            }
+            ExprKind::Continue { target } => Err(Flow::Continue(*target)),
+            ExprKind::Return(value) => {
+                let value = match value {
+                    Some(value) => self.expr(value)?,
+                    None => Value::Fields(Vec::new()),
+                };
+                Err(Flow::Return(value))
+            }
+            ExprKind::Format { mac, pieces, args } => {
+                let text = self.format(pieces, args)?;
+                self.span = expr.span;
+                self.print(*mac, text)
+            }
+            ExprKind::AssocConst { .. } | ExprKind::Range { .. } => {
+                Err(self.abort("an expression the interpreter cannot run"))
+            }
+            ExprKind::Err => Err(self.abort("an expression with errors")),
+        }
+    }
+
+    /// where `expr` is, or a new temporary holding its value
+    fn place(&mut self, expr: &'a Expr) -> Eval<Pointer> {
+        match &expr.kind {
+            ExprKind::Local(local) => {
+                match self.frame().locals[local.index()] {
+                    Some(alloc) => Ok(Pointer::new(alloc)),
+                    None => Err(self.abort("use of an unbound local")),
+                }
+            }
+            ExprKind::Def(id) if self.res.def(*id).kind == DefKind::Static => {
+                self.static_place(*id)
+            }
+            ExprKind::Field(base, index) => {
+                let place = self.place(base)?;
+                let place = self.follow(place, base.ty)?;
+                Ok(place.field(*index))
+            }
+            ExprKind::Index(base, index) => {
+                let place = self.place(base)?;
+                let place = self.follow(place, base.ty)?;
+                let Value::Int(index) = self.expr(index)? else {
+                    return Err(self.abort("an index that is not an integer"));
+                };
+                self.span = expr.span;
+                let len = match self.slot(&place)? {
+                    Value::Fields(elems) => elems.len(),
+                    _ => {
+                        return Err(
+                            self.abort("indexed a value that is not an array")
+                        );
+                    }
+                };
+                if index >= len as u128 {
+                    return Err(self.abort(format!(
+                        "index out of bounds: the len is {len} but the index \
+                         is {index}"
+                    )));
+                }
+                Ok(place.field(index as usize))
+            }
+            ExprKind::Unary(UnOp::Deref, base) => {
+                let place = self.place(base)?;
+                self.span = expr.span;
+                self.deref(&place)
+            }
+            _ => {
+                let value = self.expr(expr)?;
+                Ok(self.temp(value, expr.ty))
+            }
+        }
+    }
+
+    /// the place behind the pointers of type `ty` at `place`, for the
+    /// fields and the elements of a value behind a reference
+    fn follow(&mut self, mut place: Pointer, mut ty: Ty) -> Eval<Pointer> {
+        while let TyKind::Ref { ty: inner, .. }
+        | TyKind::Ptr { ty: inner, .. }
+        | TyKind::Box(inner) = *self.tcx.kind(ty)
+        {
+            place = self.deref(&place)?;
+            ty = inner;
+        }
+        Ok(place)
+    }
+
+    /// the place a pointer at `place` points to
+    fn deref(&mut self, place: &Pointer) -> Eval<Pointer> {
+        match self.slot(place)? {
+            Value::Ptr(pointer) => Ok(pointer.clone()),
+            _ => Err(self.abort("dereferenced a value that is not a pointer")),
+        }
+    }
+
+    fn block(&mut self, block: &'a Block) -> Eval<Value> {
+        self.scoped(true, |this| {
+            for stmt in &block.stmts {
+                this.span = stmt.span;
+                match &stmt.kind {
+                    StmtKind::Let { pat, init: None, .. } => {
+                        this.declare_uninit(pat);
+                    }
+                    StmtKind::Let { pat, init: Some(init), els } => {
+                        let place = this.place(init)?;
+                        if !this.matches(pat, &place)? {
+                            let Some(els) = els else {
+                                return Err(this.abort("a refutable pattern"));
+                            };
+                            // `else` diverges
+                            this.block(els)?;
+                            return Err(
+                                this.abort("`else` of `let` did not diverge")
+                            );
+                        }
+                        this.bind(pat, &place, false)?;
+                    }
+                    StmtKind::Expr(expr) => {
+                        let value = this.expr(expr)?;
+                        this.drop_value(value, expr.ty);
+                    }
+                }
+            }
+            match &block.expr {
+                Some(expr) => this.expr(expr),
+                None => Ok(Value::Fields(Vec::new())),
+            }
+        })
+    }
+
+    /// runs `f` in a new scope, and drops its locals and temporaries unless
+    /// they are copies
+    fn scoped<T>(
+        &mut self, drop: bool, f: impl FnOnce(&mut Self) -> Eval<T>,
+    ) -> Eval<T> {
+        self.frame_mut().scopes.push(Vec::new());
+        let result = f(self);
+        let scope = self.frame_mut().scopes.pop().unwrap();
+        if drop {
+            self.drop_scope(scope);
+        } else {
+            for (alloc, _) in scope {
+                self.deallocate(alloc);
+            }
+        }
+        result
+    }
+
+    fn for_loop(
+        &mut self, id: LoopId, pat: &'a Pat, iter: &'a Expr, body: &'a Block,
+    ) -> Eval<()> {
+        if let ExprKind::Range { start, end, inclusive } = &iter.kind {
+            let (Value::Int(mut index), Value::Int(end)) =
+                (self.expr(start)?, self.expr(end)?)
+            else {
+                return Err(
+                    self.abort("a range of values that are not integers")
+                );
+            };
+            let ty = start.ty;
+            loop {
+                match self.compare(&Value::Int(index), &Value::Int(end), ty) {
+                    Ordering::Less => {}
+                    Ordering::Equal if *inclusive => {}
+                    _ => return Ok(()),
+                }
+                if !self.iterate(id, pat, body, Value::Int(index))?
+                    || index == end
+                {
+                    return Ok(());
+                }
+                index = index.wrapping_add(1) & self.mask(ty);
+            }
+        }
+        let value = self.expr(iter)?;
+        match (value, self.tcx.kind(iter.ty).clone()) {
+            (Value::Fields(elems), TyKind::Array(elem_ty, _)) => {
+                let mut elems = elems.into_iter();
+                for elem in elems.by_ref() {
+                    if !self.iterate(id, pat, body, elem)? {
+                        break;
+                    }
+                }
+                for elem in elems {
+                    self.drop_value(elem, elem_ty);
+                }
+                Ok(())
+            }
+            (Value::Ptr(pointer), _) => {
+                let len = match self.slot(&pointer)? {
+                    Value::Fields(elems) => elems.len(),
+                    _ => {
+                        return Err(self.abort(
+                            "iterated over a value that is not an array",
+                        ));
+                    }
+                };
+                for index in 0..len {
+                    let elem = Value::Ptr(pointer.field(index));
+                    if !self.iterate(id, pat, body, elem)? {
+                        break;
+                    }
+                }
+                Ok(())
+            }
+            _ => Err(self.abort("iterated over a value that is not an array")),
+        }
+    }
+
+    /// binds `value` to `pat` and runs `body`, [false] for a `break`
+    fn iterate(
+        &mut self, id: LoopId, pat: &'a Pat, body: &'a Block, value: Value,
+    ) -> Eval<bool> {
+        self.scoped(true, |this| {
+            let place = this.temp(value, pat.ty);
+            this.bind(pat, &place, false)?;
+            match this.block(body) {
+                Ok(_) => Ok(true),
+                Err(Flow::Continue(target)) if target == id => Ok(true),
+                Err(Flow::Break(target, _)) if target == id => Ok(false),
+                Err(flow) => Err(flow),
+            }
+        })
+    }
+}
+impl<'a> Interpreter<'a> {
+    /// the value at `place` matches `pat`, without binding it
+    fn matches(&mut self, pat: &'a Pat, place: &Pointer) -> Eval<bool> {
+        Ok(match &pat.kind {
+            PatKind::Wild | PatKind::Binding { sub: None, .. } => true,
+            PatKind::Binding { sub: Some(sub), .. } => {
+                self.matches(sub, place)?
+            }
+            PatKind::Literal(_) | PatKind::Const(_) => {
+                let expected = self.pat_value(pat)?;
+                let value = self.slot(place)?.clone();
+                self.values_eq(&value, &expected)?
+            }
+            PatKind::Range { start, end, inclusive } => {
+                let value = self.slot(place)?.clone();
+                if let Some(start) = start {
+                    let start = self.pat_value(start)?;
+                    if self.compare(&value, &start, pat.ty) == Ordering::Less {
+                        return Ok(false);
+                    }
+                }
+                match end {
+                    Some(end) => {
+                        let end = self.pat_value(end)?;
+                        match self.compare(&value, &end, pat.ty) {
+                            Ordering::Less => true,
+                            Ordering::Equal => *inclusive,
+                            Ordering::Greater => false,
+                        }
+                    }
+                    None => true,
+                }
+            }
+            PatKind::Adt { variant, fields, .. } => {
+                if let Value::Variant(index, _) = self.slot(place)?
+                    && index != variant
+                {
+                    return Ok(false);
+                }
+                for (index, field) in fields {
+                    if !self.matches(field, &place.field(*index))? {
+                        return Ok(false);
+                    }
+                }
+                true
+            }
+            PatKind::Tuple(pats) => {
+                for (index, pat) in pats.iter().enumerate() {
+                    if !self.matches(pat, &place.field(index))? {
+                        return Ok(false);
+                    }
+                }
+                true
+            }
+            PatKind::Slice { prefix, rest, suffix } => {
+                let Value::Fields(elems) = self.slot(place)? else {
+                    return Ok(false);
+                };
+                let len = elems.len();
+                let min = prefix.len() + suffix.len();
+                if len < min || (!rest && len != min) {
+                    return Ok(false);
+                }
+                let indices = (0..prefix.len()).chain(len - suffix.len()..len);
+                for (index, pat) in indices.zip(prefix.iter().chain(suffix)) {
+                    if !self.matches(pat, &place.field(index))? {
+                        return Ok(false);
+                    }
+                }
+                true
+            }
+            PatKind::Ref(inner) => {
+                let target = self.deref(place)?;
+                self.matches(inner, &target)?
+            }
+            PatKind::Or(pats) => {
+                for pat in pats {
+                    if self.matches(pat, place)? {
+                        return Ok(true);
+                    }
+                }
+                false
+            }
+        })
+    }
+
+    /// binds the locals of `pat` to the value at `place` it matches, by
+    /// moving it out of `place` unless `copy`
+    fn bind(&mut self, pat: &'a Pat, place: &Pointer, copy: bool) -> Eval<()> {
+        match &pat.kind {
+            PatKind::Wild
+            | PatKind::Literal(_)
+            | PatKind::Const(_)
+            | PatKind::Range { .. } => {}
+            PatKind::Binding { local, by_ref, sub } => {
+                if let Some(sub) = sub {
+                    self.bind(sub, place, copy)?;
+                }
+                let value = if *by_ref {
+                    Value::Ptr(place.clone())
+                } else if copy {
+                    self.slot(place)?.clone()
+                } else {
+                    self.read(place, pat.ty)?
+                };
+                self.declare(*local, value);
+            }
+            PatKind::Adt { fields, .. } => {
+                for (index, field) in fields {
+                    self.bind(field, &place.field(*index), copy)?;
+                }
+            }
+            PatKind::Tuple(pats) => {
+                for (index, pat) in pats.iter().enumerate() {
+                    self.bind(pat, &place.field(index), copy)?;
+                }
+            }
+            PatKind::Slice { prefix, suffix, .. } => {
+                let len = match self.slot(place)? {
+                    Value::Fields(elems) => elems.len(),
+                    _ => 0,
+                };
+                let indices = (0..prefix.len()).chain(len - suffix.len()..len);
+                for (index, pat) in indices.zip(prefix.iter().chain(suffix)) {
+                    self.bind(pat, &place.field(index), copy)?;
+                }
+            }
+            PatKind::Ref(inner) => {
+                let target = self.deref(place)?;
+                self.bind(inner, &target, copy)?;
+            }
+            PatKind::Or(pats) => {
+                for pat in pats {
+                    if self.matches(pat, place)? {
+                        return self.bind(pat, place, copy);
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// the value of a literal or a constant pattern
+    fn pat_value(&mut self, pat: &'a Pat) -> Eval<Value> {
+        match &pat.kind {
+            PatKind::Literal(lit) => Ok(self.literal(lit, pat.ty, pat.span)),
+            PatKind::Const(id) => self.const_value(*id),
+            _ => Err(self.abort("a pattern without a value")),
+        }
+    }
+
+    fn declare(&mut self, local: LocalId, value: Value) {
+        let ty = self.frame().body.local(local).ty;
+        let alloc = self.allocate(value, false);
+        let frame = self.frame_mut();
+        frame.locals[local.index()] = Some(alloc);
+        frame.scopes.last_mut().unwrap().push((alloc, ty));
+    }
+
+    /// declares the locals of `let pat;`
+    fn declare_uninit(&mut self, pat: &'a Pat) {
+        match &pat.kind {
+            PatKind::Binding { local, sub, .. } => {
+                self.declare(*local, Value::Uninit);
+                if let Some(sub) = sub {
+                    self.declare_uninit(sub);
+                }
+            }
+            PatKind::Adt { fields, .. } => {
+                for (_, field) in fields {
+                    self.declare_uninit(field);
+                }
+            }
+            PatKind::Tuple(pats) | PatKind::Or(pats) => {
+                for pat in pats {
+                    self.declare_uninit(pat);
+                }
+            }
+            PatKind::Slice { prefix, suffix, .. } => {
+                for pat in prefix.iter().chain(suffix) {
+                    self.declare_uninit(pat);
+                }
+            }
+            PatKind::Ref(inner) => self.declare_uninit(inner),
+            PatKind::Wild
+            | PatKind::Literal(_)
+            | PatKind::Const(_)
+            | PatKind::Range { .. } => {}
+        }
+    }
+
+    fn frame(&self) -> &Frame<'a> { self.frames.last().unwrap() }
+
+    fn frame_mut(&mut self) -> &mut Frame<'a> {
+        self.frames.last_mut().unwrap()
+    }
+
+    fn allocate(&mut self, value: Value, heap: bool) -> usize {
+        let allocation = Some(Allocation { value, heap });
+        match self.free.pop() {
+            Some(alloc) => {
+                self.memory[alloc] = allocation;
+                alloc
+            }
+            None => {
+                self.memory.push(allocation);
+                self.memory.len() - 1
+            }
+        }
+    }
+
+    fn deallocate(&mut self, alloc: usize) -> Option<Allocation> {
+        let allocation = self.memory.get_mut(alloc)?.take()?;
+        self.free.push(alloc);
+        Some(allocation)
+    }
+
+    /// a temporary of the innermost scope holding `value`
+    fn temp(&mut self, value: Value, ty: Ty) -> Pointer {
+        let alloc = self.allocate(value, false);
+        self.frame_mut().scopes.last_mut().unwrap().push((alloc, ty));
+        Pointer::new(alloc)
+    }
+
+    fn slot(&mut self, place: &Pointer) -> Eval<&mut Value> {
+        let span = self.span;
+        let Some(Some(allocation)) = self.memory.get_mut(place.alloc) else {
+            return Err(abort(span, "use of a dangling pointer"));
+        };
+        let mut value = &mut allocation.value;
+        for &index in &place.path {
+            value = match value {
+                Value::Fields(fields) | Value::Variant(_, fields) => {
+                    fields.get_mut(index)
+                }
+                _ => None,
+            }
+            .ok_or_else(|| abort(span, "use of a field that does not exist"))?;
+        }
+        Ok(value)
+    }
+
+    /// the value at `place`, moved out unless it owns nothing to drop
+    fn read(&mut self, place: &Pointer, ty: Ty) -> Eval<Value> {
+        let moves = self.needs_drop(ty);
+        let span = self.span;
+        let slot = self.slot(place)?;
+        if *slot == Value::Uninit {
+            return Err(abort(span, "use of an uninitialized or moved value"));
+        }
+        Ok(if moves { mem::replace(slot, Value::Uninit) } else { slot.clone() })
+    }
+
+    /// the allocation of a static, initialized when it is first used
+    fn static_place(&mut self, id: DefId) -> Eval<Pointer> {
+        if let Some(alloc) = self.statics.get(&id) {
+            return Ok(Pointer::new(*alloc));
+        }
+        let value =
+            self.call(Instance { def: id, args: Vec::new() }, Vec::new())?;
+        let alloc = self.allocate(value, false);
+        self.statics.insert(id, alloc);
+        Ok(Pointer::new(alloc))
+    }
+
+    /// the value of a constant, evaluated when it is first used
+    fn const_value(&mut self, id: DefId) -> Eval<Value> {
+        if let Some(value) = self.consts.get(&id) {
+            return Ok(value.clone());
+        }
+        let instance = self.items.keys().find(|instance| instance.def == id);
+        let Some(instance) = instance.map(|instance| (*instance).clone())
+        else {
+            let name = self.res.def_path(id);
+            return Err(self.abort(format!("`{name}` has no body to run")));
+        };
+        let value = self.call(instance, Vec::new())?;
+        self.consts.insert(id, value.clone());
+        Ok(value)
+    }
+
+    fn drop_scope(&mut self, scope: Vec<(usize, Ty)>) {
+        for (alloc, ty) in scope.into_iter().rev() {
+            if let Some(allocation) = self.deallocate(alloc) {
+                self.drop_value(allocation.value, ty);
+            }
+        }
+    }
+
+    /// frees the boxes owned by `value` of type `ty`
+    fn drop_value(&mut self, value: Value, ty: Ty) {
+        if !self.needs_drop(ty) {
+            return;
+        }
+        match (value, self.tcx.kind(ty).clone()) {
+            (Value::Ptr(pointer), TyKind::Box(inner)) => {
+                if let Some(allocation) = self.deallocate(pointer.alloc) {
+                    let inner = pointer.dyn_ty.unwrap_or(inner);
+                    self.drop_value(allocation.value, inner);
+                }
+            }
+            (Value::Fields(values), TyKind::Tuple(tys)) => {
+                for (value, ty) in values.into_iter().zip(tys) {
+                    self.drop_value(value, ty);
+                }
+            }
+            (
+                Value::Fields(values),
+                TyKind::Array(elem, _) | TyKind::Slice(elem),
+            ) => {
+                for value in values {
+                    self.drop_value(value, elem);
+                }
+            }
+            (Value::Fields(values), TyKind::Adt(..)) => {
+                for (index, value) in values.into_iter().enumerate() {
+                    let ty = self.tcx.field_ty(ty, 0, index);
+                    self.drop_value(value, ty);
+                }
+            }
+            (Value::Variant(variant, values), TyKind::Adt(..)) => {
+                for (index, value) in values.into_iter().enumerate() {
+                    let ty = self.tcx.field_ty(ty, variant, index);
+                    self.drop_value(value, ty);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    /// values of `ty` may own a box, so they are moved instead of copied
+    fn needs_drop(&mut self, ty: Ty) -> bool {
+        if let Some(needs_drop) = self.drop_cache.get(&ty) {
+            return *needs_drop;
+        }
+        // a type containing itself does so through a box
+        self.drop_cache.insert(ty, false);
+        let needs_drop = match self.tcx.kind(ty).clone() {
+            TyKind::Box(_) => true,
+            TyKind::Array(elem, _) | TyKind::Slice(elem) => {
+                self.needs_drop(elem)
+            }
+            TyKind::Tuple(tys) => tys.into_iter().any(|ty| self.needs_drop(ty)),
+            TyKind::Adt(id, _) => {
+                let fields: Vec<usize> = match self.tcx.adt(id) {
+                    Some(adt) if adt.kind != AdtKind::Union => adt
+                        .variants
+                        .iter()
+                        .map(|variant| variant.fields.len())
+                        .collect(),
+                    _ => Vec::new(),
+                };
+                fields.into_iter().enumerate().any(|(variant, len)| {
+                    (0..len).any(|index| {
+                        let ty = self.tcx.field_ty(ty, variant, index);
+                        self.needs_drop(ty)
+                    })
+                })
+            }
+            _ => false,
+        };
+        self.drop_cache.insert(ty, needs_drop);
+        needs_drop
+    }
+
+    /// a panic at the expression being evaluated
+    fn abort(&self, message: impl Into<String>) -> Flow {
+        abort(self.span, message)
+    }
+}
+fn abort(span: Span, message: impl Into<String>) -> Flow {
+    Flow::Abort(Box::new(
+        Diagnostic::error(message)
+            .with_primary(span, "the program panicked here"),
+    ))
+}
+impl<'a> Interpreter<'a> {
+    fn literal(&mut self, lit: &Literal, ty: Ty, span: Span) -> Value {
+        match lit {
+            Literal::Int(lit) => {
+                if let TyKind::Float(_) = self.tcx.kind(ty) {
+                    let value = lit.value as f64;
+                    return self
+                        .round(if lit.negative { -value } else { value }, ty);
+                }
+                let value = if lit.negative {
+                    lit.value.wrapping_neg()
+                } else {
+                    lit.value
+                };
+                Value::Int(value & self.mask(ty))
+            }
+            Literal::Float(lit) => {
+                let value = match self.tcx.kind(ty) {
+                    TyKind::Float(FloatTy::F32) => {
+                        lit.text.parse::<f32>().map(f64::from)
+                    }
+                    _ => lit.text.parse::<f64>(),
+                };
+                Value::Float(value.unwrap_or(f64::NAN))
+            }
+            Literal::Bool(value) => Value::Bool(*value),
+            Literal::Char(value) => Value::Int(*value as u128),
+            Literal::CChar8(value) => Value::Int(*value as u128),
+            Literal::CChar16(value) => Value::Int(*value as u128),
+            Literal::String(_)
+            | Literal::CChar8Str(_)
+            | Literal::CChar16Str(_) => {
+                if let Some(alloc) = self.literals.get(&span) {
+                    return Value::Ptr(Pointer::new(*alloc));
+                }
+                let value = match lit {
+                    Literal::String(text) => Value::Str(text.clone()),
+                    Literal::CChar8Str(units) => Value::Fields(
+                        units
+                            .iter()
+                            .map(|unit| Value::Int(*unit as u128))
+                            .collect(),
+                    ),
+                    Literal::CChar16Str(units) => Value::Fields(
+                        units
+                            .iter()
+                            .map(|unit| Value::Int(*unit as u128))
+                            .collect(),
+                    ),
+                    _ => unreachable!(),
+                };
+                let alloc = self.allocate(value, false);
+                self.literals.insert(span, alloc);
+                Value::Ptr(Pointer::new(alloc))
+            }
+        }
+    }
+
+    fn unary(&mut self, op: UnOp, value: Value, ty: Ty) -> Eval<Value> {
+        Ok(match (op, value) {
+            (UnOp::Not, Value::Bool(value)) => Value::Bool(!value),
+            (UnOp::Not, Value::Int(value)) => {
+                Value::Int(!value & self.mask(ty))
+            }
+            (UnOp::Neg, Value::Float(value)) => Value::Float(-value),
+            (UnOp::Neg, Value::Int(value)) => {
+                let (bits, _) = self.width(ty);
+                if value == 1 << (bits - 1) {
+                    return Err(self.abort("attempt to negate with overflow"));
+                }
+                Value::Int(value.wrapping_neg() & self.mask(ty))
+            }
+            _ => {
+                return Err(
+                    self.abort("an operation on values of the wrong type")
+                );
+            }
+        })
+    }
+
+    /// `lhs op rhs`, `ty` is the type of `lhs` and `rhs_ty` of `rhs`, which
+    /// differ for shifts
+    fn binary(
+        &mut self, op: BinOp, lhs: Value, rhs: Value, ty: Ty, rhs_ty: Ty,
+    ) -> Eval<Value> {
+        let compare = |ordering: Ordering| {
+            Value::Bool(match op {
+                BinOp::Eq => ordering == Ordering::Equal,
+                BinOp::Ne => ordering != Ordering::Equal,
+                BinOp::Lt => ordering == Ordering::Less,
+                BinOp::Le => ordering != Ordering::Greater,
+                BinOp::Gt => ordering == Ordering::Greater,
+                _ => ordering != Ordering::Less,
+            })
+        };
+        let is_comparison = matches!(
+            op,
+            BinOp::Eq
+                | BinOp::Ne
+                | BinOp::Lt
+                | BinOp::Le
+                | BinOp::Gt
+                | BinOp::Ge
+        );
+        match (lhs, rhs) {
+            (Value::Int(lhs), Value::Int(rhs)) => {
+                if is_comparison {
+                    let ordering =
+                        self.compare(&Value::Int(lhs), &Value::Int(rhs), ty);
+                    return Ok(compare(ordering));
+                }
+                self.int_binary(op, lhs, rhs, ty, rhs_ty).map(Value::Int)
+            }
+            (Value::Float(lhs), Value::Float(rhs)) => {
+                if is_comparison {
+                    // every comparison with NaN is false, but `!=`
+                    return Ok(match lhs.partial_cmp(&rhs) {
+                        Some(ordering) => compare(ordering),
+                        None => Value::Bool(op == BinOp::Ne),
+                    });
+                }
+                let value = match op {
+                    BinOp::Add => lhs + rhs,
+                    BinOp::Sub => lhs - rhs,
+                    BinOp::Mul => lhs * rhs,
+                    BinOp::Div => lhs / rhs,
+                    BinOp::Rem => lhs % rhs,
+                    _ => {
+                        return Err(self.abort(
+                            "an operation on values of the wrong type",
+                        ));
+                    }
+                };
+                Ok(self.round(value, ty))
+            }
+            (Value::Bool(lhs), Value::Bool(rhs)) => Ok(match op {
+                BinOp::BitAnd => Value::Bool(lhs & rhs),
+                BinOp::BitOr => Value::Bool(lhs | rhs),
+                BinOp::BitXor => Value::Bool(lhs ^ rhs),
+                _ => compare(lhs.cmp(&rhs)),
+            }),
+            (Value::Ptr(lhs), Value::Ptr(rhs)) => {
+                let key =
+                    |pointer: &Pointer| (pointer.alloc, pointer.path.clone());
+                Ok(compare(key(&lhs).cmp(&key(&rhs))))
+            }
+            (lhs, rhs) if is_comparison && lhs == rhs => {
+                Ok(compare(Ordering::Equal))
+            }
+            _ => Err(self.abort("an operation on values of the wrong type")),
+        }
+    }
+
+    fn int_binary(
+        &mut self, op: BinOp, lhs: u128, rhs: u128, ty: Ty, rhs_ty: Ty,
+    ) -> Eval<u128> {
+        let (bits, signed) = self.width(ty);
+        let mask = self.mask(ty);
+        let (a, b) = (sext(lhs, bits), sext(rhs, bits));
+        let checked = match op {
+            BinOp::Add | BinOp::Sub | BinOp::Mul => {
+                let (value, name) = if signed {
+                    let value = match op {
+                        BinOp::Add => a.checked_add(b),
+                        BinOp::Sub => a.checked_sub(b),
+                        _ => a.checked_mul(b),
+                    };
+                    let value = value.filter(|value| {
+                        sext(*value as u128 & mask, bits) == *value
+                    });
+                    (value.map(|value| value as u128 & mask), op_name(op))
+                } else {
+                    let value = match op {
+                        BinOp::Add => lhs.checked_add(rhs),
+                        BinOp::Sub => lhs.checked_sub(rhs),
+                        _ => lhs.checked_mul(rhs),
+                    };
+                    (value.filter(|value| *value <= mask), op_name(op))
+                };
+                value.ok_or_else(|| format!("attempt to {name} with overflow"))
+            }
+            BinOp::Div | BinOp::Rem => {
+                let div = op == BinOp::Div;
+                if rhs == 0 {
+                    Err(if div {
+                        "attempt to divide by zero".to_string()
+                    } else {
+                        "attempt to calculate the remainder with a divisor of \
+                         zero"
+                            .to_string()
+                    })
+                } else if signed && b == -1 && lhs == 1 << (bits - 1) {
+                    Err(if div {
+                        "attempt to divide with overflow".to_string()
+                    } else {
+                        "attempt to calculate the remainder with overflow"
+                            .to_string()
+                    })
+                } else if signed {
+                    Ok((if div { a / b } else { a % b }) as u128 & mask)
+                } else {
+                    Ok(if div { lhs / rhs } else { lhs % rhs })
+                }
+            }
+            BinOp::BitAnd => Ok(lhs & rhs),
+            BinOp::BitOr => Ok(lhs | rhs),
+            BinOp::BitXor => Ok(lhs ^ rhs),
+            BinOp::Shl | BinOp::Shr => {
+                let (rhs_bits, rhs_signed) = self.width(rhs_ty);
+                let amount =
+                    if rhs_signed { sext(rhs, rhs_bits) } else { rhs as i128 };
+                let name =
+                    if op == BinOp::Shl { "shift left" } else { "shift right" };
+                if !(0..bits as i128).contains(&amount) {
+                    Err(format!("attempt to {name} with overflow"))
+                } else if op == BinOp::Shl {
+                    Ok((lhs << amount) & mask)
+                } else if signed {
+                    Ok((a >> amount) as u128 & mask)
+                } else {
+                    Ok(lhs >> amount)
+                }
+            }
+            _ => Err("an operation on values of the wrong type".to_string()),
+        };
+        checked.map_err(|message| self.abort(message))
+    }
+
+    /// `value as ty`, or a coercion from `from` to `ty`
+    fn cast(&mut self, value: Value, from: Ty, ty: Ty) -> Value {
+        match (value, self.tcx.kind(ty).clone()) {
+            (
+                Value::Int(value),
+                TyKind::Int(_) | TyKind::Uint(_) | TyKind::Char(_),
+            ) => {
+                let (bits, signed) = self.width(from);
+                let value =
+                    if signed { sext(value, bits) as u128 } else { value };
+                Value::Int(value & self.mask(ty))
+            }
+            (Value::Int(value), TyKind::Float(_)) => {
+                let (bits, signed) = self.width(from);
+                let value = if signed {
+                    sext(value, bits) as f64
+                } else {
+                    value as f64
+                };
+                self.round(value, ty)
+            }
+            (Value::Float(value), TyKind::Int(_) | TyKind::Uint(_)) => {
+                // saturating, and NaN is zero
+                let (bits, signed) = self.width(ty);
+                let value = if signed {
+                    let max = (1i128 << (bits - 1)) - 1;
+                    (value as i128).clamp(-max - 1, max) as u128
+                } else {
+                    (value as u128).min(self.mask(ty))
+                };
+                Value::Int(value & self.mask(ty))
+            }
+            (Value::Float(value), TyKind::Float(_)) => self.round(value, ty),
+            (Value::Bool(value), TyKind::Int(_) | TyKind::Uint(_)) => {
+                Value::Int(value as u128)
+            }
+            (Value::Ptr(mut pointer), kind) => {
+                // e.g. `i32.Box` to `dyn A.Box` keeps the type it points to
+                let pointee = |kind: &TyKind| match kind {
+                    TyKind::Ref { ty, .. }
+                    | TyKind::Ptr { ty, .. }
+                    | TyKind::Box(ty) => Some(*ty),
+                    _ => None,
+                };
+                if let Some(target) = pointee(&kind)
+                    && let TyKind::Dynamic(_) = self.tcx.kind(target)
+                    && pointer.dyn_ty.is_none()
+                {
+                    pointer.dyn_ty = pointee(&self.tcx.kind(from).clone());
+                }
+                Value::Ptr(pointer)
+            }
+            (value, _) => value,
+        }
+    }
+
+    /// orders two integers, floats or booleans of type `ty`
+    fn compare(&self, lhs: &Value, rhs: &Value, ty: Ty) -> Ordering {
+        match (lhs, rhs) {
+            (Value::Int(lhs), Value::Int(rhs)) => match self.width(ty) {
+                (bits, true) => sext(*lhs, bits).cmp(&sext(*rhs, bits)),
+                _ => lhs.cmp(rhs),
+            },
+            (Value::Float(lhs), Value::Float(rhs)) => {
+                lhs.partial_cmp(rhs).unwrap_or(Ordering::Equal)
+            }
+            (Value::Bool(lhs), Value::Bool(rhs)) => lhs.cmp(rhs),
+            _ => Ordering::Equal,
+        }
+    }
+
+    /// compares the values behind pointers, unlike `==` on pointers
+    fn values_eq(&mut self, lhs: &Value, rhs: &Value) -> Eval<bool> {
+        match (lhs, rhs) {
+            (Value::Ptr(lhs), Value::Ptr(rhs)) => {
+                let lhs = self.slot(lhs)?.clone();
+                let rhs = self.slot(rhs)?.clone();
+                self.values_eq(&lhs, &rhs)
+            }
+            (Value::Variant(lhs, _), Value::Variant(rhs, _)) if lhs != rhs => {
+                Ok(false)
+            }
+            (Value::Fields(lhs), Value::Fields(rhs))
+            | (Value::Variant(_, lhs), Value::Variant(_, rhs)) => {
+                if lhs.len() != rhs.len() {
+                    return Ok(false);
+                }
+                for (lhs, rhs) in lhs.iter().zip(rhs) {
+                    if !self.values_eq(lhs, rhs)? {
+                        return Ok(false);
+                    }
+                }
+                Ok(true)
+            }
+            _ => Ok(lhs == rhs),
+        }
+    }
+
+    /// the width of an integer or a character type, and whether it is signed
+    fn width(&self, ty: Ty) -> (u32, bool) {
+        self.tcx.int_width(ty, self.target).unwrap_or((128, false))
+    }
+
+    /// the bits of an integer or a character type
+    fn mask(&self, ty: Ty) -> u128 { u128::MAX >> (128 - self.width(ty).0) }
+
+    /// rounds `value` to an `f32` for that type
+    fn round(&self, value: f64, ty: Ty) -> Value {
+        match self.tcx.kind(ty) {
+            TyKind::Float(FloatTy::F32) => Value::Float(value as f32 as f64),
+            _ => Value::Float(value),
+        }
+    }
+}
+/// `value` of `bits` bits as a signed integer
+fn sext(value: u128, bits: u32) -> i128 {
+    ((value << (128 - bits)) as i128) >> (128 - bits)
+}
+fn op_name(op: BinOp) -> &'static str {
+    match op {
+        BinOp::Add => "add",
+        BinOp::Sub => "subtract",
+        _ => "multiply",
+    }
+}
+impl<'a> Interpreter<'a> {
+    /// the text of a format string with its arguments
+    fn format(
+        &mut self, pieces: &'a [FormatPiece], args: &'a [Expr],
+    ) -> Eval<String> {
+        let mut places = Vec::new();
+        for arg in args {
+            places.push(self.place(arg)?);
+        }
+        let mut text = String::new();
+        for piece in pieces {
+            match piece {
+                FormatPiece::Literal(literal) => text.push_str(literal),
+                FormatPiece::Placeholder { arg, spec } => {
+                    let value = self.slot(&places[*arg])?.clone();
+                    let (formatted, numeric) =
+                        self.format_value(&value, args[*arg].ty, spec)?;
+                    text.push_str(&pad(formatted, spec, numeric));
+                }
+            }
+        }
+        Ok(text)
+    }
+
+    /// writes the text of `print!` and the like, a `str.Box` for `format!`
+    fn print(&mut self, mac: FormatMacro, text: String) -> Eval<Value> {
+        let (result, name) = match mac {
+            FormatMacro::Print => (write!(self.stdout, "{text}"), "stdout"),
+            FormatMacro::Println => (writeln!(self.stdout, "{text}"), "stdout"),
+            FormatMacro::Eprint => (write!(self.stderr, "{text}"), "stderr"),
+            FormatMacro::Eprintln => {
+                (writeln!(self.stderr, "{text}"), "stderr")
+            }
+            FormatMacro::Format => {
+                let alloc = self.allocate(Value::Str(text), true);
+                return Ok(Value::Ptr(Pointer::new(alloc)));
+            }
+        };
+        match result {
+            Ok(()) => Ok(Value::Fields(Vec::new())),
+            Err(error) => {
+                Err(self.abort(format!("failed printing to {name}: {error}")))
+            }
+        }
+    }
+
+    /// `value` of type `ty` formatted by `spec` without the padding, and
+    /// whether it is a number, which is padded with `0` for `{:0}`
+    fn format_value(
+        &mut self, value: &Value, ty: Ty, spec: &FormatSpec,
+    ) -> Eval<(String, bool)> {
+        let kind = self.tcx.kind(ty).clone();
+        let text = match (value, kind) {
+            (Value::Ptr(pointer), TyKind::Ref { ty, .. } | TyKind::Box(ty)) => {
+                let value = self.slot(pointer)?.clone();
+                return self.format_value(&value, ty, spec);
+            }
+            (Value::Int(value), TyKind::Char(_)) => {
+                let value = char::from_u32(*value as u32).unwrap_or('\u{FFFD}');
+                match spec.format_trait {
+                    FormatTrait::Debug => format!("{value:?}"),
+                    _ => value.to_string(),
+                }
+            }
+            (Value::Int(value), _) => {
+                let (bits, signed) = self.width(ty);
+                let signed = signed.then(|| sext(*value, bits));
+                let alternate = spec.alternate;
+                let text = match spec.format_trait {
+                    FormatTrait::LowerHex if alternate => format!("{value:#x}"),
+                    FormatTrait::LowerHex => format!("{value:x}"),
+                    FormatTrait::UpperHex if alternate => format!("{value:#X}"),
+                    FormatTrait::UpperHex => format!("{value:X}"),
+                    FormatTrait::Binary if alternate => format!("{value:#b}"),
+                    FormatTrait::Binary => format!("{value:b}"),
+                    FormatTrait::Octal if alternate => format!("{value:#o}"),
+                    FormatTrait::Octal => format!("{value:o}"),
+                    FormatTrait::LowerExp => match signed {
+                        Some(signed) => format!("{signed:e}"),
+                        None => format!("{value:e}"),
+                    },
+                    FormatTrait::UpperExp => match signed {
+                        Some(signed) => format!("{signed:E}"),
+                        None => format!("{value:E}"),
+                    },
+                    FormatTrait::Display | FormatTrait::Debug => match signed {
+                        Some(signed) => signed.to_string(),
+                        None => value.to_string(),
+                    },
+                };
+                return Ok((sign(text, spec), true));
+            }
+            (Value::Float(value), kind) => {
+                let text = if kind == TyKind::Float(FloatTy::F32) {
+                    format_float(*value as f32, spec)
+                } else {
+                    format_float(*value, spec)
+                };
+                return Ok((sign(text, spec), true));
+            }
+            (Value::Bool(value), _) => value.to_string(),
+            (Value::Str(value), _) => match spec.format_trait {
+                FormatTrait::Debug => format!("{value:?}"),
+                _ => match spec.precision {
+                    Some(precision) => value.chars().take(precision).collect(),
+                    None => value.clone(),
+                },
+            },
+            (Value::Fields(values), TyKind::Tuple(tys)) => {
+                let mut fields = Vec::new();
+                for (value, ty) in values.iter().zip(tys) {
+                    fields.push((None, self.format_field(value, ty, spec)?));
+                }
+                let text =
+                    debug_list(("(", ")"), fields.clone(), spec.alternate);
+                match fields.len() {
+                    1 if !spec.alternate => format!("({},)", fields[0].1),
+                    _ => text,
+                }
+            }
+            (
+                Value::Fields(values),
+                TyKind::Array(elem, _) | TyKind::Slice(elem),
+            ) => {
+                let mut elems = Vec::new();
+                for value in values {
+                    elems.push((None, self.format_field(value, elem, spec)?));
+                }
+                debug_list(("[", "]"), elems, spec.alternate)
+            }
+            (
+                Value::Fields(values) | Value::Variant(_, values),
+                TyKind::Adt(id, _),
+            ) => {
+                let variant = match value {
+                    Value::Variant(variant, _) => *variant,
+                    _ => 0,
+                };
+                let Some(def) = self.tcx.adt(id) else {
+                    return Err(self.abort("a type with errors"));
+                };
+                let def = def.variants[variant].clone();
+                let tuple = def.fields.iter().all(|field| {
+                    field.name.bytes().all(|byte| byte.is_ascii_digit())
+                });
+                let mut fields = Vec::new();
+                for (index, (value, field)) in
+                    values.iter().zip(&def.fields).enumerate()
+                {
+                    let ty = self.tcx.field_ty(ty, variant, index);
+                    let name = (!tuple).then(|| field.name.clone());
+                    fields.push((name, self.format_field(value, ty, spec)?));
+                }
+                if fields.is_empty() {
+                    def.name
+                } else if tuple {
+                    def.name + &debug_list(("(", ")"), fields, spec.alternate)
+                } else {
+                    def.name
+                        + " "
+                        + &debug_list(("{", "}"), fields, spec.alternate)
+                }
+            }
+            (Value::Ptr(pointer), _) => format!("{:#x}", address(pointer)),
+            (Value::Fn(_), _) => "fn".to_string(),
+            _ => return Err(self.abort("formatted a value of the wrong type")),
+        };
+        Ok((text, false))
+    }
+
+    /// a field of a tuple, an array or a struct for `{:?}`, padded by the
+    /// same spec
+    fn format_field(
+        &mut self, value: &Value, ty: Ty, spec: &FormatSpec,
+    ) -> Eval<String> {
+        if *value == Value::Uninit {
+            return Ok("<uninit>".to_string());
+        }
+        let (text, numeric) = self.format_value(value, ty, spec)?;
+        Ok(pad(text, spec, numeric))
+    }
+}
+/// a made-up address of `pointer` for `{:?}`
+fn address(pointer: &Pointer) -> usize {
+    0x1000 + pointer.alloc * 0x100 + pointer.path.iter().sum::<usize>() * 8
+}
+/// adds the `+` of `{:+}` to a number
+fn sign(text: String, spec: &FormatSpec) -> String {
+    match spec.sign_plus && !text.starts_with('-') {
+        true => format!("+{text}"),
+        false => text,
+    }
+}
+fn format_float<T>(value: T, spec: &FormatSpec) -> String
+where T: std::fmt::Display
+        + std::fmt::Debug
+        + std::fmt::LowerExp
+        + std::fmt::UpperExp {
+    match (spec.format_trait, spec.precision) {
+        (FormatTrait::LowerExp, Some(precision)) => {
+            format!("{value:.precision$e}")
+        }
+        (FormatTrait::LowerExp, None) => format!("{value:e}"),
+        (FormatTrait::UpperExp, Some(precision)) => {
+            format!("{value:.precision$E}")
+        }
+        (FormatTrait::UpperExp, None) => format!("{value:E}"),
+        (FormatTrait::Debug, None) => format!("{value:?}"),
+        (_, Some(precision)) => format!("{value:.precision$}"),
+        (_, None) => format!("{value}"),
+    }
+}
+/// e.g. `(1, 2)` and `{ a: 1 }`, one field per line for `{:#?}`
+fn debug_list(
+    (open, close): (&str, &str), fields: Vec<(Option<String>, String)>,
+    pretty: bool,
+) -> String {
+    let fields: Vec<String> = fields
+        .into_iter()
+        .map(|(name, value)| match name {
+            Some(name) => format!("{name}: {value}"),
+            None => value,
+        })
+        .collect();
+    if pretty && !fields.is_empty() {
+        let mut text = format!("{open}\n");
+        for field in fields {
+            for line in field.lines() {
+                text += &format!("    {line}\n");
+            }
+            text.insert(text.len() - 1, ',');
+        }
+        return text + close;
+    }
+    match open {
+        "{" => format!("{{ {} }}", fields.join(", ")),
+        _ => format!("{open}{}{close}", fields.join(", ")),
+    }
+}
+/// fills `text` to the width of `spec`, numbers align right by default
+fn pad(text: String, spec: &FormatSpec, numeric: bool) -> String {
+    let len = text.chars().count();
+    let Some(width) = spec.width.filter(|width| *width > len) else {
+        return text;
+    };
+    let fill = width - len;
+    if spec.zero_pad && numeric {
+        // after the sign and the prefix, e.g. `-0x0001`
+        let sign = text.starts_with(['+', '-']) as usize;
+        let digits = &text[sign..];
+        let prefix = match spec.alternate
+            && digits.len() > 2
+            && digits.starts_with('0')
+            && digits[1..].starts_with(['x', 'X', 'b', 'o'])
+        {
+            true => sign + 2,
+            false => sign,
+        };
+        return format!(
+            "{}{}{}",
+            &text[..prefix],
+            "0".repeat(fill),
+            &text[prefix..]
+        );
+    }
+    let fill_char = spec.fill.to_string();
+    let align = spec.align.unwrap_or(match numeric {
+        true => FormatAlign::Right,
+        false => FormatAlign::Left,
+    });
+    let (left, right) = match align {
+        FormatAlign::Left => (0, fill),
+        FormatAlign::Center => (fill / 2, fill - fill / 2),
+        FormatAlign::Right => (fill, 0),
+    };
+    fill_char.repeat(left) + &text + &fill_char.repeat(right)
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::semantic::{
+        mono::Monomorphizer,
+        tests::{Checked, check_clean},
+    };
+    /// the output of `main` of `source`, the message and the text of its
+    /// panic, and the boxes left after it
+    fn run(source: &str) -> (String, Option<String>, usize) {
+        let Checked { source_map, res, mut tcx, bodies, .. } =
+            check_clean(source);
+        let mut monomorphizer = Monomorphizer::new(&res, &mut tcx, &bodies);
+        let items = monomorphizer.collect_crate();
+        assert_eq!(monomorphizer.take_diagnostics(), []);
+        let main = Interpreter::find_main(&res).unwrap();
+        let (mut stdout, mut stderr) = (Vec::new(), Vec::new());
+        let mut interpreter =
+            Interpreter::new(&res, &mut tcx, &items, &mut stdout, &mut stderr);
+        let panic = interpreter.run(main).err().map(|diagnostic| {
+            let span = diagnostic.primary_span().unwrap();
+            format!("{} `{}`", diagnostic.message(), source_map.snippet(span))
+        });
+        let boxes = interpreter.heap_allocations();
+        (String::from_utf8(stdout).unwrap(), panic, boxes)
+    }
+    #[test]
+    fn boxes() {
+        assert_eq!(
+            run("fn main() {
+                let number = box 1;
+                println!(\"{*number}\");
+            }"),
+            ("1\n".to_string(), None, 0)
+        );
+        let (output, panic, boxes) = run("struct Point { x: i32, y: i32 }
+            enum List { Cons(i32, List.Box), Nil }
+            fn sum(list: &List) -> i32 {
+                match list {
+                    &List::Cons(value, ref rest) => value + sum(&**rest),
+                    &List::Nil => 0,
+                }
+            }
+            fn take(b: i32.Box) -> i32 { *b }
+            fn main() {
+                let mut b = box 5;
+                *b += 1;
+                println!(\"{}\", *b);
+                b = box 10;
+                println!(\"{}\", take(b));
+                let p = box Point { x: 1, y: 2 };
+                let q = *p;
+                println!(\"{} {}\", q.x, p.y);
+                let nested = box box 3;
+                let inner = *nested;
+                println!(\"{*inner}\");
+                let list = List::Cons(1, box List::Cons(2, box List::Nil));
+                println!(\"{}\", sum(&list));
+                let text = format!(\"{}-{}\", 1, true);
+                println!(\"{text}\");
+            }");
+        assert_eq!(panic, None);
+        assert_eq!(output, "6\n10\n1 2\n3\n3\n1-true\n");
+        assert_eq!(boxes, 0);
+    }
+    #[test]
+    fn control_flow() {
+        let (output, panic, boxes) = run("enum Option<T> { Some(T), None }
+            use Option::{None, Some};
+            trait Shape { fn area(&self) -> i32; }
+            struct Square { side: i32 }
+            impl Shape for Square { fn area(&self) -> i32 { self.side * \
+                                          self.side } }
+            struct Rect { w: i32, h: i32 }
+            impl Shape for Rect { fn area(&self) -> i32 { self.w * self.h } }
+            fn classify(n: i32) -> i32 {
+                match n {
+                    0 => 0,
+                    1..=9 if n % 2 == 0 => 2,
+                    1..=9 => 1,
+                    _ => 3,
+                }
+            }
+            fn main() {
+                let shapes: [(dyn Shape).Box; 2] =
+                    [box Square { side: 2 }, box Rect { w: 2, h: 3 }];
+                for shape in &shapes {
+                    print!(\"{} \", shape.area());
+                }
+                let mut total = 0;
+                for i in 0..10 {
+                    if i % 3 == 0 { continue; }
+                    total += i;
+                }
+                let mut n = 0;
+                let found = loop { n += 1; if n * n > 50 { break n; } };
+                let mut c = 0;
+                while c < 3 { c += 1; }
+                let [first, .., last] = [1, 2, 3, 4];
+                let Some(x) = Some(5) else { return };
+                println!(
+                    \"{total} {found} {c} {first} {last} {x} {}{}{}{}\",
+                    classify(0), classify(4), classify(5), classify(20)
+                );
+            }");
+        assert_eq!(panic, None);
+        assert_eq!(output, "4 6 27 8 3 1 4 5 0213\n");
+        assert_eq!(boxes, 0);
+    }
+    #[test]
+    fn formatting() {
+        let (output, ..) = run("struct Pair { a: i32, b: (u8, bool) }
+            fn main() {
+                let n = 25;
+                \
+                                println!(\"{n:>4}|{n:<4}|{n:^4}|{n:04}|{n:\
+                                +}|{n:#x}|{n:#06b}\");
+                println!(\"{:.2} {:e} {} {}\", 1.0 / 3.0, 1500.0, 0.1f32, -1i8 \
+                                as u8);
+                println!(\"{:?} {:?} {:*^7}\", 'a', \"a\\n\", \"ab\");
+                let p = Pair { a: -3, b: (7, true) };
+                println!(\"{p:?}\");
+                println!(\"{p:#?}\");
+            }");
+        assert_eq!(
+            output,
+            "  25|25  | 25 |0025|+25|0x19|0b11001\n0.33 1.5e3 0.1 255\n'a' \
+             \"a\\n\" **ab***\nPair { a: -3, b: (7, true) }\nPair {\n    a: \
+             -3,\n    b: (\n        7,\n        true,\n    ),\n}\n"
+        );
+    }
+    #[test]
+    fn panics() {
+        let panic = |source: &str| run(source).1;
+        assert_eq!(
+            panic("fn main() { let x: u8 = 255; let y = x + 1; }"),
+            Some("attempt to add with overflow `x + 1`".to_string())
+        );
+        assert_eq!(
+            panic("fn main() { let a = [1, 2, 3]; let i = 3; let b = a[i]; }"),
+            Some(
+                "index out of bounds: the len is 3 but the index is 3 `a[i]`"
+                    .to_string()
+            )
+        );
+        assert_eq!(
+            panic("fn main() { let a = 0; let b = 1 / a; }"),
+            Some("attempt to divide by zero `1 / a`".to_string())
+        );
+        assert_eq!(
+            panic("fn main() { let a = -128i8; let b = -a; }"),
+            Some("attempt to negate with overflow `-a`".to_string())
+        );
+        // the boxes are freed while unwinding
+        let (output, panic, boxes) = run("fn main() { let b = box 1; \
+                                          println!(\"{*b}\"); let c = 1 << \
+                                          40; }");
+        assert_eq!(output, "1\n");
+        assert_eq!(
+            panic,
+            Some("attempt to shift left with overflow `1 << 40`".to_string())
+        );
+        assert_eq!(boxes, 0);
+    }
+}
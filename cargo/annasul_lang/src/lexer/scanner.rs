@@ -10,20 +10,58 @@
 //
 // You should have received a copy of the GNU Affero General Public License
 // along with this program. If not, see <https://www.gnu.org/licenses/>.
-use std::{io::BufRead, string::IntoChars};
+use std::{collections::VecDeque, io::BufRead, string::IntoChars};
 
 #[cfg(feature = "serde")]
 use serde::{Deserialize, Serialize};
 
-use crate::lexer::token::Token;
+use crate::{
+    lexer::token::{
+        Comment,
+        CommentLineType,
+        CommentType,
+        FloatLit,
+        FloatSuffix,
+        Identifier,
+        IntLit,
+        IntSuffix,
+        Keyword,
+        Literal,
+        Operator,
+        OperatorKind,
+        Token,
+    },
+    utils::{
+        error::{Error, Result},
+        span::{FileId, Span, Spanned},
+    },
+};
 pub trait Scanner<I: BufRead>: IntoIterator {
     fn new(input: I) -> Self;
 }
 #[derive(Debug, Clone)]
 pub struct AnnasulScanner<I: BufRead> {
-    input:      I,
-    into_chars: IntoChars,
-    state:      AnnasulScannerState,
+    input:               I,
+    into_chars:          IntoChars,
+    /// chars already read from `into_chars` but not consumed yet
+    lookahead:           VecDeque<char>,
+    state:               AnnasulScannerState,
+    /// see [AnnasulScanner::with_file]
+    file:                FileId,
+    /// byte offset of the next unconsumed char
+    offset:              usize,
+    /// 1-based line of the next unconsumed char
+    line:                usize,
+    /// 1-based column of the next unconsumed char
+    col:                 usize,
+    /// see [AnnasulScanner::skip_plain_comments]
+    skip_plain_comments: bool,
+    /// the literal of the same kind a literal with an error is replaced by,
+    /// set by the literal being lexed
+    placeholder:         Option<Token>,
+    /// the placeholder of the last error, emitted after it so the parser
+    /// sees a literal
+    recovered:           Option<Spanned<Token>>,
 }
 #[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 #[derive(Default, Debug, Clone, Hash, Ord, PartialOrd, PartialEq, Eq)]
@@ -31,7 +69,25 @@ enum AnnasulScannerState {
     #[default]
     Initial,
     Whitespace,
-    Comment,
+    /// [Token::EOF] has been emitted
+    Finished,
+}
+/// the character type selected by a literal prefix, see `lib.md`
+#[derive(Debug, Clone, Copy, Hash, Ord, PartialOrd, PartialEq, Eq)]
+enum CharType {
+    /// e.g. `'a'`, `"abc"`
+    Char,
+    /// e.g. `b'a'`, `b"abc"`
+    CChar8,
+    /// e.g. `u'a'`, `u"abc"`
+    CChar16,
+}
+/// a position in the input, see [AnnasulScanner::mark]
+#[derive(Debug, Clone, Copy, Hash, Ord, PartialOrd, PartialEq, Eq)]
+struct Mark {
+    offset: usize,
+    line:   usize,
+    col:    usize,
 }
 #[derive(Debug, Clone)]
 pub struct AnnasulScannerIter<I: BufRead> {
@@ -42,58 +98,1102 @@ impl<I: BufRead> Scanner<I> for AnnasulScanner<I> {
         Self {
             input,
             into_chars: String::new().into_chars(),
+            lookahead: VecDeque::new(),
             state: Default::default(),
+            file: Default::default(),
+            offset: 0,
+            line: 1,
+            col: 1,
+            skip_plain_comments: false,
+            placeholder: None,
+            recovered: None,
         }
     }
 }
+impl<I: BufRead> AnnasulScanner<I> {
+    /// the file the [Span]s of the tokens refer to
+    pub fn with_file(mut self, file: FileId) -> Self {
+        self.file = file;
+        self
+    }
+
+    /// skips [`CommentType::Line`] comments, documentation comments are
+    /// still emitted
+    pub fn skip_plain_comments(mut self, skip: bool) -> Self {
+        self.skip_plain_comments = skip;
+        self
+    }
+
+    /// all tokens and all errors of the input
+    pub fn tokenize(self) -> (Vec<Spanned<Token>>, Vec<Error>) {
+        let mut tokens = Vec::new();
+        let mut errors = Vec::new();
+        for token in self {
+            match token {
+                Ok(token) => tokens.push(token),
+                Err(error) => errors.push(error),
+            }
+        }
+        (tokens, errors)
+    }
+
+    /// returns the `n`th unconsumed char, reading more lines if needed
+    fn peek_nth(&mut self, n: usize) -> Result<Option<char>> {
+        while self.lookahead.len() <= n {
+            if let Some(c) = self.into_chars.next() {
+                self.lookahead.push_back(c);
+                continue;
+            }
+            let mut buf = String::new();
+            match self.input.read_line(&mut buf) {
+                Ok(0) => return Ok(None),
+                Ok(_) => self.into_chars = buf.into_chars(),
+                Err(error) => {
+                    // the input is unusable, stop after this error
+                    self.state = AnnasulScannerState::Finished;
+                    let span = self.span_from(self.mark());
+                    return Err(Error::new(
+                        span,
+                        format!("failed to read input: {error}"),
+                    ));
+                }
+            }
+        }
+        Ok(self.lookahead.get(n).copied())
+    }
+
+    fn peek(&mut self) -> Result<Option<char>> { self.peek_nth(0) }
+
+    fn bump(&mut self) -> Result<Option<char>> {
+        self.peek()?;
+        let c = self.lookahead.pop_front();
+        self.offset += c.map_or(0, char::len_utf8);
+        match c {
+            Some('\n') => {
+                self.line += 1;
+                self.col = 1;
+            }
+            Some(_) => self.col += 1,
+            None => {}
+        }
+        Ok(c)
+    }
+
+    /// the position of the next unconsumed char
+    fn mark(&self) -> Mark {
+        Mark { offset: self.offset, line: self.line, col: self.col }
+    }
+
+    /// from `start` to the next unconsumed char
+    fn span_from(&self, start: Mark) -> Span {
+        Span::new(self.file, start.offset, self.offset, start.line, start.col)
+    }
+
+    /// `len` bytes from `start`, e.g. an opening quote
+    fn span_at(&self, start: Mark, len: usize) -> Span {
+        Span::new(
+            self.file,
+            start.offset,
+            start.offset + len,
+            start.line,
+            start.col,
+        )
+    }
+
+    /// e.g. `+`, `foo`, `"abc"`
+    fn lex_token(&mut self, c: char) -> Result<Token> {
+        match c {
+            '"' => self.lex_string(CharType::Char, None),
+            '\'' if self.peek_nth(1)?.is_some_and(Self::is_ident_start) => {
+                self.lex_lifetime()
+            }
+            '\'' => self.lex_char(CharType::Char),
+            c if Self::is_ident_start(c) => {
+                match self.lex_prefixed_literal()? {
+                    Some(token) => Ok(token),
+                    None => self.lex_identifier_or_keyword(),
+                }
+            }
+            c if c.is_ascii_digit() => self.lex_number(),
+            '/' if matches!(self.peek_nth(1)?, Some('/' | '*')) => {
+                self.lex_comment()
+            }
+            c => match self.lex_operator()? {
+                Some(token) => Ok(token),
+                None => {
+                    // skip it, the next char starts a new token
+                    let start = self.mark();
+                    self.bump()?;
+                    Err(Error::new(
+                        self.span_from(start),
+                        format!("unknown character `{}`", c.escape_debug()),
+                    ))
+                }
+            },
+        }
+    }
+
+    /// the longest match of [OperatorKind], e.g. `..=`, `>>=`, `::`
+    fn lex_operator(&mut self) -> Result<Option<Token>> {
+        let mut source = String::new();
+        for n in 0..3 {
+            match self.peek_nth(n)? {
+                Some(c) => source.push(c),
+                None => break,
+            }
+        }
+        let Some(kind) = OperatorKind::longest_match(&source) else {
+            return Ok(None);
+        };
+        for _ in 0..kind.as_str().len() {
+            self.bump()?;
+        }
+        Ok(Some(Token::Operator(Operator::new(kind))))
+    }
+
+    /// see [Comment]
+    fn lex_comment(&mut self) -> Result<Token> {
+        let start = self.mark();
+        self.bump()?;
+        let comment_line_type = match self.bump()? {
+            Some('/') => CommentLineType::SingleLine,
+            _ => CommentLineType::MultiLine,
+        };
+        // `////` and `/***` are plain comments, so are `/**/` and `/***/`
+        let comment_type = match (&comment_line_type, self.peek()?) {
+            (CommentLineType::SingleLine, Some('/'))
+                if self.peek_nth(1)? != Some('/') =>
+            {
+                CommentType::Inner
+            }
+            (CommentLineType::MultiLine, Some('*'))
+                if !matches!(self.peek_nth(1)?, Some('*' | '/')) =>
+            {
+                CommentType::Inner
+            }
+            (_, Some('!')) => CommentType::Outer,
+            _ => CommentType::Line,
+        };
+        if comment_type != CommentType::Line {
+            self.bump()?;
+        }
+        let mut comment = String::new();
+        match comment_line_type {
+            CommentLineType::SingleLine => {
+                while let Some(c) = self.peek()? {
+                    if c == '\n' {
+                        break;
+                    }
+                    comment.push(c);
+                    self.bump()?;
+                }
+                if comment.ends_with('\r') {
+                    comment.pop();
+                }
+            }
+            CommentLineType::MultiLine => {
+                let mut depth = 1;
+                loop {
+                    match (self.bump()?, self.peek()?) {
+                        (None, _) => {
+                            return Err(Error::new(
+                                self.span_at(start, 2),
+                                "unterminated block comment",
+                            ));
+                        }
+                        (Some('/'), Some('*')) => {
+                            self.bump()?;
+                            depth += 1;
+                            comment.push_str("/*");
+                        }
+                        (Some('*'), Some('/')) => {
+                            self.bump()?;
+                            depth -= 1;
+                            if depth == 0 {
+                                break;
+                            }
+                            comment.push_str("*/");
+                        }
+                        (Some(c), _) => comment.push(c),
+                    }
+                }
+            }
+        }
+        Ok(Token::Comment(Comment::new(
+            comment_line_type, comment_type, comment,
+        )))
+    }
+
+    /// e.g. `b'a'`, `u"abc"`, `r#"abc"#`, `br"abc"`
+    ///
+    /// returns [`None`] without consuming anything if there is no prefixed
+    /// literal here
+    fn lex_prefixed_literal(&mut self) -> Result<Option<Token>> {
+        let (char_type, mut len) = match self.peek()? {
+            Some('b') => (CharType::CChar8, 1),
+            Some('u') => (CharType::CChar16, 1),
+            _ => (CharType::Char, 0),
+        };
+        let mut hashes = None;
+        if self.peek_nth(len)? == Some('r') {
+            let mut count = 0;
+            while self.peek_nth(len + 1 + count)? == Some('#') {
+                count += 1;
+            }
+            if self.peek_nth(len + 1 + count)? != Some('"') {
+                return Ok(None);
+            }
+            hashes = Some(count);
+            len += 1 + count;
+        }
+        let quote = self.peek_nth(len)?;
+        let token = match quote {
+            Some('"') if len > 0 => {
+                for _ in 0..len {
+                    self.bump()?;
+                }
+                self.lex_string(char_type, hashes)?
+            }
+            Some('\'') if len > 0 && hashes.is_none() => {
+                for _ in 0..len {
+                    self.bump()?;
+                }
+                self.lex_char(char_type)?
+            }
+            _ => return Ok(None),
+        };
+        Ok(Some(token))
+    }
+
+    /// checks a char written literally (not escaped) in a `char_type` literal
+    fn check_literal_char(
+        &self, char_type: CharType, c: char, error: &mut Option<Error>,
+    ) {
+        if char_type == CharType::CChar8 && !c.is_ascii() && error.is_none() {
+            let span = Span::new(
+                self.file,
+                self.offset - c.len_utf8(),
+                self.offset,
+                self.line,
+                self.col - 1,
+            );
+            *error = Some(Error::new(
+                span,
+                format!("non-ASCII character `{c}` in `c_char8` literal"),
+            ));
+        }
+    }
+
+    /// e.g. `\n`, `\x7F`, `\u{1F600}`, the leading `\` is consumed already
+    ///
+    /// returns [`None`] for a line continuation or an invalid escape, the
+    /// first invalid escape is stored in `error`
+    fn lex_escape(
+        &mut self, char_type: CharType, error: &mut Option<Error>,
+    ) -> Result<Option<char>> {
+        // the `\` is always one byte
+        let start = Mark {
+            offset: self.offset - 1,
+            line:   self.line,
+            col:    self.col - 1,
+        };
+        match self.scan_escape(char_type)? {
+            Ok(c) => Ok(c),
+            Err(msg) => {
+                if error.is_none() {
+                    *error = Some(Error::new(self.span_from(start), msg));
+                }
+                Ok(None)
+            }
+        }
+    }
+
+    /// see [AnnasulScanner::lex_escape], an invalid escape is returned as
+    /// the inner [Err]
+    fn scan_escape(
+        &mut self, char_type: CharType,
+    ) -> Result<std::result::Result<Option<char>, String>> {
+        let fail = |msg: String| Ok(Err(msg));
+        let Some(c) = self.bump()? else {
+            return Ok(Ok(None));
+        };
+        let escaped = match c {
+            'n' => '\n',
+            'r' => '\r',
+            't' => '\t',
+            '\\' => '\\',
+            '0' => '\0',
+            '\'' => '\'',
+            '"' => '"',
+            'x' => {
+                let mut hex = String::new();
+                while hex.len() < 2 {
+                    match self.peek()? {
+                        Some(c) if c.is_ascii_hexdigit() => {
+                            hex.push(c);
+                            self.bump()?;
+                        }
+                        _ => break,
+                    }
+                }
+                let max = match char_type {
+                    CharType::CChar8 => 0xFF,
+                    _ => 0x7F,
+                };
+                match u32::from_str_radix(&hex, 16) {
+                    Ok(value) if hex.len() == 2 && value <= max => {
+                        char::from_u32(value).unwrap()
+                    }
+                    _ => {
+                        return fail(format!(
+                            "invalid escape `\\x{hex}`, expected `\\x00` to \
+                             `\\x{max:X}`"
+                        ));
+                    }
+                }
+            }
+            'u' => {
+                if char_type == CharType::CChar8 {
+                    return fail(
+                        "unicode escape in `c_char8` literal".to_string(),
+                    );
+                }
+                if self.peek()? != Some('{') {
+                    return fail(
+                        "incorrect unicode escape, expected `{`".to_string(),
+                    );
+                }
+                self.bump()?;
+                let mut hex = String::new();
+                while let Some(c) = self.peek()? {
+                    if !c.is_ascii_hexdigit() && c != '_' {
+                        break;
+                    }
+                    hex.push(c);
+                    self.bump()?;
+                }
+                if self.peek()? != Some('}') {
+                    return fail("unterminated unicode escape".to_string());
+                }
+                self.bump()?;
+                let hex = hex.replace('_', "");
+                match u32::from_str_radix(&hex, 16)
+                    .ok()
+                    .and_then(char::from_u32)
+                {
+                    Some(c) if hex.len() <= 6 => c,
+                    _ => {
+                        return fail(format!(
+                            "invalid unicode escape `\\u{{{hex}}}`"
+                        ));
+                    }
+                }
+            }
+            // line continuation, skips the newline and leading whitespace
+            '\n' => {
+                while let Some(c) = self.peek()? {
+                    if !c.is_whitespace() {
+                        break;
+                    }
+                    self.bump()?;
+                }
+                return Ok(Ok(None));
+            }
+            c => return fail(format!("unknown character escape `\\{c}`")),
+        };
+        Ok(Ok(Some(escaped)))
+    }
+
+    /// e.g. `"abc"`, `"a\nb"`, `r#"a"b"#`, the prefix (e.g. `b`, `r#`) is
+    /// consumed already
+    fn lex_string(
+        &mut self, char_type: CharType, hashes: Option<usize>,
+    ) -> Result<Token> {
+        self.placeholder = Some(Token::Literal(match char_type {
+            CharType::Char => Literal::String(String::new()),
+            CharType::CChar8 => Literal::CChar8Str(Vec::new()),
+            CharType::CChar16 => Literal::CChar16Str(Vec::new()),
+        }));
+        let start = self.mark();
+        self.bump()?;
+        let mut value = String::new();
+        let mut error = None;
+        loop {
+            let Some(c) = self.bump()? else {
+                return Err(Error::new(
+                    self.span_at(start, 1),
+                    "unterminated double quote string",
+                ));
+            };
+            match (c, hashes) {
+                ('"', None) => break,
+                ('"', Some(hashes)) => {
+                    let mut closing = 0;
+                    while closing < hashes
+                        && self.peek_nth(closing)? == Some('#')
+                    {
+                        closing += 1;
+                    }
+                    if closing == hashes {
+                        for _ in 0..hashes {
+                            self.bump()?;
+                        }
+                        break;
+                    }
+                    value.push('"');
+                }
+                ('\\', None) => {
+                    if let Some(c) = self.lex_escape(char_type, &mut error)? {
+                        value.push(c);
+                    }
+                }
+                (c, _) => {
+                    self.check_literal_char(char_type, c, &mut error);
+                    value.push(c);
+                }
+            }
+        }
+        if let Some(error) = error {
+            return Err(error);
+        }
+        Ok(Token::Literal(match char_type {
+            CharType::Char => Literal::String(value),
+            // every char is checked to be `<= '\xFF'`
+            CharType::CChar8 => {
+                Literal::CChar8Str(value.chars().map(|c| c as u8).collect())
+            }
+            CharType::CChar16 => {
+                Literal::CChar16Str(value.encode_utf16().collect())
+            }
+        }))
+    }
+
+    /// e.g. `'a'`, `'\n'`, `b'a'`, the prefix (e.g. `b`) is consumed already
+    fn lex_char(&mut self, char_type: CharType) -> Result<Token> {
+        self.placeholder = Some(Token::Literal(match char_type {
+            CharType::Char => Literal::Char('\0'),
+            CharType::CChar8 => Literal::CChar8(0),
+            CharType::CChar16 => Literal::CChar16(0),
+        }));
+        let start = self.mark();
+        self.bump()?;
+        let mut chars = Vec::new();
+        let mut error = None;
+        loop {
+            match self.peek()? {
+                None | Some('\n') => {
+                    return Err(Error::new(
+                        self.span_at(start, 1),
+                        "unterminated character literal",
+                    ));
+                }
+                Some('\'') => {
+                    self.bump()?;
+                    break;
+                }
+                Some('\\') => {
+                    self.bump()?;
+                    if let Some(c) = self.lex_escape(char_type, &mut error)? {
+                        chars.push(c);
+                    }
+                }
+                Some(c) => {
+                    self.bump()?;
+                    self.check_literal_char(char_type, c, &mut error);
+                    chars.push(c);
+                }
+            }
+        }
+        if let Some(error) = error {
+            return Err(error);
+        }
+        let c = match chars.as_slice() {
+            [c] => *c,
+            [] => {
+                return Err(Error::new(
+                    self.span_from(start),
+                    "empty character literal",
+                ));
+            }
+            _ => {
+                return Err(Error::new(
+                    self.span_from(start),
+                    "character literal may only contain one codepoint",
+                ));
+            }
+        };
+        Ok(Token::Literal(match char_type {
+            CharType::Char => Literal::Char(c),
+            // `c` is checked to be `<= '\xFF'`
+            CharType::CChar8 => Literal::CChar8(c as u8),
+            CharType::CChar16 => {
+                let mut unit = [0; 2];
+                match c.encode_utf16(&mut unit) {
+                    [unit] => Literal::CChar16(*unit),
+                    _ => {
+                        return Err(Error::new(
+                            self.span_from(start),
+                            format!(
+                                "character `{c}` does not fit in `c_char16`"
+                            ),
+                        ));
+                    }
+                }
+            }
+        }))
+    }
+
+    fn eat_digits(&mut self, buf: &mut String, radix: u32) -> Result<()> {
+        while let Some(c) = self.peek()? {
+            if c != '_' && !c.is_digit(radix.max(10)) {
+                break;
+            }
+            buf.push(c);
+            self.bump()?;
+        }
+        Ok(())
+    }
+
+    /// e.g. `42`, `0xFF_u8`, `0b1010`, `1.5e-3f32`
+    fn lex_number(&mut self) -> Result<Token> {
+        let start = self.mark();
+        let mut radix = 10;
+        let mut prefix = "";
+        if self.peek()? == Some('0') {
+            (radix, prefix) = match self.peek_nth(1)? {
+                Some('x') => (16, "0x"),
+                Some('o') => (8, "0o"),
+                Some('b') => (2, "0b"),
+                _ => (10, ""),
+            };
+            if radix != 10 {
+                self.bump()?;
+                self.bump()?;
+            }
+        }
+        let mut digits = String::new();
+        self.eat_digits(&mut digits, radix)?;
+        let mut is_float = false;
+        if radix == 10 {
+            // `1.` is a float, but `1..2` and `1.foo()` are not
+            if self.peek()? == Some('.')
+                && self
+                    .peek_nth(1)?
+                    .is_none_or(|c| c != '.' && !Self::is_ident_start(c))
+            {
+                is_float = true;
+                digits.push('.');
+                self.bump()?;
+                self.eat_digits(&mut digits, 10)?;
+            }
+            let exponent = matches!(self.peek()?, Some('e' | 'E'))
+                && match self.peek_nth(1)? {
+                    Some('+' | '-') => {
+                        self.peek_nth(2)?.is_some_and(|c| c.is_ascii_digit())
+                    }
+                    Some(c) => c.is_ascii_digit(),
+                    None => false,
+                };
+            if exponent {
+                is_float = true;
+                digits.push('e');
+                self.bump()?;
+                if let Some(sign @ ('+' | '-')) = self.peek()? {
+                    digits.push(sign);
+                    self.bump()?;
+                }
+                self.eat_digits(&mut digits, 10)?;
+            }
+        }
+        let mut suffix = String::new();
+        while let Some(c) = self.peek()? {
+            if !Self::is_ident_continue(c) {
+                break;
+            }
+            suffix.push(c);
+            self.bump()?;
+        }
+        let written = format!("{prefix}{digits}");
+        let text = format!("{written}{suffix}");
+        let span = self.span_from(start);
+        let invalid = |msg: String| Error::new(span, msg);
+        let digits = digits.replace('_', "");
+        if digits.is_empty() {
+            return Err(invalid(format!("no valid digits found for `{text}`")));
+        }
+        let is_float_suffix =
+            matches!(suffix.as_str(), "f16" | "f32" | "f64" | "f128");
+        self.placeholder =
+            Some(Token::Literal(match is_float || is_float_suffix {
+                true => Literal::Float(FloatLit {
+                    text:   "0.0".to_string(),
+                    suffix: None,
+                }),
+                false => Literal::Int(IntLit {
+                    value:    0,
+                    text:     "0".to_string(),
+                    negative: false,
+                    suffix:   None,
+                }),
+            }));
+        if is_float || is_float_suffix {
+            if radix != 10 {
+                return Err(invalid(format!(
+                    "base {radix} float literal `{text}` is not supported"
+                )));
+            }
+            return Self::float_literal(&digits, &suffix, &text, span)
+                .map(Token::Literal);
+        }
+        if let Some(c) = digits.chars().find(|c| !c.is_digit(radix)) {
+            return Err(invalid(format!(
+                "invalid digit `{c}` for a base {radix} literal `{text}`"
+            )));
+        }
+        let Ok(value) = u128::from_str_radix(&digits, radix) else {
+            return Err(invalid(format!(
+                "integer literal `{text}` is too large"
+            )));
+        };
+        let suffix = match suffix.as_str() {
+            "" => None,
+            _ => Some(IntSuffix::from_suffix(&suffix).ok_or_else(|| {
+                invalid(format!(
+                    "invalid suffix `{suffix}` for number literal `{text}`"
+                ))
+            })?),
+        };
+        Ok(Token::Literal(Literal::Int(IntLit {
+            value,
+            text: written,
+            negative: false,
+            suffix,
+        })))
+    }
+
+    /// `digits` has no `_` separators left, the range is checked with the
+    /// type
+    fn float_literal(
+        digits: &str, suffix: &str, text: &str, span: Span,
+    ) -> Result<Literal> {
+        let invalid = |msg: String| Error::new(span, msg);
+        let suffix = match suffix {
+            "" => None,
+            _ => Some(FloatSuffix::from_suffix(suffix).ok_or_else(|| {
+                invalid(format!(
+                    "invalid suffix `{suffix}` for float literal `{text}`"
+                ))
+            })?),
+        };
+        Ok(Literal::Float(FloatLit { text: digits.to_string(), suffix }))
+    }
+
+    fn is_ident_start(c: char) -> bool { c == '_' || c.is_alphabetic() }
+
+    fn is_ident_continue(c: char) -> bool { c == '_' || c.is_alphanumeric() }
+
+    /// e.g. `'outer`, or a character literal like `'a'` and `'ab'`
+    fn lex_lifetime(&mut self) -> Result<Token> {
+        let mut n = 1;
+        while self.peek_nth(n)?.is_some_and(Self::is_ident_continue) {
+            n += 1;
+        }
+        if self.peek_nth(n)? == Some('\'') {
+            return self.lex_char(CharType::Char);
+        }
+        self.bump()?;
+        let mut ident = String::new();
+        while let Some(c) = self.peek()? {
+            if !Self::is_ident_continue(c) {
+                break;
+            }
+            ident.push(c);
+            self.bump()?;
+        }
+        Ok(Token::Lifetime(Identifier::new(ident)))
+    }
+
+    /// e.g. `foo`, `r#match`, `crate`
+    fn lex_identifier_or_keyword(&mut self) -> Result<Token> {
+        let raw = self.peek()? == Some('r')
+            && self.peek_nth(1)? == Some('#')
+            && self.peek_nth(2)?.is_some_and(Self::is_ident_start);
+        if raw {
+            self.bump()?;
+            self.bump()?;
+        }
+        let mut ident = String::new();
+        while let Some(c) = self.peek()? {
+            if !Self::is_ident_continue(c) {
+                break;
+            }
+            ident.push(c);
+            self.bump()?;
+        }
+        if raw {
+            return Ok(Token::Identifier(Identifier::new_raw(ident)));
+        }
+        Ok(match Keyword::from_ident(&ident) {
+            Some(keyword) => Token::Keyword(keyword),
+            None => Token::Identifier(Identifier::new(ident)),
+        })
+    }
+}
 impl<I: BufRead> IntoIterator for AnnasulScanner<I> {
     type IntoIter = AnnasulScannerIter<I>;
-    type Item = std::io::Result<Token>;
+    type Item = Result<Spanned<Token>>;
 
     fn into_iter(self) -> Self::IntoIter {
         AnnasulScannerIter { annasul_scanner: self }
     }
 }
 impl<I: BufRead> Iterator for AnnasulScannerIter<I> {
-    type Item = std::io::Result<Token>;
+    type Item = Result<Spanned<Token>>;
 
     fn next(&mut self) -> Option<Self::Item> {
         let scanner = &mut self.annasul_scanner;
         loop {
-            let c = 'c: {
-                if let Some(c) = scanner.into_chars.next() {
-                    break 'c c;
-                }
-                let mut buf = String::new();
-                if match scanner.input.read_line(&mut buf) {
-                    Ok(num) => num,
-                    Err(e) => return Some(Err(e)),
-                } == 0
-                {
-                    return None;
-                }
-                scanner.into_chars = buf.into_chars();
-                if let Some(c) = scanner.into_chars.next() {
-                    break 'c c;
-                } else {
-                    return None;
+            if let Some(token) = scanner.recovered.take() {
+                return Some(Ok(token));
+            }
+            if scanner.state == AnnasulScannerState::Finished {
+                return None;
+            }
+            let c = match scanner.peek() {
+                Ok(Some(c)) => c,
+                Ok(None) => {
+                    scanner.state = AnnasulScannerState::Finished;
+                    let span = scanner.span_from(scanner.mark());
+                    return Some(Ok(Spanned::new(Token::EOF, span)));
                 }
+                Err(e) => return Some(Err(e)),
             };
             match (&scanner.state, c) {
-                (AnnasulScannerState::Initial, c) if c.is_whitespace() => {
+                (
+                    AnnasulScannerState::Initial
+                    | AnnasulScannerState::Whitespace,
+                    c,
+                ) if c.is_whitespace() => {
                     scanner.state = AnnasulScannerState::Whitespace;
+                    if let Err(e) = scanner.bump() {
+                        return Some(Err(e));
+                    }
                 }
-                (AnnasulScannerState::Initial, c) => {
-                    panic!("unknown char '{c}'")
-                }
-                (AnnasulScannerState::Whitespace, c) if c.is_whitespace() => {}
-                (AnnasulScannerState::Whitespace, c) => {
-                    panic!("unknown char '{c}'")
-                }
-                (AnnasulScannerState::Comment, c) => {
-                    panic!("unknown char '{c}'")
+                (
+                    AnnasulScannerState::Initial
+                    | AnnasulScannerState::Whitespace,
+                    c,
+                ) => {
+                    scanner.state = AnnasulScannerState::Initial;
+                    let start = scanner.mark();
+                    let token = scanner.lex_token(c);
+                    let placeholder = scanner.placeholder.take();
+                    match token {
+                        Ok(Token::Comment(comment))
+                            if scanner.skip_plain_comments
+                                && !comment.is_doc() => {}
+                        Ok(token) => {
+                            let span = scanner.span_from(start);
+                            return Some(Ok(Spanned::new(token, span)));
+                        }
+                        Err(error) => {
+                            let span = scanner.span_from(start);
+                            scanner.recovered = placeholder
+                                .map(|token| Spanned::new(token, span));
+                            return Some(Err(error));
+                        }
+                    }
                 }
+                (AnnasulScannerState::Finished, _) => unreachable!(),
             }
         }
     }
 }
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::span::SourceMap;
+    fn scan(source: &str) -> Vec<Token> {
+        AnnasulScanner::new(source.as_bytes())
+            .into_iter()
+            .map(|token| token.unwrap().node)
+            .collect()
+    }
+    #[test]
+    fn identifiers_and_keywords() {
+        assert_eq!(
+            scan("fn main crate Self self super marco u8 str r#match _x"),
+            [
+                Token::Keyword(Keyword::r#fn),
+                Token::Identifier(Identifier::new("main")),
+                Token::Keyword(Keyword::crate_),
+                Token::Keyword(Keyword::Self_),
+                Token::Keyword(Keyword::self_),
+                Token::Keyword(Keyword::super_),
+                Token::Keyword(Keyword::r#marco),
+                Token::Keyword(Keyword::r#u8),
+                Token::Keyword(Keyword::r#str),
+                Token::Identifier(Identifier::new_raw("match")),
+                Token::Identifier(Identifier::new("_x")),
+                Token::EOF,
+            ]
+        );
+    }
+    #[test]
+    fn keyword_round_trip() {
+        for keyword in ["crate", "Self", "self", "super", "marco", "i128"] {
+            assert_eq!(Keyword::from_ident(keyword).unwrap().as_str(), keyword);
+        }
+        assert_eq!(scan(""), [Token::EOF]);
+    }
+    #[test]
+    fn numbers() {
+        let int = |value, text: &str, suffix| {
+            Token::Literal(Literal::Int(IntLit {
+                value,
+                text: text.to_string(),
+                negative: false,
+                suffix,
+            }))
+        };
+        assert_eq!(scan("42 0xFF_u8 0o17 0b1010_1010u8 1_000i64 7u128"), [
+            int(42, "42", None),
+            int(255, "0xFF_", Some(IntSuffix::U8)),
+            int(15, "0o17", None),
+            int(0b1010_1010, "0b1010_1010", Some(IntSuffix::U8)),
+            int(1000, "1_000", Some(IntSuffix::I64)),
+            int(7, "7", Some(IntSuffix::U128)),
+            Token::EOF,
+        ]);
+        // the range is checked with the type, e.g. `300u8`
+        assert_eq!(scan("300u8 5_000_000_000 3usize"), [
+            int(300, "300", Some(IntSuffix::U8)),
+            int(5_000_000_000, "5_000_000_000", None),
+            int(3, "3", Some(IntSuffix::Usize)),
+            Token::EOF,
+        ]);
+        let float = |text: &str, suffix| {
+            Token::Literal(Literal::Float(FloatLit {
+                text: text.to_string(),
+                suffix,
+            }))
+        };
+        assert_eq!(scan("1.5 1.5f32 2e3 1_0.2_5E-1 3f32 1."), [
+            float("1.5", None),
+            float("1.5", Some(FloatSuffix::F32)),
+            float("2e3", None),
+            float("10.25e-1", None),
+            float("3", Some(FloatSuffix::F32)),
+            float("1.", None),
+            Token::EOF,
+        ]);
+        // `..` and method calls are not part of the literal
+        assert_eq!(scan("1..2")[..2], [
+            int(1, "1", None),
+            Token::Operator(Operator::new(OperatorKind::DotDot)),
+        ]);
+        assert_eq!(scan("1.max")[..2], [
+            int(1, "1", None),
+            Token::Operator(Operator::new(OperatorKind::Dot)),
+        ]);
+    }
+    #[test]
+    fn number_errors_do_not_stop_scanning() {
+        let tokens: Vec<_> = AnnasulScanner::new(
+            "0x 0b102 1q 340282366920938463463374607431768211456 x".as_bytes(),
+        )
+        .into_iter()
+        .collect();
+        let errors: Vec<_> = tokens
+            .iter()
+            .filter_map(|token| token.as_ref().err())
+            .map(|error| error.to_string())
+            .collect();
+        assert_eq!(errors, [
+            "no valid digits found for `0x` at 1:1",
+            "invalid digit `2` for a base 2 literal `0b102` at 1:4",
+            "invalid suffix `q` for number literal `1q` at 1:10",
+            "integer literal `340282366920938463463374607431768211456` is too \
+             large at 1:13",
+        ]);
+        assert_eq!(
+            &tokens[tokens.len() - 2].as_ref().unwrap().node,
+            &Token::Identifier(Identifier::new("x"))
+        );
+    }
+    #[test]
+    fn strings_and_chars() {
+        let source = r####"'a' '\n' '\u{1F600}' "a\tb\x41\"" r#"a"b"# b'\xFF'
+            u'\u{4E2D}' b"ab\0" br"\n" u"中" "line
+            two\
+            three""####;
+        assert_eq!(scan(source), [
+            Token::Literal(Literal::Char('a')),
+            Token::Literal(Literal::Char('\n')),
+            Token::Literal(Literal::Char('\u{1F600}')),
+            Token::Literal(Literal::String("a\tbA\"".to_string())),
+            Token::Literal(Literal::String("a\"b".to_string())),
+            Token::Literal(Literal::CChar8(0xFF)),
+            Token::Literal(Literal::CChar16(0x4E2D)),
+            Token::Literal(Literal::CChar8Str(b"ab\0".to_vec())),
+            Token::Literal(Literal::CChar8Str(b"\\n".to_vec())),
+            Token::Literal(Literal::CChar16Str(vec![0x4E2D])),
+            Token::Literal(Literal::String(
+                "line\n            twothree".into()
+            )),
+            Token::EOF,
+        ]);
+        // `r#ident` and `b`/`u` identifiers are not literals
+        assert_eq!(scan("r#b u b")[..3], [
+            Token::Identifier(Identifier::new_raw("b")),
+            Token::Identifier(Identifier::new("u")),
+            Token::Identifier(Identifier::new("b")),
+        ]);
+        // a quote followed by an identifier is a label
+        assert_eq!(scan("'outer: 'a'")[..3], [
+            Token::Lifetime(Identifier::new("outer")),
+            Token::Operator(Operator::new(OperatorKind::Colon)),
+            Token::Literal(Literal::Char('a')),
+        ]);
+    }
+    #[test]
+    fn string_errors_point_at_the_opening_quote() {
+        let source = "'ab' ''\n  \"\\q\" b'é' '\\x80' u'\\u{1F600}' x \"open";
+        let errors: Vec<_> = AnnasulScanner::new(source.as_bytes())
+            .into_iter()
+            .filter_map(|token| token.err())
+            .map(|error| error.to_string())
+            .collect();
+        assert_eq!(errors, [
+            "character literal may only contain one codepoint at 1:1",
+            "empty character literal at 1:6",
+            "unknown character escape `\\q` at 2:4",
+            "non-ASCII character `é` in `c_char8` literal at 2:10",
+            "invalid escape `\\x80`, expected `\\x00` to `\\x7F` at 2:14",
+            "character `😀` does not fit in `c_char16` at 2:21",
+            "unterminated double quote string at 2:35",
+        ]);
+    }
+    #[test]
+    fn comments() {
+        let comment = |line_type, comment_type, text: &str| {
+            Token::Comment(Comment::new(line_type, comment_type, text))
+        };
+        let source = "// plain\r\n/// inner\n//! outer\n//// plain\n/* a /* \
+                      nested */ b */ /** inner */ /*! outer */ /**/ /***/";
+        assert_eq!(scan(source), [
+            comment(CommentLineType::SingleLine, CommentType::Line, " plain"),
+            comment(CommentLineType::SingleLine, CommentType::Inner, " inner"),
+            comment(CommentLineType::SingleLine, CommentType::Outer, " outer"),
+            comment(CommentLineType::SingleLine, CommentType::Line, "// plain"),
+            comment(
+                CommentLineType::MultiLine,
+                CommentType::Line,
+                " a /* nested */ b ",
+            ),
+            comment(CommentLineType::MultiLine, CommentType::Inner, " inner "),
+            comment(CommentLineType::MultiLine, CommentType::Outer, " outer "),
+            comment(CommentLineType::MultiLine, CommentType::Line, ""),
+            comment(CommentLineType::MultiLine, CommentType::Line, "*"),
+            Token::EOF,
+        ]);
+        let tokens: Vec<_> = AnnasulScanner::new(source.as_bytes())
+            .skip_plain_comments(true)
+            .into_iter()
+            .map(|token| token.unwrap().node.to_string())
+            .collect();
+        assert_eq!(tokens, [
+            "/// inner\n", "//! outer\n", "/** inner */", "/*! outer */",
+            "<EOF>",
+        ]);
+        let error = AnnasulScanner::new("x /* /* */".as_bytes())
+            .into_iter()
+            .find_map(|token| token.err())
+            .unwrap();
+        assert_eq!(error.to_string(), "unterminated block comment at 1:3");
+    }
+    #[test]
+    fn operators_use_longest_match() {
+        let operators: Vec<_> = scan("a>>=b..=c::d->e=>f<<g&&!h!=i ...;#[x]$?")
+            .into_iter()
+            .filter_map(|token| match token {
+                Token::Operator(operator) => Some(operator.kind()),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(operators, [
+            OperatorKind::ShrEq,
+            OperatorKind::DotDotEq,
+            OperatorKind::PathSep,
+            OperatorKind::RArrow,
+            OperatorKind::FatArrow,
+            OperatorKind::Shl,
+            OperatorKind::AndAnd,
+            OperatorKind::Not,
+            OperatorKind::Ne,
+            OperatorKind::DotDot,
+            OperatorKind::Dot,
+            OperatorKind::Semi,
+            OperatorKind::Pound,
+            OperatorKind::OpenBracket,
+            OperatorKind::CloseBracket,
+            OperatorKind::Dollar,
+            OperatorKind::Question,
+        ]);
+        for kind in OperatorKind::ALL {
+            assert_eq!(OperatorKind::longest_match(kind.as_str()), Some(kind));
+        }
+    }
+    #[test]
+    fn spans() {
+        let mut source_map = SourceMap::new();
+        source_map.add_file("a.aau", "");
+        let source = "fn main() {\n    \"中文\" // x\n}";
+        let file = source_map.add_file("b.aau", source);
+        let tokens: Vec<_> = AnnasulScanner::new(source.as_bytes())
+            .with_file(file)
+            .into_iter()
+            .map(|token| token.unwrap())
+            .collect();
+        let spans: Vec<_> = tokens
+            .iter()
+            .map(|token| {
+                let span = token.span;
+                (source_map.snippet(span), span.line(), span.col())
+            })
+            .collect();
+        assert_eq!(spans, [
+            ("fn", 1, 1),
+            ("main", 1, 4),
+            ("(", 1, 8),
+            (")", 1, 9),
+            ("{", 1, 11),
+            ("\"中文\"", 2, 5),
+            ("// x", 2, 10),
+            ("}", 3, 1),
+            ("", 3, 2),
+        ]);
+        let string = tokens[5].span;
+        assert_eq!(string.file(), file);
+        assert_eq!(source_map.file(file).line_col(string.hi()), (2, 9));
+        assert_eq!(source_map.file(file).line(2), "    \"中文\" // x");
+    }
+    #[test]
+    fn unknown_characters_are_skipped() {
+        let source = "let a = 1 ¤ 2;\nlet b = \\ `c`;";
+        let (tokens, errors) =
+            AnnasulScanner::new(source.as_bytes()).tokenize();
+        let errors: Vec<_> = errors
+            .iter()
+            .map(|error| (error.to_string(), error.span().len()))
+            .collect();
+        assert_eq!(errors, [
+            ("unknown character `¤` at 1:11".to_string(), 2),
+            ("unknown character `\\\\` at 2:9".to_string(), 1),
+            ("unknown character ``` at 2:11".to_string(), 1),
+            ("unknown character ``` at 2:13".to_string(), 1),
+        ]);
+        assert_eq!(tokens.len(), 12);
+        assert_eq!(tokens.last().unwrap().node, Token::EOF);
+    }
+}
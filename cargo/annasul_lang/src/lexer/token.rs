@@ -23,6 +23,8 @@ pub enum Token {
     Identifier(Identifier),
     /// see [Keyword]
     Keyword(Keyword),
+    /// e.g. `'outer`, used as a loop label
+    Lifetime(Identifier),
     /// see [Literal]
     Literal(Literal),
     /// see [Operator]
@@ -58,46 +60,192 @@ pub enum CommentLineType {
     /// e.g. `/* comment */`
     MultiLine,
 }
+/// operators and punctuation, including delimiters
 #[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 #[derive(Debug, Clone, Hash, Ord, PartialOrd, PartialEq, Eq)]
 pub struct Operator {
-    inner: String,
+    kind: OperatorKind,
+}
+/// see [Operator]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Debug, Clone, Copy, Hash, Ord, PartialOrd, PartialEq, Eq)]
+pub enum OperatorKind {
+    /// `+`
+    Plus,
+    /// `-`
+    Minus,
+    /// `*`
+    Star,
+    /// `/`
+    Slash,
+    /// `%`
+    Percent,
+    /// `^`
+    Caret,
+    /// `!`
+    Not,
+    /// `&`
+    And,
+    /// `|`
+    Or,
+    /// `&&`
+    AndAnd,
+    /// `||`
+    OrOr,
+    /// `<<`
+    Shl,
+    /// `>>`
+    Shr,
+    /// `+=`
+    PlusEq,
+    /// `-=`
+    MinusEq,
+    /// `*=`
+    StarEq,
+    /// `/=`
+    SlashEq,
+    /// `%=`
+    PercentEq,
+    /// `^=`
+    CaretEq,
+    /// `&=`
+    AndEq,
+    /// `|=`
+    OrEq,
+    /// `<<=`
+    ShlEq,
+    /// `>>=`
+    ShrEq,
+    /// `=`
+    Eq,
+    /// `==`
+    EqEq,
+    /// `!=`
+    Ne,
+    /// `>`
+    Gt,
+    /// `<`
+    Lt,
+    /// `>=`
+    Ge,
+    /// `<=`
+    Le,
+    /// `@`
+    At,
+    /// `.`
+    Dot,
+    /// `..`
+    DotDot,
+    /// `..=`
+    DotDotEq,
+    /// `,`
+    Comma,
+    /// `;`
+    Semi,
+    /// `:`
+    Colon,
+    /// `::`
+    PathSep,
+    /// `->`
+    RArrow,
+    /// `=>`
+    FatArrow,
+    /// `#`
+    Pound,
+    /// `$`
+    Dollar,
+    /// `?`
+    Question,
+    /// `(`
+    OpenParen,
+    /// `)`
+    CloseParen,
+    /// `[`
+    OpenBracket,
+    /// `]`
+    CloseBracket,
+    /// `{`
+    OpenBrace,
+    /// `}`
+    CloseBrace,
 }
 #[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 #[derive(Debug, Clone, Hash, Ord, PartialOrd, PartialEq, Eq)]
 pub struct Identifier {
     inner: String,
+    /// e.g. `r#match`
+    raw:   bool,
+    /// the macro expansion that introduced a local binding, `0` otherwise,
+    /// so equal names from different expansions are different identifiers
+    mark:  u32,
 }
-
-///
-/// serde is not support f16&f128 now
-/// 
-#[cfg_attr(all(feature = "serde", not(any(feature = "unstable-f16", feature = "unstable-f128"))), derive(Serialize, Deserialize))]
-#[derive(Debug, Clone, PartialOrd, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Debug, Clone, Hash, PartialOrd, PartialEq, Eq)]
 pub enum Literal {
-    U8(u8),
-    U16(u16),
-    U32(u32),
-    U64(u64),
-    U128(u128),
-    I8(i8),
-    I16(i16),
-    I32(i32),
-    I64(i64),
-    I128(i128),
-    #[cfg(feature = "unstable-f16")]
-    F16(f16),
-    F32(f32),
-    F64(f64),
-    #[cfg(feature = "unstable-f128")]
-    F128(f128),
+    /// see [IntLit]
+    Int(IntLit),
+    /// see [FloatLit]
+    Float(FloatLit),
     Bool(bool),
     Char(char),
     String(String),
+    /// e.g. `b'a'`
+    CChar8(u8),
+    /// e.g. `u'a'`
+    CChar16(u16),
+    /// e.g. `b"abc"`
+    CChar8Str(Vec<u8>),
+    /// e.g. `u"abc"`
+    CChar16Str(Vec<u16>),
 }
+/// e.g. `0xFF_u8`, the type is inferred without a suffix and the range is
+/// checked against it, see [crate::semantic::typeck]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Debug, Clone, Hash, Ord, PartialOrd, PartialEq, Eq)]
+pub struct IntLit {
+    /// without the sign
+    pub value:    u128,
+    /// as written, without the sign and the suffix, e.g. `0xFF_FF` or `42`
+    pub text:     String,
+    /// e.g. `-1` in a pattern
+    pub negative: bool,
+    pub suffix:   Option<IntSuffix>,
+}
+/// e.g. `1.5f32`, the text is parsed once the type is known
 #[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
-#[allow(non_camel_case_types)]
 #[derive(Debug, Clone, Hash, Ord, PartialOrd, PartialEq, Eq)]
+pub struct FloatLit {
+    /// without `_` and the suffix, e.g. `1.025` or `-2e3`
+    pub text:   String,
+    pub suffix: Option<FloatSuffix>,
+}
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Debug, Clone, Copy, Hash, Ord, PartialOrd, PartialEq, Eq)]
+pub enum IntSuffix {
+    U8,
+    U16,
+    U32,
+    U64,
+    U128,
+    Usize,
+    I8,
+    I16,
+    I32,
+    I64,
+    I128,
+    Isize,
+}
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Debug, Clone, Copy, Hash, Ord, PartialOrd, PartialEq, Eq)]
+pub enum FloatSuffix {
+    F16,
+    F32,
+    F64,
+    F128,
+}
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[allow(non_camel_case_types)]
+#[derive(Debug, Clone, Copy, Hash, Ord, PartialOrd, PartialEq, Eq)]
 pub enum Keyword {
     r#abstract,
     r#as,
@@ -167,22 +315,400 @@ pub enum Keyword {
     r#i32,
     r#i64,
     r#i128,
-    #[cfg(feature = "unstable-f16")]
     r#f16,
     r#f32,
     r#f64,
-    #[cfg(feature = "unstable-f128")]
     r#f128,
     r#bool,
     r#char,
     r#str,
 }
+impl Comment {
+    pub fn new(
+        comment_line_type: CommentLineType, comment_type: CommentType,
+        comment: impl Into<String>,
+    ) -> Self {
+        Self { comment_line_type, comment_type, comment: comment.into() }
+    }
+
+    pub fn comment_line_type(&self) -> &CommentLineType {
+        &self.comment_line_type
+    }
+
+    pub fn comment_type(&self) -> &CommentType { &self.comment_type }
+
+    /// the text between the comment markers, e.g. ` comment` for
+    /// `/// comment`
+    pub fn comment(&self) -> &str { &self.comment }
+
+    /// [`CommentType::Inner`] or [`CommentType::Outer`]
+    pub fn is_doc(&self) -> bool { self.comment_type != CommentType::Line }
+}
+impl Identifier {
+    pub fn new(inner: impl Into<String>) -> Self {
+        Self { inner: inner.into(), raw: false, mark: 0 }
+    }
+
+    /// e.g. `r#match`
+    pub fn new_raw(inner: impl Into<String>) -> Self {
+        Self { inner: inner.into(), raw: true, mark: 0 }
+    }
+
+    pub fn as_str(&self) -> &str { &self.inner }
+
+    pub fn is_raw(&self) -> bool { self.raw }
+
+    /// see [Identifier::with_mark]
+    pub fn mark(&self) -> u32 { self.mark }
+
+    /// the same name as seen by the macro expansion `mark`, see
+    /// [expand](crate::expand)
+    pub fn with_mark(mut self, mark: u32) -> Self {
+        self.mark = mark;
+        self
+    }
+}
+impl Operator {
+    pub fn new(kind: OperatorKind) -> Self { Self { kind } }
+
+    pub fn kind(&self) -> OperatorKind { self.kind }
+}
+impl OperatorKind {
+    /// every operator, see [OperatorKind::longest_match]
+    pub const ALL: [OperatorKind; 49] = [
+        OperatorKind::Plus,
+        OperatorKind::Minus,
+        OperatorKind::Star,
+        OperatorKind::Slash,
+        OperatorKind::Percent,
+        OperatorKind::Caret,
+        OperatorKind::Not,
+        OperatorKind::And,
+        OperatorKind::Or,
+        OperatorKind::AndAnd,
+        OperatorKind::OrOr,
+        OperatorKind::Shl,
+        OperatorKind::Shr,
+        OperatorKind::PlusEq,
+        OperatorKind::MinusEq,
+        OperatorKind::StarEq,
+        OperatorKind::SlashEq,
+        OperatorKind::PercentEq,
+        OperatorKind::CaretEq,
+        OperatorKind::AndEq,
+        OperatorKind::OrEq,
+        OperatorKind::ShlEq,
+        OperatorKind::ShrEq,
+        OperatorKind::Eq,
+        OperatorKind::EqEq,
+        OperatorKind::Ne,
+        OperatorKind::Gt,
+        OperatorKind::Lt,
+        OperatorKind::Ge,
+        OperatorKind::Le,
+        OperatorKind::At,
+        OperatorKind::Dot,
+        OperatorKind::DotDot,
+        OperatorKind::DotDotEq,
+        OperatorKind::Comma,
+        OperatorKind::Semi,
+        OperatorKind::Colon,
+        OperatorKind::PathSep,
+        OperatorKind::RArrow,
+        OperatorKind::FatArrow,
+        OperatorKind::Pound,
+        OperatorKind::Dollar,
+        OperatorKind::Question,
+        OperatorKind::OpenParen,
+        OperatorKind::CloseParen,
+        OperatorKind::OpenBracket,
+        OperatorKind::CloseBracket,
+        OperatorKind::OpenBrace,
+        OperatorKind::CloseBrace,
+    ];
+
+    /// the longest operator `source` starts with, e.g. `">>=x"` ->
+    /// [`OperatorKind::ShrEq`]
+    pub fn longest_match(source: &str) -> Option<Self> {
+        Self::ALL
+            .into_iter()
+            .filter(|kind| source.starts_with(kind.as_str()))
+            .max_by_key(|kind| kind.as_str().len())
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            OperatorKind::Plus => "+",
+            OperatorKind::Minus => "-",
+            OperatorKind::Star => "*",
+            OperatorKind::Slash => "/",
+            OperatorKind::Percent => "%",
+            OperatorKind::Caret => "^",
+            OperatorKind::Not => "!",
+            OperatorKind::And => "&",
+            OperatorKind::Or => "|",
+            OperatorKind::AndAnd => "&&",
+            OperatorKind::OrOr => "||",
+            OperatorKind::Shl => "<<",
+            OperatorKind::Shr => ">>",
+            OperatorKind::PlusEq => "+=",
+            OperatorKind::MinusEq => "-=",
+            OperatorKind::StarEq => "*=",
+            OperatorKind::SlashEq => "/=",
+            OperatorKind::PercentEq => "%=",
+            OperatorKind::CaretEq => "^=",
+            OperatorKind::AndEq => "&=",
+            OperatorKind::OrEq => "|=",
+            OperatorKind::ShlEq => "<<=",
+            OperatorKind::ShrEq => ">>=",
+            OperatorKind::Eq => "=",
+            OperatorKind::EqEq => "==",
+            OperatorKind::Ne => "!=",
+            OperatorKind::Gt => ">",
+            OperatorKind::Lt => "<",
+            OperatorKind::Ge => ">=",
+            OperatorKind::Le => "<=",
+            OperatorKind::At => "@",
+            OperatorKind::Dot => ".",
+            OperatorKind::DotDot => "..",
+            OperatorKind::DotDotEq => "..=",
+            OperatorKind::Comma => ",",
+            OperatorKind::Semi => ";",
+            OperatorKind::Colon => ":",
+            OperatorKind::PathSep => "::",
+            OperatorKind::RArrow => "->",
+            OperatorKind::FatArrow => "=>",
+            OperatorKind::Pound => "#",
+            OperatorKind::Dollar => "$",
+            OperatorKind::Question => "?",
+            OperatorKind::OpenParen => "(",
+            OperatorKind::CloseParen => ")",
+            OperatorKind::OpenBracket => "[",
+            OperatorKind::CloseBracket => "]",
+            OperatorKind::OpenBrace => "{",
+            OperatorKind::CloseBrace => "}",
+        }
+    }
+}
+impl IntSuffix {
+    /// e.g. `u8` of `0xFF_u8`
+    pub fn from_suffix(suffix: &str) -> Option<Self> {
+        Some(match suffix {
+            "u8" => IntSuffix::U8,
+            "u16" => IntSuffix::U16,
+            "u32" => IntSuffix::U32,
+            "u64" => IntSuffix::U64,
+            "u128" => IntSuffix::U128,
+            "usize" => IntSuffix::Usize,
+            "i8" => IntSuffix::I8,
+            "i16" => IntSuffix::I16,
+            "i32" => IntSuffix::I32,
+            "i64" => IntSuffix::I64,
+            "i128" => IntSuffix::I128,
+            "isize" => IntSuffix::Isize,
+            _ => return None,
+        })
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            IntSuffix::U8 => "u8",
+            IntSuffix::U16 => "u16",
+            IntSuffix::U32 => "u32",
+            IntSuffix::U64 => "u64",
+            IntSuffix::U128 => "u128",
+            IntSuffix::Usize => "usize",
+            IntSuffix::I8 => "i8",
+            IntSuffix::I16 => "i16",
+            IntSuffix::I32 => "i32",
+            IntSuffix::I64 => "i64",
+            IntSuffix::I128 => "i128",
+            IntSuffix::Isize => "isize",
+        }
+    }
+}
+impl FloatSuffix {
+    /// e.g. `f32` of `1.5f32`
+    pub fn from_suffix(suffix: &str) -> Option<Self> {
+        Some(match suffix {
+            "f16" => FloatSuffix::F16,
+            "f32" => FloatSuffix::F32,
+            "f64" => FloatSuffix::F64,
+            "f128" => FloatSuffix::F128,
+            _ => return None,
+        })
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            FloatSuffix::F16 => "f16",
+            FloatSuffix::F32 => "f32",
+            FloatSuffix::F64 => "f64",
+            FloatSuffix::F128 => "f128",
+        }
+    }
+}
+impl Keyword {
+    /// e.g. `"crate"` -> [`Keyword::crate_`], `"foo"` -> [`None`]
+    pub fn from_ident(ident: &str) -> Option<Self> {
+        Some(match ident {
+            "abstract" => Keyword::r#abstract,
+            "as" => Keyword::r#as,
+            "async" => Keyword::r#async,
+            "await" => Keyword::r#await,
+            "become" => Keyword::r#become,
+            "box" => Keyword::r#box,
+            "break" => Keyword::r#break,
+            "const" => Keyword::r#const,
+            "continue" => Keyword::r#continue,
+            "crate" => Keyword::crate_,
+            "do" => Keyword::r#do,
+            "dyn" => Keyword::r#dyn,
+            "else" => Keyword::r#else,
+            "enum" => Keyword::r#enum,
+            "extern" => Keyword::r#extern,
+            "false" => Keyword::r#false,
+            "final" => Keyword::r#final,
+            "fn" => Keyword::r#fn,
+            "for" => Keyword::r#for,
+            "gen" => Keyword::r#gen,
+            "if" => Keyword::r#if,
+            "impl" => Keyword::r#impl,
+            "in" => Keyword::r#in,
+            "let" => Keyword::r#let,
+            "loop" => Keyword::r#loop,
+            "marco" => Keyword::r#marco,
+            "match" => Keyword::r#match,
+            "mod" => Keyword::r#mod,
+            "move" => Keyword::r#move,
+            "mut" => Keyword::r#mut,
+            "override" => Keyword::r#override,
+            "priv" => Keyword::r#priv,
+            "pub" => Keyword::r#pub,
+            "ref" => Keyword::r#ref,
+            "return" => Keyword::r#return,
+            "Self" => Keyword::Self_,
+            "self" => Keyword::self_,
+            "static" => Keyword::r#static,
+            "struct" => Keyword::r#struct,
+            "super" => Keyword::super_,
+            "trait" => Keyword::r#trait,
+            "true" => Keyword::r#true,
+            "try" => Keyword::r#try,
+            "typeof" => Keyword::r#typeof,
+            "type" => Keyword::r#type,
+            "union" => Keyword::r#union,
+            "unsafe" => Keyword::r#unsafe,
+            "unsized" => Keyword::r#unsized,
+            "use" => Keyword::r#use,
+            "virtual" => Keyword::r#virtual,
+            "where" => Keyword::r#where,
+            "while" => Keyword::r#while,
+            "yield" => Keyword::r#yield,
+            "u8" => Keyword::r#u8,
+            "u16" => Keyword::r#u16,
+            "u32" => Keyword::r#u32,
+            "u64" => Keyword::r#u64,
+            "u128" => Keyword::r#u128,
+            "i8" => Keyword::r#i8,
+            "i16" => Keyword::r#i16,
+            "i32" => Keyword::r#i32,
+            "i64" => Keyword::r#i64,
+            "i128" => Keyword::r#i128,
+            "f16" => Keyword::r#f16,
+            "f32" => Keyword::r#f32,
+            "f64" => Keyword::r#f64,
+            "f128" => Keyword::r#f128,
+            "bool" => Keyword::r#bool,
+            "char" => Keyword::r#char,
+            "str" => Keyword::r#str,
+            _ => return None,
+        })
+    }
+
+    /// e.g. [`Keyword::Self_`] -> `"Self"`
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Keyword::r#abstract => "abstract",
+            Keyword::r#as => "as",
+            Keyword::r#async => "async",
+            Keyword::r#await => "await",
+            Keyword::r#become => "become",
+            Keyword::r#box => "box",
+            Keyword::r#break => "break",
+            Keyword::r#const => "const",
+            Keyword::r#continue => "continue",
+            Keyword::crate_ => "crate",
+            Keyword::r#do => "do",
+            Keyword::r#dyn => "dyn",
+            Keyword::r#else => "else",
+            Keyword::r#enum => "enum",
+            Keyword::r#extern => "extern",
+            Keyword::r#false => "false",
+            Keyword::r#final => "final",
+            Keyword::r#fn => "fn",
+            Keyword::r#for => "for",
+            Keyword::r#gen => "gen",
+            Keyword::r#if => "if",
+            Keyword::r#impl => "impl",
+            Keyword::r#in => "in",
+            Keyword::r#let => "let",
+            Keyword::r#loop => "loop",
+            Keyword::r#marco => "marco",
+            Keyword::r#match => "match",
+            Keyword::r#mod => "mod",
+            Keyword::r#move => "move",
+            Keyword::r#mut => "mut",
+            Keyword::r#override => "override",
+            Keyword::r#priv => "priv",
+            Keyword::r#pub => "pub",
+            Keyword::r#ref => "ref",
+            Keyword::r#return => "return",
+            Keyword::Self_ => "Self",
+            Keyword::self_ => "self",
+            Keyword::r#static => "static",
+            Keyword::r#struct => "struct",
+            Keyword::super_ => "super",
+            Keyword::r#trait => "trait",
+            Keyword::r#true => "true",
+            Keyword::r#try => "try",
+            Keyword::r#typeof => "typeof",
+            Keyword::r#type => "type",
+            Keyword::r#union => "union",
+            Keyword::r#unsafe => "unsafe",
+            Keyword::r#unsized => "unsized",
+            Keyword::r#use => "use",
+            Keyword::r#virtual => "virtual",
+            Keyword::r#where => "where",
+            Keyword::r#while => "while",
+            Keyword::r#yield => "yield",
+            Keyword::r#u8 => "u8",
+            Keyword::r#u16 => "u16",
+            Keyword::r#u32 => "u32",
+            Keyword::r#u64 => "u64",
+            Keyword::r#u128 => "u128",
+            Keyword::r#i8 => "i8",
+            Keyword::r#i16 => "i16",
+            Keyword::r#i32 => "i32",
+            Keyword::r#i64 => "i64",
+            Keyword::r#i128 => "i128",
+            Keyword::r#f16 => "f16",
+            Keyword::r#f32 => "f32",
+            Keyword::r#f64 => "f64",
+            Keyword::r#f128 => "f128",
+            Keyword::r#bool => "bool",
+            Keyword::r#char => "char",
+            Keyword::r#str => "str",
+        }
+    }
+}
 impl Display for Token {
     fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
         match self {
             Token::Comment(comm) => write!(f, "{comm}"),
             Token::Identifier(identifier) => write!(f, "{identifier}"),
             Token::Keyword(keyword) => write!(f, "{keyword}"),
+            Token::Lifetime(lifetime) => write!(f, "'{lifetime}"),
             Token::Literal(literal) => write!(f, "{literal}"),
             Token::Operator(operator) => write!(f, "{operator}"),
             Token::EOF => write!(f, "<EOF>"),
@@ -224,41 +750,68 @@ impl Display for Comment {
 }
 impl Display for Identifier {
     fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
-        write!(f, "{}", self.inner)
+        if self.raw {
+            write!(f, "r#{}", self.inner)
+        } else {
+            write!(f, "{}", self.inner)
+        }
     }
 }
 impl Display for Keyword {
     fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
-        write!(f, "{self:?}")
+        write!(f, "{}", self.as_str())
     }
 }
 impl Display for Literal {
     fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
         match self {
-            Literal::U8(val) => write!(f, "{val:?}"),
-            Literal::U16(val) => write!(f, "{val:?}"),
-            Literal::U32(val) => write!(f, "{val:?}"),
-            Literal::U64(val) => write!(f, "{val:?}"),
-            Literal::U128(val) => write!(f, "{val:?}"),
-            Literal::I8(val) => write!(f, "{val:?}"),
-            Literal::I16(val) => write!(f, "{val:?}"),
-            Literal::I32(val) => write!(f, "{val:?}"),
-            Literal::I64(val) => write!(f, "{val:?}"),
-            Literal::I128(val) => write!(f, "{val:?}"),
-            #[cfg(feature = "unstable-f16")]
-            Literal::F16(val) => write!(f, "{:?}", val),
-            Literal::F32(val) => write!(f, "{val:?}"),
-            Literal::F64(val) => write!(f, "{val:?}"),
-            #[cfg(feature = "unstable-f128")]
-            Literal::F128(val) => write!(f, "{:?}", val),
+            Literal::Int(lit) => write!(f, "{lit}"),
+            Literal::Float(lit) => write!(f, "{lit}"),
             Literal::Bool(val) => write!(f, "{val:?}"),
             Literal::Char(val) => write!(f, "{val:?}"),
             Literal::String(val) => write!(f, "{val:?}"),
+            Literal::CChar8(val) => {
+                write!(f, "b'{}'", std::ascii::escape_default(*val))
+            }
+            Literal::CChar16(val) => {
+                let val = String::from_utf16_lossy(&[*val]);
+                write!(f, "u'{}'", val.escape_debug())
+            }
+            Literal::CChar8Str(val) => write!(f, "b\"{}\"", val.escape_ascii()),
+            Literal::CChar16Str(val) => {
+                write!(f, "u{:?}", String::from_utf16_lossy(val))
+            }
+        }
+    }
+}
+impl Display for IntLit {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        if self.negative {
+            write!(f, "-")?;
+        }
+        write!(f, "{}", self.text)?;
+        match self.suffix {
+            Some(suffix) => write!(f, "{}", suffix.as_str()),
+            None => Ok(()),
+        }
+    }
+}
+impl Display for FloatLit {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "{}", self.text)?;
+        match self.suffix {
+            Some(suffix) => write!(f, "{}", suffix.as_str()),
+            None => Ok(()),
         }
     }
 }
 impl Display for Operator {
     fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
-        write!(f, "{}", self.inner)
+        write!(f, "{}", self.kind)
+    }
+}
+impl Display for OperatorKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "{}", self.as_str())
     }
 }
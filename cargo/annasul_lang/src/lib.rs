@@ -11,10 +11,11 @@
 // You should have received a copy of the GNU Affero General Public License
 // along with this program. If not, see <https://www.gnu.org/licenses/>.
 #![doc = include_str!("lib.md")]
-#![cfg_attr(feature = "unstable-f16", feature(f16))]
-#![cfg_attr(feature = "unstable-f128", feature(f128))]
 #![feature(string_into_chars)]
+pub mod attr;
 pub mod codegen;
+pub mod expand;
+pub mod interp;
 pub mod ir;
 pub mod lexer;
 pub mod parser;
@@ -11,3 +11,1123 @@
 // You should have received a copy of the GNU Affero General Public License
 // along with this program. If not, see <https://www.gnu.org/licenses/>.
 pub mod ast;
+mod attr;
+pub mod cst;
+mod expr;
+mod format;
+mod item;
+mod mac;
+mod pat;
+mod stmt;
+mod ty;
+pub mod visit;
+use std::{collections::HashMap, rc::Rc};
+
+use crate::{
+    lexer::token::{
+        Comment,
+        CommentType,
+        Identifier,
+        Keyword,
+        Operator,
+        OperatorKind,
+        Token,
+    },
+    parser::ast::{GenericArgs, Ident, Path, PathSegment},
+    utils::{
+        error::{Error, Result},
+        span::{FileId, Span, Spanned},
+    },
+};
+/// a recursive descent parser over the tokens of
+/// [AnnasulScanner](crate::lexer::scanner::AnnasulScanner)
+#[derive(Debug, Clone)]
+pub struct Parser {
+    /// never empty, the last token is [Token::EOF]
+    tokens:          Vec<Spanned<Token>>,
+    /// index of the next unconsumed token
+    pos:             usize,
+    /// `false` in the condition of `if`/`while`/`match`/`for`, where `a {`
+    /// starts a block instead of a struct literal
+    struct_literals: bool,
+    /// documentation comments in source order, see [Parser::docs_before]
+    docs:            Vec<Spanned<Comment>>,
+    /// see [Parser::take_errors]
+    errors:          Vec<Error>,
+    /// see [Parser::with_source]
+    sources:         HashMap<FileId, Rc<str>>,
+}
+impl Parser {
+    /// comments in `tokens` are skipped, documentation comments are
+    /// attached to the items, fields and variants after them
+    pub fn new(tokens: Vec<Spanned<Token>>) -> Self {
+        let mut docs = Vec::new();
+        let mut tokens: Vec<_> = tokens
+            .into_iter()
+            .filter_map(|token| match token.node {
+                Token::Comment(comment) if comment.is_doc() => {
+                    docs.push(Spanned::new(comment, token.span));
+                    None
+                }
+                Token::Comment(_) => None,
+                _ => Some(token),
+            })
+            .collect();
+        if !matches!(tokens.last(), Some(Spanned { node: Token::EOF, .. })) {
+            let span = tokens
+                .last()
+                .map(|token| token.span.shrink_to_hi())
+                .unwrap_or_default();
+            tokens.push(Spanned::new(Token::EOF, span));
+        }
+        Self {
+            tokens,
+            pos: 0,
+            struct_literals: true,
+            docs,
+            errors: Vec::new(),
+            sources: HashMap::new(),
+        }
+    }
+
+    /// the text the tokens were scanned from, so the placeholders of format
+    /// strings with escapes get exact spans
+    pub fn with_source(self, source: impl Into<Rc<str>>) -> Self {
+        let file = self.tokens[0].span.file();
+        self.with_file_source(file, source)
+    }
+
+    /// see [Parser::with_source], for tokens from several files, e.g. a
+    /// macro expansion
+    pub fn with_file_source(
+        mut self, file: FileId, source: impl Into<Rc<str>>,
+    ) -> Self {
+        self.sources.insert(file, source.into());
+        // the end of input added by [Parser::new] right after the last token
+        if let [.., last, eof] = &self.tokens[..]
+            && last.span.file() == file
+            && eof.span.lo() == last.span.hi()
+        {
+            let span = self.end_of(last.span);
+            self.tokens.last_mut().unwrap().span = span;
+        }
+        self
+    }
+
+    /// the syntax errors recovered from so far, the erroneous parts of the
+    /// tree are [ExprKind::Err](ast::ExprKind::Err) and
+    /// [ItemKind::Err](ast::ItemKind::Err)
+    pub fn take_errors(&mut self) -> Vec<Error> {
+        std::mem::take(&mut self.errors)
+    }
+
+    /// all tokens are consumed
+    pub fn is_eof(&self) -> bool { self.peek() == &Token::EOF }
+
+    /// the number of consumed tokens, e.g. to match a macro fragment
+    pub(crate) fn consumed(&self) -> usize { self.pos }
+
+    /// e.g. `a::B<i32>` of a `$p:path` macro fragment
+    pub(crate) fn parse_type_path(&mut self) -> Result<Path> {
+        self.parse_path(PathStyle::Type)
+    }
+
+    /// the documentation comments of `comment_type` between the last
+    /// consumed token and the next one
+    fn docs_before(&self, comment_type: CommentType) -> Vec<Spanned<Comment>> {
+        let lo = match self.pos {
+            0 => 0,
+            pos => self.tokens[pos - 1].span.hi(),
+        };
+        let hi = self.span().lo();
+        let start = self.docs.partition_point(|doc| doc.span.lo() < lo);
+        self.docs[start..]
+            .iter()
+            .take_while(|doc| doc.span.lo() < hi)
+            .filter(|doc| *doc.node.comment_type() == comment_type)
+            .cloned()
+            .collect()
+    }
+
+    /// the next unconsumed token
+    fn peek(&self) -> &Token { &self.tokens[self.pos].node }
+
+    /// the `n`th unconsumed token, [Token::EOF] past the end
+    fn peek_nth(&self, n: usize) -> &Token {
+        let last = self.tokens.len() - 1;
+        &self.tokens[(self.pos + n).min(last)].node
+    }
+
+    /// the span of the next unconsumed token
+    fn span(&self) -> Span { self.tokens[self.pos].span }
+
+    /// the span of the last consumed token
+    /// the empty span at the end of `span`, exact if its source is known
+    fn end_of(&self, span: Span) -> Span {
+        match self.sources.get(&span.file()) {
+            Some(source) => span.shrink_to_hi_in(source),
+            None => span.shrink_to_hi(),
+        }
+    }
+
+    fn prev_span(&self) -> Span {
+        match self.pos {
+            0 => self.span(),
+            pos => self.tokens[pos - 1].span,
+        }
+    }
+
+    /// consumes the next token, [Token::EOF] is never consumed
+    fn bump(&mut self) -> Spanned<Token> {
+        let token = self.tokens[self.pos].clone();
+        if self.pos + 1 < self.tokens.len() {
+            self.pos += 1;
+        }
+        token
+    }
+
+    fn is_op(&self, kind: OperatorKind) -> bool {
+        matches!(self.peek(), Token::Operator(op) if op.kind() == kind)
+    }
+
+    fn eat_op(&mut self, kind: OperatorKind) -> bool {
+        let is_op = self.is_op(kind);
+        if is_op {
+            self.bump();
+        }
+        is_op
+    }
+
+    fn expect_op(&mut self, kind: OperatorKind) -> Result<Span> {
+        if !self.is_op(kind) {
+            return Err(self.unexpected(&format!("`{kind}`")));
+        }
+        Ok(self.bump().span)
+    }
+
+    fn is_keyword(&self, keyword: Keyword) -> bool {
+        matches!(self.peek(), Token::Keyword(k) if *k == keyword)
+    }
+
+    fn eat_keyword(&mut self, keyword: Keyword) -> bool {
+        let is_keyword = self.is_keyword(keyword);
+        if is_keyword {
+            self.bump();
+        }
+        is_keyword
+    }
+
+    fn expect_keyword(&mut self, keyword: Keyword) -> Result<Span> {
+        if !self.is_keyword(keyword) {
+            return Err(self.unexpected(&format!("`{keyword}`")));
+        }
+        Ok(self.bump().span)
+    }
+
+    fn expect_ident(&mut self) -> Result<Ident> {
+        match self.peek().clone() {
+            Token::Identifier(ident) => {
+                Ok(Spanned::new(ident, self.bump().span))
+            }
+            _ => Err(self.unexpected("identifier")),
+        }
+    }
+
+    /// records `error` and skips to the next `boundary`, `;` or unmatched
+    /// `}` that is not nested in a delimiter, the `;` is consumed, braces
+    /// are matched from `start`, the first token of the erroneous node
+    fn recover(
+        &mut self, error: Error, start: usize, boundary: fn(&Self) -> bool,
+    ) {
+        self.errors.push(error);
+        let mut braces = 0usize;
+        for token in &self.tokens[start..self.pos] {
+            let Token::Operator(op) = &token.node else {
+                continue;
+            };
+            match op.kind() {
+                OperatorKind::OpenBrace => braces += 1,
+                OperatorKind::CloseBrace => braces = braces.saturating_sub(1),
+                _ => {}
+            }
+        }
+        // `(` and `[` after the error, the ones before may be unclosed
+        let mut groups = 0usize;
+        loop {
+            let nested = braces > 0 || groups > 0;
+            match self.peek() {
+                Token::EOF => break,
+                Token::Operator(op) => match op.kind() {
+                    OperatorKind::OpenBrace => braces += 1,
+                    OperatorKind::CloseBrace if braces == 0 => break,
+                    OperatorKind::CloseBrace => braces -= 1,
+                    OperatorKind::OpenParen | OperatorKind::OpenBracket => {
+                        groups += 1
+                    }
+                    OperatorKind::CloseParen | OperatorKind::CloseBracket => {
+                        groups = groups.saturating_sub(1)
+                    }
+                    OperatorKind::Semi if !nested => {
+                        self.bump();
+                        break;
+                    }
+                    _ if !nested && boundary(self) => break,
+                    _ => {}
+                },
+                _ if !nested && boundary(self) => break,
+                _ => {}
+            }
+            self.bump();
+        }
+        if self.pos == start && !self.is_eof() {
+            self.bump();
+        }
+    }
+
+    /// e.g. `expected expression, found `}``
+    fn unexpected(&self, expected: &str) -> Error {
+        let found = match self.peek() {
+            Token::EOF => "end of file".to_string(),
+            token => format!("`{token}`"),
+        };
+        Error::new(self.span(), format!("expected {expected}, found {found}"))
+    }
+
+    /// runs `f` with [Parser::struct_literals] set to `allowed`
+    fn with_struct_literals<T>(
+        &mut self, allowed: bool, f: impl FnOnce(&mut Self) -> Result<T>,
+    ) -> Result<T> {
+        let saved = std::mem::replace(&mut self.struct_literals, allowed);
+        let result = f(self);
+        self.struct_literals = saved;
+        result
+    }
+
+    /// `f` separated by `,` until `close`, which is consumed, e.g. `a, b)`
+    fn parse_delimited<T>(
+        &mut self, close: OperatorKind,
+        mut f: impl FnMut(&mut Self) -> Result<T>,
+    ) -> Result<Vec<T>> {
+        self.with_struct_literals(true, |this| {
+            let mut items = Vec::new();
+            while !this.eat_op(close) {
+                items.push(f(this)?);
+                if !this.eat_op(OperatorKind::Comma) {
+                    if !this.eat_op(close) {
+                        return Err(
+                            this.unexpected(&format!("`,` or `{close}`"))
+                        );
+                    }
+                    break;
+                }
+            }
+            Ok(items)
+        })
+    }
+
+    /// keywords that can be a [PathSegment], e.g. `crate`, `Self`, `i32`
+    fn is_path_keyword(keyword: &Keyword) -> bool {
+        matches!(
+            keyword,
+            Keyword::crate_ | Keyword::self_ | Keyword::Self_ | Keyword::super_
+        ) || Self::is_primitive_type(keyword)
+    }
+
+    /// e.g. `i32`, `bool`, `str`
+    fn is_primitive_type(keyword: &Keyword) -> bool {
+        matches!(
+            keyword,
+            Keyword::r#u8
+                | Keyword::r#u16
+                | Keyword::r#u32
+                | Keyword::r#u64
+                | Keyword::r#u128
+                | Keyword::r#i8
+                | Keyword::r#i16
+                | Keyword::r#i32
+                | Keyword::r#i64
+                | Keyword::r#i128
+                | Keyword::r#f16
+                | Keyword::r#f32
+                | Keyword::r#f64
+                | Keyword::r#f128
+                | Keyword::r#bool
+                | Keyword::r#char
+                | Keyword::r#str
+        )
+    }
+
+    /// the next token can start a [Path]
+    fn is_path_start(&self) -> bool {
+        match self.peek() {
+            Token::Identifier(_) => true,
+            Token::Keyword(keyword) => Self::is_path_keyword(keyword),
+            _ => false,
+        }
+    }
+
+    /// `>`, splitting `>>`, `>=` and `>>=` so `Vec<Vec<i32>>` closes both
+    /// generic argument lists
+    fn eat_gt(&mut self) -> bool {
+        let rest = match self.peek() {
+            Token::Operator(op) => match op.kind() {
+                OperatorKind::Gt => {
+                    self.bump();
+                    return true;
+                }
+                OperatorKind::Shr => OperatorKind::Gt,
+                OperatorKind::Ge => OperatorKind::Eq,
+                OperatorKind::ShrEq => OperatorKind::Ge,
+                _ => return false,
+            },
+            _ => return false,
+        };
+        let token = &mut self.tokens[self.pos];
+        let span = token.span;
+        token.node = Token::Operator(Operator::new(rest));
+        token.span = Span::new(
+            span.file(),
+            span.lo() + 1,
+            span.hi(),
+            span.line(),
+            span.col() + 1,
+        );
+        true
+    }
+
+    /// e.g. `<i32, Vec<bool>>`
+    fn parse_generic_args(&mut self) -> Result<GenericArgs> {
+        let lo = self.expect_op(OperatorKind::Lt)?;
+        let mut args = Vec::new();
+        while !self.eat_gt() {
+            args.push(self.parse_type()?);
+            if !self.eat_op(OperatorKind::Comma) {
+                if !self.eat_gt() {
+                    return Err(self.unexpected("`,` or `>`"));
+                }
+                break;
+            }
+        }
+        Ok(GenericArgs { args, span: lo.to(self.prev_span()) })
+    }
+
+    fn parse_path_segment(&mut self) -> Result<PathSegment> {
+        let ident = match self.peek().clone() {
+            Token::Identifier(ident) => ident,
+            Token::Keyword(keyword) if Self::is_path_keyword(&keyword) => {
+                Identifier::new(keyword.as_str())
+            }
+            _ => return Err(self.unexpected("identifier")),
+        };
+        let ident = Spanned::new(ident, self.bump().span);
+        Ok(PathSegment { ident, args: None })
+    }
+
+    /// e.g. `a`, `a::b::C`, `Vec::<i32>::new` and `Vec<i32>` for
+    /// [PathStyle::Type]
+    fn parse_path(&mut self, style: PathStyle) -> Result<Path> {
+        let mut segments = Vec::new();
+        loop {
+            let mut segment = self.parse_path_segment()?;
+            if style == PathStyle::Type && self.is_op(OperatorKind::Lt) {
+                segment.args = Some(self.parse_generic_args()?);
+            }
+            segments.push(segment);
+            if !self.is_op(OperatorKind::PathSep) {
+                break;
+            }
+            self.bump();
+            if self.is_op(OperatorKind::Lt) {
+                let args = self.parse_generic_args()?;
+                segments.last_mut().unwrap().args = Some(args);
+                if !self.eat_op(OperatorKind::PathSep) {
+                    break;
+                }
+            }
+        }
+        let span = segments[0].ident.span.to(self.prev_span());
+        Ok(Path::new(segments, span))
+    }
+}
+/// where a [Path] is parsed, see [Parser::parse_path]
+#[derive(Debug, Clone, Copy, Hash, PartialEq, Eq)]
+enum PathStyle {
+    /// generic arguments need `::<`, since `a < b` is a comparison
+    Expr,
+    /// e.g. `Vec<i32>`
+    Type,
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        lexer::scanner::{AnnasulScanner, Scanner},
+        parser::ast::{
+            Expr,
+            ExprKind,
+            FormatAlign,
+            FormatArg,
+            FormatPiece,
+            FormatSpec,
+            FormatTrait,
+            Pat,
+            PatKind,
+            Stmt,
+            StmtKind,
+            Type,
+            TypeKind,
+        },
+    };
+    fn parser(source: &str) -> Parser {
+        let (tokens, errors) =
+            AnnasulScanner::new(source.as_bytes()).tokenize();
+        assert_eq!(errors, []);
+        Parser::new(tokens).with_source(source)
+    }
+    fn path(path: &Path) -> String {
+        let segments: Vec<_> = path
+            .segments
+            .iter()
+            .map(|s| match &s.args {
+                Some(args) => {
+                    let args: Vec<_> = args.args.iter().map(ty).collect();
+                    format!("{}<{}>", s.ident.node, args.join(", "))
+                }
+                None => s.ident.node.to_string(),
+            })
+            .collect();
+        segments.join("::")
+    }
+    fn ty(ty: &Type) -> String {
+        let join = |tys: &[Type]| -> String {
+            tys.iter().map(self::ty).collect::<Vec<_>>().join(", ")
+        };
+        let bounds = |bounds: &[Path]| -> String {
+            bounds.iter().map(path).collect::<Vec<_>>().join(" + ")
+        };
+        match &ty.kind {
+            TypeKind::Path(p) => path(p),
+            TypeKind::Tuple(elems) => format!("({})", join(elems)),
+            TypeKind::Ptr { mutable: true, ty: inner } => {
+                format!("*mut {}", self::ty(inner))
+            }
+            TypeKind::Ptr { mutable: false, ty: inner } => {
+                format!("*const {}", self::ty(inner))
+            }
+            TypeKind::Ref { mutable: true, ty: inner } => {
+                format!("&mut {}", self::ty(inner))
+            }
+            TypeKind::Ref { mutable: false, ty: inner } => {
+                format!("&{}", self::ty(inner))
+            }
+            TypeKind::Slice(elem) => format!("[{}]", self::ty(elem)),
+            TypeKind::Array(elem, len) => {
+                format!("[{}; {}]", self::ty(elem), sexp(len))
+            }
+            TypeKind::TraitObject(b) => format!("dyn {}", bounds(b)),
+            TypeKind::ImplTrait(b) => format!("impl {}", bounds(b)),
+            TypeKind::Box(inner) => format!("({}).Box", self::ty(inner)),
+            TypeKind::Infer => "_".to_string(),
+        }
+    }
+    fn pat(pat: &Pat) -> String {
+        let join = |pats: &[Pat], sep| -> String {
+            pats.iter().map(self::pat).collect::<Vec<_>>().join(sep)
+        };
+        let opt = |p: &Option<Box<Pat>>| {
+            p.as_ref().map(|p| self::pat(p)).unwrap_or_default()
+        };
+        match &pat.kind {
+            PatKind::Wild => "_".to_string(),
+            PatKind::Rest => "..".to_string(),
+            PatKind::Ident { by_ref, mutable, name, sub } => format!(
+                "{}{}{}{}",
+                if *by_ref { "ref " } else { "" },
+                if *mutable { "mut " } else { "" },
+                name.node,
+                sub.as_ref()
+                    .map(|sub| format!(" @ {}", self::pat(sub)))
+                    .unwrap_or_default()
+            ),
+            PatKind::Literal(literal) => literal.to_string(),
+            PatKind::Path(p) => path(p),
+            PatKind::Tuple(pats) => format!("({})", join(pats, ", ")),
+            PatKind::TupleStruct(p, pats) => {
+                format!("{}({})", path(p), join(pats, ", "))
+            }
+            PatKind::Struct { path: p, fields, rest } => {
+                let mut fields: Vec<_> = fields
+                    .iter()
+                    .map(|f| format!("{}: {}", f.name.node, self::pat(&f.pat)))
+                    .collect();
+                if *rest {
+                    fields.push("..".to_string());
+                }
+                format!("{} {{ {} }}", path(p), fields.join(", "))
+            }
+            PatKind::Slice(pats) => format!("[{}]", join(pats, ", ")),
+            PatKind::Range { start, end, inclusive } => format!(
+                "({}{}{})",
+                opt(start),
+                if *inclusive { "..=" } else { ".." },
+                opt(end)
+            ),
+            PatKind::Ref { mutable, pat: inner } => format!(
+                "&{}{}",
+                if *mutable { "mut " } else { "" },
+                self::pat(inner)
+            ),
+            PatKind::Or(pats) => format!("({})", join(pats, " | ")),
+        }
+    }
+    fn list(exprs: &[Expr]) -> String {
+        exprs.iter().map(sexp).map(|e| format!(" {e}")).collect()
+    }
+    fn stmt(stmt: &Stmt) -> String {
+        match &stmt.kind {
+            StmtKind::Expr(expr) => sexp(expr),
+            StmtKind::Semi(expr) => format!("{};", sexp(expr)),
+            StmtKind::Empty => ";".to_string(),
+            StmtKind::Item(item) => {
+                format!("(item {})", item.name().unwrap().node)
+            }
+            StmtKind::Let(local) => format!(
+                "(let {}{}{}{})",
+                pat(&local.pat),
+                local
+                    .ty
+                    .as_ref()
+                    .map(|t| format!(": {}", ty(t)))
+                    .unwrap_or_default(),
+                local
+                    .init
+                    .as_ref()
+                    .map(|e| format!(" = {}", sexp(e)))
+                    .unwrap_or_default(),
+                local
+                    .els
+                    .as_ref()
+                    .map(|b| format!(" else {}", block(b)))
+                    .unwrap_or_default(),
+            ),
+        }
+    }
+    fn block(block: &ast::Block) -> String {
+        let stmts: Vec<_> = block.stmts.iter().map(stmt).collect();
+        format!("{{{}}}", stmts.join(" "))
+    }
+    /// a fully parenthesized form of `expr`
+    fn sexp(expr: &Expr) -> String {
+        let opt = |e: &Option<Box<Expr>>| {
+            e.as_ref().map(|e| format!(" {}", sexp(e))).unwrap_or_default()
+        };
+        let label = |l: &Option<ast::Label>| {
+            l.as_ref().map(|l| format!(" '{}", l.node)).unwrap_or_default()
+        };
+        match &expr.kind {
+            ExprKind::Literal(literal) => literal.to_string(),
+            ExprKind::Path(p) => path(p),
+            ExprKind::Tuple(elems) => format!("(tuple{})", list(elems)),
+            ExprKind::Paren(inner) => sexp(inner),
+            ExprKind::Array(elems) => format!("[{}]", list(elems)),
+            ExprKind::Repeat(elem, len) => {
+                format!("[{}; {}]", sexp(elem), sexp(len))
+            }
+            ExprKind::Struct { path: p, fields, rest } => {
+                let fields: String = fields
+                    .iter()
+                    .map(|f| format!(" {}: {}", f.name.node, sexp(&f.expr)))
+                    .collect();
+                format!("(struct {}{fields}{})", path(p), opt(rest))
+            }
+            ExprKind::Unary(op, e) => format!("({} {})", op.as_str(), sexp(e)),
+            ExprKind::Ref { mutable, expr } => {
+                let m = if *mutable { "&mut" } else { "&" };
+                format!("({m} {})", sexp(expr))
+            }
+            ExprKind::Box(e) => format!("(box {})", sexp(e)),
+            ExprKind::Binary(op, l, r) => {
+                format!("({} {} {})", op.node.as_str(), sexp(l), sexp(r))
+            }
+            ExprKind::Assign(l, r) => format!("(= {} {})", sexp(l), sexp(r)),
+            ExprKind::AssignOp(op, l, r) => {
+                format!("({}= {} {})", op.node.as_str(), sexp(l), sexp(r))
+            }
+            ExprKind::Cast(e, t) => format!("(as {} {})", sexp(e), ty(t)),
+            ExprKind::Call(f, args) => {
+                format!("(call {}{})", sexp(f), list(args))
+            }
+            ExprKind::MethodCall { receiver, method, args } => {
+                format!("(.{}() {}{})", method.node, sexp(receiver), list(args))
+            }
+            ExprKind::Field(e, name) => format!("(.{} {})", name.node, sexp(e)),
+            ExprKind::Index(e, i) => format!("([] {} {})", sexp(e), sexp(i)),
+            ExprKind::Try(e) => format!("(? {})", sexp(e)),
+            ExprKind::Range { start, end, inclusive } => {
+                let op = if *inclusive { "..=" } else { ".." };
+                format!("({op}{}{})", opt(start), opt(end))
+            }
+            ExprKind::Block(b) => block(b),
+            ExprKind::If { cond, then_branch, else_branch } => format!(
+                "(if {} {}{})",
+                sexp(cond),
+                block(then_branch),
+                opt(else_branch)
+            ),
+            ExprKind::Match { scrutinee, arms } => {
+                let arms: String = arms
+                    .iter()
+                    .map(|arm| {
+                        let pat = pat(&arm.pat);
+                        let guard = arm
+                            .guard
+                            .as_ref()
+                            .map(|g| format!(" if {}", sexp(g)))
+                            .unwrap_or_default();
+                        format!(" ({pat}{guard} => {})", sexp(&arm.body))
+                    })
+                    .collect();
+                format!("(match {}{arms})", sexp(scrutinee))
+            }
+            ExprKind::Loop { label: l, body } => {
+                format!("(loop{} {})", label(l), block(body))
+            }
+            ExprKind::While { label: l, cond, body } => {
+                format!("(while{} {} {})", label(l), sexp(cond), block(body))
+            }
+            ExprKind::For { label: l, pat, iter, body } => {
+                format!(
+                    "(for{} {} {} {})",
+                    label(l),
+                    self::pat(pat),
+                    sexp(iter),
+                    block(body)
+                )
+            }
+            ExprKind::Break { label: l, value } => {
+                format!("(break{}{})", label(l), opt(value))
+            }
+            ExprKind::Continue { label: l } => {
+                format!("(continue{})", label(l))
+            }
+            ExprKind::Return(value) => format!("(return{})", opt(value)),
+            ExprKind::Format(format) => {
+                let pieces: String = format
+                    .pieces
+                    .iter()
+                    .map(|piece| match piece {
+                        FormatPiece::Literal(text) => format!(" {text:?}"),
+                        FormatPiece::Placeholder(p) => match &p.arg {
+                            FormatArg::Index(i) => format!(" {{{i}}}"),
+                            FormatArg::Expr(e) => format!(" {{{}}}", sexp(e)),
+                        },
+                    })
+                    .collect();
+                format!(
+                    "({}!{pieces}{})",
+                    format.mac.as_str(),
+                    list(&format.args)
+                )
+            }
+            ExprKind::MacCall(mac) => {
+                let tokens: String = ast::TokenTree::flatten(&mac.tokens)
+                    .iter()
+                    .map(|token| format!(" {}", token.node))
+                    .collect();
+                format!("({}!{tokens})", path(&mac.path))
+            }
+            ExprKind::Err => "<err>".to_string(),
+        }
+    }
+    fn parse(source: &str) -> String {
+        let mut parser = parser(source);
+        let expr = parser.parse_expr().unwrap();
+        assert!(parser.is_eof(), "`{source}` is not fully parsed");
+        sexp(&expr)
+    }
+    /// the first syntax error, recovered from or not
+    fn parse_err(source: &str) -> String {
+        let mut parser = parser(source);
+        let error = match parser.parse_expr() {
+            Ok(_) => parser.take_errors().remove(0),
+            Err(error) => error,
+        };
+        error.to_string()
+    }
+    #[test]
+    fn precedence_and_associativity() {
+        for (source, expected) in [
+            ("1 + 2 * 3", "(+ 1 (* 2 3))"),
+            ("1 - 2 - 3", "(- (- 1 2) 3)"),
+            ("a = b = c", "(= a (= b c))"),
+            ("a += 1 << 2 | 3", "(+= a (| (<< 1 2) 3))"),
+            ("a || b && c == d", "(|| a (&& b (== c d)))"),
+            ("a & b ^ c | d", "(| (^ (& a b) c) d)"),
+            ("-a.b as i64 * 2", "(* (as (- (.b a)) i64) 2)"),
+            ("*&mut x[0]", "(* (&mut ([] x 0)))"),
+            ("&&a", "(& (& a))"),
+            ("!box f(1, 2)?", "(! (box (? (call f 1 2))))"),
+            ("a.b(c).d.0", "(.0 (.d (.b() a c)))"),
+            ("(1 + 2) * 3", "(* (+ 1 2) 3)"),
+            ("((), (1,), [a; 2], [])", "(tuple (tuple) (tuple 1) [a; 2] [])"),
+            ("1..2 + 3", "(.. 1 (+ 2 3))"),
+            ("..=a", "(..= a)"),
+            ("a..", "(.. a)"),
+            ("x = ..", "(= x (..))"),
+            (
+                "crate::a::B { x: 1, y, ..c }",
+                "(struct crate::a::B x: 1 y: y c)",
+            ),
+            ("i32::MAX as u8", "(as i32::MAX u8)"),
+        ] {
+            assert_eq!(parse(source), expected, "{source}");
+        }
+    }
+    #[test]
+    fn control_flow() {
+        for (source, expected) in [
+            (
+                "if a { b } else if c { d } else { e }",
+                "(if a {b} (if c {d} {e}))",
+            ),
+            ("if a == B {}", "(if (== a B) {})"),
+            (
+                "match x { 0 => a, -1 => {} _ if y => b }",
+                "(match x (0 => a) (-1 => {}) (_ if y => b))",
+            ),
+            (
+                "'outer: loop { while x { break 'outer 1; } }",
+                "(loop 'outer {(while x {(break 'outer 1);})})",
+            ),
+            ("for i in 0.. { continue }", "(for i (.. 0) {(continue)})"),
+            ("{ if a {} -1 }", "{(if a {}) (- 1)}"),
+            ("{ a; ; match a {}.b() }", "{a; ; (.b() (match a))}"),
+            ("return", "(return)"),
+        ] {
+            assert_eq!(parse(source), expected, "{source}");
+        }
+    }
+    #[test]
+    fn syntax_errors() {
+        assert_eq!(
+            parse_err("a < b < c"),
+            "comparison operators cannot be chained at 1:7"
+        );
+        assert_eq!(
+            parse_err("a..b..c"),
+            "range operators cannot be chained at 1:5"
+        );
+        assert_eq!(parse_err("a..="), "inclusive range with no end at 1:2");
+        assert_eq!(
+            parse_err("(1 + )"),
+            "expected expression, found `)` at 1:6"
+        );
+        assert_eq!(
+            parse_err("{ a b }"),
+            "expected `;` or `}`, found `b` at 1:5"
+        );
+        assert_eq!(
+            parse_err("f(1"),
+            "expected `,` or `)`, found end of file at 1:4"
+        );
+    }
+    #[test]
+    fn format_macros() {
+        for (source, expected) in [
+            (r#"println!("Hello, world!")"#, r#"(println! "Hello, world!")"#),
+            (r#"println!("{*number}")"#, "(println! {(* number)})"),
+            (
+                r#"format!("{{{}}} {0} {a.b(1):?}, {}", x, y)"#,
+                r#"(format! "{" {0} "} " {0} " " {(.b() a 1)} ", " {1} x y)"#,
+            ),
+            (r#"print!["{P { x: 1 }.x}",]"#, "(print! {(.x (struct P x: 1))})"),
+            (
+                r#"eprintln!("{s:?}\n\x41{\"}\"}")"#,
+                r#"(eprintln! {s} "\nA" {"}"})"#,
+            ),
+            ("println!()", "(println!)"),
+        ] {
+            assert_eq!(parse(source), expected, "{source}");
+        }
+        let spec = |source: &str| {
+            let expr = parser(source).parse_expr().unwrap();
+            let ExprKind::Format(format) = expr.kind else { unreachable!() };
+            let [FormatPiece::Placeholder(placeholder)] = &format.pieces[..]
+            else {
+                unreachable!()
+            };
+            placeholder.spec
+        };
+        assert_eq!(spec(r#"print!("{a:>8.3}")"#), FormatSpec {
+            align: Some(FormatAlign::Right),
+            width: Some(8),
+            precision: Some(3),
+            ..FormatSpec::default()
+        });
+        assert_eq!(spec(r#"print!("{a:*^+#010X}")"#), FormatSpec {
+            fill: '*',
+            align: Some(FormatAlign::Center),
+            sign_plus: true,
+            alternate: true,
+            zero_pad: true,
+            width: Some(10),
+            format_trait: FormatTrait::UpperHex,
+            ..FormatSpec::default()
+        });
+        assert_eq!(spec(r#"print!("{:#?}")"#).format_trait, FormatTrait::Debug);
+        // other macros are expanded later
+        assert_eq!(parse("printf!(a, [b]) + 1"), "(+ (printf! a , [ b ]) 1)");
+        // the errors point inside the literal, after the escapes
+        for (source, expected) in [
+            (
+                r#"print!("\t\u{2764}{a:q}")"#,
+                "invalid format string: unknown format trait `q` at 1:22",
+            ),
+            (
+                r#"print!("a}")"#,
+                "invalid format string: unmatched `}` found at 1:10",
+            ),
+            (
+                r#"print!("a {b")"#,
+                "invalid format string: expected `}` but string was \
+                 terminated at 1:11",
+            ),
+            (
+                r#"print!("{a:.}")"#,
+                "invalid format string: expected a precision after `.` at 1:12",
+            ),
+            (
+                r#"print!("{a +}")"#,
+                "expected expression, found end of file at 1:13",
+            ),
+            (r#"print!("{a b}")"#, "expected `:` or `}`, found `b` at 1:12"),
+            (
+                r#"print!("{} {1}", a)"#,
+                "invalid reference to positional argument 1 (there is 1 \
+                 argument) at 1:12",
+            ),
+            (r#"print!("{a}", b)"#, "argument never used at 1:15"),
+            ("print!(a)", "format argument must be a string literal at 1:8"),
+            (
+                "format!()",
+                "`format!` requires at least a format string argument at 1:8",
+            ),
+            ("printf!(a, (b]", "unexpected closing delimiter `]` at 1:14"),
+            ("printf!(a, (b)", "unclosed delimiter at 1:8"),
+            (
+                "println!(\"x\\\n    {a b}\")",
+                "expected `:` or `}`, found `b` at 2:8",
+            ),
+        ] {
+            assert_eq!(parse_err(source), expected, "{source}");
+        }
+    }
+    #[test]
+    fn items() {
+        let source = r#"//! crate doc
+/// a point
+pub struct Point {
+    /// x
+    pub x: i32,
+    y: i32,
+}
+struct Unit;
+priv struct Pair(i32, pub bool);
+enum E { A, B(i32), C { c: u8 } = 2 }
+union U { a: i32 }
+trait T { fn a(&self); fn b(mut self) -> i32 { 1 } const C: i32; type A; }
+impl T for Point { fn a(&self) {} }
+impl Point { fn new(x: i32, y: i32) -> Self { Self { x, y } } }
+mod m {
+    //! m doc
+    use super::{Point as P, self::*};
+}
+mod file;
+const C: i32 = 1;
+static mut S: u8 = 0;
+type A = (i32, bool);
+extern "C" { fn puts(s: i32) -> i32; static errno: i32; }
+fn main() { fn inner() {} inner() }
+"#;
+        let module = parser(source).parse_module();
+        let names: Vec<_> = module
+            .items
+            .iter()
+            .map(|item| item.name().map_or("_".into(), |n| n.node.to_string()))
+            .collect();
+        assert_eq!(names, [
+            "Point", "Unit", "Pair", "E", "U", "T", "_", "_", "m", "file", "C",
+            "S", "A", "_", "main",
+        ]);
+        let comments = |docs: &[Spanned<Comment>]| -> Vec<String> {
+            docs.iter().map(|doc| doc.node.comment().to_string()).collect()
+        };
+        assert_eq!(comments(&module.docs), [" crate doc"]);
+        let [point, _, pair, enum_, _, trait_, impl_, _, m, file, ..] =
+            module.items.as_slice()
+        else {
+            unreachable!()
+        };
+        assert_eq!(comments(&point.docs), [" a point"]);
+        assert!(matches!(point.vis, ast::Visibility::Public(_)));
+        assert!(matches!(pair.vis, ast::Visibility::Private(_)));
+        let ast::ItemKind::Struct {
+            data: ast::VariantData::Struct(fields),
+            ..
+        } = &point.kind
+        else {
+            unreachable!()
+        };
+        assert_eq!(comments(&fields[0].docs), [" x"]);
+        assert!(matches!(fields[1].vis, ast::Visibility::Inherited));
+        let ast::ItemKind::Enum { variants, .. } = &enum_.kind else {
+            unreachable!()
+        };
+        assert!(matches!(variants[1].data, ast::VariantData::Tuple(_)));
+        assert!(variants[2].discriminant.is_some());
+        let ast::ItemKind::Trait { items, .. } = &trait_.kind else {
+            unreachable!()
+        };
+        let ast::ItemKind::Fn(b) = &items[1].kind else { unreachable!() };
+        let self_param = b.sig.self_param.unwrap();
+        assert!(!self_param.by_ref && self_param.mutable && b.body.is_some());
+        let ast::ItemKind::Impl { of_trait: Some(of_trait), .. } = &impl_.kind
+        else {
+            unreachable!()
+        };
+        assert_eq!(path(of_trait), "T");
+        let ast::ItemKind::Mod { module: Some(inline), .. } = &m.kind else {
+            unreachable!()
+        };
+        assert_eq!(comments(&inline.docs), [" m doc"]);
+        let ast::ItemKind::Use(tree) = &inline.items[0].kind else {
+            unreachable!()
+        };
+        let ast::UseTreeKind::Nested(trees) = &tree.kind else {
+            unreachable!()
+        };
+        assert_eq!(path(&tree.prefix), "super");
+        assert!(matches!(trees[0].kind, ast::UseTreeKind::Simple(Some(_))));
+        assert!(matches!(trees[1].kind, ast::UseTreeKind::Glob));
+        assert!(matches!(file.kind, ast::ItemKind::Mod { module: None, .. }));
+    }
+    #[test]
+    fn statements_patterns_and_types() {
+        for (source, expected) in [
+            ("{ let a; }", "{(let a)}"),
+            ("{ let mut a: i32 = 1; }", "{(let mut a: i32 = 1)}"),
+            (
+                "{ let Some(a) = b else { return }; }",
+                "{(let Some(a) = b else {(return)})}",
+            ),
+            (
+                "{ let (ref a, [b, .., c @ 1..=5], _) = x; }",
+                "{(let (ref a, [b, .., c @ (1..=5)], _) = x)}",
+            ),
+            (
+                "{ let &mut P { x, y: (0 | -1), ref mut z, .. } = p; }",
+                "{(let &mut P { x: x, y: (0 | -1), z: ref mut z, .. } = p)}",
+            ),
+            (
+                "match c { 'a'..='z' | ..=0 | 1.. | a::B => {} }",
+                "(match c ((('a'..='z') | (..=0) | (1..) | a::B) => {}))",
+            ),
+            (
+                "{ let a: &mut [*const (i32, bool); 2 + 1] = b; }",
+                "{(let a: &mut [*const (i32, bool); (+ 2 1)] = b)}",
+            ),
+            (
+                "{ let a: Vec<Vec<&dyn A + B>> = Vec::<i32>::new(); }",
+                "{(let a: Vec<Vec<&dyn A + B>> = (call Vec<i32>::new))}",
+            ),
+            (
+                "{ let a: (i32.Box, impl C, *mut (), [_]) = box 1; }",
+                "{(let a: ((i32).Box, impl C, *mut (), [_]) = (box 1))}",
+            ),
+            ("a as &i32.Box", "(as a &(i32).Box)"),
+            ("a as u8 >= b", "(>= (as a u8) b)"),
+        ] {
+            assert_eq!(parse(source), expected, "{source}");
+        }
+    }
+    #[test]
+    fn error_recovery() {
+        let source = r#"fn a() {
+    let x = 1 +;
+    let y = (2;
+    y
+}
+struct S { a: i32 b: i32 }
+fn b() -> i32 { match x { 1 => , 2 => 3 } }
+fn c( {}
+const D: i32 = 4;
+}
+fn e() { e
+"#;
+        let mut parser = parser(source);
+        let module = parser.parse_module();
+        let errors: Vec<_> =
+            parser.take_errors().iter().map(|e| e.to_string()).collect();
+        assert_eq!(errors, [
+            "expected expression, found `;` at 2:16",
+            "expected `,` or `)`, found `;` at 3:15",
+            "expected `,` or `}`, found `b` at 6:19",
+            "expected expression, found `,` at 7:32",
+            "expected pattern, found `{` at 8:7",
+            "expected item, found `}` at 10:1",
+            "expected `}`, found end of file at 12:1",
+        ]);
+        let items: Vec<_> = module
+            .items
+            .iter()
+            .map(|item| match &item.kind {
+                ast::ItemKind::Fn(f) => {
+                    format!(
+                        "fn {} {}",
+                        f.name.node,
+                        block(f.body.as_ref().unwrap())
+                    )
+                }
+                ast::ItemKind::Err => "<err>".to_string(),
+                _ => item.name().unwrap().node.to_string(),
+            })
+            .collect();
+        assert_eq!(items, [
+            "fn a {<err> <err> y}",
+            "<err>",
+            "fn b {(match x (2 => 3))}",
+            "<err>",
+            "D",
+            "<err>",
+            "fn e {e}",
+        ]);
+    }
+    #[test]
+    fn literals_with_lexer_errors() {
+        let source = r#"fn a() {
+    let b = 1.5f8 + 2.5;
+    let c = "\q";
+    let d = 'ef';
+    let e = 0b2;
+}"#;
+        let (tokens, errors) =
+            AnnasulScanner::new(source.as_bytes()).tokenize();
+        let errors: Vec<_> = errors.iter().map(|e| e.to_string()).collect();
+        assert_eq!(errors, [
+            "invalid suffix `f8` for float literal `1.5f8` at 2:13",
+            "unknown character escape `\\q` at 3:14",
+            "character literal may only contain one codepoint at 4:13",
+            "invalid digit `2` for a base 2 literal `0b2` at 5:13",
+        ]);
+        // each literal is replaced by one of its kind, so the parser reports
+        // nothing more
+        let mut parser = Parser::new(tokens).with_source(source);
+        let module = parser.parse_module();
+        assert_eq!(parser.take_errors(), []);
+        let ast::ItemKind::Fn(f) = &module.items[0].kind else { panic!() };
+        assert_eq!(
+            block(f.body.as_ref().unwrap()),
+            r#"{(let b = (+ 0.0 2.5)) (let c = "") (let d = '\0') (let e = 0)}"#
+        );
+    }
+}
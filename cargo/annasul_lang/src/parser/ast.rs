@@ -10,3 +10,997 @@
 //
 // You should have received a copy of the GNU Affero General Public License
 // along with this program. If not, see <https://www.gnu.org/licenses/>.
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    lexer::token::{
+        Comment,
+        Identifier,
+        Literal,
+        Operator,
+        OperatorKind,
+        Token,
+    },
+    utils::span::{Span, Spanned},
+};
+/// e.g. `foo`, `r#match`
+pub type Ident = Spanned<Identifier>;
+/// e.g. `'outer`, without the quote
+pub type Label = Spanned<Identifier>;
+/// identifies a [Path], a [Pat] or an [Item] of a crate, assigned after
+/// expansion by [assign_node_ids](crate::semantic::assign_node_ids)
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Debug, Clone, Copy, Hash, Ord, PartialOrd, PartialEq, Eq)]
+pub struct NodeId(u32);
+/// e.g. `foo`, `crate::foo::Bar`, `i32::MAX`, `Vec<i32>`
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Debug, Clone, PartialEq)]
+pub struct Path {
+    pub id:       NodeId,
+    pub segments: Vec<PathSegment>,
+    pub span:     Span,
+}
+/// e.g. `foo`, `crate`, `Self`, `i32`, `Vec<i32>`
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Debug, Clone, PartialEq)]
+pub struct PathSegment {
+    /// keywords like `crate` are stored as non-raw identifiers
+    pub ident: Ident,
+    /// e.g. `<i32>` of `Vec<i32>` or `<i32>` of `Vec::<i32>::new`
+    pub args:  Option<GenericArgs>,
+}
+/// e.g. `<i32, bool>`
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Debug, Clone, PartialEq)]
+pub struct GenericArgs {
+    pub args: Vec<Type>,
+    pub span: Span,
+}
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Debug, Clone, PartialEq)]
+pub struct Expr {
+    /// e.g. `#[cfg(a)]` of `[#[cfg(a)] 1]`, or of an expression statement
+    pub attrs: Vec<Attribute>,
+    pub kind:  ExprKind,
+    pub span:  Span,
+}
+/// see [Expr]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Debug, Clone, PartialEq)]
+pub enum ExprKind {
+    /// e.g. `1`, `"abc"`, `true`
+    Literal(Literal),
+    /// see [Path]
+    Path(Path),
+    /// e.g. `()`, `(a,)`, `(a, b)`
+    Tuple(Vec<Expr>),
+    /// e.g. `(a)`
+    Paren(Box<Expr>),
+    /// e.g. `[a, b]`
+    Array(Vec<Expr>),
+    /// e.g. `[a; 3]`
+    Repeat(Box<Expr>, Box<Expr>),
+    /// e.g. `Point { x: 1, y }`, `Point { x: 1, ..origin }`
+    Struct {
+        path:   Path,
+        fields: Vec<ExprField>,
+        rest:   Option<Box<Expr>>,
+    },
+    /// e.g. `-a`, `!a`, `*a`
+    Unary(UnOp, Box<Expr>),
+    /// e.g. `&a`, `&mut a`
+    Ref { mutable: bool, expr: Box<Expr> },
+    /// e.g. `box a`
+    Box(Box<Expr>),
+    /// e.g. `a + b`
+    Binary(Spanned<BinOp>, Box<Expr>, Box<Expr>),
+    /// e.g. `a = b`
+    Assign(Box<Expr>, Box<Expr>),
+    /// e.g. `a += b`
+    AssignOp(Spanned<BinOp>, Box<Expr>, Box<Expr>),
+    /// e.g. `a as i64`
+    Cast(Box<Expr>, Box<Type>),
+    /// e.g. `f(a, b)`
+    Call(Box<Expr>, Vec<Expr>),
+    /// e.g. `a.f(b)`
+    MethodCall { receiver: Box<Expr>, method: Ident, args: Vec<Expr> },
+    /// e.g. `a.b`, `a.0`
+    Field(Box<Expr>, Ident),
+    /// e.g. `a[b]`
+    Index(Box<Expr>, Box<Expr>),
+    /// e.g. `a?`
+    Try(Box<Expr>),
+    /// e.g. `a..b`, `..b`, `a..`, `..`, `a..=b`
+    Range {
+        start:     Option<Box<Expr>>,
+        end:       Option<Box<Expr>>,
+        inclusive: bool,
+    },
+    /// see [Block]
+    Block(Block),
+    /// e.g. `if a { b } else if c { d } else { e }`
+    If {
+        cond:        Box<Expr>,
+        then_branch: Block,
+        /// a [ExprKind::Block] or another [ExprKind::If]
+        else_branch: Option<Box<Expr>>,
+    },
+    /// e.g. `match a { b => c, _ => {} }`
+    Match { scrutinee: Box<Expr>, arms: Vec<Arm> },
+    /// e.g. `'outer: loop {}`
+    Loop { label: Option<Label>, body: Block },
+    /// e.g. `while a {}`
+    While { label: Option<Label>, cond: Box<Expr>, body: Block },
+    /// e.g. `for a in b {}`
+    For {
+        label: Option<Label>,
+        pat:   Pat,
+        iter:  Box<Expr>,
+        body:  Block,
+    },
+    /// e.g. `break`, `break 'outer a`
+    Break { label: Option<Label>, value: Option<Box<Expr>> },
+    /// e.g. `continue 'outer`
+    Continue { label: Option<Label> },
+    /// e.g. `return`, `return a`
+    Return(Option<Box<Expr>>),
+    /// e.g. `println!("{a} {}", b)`, see [FormatArgs]
+    Format(FormatArgs),
+    /// e.g. `square!(2)`, replaced by [expand](crate::expand)
+    MacCall(MacCall),
+    /// a statement with a syntax error
+    Err,
+}
+/// e.g. `x: 1` or the shorthand `x` in a [ExprKind::Struct]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Debug, Clone, PartialEq)]
+pub struct ExprField {
+    pub name: Ident,
+    /// a [ExprKind::Path] of `name` for the shorthand
+    pub expr: Expr,
+    pub span: Span,
+}
+/// a built-in format macro and its arguments, e.g. `println!("{a:>5} {}", b)`
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Debug, Clone, PartialEq)]
+pub struct FormatArgs {
+    pub mac:      FormatMacro,
+    /// the format string split at the placeholders
+    pub pieces:   Vec<FormatPiece>,
+    /// the arguments after the format string, `{}` and `{0}` refer to them
+    pub args:     Vec<Expr>,
+    /// the span of the format string
+    pub template: Span,
+}
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Debug, Clone, Copy, Hash, PartialEq, Eq)]
+pub enum FormatMacro {
+    /// `print!`
+    Print,
+    /// `println!`
+    Println,
+    /// `eprint!`
+    Eprint,
+    /// `eprintln!`
+    Eprintln,
+    /// `format!`, evaluates to the formatted string
+    Format,
+}
+/// see [FormatArgs]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Debug, Clone, PartialEq)]
+pub enum FormatPiece {
+    /// the text between placeholders, with `{{` and `}}` unescaped
+    Literal(String),
+    /// see [Placeholder]
+    Placeholder(Placeholder),
+}
+/// e.g. `{}`, `{0:?}`, `{*number}`, `{a.b:>8.3}`
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Debug, Clone, PartialEq)]
+pub struct Placeholder {
+    pub arg:  FormatArg,
+    pub spec: FormatSpec,
+    /// from `{` to `}` inside the format string
+    pub span: Span,
+}
+/// see [Placeholder]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Debug, Clone, PartialEq)]
+pub enum FormatArg {
+    /// an index into [FormatArgs::args], e.g. `{0}`, or the next one for `{}`
+    Index(usize),
+    /// an expression inside the braces, e.g. `{*number}`, unlike Rust
+    Expr(Box<Expr>),
+}
+/// e.g. `*^+#08.3x` of `{a:*^+#08.3x}`
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Debug, Clone, Copy, Hash, PartialEq, Eq)]
+pub struct FormatSpec {
+    /// ` ` by default
+    pub fill:         char,
+    pub align:        Option<FormatAlign>,
+    /// `+`
+    pub sign_plus:    bool,
+    /// `#`
+    pub alternate:    bool,
+    /// `0`
+    pub zero_pad:     bool,
+    pub width:        Option<usize>,
+    /// e.g. `.3`
+    pub precision:    Option<usize>,
+    pub format_trait: FormatTrait,
+}
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Debug, Clone, Copy, Hash, PartialEq, Eq)]
+pub enum FormatAlign {
+    /// `<`
+    Left,
+    /// `^`
+    Center,
+    /// `>`
+    Right,
+}
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Debug, Clone, Copy, Hash, PartialEq, Eq)]
+pub enum FormatTrait {
+    /// no suffix
+    Display,
+    /// `?`
+    Debug,
+    /// `x`
+    LowerHex,
+    /// `X`
+    UpperHex,
+    /// `b`
+    Binary,
+    /// `o`
+    Octal,
+    /// `e`
+    LowerExp,
+    /// `E`
+    UpperExp,
+}
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Debug, Clone, Copy, Hash, PartialEq, Eq)]
+pub enum UnOp {
+    /// `-`
+    Neg,
+    /// `!`
+    Not,
+    /// `*`
+    Deref,
+}
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Debug, Clone, Copy, Hash, PartialEq, Eq)]
+pub enum BinOp {
+    /// `+`
+    Add,
+    /// `-`
+    Sub,
+    /// `*`
+    Mul,
+    /// `/`
+    Div,
+    /// `%`
+    Rem,
+    /// `&&`
+    And,
+    /// `||`
+    Or,
+    /// `^`
+    BitXor,
+    /// `&`
+    BitAnd,
+    /// `|`
+    BitOr,
+    /// `<<`
+    Shl,
+    /// `>>`
+    Shr,
+    /// `==`
+    Eq,
+    /// `!=`
+    Ne,
+    /// `<`
+    Lt,
+    /// `<=`
+    Le,
+    /// `>`
+    Gt,
+    /// `>=`
+    Ge,
+}
+/// the items of a file or of an inline `mod a { ... }`
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Debug, Clone, PartialEq)]
+pub struct Module {
+    /// e.g. `//! doc` at the start of the module
+    pub docs:  Vec<Spanned<Comment>>,
+    /// e.g. `#![allow(unused)]` at the start of the module
+    pub attrs: Vec<Attribute>,
+    pub items: Vec<Item>,
+    pub span:  Span,
+}
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Debug, Clone, PartialEq)]
+pub struct Item {
+    pub id:    NodeId,
+    /// e.g. `/// doc` before the item
+    pub docs:  Vec<Spanned<Comment>>,
+    pub attrs: Vec<Attribute>,
+    pub vis:   Visibility,
+    pub kind:  ItemKind,
+    pub span:  Span,
+}
+/// see [Item]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Debug, Clone, PartialEq)]
+pub enum ItemKind {
+    /// see [FnDef]
+    Fn(FnDef),
+    /// e.g. `struct A;`, `struct A<T>(T);`, `struct A { a: i32 }`
+    Struct { name: Ident, generics: Generics, data: VariantData },
+    /// e.g. `enum A<T> { B, C(T), D { d: i32 }, E = 1 }`
+    Enum { name: Ident, generics: Generics, variants: Vec<Variant> },
+    /// e.g. `union A { a: i32, b: f32 }`
+    Union { name: Ident, generics: Generics, fields: Vec<FieldDef> },
+    /// e.g. `trait A { fn a(&self); fn b() {} }`, the items are
+    /// [ItemKind::Fn], [ItemKind::Const] and [ItemKind::TypeAlias]
+    Trait { name: Ident, items: Vec<Item> },
+    /// e.g. `impl A {}`, `impl<T: C> B for A<T> {}`
+    Impl {
+        generics: Generics,
+        of_trait: Option<Path>,
+        self_ty:  Type,
+        items:    Vec<Item>,
+    },
+    /// e.g. `mod a;`, `mod a { ... }`
+    Mod { name: Ident, module: Option<Module> },
+    /// see [UseTree]
+    Use(UseTree),
+    /// e.g. `const A: i32 = 1;`, `const A: i32;` in a trait
+    Const { name: Ident, ty: Type, value: Option<Expr> },
+    /// e.g. `static mut A: i32 = 1;`, `static A: i32;` in an extern block
+    Static {
+        name:    Ident,
+        mutable: bool,
+        ty:      Type,
+        value:   Option<Expr>,
+    },
+    /// e.g. `type A = i32;`, `type A;` in a trait
+    TypeAlias { name: Ident, ty: Option<Type> },
+    /// e.g. `extern "C" { fn a(); }`, the items are [ItemKind::Fn] and
+    /// [ItemKind::Static] without bodies
+    Extern { abi: Option<Spanned<String>>, items: Vec<Item> },
+    /// see [MacroDef]
+    Marco(MacroDef),
+    /// e.g. `items! { ... }`, `items!(...);`, replaced by
+    /// [expand](crate::expand)
+    MacCall(MacCall),
+    /// an item with a syntax error
+    Err,
+}
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Debug, Clone, Copy, Hash, PartialEq, Eq)]
+pub enum Visibility {
+    /// no visibility keyword
+    Inherited,
+    /// `pub`
+    Public(Span),
+    /// `priv`
+    Private(Span),
+}
+/// e.g. `fn a(&self, b: i32) -> i32 { b }`
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Debug, Clone, PartialEq)]
+pub struct FnDef {
+    pub name:     Ident,
+    pub generics: Generics,
+    pub sig:      FnSig,
+    /// [None] for `fn a();`
+    pub body:     Option<Block>,
+}
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Debug, Clone, PartialEq)]
+pub struct FnSig {
+    pub self_param: Option<SelfParam>,
+    pub params:     Vec<Param>,
+    /// [None] for `()`
+    pub ret:        Option<Type>,
+    pub span:       Span,
+}
+/// e.g. `self`, `mut self`, `&self`, `&mut self`
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Debug, Clone, Copy, Hash, PartialEq, Eq)]
+pub struct SelfParam {
+    /// `&self` or `&mut self`
+    pub by_ref:  bool,
+    pub mutable: bool,
+    pub span:    Span,
+}
+/// e.g. `a: i32`
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Debug, Clone, PartialEq)]
+pub struct Param {
+    pub pat:  Pat,
+    pub ty:   Type,
+    pub span: Span,
+}
+/// e.g. `<T: A + B, U>` and `where U: C`
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct Generics {
+    pub params:       Vec<GenericParam>,
+    /// after `where`
+    pub where_clause: Vec<WherePredicate>,
+    /// the `<...>`, empty after the name without parameters
+    pub span:         Span,
+}
+/// e.g. `T: A + B`
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Debug, Clone, PartialEq)]
+pub struct GenericParam {
+    pub id:     NodeId,
+    pub name:   Ident,
+    /// the traits
+    pub bounds: Vec<Path>,
+    pub span:   Span,
+}
+/// e.g. `T: A + B` after `where`
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Debug, Clone, PartialEq)]
+pub struct WherePredicate {
+    pub ty:     Type,
+    /// the traits
+    pub bounds: Vec<Path>,
+    pub span:   Span,
+}
+/// the fields of a struct or an enum variant
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Debug, Clone, PartialEq)]
+pub enum VariantData {
+    /// e.g. `{ a: i32 }`
+    Struct(Vec<FieldDef>),
+    /// e.g. `(i32)`
+    Tuple(Vec<FieldDef>),
+    /// no fields
+    Unit,
+}
+/// e.g. `pub a: i32`, or `i32` in a tuple struct
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Debug, Clone, PartialEq)]
+pub struct FieldDef {
+    pub docs:  Vec<Spanned<Comment>>,
+    pub attrs: Vec<Attribute>,
+    pub vis:   Visibility,
+    /// [None] in a tuple struct
+    pub name:  Option<Ident>,
+    pub ty:    Type,
+    pub span:  Span,
+}
+/// e.g. `A`, `B(i32)`, `C { c: i32 }`, `D = 1`
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Debug, Clone, PartialEq)]
+pub struct Variant {
+    pub docs:         Vec<Spanned<Comment>>,
+    pub attrs:        Vec<Attribute>,
+    pub name:         Ident,
+    pub data:         VariantData,
+    pub discriminant: Option<Expr>,
+    pub span:         Span,
+}
+/// e.g. `a::b`, `a::b as c`, `a::*`, `a::{b, c::*}`
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Debug, Clone, PartialEq)]
+pub struct UseTree {
+    /// empty for `{a, b}` and `*`
+    pub prefix: Path,
+    pub kind:   UseTreeKind,
+    pub span:   Span,
+}
+/// see [UseTree]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Debug, Clone, PartialEq)]
+pub enum UseTreeKind {
+    /// e.g. `a::b`, `a::b as c`
+    Simple(Option<Ident>),
+    /// e.g. `a::*`
+    Glob,
+    /// e.g. `a::{b, c}`
+    Nested(Vec<UseTree>),
+}
+/// e.g. `marco square { ($x:expr) => { $x * $x }; }`
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Debug, Clone, PartialEq)]
+pub struct MacroDef {
+    pub name:  Ident,
+    pub rules: Vec<MacroRule>,
+}
+/// e.g. `($x:expr) => { $x * $x }`
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Debug, Clone, PartialEq)]
+pub struct MacroRule {
+    /// inside the delimiters of the left side
+    pub matcher: Vec<TokenTree>,
+    /// inside the delimiters of the right side
+    pub body:    Vec<TokenTree>,
+    pub span:    Span,
+}
+/// e.g. `square!(2)`, `items! { ... }`
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Debug, Clone, PartialEq)]
+pub struct MacCall {
+    pub path:   Path,
+    /// a brace-delimited call is a statement without `;`
+    pub delim:  Delimiter,
+    /// inside the delimiters
+    pub tokens: Vec<TokenTree>,
+    /// the closing delimiter
+    pub close:  Span,
+    pub span:   Span,
+}
+/// a token, or tokens in matching delimiters
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Debug, Clone, PartialEq)]
+pub enum TokenTree {
+    /// never a delimiter or [Token::EOF]
+    Token(Spanned<Token>),
+    /// e.g. `(a, [b])`
+    Delimited {
+        delim: Delimiter,
+        open:  Span,
+        close: Span,
+        tts:   Vec<TokenTree>,
+    },
+}
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Debug, Clone, Copy, Hash, PartialEq, Eq)]
+pub enum Delimiter {
+    /// `( ... )`
+    Paren,
+    /// `[ ... ]`
+    Bracket,
+    /// `{ ... }`
+    Brace,
+}
+/// e.g. `#[inline]`, `#[cfg(not(test))]`, `#![allow(unused)]`, see
+/// [attr](crate::attr) for the built-in ones
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Debug, Clone, PartialEq)]
+pub struct Attribute {
+    pub style: AttrStyle,
+    pub path:  Path,
+    pub args:  AttrArgs,
+    pub span:  Span,
+}
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Debug, Clone, Copy, Hash, PartialEq, Eq)]
+pub enum AttrStyle {
+    /// `#[...]`, applies to what follows
+    Outer,
+    /// `#![...]`, applies to the enclosing module
+    Inner,
+}
+/// see [Attribute]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Debug, Clone, PartialEq)]
+pub enum AttrArgs {
+    /// e.g. `#[test]`
+    Empty,
+    /// e.g. `#[derive(Clone, Copy)]`
+    Delimited { delim: Delimiter, tokens: Vec<TokenTree>, span: Span },
+    /// e.g. `#[deprecated = "use b"]`
+    Eq(Spanned<Literal>),
+}
+/// e.g. `{ a; b }`, the last statement is the value if it is a
+/// [StmtKind::Expr]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Debug, Clone, PartialEq)]
+pub struct Block {
+    pub stmts: Vec<Stmt>,
+    pub span:  Span,
+}
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Debug, Clone, PartialEq)]
+pub struct Stmt {
+    pub kind: StmtKind,
+    pub span: Span,
+}
+/// see [Stmt]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Debug, Clone, PartialEq)]
+pub enum StmtKind {
+    /// e.g. `a` at the end of a block, or `if a {}` without `;`
+    Expr(Expr),
+    /// e.g. `a;`
+    Semi(Expr),
+    /// `;`
+    Empty,
+    /// e.g. `fn a() {}` in a block
+    Item(Box<Item>),
+    /// see [Local]
+    Let(Box<Local>),
+}
+/// e.g. `let a;`, `let a: i32 = 1;`, `let Some(a) = b else { return };`
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Debug, Clone, PartialEq)]
+pub struct Local {
+    pub attrs: Vec<Attribute>,
+    pub pat:   Pat,
+    pub ty:    Option<Type>,
+    pub init:  Option<Expr>,
+    /// the `else` block of `let ... else`
+    pub els:   Option<Block>,
+    pub span:  Span,
+}
+/// e.g. `a => b,`, `a if c => { b }`
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Debug, Clone, PartialEq)]
+pub struct Arm {
+    pub attrs: Vec<Attribute>,
+    pub pat:   Pat,
+    pub guard: Option<Expr>,
+    pub body:  Expr,
+    pub span:  Span,
+}
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Debug, Clone, PartialEq)]
+pub struct Pat {
+    pub id:   NodeId,
+    pub kind: PatKind,
+    pub span: Span,
+}
+/// see [Pat]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Debug, Clone, PartialEq)]
+pub enum PatKind {
+    /// `_`
+    Wild,
+    /// `..` in a tuple or slice pattern
+    Rest,
+    /// e.g. `a`, `ref mut a`, `a @ 1..=5`
+    Ident {
+        by_ref:  bool,
+        mutable: bool,
+        name:    Ident,
+        sub:     Option<Box<Pat>>,
+    },
+    /// e.g. `1`, `-1`, `"abc"`
+    Literal(Literal),
+    /// e.g. `None`, `a::B`
+    Path(Path),
+    /// e.g. `(a, b)`, `()`
+    Tuple(Vec<Pat>),
+    /// e.g. `Some(a)`
+    TupleStruct(Path, Vec<Pat>),
+    /// e.g. `Point { x, y: 0, .. }`
+    Struct {
+        path:   Path,
+        fields: Vec<PatField>,
+        /// ends with `..`
+        rest:   bool,
+    },
+    /// e.g. `[a, .., b]`
+    Slice(Vec<Pat>),
+    /// e.g. `1..=5`, `'a'..'z'`, `..=5`, `1..`, the ends are
+    /// [PatKind::Literal] or [PatKind::Path]
+    Range {
+        start:     Option<Box<Pat>>,
+        end:       Option<Box<Pat>>,
+        inclusive: bool,
+    },
+    /// e.g. `&a`, `&mut a`
+    Ref { mutable: bool, pat: Box<Pat> },
+    /// e.g. `A | B`
+    Or(Vec<Pat>),
+}
+/// e.g. `x: 0` or the shorthand `ref x` in a [PatKind::Struct]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Debug, Clone, PartialEq)]
+pub struct PatField {
+    pub name: Ident,
+    /// a [PatKind::Ident] of `name` for the shorthand
+    pub pat:  Pat,
+    pub span: Span,
+}
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Debug, Clone, PartialEq)]
+pub struct Type {
+    pub kind: TypeKind,
+    pub span: Span,
+}
+/// see [Type], and the type table in `lib.md`
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Debug, Clone, PartialEq)]
+pub enum TypeKind {
+    /// e.g. `i32`, `a::B`, `Vec<i32>`
+    Path(Path),
+    /// e.g. `()`, `(i32,)`, `(i32, bool)`
+    Tuple(Vec<Type>),
+    /// e.g. `*const i32`, `*mut i32`
+    Ptr { mutable: bool, ty: Box<Type> },
+    /// e.g. `&i32`, `&mut i32`
+    Ref { mutable: bool, ty: Box<Type> },
+    /// e.g. `[i32]`
+    Slice(Box<Type>),
+    /// e.g. `[i32; 3]`
+    Array(Box<Type>, Box<Expr>),
+    /// e.g. `dyn A + B`
+    TraitObject(Vec<Path>),
+    /// e.g. `impl A + B`
+    ImplTrait(Vec<Path>),
+    /// e.g. `i32.Box`, the owning pointer created by `box 1`
+    Box(Box<Type>),
+    /// `_`
+    Infer,
+}
+impl NodeId {
+    /// before [assign_node_ids](crate::semantic::assign_node_ids)
+    pub const DUMMY: NodeId = NodeId(u32::MAX);
+
+    pub fn new(index: usize) -> Self { Self(index as u32) }
+
+    pub fn index(&self) -> usize { self.0 as usize }
+}
+impl Pat {
+    pub fn new(kind: PatKind, span: Span) -> Self {
+        Self { id: NodeId::DUMMY, kind, span }
+    }
+}
+impl Expr {
+    pub fn new(kind: ExprKind, span: Span) -> Self {
+        Self { attrs: Vec::new(), kind, span }
+    }
+
+    /// ends a statement without `;`, e.g. `if a {}`, `loop {}`
+    pub fn is_block_like(&self) -> bool {
+        matches!(
+            self.kind,
+            ExprKind::MacCall(MacCall { delim: Delimiter::Brace, .. })
+                | ExprKind::Block(_)
+                | ExprKind::If { .. }
+                | ExprKind::Match { .. }
+                | ExprKind::Loop { .. }
+                | ExprKind::While { .. }
+                | ExprKind::For { .. }
+        )
+    }
+}
+impl Item {
+    /// the name of the item, [None] for `impl`, `use` and `extern` blocks
+    pub fn name(&self) -> Option<&Ident> {
+        match &self.kind {
+            ItemKind::Fn(FnDef { name, .. })
+            | ItemKind::Struct { name, .. }
+            | ItemKind::Enum { name, .. }
+            | ItemKind::Union { name, .. }
+            | ItemKind::Trait { name, .. }
+            | ItemKind::Mod { name, .. }
+            | ItemKind::Const { name, .. }
+            | ItemKind::Static { name, .. }
+            | ItemKind::TypeAlias { name, .. } => Some(name),
+            ItemKind::Marco(MacroDef { name, .. }) => Some(name),
+            ItemKind::Impl { .. }
+            | ItemKind::Use(_)
+            | ItemKind::Extern { .. }
+            | ItemKind::MacCall(_)
+            | ItemKind::Err => None,
+        }
+    }
+
+    /// the type parameters of a fn, struct, enum, union or impl
+    pub fn generics(&self) -> Option<&Generics> {
+        match &self.kind {
+            ItemKind::Fn(FnDef { generics, .. })
+            | ItemKind::Struct { generics, .. }
+            | ItemKind::Enum { generics, .. }
+            | ItemKind::Union { generics, .. }
+            | ItemKind::Impl { generics, .. } => Some(generics),
+            _ => None,
+        }
+    }
+}
+impl TokenTree {
+    /// from the first to the last token
+    pub fn span(&self) -> Span {
+        match self {
+            TokenTree::Token(token) => token.span,
+            TokenTree::Delimited { open, close, .. } => open.to(*close),
+        }
+    }
+
+    /// the tokens of `tts` with the delimiters, e.g. for a [Parser]
+    ///
+    /// [Parser]: crate::parser::Parser
+    pub fn flatten(tts: &[TokenTree]) -> Vec<Spanned<Token>> {
+        let mut tokens = Vec::new();
+        for tt in tts {
+            tt.flatten_into(&mut tokens);
+        }
+        tokens
+    }
+
+    fn flatten_into(&self, tokens: &mut Vec<Spanned<Token>>) {
+        match self {
+            TokenTree::Token(token) => tokens.push(token.clone()),
+            TokenTree::Delimited { delim, open, close, tts } => {
+                let (open_kind, close_kind) = delim.operators();
+                tokens.push(Spanned::new(
+                    Token::Operator(Operator::new(open_kind)),
+                    *open,
+                ));
+                for tt in tts {
+                    tt.flatten_into(tokens);
+                }
+                tokens.push(Spanned::new(
+                    Token::Operator(Operator::new(close_kind)),
+                    *close,
+                ));
+            }
+        }
+    }
+}
+impl Delimiter {
+    /// e.g. `(` and `)`
+    pub fn operators(&self) -> (OperatorKind, OperatorKind) {
+        match self {
+            Delimiter::Paren => {
+                (OperatorKind::OpenParen, OperatorKind::CloseParen)
+            }
+            Delimiter::Bracket => {
+                (OperatorKind::OpenBracket, OperatorKind::CloseBracket)
+            }
+            Delimiter::Brace => {
+                (OperatorKind::OpenBrace, OperatorKind::CloseBrace)
+            }
+        }
+    }
+
+    /// the delimiter opened by `kind`
+    pub fn from_open(kind: OperatorKind) -> Option<Self> {
+        match kind {
+            OperatorKind::OpenParen => Some(Delimiter::Paren),
+            OperatorKind::OpenBracket => Some(Delimiter::Bracket),
+            OperatorKind::OpenBrace => Some(Delimiter::Brace),
+            _ => None,
+        }
+    }
+
+    /// the delimiter closed by `kind`
+    pub fn from_close(kind: OperatorKind) -> Option<Self> {
+        match kind {
+            OperatorKind::CloseParen => Some(Delimiter::Paren),
+            OperatorKind::CloseBracket => Some(Delimiter::Bracket),
+            OperatorKind::CloseBrace => Some(Delimiter::Brace),
+            _ => None,
+        }
+    }
+}
+impl FormatMacro {
+    /// e.g. `println` for [FormatMacro::Println]
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "print" => Some(FormatMacro::Print),
+            "println" => Some(FormatMacro::Println),
+            "eprint" => Some(FormatMacro::Eprint),
+            "eprintln" => Some(FormatMacro::Eprintln),
+            "format" => Some(FormatMacro::Format),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            FormatMacro::Print => "print",
+            FormatMacro::Println => "println",
+            FormatMacro::Eprint => "eprint",
+            FormatMacro::Eprintln => "eprintln",
+            FormatMacro::Format => "format",
+        }
+    }
+}
+impl Default for FormatSpec {
+    fn default() -> Self {
+        Self {
+            fill:         ' ',
+            align:        None,
+            sign_plus:    false,
+            alternate:    false,
+            zero_pad:     false,
+            width:        None,
+            precision:    None,
+            format_trait: FormatTrait::Display,
+        }
+    }
+}
+impl FormatTrait {
+    /// e.g. `?` for [FormatTrait::Debug]
+    pub fn from_suffix(suffix: &str) -> Option<Self> {
+        match suffix {
+            "" => Some(FormatTrait::Display),
+            "?" => Some(FormatTrait::Debug),
+            "x" => Some(FormatTrait::LowerHex),
+            "X" => Some(FormatTrait::UpperHex),
+            "b" => Some(FormatTrait::Binary),
+            "o" => Some(FormatTrait::Octal),
+            "e" => Some(FormatTrait::LowerExp),
+            "E" => Some(FormatTrait::UpperExp),
+            _ => None,
+        }
+    }
+
+    /// e.g. `Debug`
+    pub fn name(&self) -> &'static str {
+        match self {
+            FormatTrait::Display => "Display",
+            FormatTrait::Debug => "Debug",
+            FormatTrait::LowerHex => "LowerHex",
+            FormatTrait::UpperHex => "UpperHex",
+            FormatTrait::Binary => "Binary",
+            FormatTrait::Octal => "Octal",
+            FormatTrait::LowerExp => "LowerExp",
+            FormatTrait::UpperExp => "UpperExp",
+        }
+    }
+}
+impl Path {
+    pub fn new(segments: Vec<PathSegment>, span: Span) -> Self {
+        Self { id: NodeId::DUMMY, segments, span }
+    }
+
+    /// e.g. `a` but not `a::b` or `r#a`
+    pub fn as_ident(&self) -> Option<&Ident> {
+        match self.segments.as_slice() {
+            [segment] if segment.args.is_none() => Some(&segment.ident),
+            _ => None,
+        }
+    }
+}
+impl BinOp {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            BinOp::Add => "+",
+            BinOp::Sub => "-",
+            BinOp::Mul => "*",
+            BinOp::Div => "/",
+            BinOp::Rem => "%",
+            BinOp::And => "&&",
+            BinOp::Or => "||",
+            BinOp::BitXor => "^",
+            BinOp::BitAnd => "&",
+            BinOp::BitOr => "|",
+            BinOp::Shl => "<<",
+            BinOp::Shr => ">>",
+            BinOp::Eq => "==",
+            BinOp::Ne => "!=",
+            BinOp::Lt => "<",
+            BinOp::Le => "<=",
+            BinOp::Gt => ">",
+            BinOp::Ge => ">=",
+        }
+    }
+
+    /// e.g. `==`, `<`
+    pub fn is_comparison(&self) -> bool {
+        matches!(
+            self,
+            BinOp::Eq
+                | BinOp::Ne
+                | BinOp::Lt
+                | BinOp::Le
+                | BinOp::Gt
+                | BinOp::Ge
+        )
+    }
+}
+impl UnOp {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            UnOp::Neg => "-",
+            UnOp::Not => "!",
+            UnOp::Deref => "*",
+        }
+    }
+}
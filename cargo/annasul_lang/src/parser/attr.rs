@@ -0,0 +1,102 @@
+// Copyright (c) 2025 air (https://yuanair.github.io).
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published by
+// the Free Software Foundation, version 3 of the License only.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+use crate::{
+    lexer::token::{OperatorKind, Token},
+    parser::{
+        Parser,
+        PathStyle,
+        ast::{AttrArgs, AttrStyle, Attribute, Delimiter, TokenTree},
+    },
+    utils::{
+        error::{Error, Result},
+        span::Spanned,
+    },
+};
+impl Parser {
+    /// e.g. `#[inline] #[cfg(test)]` before an item, a field, a statement
+    /// or an expression
+    pub(crate) fn parse_outer_attrs(&mut self) -> Result<Vec<Attribute>> {
+        let mut attrs = Vec::new();
+        while self.is_op(OperatorKind::Pound) {
+            if self.is_inner_attr_start() {
+                return Err(Error::new(
+                    self.span().to(self.tokens[self.pos + 1].span),
+                    "an inner attribute is not permitted in this context",
+                ));
+            }
+            attrs.push(self.parse_attr(AttrStyle::Outer)?);
+        }
+        Ok(attrs)
+    }
+
+    /// e.g. `#![allow(unused)]` at the start of a module, an erroneous
+    /// attribute is skipped
+    pub(crate) fn parse_inner_attrs(&mut self) -> Vec<Attribute> {
+        let mut attrs = Vec::new();
+        while self.is_inner_attr_start() {
+            let start = self.pos;
+            match self.parse_attr(AttrStyle::Inner) {
+                Ok(attr) => attrs.push(attr),
+                Err(error) => self.recover(error, start, Self::is_item_start),
+            }
+        }
+        attrs
+    }
+
+    /// the next tokens are `#!`
+    fn is_inner_attr_start(&self) -> bool {
+        self.is_op(OperatorKind::Pound)
+            && matches!(
+                self.peek_nth(1),
+                Token::Operator(op) if op.kind() == OperatorKind::Not
+            )
+    }
+
+    /// e.g. `#[test]`, `#[derive(Clone)]`, `#[deprecated = "use b"]`
+    fn parse_attr(&mut self, style: AttrStyle) -> Result<Attribute> {
+        let lo = self.expect_op(OperatorKind::Pound)?;
+        if style == AttrStyle::Inner {
+            self.expect_op(OperatorKind::Not)?;
+        }
+        self.expect_op(OperatorKind::OpenBracket)?;
+        let path = self.parse_path(PathStyle::Expr)?;
+        let args = match self.peek() {
+            Token::Operator(op)
+                if Delimiter::from_open(op.kind()).is_some() =>
+            {
+                match self.parse_token_tree()? {
+                    TokenTree::Delimited { delim, tts, open, close } => {
+                        AttrArgs::Delimited {
+                            delim,
+                            tokens: tts,
+                            span: open.to(close),
+                        }
+                    }
+                    TokenTree::Token(_) => unreachable!(),
+                }
+            }
+            Token::Operator(op) if op.kind() == OperatorKind::Eq => {
+                self.bump();
+                match self.peek().clone() {
+                    Token::Literal(literal) => {
+                        AttrArgs::Eq(Spanned::new(literal, self.bump().span))
+                    }
+                    _ => return Err(self.unexpected("literal")),
+                }
+            }
+            _ => AttrArgs::Empty,
+        };
+        self.expect_op(OperatorKind::CloseBracket)?;
+        Ok(Attribute { style, path, args, span: lo.to(self.prev_span()) })
+    }
+}
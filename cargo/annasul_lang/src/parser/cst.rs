@@ -0,0 +1,692 @@
+// Copyright (c) 2025 air (https://yuanair.github.io).
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published by
+// the Free Software Foundation, version 3 of the License only.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+mod build;
+pub mod view;
+use std::{fmt::Display, ops::Range, rc::Rc};
+
+pub use build::build;
+
+use crate::{
+    lexer::scanner::{AnnasulScanner, Scanner},
+    parser::{Parser, cst::view::SourceFile},
+    utils::error::Error,
+};
+/// the kind of a [GreenToken] or a [GreenNode]
+#[derive(Debug, Clone, Copy, Hash, Ord, PartialOrd, PartialEq, Eq)]
+pub enum SyntaxKind {
+    /// spaces, tabs and newlines between tokens
+    Whitespace,
+    /// see [Token::Comment](crate::lexer::token::Token::Comment)
+    Comment,
+    /// e.g. `foo`, `r#match`
+    Ident,
+    /// e.g. `fn`, `true`
+    Keyword,
+    /// e.g. `'outer`
+    Lifetime,
+    /// e.g. `1`, `"abc"`
+    Literal,
+    /// e.g. `+`, `::`, `{`
+    Punct,
+    /// text the scanner could not turn into a token
+    Error,
+    /// the whole file
+    SourceFile,
+    /// e.g. `fn a() {}`
+    FnDef,
+    /// e.g. `struct A;`
+    StructDef,
+    /// e.g. `enum A {}`
+    EnumDef,
+    /// e.g. `union A {}`
+    UnionDef,
+    /// e.g. `trait A {}`
+    TraitDef,
+    /// e.g. `impl A {}`
+    ImplDef,
+    /// e.g. `mod a;`
+    ModDef,
+    /// e.g. `use a::b;`
+    UseItem,
+    /// e.g. `const A: i32 = 1;`
+    ConstDef,
+    /// e.g. `static A: i32 = 1;`
+    StaticDef,
+    /// e.g. `type A = i32;`
+    TypeAliasDef,
+    /// e.g. `extern "C" {}`
+    ExternBlock,
+    /// e.g. `marco a { () => {} }`, the rules are tokens
+    MacroDef,
+    /// e.g. `a! {}`, the arguments are tokens
+    MacroCall,
+    /// an item with a syntax error
+    ErrorItem,
+    /// `pub` or `priv`
+    Visibility,
+    /// e.g. `#[inline]`, `#![allow(unused)]`
+    Attr,
+    /// the identifier that an item, field, variant or binding defines
+    Name,
+    /// an identifier that refers to a definition, e.g. in a path
+    NameRef,
+    /// e.g. `&self`
+    SelfParam,
+    /// e.g. `a: i32`
+    Param,
+    /// e.g. `pub a: i32`, or `i32` in a tuple struct
+    FieldDef,
+    /// e.g. `B(i32)` in an enum
+    Variant,
+    /// e.g. `a::{b, c}`
+    UseTree,
+    /// e.g. `a::b::<i32>`
+    Path,
+    /// e.g. `b::<i32>`
+    PathSegment,
+    /// e.g. `<i32>`
+    GenericArgs,
+    /// e.g. `<T: A, U>`
+    GenericParams,
+    /// e.g. `T: A`
+    GenericParam,
+    /// e.g. `T: A` after `where`
+    WherePred,
+    /// e.g. `{ a; b }`
+    Block,
+    /// e.g. `let a = 1;`
+    LetStmt,
+    /// e.g. `a;`, or `a` at the end of a block
+    ExprStmt,
+    /// e.g. `1`
+    LiteralExpr,
+    /// e.g. `a::b`
+    PathExpr,
+    /// e.g. `(a, b)`
+    TupleExpr,
+    /// e.g. `(a)`
+    ParenExpr,
+    /// e.g. `[a, b]`
+    ArrayExpr,
+    /// e.g. `[a; 3]`
+    RepeatExpr,
+    /// e.g. `A { a: 1 }`
+    StructExpr,
+    /// e.g. `a: 1` in a [SyntaxKind::StructExpr]
+    ExprField,
+    /// e.g. `-a`
+    UnaryExpr,
+    /// e.g. `&a`
+    RefExpr,
+    /// e.g. `box a`
+    BoxExpr,
+    /// e.g. `a + b`
+    BinaryExpr,
+    /// e.g. `a = b`, `a += b`
+    AssignExpr,
+    /// e.g. `a as i64`
+    CastExpr,
+    /// e.g. `f(a)`
+    CallExpr,
+    /// e.g. `a.f(b)`
+    MethodCallExpr,
+    /// e.g. `a.b`
+    FieldExpr,
+    /// e.g. `a[b]`
+    IndexExpr,
+    /// e.g. `a?`
+    TryExpr,
+    /// e.g. `a..b`
+    RangeExpr,
+    /// e.g. `if a {}`
+    IfExpr,
+    /// e.g. `match a {}`
+    MatchExpr,
+    /// e.g. `a => b,`
+    MatchArm,
+    /// e.g. `loop {}`
+    LoopExpr,
+    /// e.g. `while a {}`
+    WhileExpr,
+    /// e.g. `for a in b {}`
+    ForExpr,
+    /// e.g. `break 'outer a`
+    BreakExpr,
+    /// e.g. `continue`
+    ContinueExpr,
+    /// e.g. `return a`
+    ReturnExpr,
+    /// e.g. `println!("{a}", b)`, the placeholders are inside the string
+    /// literal token
+    FormatExpr,
+    /// e.g. `a!(1)`, the arguments are tokens
+    MacroCallExpr,
+    /// an expression or statement with a syntax error
+    ErrorExpr,
+    /// `_`
+    WildPat,
+    /// `..`
+    RestPat,
+    /// e.g. `ref mut a`
+    IdentPat,
+    /// e.g. `-1`
+    LiteralPat,
+    /// e.g. `None`
+    PathPat,
+    /// e.g. `(a, b)`
+    TuplePat,
+    /// e.g. `Some(a)`
+    TupleStructPat,
+    /// e.g. `A { a, .. }`
+    StructPat,
+    /// e.g. `a: 0` in a [SyntaxKind::StructPat]
+    PatField,
+    /// e.g. `[a, .., b]`
+    SlicePat,
+    /// e.g. `1..=5`
+    RangePat,
+    /// e.g. `&a`
+    RefPat,
+    /// e.g. `A | B`
+    OrPat,
+    /// e.g. `Vec<i32>`
+    PathType,
+    /// e.g. `(i32, bool)`
+    TupleType,
+    /// e.g. `*const i32`
+    PtrType,
+    /// e.g. `&i32`
+    RefType,
+    /// e.g. `[i32]`
+    SliceType,
+    /// e.g. `[i32; 3]`
+    ArrayType,
+    /// e.g. `dyn A`
+    DynTraitType,
+    /// e.g. `impl A`
+    ImplTraitType,
+    /// e.g. `i32.Box`
+    BoxType,
+    /// `_`
+    InferType,
+}
+/// a leaf of the green tree, immutable and shareable
+#[derive(Debug, Clone, Hash, PartialEq, Eq)]
+pub struct GreenToken {
+    kind: SyntaxKind,
+    text: String,
+}
+/// an inner node of the green tree, immutable and shareable, it only knows
+/// its length so equal subtrees can be shared between files and edits
+#[derive(Debug, Clone, Hash, PartialEq, Eq)]
+pub struct GreenNode {
+    kind:     SyntaxKind,
+    len:      usize,
+    children: Vec<GreenElement>,
+}
+#[derive(Debug, Clone, Hash, PartialEq, Eq)]
+pub enum GreenElement {
+    Node(Rc<GreenNode>),
+    Token(Rc<GreenToken>),
+}
+/// a [GreenNode] with its absolute offset and parent, created on demand
+/// while walking down from [SyntaxNode::new_root]
+#[derive(Clone)]
+pub struct SyntaxNode(Rc<NodeData>);
+struct NodeData {
+    green:  Rc<GreenNode>,
+    parent: Option<SyntaxNode>,
+    /// index in the children of the parent
+    index:  usize,
+    offset: usize,
+}
+/// a [GreenToken] with its absolute offset and parent
+#[derive(Clone)]
+pub struct SyntaxToken {
+    green:  Rc<GreenToken>,
+    parent: SyntaxNode,
+    /// index in the children of the parent
+    index:  usize,
+    offset: usize,
+}
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SyntaxElement {
+    Node(SyntaxNode),
+    Token(SyntaxToken),
+}
+/// scans and parses `source` into a lossless tree, the errors are the same
+/// as those of [AnnasulScanner] and [Parser]
+pub fn parse(source: &str) -> (SourceFile, Vec<Error>) {
+    let (tokens, mut errors) =
+        AnnasulScanner::new(source.as_bytes()).tokenize();
+    let mut parser = Parser::new(tokens.clone()).with_source(source);
+    let module = parser.parse_module();
+    errors.extend(parser.take_errors());
+    let root = SyntaxNode::new_root(build(source, &tokens, &module));
+    (SourceFile::new(root), errors)
+}
+impl SyntaxKind {
+    /// whitespace and comments, see [SyntaxToken::is_trivia]
+    pub fn is_trivia(self) -> bool {
+        matches!(self, SyntaxKind::Whitespace | SyntaxKind::Comment)
+    }
+
+    pub fn is_token(self) -> bool { self <= SyntaxKind::Error }
+
+    pub fn is_item(self) -> bool {
+        (SyntaxKind::FnDef..=SyntaxKind::ErrorItem).contains(&self)
+    }
+
+    /// [SyntaxKind::Block] is also an expression
+    pub fn is_expr(self) -> bool {
+        self == SyntaxKind::Block
+            || (SyntaxKind::LiteralExpr..=SyntaxKind::ErrorExpr).contains(&self)
+                && self != SyntaxKind::ExprField
+                && self != SyntaxKind::MatchArm
+    }
+
+    pub fn is_pat(self) -> bool {
+        (SyntaxKind::WildPat..=SyntaxKind::OrPat).contains(&self)
+            && self != SyntaxKind::PatField
+    }
+
+    pub fn is_type(self) -> bool {
+        (SyntaxKind::PathType..=SyntaxKind::InferType).contains(&self)
+    }
+}
+impl GreenToken {
+    pub fn new(kind: SyntaxKind, text: impl Into<String>) -> Self {
+        Self { kind, text: text.into() }
+    }
+
+    pub fn kind(&self) -> SyntaxKind { self.kind }
+
+    pub fn text(&self) -> &str { &self.text }
+}
+impl GreenNode {
+    pub fn new(kind: SyntaxKind, children: Vec<GreenElement>) -> Self {
+        let len = children.iter().map(GreenElement::len).sum();
+        Self { kind, len, children }
+    }
+
+    pub fn kind(&self) -> SyntaxKind { self.kind }
+
+    /// the length of the text in bytes
+    pub fn len(&self) -> usize { self.len }
+
+    pub fn is_empty(&self) -> bool { self.len == 0 }
+
+    pub fn children(&self) -> &[GreenElement] { &self.children }
+
+    /// a copy of `self` with the `index`th child replaced
+    pub fn replace_child(&self, index: usize, child: GreenElement) -> Self {
+        let mut children = self.children.clone();
+        children[index] = child;
+        Self::new(self.kind, children)
+    }
+}
+impl GreenElement {
+    pub fn kind(&self) -> SyntaxKind {
+        match self {
+            GreenElement::Node(node) => node.kind,
+            GreenElement::Token(token) => token.kind,
+        }
+    }
+
+    /// the length of the text in bytes
+    pub fn len(&self) -> usize {
+        match self {
+            GreenElement::Node(node) => node.len,
+            GreenElement::Token(token) => token.text.len(),
+        }
+    }
+
+    pub fn is_empty(&self) -> bool { self.len() == 0 }
+}
+impl SyntaxNode {
+    pub fn new_root(green: Rc<GreenNode>) -> Self {
+        Self(Rc::new(NodeData { green, parent: None, index: 0, offset: 0 }))
+    }
+
+    pub fn kind(&self) -> SyntaxKind { self.0.green.kind }
+
+    pub fn green(&self) -> &Rc<GreenNode> { &self.0.green }
+
+    /// the byte range in the source text
+    pub fn text_range(&self) -> Range<usize> {
+        self.0.offset..self.0.offset + self.0.green.len
+    }
+
+    pub fn parent(&self) -> Option<&SyntaxNode> { self.0.parent.as_ref() }
+
+    /// `self` and its parents up to the root
+    pub fn ancestors(&self) -> impl Iterator<Item = SyntaxNode> {
+        std::iter::successors(Some(self.clone()), |node| node.parent().cloned())
+    }
+
+    pub fn root(&self) -> SyntaxNode {
+        self.ancestors().last().expect("`ancestors` yields `self`")
+    }
+
+    pub fn children_with_tokens(&self) -> Vec<SyntaxElement> {
+        let mut offset = self.0.offset;
+        let mut elements = Vec::with_capacity(self.0.green.children.len());
+        for (index, child) in self.0.green.children.iter().enumerate() {
+            elements.push(match child {
+                GreenElement::Node(green) => {
+                    SyntaxElement::Node(SyntaxNode(Rc::new(NodeData {
+                        green: green.clone(),
+                        parent: Some(self.clone()),
+                        index,
+                        offset,
+                    })))
+                }
+                GreenElement::Token(green) => {
+                    SyntaxElement::Token(SyntaxToken {
+                        green: green.clone(),
+                        parent: self.clone(),
+                        index,
+                        offset,
+                    })
+                }
+            });
+            offset += child.len();
+        }
+        elements
+    }
+
+    pub fn children(&self) -> impl Iterator<Item = SyntaxNode> + use<> {
+        self.children_with_tokens().into_iter().filter_map(|e| match e {
+            SyntaxElement::Node(node) => Some(node),
+            SyntaxElement::Token(_) => None,
+        })
+    }
+
+    /// the tokens that are direct children of `self`
+    pub fn child_tokens(&self) -> impl Iterator<Item = SyntaxToken> + use<> {
+        self.children_with_tokens().into_iter().filter_map(|e| match e {
+            SyntaxElement::Node(_) => None,
+            SyntaxElement::Token(token) => Some(token),
+        })
+    }
+
+    /// `self` and all nodes below it in source order
+    pub fn descendants(&self) -> Vec<SyntaxNode> {
+        let mut nodes = vec![self.clone()];
+        for child in self.children() {
+            nodes.extend(child.descendants());
+        }
+        nodes
+    }
+
+    /// all tokens below `self` in source order, including trivia
+    pub fn tokens(&self) -> Vec<SyntaxToken> {
+        let mut tokens = Vec::new();
+        for element in self.children_with_tokens() {
+            match element {
+                SyntaxElement::Node(node) => tokens.extend(node.tokens()),
+                SyntaxElement::Token(token) => tokens.push(token),
+            }
+        }
+        tokens
+    }
+
+    /// the token that contains the byte at `offset`
+    pub fn token_at_offset(&self, offset: usize) -> Option<SyntaxToken> {
+        for element in self.children_with_tokens() {
+            match element {
+                SyntaxElement::Node(node)
+                    if node.text_range().contains(&offset) =>
+                {
+                    return node.token_at_offset(offset);
+                }
+                SyntaxElement::Token(token)
+                    if token.text_range().contains(&offset) =>
+                {
+                    return Some(token);
+                }
+                _ => {}
+            }
+        }
+        None
+    }
+
+    /// the new root after replacing `self` with `green`, `self` is not
+    /// changed since the tree is immutable
+    pub fn replace_with(&self, green: Rc<GreenNode>) -> Rc<GreenNode> {
+        match self.parent() {
+            Some(parent) => parent.replace_with(Rc::new(
+                parent
+                    .green()
+                    .replace_child(self.0.index, GreenElement::Node(green)),
+            )),
+            None => green,
+        }
+    }
+
+    /// an indented outline of the tree for debugging, e.g.
+    /// ```text
+    /// SourceFile@0..5
+    ///   Whitespace@0..1 " "
+    /// ```
+    pub fn debug_tree(&self) -> String {
+        let mut out = String::new();
+        self.write_debug_tree(&mut out, 0);
+        out
+    }
+
+    fn write_debug_tree(&self, out: &mut String, depth: usize) {
+        use std::fmt::Write;
+        let indent = "  ".repeat(depth);
+        let _ = writeln!(out, "{indent}{self:?}");
+        for element in self.children_with_tokens() {
+            match element {
+                SyntaxElement::Node(node) => {
+                    node.write_debug_tree(out, depth + 1)
+                }
+                SyntaxElement::Token(token) => {
+                    let _ = writeln!(out, "{indent}  {token:?}");
+                }
+            }
+        }
+    }
+}
+impl SyntaxToken {
+    pub fn kind(&self) -> SyntaxKind { self.green.kind }
+
+    pub fn text(&self) -> &str { &self.green.text }
+
+    pub fn green(&self) -> &Rc<GreenToken> { &self.green }
+
+    /// the byte range in the source text
+    pub fn text_range(&self) -> Range<usize> {
+        self.offset..self.offset + self.green.text.len()
+    }
+
+    pub fn parent(&self) -> &SyntaxNode { &self.parent }
+
+    pub fn is_trivia(&self) -> bool { self.kind().is_trivia() }
+
+    /// the new root after replacing `self` with `green`, e.g. to rename an
+    /// identifier while keeping the comments around it
+    pub fn replace_with(&self, green: GreenToken) -> Rc<GreenNode> {
+        let parent = self.parent();
+        parent.replace_with(Rc::new(
+            parent
+                .green()
+                .replace_child(self.index, GreenElement::Token(Rc::new(green))),
+        ))
+    }
+}
+impl SyntaxElement {
+    pub fn kind(&self) -> SyntaxKind {
+        match self {
+            SyntaxElement::Node(node) => node.kind(),
+            SyntaxElement::Token(token) => token.kind(),
+        }
+    }
+
+    pub fn text_range(&self) -> Range<usize> {
+        match self {
+            SyntaxElement::Node(node) => node.text_range(),
+            SyntaxElement::Token(token) => token.text_range(),
+        }
+    }
+}
+impl PartialEq for SyntaxNode {
+    fn eq(&self, other: &Self) -> bool {
+        Rc::ptr_eq(&self.0.green, &other.0.green)
+            && self.0.offset == other.0.offset
+    }
+}
+impl Eq for SyntaxNode {}
+impl PartialEq for SyntaxToken {
+    fn eq(&self, other: &Self) -> bool {
+        Rc::ptr_eq(&self.green, &other.green) && self.offset == other.offset
+    }
+}
+impl Eq for SyntaxToken {}
+impl std::fmt::Debug for SyntaxNode {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{:?}@{:?}", self.kind(), self.text_range())
+    }
+}
+impl std::fmt::Debug for SyntaxToken {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{:?}@{:?} {:?}", self.kind(), self.text_range(), self.text())
+    }
+}
+impl Display for GreenNode {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        for child in &self.children {
+            match child {
+                GreenElement::Node(node) => write!(f, "{node}")?,
+                GreenElement::Token(token) => write!(f, "{}", token.text)?,
+            }
+        }
+        Ok(())
+    }
+}
+/// the exact source text
+impl Display for SyntaxNode {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "{}", self.0.green)
+    }
+}
+impl Display for SyntaxToken {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "{}", self.text())
+    }
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::cst::view::{AstNode, FnDef};
+    const SOURCE: &str = "//! crate docs
+// a plain comment
+/// adds one
+pub fn add_one(x: i32) -> i32 { /* inline */ x + 1 } // trailing
+
+struct Point { /// the x\r\n    x: f64, y: f64 }
+fn main() {
+    let   p = Point { x: 1.0, y: 2.0 };   // keep me
+    match p { Point { x, .. } if x > 0.0 => {}, _ => {} }
+    'outer: loop { break 'outer; }
+    let s = \"中文\"; let v: i32.Box = box add_one(1);
+}
+";
+    #[test]
+    fn round_trip() {
+        for source in [
+            SOURCE,
+            include_str!("../../../test.aau"),
+            "",
+            "  \n\t",
+            "fn a( { let = ; } struct ",
+            "fn a() { \"unterminated }",
+            "fn a() { let x = 1 @ 2; } $ mod b {}",
+            "type A = Vec<Vec<i32>>; fn b() { a >>= 1; }",
+            "struct A<T>(T) where T: B; enum C<T: D> { E(T) } union F<T> \
+             where T: G { t: T } impl<T> H for A<T> where T: I {}",
+        ] {
+            let (file, _) = parse(source);
+            assert_eq!(
+                file.to_string(),
+                source,
+                "{}",
+                file.syntax().debug_tree()
+            );
+            let len: usize =
+                file.syntax().tokens().iter().map(|t| t.text().len()).sum();
+            assert_eq!(len, source.len());
+        }
+    }
+    #[test]
+    fn typed_view_and_rename() {
+        let (file, errors) = parse(SOURCE);
+        assert_eq!(errors, []);
+        let items: Vec<_> = file.items().collect();
+        let names: Vec<_> = items
+            .iter()
+            .map(|item| item.name().map(|name| name.text()))
+            .collect();
+        assert_eq!(names, [
+            Some("add_one".to_string()),
+            Some("Point".to_string()),
+            Some("main".to_string())
+        ]);
+        let docs: Vec<_> =
+            items[0].docs().map(|doc| doc.text().to_string()).collect();
+        assert_eq!(docs, ["/// adds one"]);
+        let comments = file.comments().filter(|c| !c.is_doc()).count();
+        assert_eq!(comments, 4);
+        let add_one = FnDef::cast(items[0].syntax().clone()).unwrap();
+        let param = add_one.params().next().unwrap();
+        assert_eq!(param.pat().unwrap().name().unwrap().text(), "x");
+        assert_eq!(param.ty().unwrap().to_string(), "i32");
+        assert_eq!(add_one.ret_type().unwrap().to_string(), "i32");
+        assert_eq!(
+            add_one.body().unwrap().to_string(),
+            "{ /* inline */ x + 1 }"
+        );
+        let main = FnDef::cast(items[2].syntax().clone()).unwrap();
+        let stmts: Vec<_> = main.body().unwrap().stmts().collect();
+        let local = stmts[0].as_let().unwrap();
+        assert_eq!(local.pat().unwrap().to_string(), "p");
+        assert_eq!(
+            local.init().unwrap().to_string(),
+            "Point { x: 1.0, y: 2.0 }"
+        );
+        // rename `add_one` at its definition and its call, one edit at a time
+        let mut root = file.syntax().clone();
+        while let Some(token) =
+            root.tokens().into_iter().find(|token| token.text() == "add_one")
+        {
+            let renamed = GreenToken::new(SyntaxKind::Ident, "increment");
+            root = SyntaxNode::new_root(token.replace_with(renamed));
+        }
+        assert_eq!(root.to_string(), SOURCE.replace("add_one", "increment"));
+        let call = root
+            .descendants()
+            .into_iter()
+            .find(|node| node.kind() == SyntaxKind::CallExpr)
+            .unwrap();
+        assert_eq!(call.to_string(), "increment(1)");
+        assert_eq!(
+            call.parent().unwrap().kind(),
+            SyntaxKind::BoxExpr,
+            "{}",
+            root.debug_tree()
+        );
+    }
+}
@@ -0,0 +1,707 @@
+// Copyright (c) 2025 air (https://yuanair.github.io).
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published by
+// the Free Software Foundation, version 3 of the License only.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+use std::{ops::Range, rc::Rc};
+
+use crate::{
+    lexer::token::Token,
+    parser::{
+        ast::{
+            Attribute,
+            Block,
+            Expr,
+            ExprKind,
+            FieldDef,
+            GenericArgs,
+            Generics,
+            Ident,
+            Item,
+            ItemKind,
+            Module,
+            Pat,
+            PatKind,
+            Path,
+            Stmt,
+            StmtKind,
+            Type,
+            TypeKind,
+            UseTree,
+            UseTreeKind,
+            VariantData,
+            Visibility,
+        },
+        cst::{GreenElement, GreenNode, GreenToken, SyntaxKind},
+    },
+    utils::span::{Span, Spanned},
+};
+/// the lossless tree of `source`, `tokens` are all tokens of `source`
+/// including comments and `module` is what [Parser](crate::parser::Parser)
+/// made of them
+///
+/// every byte of `source` ends up in exactly one token, the text between
+/// `tokens` becomes [SyntaxKind::Whitespace] or [SyntaxKind::Error], and the
+/// nodes are placed by the spans of `module`
+pub fn build(
+    source: &str, tokens: &[Spanned<Token>], module: &Module,
+) -> Rc<GreenNode> {
+    let mut builder = Builder::new(source, tokens);
+    builder.start(SyntaxKind::SourceFile, 0);
+    builder.attrs(&module.attrs);
+    for item in &module.items {
+        builder.item(item);
+    }
+    builder.finish(usize::MAX);
+    match builder.stack.pop() {
+        Some((_, mut children)) => match children.pop() {
+            Some(GreenElement::Node(root)) => root,
+            _ => unreachable!("the root is finished last"),
+        },
+        None => unreachable!("the stack starts with a frame"),
+    }
+}
+/// places the tokens into nodes while the AST is walked in source order
+struct Builder<'a> {
+    source: &'a str,
+    tokens: Vec<(SyntaxKind, Range<usize>)>,
+    /// index of the next token that is not in a node yet
+    pos:    usize,
+    /// the unfinished nodes, the first frame only holds the root
+    stack:  Vec<(SyntaxKind, Vec<GreenElement>)>,
+}
+impl<'a> Builder<'a> {
+    fn new(source: &'a str, tokens: &[Spanned<Token>]) -> Self {
+        let mut lexemes = Vec::new();
+        let mut cursor = 0;
+        for token in tokens {
+            let kind = match token.node {
+                Token::Comment(_) => SyntaxKind::Comment,
+                Token::Identifier(_) => SyntaxKind::Ident,
+                Token::Keyword(_) => SyntaxKind::Keyword,
+                Token::Lifetime(_) => SyntaxKind::Lifetime,
+                Token::Literal(_) => SyntaxKind::Literal,
+                Token::Operator(_) => SyntaxKind::Punct,
+                Token::EOF => continue,
+            };
+            let (lo, hi) = (token.span.lo(), token.span.hi().min(source.len()));
+            if lo < cursor || hi <= lo {
+                continue;
+            }
+            Self::gap(source, cursor..lo, &mut lexemes);
+            lexemes.push((kind, lo..hi));
+            cursor = hi;
+        }
+        Self::gap(source, cursor..source.len(), &mut lexemes);
+        Self {
+            source,
+            tokens: lexemes,
+            pos: 0,
+            stack: vec![(SyntaxKind::SourceFile, Vec::new())],
+        }
+    }
+
+    /// splits the text between two tokens into runs of whitespace and of
+    /// anything else
+    fn gap(
+        source: &str, range: Range<usize>,
+        lexemes: &mut Vec<(SyntaxKind, Range<usize>)>,
+    ) {
+        let mut lo = range.start;
+        let mut kind = None;
+        for (i, c) in source[range.clone()].char_indices() {
+            let next = match c.is_whitespace() {
+                true => SyntaxKind::Whitespace,
+                false => SyntaxKind::Error,
+            };
+            let i = range.start + i;
+            if let Some(kind) = kind.filter(|&kind| kind != next) {
+                lexemes.push((kind, lo..i));
+                lo = i;
+            }
+            kind = Some(next);
+        }
+        if let Some(kind) = kind {
+            lexemes.push((kind, lo..range.end));
+        }
+    }
+
+    /// moves the tokens that satisfy `f` into the innermost unfinished node
+    fn flush(&mut self, f: impl Fn(&Range<usize>) -> bool) {
+        while let Some((kind, range)) = self.tokens.get(self.pos) {
+            if !f(range) {
+                break;
+            }
+            let token = GreenToken::new(*kind, &self.source[range.clone()]);
+            let frame = self.stack.last_mut().expect("the root is unfinished");
+            frame.1.push(GreenElement::Token(Rc::new(token)));
+            self.pos += 1;
+        }
+    }
+
+    /// the tokens before `lo` stay in the parent
+    fn start(&mut self, kind: SyntaxKind, lo: usize) {
+        self.flush(|range| range.start < lo);
+        self.stack.push((kind, Vec::new()));
+    }
+
+    /// the tokens up to `hi` go into the node
+    fn finish(&mut self, hi: usize) {
+        self.flush(|range| range.end <= hi);
+        let (kind, children) = self.stack.pop().expect("a node was started");
+        let node = GreenNode::new(kind, children);
+        let frame = self.stack.last_mut().expect("the root is unfinished");
+        frame.1.push(GreenElement::Node(Rc::new(node)));
+    }
+
+    fn node(
+        &mut self, kind: SyntaxKind, span: Span, f: impl FnOnce(&mut Self),
+    ) {
+        self.start(kind, span.lo());
+        f(self);
+        self.finish(span.hi());
+    }
+
+    fn name(&mut self, name: &Ident) {
+        self.node(SyntaxKind::Name, name.span, |_| {});
+    }
+
+    fn name_ref(&mut self, name: &Ident) {
+        self.node(SyntaxKind::NameRef, name.span, |_| {});
+    }
+
+    /// documentation comments before an item, field or variant belong to it
+    fn item_start<T>(
+        &mut self, kind: SyntaxKind, docs: &[Spanned<T>], span: Span,
+    ) {
+        let lo = docs.first().map_or(span.lo(), |doc| doc.span.lo());
+        self.start(kind, lo.min(span.lo()));
+    }
+
+    fn item(&mut self, item: &Item) {
+        let kind = match &item.kind {
+            ItemKind::Fn(_) => SyntaxKind::FnDef,
+            ItemKind::Struct { .. } => SyntaxKind::StructDef,
+            ItemKind::Enum { .. } => SyntaxKind::EnumDef,
+            ItemKind::Union { .. } => SyntaxKind::UnionDef,
+            ItemKind::Trait { .. } => SyntaxKind::TraitDef,
+            ItemKind::Impl { .. } => SyntaxKind::ImplDef,
+            ItemKind::Mod { .. } => SyntaxKind::ModDef,
+            ItemKind::Use(_) => SyntaxKind::UseItem,
+            ItemKind::Const { .. } => SyntaxKind::ConstDef,
+            ItemKind::Static { .. } => SyntaxKind::StaticDef,
+            ItemKind::TypeAlias { .. } => SyntaxKind::TypeAliasDef,
+            ItemKind::Extern { .. } => SyntaxKind::ExternBlock,
+            ItemKind::Marco(_) => SyntaxKind::MacroDef,
+            ItemKind::MacCall(_) => SyntaxKind::MacroCall,
+            ItemKind::Err => SyntaxKind::ErrorItem,
+        };
+        self.item_start(kind, &item.docs, item.span);
+        self.attrs(&item.attrs);
+        self.visibility(item.vis);
+        match &item.kind {
+            ItemKind::Fn(def) => {
+                self.name(&def.name);
+                self.generic_params(&def.generics);
+                if let Some(param) = &def.sig.self_param {
+                    self.node(SyntaxKind::SelfParam, param.span, |_| {});
+                }
+                for param in &def.sig.params {
+                    self.node(SyntaxKind::Param, param.span, |b| {
+                        b.pat(&param.pat);
+                        b.ty(&param.ty);
+                    });
+                }
+                if let Some(ret) = &def.sig.ret {
+                    self.ty(ret);
+                }
+                self.where_clause(&def.generics);
+                if let Some(body) = &def.body {
+                    self.block(body);
+                }
+            }
+            ItemKind::Struct { name, generics, data } => {
+                self.name(name);
+                self.generic_params(generics);
+                if matches!(data, VariantData::Struct(_)) {
+                    self.where_clause(generics);
+                    self.variant_data(data);
+                } else {
+                    self.variant_data(data);
+                    self.where_clause(generics);
+                }
+            }
+            ItemKind::Enum { name, generics, variants } => {
+                self.name(name);
+                self.generic_params(generics);
+                self.where_clause(generics);
+                for variant in variants {
+                    self.item_start(
+                        SyntaxKind::Variant,
+                        &variant.docs,
+                        variant.span,
+                    );
+                    self.attrs(&variant.attrs);
+                    self.name(&variant.name);
+                    self.variant_data(&variant.data);
+                    if let Some(discriminant) = &variant.discriminant {
+                        self.expr(discriminant);
+                    }
+                    self.finish(variant.span.hi());
+                }
+            }
+            ItemKind::Union { name, generics, fields } => {
+                self.name(name);
+                self.generic_params(generics);
+                self.where_clause(generics);
+                self.fields(fields);
+            }
+            ItemKind::Trait { name, items } => {
+                self.name(name);
+                items.iter().for_each(|item| self.item(item));
+            }
+            ItemKind::Impl { generics, of_trait, self_ty, items } => {
+                self.generic_params(generics);
+                if let Some(of_trait) = of_trait {
+                    self.path(SyntaxKind::PathType, of_trait);
+                }
+                self.ty(self_ty);
+                self.where_clause(generics);
+                items.iter().for_each(|item| self.item(item));
+            }
+            ItemKind::Mod { name, module } => {
+                self.name(name);
+                if let Some(module) = module {
+                    self.attrs(&module.attrs);
+                    module.items.iter().for_each(|item| self.item(item));
+                }
+            }
+            ItemKind::Use(tree) => self.use_tree(tree),
+            ItemKind::Const { name, ty, value }
+            | ItemKind::Static { name, ty, value, .. } => {
+                self.name(name);
+                self.ty(ty);
+                if let Some(value) = value {
+                    self.expr(value);
+                }
+            }
+            ItemKind::TypeAlias { name, ty } => {
+                self.name(name);
+                if let Some(ty) = ty {
+                    self.ty(ty);
+                }
+            }
+            ItemKind::Extern { items, .. } => {
+                items.iter().for_each(|item| self.item(item));
+            }
+            ItemKind::Marco(def) => self.name(&def.name),
+            ItemKind::MacCall(mac) => self.path(SyntaxKind::Path, &mac.path),
+            ItemKind::Err => {}
+        }
+        self.finish(item.span.hi());
+    }
+
+    fn attrs<'t>(&mut self, attrs: impl IntoIterator<Item = &'t Attribute>) {
+        for attr in attrs {
+            self.node(SyntaxKind::Attr, attr.span, |b| {
+                b.path(SyntaxKind::Path, &attr.path)
+            });
+        }
+    }
+
+    fn visibility(&mut self, vis: Visibility) {
+        match vis {
+            Visibility::Public(span) | Visibility::Private(span) => {
+                self.node(SyntaxKind::Visibility, span, |_| {})
+            }
+            Visibility::Inherited => {}
+        }
+    }
+
+    fn variant_data(&mut self, data: &VariantData) {
+        match data {
+            VariantData::Struct(fields) | VariantData::Tuple(fields) => {
+                self.fields(fields)
+            }
+            VariantData::Unit => {}
+        }
+    }
+
+    fn fields(&mut self, fields: &[FieldDef]) {
+        for field in fields {
+            self.item_start(SyntaxKind::FieldDef, &field.docs, field.span);
+            self.attrs(&field.attrs);
+            self.visibility(field.vis);
+            if let Some(name) = &field.name {
+                self.name(name);
+            }
+            self.ty(&field.ty);
+            self.finish(field.span.hi());
+        }
+    }
+
+    fn use_tree(&mut self, tree: &UseTree) {
+        self.node(SyntaxKind::UseTree, tree.span, |b| {
+            b.path(SyntaxKind::Path, &tree.prefix);
+            match &tree.kind {
+                UseTreeKind::Simple(Some(rename)) => b.name(rename),
+                UseTreeKind::Simple(None) | UseTreeKind::Glob => {}
+                UseTreeKind::Nested(trees) => {
+                    trees.iter().for_each(|tree| b.use_tree(tree))
+                }
+            }
+        });
+    }
+
+    /// a `kind` node around a [SyntaxKind::Path] node
+    fn path(&mut self, kind: SyntaxKind, path: &Path) {
+        if path.segments.is_empty() {
+            return;
+        }
+        let wrap = kind != SyntaxKind::Path;
+        if wrap {
+            self.start(kind, path.span.lo());
+        }
+        self.node(SyntaxKind::Path, path.span, |b| {
+            for segment in &path.segments {
+                let span = match &segment.args {
+                    Some(args) => segment.ident.span.to(args.span),
+                    None => segment.ident.span,
+                };
+                b.node(SyntaxKind::PathSegment, span, |b| {
+                    b.name_ref(&segment.ident);
+                    if let Some(args) = &segment.args {
+                        b.generic_args(args);
+                    }
+                });
+            }
+        });
+        if wrap {
+            self.finish(path.span.hi());
+        }
+    }
+
+    fn generic_params(&mut self, generics: &Generics) {
+        if generics.params.is_empty() {
+            return;
+        }
+        self.node(SyntaxKind::GenericParams, generics.span, |b| {
+            for param in &generics.params {
+                b.node(SyntaxKind::GenericParam, param.span, |b| {
+                    b.name(&param.name);
+                    for bound in &param.bounds {
+                        b.path(SyntaxKind::PathType, bound);
+                    }
+                });
+            }
+        });
+    }
+
+    fn where_clause(&mut self, generics: &Generics) {
+        for predicate in &generics.where_clause {
+            self.node(SyntaxKind::WherePred, predicate.span, |b| {
+                b.ty(&predicate.ty);
+                for bound in &predicate.bounds {
+                    b.path(SyntaxKind::PathType, bound);
+                }
+            });
+        }
+    }
+
+    fn generic_args(&mut self, args: &GenericArgs) {
+        self.node(SyntaxKind::GenericArgs, args.span, |b| {
+            args.args.iter().for_each(|ty| b.ty(ty));
+        });
+    }
+
+    fn block(&mut self, block: &Block) {
+        self.node(SyntaxKind::Block, block.span, |b| {
+            block.stmts.iter().for_each(|stmt| b.stmt(stmt));
+        });
+    }
+
+    fn stmt(&mut self, stmt: &Stmt) {
+        match &stmt.kind {
+            StmtKind::Expr(expr) | StmtKind::Semi(expr) => {
+                self.node(SyntaxKind::ExprStmt, stmt.span, |b| {
+                    // the attributes of the statement, not in the expression
+                    let lo = expr.span.lo();
+                    b.attrs(expr.attrs.iter().filter(|a| a.span.lo() < lo));
+                    b.expr(expr)
+                })
+            }
+            StmtKind::Empty => {}
+            StmtKind::Item(item) => self.item(item),
+            StmtKind::Let(local) => {
+                self.node(SyntaxKind::LetStmt, stmt.span, |b| {
+                    b.attrs(&local.attrs);
+                    b.pat(&local.pat);
+                    if let Some(ty) = &local.ty {
+                        b.ty(ty);
+                    }
+                    if let Some(init) = &local.init {
+                        b.expr(init);
+                    }
+                    if let Some(els) = &local.els {
+                        b.block(els);
+                    }
+                })
+            }
+        }
+    }
+
+    fn exprs(&mut self, exprs: &[Expr]) {
+        exprs.iter().for_each(|expr| self.expr(expr));
+    }
+
+    fn expr(&mut self, expr: &Expr) {
+        let kind = match &expr.kind {
+            ExprKind::Literal(_) => SyntaxKind::LiteralExpr,
+            ExprKind::Path(path) => {
+                return self.path(SyntaxKind::PathExpr, path);
+            }
+            ExprKind::Tuple(_) => SyntaxKind::TupleExpr,
+            ExprKind::Paren(_) => SyntaxKind::ParenExpr,
+            ExprKind::Array(_) => SyntaxKind::ArrayExpr,
+            ExprKind::Repeat(..) => SyntaxKind::RepeatExpr,
+            ExprKind::Struct { .. } => SyntaxKind::StructExpr,
+            ExprKind::Unary(..) => SyntaxKind::UnaryExpr,
+            ExprKind::Ref { .. } => SyntaxKind::RefExpr,
+            ExprKind::Box(_) => SyntaxKind::BoxExpr,
+            ExprKind::Binary(..) => SyntaxKind::BinaryExpr,
+            ExprKind::Assign(..) | ExprKind::AssignOp(..) => {
+                SyntaxKind::AssignExpr
+            }
+            ExprKind::Cast(..) => SyntaxKind::CastExpr,
+            ExprKind::Call(..) => SyntaxKind::CallExpr,
+            ExprKind::MethodCall { .. } => SyntaxKind::MethodCallExpr,
+            ExprKind::Field(..) => SyntaxKind::FieldExpr,
+            ExprKind::Index(..) => SyntaxKind::IndexExpr,
+            ExprKind::Try(_) => SyntaxKind::TryExpr,
+            ExprKind::Range { .. } => SyntaxKind::RangeExpr,
+            ExprKind::Block(block) => return self.block(block),
+            ExprKind::If { .. } => SyntaxKind::IfExpr,
+            ExprKind::Match { .. } => SyntaxKind::MatchExpr,
+            ExprKind::Loop { .. } => SyntaxKind::LoopExpr,
+            ExprKind::While { .. } => SyntaxKind::WhileExpr,
+            ExprKind::For { .. } => SyntaxKind::ForExpr,
+            ExprKind::Break { .. } => SyntaxKind::BreakExpr,
+            ExprKind::Continue { .. } => SyntaxKind::ContinueExpr,
+            ExprKind::Return(_) => SyntaxKind::ReturnExpr,
+            ExprKind::Format(_) => SyntaxKind::FormatExpr,
+            ExprKind::MacCall(_) => SyntaxKind::MacroCallExpr,
+            ExprKind::Err => SyntaxKind::ErrorExpr,
+        };
+        self.start(kind, expr.span.lo());
+        let lo = expr.span.lo();
+        self.attrs(expr.attrs.iter().filter(|a| a.span.lo() >= lo));
+        match &expr.kind {
+            ExprKind::Literal(_)
+            | ExprKind::Path(_)
+            | ExprKind::Block(_)
+            | ExprKind::Continue { .. }
+            | ExprKind::Err => {}
+            ExprKind::Tuple(exprs) | ExprKind::Array(exprs) => {
+                self.exprs(exprs)
+            }
+            ExprKind::Format(format) => self.exprs(&format.args),
+            ExprKind::MacCall(mac) => self.path(SyntaxKind::Path, &mac.path),
+            ExprKind::Paren(inner)
+            | ExprKind::Unary(_, inner)
+            | ExprKind::Ref { expr: inner, .. }
+            | ExprKind::Box(inner)
+            | ExprKind::Try(inner) => self.expr(inner),
+            ExprKind::Repeat(l, r)
+            | ExprKind::Binary(_, l, r)
+            | ExprKind::Assign(l, r)
+            | ExprKind::AssignOp(_, l, r)
+            | ExprKind::Index(l, r) => {
+                self.expr(l);
+                self.expr(r);
+            }
+            ExprKind::Struct { path, fields, rest } => {
+                self.path(SyntaxKind::Path, path);
+                for field in fields {
+                    self.node(SyntaxKind::ExprField, field.span, |b| {
+                        // the shorthand `x` is only a path expression
+                        if field.expr.span != field.name.span {
+                            b.name_ref(&field.name);
+                        }
+                        b.expr(&field.expr);
+                    });
+                }
+                if let Some(rest) = rest {
+                    self.expr(rest);
+                }
+            }
+            ExprKind::Cast(inner, ty) => {
+                self.expr(inner);
+                self.ty(ty);
+            }
+            ExprKind::Call(callee, args) => {
+                self.expr(callee);
+                self.exprs(args);
+            }
+            ExprKind::MethodCall { receiver, method, args } => {
+                self.expr(receiver);
+                self.name_ref(method);
+                self.exprs(args);
+            }
+            ExprKind::Field(inner, name) => {
+                self.expr(inner);
+                self.name_ref(name);
+            }
+            ExprKind::Range { start, end, .. } => {
+                if let Some(start) = start {
+                    self.expr(start);
+                }
+                if let Some(end) = end {
+                    self.expr(end);
+                }
+            }
+            ExprKind::If { cond, then_branch, else_branch } => {
+                self.expr(cond);
+                self.block(then_branch);
+                if let Some(else_branch) = else_branch {
+                    self.expr(else_branch);
+                }
+            }
+            ExprKind::Match { scrutinee, arms } => {
+                self.expr(scrutinee);
+                for arm in arms {
+                    self.node(SyntaxKind::MatchArm, arm.span, |b| {
+                        b.attrs(&arm.attrs);
+                        b.pat(&arm.pat);
+                        if let Some(guard) = &arm.guard {
+                            b.expr(guard);
+                        }
+                        b.expr(&arm.body);
+                    });
+                }
+            }
+            ExprKind::Loop { body, .. } => self.block(body),
+            ExprKind::While { cond, body, .. } => {
+                self.expr(cond);
+                self.block(body);
+            }
+            ExprKind::For { pat, iter, body, .. } => {
+                self.pat(pat);
+                self.expr(iter);
+                self.block(body);
+            }
+            ExprKind::Break { value, .. } => {
+                if let Some(value) = value {
+                    self.expr(value);
+                }
+            }
+            ExprKind::Return(value) => {
+                if let Some(value) = value {
+                    self.expr(value);
+                }
+            }
+        }
+        self.finish(expr.span.hi());
+    }
+
+    fn pat(&mut self, pat: &Pat) {
+        let kind = match &pat.kind {
+            PatKind::Wild => SyntaxKind::WildPat,
+            PatKind::Rest => SyntaxKind::RestPat,
+            PatKind::Ident { .. } => SyntaxKind::IdentPat,
+            PatKind::Literal(_) => SyntaxKind::LiteralPat,
+            PatKind::Path(path) => return self.path(SyntaxKind::PathPat, path),
+            PatKind::Tuple(_) => SyntaxKind::TuplePat,
+            PatKind::TupleStruct(..) => SyntaxKind::TupleStructPat,
+            PatKind::Struct { .. } => SyntaxKind::StructPat,
+            PatKind::Slice(_) => SyntaxKind::SlicePat,
+            PatKind::Range { .. } => SyntaxKind::RangePat,
+            PatKind::Ref { .. } => SyntaxKind::RefPat,
+            PatKind::Or(_) => SyntaxKind::OrPat,
+        };
+        self.start(kind, pat.span.lo());
+        match &pat.kind {
+            PatKind::Wild
+            | PatKind::Rest
+            | PatKind::Literal(_)
+            | PatKind::Path(_) => {}
+            PatKind::Ident { name, sub, .. } => {
+                self.name(name);
+                if let Some(sub) = sub {
+                    self.pat(sub);
+                }
+            }
+            PatKind::Tuple(pats) | PatKind::Slice(pats) | PatKind::Or(pats) => {
+                pats.iter().for_each(|pat| self.pat(pat))
+            }
+            PatKind::TupleStruct(path, pats) => {
+                self.path(SyntaxKind::Path, path);
+                pats.iter().for_each(|pat| self.pat(pat));
+            }
+            PatKind::Struct { path, fields, .. } => {
+                self.path(SyntaxKind::Path, path);
+                for field in fields {
+                    self.node(SyntaxKind::PatField, field.span, |b| {
+                        // the shorthand `ref x` is only a binding
+                        if field.pat.span.hi() != field.name.span.hi() {
+                            b.name_ref(&field.name);
+                        }
+                        b.pat(&field.pat);
+                    });
+                }
+            }
+            PatKind::Range { start, end, .. } => {
+                if let Some(start) = start {
+                    self.pat(start);
+                }
+                if let Some(end) = end {
+                    self.pat(end);
+                }
+            }
+            PatKind::Ref { pat: inner, .. } => self.pat(inner),
+        }
+        self.finish(pat.span.hi());
+    }
+
+    fn ty(&mut self, ty: &Type) {
+        let kind = match &ty.kind {
+            TypeKind::Path(path) => {
+                return self.path(SyntaxKind::PathType, path);
+            }
+            TypeKind::Tuple(_) => SyntaxKind::TupleType,
+            TypeKind::Ptr { .. } => SyntaxKind::PtrType,
+            TypeKind::Ref { .. } => SyntaxKind::RefType,
+            TypeKind::Slice(_) => SyntaxKind::SliceType,
+            TypeKind::Array(..) => SyntaxKind::ArrayType,
+            TypeKind::TraitObject(_) => SyntaxKind::DynTraitType,
+            TypeKind::ImplTrait(_) => SyntaxKind::ImplTraitType,
+            TypeKind::Box(_) => SyntaxKind::BoxType,
+            TypeKind::Infer => SyntaxKind::InferType,
+        };
+        self.start(kind, ty.span.lo());
+        match &ty.kind {
+            TypeKind::Path(_) | TypeKind::Infer => {}
+            TypeKind::Tuple(tys) => tys.iter().for_each(|ty| self.ty(ty)),
+            TypeKind::Ptr { ty: inner, .. }
+            | TypeKind::Ref { ty: inner, .. }
+            | TypeKind::Slice(inner)
+            | TypeKind::Box(inner) => self.ty(inner),
+            TypeKind::Array(elem, len) => {
+                self.ty(elem);
+                self.expr(len);
+            }
+            TypeKind::TraitObject(bounds) | TypeKind::ImplTrait(bounds) => {
+                for bound in bounds {
+                    self.path(SyntaxKind::Path, bound);
+                }
+            }
+        }
+        self.finish(ty.span.hi());
+    }
+}
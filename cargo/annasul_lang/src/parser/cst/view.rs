@@ -0,0 +1,310 @@
+// Copyright (c) 2025 air (https://yuanair.github.io).
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published by
+// the Free Software Foundation, version 3 of the License only.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+use crate::{
+    lexer::{
+        scanner::{AnnasulScanner, Scanner},
+        token::Token,
+    },
+    parser::cst::{SyntaxElement, SyntaxKind, SyntaxNode, SyntaxToken},
+    utils::span::Spanned,
+};
+/// a typed view of a [SyntaxNode], the accessors return [None] for the parts
+/// that are missing because of syntax errors
+pub trait AstNode: Sized {
+    fn can_cast(kind: SyntaxKind) -> bool;
+    fn cast(node: SyntaxNode) -> Option<Self>;
+    fn syntax(&self) -> &SyntaxNode;
+}
+macro_rules! ast_node {
+    ($(#[$meta:meta])* $name:ident, |$kind:ident| $can_cast:expr) => {
+        $(#[$meta])*
+        #[derive(Debug, Clone, PartialEq, Eq)]
+        pub struct $name(SyntaxNode);
+        impl AstNode for $name {
+            fn can_cast($kind: SyntaxKind) -> bool { $can_cast }
+
+            fn cast(node: SyntaxNode) -> Option<Self> {
+                Self::can_cast(node.kind()).then_some(Self(node))
+            }
+
+            fn syntax(&self) -> &SyntaxNode { &self.0 }
+        }
+        impl std::fmt::Display for $name {
+            fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+                write!(f, "{}", self.0)
+            }
+        }
+    };
+}
+ast_node!(
+    /// see [SyntaxKind::SourceFile]
+    SourceFile,
+    |kind| kind == SyntaxKind::SourceFile
+);
+ast_node!(
+    /// any item, see [SyntaxKind::is_item]
+    Item,
+    |kind| kind.is_item()
+);
+ast_node!(
+    /// see [SyntaxKind::FnDef]
+    FnDef,
+    |kind| kind == SyntaxKind::FnDef
+);
+ast_node!(
+    /// see [SyntaxKind::Param]
+    Param,
+    |kind| kind == SyntaxKind::Param
+);
+ast_node!(
+    /// see [SyntaxKind::Name]
+    Name,
+    |kind| kind == SyntaxKind::Name
+);
+ast_node!(
+    /// see [SyntaxKind::NameRef]
+    NameRef,
+    |kind| kind == SyntaxKind::NameRef
+);
+ast_node!(
+    /// see [SyntaxKind::Path]
+    Path,
+    |kind| kind == SyntaxKind::Path
+);
+ast_node!(
+    /// see [SyntaxKind::Block]
+    Block,
+    |kind| kind == SyntaxKind::Block
+);
+ast_node!(
+    /// a [SyntaxKind::LetStmt], a [SyntaxKind::ExprStmt] or an item
+    Stmt,
+    |kind| matches!(kind, SyntaxKind::LetStmt | SyntaxKind::ExprStmt)
+        || kind.is_item()
+);
+ast_node!(
+    /// see [SyntaxKind::LetStmt]
+    LetStmt,
+    |kind| kind == SyntaxKind::LetStmt
+);
+ast_node!(
+    /// any expression, see [SyntaxKind::is_expr]
+    Expr,
+    |kind| kind.is_expr()
+);
+ast_node!(
+    /// any pattern, see [SyntaxKind::is_pat]
+    Pat,
+    |kind| kind.is_pat()
+);
+ast_node!(
+    /// any type, see [SyntaxKind::is_type]
+    Type,
+    |kind| kind.is_type()
+);
+/// a [SyntaxKind::Comment] token
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Comment(SyntaxToken);
+/// the first child of `node` that casts to `N`
+fn child<N: AstNode>(node: &SyntaxNode) -> Option<N> {
+    node.children().find_map(N::cast)
+}
+fn children<N: AstNode>(node: &SyntaxNode) -> impl Iterator<Item = N> + use<N> {
+    node.children().filter_map(N::cast)
+}
+impl SourceFile {
+    /// a [SyntaxKind::SourceFile] node, see [parse](super::parse)
+    pub fn new(node: SyntaxNode) -> Self {
+        Self::cast(node).expect("the root of a file is a `SourceFile`")
+    }
+
+    pub fn items(&self) -> impl Iterator<Item = Item> + use<> {
+        children(&self.0)
+    }
+
+    /// every comment of the file, including documentation comments
+    pub fn comments(&self) -> impl Iterator<Item = Comment> + use<> {
+        self.0.tokens().into_iter().filter_map(Comment::cast)
+    }
+}
+impl Item {
+    /// [None] for `impl`, `use` and `extern` blocks
+    pub fn name(&self) -> Option<Name> { child(&self.0) }
+
+    /// `pub` or `priv`
+    pub fn visibility(&self) -> Option<SyntaxNode> {
+        self.0.children().find(|node| node.kind() == SyntaxKind::Visibility)
+    }
+
+    /// the documentation comments before the item
+    pub fn docs(&self) -> impl Iterator<Item = Comment> + use<> {
+        self.0.child_tokens().filter_map(Comment::cast).filter(Comment::is_doc)
+    }
+
+    /// the items of a `trait`, `impl`, `mod` or `extern` block
+    pub fn items(&self) -> impl Iterator<Item = Item> + use<> {
+        children(&self.0)
+    }
+}
+impl FnDef {
+    pub fn name(&self) -> Option<Name> { child(&self.0) }
+
+    /// `self`, `&self` or `&mut self`
+    pub fn self_param(&self) -> Option<SyntaxNode> {
+        self.0.children().find(|node| node.kind() == SyntaxKind::SelfParam)
+    }
+
+    pub fn params(&self) -> impl Iterator<Item = Param> + use<> {
+        children(&self.0)
+    }
+
+    /// the type after `->`
+    pub fn ret_type(&self) -> Option<Type> { child(&self.0) }
+
+    /// [None] for `fn a();`
+    pub fn body(&self) -> Option<Block> { child(&self.0) }
+}
+impl From<FnDef> for Item {
+    fn from(def: FnDef) -> Self { Self(def.0) }
+}
+impl Param {
+    pub fn pat(&self) -> Option<Pat> { child(&self.0) }
+
+    pub fn ty(&self) -> Option<Type> { child(&self.0) }
+}
+impl Name {
+    pub fn ident(&self) -> Option<SyntaxToken> {
+        self.0.child_tokens().find(|token| !token.is_trivia())
+    }
+
+    /// e.g. `r#match` for a raw identifier
+    pub fn text(&self) -> String {
+        self.ident().map(|token| token.text().to_string()).unwrap_or_default()
+    }
+}
+impl NameRef {
+    /// an identifier or a path keyword like `crate`
+    pub fn ident(&self) -> Option<SyntaxToken> {
+        self.0.child_tokens().find(|token| !token.is_trivia())
+    }
+
+    pub fn text(&self) -> String {
+        self.ident().map(|token| token.text().to_string()).unwrap_or_default()
+    }
+}
+impl Path {
+    /// the names of the segments, without their generic arguments
+    pub fn segments(&self) -> impl Iterator<Item = NameRef> + use<> {
+        self.0
+            .children()
+            .filter(|node| node.kind() == SyntaxKind::PathSegment)
+            .filter_map(|segment| child(&segment))
+    }
+}
+impl Block {
+    pub fn stmts(&self) -> impl Iterator<Item = Stmt> + use<> {
+        children(&self.0)
+    }
+}
+impl LetStmt {
+    pub fn pat(&self) -> Option<Pat> { child(&self.0) }
+
+    pub fn ty(&self) -> Option<Type> { child(&self.0) }
+
+    /// the expression after `=`
+    pub fn init(&self) -> Option<Expr> {
+        self.0
+            .children_with_tokens()
+            .into_iter()
+            .take_while(|element| match element {
+                SyntaxElement::Token(token) => token.text() != "else",
+                SyntaxElement::Node(_) => true,
+            })
+            .find_map(|element| match element {
+                SyntaxElement::Node(node) => Expr::cast(node),
+                SyntaxElement::Token(_) => None,
+            })
+    }
+
+    /// the block of `let ... else`
+    pub fn else_block(&self) -> Option<Block> {
+        let mut elements = self.0.children_with_tokens().into_iter();
+        elements.find(|element| match element {
+            SyntaxElement::Token(token) => token.text() == "else",
+            SyntaxElement::Node(_) => false,
+        })?;
+        elements.find_map(|element| match element {
+            SyntaxElement::Node(node) => Block::cast(node),
+            SyntaxElement::Token(_) => None,
+        })
+    }
+}
+impl Stmt {
+    /// the `let` statement, see [LetStmt]
+    pub fn as_let(&self) -> Option<LetStmt> { LetStmt::cast(self.0.clone()) }
+
+    /// the expression of an expression statement
+    pub fn expr(&self) -> Option<Expr> {
+        match self.0.kind() {
+            SyntaxKind::ExprStmt => child(&self.0),
+            _ => None,
+        }
+    }
+
+    /// the item of an item statement
+    pub fn item(&self) -> Option<Item> { Item::cast(self.0.clone()) }
+}
+impl Expr {
+    pub fn kind(&self) -> SyntaxKind { self.0.kind() }
+
+    /// the operands, e.g. `a` and `b` of `a + b`
+    pub fn exprs(&self) -> impl Iterator<Item = Expr> + use<> {
+        children(&self.0)
+    }
+
+    /// e.g. the `a::b` of a path expression
+    pub fn path(&self) -> Option<Path> { child(&self.0) }
+}
+impl Pat {
+    pub fn kind(&self) -> SyntaxKind { self.0.kind() }
+
+    /// the binding of an identifier pattern
+    pub fn name(&self) -> Option<Name> { child(&self.0) }
+}
+impl Type {
+    pub fn kind(&self) -> SyntaxKind { self.0.kind() }
+
+    /// e.g. the `Vec<i32>` of a path type
+    pub fn path(&self) -> Option<Path> { child(&self.0) }
+}
+impl Comment {
+    pub fn cast(token: SyntaxToken) -> Option<Self> {
+        (token.kind() == SyntaxKind::Comment).then_some(Self(token))
+    }
+
+    pub fn syntax(&self) -> &SyntaxToken { &self.0 }
+
+    pub fn text(&self) -> &str { self.0.text() }
+
+    /// e.g. `/// doc`, `//! doc`, see
+    /// [Comment::is_doc](crate::lexer::token::Comment::is_doc)
+    pub fn is_doc(&self) -> bool {
+        let (tokens, _) =
+            AnnasulScanner::new(self.text().as_bytes()).tokenize();
+        matches!(
+            tokens.first(),
+            Some(Spanned { node: Token::Comment(comment), .. })
+                if comment.is_doc()
+        )
+    }
+}
@@ -0,0 +1,734 @@
+// Copyright (c) 2025 air (https://yuanair.github.io).
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published by
+// the Free Software Foundation, version 3 of the License only.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+use crate::{
+    lexer::token::{Identifier, IntLit, Keyword, Literal, OperatorKind, Token},
+    parser::{
+        Parser,
+        PathStyle,
+        ast::{self, BinOp, Block, Expr, ExprField, ExprKind, Label, UnOp},
+    },
+    utils::{
+        error::{Error, Result},
+        span::{Span, Spanned},
+    },
+};
+/// binding power of infix operators, from the loosest to the tightest
+#[derive(Debug, Clone, Copy, Hash, Ord, PartialOrd, PartialEq, Eq)]
+enum Prec {
+    /// `=`, `+=`, right associative
+    Assign,
+    /// `..`, `..=`, not associative
+    Range,
+    /// `||`
+    Or,
+    /// `&&`
+    And,
+    /// `==`, `<`, not associative
+    Compare,
+    /// `|`
+    BitOr,
+    /// `^`
+    BitXor,
+    /// `&`
+    BitAnd,
+    /// `<<`, `>>`
+    Shift,
+    /// `+`, `-`
+    Sum,
+    /// `*`, `/`, `%`
+    Product,
+    /// `as`
+    Cast,
+}
+/// an infix operator, see [Prec]
+#[derive(Debug, Clone, Copy, Hash, PartialEq, Eq)]
+enum AssocOp {
+    Binary(BinOp),
+    Assign,
+    AssignOp(BinOp),
+    Range { inclusive: bool },
+    Cast,
+}
+impl Prec {
+    /// the next tighter precedence, for left associative operators
+    fn next(self) -> Self {
+        match self {
+            Prec::Assign => Prec::Range,
+            Prec::Range => Prec::Or,
+            Prec::Or => Prec::And,
+            Prec::And => Prec::Compare,
+            Prec::Compare => Prec::BitOr,
+            Prec::BitOr => Prec::BitXor,
+            Prec::BitXor => Prec::BitAnd,
+            Prec::BitAnd => Prec::Shift,
+            Prec::Shift => Prec::Sum,
+            Prec::Sum => Prec::Product,
+            Prec::Product | Prec::Cast => Prec::Cast,
+        }
+    }
+}
+impl AssocOp {
+    fn from_token(token: &Token) -> Option<Self> {
+        let kind = match token {
+            Token::Operator(op) => op.kind(),
+            Token::Keyword(Keyword::r#as) => return Some(AssocOp::Cast),
+            _ => return None,
+        };
+        Some(match kind {
+            OperatorKind::Plus => AssocOp::Binary(BinOp::Add),
+            OperatorKind::Minus => AssocOp::Binary(BinOp::Sub),
+            OperatorKind::Star => AssocOp::Binary(BinOp::Mul),
+            OperatorKind::Slash => AssocOp::Binary(BinOp::Div),
+            OperatorKind::Percent => AssocOp::Binary(BinOp::Rem),
+            OperatorKind::Caret => AssocOp::Binary(BinOp::BitXor),
+            OperatorKind::And => AssocOp::Binary(BinOp::BitAnd),
+            OperatorKind::Or => AssocOp::Binary(BinOp::BitOr),
+            OperatorKind::AndAnd => AssocOp::Binary(BinOp::And),
+            OperatorKind::OrOr => AssocOp::Binary(BinOp::Or),
+            OperatorKind::Shl => AssocOp::Binary(BinOp::Shl),
+            OperatorKind::Shr => AssocOp::Binary(BinOp::Shr),
+            OperatorKind::EqEq => AssocOp::Binary(BinOp::Eq),
+            OperatorKind::Ne => AssocOp::Binary(BinOp::Ne),
+            OperatorKind::Lt => AssocOp::Binary(BinOp::Lt),
+            OperatorKind::Le => AssocOp::Binary(BinOp::Le),
+            OperatorKind::Gt => AssocOp::Binary(BinOp::Gt),
+            OperatorKind::Ge => AssocOp::Binary(BinOp::Ge),
+            OperatorKind::PlusEq => AssocOp::AssignOp(BinOp::Add),
+            OperatorKind::MinusEq => AssocOp::AssignOp(BinOp::Sub),
+            OperatorKind::StarEq => AssocOp::AssignOp(BinOp::Mul),
+            OperatorKind::SlashEq => AssocOp::AssignOp(BinOp::Div),
+            OperatorKind::PercentEq => AssocOp::AssignOp(BinOp::Rem),
+            OperatorKind::CaretEq => AssocOp::AssignOp(BinOp::BitXor),
+            OperatorKind::AndEq => AssocOp::AssignOp(BinOp::BitAnd),
+            OperatorKind::OrEq => AssocOp::AssignOp(BinOp::BitOr),
+            OperatorKind::ShlEq => AssocOp::AssignOp(BinOp::Shl),
+            OperatorKind::ShrEq => AssocOp::AssignOp(BinOp::Shr),
+            OperatorKind::Eq => AssocOp::Assign,
+            OperatorKind::DotDot => AssocOp::Range { inclusive: false },
+            OperatorKind::DotDotEq => AssocOp::Range { inclusive: true },
+            _ => return None,
+        })
+    }
+
+    fn prec(&self) -> Prec {
+        match self {
+            AssocOp::Binary(op) => match op {
+                BinOp::Or => Prec::Or,
+                BinOp::And => Prec::And,
+                BinOp::Eq
+                | BinOp::Ne
+                | BinOp::Lt
+                | BinOp::Le
+                | BinOp::Gt
+                | BinOp::Ge => Prec::Compare,
+                BinOp::BitOr => Prec::BitOr,
+                BinOp::BitXor => Prec::BitXor,
+                BinOp::BitAnd => Prec::BitAnd,
+                BinOp::Shl | BinOp::Shr => Prec::Shift,
+                BinOp::Add | BinOp::Sub => Prec::Sum,
+                BinOp::Mul | BinOp::Div | BinOp::Rem => Prec::Product,
+            },
+            AssocOp::Assign | AssocOp::AssignOp(_) => Prec::Assign,
+            AssocOp::Range { .. } => Prec::Range,
+            AssocOp::Cast => Prec::Cast,
+        }
+    }
+}
+impl Parser {
+    /// e.g. `a = b + c * -d`
+    pub fn parse_expr(&mut self) -> Result<Expr> {
+        self.parse_assoc(Prec::Assign)
+    }
+
+    /// like [Parser::parse_expr], but a block-like expression such as
+    /// `if a {}` is not continued by an infix operator, e.g. the statement
+    /// `if a {} - 1` is `if a {}` followed by `-1`
+    pub(crate) fn parse_stmt_expr(&mut self) -> Result<Expr> {
+        if !self.is_block_like_start() {
+            return self.parse_expr();
+        }
+        let expr = self.parse_primary()?;
+        if self.is_op(OperatorKind::Dot) || self.is_op(OperatorKind::Question) {
+            let expr = self.parse_postfix_rest(expr)?;
+            return self.parse_assoc_rest(expr, Prec::Assign);
+        }
+        Ok(expr)
+    }
+
+    /// the next token starts an expression that is
+    /// [block-like](Expr::is_block_like)
+    fn is_block_like_start(&self) -> bool {
+        match self.peek() {
+            Token::Keyword(
+                Keyword::r#if
+                | Keyword::r#match
+                | Keyword::r#loop
+                | Keyword::r#while
+                | Keyword::r#for,
+            ) => true,
+            Token::Lifetime(_) => true,
+            Token::Operator(op) => op.kind() == OperatorKind::OpenBrace,
+            _ => false,
+        }
+    }
+
+    /// the next token can start an expression, e.g. after `break` or `..`
+    fn can_begin_expr(&self) -> bool {
+        match self.peek() {
+            Token::Literal(_) | Token::Identifier(_) | Token::Lifetime(_) => {
+                true
+            }
+            Token::Keyword(keyword) => {
+                Self::is_path_keyword(keyword)
+                    || matches!(
+                        keyword,
+                        Keyword::r#true
+                            | Keyword::r#false
+                            | Keyword::r#if
+                            | Keyword::r#match
+                            | Keyword::r#loop
+                            | Keyword::r#while
+                            | Keyword::r#for
+                            | Keyword::r#break
+                            | Keyword::r#continue
+                            | Keyword::r#return
+                            | Keyword::r#box
+                    )
+            }
+            Token::Operator(op) => match op.kind() {
+                OperatorKind::OpenBrace => self.struct_literals,
+                kind => matches!(
+                    kind,
+                    OperatorKind::OpenParen
+                        | OperatorKind::OpenBracket
+                        | OperatorKind::Minus
+                        | OperatorKind::Not
+                        | OperatorKind::Star
+                        | OperatorKind::And
+                        | OperatorKind::AndAnd
+                        | OperatorKind::DotDot
+                        | OperatorKind::DotDotEq
+                ),
+            },
+            Token::Comment(_) | Token::EOF => false,
+        }
+    }
+
+    /// an expression whose infix operators bind at least as tight as `min`
+    fn parse_assoc(&mut self, min: Prec) -> Result<Expr> {
+        let lhs = match AssocOp::from_token(self.peek()) {
+            Some(AssocOp::Range { inclusive }) if min <= Prec::Range => {
+                let lo = self.bump().span;
+                self.parse_range(None, inclusive, lo)?
+            }
+            _ => self.parse_unary()?,
+        };
+        self.parse_assoc_rest(lhs, min)
+    }
+
+    /// the infix operators after `lhs`, see [Parser::parse_assoc]
+    fn parse_assoc_rest(&mut self, mut lhs: Expr, min: Prec) -> Result<Expr> {
+        while let Some(op) = AssocOp::from_token(self.peek()) {
+            let prec = op.prec();
+            if prec < min {
+                break;
+            }
+            let op_span = self.bump().span;
+            lhs = match op {
+                AssocOp::Cast => {
+                    let ty = self.parse_type()?;
+                    let span = lhs.span.to(ty.span);
+                    Expr::new(ExprKind::Cast(Box::new(lhs), Box::new(ty)), span)
+                }
+                AssocOp::Range { inclusive } => {
+                    let lo = lhs.span;
+                    self.parse_range(Some(lhs), inclusive, lo)?
+                }
+                AssocOp::Assign => {
+                    let rhs = self.parse_assoc(Prec::Assign)?;
+                    let span = lhs.span.to(rhs.span);
+                    let kind = ExprKind::Assign(Box::new(lhs), Box::new(rhs));
+                    Expr::new(kind, span)
+                }
+                AssocOp::AssignOp(op) => {
+                    let rhs = self.parse_assoc(Prec::Assign)?;
+                    let span = lhs.span.to(rhs.span);
+                    let op = Spanned::new(op, op_span);
+                    let kind =
+                        ExprKind::AssignOp(op, Box::new(lhs), Box::new(rhs));
+                    Expr::new(kind, span)
+                }
+                AssocOp::Binary(op) => {
+                    let rhs = self.parse_assoc(prec.next())?;
+                    let span = lhs.span.to(rhs.span);
+                    if op.is_comparison()
+                        && matches!(
+                            AssocOp::from_token(self.peek()),
+                            Some(AssocOp::Binary(next)) if next.is_comparison()
+                        )
+                    {
+                        return Err(Error::new(
+                            self.span(),
+                            "comparison operators cannot be chained",
+                        ));
+                    }
+                    let op = Spanned::new(op, op_span);
+                    let kind =
+                        ExprKind::Binary(op, Box::new(lhs), Box::new(rhs));
+                    Expr::new(kind, span)
+                }
+            };
+        }
+        Ok(lhs)
+    }
+
+    /// the rest of a range after `..`/`..=`, which started at `lo`
+    fn parse_range(
+        &mut self, start: Option<Expr>, inclusive: bool, lo: Span,
+    ) -> Result<Expr> {
+        let end = if self.can_begin_expr() {
+            Some(Box::new(self.parse_assoc(Prec::Range.next())?))
+        } else if inclusive {
+            return Err(Error::new(
+                self.prev_span(),
+                "inclusive range with no end",
+            ));
+        } else {
+            None
+        };
+        if matches!(
+            AssocOp::from_token(self.peek()),
+            Some(AssocOp::Range { .. })
+        ) {
+            return Err(Error::new(
+                self.span(),
+                "range operators cannot be chained",
+            ));
+        }
+        let span = lo.to(self.prev_span());
+        let start = start.map(Box::new);
+        Ok(Expr::new(ExprKind::Range { start, end, inclusive }, span))
+    }
+
+    /// e.g. `-a`, `&mut a`, `box a`
+    fn parse_unary(&mut self) -> Result<Expr> {
+        let lo = self.span();
+        if self.is_op(OperatorKind::Pound) {
+            let attrs = self.parse_outer_attrs()?;
+            let expr = self.parse_unary()?;
+            let span = lo.to(expr.span);
+            return Ok(Expr {
+                attrs: [attrs, expr.attrs].concat(),
+                span,
+                ..expr
+            });
+        }
+        let op = match self.peek() {
+            Token::Operator(op) => match op.kind() {
+                OperatorKind::Minus => Some(UnOp::Neg),
+                OperatorKind::Not => Some(UnOp::Not),
+                OperatorKind::Star => Some(UnOp::Deref),
+                OperatorKind::And => {
+                    self.bump();
+                    let mutable = self.eat_keyword(Keyword::r#mut);
+                    let expr = self.parse_unary()?;
+                    let span = lo.to(expr.span);
+                    let kind = ExprKind::Ref { mutable, expr: Box::new(expr) };
+                    return Ok(Expr::new(kind, span));
+                }
+                OperatorKind::AndAnd => {
+                    // `&&a` is `&(&a)`
+                    self.bump();
+                    let mutable = self.eat_keyword(Keyword::r#mut);
+                    let expr = self.parse_unary()?;
+                    let inner_lo = Span::new(
+                        lo.file(),
+                        lo.lo() + 1,
+                        lo.lo() + 1,
+                        lo.line(),
+                        lo.col() + 1,
+                    )
+                    .with_expansion(lo.expansion());
+                    let inner = Expr::new(
+                        ExprKind::Ref { mutable, expr: Box::new(expr) },
+                        inner_lo.to(self.prev_span()),
+                    );
+                    let kind = ExprKind::Ref {
+                        mutable: false,
+                        expr:    Box::new(inner),
+                    };
+                    return Ok(Expr::new(kind, lo.to(self.prev_span())));
+                }
+                _ => None,
+            },
+            Token::Keyword(Keyword::r#box) => {
+                self.bump();
+                let expr = self.parse_unary()?;
+                let span = lo.to(expr.span);
+                return Ok(Expr::new(ExprKind::Box(Box::new(expr)), span));
+            }
+            _ => None,
+        };
+        let Some(op) = op else {
+            return self.parse_postfix();
+        };
+        self.bump();
+        let expr = self.parse_unary()?;
+        let span = lo.to(expr.span);
+        Ok(Expr::new(ExprKind::Unary(op, Box::new(expr)), span))
+    }
+
+    /// e.g. `a.b`, `a.b(c)`, `a(b)`, `a[b]`, `a?`
+    fn parse_postfix(&mut self) -> Result<Expr> {
+        let expr = self.parse_primary()?;
+        self.parse_postfix_rest(expr)
+    }
+
+    fn parse_postfix_rest(&mut self, mut expr: Expr) -> Result<Expr> {
+        loop {
+            let Token::Operator(op) = self.peek() else {
+                return Ok(expr);
+            };
+            let kind = match op.kind() {
+                OperatorKind::Question => {
+                    self.bump();
+                    ExprKind::Try(Box::new(expr))
+                }
+                OperatorKind::Dot => {
+                    self.bump();
+                    self.parse_dot_rest(expr)?
+                }
+                OperatorKind::OpenParen => {
+                    self.bump();
+                    let args = self.parse_delimited(
+                        OperatorKind::CloseParen,
+                        Self::parse_expr,
+                    )?;
+                    ExprKind::Call(Box::new(expr), args)
+                }
+                OperatorKind::OpenBracket => {
+                    self.bump();
+                    let index =
+                        self.with_struct_literals(true, Self::parse_expr)?;
+                    self.expect_op(OperatorKind::CloseBracket)?;
+                    ExprKind::Index(Box::new(expr), Box::new(index))
+                }
+                _ => return Ok(expr),
+            };
+            let span = match &kind {
+                ExprKind::Try(inner)
+                | ExprKind::Call(inner, _)
+                | ExprKind::Index(inner, _)
+                | ExprKind::Field(inner, _)
+                | ExprKind::MethodCall { receiver: inner, .. } => inner.span,
+                _ => unreachable!(),
+            };
+            expr = Expr::new(kind, span.to(self.prev_span()));
+        }
+    }
+
+    /// after `.`, e.g. `b` or `b(c)` or `0`
+    fn parse_dot_rest(&mut self, expr: Expr) -> Result<ExprKind> {
+        let name = match self.peek().clone() {
+            Token::Identifier(ident) => ident,
+            Token::Literal(Literal::Int(IntLit {
+                value,
+                suffix: None,
+                ..
+            })) => Identifier::new(value.to_string()),
+            Token::Literal(Literal::Float(_)) => {
+                return Err(Error::new(
+                    self.span(),
+                    "nested tuple fields like `a.0.1` need parentheses, e.g. \
+                     `(a.0).1`",
+                ));
+            }
+            _ => return Err(self.unexpected("field name or method")),
+        };
+        let name = Spanned::new(name, self.bump().span);
+        if !self.is_op(OperatorKind::OpenParen)
+            || name.node.as_str().starts_with(char::is_numeric)
+        {
+            return Ok(ExprKind::Field(Box::new(expr), name));
+        }
+        self.bump();
+        let args =
+            self.parse_delimited(OperatorKind::CloseParen, Self::parse_expr)?;
+        Ok(ExprKind::MethodCall {
+            receiver: Box::new(expr),
+            method: name,
+            args,
+        })
+    }
+
+    /// literals, paths, groups and expressions starting with a keyword
+    fn parse_primary(&mut self) -> Result<Expr> {
+        let lo = self.span();
+        let kind = match self.peek().clone() {
+            Token::Literal(literal) => {
+                self.bump();
+                ExprKind::Literal(literal)
+            }
+            Token::Keyword(Keyword::r#true) => {
+                self.bump();
+                ExprKind::Literal(Literal::Bool(true))
+            }
+            Token::Keyword(Keyword::r#false) => {
+                self.bump();
+                ExprKind::Literal(Literal::Bool(false))
+            }
+            Token::Lifetime(label) => {
+                self.bump();
+                let label = Spanned::new(label, lo);
+                self.expect_op(OperatorKind::Colon)?;
+                return self.parse_loop(Some(label));
+            }
+            Token::Keyword(
+                Keyword::r#loop | Keyword::r#while | Keyword::r#for,
+            ) => return self.parse_loop(None),
+            Token::Keyword(Keyword::r#if) => return self.parse_if(),
+            Token::Keyword(Keyword::r#match) => return self.parse_match(),
+            Token::Keyword(Keyword::r#break) => {
+                self.bump();
+                let label = self.parse_label();
+                let value = match self.can_begin_expr() {
+                    true => Some(Box::new(self.parse_expr()?)),
+                    false => None,
+                };
+                ExprKind::Break { label, value }
+            }
+            Token::Keyword(Keyword::r#continue) => {
+                self.bump();
+                ExprKind::Continue { label: self.parse_label() }
+            }
+            Token::Keyword(Keyword::r#return) => {
+                self.bump();
+                match self.can_begin_expr() {
+                    true => {
+                        ExprKind::Return(Some(Box::new(self.parse_expr()?)))
+                    }
+                    false => ExprKind::Return(None),
+                }
+            }
+            Token::Operator(op) => match op.kind() {
+                OperatorKind::OpenParen => self.parse_paren()?,
+                OperatorKind::OpenBracket => self.parse_array()?,
+                OperatorKind::OpenBrace => ExprKind::Block(self.parse_block()?),
+                _ => return Err(self.unexpected("expression")),
+            },
+            _ if self.is_path_start() => {
+                let path = self.parse_path(PathStyle::Expr)?;
+                if self.is_macro_bang() {
+                    self.parse_macro_call(path)?
+                } else if self.struct_literals
+                    && self.is_op(OperatorKind::OpenBrace)
+                {
+                    self.parse_struct_rest(path)?
+                } else {
+                    ExprKind::Path(path)
+                }
+            }
+            _ => return Err(self.unexpected("expression")),
+        };
+        Ok(Expr::new(kind, lo.to(self.prev_span())))
+    }
+
+    /// e.g. `'outer` after `break`
+    fn parse_label(&mut self) -> Option<Label> {
+        let Token::Lifetime(label) = self.peek().clone() else {
+            return None;
+        };
+        Some(Spanned::new(label, self.bump().span))
+    }
+
+    /// e.g. `()`, `(a)`, `(a,)`
+    fn parse_paren(&mut self) -> Result<ExprKind> {
+        self.expect_op(OperatorKind::OpenParen)?;
+        if self.eat_op(OperatorKind::CloseParen) {
+            return Ok(ExprKind::Tuple(Vec::new()));
+        }
+        let first = self.with_struct_literals(true, Self::parse_expr)?;
+        if self.eat_op(OperatorKind::CloseParen) {
+            return Ok(ExprKind::Paren(Box::new(first)));
+        }
+        if !self.eat_op(OperatorKind::Comma) {
+            return Err(self.unexpected("`,` or `)`"));
+        }
+        let mut elems = vec![first];
+        elems.extend(
+            self.parse_delimited(OperatorKind::CloseParen, Self::parse_expr)?,
+        );
+        Ok(ExprKind::Tuple(elems))
+    }
+
+    /// e.g. `[]`, `[a, b]`, `[a; 3]`
+    fn parse_array(&mut self) -> Result<ExprKind> {
+        self.expect_op(OperatorKind::OpenBracket)?;
+        if self.eat_op(OperatorKind::CloseBracket) {
+            return Ok(ExprKind::Array(Vec::new()));
+        }
+        let first = self.with_struct_literals(true, Self::parse_expr)?;
+        if self.eat_op(OperatorKind::Semi) {
+            let len = self.with_struct_literals(true, Self::parse_expr)?;
+            self.expect_op(OperatorKind::CloseBracket)?;
+            return Ok(ExprKind::Repeat(Box::new(first), Box::new(len)));
+        }
+        let mut elems = vec![first];
+        if self.eat_op(OperatorKind::Comma) {
+            elems.extend(self.parse_delimited(
+                OperatorKind::CloseBracket,
+                Self::parse_expr,
+            )?);
+        } else {
+            self.expect_op(OperatorKind::CloseBracket)?;
+        }
+        Ok(ExprKind::Array(elems))
+    }
+
+    /// `{ x: 1, y, ..rest }` after the path of a struct literal
+    fn parse_struct_rest(&mut self, path: ast::Path) -> Result<ExprKind> {
+        self.expect_op(OperatorKind::OpenBrace)?;
+        let mut fields = Vec::new();
+        let mut rest = None;
+        self.with_struct_literals(true, |this| {
+            while !this.eat_op(OperatorKind::CloseBrace) {
+                if this.eat_op(OperatorKind::DotDot) {
+                    rest = Some(Box::new(this.parse_expr()?));
+                    this.expect_op(OperatorKind::CloseBrace)?;
+                    break;
+                }
+                let name = this.expect_ident()?;
+                let expr = if this.eat_op(OperatorKind::Colon) {
+                    this.parse_expr()?
+                } else {
+                    let segment =
+                        ast::PathSegment { ident: name.clone(), args: None };
+                    let path = ast::Path::new(vec![segment], name.span);
+                    Expr::new(ExprKind::Path(path), name.span)
+                };
+                let span = name.span.to(expr.span);
+                fields.push(ExprField { name, expr, span });
+                if !this.eat_op(OperatorKind::Comma) {
+                    this.expect_op(OperatorKind::CloseBrace)?;
+                    break;
+                }
+            }
+            Ok(())
+        })?;
+        Ok(ExprKind::Struct { path, fields, rest })
+    }
+
+    /// e.g. `if a {} else if b {} else {}`
+    fn parse_if(&mut self) -> Result<Expr> {
+        let lo = self.expect_keyword(Keyword::r#if)?;
+        let cond = self.with_struct_literals(false, Self::parse_expr)?;
+        let then_branch = self.parse_block()?;
+        let else_branch = if self.eat_keyword(Keyword::r#else) {
+            Some(Box::new(if self.is_keyword(Keyword::r#if) {
+                self.parse_if()?
+            } else {
+                Expr::from(self.parse_block()?)
+            }))
+        } else {
+            None
+        };
+        let kind =
+            ExprKind::If { cond: Box::new(cond), then_branch, else_branch };
+        Ok(Expr::new(kind, lo.to(self.prev_span())))
+    }
+
+    /// e.g. `match a { b => c, d if e => {} }`
+    fn parse_match(&mut self) -> Result<Expr> {
+        let lo = self.expect_keyword(Keyword::r#match)?;
+        let scrutinee = self.with_struct_literals(false, Self::parse_expr)?;
+        self.expect_op(OperatorKind::OpenBrace)?;
+        let mut arms = Vec::new();
+        self.with_struct_literals(true, |this| {
+            while !this.eat_op(OperatorKind::CloseBrace) {
+                if this.is_eof() {
+                    return Err(this.unexpected("`}`"));
+                }
+                let start = this.pos;
+                match this.parse_arm() {
+                    Ok(arm) => arms.push(arm),
+                    Err(error) => {
+                        // skips to the next arm
+                        this.recover(error, start, |this| {
+                            this.is_op(OperatorKind::Comma)
+                        });
+                        this.eat_op(OperatorKind::Comma);
+                    }
+                }
+            }
+            Ok(())
+        })?;
+        let kind = ExprKind::Match { scrutinee: Box::new(scrutinee), arms };
+        Ok(Expr::new(kind, lo.to(self.prev_span())))
+    }
+
+    /// e.g. `a => b,`, `a if c => {}`
+    fn parse_arm(&mut self) -> Result<ast::Arm> {
+        let lo = self.span();
+        let attrs = self.parse_outer_attrs()?;
+        let pat = self.parse_pat()?;
+        let guard = match self.eat_keyword(Keyword::r#if) {
+            true => Some(self.parse_expr()?),
+            false => None,
+        };
+        self.expect_op(OperatorKind::FatArrow)?;
+        let body = self.parse_stmt_expr()?;
+        let span = lo.to(body.span);
+        if !self.eat_op(OperatorKind::Comma)
+            && !body.is_block_like()
+            && !self.is_op(OperatorKind::CloseBrace)
+        {
+            return Err(self.unexpected("`,` or `}`"));
+        }
+        Ok(ast::Arm { attrs, pat, guard, body, span })
+    }
+
+    /// e.g. `loop {}`, `while a {}`, `for a in b {}`, after the label
+    fn parse_loop(&mut self, label: Option<Label>) -> Result<Expr> {
+        let lo = label.as_ref().map_or(self.span(), |label| label.span);
+        let kind = match self.peek() {
+            Token::Keyword(Keyword::r#loop) => {
+                self.bump();
+                ExprKind::Loop { label, body: self.parse_block()? }
+            }
+            Token::Keyword(Keyword::r#while) => {
+                self.bump();
+                let cond =
+                    self.with_struct_literals(false, Self::parse_expr)?;
+                let body = self.parse_block()?;
+                ExprKind::While { label, cond: Box::new(cond), body }
+            }
+            Token::Keyword(Keyword::r#for) => {
+                self.bump();
+                let pat = self.parse_pat()?;
+                self.expect_keyword(Keyword::r#in)?;
+                let iter =
+                    self.with_struct_literals(false, Self::parse_expr)?;
+                let body = self.parse_block()?;
+                ExprKind::For { label, pat, iter: Box::new(iter), body }
+            }
+            _ => return Err(self.unexpected("`loop`, `while` or `for`")),
+        };
+        Ok(Expr::new(kind, lo.to(self.prev_span())))
+    }
+}
+impl From<Block> for Expr {
+    fn from(block: Block) -> Self {
+        let span = block.span;
+        Expr::new(ExprKind::Block(block), span)
+    }
+}
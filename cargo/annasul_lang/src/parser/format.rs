@@ -0,0 +1,481 @@
+// Copyright (c) 2025 air (https://yuanair.github.io).
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published by
+// the Free Software Foundation, version 3 of the License only.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+use std::ops::Range;
+
+use crate::{
+    lexer::{
+        scanner::{AnnasulScanner, Scanner},
+        token::{Literal, OperatorKind, Token},
+    },
+    parser::{
+        Parser,
+        ast::{
+            Expr,
+            ExprKind,
+            FormatAlign,
+            FormatArg,
+            FormatArgs,
+            FormatMacro,
+            FormatPiece,
+            FormatSpec,
+            FormatTrait,
+            Placeholder,
+        },
+    },
+    utils::{
+        error::{Error, Result},
+        span::{FileId, Span, Spanned},
+    },
+};
+/// the source position of a byte of [Template::text]
+#[derive(Debug, Clone, Copy)]
+struct Pos {
+    offset: usize,
+    line:   usize,
+    col:    usize,
+}
+/// an unescaped format string that knows where each of its bytes came from,
+/// so errors and placeholder expressions point inside the literal
+struct Template {
+    text:      String,
+    /// one more than the bytes of `text`, the last is the closing quote
+    pos:       Vec<Pos>,
+    file:      FileId,
+    /// see [Span::expansion]
+    expansion: u32,
+}
+impl Template {
+    /// `raw` is the source text of the literal at `span`, without it the
+    /// literal is assumed to have no escapes
+    fn new(text: String, span: Span, raw: Option<&str>) -> Self {
+        let quoted;
+        let (raw, escapes) = match raw {
+            Some(raw) => (raw, !raw.starts_with('r')),
+            None => {
+                quoted = format!("\"{text}\"");
+                (quoted.as_str(), false)
+            }
+        };
+        let mut cursor =
+            Pos { offset: span.lo(), line: span.line(), col: span.col() };
+        let mut chars = raw.chars().peekable();
+        let advance = |cursor: &mut Pos, c: char| {
+            cursor.offset += c.len_utf8();
+            match c {
+                '\n' => {
+                    cursor.line += 1;
+                    cursor.col = 1;
+                }
+                _ => cursor.col += 1,
+            }
+        };
+        // the prefix, e.g. `"` or `r#"`
+        for c in chars.by_ref() {
+            advance(&mut cursor, c);
+            if c == '"' {
+                break;
+            }
+        }
+        let mut pos = Vec::with_capacity(text.len() + 1);
+        for c in text.chars() {
+            // line continuations produce no chars
+            while escapes && chars.peek() == Some(&'\\') {
+                let mut ahead = chars.clone();
+                ahead.next();
+                if ahead.next() != Some('\n') {
+                    break;
+                }
+                for c in chars.by_ref().take(2) {
+                    advance(&mut cursor, c);
+                }
+                while let Some(&c) = chars.peek().filter(|c| c.is_whitespace())
+                {
+                    chars.next();
+                    advance(&mut cursor, c);
+                }
+            }
+            pos.extend(std::iter::repeat_n(cursor, c.len_utf8()));
+            let Some(first) = chars.next() else {
+                continue;
+            };
+            advance(&mut cursor, first);
+            if escapes && first == '\\' {
+                let Some(kind) = chars.next() else {
+                    continue;
+                };
+                advance(&mut cursor, kind);
+                let len = match kind {
+                    'x' => 2,
+                    'u' => chars.clone().take_while(|&c| c != '}').count() + 1,
+                    _ => 0,
+                };
+                for c in chars.by_ref().take(len) {
+                    advance(&mut cursor, c);
+                }
+            }
+        }
+        pos.push(cursor);
+        Self { text, pos, file: span.file(), expansion: span.expansion() }
+    }
+
+    /// the span of `range` of [Template::text]
+    fn span(&self, range: Range<usize>) -> Span {
+        let (lo, hi) = (self.pos[range.start], self.pos[range.end]);
+        Span::new(
+            self.file,
+            lo.offset,
+            hi.offset.max(lo.offset),
+            lo.line,
+            lo.col,
+        )
+        .with_expansion(self.expansion)
+    }
+}
+impl Parser {
+    /// e.g. `("{a}, {}", b)` of `println!("{a}, {}", b)`, the `println!` is
+    /// consumed already
+    pub(crate) fn parse_format_args(
+        &mut self, mac: FormatMacro,
+    ) -> Result<FormatArgs> {
+        let close = match self.peek() {
+            Token::Operator(op) => match op.kind() {
+                OperatorKind::OpenParen => OperatorKind::CloseParen,
+                OperatorKind::OpenBracket => OperatorKind::CloseBracket,
+                OperatorKind::OpenBrace => OperatorKind::CloseBrace,
+                _ => return Err(self.unexpected("`(`")),
+            },
+            _ => return Err(self.unexpected("`(`")),
+        };
+        let open = self.bump().span;
+        let (text, template) = match self.peek().clone() {
+            Token::Literal(Literal::String(text)) => (text, self.bump().span),
+            Token::Operator(op)
+                if op.kind() == close
+                    && matches!(
+                        mac,
+                        FormatMacro::Println | FormatMacro::Eprintln
+                    ) =>
+            {
+                self.bump();
+                return Ok(FormatArgs {
+                    mac,
+                    pieces: Vec::new(),
+                    args: Vec::new(),
+                    template: open.shrink_to_hi(),
+                });
+            }
+            Token::Operator(op) if op.kind() == close => {
+                return Err(Error::new(
+                    open.to(self.span()),
+                    format!(
+                        "`{}!` requires at least a format string argument",
+                        mac.as_str()
+                    ),
+                ));
+            }
+            _ => {
+                return Err(Error::new(
+                    self.span(),
+                    "format argument must be a string literal",
+                ));
+            }
+        };
+        let args = match self.eat_op(close) {
+            true => Vec::new(),
+            false => {
+                self.expect_op(OperatorKind::Comma)?;
+                self.parse_delimited(close, Self::parse_expr)?
+            }
+        };
+        let raw = self
+            .sources
+            .get(&template.file())
+            .and_then(|source| source.get(template.lo()..template.hi()));
+        let template_text = Template::new(text, template, raw);
+        let pieces = self.parse_template(&template_text);
+        let mut used = vec![false; args.len()];
+        for piece in &pieces {
+            let FormatPiece::Placeholder(Placeholder {
+                arg: FormatArg::Index(index),
+                span,
+                ..
+            }) = piece
+            else {
+                continue;
+            };
+            match used.get_mut(*index) {
+                Some(used) => *used = true,
+                None => {
+                    let there = match args.len() {
+                        0 => "no arguments were given".to_string(),
+                        1 => "there is 1 argument".to_string(),
+                        n => format!("there are {n} arguments"),
+                    };
+                    self.errors.push(Error::new(
+                        *span,
+                        format!(
+                            "invalid reference to positional argument {index} \
+                             ({there})"
+                        ),
+                    ));
+                }
+            }
+        }
+        for (arg, used) in args.iter().zip(used) {
+            if !used {
+                self.errors.push(Error::new(arg.span, "argument never used"));
+            }
+        }
+        Ok(FormatArgs { mac, pieces, args, template })
+    }
+
+    /// splits the format string at `{...}`, errors are recovered from
+    fn parse_template(&mut self, template: &Template) -> Vec<FormatPiece> {
+        let text = template.text.as_str();
+        let mut pieces = Vec::new();
+        let mut literal = String::new();
+        let mut next = 0;
+        let mut i = 0;
+        while let Some(c) = text[i..].chars().next() {
+            let escaped = text[i + c.len_utf8()..].starts_with(c);
+            match c {
+                '{' | '}' if escaped => {
+                    literal.push(c);
+                    i += 2;
+                }
+                '}' => {
+                    self.errors.push(Error::new(
+                        template.span(i..i + 1),
+                        "invalid format string: unmatched `}` found",
+                    ));
+                    i += 1;
+                }
+                '{' => {
+                    let Some(close) = Self::placeholder_end(text, i + 1) else {
+                        self.errors.push(Error::new(
+                            template.span(i..i + 1),
+                            "invalid format string: expected `}` but string \
+                             was terminated",
+                        ));
+                        break;
+                    };
+                    if !literal.is_empty() {
+                        pieces.push(FormatPiece::Literal(std::mem::take(
+                            &mut literal,
+                        )));
+                    }
+                    let placeholder = self.parse_placeholder(
+                        template,
+                        i..close + 1,
+                        &mut next,
+                    );
+                    pieces.push(FormatPiece::Placeholder(placeholder));
+                    i = close + 1;
+                }
+                c => {
+                    literal.push(c);
+                    i += c.len_utf8();
+                }
+            }
+        }
+        if !literal.is_empty() {
+            pieces.push(FormatPiece::Literal(literal));
+        }
+        pieces
+    }
+
+    /// the `}` closing the placeholder that starts at `start`, braces and
+    /// strings of the expression are skipped
+    fn placeholder_end(text: &str, start: usize) -> Option<usize> {
+        let mut depth = 0;
+        let mut in_string = false;
+        let mut chars = text[start..].char_indices();
+        while let Some((i, c)) = chars.next() {
+            match c {
+                '\\' if in_string => {
+                    chars.next();
+                }
+                '"' => in_string = !in_string,
+                _ if in_string => {}
+                '{' => depth += 1,
+                '}' if depth == 0 => return Some(start + i),
+                '}' => depth -= 1,
+                _ => {}
+            }
+        }
+        None
+    }
+
+    /// e.g. `{}`, `{0:?}`, `{*number:>5}`, `range` is from `{` to `}`
+    fn parse_placeholder(
+        &mut self, template: &Template, range: Range<usize>, next: &mut usize,
+    ) -> Placeholder {
+        let span = template.span(range.clone());
+        let body_lo = range.start + 1;
+        let body = &template.text[body_lo..range.end - 1];
+        let (tokens, lex_errors) = AnnasulScanner::new(body.as_bytes())
+            .with_file(template.file)
+            .tokenize();
+        // relative byte ranges, and the tokens with spans in the source
+        let mut ranges = Vec::new();
+        let mut mapped = Vec::new();
+        for token in tokens {
+            if matches!(token.node, Token::Comment(_)) {
+                continue;
+            }
+            let lo = body_lo + token.span.lo().min(body.len());
+            let hi = body_lo + token.span.hi().min(body.len());
+            ranges.push(lo..hi);
+            mapped.push(Spanned::new(token.node, template.span(lo..hi)));
+        }
+        let is_colon = |token: &Spanned<Token>| match &token.node {
+            Token::Operator(op) => op.kind() == OperatorKind::Colon,
+            _ => false,
+        };
+        let (arg, colon) = match mapped.first() {
+            None | Some(Spanned { node: Token::EOF, .. }) => {
+                *next += 1;
+                (FormatArg::Index(*next - 1), None)
+            }
+            Some(first) if is_colon(first) => {
+                *next += 1;
+                (FormatArg::Index(*next - 1), Some(0))
+            }
+            Some(_)
+                if template.text[ranges[0].clone()]
+                    .bytes()
+                    .all(|b| b.is_ascii_digit())
+                    && mapped.get(1).is_none_or(|token| {
+                        is_colon(token) || token.node == Token::EOF
+                    }) =>
+            {
+                let index = template.text[ranges[0].clone()]
+                    .parse()
+                    .unwrap_or(usize::MAX);
+                let colon = mapped.get(1).filter(|t| is_colon(t)).map(|_| 1);
+                (FormatArg::Index(index), colon)
+            }
+            Some(_) => {
+                let mut parser = Parser::new(mapped.clone());
+                parser.sources = self.sources.clone();
+                let expr = match parser.parse_expr() {
+                    Ok(expr) => expr,
+                    Err(error) => {
+                        parser.errors.push(error);
+                        Expr::new(ExprKind::Err, span)
+                    }
+                };
+                let colon = match parser.peek() {
+                    Token::EOF => None,
+                    Token::Operator(op) if op.kind() == OperatorKind::Colon => {
+                        Some(parser.pos)
+                    }
+                    _ => {
+                        let error = parser.unexpected("`:` or `}`");
+                        parser.errors.push(error);
+                        None
+                    }
+                };
+                self.errors.append(&mut parser.errors);
+                (FormatArg::Expr(Box::new(expr)), colon)
+            }
+        };
+        // the spec may contain chars like `'` that are not tokens
+        let spec_lo = colon.map(|colon| ranges[colon].end);
+        for error in lex_errors {
+            let lo = body_lo + error.span().lo().min(body.len());
+            if spec_lo.is_none_or(|spec_lo| lo < spec_lo) {
+                let hi = body_lo + error.span().hi().min(body.len());
+                self.errors
+                    .push(Error::new(template.span(lo..hi), error.msg()));
+            }
+        }
+        let spec = match spec_lo {
+            Some(spec_lo) => {
+                self.parse_format_spec(template, spec_lo..range.end - 1)
+            }
+            None => FormatSpec::default(),
+        };
+        Placeholder { arg, spec, span }
+    }
+
+    /// e.g. `*^+#08.3x` of `{a:*^+#08.3x}`
+    fn parse_format_spec(
+        &mut self, template: &Template, range: Range<usize>,
+    ) -> FormatSpec {
+        let text = &template.text[range.clone()];
+        let chars: Vec<_> = text.char_indices().collect();
+        let align = |c: char| match c {
+            '<' => Some(FormatAlign::Left),
+            '^' => Some(FormatAlign::Center),
+            '>' => Some(FormatAlign::Right),
+            _ => None,
+        };
+        let mut spec = FormatSpec::default();
+        let mut k = 0;
+        match chars.as_slice() {
+            [(_, fill), (_, c), ..] if align(*c).is_some() => {
+                spec.fill = *fill;
+                spec.align = align(*c);
+                k = 2;
+            }
+            [(_, c), ..] if align(*c).is_some() => {
+                spec.align = align(*c);
+                k = 1;
+            }
+            _ => {}
+        }
+        let eat = |k: &mut usize, c: char| {
+            let eaten = chars.get(*k).is_some_and(|&(_, next)| next == c);
+            if eaten {
+                *k += 1;
+            }
+            eaten
+        };
+        spec.sign_plus = eat(&mut k, '+');
+        spec.alternate = eat(&mut k, '#');
+        spec.zero_pad = eat(&mut k, '0');
+        let offset = |k: usize| chars.get(k).map_or(text.len(), |&(i, _)| i);
+        let number = |k: &mut usize| {
+            let start = *k;
+            while chars.get(*k).is_some_and(|(_, c)| c.is_ascii_digit()) {
+                *k += 1;
+            }
+            (start < *k).then(|| {
+                text[offset(start)..offset(*k)].parse().unwrap_or(usize::MAX)
+            })
+        };
+        spec.width = number(&mut k);
+        if eat(&mut k, '.') {
+            spec.precision = number(&mut k);
+            if spec.precision.is_none() {
+                let lo = range.start + offset(k - 1);
+                self.errors.push(Error::new(
+                    template.span(lo..lo + 1),
+                    "invalid format string: expected a precision after `.`",
+                ));
+            }
+        }
+        let suffix = &text[offset(k)..];
+        match FormatTrait::from_suffix(suffix) {
+            Some(format_trait) => spec.format_trait = format_trait,
+            None => self.errors.push(Error::new(
+                template.span(range.start + offset(k)..range.end),
+                format!(
+                    "invalid format string: unknown format trait `{suffix}`"
+                ),
+            )),
+        }
+        spec
+    }
+}
@@ -0,0 +1,481 @@
+// Copyright (c) 2025 air (https://yuanair.github.io).
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published by
+// the Free Software Foundation, version 3 of the License only.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+use crate::{
+    lexer::token::{CommentType, Keyword, Literal, OperatorKind, Token},
+    parser::{
+        Parser,
+        ast::{
+            Expr,
+            FieldDef,
+            FnDef,
+            FnSig,
+            GenericParam,
+            Generics,
+            Item,
+            ItemKind,
+            Module,
+            NodeId,
+            Param,
+            Path,
+            SelfParam,
+            TypeKind,
+            UseTree,
+            UseTreeKind,
+            Variant,
+            VariantData,
+            Visibility,
+            WherePredicate,
+        },
+    },
+    utils::{
+        error::{Error, Result},
+        span::Spanned,
+    },
+};
+impl Parser {
+    /// the items of a whole file, see [Parser::take_errors]
+    pub fn parse_module(&mut self) -> Module {
+        let lo = self.span();
+        let docs = self.docs_before(CommentType::Outer);
+        let attrs = self.parse_inner_attrs();
+        let items = self.parse_items(false);
+        Module { docs, attrs, items, span: lo.to(self.span()) }
+    }
+
+    /// items until `}` if `braced`, which is consumed, or until the end of
+    /// the file, an item with a syntax error is a [ItemKind::Err]
+    pub(crate) fn parse_items(&mut self, braced: bool) -> Vec<Item> {
+        let mut items = Vec::new();
+        loop {
+            if braced && self.eat_op(OperatorKind::CloseBrace) {
+                break;
+            }
+            if self.is_eof() {
+                if braced {
+                    let error = self.unexpected("`}`");
+                    self.errors.push(error);
+                }
+                break;
+            }
+            let start = self.pos;
+            let lo = self.span();
+            match self.parse_item() {
+                Ok(item) => items.push(item),
+                Err(error) => {
+                    self.recover(error, start, Self::is_item_start);
+                    items.push(Item {
+                        id:    NodeId::DUMMY,
+                        docs:  Vec::new(),
+                        attrs: Vec::new(),
+                        vis:   Visibility::Inherited,
+                        kind:  ItemKind::Err,
+                        span:  lo.to(self.prev_span()),
+                    });
+                }
+            }
+        }
+        items
+    }
+
+    /// the next token starts an [Item], or its attributes
+    pub(crate) fn is_item_start(&self) -> bool {
+        self.is_op(OperatorKind::Pound)
+            || matches!(
+                self.peek(),
+                Token::Keyword(
+                    Keyword::r#pub
+                        | Keyword::r#priv
+                        | Keyword::r#fn
+                        | Keyword::r#struct
+                        | Keyword::r#enum
+                        | Keyword::r#union
+                        | Keyword::r#trait
+                        | Keyword::r#impl
+                        | Keyword::r#mod
+                        | Keyword::r#use
+                        | Keyword::r#const
+                        | Keyword::r#static
+                        | Keyword::r#type
+                        | Keyword::r#extern
+                        | Keyword::r#marco
+                )
+            )
+    }
+
+    /// e.g. `/// doc` `#[inline]` `pub fn a() {}`
+    pub fn parse_item(&mut self) -> Result<Item> {
+        let mut docs = self.docs_before(CommentType::Inner);
+        let lo = self.span();
+        let attrs = self.parse_outer_attrs()?;
+        if !attrs.is_empty() {
+            docs.extend(self.docs_before(CommentType::Inner));
+        }
+        let vis = self.parse_visibility();
+        let kind = match self.peek() {
+            Token::Keyword(Keyword::r#fn) => ItemKind::Fn(self.parse_fn()?),
+            Token::Keyword(Keyword::r#struct) => {
+                self.bump();
+                let name = self.expect_ident()?;
+                let mut generics = self.parse_generics()?;
+                generics.where_clause = self.parse_where_clause()?;
+                let data = self.parse_variant_data()?;
+                if !matches!(data, VariantData::Struct(_)) {
+                    generics.where_clause.extend(self.parse_where_clause()?);
+                    self.expect_op(OperatorKind::Semi)?;
+                }
+                ItemKind::Struct { name, generics, data }
+            }
+            Token::Keyword(Keyword::r#enum) => {
+                self.bump();
+                let name = self.expect_ident()?;
+                let mut generics = self.parse_generics()?;
+                generics.where_clause = self.parse_where_clause()?;
+                self.expect_op(OperatorKind::OpenBrace)?;
+                let variants = self.parse_delimited(
+                    OperatorKind::CloseBrace,
+                    Self::parse_variant,
+                )?;
+                ItemKind::Enum { name, generics, variants }
+            }
+            Token::Keyword(Keyword::r#union) => {
+                self.bump();
+                let name = self.expect_ident()?;
+                let mut generics = self.parse_generics()?;
+                generics.where_clause = self.parse_where_clause()?;
+                self.expect_op(OperatorKind::OpenBrace)?;
+                let fields = self.parse_delimited(
+                    OperatorKind::CloseBrace,
+                    Self::parse_named_field,
+                )?;
+                ItemKind::Union { name, generics, fields }
+            }
+            Token::Keyword(Keyword::r#trait) => {
+                self.bump();
+                let name = self.expect_ident()?;
+                ItemKind::Trait { name, items: self.parse_item_block()? }
+            }
+            Token::Keyword(Keyword::r#impl) => {
+                self.bump();
+                let mut generics = self.parse_generics()?;
+                let ty = self.parse_type()?;
+                let (of_trait, self_ty) = if self.eat_keyword(Keyword::r#for) {
+                    let TypeKind::Path(path) = ty.kind else {
+                        return Err(Error::new(ty.span, "expected a trait"));
+                    };
+                    (Some(path), self.parse_type()?)
+                } else {
+                    (None, ty)
+                };
+                generics.where_clause = self.parse_where_clause()?;
+                let items = self.parse_item_block()?;
+                ItemKind::Impl { generics, of_trait, self_ty, items }
+            }
+            Token::Keyword(Keyword::r#mod) => {
+                self.bump();
+                let name = self.expect_ident()?;
+                let module = if self.eat_op(OperatorKind::Semi) {
+                    None
+                } else {
+                    let lo = self.expect_op(OperatorKind::OpenBrace)?;
+                    let docs = self.docs_before(CommentType::Outer);
+                    let attrs = self.parse_inner_attrs();
+                    let items = self.parse_items(true);
+                    let span = lo.to(self.prev_span());
+                    Some(Module { docs, attrs, items, span })
+                };
+                ItemKind::Mod { name, module }
+            }
+            Token::Keyword(Keyword::r#use) => {
+                self.bump();
+                let tree = self.parse_use_tree()?;
+                self.expect_op(OperatorKind::Semi)?;
+                ItemKind::Use(tree)
+            }
+            Token::Keyword(Keyword::r#const) => {
+                self.bump();
+                let name = self.expect_ident()?;
+                self.expect_op(OperatorKind::Colon)?;
+                let ty = self.parse_type()?;
+                let value = self.parse_item_value()?;
+                ItemKind::Const { name, ty, value }
+            }
+            Token::Keyword(Keyword::r#static) => {
+                self.bump();
+                let mutable = self.eat_keyword(Keyword::r#mut);
+                let name = self.expect_ident()?;
+                self.expect_op(OperatorKind::Colon)?;
+                let ty = self.parse_type()?;
+                let value = self.parse_item_value()?;
+                ItemKind::Static { name, mutable, ty, value }
+            }
+            Token::Keyword(Keyword::r#type) => {
+                self.bump();
+                let name = self.expect_ident()?;
+                let ty = match self.eat_op(OperatorKind::Eq) {
+                    true => Some(self.parse_type()?),
+                    false => None,
+                };
+                self.expect_op(OperatorKind::Semi)?;
+                ItemKind::TypeAlias { name, ty }
+            }
+            Token::Keyword(Keyword::r#extern) => {
+                self.bump();
+                let abi = match self.peek().clone() {
+                    Token::Literal(Literal::String(abi)) => {
+                        Some(Spanned::new(abi, self.bump().span))
+                    }
+                    _ => None,
+                };
+                ItemKind::Extern { abi, items: self.parse_item_block()? }
+            }
+            Token::Keyword(Keyword::r#marco) => {
+                ItemKind::Marco(self.parse_macro_def()?)
+            }
+            _ if self.is_macro_call_start() => {
+                ItemKind::MacCall(self.parse_item_mac_call()?)
+            }
+            _ => return Err(self.unexpected("item")),
+        };
+        let span = lo.to(self.prev_span());
+        Ok(Item { id: NodeId::DUMMY, docs, attrs, vis, kind, span })
+    }
+
+    /// e.g. `pub`, `priv`, or nothing
+    fn parse_visibility(&mut self) -> Visibility {
+        match self.peek() {
+            Token::Keyword(Keyword::r#pub) => {
+                Visibility::Public(self.bump().span)
+            }
+            Token::Keyword(Keyword::r#priv) => {
+                Visibility::Private(self.bump().span)
+            }
+            _ => Visibility::Inherited,
+        }
+    }
+
+    /// e.g. `{ fn a(); }` of a trait, impl or extern block
+    fn parse_item_block(&mut self) -> Result<Vec<Item>> {
+        self.expect_op(OperatorKind::OpenBrace)?;
+        Ok(self.parse_items(true))
+    }
+
+    /// e.g. `= 1;` or `;` of a const or static
+    fn parse_item_value(&mut self) -> Result<Option<Expr>> {
+        let value = match self.eat_op(OperatorKind::Eq) {
+            true => Some(self.parse_expr()?),
+            false => None,
+        };
+        self.expect_op(OperatorKind::Semi)?;
+        Ok(value)
+    }
+
+    /// e.g. `fn a(&self, b: i32) -> i32 { b }`, `fn a();`,
+    /// `fn a<T: B>(t: T) where T: C {}`
+    fn parse_fn(&mut self) -> Result<FnDef> {
+        let lo = self.expect_keyword(Keyword::r#fn)?;
+        let name = self.expect_ident()?;
+        let mut generics = self.parse_generics()?;
+        self.expect_op(OperatorKind::OpenParen)?;
+        let self_param = self.parse_self_param()?;
+        let params = match self_param.is_none()
+            || self.eat_op(OperatorKind::Comma)
+        {
+            true => self
+                .parse_delimited(OperatorKind::CloseParen, Self::parse_param)?,
+            false => {
+                self.expect_op(OperatorKind::CloseParen)?;
+                Vec::new()
+            }
+        };
+        let ret = match self.eat_op(OperatorKind::RArrow) {
+            true => Some(self.parse_type()?),
+            false => None,
+        };
+        let sig =
+            FnSig { self_param, params, ret, span: lo.to(self.prev_span()) };
+        generics.where_clause = self.parse_where_clause()?;
+        let body = match self.eat_op(OperatorKind::Semi) {
+            true => None,
+            false => Some(self.parse_block()?),
+        };
+        Ok(FnDef { name, generics, sig, body })
+    }
+
+    /// e.g. `<T: A + B, U>`, or nothing
+    fn parse_generics(&mut self) -> Result<Generics> {
+        let lo = self.span();
+        if !self.eat_op(OperatorKind::Lt) {
+            let span = self.end_of(self.prev_span());
+            return Ok(Generics { span, ..Generics::default() });
+        }
+        let mut params = Vec::new();
+        while !self.eat_gt() {
+            let name = self.expect_ident()?;
+            let bounds = match self.eat_op(OperatorKind::Colon) {
+                true => self.parse_bounds()?,
+                false => Vec::new(),
+            };
+            let span = name.span.to(self.prev_span());
+            params.push(GenericParam { id: NodeId::DUMMY, name, bounds, span });
+            if !self.eat_op(OperatorKind::Comma) {
+                if !self.eat_gt() {
+                    return Err(self.unexpected("`,` or `>`"));
+                }
+                break;
+            }
+        }
+        let span = lo.to(self.prev_span());
+        Ok(Generics { params, where_clause: Vec::new(), span })
+    }
+
+    /// e.g. `where T: A, U: B + C`, or nothing
+    fn parse_where_clause(&mut self) -> Result<Vec<WherePredicate>> {
+        let mut predicates = Vec::new();
+        if !self.eat_keyword(Keyword::r#where) {
+            return Ok(predicates);
+        }
+        while !self.is_op(OperatorKind::OpenBrace)
+            && !self.is_op(OperatorKind::Semi)
+        {
+            let ty = self.parse_type()?;
+            self.expect_op(OperatorKind::Colon)?;
+            let bounds = self.parse_bounds()?;
+            let span = ty.span.to(self.prev_span());
+            predicates.push(WherePredicate { ty, bounds, span });
+            if !self.eat_op(OperatorKind::Comma) {
+                break;
+            }
+        }
+        Ok(predicates)
+    }
+
+    /// e.g. `self`, `mut self`, `&self`, `&mut self`
+    fn parse_self_param(&mut self) -> Result<Option<SelfParam>> {
+        let is_self =
+            |n| matches!(self.peek_nth(n), Token::Keyword(Keyword::self_));
+        let is_mut =
+            |n| matches!(self.peek_nth(n), Token::Keyword(Keyword::r#mut));
+        let is_ref = self.is_op(OperatorKind::And);
+        let (by_ref, mutable, len) = if is_self(0) {
+            (false, false, 1)
+        } else if is_mut(0) && is_self(1) {
+            (false, true, 2)
+        } else if is_ref && is_self(1) {
+            (true, false, 2)
+        } else if is_ref && is_mut(1) && is_self(2) {
+            (true, true, 3)
+        } else {
+            return Ok(None);
+        };
+        let lo = self.span();
+        for _ in 0..len {
+            self.bump();
+        }
+        Ok(Some(SelfParam { by_ref, mutable, span: lo.to(self.prev_span()) }))
+    }
+
+    /// e.g. `a: i32`
+    fn parse_param(&mut self) -> Result<Param> {
+        let pat = self.parse_pat_no_alt()?;
+        self.expect_op(OperatorKind::Colon)?;
+        let ty = self.parse_type()?;
+        let span = pat.span.to(ty.span);
+        Ok(Param { pat, ty, span })
+    }
+
+    /// e.g. `{ a: i32 }`, `(i32)`, or nothing
+    fn parse_variant_data(&mut self) -> Result<VariantData> {
+        if self.eat_op(OperatorKind::OpenBrace) {
+            let fields = self.parse_delimited(
+                OperatorKind::CloseBrace,
+                Self::parse_named_field,
+            )?;
+            Ok(VariantData::Struct(fields))
+        } else if self.eat_op(OperatorKind::OpenParen) {
+            let fields = self.parse_delimited(
+                OperatorKind::CloseParen,
+                Self::parse_tuple_field,
+            )?;
+            Ok(VariantData::Tuple(fields))
+        } else {
+            Ok(VariantData::Unit)
+        }
+    }
+
+    /// e.g. `pub a: i32`
+    fn parse_named_field(&mut self) -> Result<FieldDef> {
+        let docs = self.docs_before(CommentType::Inner);
+        let lo = self.span();
+        let attrs = self.parse_outer_attrs()?;
+        let vis = self.parse_visibility();
+        let name = self.expect_ident()?;
+        self.expect_op(OperatorKind::Colon)?;
+        let ty = self.parse_type()?;
+        let span = lo.to(ty.span);
+        Ok(FieldDef { docs, attrs, vis, name: Some(name), ty, span })
+    }
+
+    /// e.g. `pub i32`
+    fn parse_tuple_field(&mut self) -> Result<FieldDef> {
+        let docs = self.docs_before(CommentType::Inner);
+        let lo = self.span();
+        let attrs = self.parse_outer_attrs()?;
+        let vis = self.parse_visibility();
+        let ty = self.parse_type()?;
+        let span = lo.to(ty.span);
+        Ok(FieldDef { docs, attrs, vis, name: None, ty, span })
+    }
+
+    /// e.g. `A`, `B(i32)`, `C { c: i32 }`, `D = 1`
+    fn parse_variant(&mut self) -> Result<Variant> {
+        let docs = self.docs_before(CommentType::Inner);
+        let lo = self.span();
+        let attrs = self.parse_outer_attrs()?;
+        let name = self.expect_ident()?;
+        let data = self.parse_variant_data()?;
+        let discriminant = match self.eat_op(OperatorKind::Eq) {
+            true => Some(self.parse_expr()?),
+            false => None,
+        };
+        let span = lo.to(self.prev_span());
+        Ok(Variant { docs, attrs, name, data, discriminant, span })
+    }
+
+    /// e.g. `a::b`, `a::b as c`, `a::*`, `a::{b, c::*}`
+    fn parse_use_tree(&mut self) -> Result<UseTree> {
+        let lo = self.span();
+        let mut prefix = Path::new(Vec::new(), lo);
+        let kind = loop {
+            if self.eat_op(OperatorKind::Star) {
+                break UseTreeKind::Glob;
+            }
+            if self.eat_op(OperatorKind::OpenBrace) {
+                let trees = self.parse_delimited(
+                    OperatorKind::CloseBrace,
+                    Self::parse_use_tree,
+                )?;
+                break UseTreeKind::Nested(trees);
+            }
+            prefix.segments.push(self.parse_path_segment()?);
+            prefix.span = lo.to(self.prev_span());
+            if !self.eat_op(OperatorKind::PathSep) {
+                let rename = match self.eat_keyword(Keyword::r#as) {
+                    true => Some(self.expect_ident()?),
+                    false => None,
+                };
+                break UseTreeKind::Simple(rename);
+            }
+        };
+        Ok(UseTree { prefix, kind, span: lo.to(self.prev_span()) })
+    }
+}
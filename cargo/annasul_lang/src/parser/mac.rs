@@ -0,0 +1,164 @@
+// Copyright (c) 2025 air (https://yuanair.github.io).
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published by
+// the Free Software Foundation, version 3 of the License only.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+use crate::{
+    lexer::token::{Keyword, OperatorKind, Token},
+    parser::{
+        Parser,
+        PathStyle,
+        ast::{
+            Delimiter,
+            ExprKind,
+            FormatMacro,
+            MacCall,
+            MacroDef,
+            MacroRule,
+            Path,
+            TokenTree,
+        },
+    },
+    utils::{
+        error::{Error, Result},
+        span::Span,
+    },
+};
+impl Parser {
+    /// a token, or tokens in matching delimiters, e.g. `a`, `(a, [b])`
+    pub(crate) fn parse_token_tree(&mut self) -> Result<TokenTree> {
+        let Token::Operator(op) = self.peek() else {
+            return match self.peek() {
+                Token::EOF => Err(self.unexpected("token")),
+                _ => Ok(TokenTree::Token(self.bump())),
+            };
+        };
+        if Delimiter::from_close(op.kind()).is_some() {
+            return Err(Error::new(
+                self.span(),
+                format!("unexpected closing delimiter `{op}`"),
+            ));
+        }
+        let Some(delim) = Delimiter::from_open(op.kind()) else {
+            return Ok(TokenTree::Token(self.bump()));
+        };
+        let open = self.bump().span;
+        let close_kind = delim.operators().1;
+        let mut tts = Vec::new();
+        while !self.is_op(close_kind) {
+            if self.is_eof() {
+                return Err(Error::new(open, "unclosed delimiter"));
+            }
+            tts.push(self.parse_token_tree()?);
+        }
+        let close = self.bump().span;
+        Ok(TokenTree::Delimited { delim, open, close, tts })
+    }
+
+    /// the token trees of `( ... )`, `[ ... ]` or `{ ... }`, and the span
+    /// of the closing delimiter
+    fn parse_delimited_tts(
+        &mut self,
+    ) -> Result<(Delimiter, Vec<TokenTree>, Span)> {
+        match self.parse_token_tree()? {
+            TokenTree::Delimited { delim, tts, close, .. } => {
+                Ok((delim, tts, close))
+            }
+            TokenTree::Token(token) => Err(Error::new(
+                token.span,
+                format!(
+                    "expected one of `(`, `[` or `{{`, found `{}`",
+                    token.node
+                ),
+            )),
+        }
+    }
+
+    /// the next tokens are `name!(`, `name![` or `name!{`
+    pub(crate) fn is_macro_call_start(&self) -> bool {
+        matches!(self.peek(), Token::Identifier(_)) && self.is_macro_bang_at(1)
+    }
+
+    /// the next tokens are `!(`, `![` or `!{` after the path of a macro call
+    pub(crate) fn is_macro_bang(&self) -> bool { self.is_macro_bang_at(0) }
+
+    fn is_macro_bang_at(&self, n: usize) -> bool {
+        matches!(
+            self.peek_nth(n),
+            Token::Operator(op) if op.kind() == OperatorKind::Not
+        ) && matches!(
+            self.peek_nth(n + 1),
+            Token::Operator(op) if Delimiter::from_open(op.kind()).is_some()
+        )
+    }
+
+    /// e.g. `println!("{a}")`, `square!(2)`, the path is consumed already
+    pub(crate) fn parse_macro_call(&mut self, path: Path) -> Result<ExprKind> {
+        self.expect_op(OperatorKind::Not)?;
+        if let Some(mac) = path
+            .as_ident()
+            .and_then(|name| FormatMacro::from_name(name.node.as_str()))
+        {
+            return Ok(ExprKind::Format(self.parse_format_args(mac)?));
+        }
+        Ok(ExprKind::MacCall(self.parse_mac_call_rest(path)?))
+    }
+
+    /// e.g. `(2)` of `square!(2)`
+    fn parse_mac_call_rest(&mut self, path: Path) -> Result<MacCall> {
+        let (delim, tokens, close) = self.parse_delimited_tts()?;
+        let span = path.span.to(close);
+        Ok(MacCall { path, delim, tokens, close, span })
+    }
+
+    /// e.g. `items! { ... }`, `items!(...);`
+    pub(crate) fn parse_item_mac_call(&mut self) -> Result<MacCall> {
+        let path = self.parse_path(PathStyle::Expr)?;
+        self.expect_op(OperatorKind::Not)?;
+        let mac = self.parse_mac_call_rest(path)?;
+        if mac.delim != Delimiter::Brace {
+            self.expect_op(OperatorKind::Semi)?;
+        }
+        Ok(mac)
+    }
+
+    /// e.g. `marco square { ($x:expr) => { $x * $x }; }`
+    pub(crate) fn parse_macro_def(&mut self) -> Result<MacroDef> {
+        self.expect_keyword(Keyword::r#marco)?;
+        let name = self.expect_ident()?;
+        if FormatMacro::from_name(name.node.as_str()).is_some() {
+            self.errors.push(Error::new(
+                name.span,
+                format!(
+                    "`{}!` is a built-in macro and cannot be redefined",
+                    name.node
+                ),
+            ));
+        }
+        self.expect_op(OperatorKind::OpenBrace)?;
+        let mut rules = Vec::new();
+        while !self.eat_op(OperatorKind::CloseBrace) {
+            let lo = self.span();
+            let (_, matcher, _) = self.parse_delimited_tts()?;
+            self.expect_op(OperatorKind::FatArrow)?;
+            let (_, body, _) = self.parse_delimited_tts()?;
+            rules.push(MacroRule {
+                matcher,
+                body,
+                span: lo.to(self.prev_span()),
+            });
+            if !self.eat_op(OperatorKind::Semi) {
+                self.expect_op(OperatorKind::CloseBrace)?;
+                break;
+            }
+        }
+        Ok(MacroDef { name, rules })
+    }
+}
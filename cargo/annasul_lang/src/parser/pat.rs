@@ -0,0 +1,266 @@
+// Copyright (c) 2025 air (https://yuanair.github.io).
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published by
+// the Free Software Foundation, version 3 of the License only.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+use crate::{
+    lexer::token::{FloatLit, IntLit, Keyword, Literal, OperatorKind, Token},
+    parser::{
+        Parser,
+        PathStyle,
+        ast::{Pat, PatField, PatKind, Path},
+    },
+    utils::error::{Error, Result},
+};
+impl Parser {
+    /// e.g. `Some(a) | None`, a leading `|` is allowed
+    pub fn parse_pat(&mut self) -> Result<Pat> {
+        let lo = self.span();
+        self.eat_op(OperatorKind::Or);
+        let first = self.parse_pat_no_alt()?;
+        if !self.is_op(OperatorKind::Or) {
+            return Ok(first);
+        }
+        let mut pats = vec![first];
+        while self.eat_op(OperatorKind::Or) {
+            pats.push(self.parse_pat_no_alt()?);
+        }
+        Ok(Pat::new(PatKind::Or(pats), lo.to(self.prev_span())))
+    }
+
+    /// a pattern without a top-level `|`, e.g. a parameter
+    pub(crate) fn parse_pat_no_alt(&mut self) -> Result<Pat> {
+        let lo = self.span();
+        let kind = match self.peek().clone() {
+            Token::Identifier(ident)
+                if !ident.is_raw() && ident.as_str() == "_" =>
+            {
+                self.bump();
+                PatKind::Wild
+            }
+            Token::Operator(op) => match op.kind() {
+                OperatorKind::DotDot | OperatorKind::DotDotEq => {
+                    let inclusive = op.kind() == OperatorKind::DotDotEq;
+                    self.bump();
+                    if !inclusive && !self.is_range_end_start() {
+                        PatKind::Rest
+                    } else {
+                        let end = Some(Box::new(self.parse_range_end()?));
+                        PatKind::Range { start: None, end, inclusive }
+                    }
+                }
+                OperatorKind::And | OperatorKind::AndAnd => {
+                    let double = op.kind() == OperatorKind::AndAnd;
+                    self.bump();
+                    let mutable = self.eat_keyword(Keyword::r#mut);
+                    let pat = self.parse_pat_no_alt()?;
+                    let mut kind = PatKind::Ref { mutable, pat: Box::new(pat) };
+                    if double {
+                        // `&&a` is `&(&a)`
+                        let span = lo.to(self.prev_span());
+                        let inner = Pat::new(kind, span);
+                        kind = PatKind::Ref {
+                            mutable: false,
+                            pat:     Box::new(inner),
+                        };
+                    }
+                    kind
+                }
+                OperatorKind::OpenParen => {
+                    self.bump();
+                    let mut pats = Vec::new();
+                    let mut trailing_comma = false;
+                    while !self.eat_op(OperatorKind::CloseParen) {
+                        pats.push(self.parse_pat()?);
+                        trailing_comma = self.eat_op(OperatorKind::Comma);
+                        if !trailing_comma {
+                            self.expect_op(OperatorKind::CloseParen)?;
+                            break;
+                        }
+                    }
+                    match (pats.len(), trailing_comma) {
+                        // `(a)` is `a`
+                        (1, false) => {
+                            let mut inner = pats.pop().unwrap();
+                            inner.span = lo.to(self.prev_span());
+                            return Ok(inner);
+                        }
+                        _ => PatKind::Tuple(pats),
+                    }
+                }
+                OperatorKind::OpenBracket => {
+                    self.bump();
+                    PatKind::Slice(self.parse_delimited(
+                        OperatorKind::CloseBracket,
+                        Self::parse_pat,
+                    )?)
+                }
+                OperatorKind::Minus => {
+                    let start = self.parse_literal_pat()?;
+                    return self.parse_range_rest(start);
+                }
+                _ => return Err(self.unexpected("pattern")),
+            },
+            Token::Literal(_)
+            | Token::Keyword(Keyword::r#true | Keyword::r#false) => {
+                let start = self.parse_literal_pat()?;
+                return self.parse_range_rest(start);
+            }
+            Token::Keyword(Keyword::r#ref | Keyword::r#mut) => {
+                self.parse_binding()?
+            }
+            // a binding, or a unit struct or variant in scope, which is
+            // decided by name resolution
+            Token::Identifier(_)
+                if !matches!(self.peek_nth(1), Token::Operator(op)
+                    if matches!(op.kind(), OperatorKind::PathSep
+                        | OperatorKind::OpenParen
+                        | OperatorKind::OpenBrace
+                        | OperatorKind::DotDot
+                        | OperatorKind::DotDotEq)) =>
+            {
+                self.parse_binding()?
+            }
+            _ if self.is_path_start() => {
+                let path = self.parse_path(PathStyle::Expr)?;
+                if self.eat_op(OperatorKind::OpenParen) {
+                    let pats = self.parse_delimited(
+                        OperatorKind::CloseParen,
+                        Self::parse_pat,
+                    )?;
+                    PatKind::TupleStruct(path, pats)
+                } else if self.is_op(OperatorKind::OpenBrace) {
+                    self.parse_struct_pat_rest(path)?
+                } else {
+                    let span = path.span;
+                    let start = Pat::new(PatKind::Path(path), span);
+                    return self.parse_range_rest(start);
+                }
+            }
+            _ => return Err(self.unexpected("pattern")),
+        };
+        Ok(Pat::new(kind, lo.to(self.prev_span())))
+    }
+
+    /// e.g. `a`, `ref mut a`, `a @ 1..=5`
+    fn parse_binding(&mut self) -> Result<PatKind> {
+        let by_ref = self.eat_keyword(Keyword::r#ref);
+        let mutable = self.eat_keyword(Keyword::r#mut);
+        let name = self.expect_ident()?;
+        let sub = match self.eat_op(OperatorKind::At) {
+            true => Some(Box::new(self.parse_pat_no_alt()?)),
+            false => None,
+        };
+        Ok(PatKind::Ident { by_ref, mutable, name, sub })
+    }
+
+    /// `{ x, y: 0, .. }` after the path of a struct pattern
+    fn parse_struct_pat_rest(&mut self, path: Path) -> Result<PatKind> {
+        self.expect_op(OperatorKind::OpenBrace)?;
+        let mut fields = Vec::new();
+        let mut rest = false;
+        while !self.eat_op(OperatorKind::CloseBrace) {
+            if self.eat_op(OperatorKind::DotDot) {
+                rest = true;
+                self.expect_op(OperatorKind::CloseBrace)?;
+                break;
+            }
+            let lo = self.span();
+            let field = if matches!(self.peek_nth(1), Token::Operator(op)
+                if op.kind() == OperatorKind::Colon)
+            {
+                let name = self.expect_ident()?;
+                self.bump();
+                let pat = self.parse_pat()?;
+                PatField { name, pat, span: lo.to(self.prev_span()) }
+            } else {
+                let kind = self.parse_binding()?;
+                let PatKind::Ident { name, .. } = &kind else { unreachable!() };
+                let name = name.clone();
+                let span = lo.to(self.prev_span());
+                PatField { name, pat: Pat::new(kind, span), span }
+            };
+            fields.push(field);
+            if !self.eat_op(OperatorKind::Comma) {
+                self.expect_op(OperatorKind::CloseBrace)?;
+                break;
+            }
+        }
+        Ok(PatKind::Struct { path, fields, rest })
+    }
+
+    /// e.g. `1`, `-1`, `true`
+    fn parse_literal_pat(&mut self) -> Result<Pat> {
+        let lo = self.span();
+        let negative = self.eat_op(OperatorKind::Minus);
+        let literal = match self.peek().clone() {
+            Token::Literal(literal) => literal,
+            Token::Keyword(Keyword::r#true) if !negative => Literal::Bool(true),
+            Token::Keyword(Keyword::r#false) if !negative => {
+                Literal::Bool(false)
+            }
+            _ => return Err(self.unexpected("literal")),
+        };
+        let span = self.bump().span;
+        let literal = match negative {
+            true => Self::negate_literal(literal).ok_or_else(|| {
+                Error::new(span, "only numbers can be negated")
+            })?,
+            false => literal,
+        };
+        Ok(Pat::new(PatKind::Literal(literal), lo.to(span)))
+    }
+
+    /// the next token can start the end of a range pattern
+    fn is_range_end_start(&self) -> bool {
+        matches!(self.peek(), Token::Literal(_))
+            || self.is_op(OperatorKind::Minus)
+            || self.is_path_start()
+    }
+
+    /// e.g. `5`, `-5`, `a::MAX`
+    fn parse_range_end(&mut self) -> Result<Pat> {
+        if self.is_path_start() {
+            let path = self.parse_path(PathStyle::Expr)?;
+            let span = path.span;
+            return Ok(Pat::new(PatKind::Path(path), span));
+        }
+        self.parse_literal_pat()
+    }
+
+    /// `start`, or a range like `start..=end` or `start..`
+    fn parse_range_rest(&mut self, start: Pat) -> Result<Pat> {
+        let inclusive = match self.peek() {
+            Token::Operator(op) if op.kind() == OperatorKind::DotDotEq => true,
+            Token::Operator(op) if op.kind() == OperatorKind::DotDot => false,
+            _ => return Ok(start),
+        };
+        self.bump();
+        let end = match inclusive || self.is_range_end_start() {
+            true => Some(Box::new(self.parse_range_end()?)),
+            false => None,
+        };
+        let span = start.span.to(self.prev_span());
+        let kind =
+            PatKind::Range { start: Some(Box::new(start)), end, inclusive };
+        Ok(Pat::new(kind, span))
+    }
+
+    /// e.g. `1` -> `-1`, [None] for non-numeric literals
+    pub(crate) fn negate_literal(literal: Literal) -> Option<Literal> {
+        Some(match literal {
+            Literal::Int(lit) => Literal::Int(IntLit { negative: true, ..lit }),
+            Literal::Float(FloatLit { text, suffix }) => {
+                Literal::Float(FloatLit { text: format!("-{text}"), suffix })
+            }
+            _ => return None,
+        })
+    }
+}
@@ -0,0 +1,110 @@
+// Copyright (c) 2025 air (https://yuanair.github.io).
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published by
+// the Free Software Foundation, version 3 of the License only.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+use crate::{
+    lexer::token::{Keyword, OperatorKind},
+    parser::{
+        Parser,
+        ast::{Block, Expr, ExprKind, Local, Stmt, StmtKind},
+    },
+    utils::error::Result,
+};
+impl Parser {
+    /// e.g. `{ a; b }`
+    pub(crate) fn parse_block(&mut self) -> Result<Block> {
+        let lo = self.expect_op(OperatorKind::OpenBrace)?;
+        let mut stmts = Vec::new();
+        self.with_struct_literals(true, |this| {
+            while !this.eat_op(OperatorKind::CloseBrace) {
+                if this.is_eof() {
+                    let error = this.unexpected("`}`");
+                    this.errors.push(error);
+                    break;
+                }
+                let start = this.pos;
+                let lo = this.span();
+                match this.parse_stmt() {
+                    Ok(stmt) => stmts.push(stmt),
+                    Err(error) => {
+                        this.recover(error, start, Self::is_stmt_start);
+                        let span = lo.to(this.prev_span());
+                        let expr = Expr::new(ExprKind::Err, span);
+                        stmts.push(Stmt { kind: StmtKind::Expr(expr), span });
+                    }
+                }
+            }
+            Ok(())
+        })?;
+        Ok(Block { stmts, span: lo.to(self.prev_span()) })
+    }
+
+    /// the next token starts a [Local] or an [Item](crate::parser::ast::Item)
+    fn is_stmt_start(&self) -> bool {
+        self.is_keyword(Keyword::r#let) || self.is_item_start()
+    }
+
+    /// e.g. `a;`, `if a {}`, `;`
+    pub fn parse_stmt(&mut self) -> Result<Stmt> {
+        let lo = self.span();
+        if self.eat_op(OperatorKind::Semi) {
+            return Ok(Stmt { kind: StmtKind::Empty, span: lo });
+        }
+        let start = self.pos;
+        let attrs = self.parse_outer_attrs()?;
+        if self.is_keyword(Keyword::r#let) {
+            let local = Local { attrs, ..self.parse_local()? };
+            let span = lo.to(local.span);
+            return Ok(Stmt { kind: StmtKind::Let(Box::new(local)), span });
+        }
+        if self.is_item_start() {
+            // the item parses its own attributes and the docs before them
+            self.pos = start;
+            let item = self.parse_item()?;
+            let span = item.span;
+            return Ok(Stmt { kind: StmtKind::Item(Box::new(item)), span });
+        }
+        let mut expr = self.parse_stmt_expr()?;
+        expr.attrs.splice(0..0, attrs);
+        let kind = if self.eat_op(OperatorKind::Semi) {
+            StmtKind::Semi(expr)
+        } else if expr.is_block_like()
+            || self.is_op(OperatorKind::CloseBrace)
+            || self.is_eof()
+        {
+            StmtKind::Expr(expr)
+        } else {
+            return Err(self.unexpected("`;` or `}`"));
+        };
+        Ok(Stmt { kind, span: lo.to(self.prev_span()) })
+    }
+
+    /// e.g. `let a: i32 = 1;`, `let Some(a) = b else { return };`
+    fn parse_local(&mut self) -> Result<Local> {
+        let lo = self.expect_keyword(Keyword::r#let)?;
+        let pat = self.parse_pat()?;
+        let ty = match self.eat_op(OperatorKind::Colon) {
+            true => Some(self.parse_type()?),
+            false => None,
+        };
+        let init = match self.eat_op(OperatorKind::Eq) {
+            true => Some(self.parse_expr()?),
+            false => None,
+        };
+        let els = match init.is_some() && self.eat_keyword(Keyword::r#else) {
+            true => Some(self.parse_block()?),
+            false => None,
+        };
+        self.expect_op(OperatorKind::Semi)?;
+        let span = lo.to(self.prev_span());
+        Ok(Local { attrs: Vec::new(), pat, ty, init, els, span })
+    }
+}
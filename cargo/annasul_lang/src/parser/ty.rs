@@ -0,0 +1,142 @@
+// Copyright (c) 2025 air (https://yuanair.github.io).
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published by
+// the Free Software Foundation, version 3 of the License only.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+use crate::{
+    lexer::token::{Keyword, OperatorKind, Token},
+    parser::{
+        Parser,
+        PathStyle,
+        ast::{Path, Type, TypeKind},
+    },
+    utils::{error::Result, span::Span},
+};
+impl Parser {
+    /// e.g. `i32`, `&mut [u8]`, `(i32, bool)`, `dyn A + B`, `i32.Box`
+    pub fn parse_type(&mut self) -> Result<Type> {
+        let mut ty = self.parse_type_no_postfix()?;
+        while self.is_op(OperatorKind::Dot)
+            && matches!(self.peek_nth(1), Token::Identifier(ident)
+                if !ident.is_raw() && ident.as_str() == "Box")
+        {
+            self.bump();
+            let span = ty.span.to(self.bump().span);
+            ty = Type { kind: TypeKind::Box(Box::new(ty)), span };
+        }
+        Ok(ty)
+    }
+
+    fn parse_type_no_postfix(&mut self) -> Result<Type> {
+        let lo = self.span();
+        let kind = match self.peek().clone() {
+            Token::Operator(op) => match op.kind() {
+                OperatorKind::OpenParen => {
+                    self.bump();
+                    let mut elems = Vec::new();
+                    let mut trailing_comma = false;
+                    while !self.eat_op(OperatorKind::CloseParen) {
+                        elems.push(self.parse_type()?);
+                        trailing_comma = self.eat_op(OperatorKind::Comma);
+                        if !trailing_comma {
+                            self.expect_op(OperatorKind::CloseParen)?;
+                            break;
+                        }
+                    }
+                    match (elems.len(), trailing_comma) {
+                        // `(i32)` is `i32`
+                        (1, false) => {
+                            let mut inner = elems.pop().unwrap();
+                            inner.span = lo.to(self.prev_span());
+                            return Ok(inner);
+                        }
+                        _ => TypeKind::Tuple(elems),
+                    }
+                }
+                OperatorKind::Star => {
+                    self.bump();
+                    let mutable = if self.eat_keyword(Keyword::r#mut) {
+                        true
+                    } else {
+                        self.expect_keyword(Keyword::r#const)?;
+                        false
+                    };
+                    TypeKind::Ptr { mutable, ty: Box::new(self.parse_type()?) }
+                }
+                OperatorKind::And => {
+                    self.bump();
+                    let mutable = self.eat_keyword(Keyword::r#mut);
+                    TypeKind::Ref { mutable, ty: Box::new(self.parse_type()?) }
+                }
+                OperatorKind::AndAnd => {
+                    // `&&i32` is `&(&i32)`
+                    self.bump();
+                    let mutable = self.eat_keyword(Keyword::r#mut);
+                    let ty = self.parse_type()?;
+                    let inner_lo = Span::new(
+                        lo.file(),
+                        lo.lo() + 1,
+                        lo.lo() + 1,
+                        lo.line(),
+                        lo.col() + 1,
+                    )
+                    .with_expansion(lo.expansion());
+                    let inner = Type {
+                        kind: TypeKind::Ref { mutable, ty: Box::new(ty) },
+                        span: inner_lo.to(self.prev_span()),
+                    };
+                    TypeKind::Ref { mutable: false, ty: Box::new(inner) }
+                }
+                OperatorKind::OpenBracket => {
+                    self.bump();
+                    let elem = Box::new(self.parse_type()?);
+                    let kind = if self.eat_op(OperatorKind::Semi) {
+                        let len =
+                            self.with_struct_literals(true, Self::parse_expr)?;
+                        TypeKind::Array(elem, Box::new(len))
+                    } else {
+                        TypeKind::Slice(elem)
+                    };
+                    self.expect_op(OperatorKind::CloseBracket)?;
+                    kind
+                }
+                _ => return Err(self.unexpected("type")),
+            },
+            Token::Identifier(ident)
+                if !ident.is_raw() && ident.as_str() == "_" =>
+            {
+                self.bump();
+                TypeKind::Infer
+            }
+            Token::Keyword(Keyword::r#dyn) => {
+                self.bump();
+                TypeKind::TraitObject(self.parse_bounds()?)
+            }
+            Token::Keyword(Keyword::r#impl) => {
+                self.bump();
+                TypeKind::ImplTrait(self.parse_bounds()?)
+            }
+            _ if self.is_path_start() => {
+                TypeKind::Path(self.parse_path(PathStyle::Type)?)
+            }
+            _ => return Err(self.unexpected("type")),
+        };
+        Ok(Type { kind, span: lo.to(self.prev_span()) })
+    }
+
+    /// e.g. `A + B<i32>`
+    pub(crate) fn parse_bounds(&mut self) -> Result<Vec<Path>> {
+        let mut bounds = vec![self.parse_path(PathStyle::Type)?];
+        while self.eat_op(OperatorKind::Plus) {
+            bounds.push(self.parse_path(PathStyle::Type)?);
+        }
+        Ok(bounds)
+    }
+}
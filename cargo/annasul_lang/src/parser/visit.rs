@@ -0,0 +1,405 @@
+// Copyright (c) 2025 air (https://yuanair.github.io).
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published by
+// the Free Software Foundation, version 3 of the License only.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+use crate::parser::ast::{
+    Block,
+    Expr,
+    ExprKind,
+    FieldDef,
+    FormatArg,
+    FormatPiece,
+    GenericParam,
+    Generics,
+    Ident,
+    Item,
+    ItemKind,
+    Module,
+    Pat,
+    PatKind,
+    Path,
+    Stmt,
+    StmtKind,
+    Type,
+    TypeKind,
+    UseTree,
+    UseTreeKind,
+    VariantData,
+};
+/// a traversal of the AST that may change it in place, every method walks
+/// the children by default, see e.g. [walk_expr]
+pub trait MutVisitor {
+    fn visit_module(&mut self, module: &mut Module) {
+        walk_module(self, module)
+    }
+    /// the items of a module, a trait, an impl or an extern block, so they
+    /// can be replaced by any number of items
+    fn visit_items(&mut self, items: &mut Vec<Item>) {
+        items.iter_mut().for_each(|item| self.visit_item(item))
+    }
+    fn visit_item(&mut self, item: &mut Item) { walk_item(self, item) }
+    fn visit_block(&mut self, block: &mut Block) { walk_block(self, block) }
+    /// the statements of a block, so they can be replaced by any number of
+    /// statements
+    fn visit_stmts(&mut self, stmts: &mut Vec<Stmt>) {
+        stmts.iter_mut().for_each(|stmt| self.visit_stmt(stmt))
+    }
+    fn visit_stmt(&mut self, stmt: &mut Stmt) { walk_stmt(self, stmt) }
+    fn visit_expr(&mut self, expr: &mut Expr) { walk_expr(self, expr) }
+    fn visit_pat(&mut self, pat: &mut Pat) { walk_pat(self, pat) }
+    fn visit_ty(&mut self, ty: &mut Type) { walk_ty(self, ty) }
+    fn visit_path(&mut self, path: &mut Path) { walk_path(self, path) }
+    fn visit_generics(&mut self, generics: &mut Generics) {
+        walk_generics(self, generics)
+    }
+    fn visit_generic_param(&mut self, param: &mut GenericParam) {
+        walk_generic_param(self, param)
+    }
+    /// every identifier, e.g. names of items, bindings, fields, labels and
+    /// path segments
+    fn visit_ident(&mut self, _ident: &mut Ident) {}
+}
+pub fn walk_module<V: MutVisitor + ?Sized>(v: &mut V, module: &mut Module) {
+    v.visit_items(&mut module.items);
+}
+pub fn walk_item<V: MutVisitor + ?Sized>(v: &mut V, item: &mut Item) {
+    match &mut item.kind {
+        ItemKind::Fn(def) => {
+            v.visit_ident(&mut def.name);
+            v.visit_generics(&mut def.generics);
+            for param in &mut def.sig.params {
+                v.visit_pat(&mut param.pat);
+                v.visit_ty(&mut param.ty);
+            }
+            if let Some(ret) = &mut def.sig.ret {
+                v.visit_ty(ret);
+            }
+            if let Some(body) = &mut def.body {
+                v.visit_block(body);
+            }
+        }
+        ItemKind::Struct { name, generics, data } => {
+            v.visit_ident(name);
+            v.visit_generics(generics);
+            walk_variant_data(v, data);
+        }
+        ItemKind::Enum { name, generics, variants } => {
+            v.visit_ident(name);
+            v.visit_generics(generics);
+            for variant in variants {
+                v.visit_ident(&mut variant.name);
+                walk_variant_data(v, &mut variant.data);
+                if let Some(discriminant) = &mut variant.discriminant {
+                    v.visit_expr(discriminant);
+                }
+            }
+        }
+        ItemKind::Union { name, generics, fields } => {
+            v.visit_ident(name);
+            v.visit_generics(generics);
+            walk_fields(v, fields);
+        }
+        ItemKind::Trait { name, items } => {
+            v.visit_ident(name);
+            v.visit_items(items);
+        }
+        ItemKind::Impl { generics, of_trait, self_ty, items } => {
+            v.visit_generics(generics);
+            if let Some(of_trait) = of_trait {
+                v.visit_path(of_trait);
+            }
+            v.visit_ty(self_ty);
+            v.visit_items(items);
+        }
+        ItemKind::Mod { name, module } => {
+            v.visit_ident(name);
+            if let Some(module) = module {
+                v.visit_module(module);
+            }
+        }
+        ItemKind::Use(tree) => walk_use_tree(v, tree),
+        ItemKind::Const { name, ty, value }
+        | ItemKind::Static { name, ty, value, .. } => {
+            v.visit_ident(name);
+            v.visit_ty(ty);
+            if let Some(value) = value {
+                v.visit_expr(value);
+            }
+        }
+        ItemKind::TypeAlias { name, ty } => {
+            v.visit_ident(name);
+            if let Some(ty) = ty {
+                v.visit_ty(ty);
+            }
+        }
+        ItemKind::Extern { items, .. } => v.visit_items(items),
+        ItemKind::Marco(def) => v.visit_ident(&mut def.name),
+        ItemKind::MacCall(mac) => v.visit_path(&mut mac.path),
+        ItemKind::Err => {}
+    }
+}
+pub fn walk_generics<V: MutVisitor + ?Sized>(
+    v: &mut V, generics: &mut Generics,
+) {
+    for param in &mut generics.params {
+        v.visit_generic_param(param);
+    }
+    for predicate in &mut generics.where_clause {
+        v.visit_ty(&mut predicate.ty);
+        predicate.bounds.iter_mut().for_each(|bound| v.visit_path(bound));
+    }
+}
+pub fn walk_generic_param<V: MutVisitor + ?Sized>(
+    v: &mut V, param: &mut GenericParam,
+) {
+    v.visit_ident(&mut param.name);
+    param.bounds.iter_mut().for_each(|bound| v.visit_path(bound));
+}
+fn walk_variant_data<V: MutVisitor + ?Sized>(
+    v: &mut V, data: &mut VariantData,
+) {
+    match data {
+        VariantData::Struct(fields) | VariantData::Tuple(fields) => {
+            walk_fields(v, fields)
+        }
+        VariantData::Unit => {}
+    }
+}
+fn walk_fields<V: MutVisitor + ?Sized>(v: &mut V, fields: &mut [FieldDef]) {
+    for field in fields {
+        if let Some(name) = &mut field.name {
+            v.visit_ident(name);
+        }
+        v.visit_ty(&mut field.ty);
+    }
+}
+fn walk_use_tree<V: MutVisitor + ?Sized>(v: &mut V, tree: &mut UseTree) {
+    v.visit_path(&mut tree.prefix);
+    match &mut tree.kind {
+        UseTreeKind::Simple(rename) => {
+            if let Some(rename) = rename {
+                v.visit_ident(rename);
+            }
+        }
+        UseTreeKind::Glob => {}
+        UseTreeKind::Nested(trees) => {
+            trees.iter_mut().for_each(|tree| walk_use_tree(v, tree))
+        }
+    }
+}
+pub fn walk_block<V: MutVisitor + ?Sized>(v: &mut V, block: &mut Block) {
+    v.visit_stmts(&mut block.stmts);
+}
+pub fn walk_stmt<V: MutVisitor + ?Sized>(v: &mut V, stmt: &mut Stmt) {
+    match &mut stmt.kind {
+        StmtKind::Expr(expr) | StmtKind::Semi(expr) => v.visit_expr(expr),
+        StmtKind::Empty => {}
+        StmtKind::Item(item) => v.visit_item(item),
+        StmtKind::Let(local) => {
+            v.visit_pat(&mut local.pat);
+            if let Some(ty) = &mut local.ty {
+                v.visit_ty(ty);
+            }
+            if let Some(init) = &mut local.init {
+                v.visit_expr(init);
+            }
+            if let Some(els) = &mut local.els {
+                v.visit_block(els);
+            }
+        }
+    }
+}
+pub fn walk_expr<V: MutVisitor + ?Sized>(v: &mut V, expr: &mut Expr) {
+    match &mut expr.kind {
+        ExprKind::Literal(_) | ExprKind::Err => {}
+        ExprKind::Path(path) => v.visit_path(path),
+        ExprKind::Tuple(exprs) | ExprKind::Array(exprs) => {
+            exprs.iter_mut().for_each(|expr| v.visit_expr(expr))
+        }
+        ExprKind::Paren(inner)
+        | ExprKind::Unary(_, inner)
+        | ExprKind::Ref { expr: inner, .. }
+        | ExprKind::Box(inner)
+        | ExprKind::Try(inner) => v.visit_expr(inner),
+        ExprKind::Repeat(l, r)
+        | ExprKind::Binary(_, l, r)
+        | ExprKind::Assign(l, r)
+        | ExprKind::AssignOp(_, l, r)
+        | ExprKind::Index(l, r) => {
+            v.visit_expr(l);
+            v.visit_expr(r);
+        }
+        ExprKind::Struct { path, fields, rest } => {
+            v.visit_path(path);
+            for field in fields {
+                v.visit_ident(&mut field.name);
+                v.visit_expr(&mut field.expr);
+            }
+            if let Some(rest) = rest {
+                v.visit_expr(rest);
+            }
+        }
+        ExprKind::Cast(inner, ty) => {
+            v.visit_expr(inner);
+            v.visit_ty(ty);
+        }
+        ExprKind::Call(callee, args) => {
+            v.visit_expr(callee);
+            args.iter_mut().for_each(|arg| v.visit_expr(arg));
+        }
+        ExprKind::MethodCall { receiver, method, args } => {
+            v.visit_expr(receiver);
+            v.visit_ident(method);
+            args.iter_mut().for_each(|arg| v.visit_expr(arg));
+        }
+        ExprKind::Field(inner, name) => {
+            v.visit_expr(inner);
+            v.visit_ident(name);
+        }
+        ExprKind::Range { start, end, .. } => {
+            if let Some(start) = start {
+                v.visit_expr(start);
+            }
+            if let Some(end) = end {
+                v.visit_expr(end);
+            }
+        }
+        ExprKind::Block(block) => v.visit_block(block),
+        ExprKind::If { cond, then_branch, else_branch } => {
+            v.visit_expr(cond);
+            v.visit_block(then_branch);
+            if let Some(else_branch) = else_branch {
+                v.visit_expr(else_branch);
+            }
+        }
+        ExprKind::Match { scrutinee, arms } => {
+            v.visit_expr(scrutinee);
+            for arm in arms {
+                v.visit_pat(&mut arm.pat);
+                if let Some(guard) = &mut arm.guard {
+                    v.visit_expr(guard);
+                }
+                v.visit_expr(&mut arm.body);
+            }
+        }
+        ExprKind::Loop { label, body } => {
+            if let Some(label) = label {
+                v.visit_ident(label);
+            }
+            v.visit_block(body);
+        }
+        ExprKind::While { label, cond, body } => {
+            if let Some(label) = label {
+                v.visit_ident(label);
+            }
+            v.visit_expr(cond);
+            v.visit_block(body);
+        }
+        ExprKind::For { label, pat, iter, body } => {
+            if let Some(label) = label {
+                v.visit_ident(label);
+            }
+            v.visit_pat(pat);
+            v.visit_expr(iter);
+            v.visit_block(body);
+        }
+        ExprKind::Break { label, value } => {
+            if let Some(label) = label {
+                v.visit_ident(label);
+            }
+            if let Some(value) = value {
+                v.visit_expr(value);
+            }
+        }
+        ExprKind::Continue { label } => {
+            if let Some(label) = label {
+                v.visit_ident(label);
+            }
+        }
+        ExprKind::Return(value) => {
+            if let Some(value) = value {
+                v.visit_expr(value);
+            }
+        }
+        ExprKind::Format(format) => {
+            for piece in &mut format.pieces {
+                if let FormatPiece::Placeholder(placeholder) = piece
+                    && let FormatArg::Expr(expr) = &mut placeholder.arg
+                {
+                    v.visit_expr(expr);
+                }
+            }
+            format.args.iter_mut().for_each(|arg| v.visit_expr(arg));
+        }
+        ExprKind::MacCall(mac) => v.visit_path(&mut mac.path),
+    }
+}
+pub fn walk_pat<V: MutVisitor + ?Sized>(v: &mut V, pat: &mut Pat) {
+    match &mut pat.kind {
+        PatKind::Wild | PatKind::Rest | PatKind::Literal(_) => {}
+        PatKind::Ident { name, sub, .. } => {
+            v.visit_ident(name);
+            if let Some(sub) = sub {
+                v.visit_pat(sub);
+            }
+        }
+        PatKind::Path(path) => v.visit_path(path),
+        PatKind::Tuple(pats) | PatKind::Slice(pats) | PatKind::Or(pats) => {
+            pats.iter_mut().for_each(|pat| v.visit_pat(pat))
+        }
+        PatKind::TupleStruct(path, pats) => {
+            v.visit_path(path);
+            pats.iter_mut().for_each(|pat| v.visit_pat(pat));
+        }
+        PatKind::Struct { path, fields, .. } => {
+            v.visit_path(path);
+            for field in fields {
+                v.visit_ident(&mut field.name);
+                v.visit_pat(&mut field.pat);
+            }
+        }
+        PatKind::Range { start, end, .. } => {
+            if let Some(start) = start {
+                v.visit_pat(start);
+            }
+            if let Some(end) = end {
+                v.visit_pat(end);
+            }
+        }
+        PatKind::Ref { pat, .. } => v.visit_pat(pat),
+    }
+}
+pub fn walk_ty<V: MutVisitor + ?Sized>(v: &mut V, ty: &mut Type) {
+    match &mut ty.kind {
+        TypeKind::Path(path) => v.visit_path(path),
+        TypeKind::Tuple(tys) => tys.iter_mut().for_each(|ty| v.visit_ty(ty)),
+        TypeKind::Ptr { ty, .. }
+        | TypeKind::Ref { ty, .. }
+        | TypeKind::Slice(ty)
+        | TypeKind::Box(ty) => v.visit_ty(ty),
+        TypeKind::Array(ty, len) => {
+            v.visit_ty(ty);
+            v.visit_expr(len);
+        }
+        TypeKind::TraitObject(paths) | TypeKind::ImplTrait(paths) => {
+            paths.iter_mut().for_each(|path| v.visit_path(path))
+        }
+        TypeKind::Infer => {}
+    }
+}
+pub fn walk_path<V: MutVisitor + ?Sized>(v: &mut V, path: &mut Path) {
+    for segment in &mut path.segments {
+        v.visit_ident(&mut segment.ident);
+        if let Some(args) = &mut segment.args {
+            args.args.iter_mut().for_each(|ty| v.visit_ty(ty));
+        }
+    }
+}
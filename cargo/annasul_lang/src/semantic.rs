@@ -10,3 +10,440 @@
 //
 // You should have received a copy of the GNU Affero General Public License
 // along with this program. If not, see <https://www.gnu.org/licenses/>.
+pub mod borrowck;
+pub mod collect;
+pub mod hir;
+pub mod matchck;
+pub mod mono;
+pub mod resolve;
+pub mod typeck;
+use std::{
+    io,
+    path::{Path, PathBuf},
+};
+
+use crate::{
+    attr::{self, cfg::Cfg},
+    expand::Expander,
+    lexer::scanner::{AnnasulScanner, Scanner},
+    parser::{
+        Parser,
+        ast::{self, Item, ItemKind, Module, NodeId, Pat},
+        visit::{self, MutVisitor},
+    },
+    utils::{
+        diagnostic::Diagnostic,
+        span::{FileId, SourceMap},
+    },
+};
+/// the extension of source files, e.g. `main.aau`
+pub const EXTENSION: &str = "aau";
+/// reads the files of a crate, see [RealFileLoader]
+pub trait FileLoader {
+    fn read(&self, path: &Path) -> io::Result<String>;
+}
+/// reads from the file system
+#[derive(Debug, Default, Clone, Copy)]
+pub struct RealFileLoader;
+/// parses the files of a crate, see [parse_crate]
+struct Loader<'a> {
+    loader:      &'a dyn FileLoader,
+    source_map:  &'a mut SourceMap,
+    cfg:         &'a Cfg,
+    /// every file of the crate, the root first
+    files:       Vec<FileId>,
+    diagnostics: Vec<Diagnostic>,
+}
+/// see [assign_node_ids]
+struct NodeIds(usize);
+impl FileLoader for RealFileLoader {
+    fn read(&self, path: &Path) -> io::Result<String> {
+        std::fs::read_to_string(path)
+    }
+}
+/// parses `root` and the files of its `mod a;` items, then expands the
+/// macros and checks the attributes of the whole crate, [None] if `root`
+/// cannot be read
+///
+/// the file of `mod a;` is `a.aau` or `a/mod.aau` in the directory of
+/// `root` for the items of `root`, and in the directory named after the
+/// module for the items of any other module, e.g. `a/b.aau` for `mod b;`
+/// in `a.aau` or in `mod a { ... }`
+pub fn parse_crate(
+    root: &Path, loader: &dyn FileLoader, source_map: &mut SourceMap, cfg: &Cfg,
+) -> (Option<Module>, Vec<Diagnostic>) {
+    let mut loader = Loader {
+        loader,
+        source_map,
+        cfg,
+        files: Vec::new(),
+        diagnostics: Vec::new(),
+    };
+    let source = match loader.loader.read(root) {
+        Ok(source) => source,
+        Err(error) => {
+            let diagnostic = Diagnostic::error(format!(
+                "couldn't read `{}`: {error}",
+                root.display()
+            ));
+            return (None, vec![diagnostic]);
+        }
+    };
+    let dir = root.parent().unwrap_or(Path::new("")).to_path_buf();
+    let mut module = loader.parse_file(root.to_path_buf(), source, &dir);
+    let mut expander =
+        loader.files.iter().fold(Expander::new(), |expander, file| {
+            let source = loader.source_map.file(*file).source();
+            expander.with_file_source(*file, source)
+        });
+    expander = expander
+        .with_cfg(cfg.clone())
+        .with_first_mark(loader.source_map.expansion_count());
+    expander.expand_module(&mut module);
+    for expansion in expander.take_expansions() {
+        loader.source_map.add_expansion(expansion);
+    }
+    let mut diagnostics = loader.diagnostics;
+    diagnostics.extend(expander.take_diagnostics());
+    for diagnostic in attr::check_attributes(&mut module) {
+        diagnostics.push(diagnostic.with_expansions(loader.source_map));
+    }
+    assign_node_ids(&mut module);
+    (Some(module), diagnostics)
+}
+/// numbers every [ast::Path], [Pat], [Item] and [ast::GenericParam] of
+/// `module`, after expansion
+pub fn assign_node_ids(module: &mut Module) { NodeIds(0).visit_module(module); }
+impl Loader<'_> {
+    /// `dir` is where the files of its `mod a;` items are
+    fn parse_file(
+        &mut self, name: PathBuf, source: String, dir: &Path,
+    ) -> Module {
+        let file = self.source_map.add_file(name, source);
+        self.files.push(file);
+        let (tokens, errors) =
+            AnnasulScanner::new(self.source_map.file(file).source().as_bytes())
+                .with_file(file)
+                .tokenize();
+        self.diagnostics.extend(errors.into_iter().map(Diagnostic::from));
+        let mut parser = Parser::new(tokens)
+            .with_source(self.source_map.file(file).source());
+        let mut module = parser.parse_module();
+        self.diagnostics
+            .extend(parser.take_errors().into_iter().map(Diagnostic::from));
+        self.load_modules(&mut module.items, dir);
+        module
+    }
+
+    /// fills in the modules of the `mod a;` items, except the ones removed
+    /// by `#[cfg(...)]`
+    fn load_modules(&mut self, items: &mut [Item], dir: &Path) {
+        for item in items {
+            // malformed `cfg` attributes are reported by the expander
+            if !self.cfg.matches(&item.attrs).unwrap_or(true) {
+                continue;
+            }
+            let ItemKind::Mod { name, module } = &mut item.kind else {
+                continue;
+            };
+            let dir = dir.join(name.node.as_str());
+            if let Some(module) = module {
+                if self.cfg.matches(&module.attrs).unwrap_or(true) {
+                    self.load_modules(&mut module.items, &dir);
+                }
+                continue;
+            }
+            let name = name.node.as_str().to_string();
+            let file = dir.with_extension(EXTENSION);
+            let mod_file = dir.join("mod").with_extension(EXTENSION);
+            let found = [&file, &mod_file]
+                .into_iter()
+                .filter_map(|path| match self.loader.read(path) {
+                    Ok(source) => Some((path, source)),
+                    Err(error) if error.kind() == io::ErrorKind::NotFound => {
+                        None
+                    }
+                    Err(error) => {
+                        self.diagnostics.push(
+                            Diagnostic::error(format!(
+                                "couldn't read `{}`: {error}",
+                                path.display()
+                            ))
+                            .with_primary(item.span, ""),
+                        );
+                        None
+                    }
+                })
+                .collect::<Vec<_>>();
+            match <[_; 1]>::try_from(found) {
+                Ok([(path, source)]) => {
+                    *module = Some(self.parse_file(path.clone(), source, &dir));
+                }
+                Err(found) if found.is_empty() => {
+                    self.diagnostics.push(
+                        Diagnostic::error(format!(
+                            "file not found for module `{name}`"
+                        ))
+                        .with_primary(item.span, "")
+                        .with_help(format!(
+                            "to create the module `{name}`, create file \
+                             \"{}\" or \"{}\"",
+                            file.display(),
+                            mod_file.display()
+                        )),
+                    );
+                }
+                Err(_) => {
+                    self.diagnostics.push(
+                        Diagnostic::error(format!(
+                            "file for module `{name}` found at both \"{}\" \
+                             and \"{}\"",
+                            file.display(),
+                            mod_file.display()
+                        ))
+                        .with_primary(item.span, "")
+                        .with_help(
+                            "delete or rename one of them to remove the \
+                             ambiguity",
+                        ),
+                    );
+                }
+            }
+        }
+    }
+}
+impl NodeIds {
+    fn next(&mut self) -> NodeId {
+        self.0 += 1;
+        NodeId::new(self.0 - 1)
+    }
+}
+impl MutVisitor for NodeIds {
+    fn visit_item(&mut self, item: &mut Item) {
+        item.id = self.next();
+        visit::walk_item(self, item);
+    }
+
+    fn visit_pat(&mut self, pat: &mut Pat) {
+        pat.id = self.next();
+        visit::walk_pat(self, pat);
+    }
+
+    fn visit_path(&mut self, path: &mut ast::Path) {
+        path.id = self.next();
+        visit::walk_path(self, path);
+    }
+
+    fn visit_generic_param(&mut self, param: &mut ast::GenericParam) {
+        param.id = self.next();
+        visit::walk_generic_param(self, param);
+    }
+}
+#[cfg(test)]
+pub(crate) mod tests {
+    use std::collections::HashMap;
+
+    use super::*;
+    use crate::{
+        attr::LintLevels,
+        semantic::{
+            collect::Collector,
+            resolve::{Resolutions, Resolver},
+            typeck::TypeChecker,
+        },
+        types::TyCtxt,
+        utils::diagnostic::Emitter,
+    };
+    /// the files of a crate in memory, e.g. `("a.aau", "fn a() {}")`
+    pub(crate) struct MemoryLoader(pub HashMap<PathBuf, String>);
+    impl FileLoader for MemoryLoader {
+        fn read(&self, path: &Path) -> io::Result<String> {
+            self.0.get(path).cloned().ok_or(io::ErrorKind::NotFound.into())
+        }
+    }
+    impl MemoryLoader {
+        pub(crate) fn new(files: &[(&str, &str)]) -> Self {
+            let files = files.iter().map(|(name, source)| {
+                (PathBuf::from(name), source.to_string())
+            });
+            Self(files.collect())
+        }
+    }
+    /// a crate of one file in memory after type checking
+    pub(crate) struct Checked {
+        pub source_map: SourceMap,
+        pub lints:      LintLevels,
+        pub res:        Resolutions,
+        pub tcx:        TyCtxt,
+        pub bodies:     Vec<hir::Body>,
+        /// the diagnostics of parsing and expansion
+        pub parse:      Vec<Diagnostic>,
+        pub resolve:    Vec<Diagnostic>,
+        pub collect:    Vec<Diagnostic>,
+        pub typeck:     Vec<Diagnostic>,
+    }
+    /// parses, resolves, collects and type checks `source` as `main.aau`
+    pub(crate) fn check(source: &str) -> Checked {
+        let loader = MemoryLoader::new(&[("main.aau", source)]);
+        let mut source_map = SourceMap::new();
+        let (module, parse) = parse_crate(
+            Path::new("main.aau"),
+            &loader,
+            &mut source_map,
+            &Cfg::new(),
+        );
+        let mut module = module.unwrap();
+        let lints = attr::lint_levels(&mut module);
+        let mut resolver = Resolver::new();
+        let res = resolver.resolve_crate(&mut module);
+        let resolve = resolver.take_diagnostics();
+        let mut tcx = TyCtxt::new();
+        let mut collector = Collector::new(&res, &mut tcx);
+        collector.collect_crate(&mut module);
+        let collect = collector.take_diagnostics();
+        let mut checker = TypeChecker::new(&res, &mut tcx);
+        let bodies = checker.check_crate(&mut module);
+        let typeck = checker.take_diagnostics();
+        Checked {
+            source_map,
+            lints,
+            res,
+            tcx,
+            bodies,
+            parse,
+            resolve,
+            collect,
+            typeck,
+        }
+    }
+    /// like [check], but every stage must succeed without diagnostics
+    pub(crate) fn check_clean(source: &str) -> Checked {
+        let checked = check(source);
+        assert_eq!(checked.parse, []);
+        assert_eq!(checked.resolve, []);
+        assert_eq!(checked.collect, []);
+        assert_eq!(checked.typeck, []);
+        checked
+    }
+    fn names(module: &Module) -> Vec<String> {
+        let mut found = Vec::new();
+        for item in &module.items {
+            let Some(name) = item.name() else { continue };
+            found.push(name.node.as_str().to_string());
+            if let ItemKind::Mod { module: Some(module), .. } = &item.kind {
+                found.push(format!("{{{}}}", names(module).join(" ")));
+            }
+        }
+        found
+    }
+    #[test]
+    fn file_modules() {
+        let loader = MemoryLoader::new(&[
+            ("src/main.aau", "mod a; mod b { mod c; } #[cfg(x)] mod d;"),
+            ("src/a.aau", "fn f() {} mod e;"),
+            ("src/a/e.aau", "fn g() {}"),
+            ("src/b/c/mod.aau", "fn h() {}"),
+        ]);
+        let mut source_map = SourceMap::new();
+        let (module, diagnostics) = parse_crate(
+            Path::new("src/main.aau"),
+            &loader,
+            &mut source_map,
+            &Cfg::new(),
+        );
+        assert_eq!(diagnostics, []);
+        assert_eq!(names(&module.unwrap()), ["a", "{f e {g}}", "b", "{c {h}}"]);
+        assert_eq!(source_map.files().count(), 4);
+    }
+    #[test]
+    fn missing_and_ambiguous_files() {
+        let loader = MemoryLoader::new(&[
+            ("main.aau", "mod a; mod b;"),
+            ("b.aau", ""),
+            ("b/mod.aau", ""),
+        ]);
+        let mut source_map = SourceMap::new();
+        let (module, diagnostics) = parse_crate(
+            Path::new("main.aau"),
+            &loader,
+            &mut source_map,
+            &Cfg::new(),
+        );
+        let messages: Vec<_> =
+            diagnostics.iter().map(|diagnostic| diagnostic.message()).collect();
+        assert_eq!(messages, [
+            "file not found for module `a`",
+            "file for module `b` found at both \"b.aau\" and \"b/mod.aau\"",
+        ]);
+        assert_eq!(names(&module.unwrap()), ["a", "b"]);
+        let (module, _) = parse_crate(
+            Path::new("missing.aau"),
+            &loader,
+            &mut source_map,
+            &Cfg::new(),
+        );
+        assert!(module.is_none());
+    }
+    #[test]
+    fn errors_in_expansions() {
+        let source = "marco add_true { ($x:expr) => { $x + true } }
+marco twice { ($x:expr) => { add_true!($x) } }
+fn main() { let a = twice!(1); }";
+        let Checked { source_map, parse, typeck, .. } = check(source);
+        assert_eq!(parse, []);
+        let rendered: Vec<_> = typeck
+            .into_iter()
+            .map(|diagnostic| {
+                let diagnostic = diagnostic.with_expansions(&source_map);
+                Emitter::new(false).render(&source_map, &diagnostic)
+            })
+            .collect();
+        assert_eq!(rendered, [r#"error[E0308]: mismatched types
+ --> main.aau:1:38
+  |
+1 | marco add_true { ($x:expr) => { $x + true } }
+  |                                      ^^^^ expected `{integer}`, found `bool`
+2 | marco twice { ($x:expr) => { add_true!($x) } }
+  |                              ------------- in this expansion of `add_true!`
+3 | fn main() { let a = twice!(1); }
+  |                     --------- in this expansion of `twice!`
+  |
+  = note: this error originates in the macro `add_true`"#]);
+    }
+    #[test]
+    fn deprecation_and_lint_levels() {
+        let source = "#![allow(frobnicate)]
+#[deprecated = \"use g\"] fn f() {}
+struct S { #[deprecated] a: i32 }
+impl S { #[deprecated] fn m(&self) {} }
+fn main() {
+    f();
+    #[allow(deprecated)] f();
+    let s = S { a: 1 };
+    s.m();
+}
+#[deny(warnings)] fn g() { f(); }";
+        let Checked { lints, parse, resolve, typeck, .. } = check(source);
+        let messages: Vec<_> = [parse, resolve, typeck]
+            .into_iter()
+            .flatten()
+            .filter_map(|diagnostic| lints.apply(diagnostic))
+            .map(|diagnostic| {
+                let span = diagnostic.primary_span().unwrap();
+                format!(
+                    "{:?} {}: {}",
+                    diagnostic.level(),
+                    span,
+                    diagnostic.message()
+                )
+            })
+            .collect();
+        assert_eq!(messages, [
+            "Warning 1:10: unknown lint: `frobnicate`",
+            "Warning 6:5: use of deprecated function `f`: use g",
+            "Error 11:28: use of deprecated function `f`: use g",
+            "Warning 8:17: use of deprecated field `S::a`",
+            "Warning 9:7: use of deprecated associated function `m`",
+        ]);
+    }
+}
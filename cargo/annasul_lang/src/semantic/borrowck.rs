@@ -0,0 +1,1738 @@
+// Copyright (c) 2025 air (https://yuanair.github.io).
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published by
+// the Free Software Foundation, version 3 of the License only.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+use std::{
+    collections::{HashMap, HashSet},
+    mem,
+};
+
+use crate::{
+    parser::ast::{BinOp, UnOp},
+    semantic::hir::{
+        self,
+        Block,
+        Body,
+        Expr,
+        ExprKind,
+        LocalId,
+        LoopId,
+        MutVisitor,
+        Pat,
+        PatKind,
+        Stmt,
+        StmtKind,
+    },
+    types::{Ty, TyCtxt, TyKind},
+    utils::{diagnostic::Diagnostic, span::Span},
+};
+/// checks the ownership rules of the typed bodies: a value is not used
+/// after it is moved unless its type is `Copy`, a place is borrowed either
+/// mutably once or shared any number of times, and a borrow does not
+/// outlive the local it borrows
+///
+/// a borrow stored in a local lives while the local is used later in the
+/// body, a temporary one until the expression using it ends
+pub struct BorrowChecker<'a> {
+    tcx:         &'a mut TyCtxt,
+    diagnostics: Vec<Diagnostic>,
+}
+/// the state of the ownership analysis of one body, see [State]
+struct BodyCx<'a, 'b> {
+    tcx:         &'b mut TyCtxt,
+    body:        &'a Body,
+    /// the positions of the uses of every local, a use inside a loop also
+    /// at the end of the loop for a local declared before it
+    uses:        HashMap<LocalId, Vec<(usize, Span)>>,
+    /// the locals bound by the parameters
+    params:      HashSet<LocalId>,
+    state:       State,
+    loops:       Vec<LoopCx>,
+    /// the locals declared in every enclosing block, the innermost last
+    scopes:      Vec<Vec<LocalId>>,
+    /// the borrows in the operands evaluated so far of the enclosing calls,
+    /// tuples and arrays
+    in_flight:   Vec<usize>,
+    /// the callee of the call an in-flight borrow is an argument of
+    callees:     HashMap<usize, Span>,
+    next_borrow: usize,
+    diagnostics: Vec<Diagnostic>,
+    /// by message and primary span, a loop body is checked twice
+    reported:    HashSet<(String, Span)>,
+}
+/// the moves and the borrows at a point of a body
+#[derive(Debug, Clone, Default)]
+struct State {
+    /// after a `return`, a `break` or a `continue`
+    unreachable: bool,
+    moves:       Vec<Move>,
+    borrows:     Vec<Borrow>,
+    /// the immutable locals with a value, by their first assignment
+    assigned:    Vec<(LocalId, Span)>,
+}
+/// a place that is moved out, or a local declared without a value
+#[derive(Debug, Clone)]
+struct Move {
+    place:  Place,
+    ty:     Ty,
+    span:   Span,
+    uninit: bool,
+}
+#[derive(Debug, Clone)]
+struct Borrow {
+    id:      usize,
+    place:   Place,
+    mutable: bool,
+    span:    Span,
+    /// the locals the reference is stored in, none for a temporary
+    holders: Vec<LocalId>,
+}
+/// a local or a part of it, e.g. `a.b`, `*a`, `a[_]`, with the type after
+/// every projection
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Place {
+    local: LocalId,
+    projs: Vec<(Proj, Ty)>,
+}
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Proj {
+    /// by the index of the variant and of the field
+    Field(usize, usize),
+    Deref,
+    /// any element
+    Index,
+}
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Access {
+    Use,
+    Borrow,
+    /// to a part of a place
+    Assign,
+}
+/// why a place cannot be written, see [BodyCx::immutability]
+enum Immutable {
+    Local,
+    /// behind a `&` reference or a `*const` pointer in the place, with the
+    /// reference or the pointer
+    Ref(Place),
+    Ptr(Place),
+}
+struct LoopCx {
+    id:        LoopId,
+    breaks:    Vec<State>,
+    continues: Vec<State>,
+}
+/// the positions of the uses of the locals and the spans of the loops, see
+/// [BodyCx::uses]
+#[derive(Default)]
+struct Uses {
+    uses:  HashMap<LocalId, Vec<(usize, Span)>>,
+    loops: Vec<Span>,
+}
+impl<'a> BorrowChecker<'a> {
+    pub fn new(tcx: &'a mut TyCtxt) -> Self {
+        Self { tcx, diagnostics: Vec::new() }
+    }
+
+    pub fn check_crate(&mut self, bodies: &[Body]) {
+        for body in bodies {
+            self.check_body(body);
+        }
+    }
+
+    pub fn check_body(&mut self, body: &Body) {
+        let mut cx = BodyCx {
+            tcx: self.tcx,
+            body,
+            uses: Uses::collect(body),
+            params: HashSet::new(),
+            state: State::default(),
+            loops: Vec::new(),
+            scopes: vec![Vec::new()],
+            in_flight: Vec::new(),
+            callees: HashMap::new(),
+            next_borrow: 0,
+            diagnostics: Vec::new(),
+            reported: HashSet::new(),
+        };
+        cx.check();
+        self.diagnostics.append(&mut cx.diagnostics);
+    }
+
+    pub fn take_diagnostics(&mut self) -> Vec<Diagnostic> {
+        mem::take(&mut self.diagnostics)
+    }
+}
+impl Uses {
+    fn collect(body: &Body) -> HashMap<LocalId, Vec<(usize, Span)>> {
+        let mut found = Uses::default();
+        found.visit_body(&mut body.clone());
+        let mut uses = found.uses;
+        for span in found.loops {
+            for (local, uses) in &mut uses {
+                let declared = body.local(*local).span.lo();
+                let inside = uses
+                    .iter()
+                    .find(|(pos, _)| span.lo() <= *pos && *pos < span.hi());
+                if declared < span.lo()
+                    && let Some(&(_, first)) = inside
+                {
+                    uses.push((span.hi(), first));
+                }
+            }
+        }
+        for uses in uses.values_mut() {
+            uses.sort_by_key(|(pos, _)| *pos);
+        }
+        uses
+    }
+}
+impl MutVisitor for Uses {
+    fn visit_expr(&mut self, expr: &mut Expr) {
+        match expr.kind {
+            ExprKind::Local(local) => {
+                let uses = self.uses.entry(local).or_default();
+                uses.push((expr.span.lo(), expr.span));
+            }
+            ExprKind::Loop { .. } | ExprKind::For { .. } => {
+                self.loops.push(expr.span);
+            }
+            _ => {}
+        }
+        hir::walk_expr(self, expr);
+    }
+}
+impl State {
+    fn unreachable() -> Self { Self { unreachable: true, ..Self::default() } }
+
+    /// the state after either `self` or `other`
+    fn merge(self, other: State) -> State {
+        if self.unreachable {
+            return other;
+        }
+        if other.unreachable {
+            return self;
+        }
+        let mut merged = self;
+        for moved in other.moves {
+            if !merged.moves.iter().any(|m| m.place == moved.place) {
+                merged.moves.push(moved);
+            }
+        }
+        for borrow in other.borrows {
+            match merged.borrows.iter_mut().find(|b| b.id == borrow.id) {
+                Some(found) => {
+                    for holder in borrow.holders {
+                        if !found.holders.contains(&holder) {
+                            found.holders.push(holder);
+                        }
+                    }
+                }
+                None => merged.borrows.push(borrow),
+            }
+        }
+        for (local, span) in other.assigned {
+            if !merged.assigned.iter().any(|(l, _)| *l == local) {
+                merged.assigned.push((local, span));
+            }
+        }
+        merged
+    }
+}
+impl Place {
+    fn new(local: LocalId) -> Self { Self { local, projs: Vec::new() } }
+
+    fn project(&self, proj: Proj, ty: Ty) -> Self {
+        let mut projs = self.projs.clone();
+        projs.push((proj, ty));
+        Self { local: self.local, projs }
+    }
+
+    /// `other` is `self` or a part of it
+    fn contains(&self, other: &Place) -> bool {
+        self.local == other.local
+            && self.projs.len() <= other.projs.len()
+            && self.projs.iter().zip(&other.projs).all(|(a, b)| a.0 == b.0)
+    }
+
+    /// `self` and `other` share memory
+    fn overlaps(&self, other: &Place) -> bool {
+        self.contains(other) || other.contains(self)
+    }
+
+    /// the place `self` is a projection of, up to the projection `index`
+    fn prefix(&self, index: usize) -> Place {
+        Self { local: self.local, projs: self.projs[..index].to_vec() }
+    }
+}
+impl BodyCx<'_, '_> {
+    fn check(&mut self) {
+        for param in &self.body.params {
+            self.bind(param, None, &[]);
+        }
+        self.params = self.scopes[0].iter().copied().collect();
+        let value = &self.body.value;
+        let carried = match &value.kind {
+            ExprKind::Block(block) => self.block(block, true),
+            _ => self.expr(value),
+        };
+        self.release(&carried);
+    }
+
+    fn block(&mut self, block: &Block, returns: bool) -> Vec<usize> {
+        self.scopes.push(Vec::new());
+        for stmt in &block.stmts {
+            self.stmt(stmt);
+        }
+        let carried = match &block.expr {
+            Some(expr) => self.expr(expr),
+            None => Vec::new(),
+        };
+        if returns && let Some(expr) = &block.expr {
+            self.check_return(expr, &carried);
+        }
+        let locals = self.scopes.pop().unwrap();
+        let escaping = if returns { &[][..] } else { &carried };
+        self.end_scope(&locals, block.span.hi(), escaping);
+        carried
+    }
+
+    fn stmt(&mut self, stmt: &Stmt) {
+        match &stmt.kind {
+            StmtKind::Let { pat, init: None, .. } => self.declare_uninit(pat),
+            StmtKind::Let { pat, init: Some(init), els } => {
+                // `let a = b;` uses `b` as a whole, a pattern only its parts
+                let place = match pat.kind {
+                    PatKind::Binding { by_ref: false, sub: None, .. } => None,
+                    _ => self.eval_place(init),
+                };
+                let carried = match place {
+                    Some(_) => Vec::new(),
+                    None => self.expr(init),
+                };
+                if let Some(els) = els {
+                    // the `else` block diverges
+                    let before = self.state.clone();
+                    let unused = self.block(els, false);
+                    self.release(&unused);
+                    self.state = before;
+                }
+                self.bind(pat, place, &carried);
+                self.release(&carried);
+            }
+            StmtKind::Expr(expr) => {
+                let carried = self.expr(expr);
+                self.release(&carried);
+            }
+        }
+    }
+
+    /// evaluates `expr` for its value, a place is moved or copied, and
+    /// returns the borrows the value holds
+    fn expr(&mut self, expr: &Expr) -> Vec<usize> {
+        if let Some(place) = self.eval_place(expr) {
+            return self.consume(place, expr.ty, expr.span);
+        }
+        match &expr.kind {
+            ExprKind::Literal(_)
+            | ExprKind::Local(_)
+            | ExprKind::Def(_)
+            | ExprKind::AssocConst { .. }
+            | ExprKind::Err => Vec::new(),
+            ExprKind::Tuple(elems) | ExprKind::Array(elems) => {
+                self.operands(elems)
+            }
+            ExprKind::Repeat(elem, _) => self.expr(elem),
+            ExprKind::Adt { fields, rest, .. } => {
+                let mut carried =
+                    self.operands(fields.iter().map(|(_, field)| field));
+                if let Some(rest) = rest {
+                    let mentioned: Vec<_> =
+                        fields.iter().map(|(index, _)| *index).collect();
+                    carried.extend(self.rest(rest, &mentioned));
+                }
+                carried
+            }
+            // `*a` of a value that is not a place, e.g. `*f()`
+            ExprKind::Unary(_, operand) | ExprKind::Field(operand, _) => {
+                let carried = self.expr(operand);
+                self.keep(carried, expr.ty)
+            }
+            ExprKind::Index(base, index) => {
+                let carried = self.expr(base);
+                let unused = self.expr(index);
+                self.release(&unused);
+                self.keep(carried, expr.ty)
+            }
+            ExprKind::Ref { mutable, expr: inner, .. } => {
+                match self.eval_place(inner) {
+                    Some(place) => self.borrow(place, *mutable, expr.span),
+                    None => self.expr(inner),
+                }
+            }
+            ExprKind::Box(inner) => self.expr(inner),
+            ExprKind::Binary(op, lhs, rhs) => {
+                let unused = self.expr(lhs);
+                self.release(&unused);
+                // the right operand of `&&` and `||` may not be evaluated
+                let before = matches!(op, BinOp::And | BinOp::Or)
+                    .then(|| self.state.clone());
+                let unused = self.expr(rhs);
+                self.release(&unused);
+                if let Some(before) = before {
+                    let after = mem::take(&mut self.state);
+                    self.state = before.merge(after);
+                }
+                Vec::new()
+            }
+            ExprKind::Assign(lhs, rhs) => {
+                let carried = self.expr(rhs);
+                match self.eval_place(lhs) {
+                    Some(place) => self.assign(place, lhs.span, carried, false),
+                    None => {
+                        let unused = self.expr(lhs);
+                        self.release(&unused);
+                        self.release(&carried);
+                    }
+                }
+                Vec::new()
+            }
+            ExprKind::AssignOp(_, lhs, rhs) => {
+                let unused = self.expr(rhs);
+                self.release(&unused);
+                match self.eval_place(lhs) {
+                    Some(place) => {
+                        self.assign(place, lhs.span, Vec::new(), true)
+                    }
+                    None => {
+                        let unused = self.expr(lhs);
+                        self.release(&unused);
+                    }
+                }
+                Vec::new()
+            }
+            ExprKind::Cast(inner) => {
+                let carried = self.expr(inner);
+                self.keep(carried, expr.ty)
+            }
+            ExprKind::Call(callee, args) => self.call(callee, args, expr.ty),
+            ExprKind::Range { start, end, .. } => {
+                self.operands([&**start, &**end])
+            }
+            ExprKind::Block(block) => self.block(block, false),
+            ExprKind::If { cond, then_branch, else_branch } => {
+                let unused = self.expr(cond);
+                self.release(&unused);
+                let before = self.state.clone();
+                let mut carried = self.block(then_branch, false);
+                let after_then = mem::replace(&mut self.state, before);
+                if let Some(else_branch) = else_branch {
+                    carried.extend(self.expr(else_branch));
+                }
+                let after_else = mem::take(&mut self.state);
+                self.state = after_then.merge(after_else);
+                carried
+            }
+            ExprKind::Match { scrutinee, arms } => {
+                self.check_match(scrutinee, arms)
+            }
+            ExprKind::Loop { id, body } => {
+                self.check_loop(*id, false, &mut |cx| {
+                    let unused = cx.block(body, false);
+                    cx.release(&unused);
+                });
+                Vec::new()
+            }
+            ExprKind::For { id, pat, iter, body } => {
+                // the iterator lives until the loop ends
+                let carried = self.expr(iter);
+                let depth = self.in_flight.len();
+                self.in_flight.extend(&carried);
+                self.check_loop(*id, true, &mut |cx| {
+                    cx.scopes.push(Vec::new());
+                    cx.bind(pat, None, &carried);
+                    let unused = cx.block(body, false);
+                    cx.release(&unused);
+                    let locals = cx.scopes.pop().unwrap();
+                    cx.end_scope(&locals, body.span.hi(), &[]);
+                });
+                self.in_flight.truncate(depth);
+                self.release(&carried);
+                Vec::new()
+            }
+            ExprKind::Break { target, value } => {
+                if let Some(value) = value {
+                    let unused = self.expr(value);
+                    self.release(&unused);
+                }
+                let state = self.state.clone();
+                self.loop_cx(*target).breaks.push(state);
+                self.state.unreachable = true;
+                Vec::new()
+            }
+            ExprKind::Continue { target } => {
+                let state = self.state.clone();
+                self.loop_cx(*target).continues.push(state);
+                self.state.unreachable = true;
+                Vec::new()
+            }
+            ExprKind::Return(value) => {
+                if let Some(value) = value {
+                    let carried = self.expr(value);
+                    self.check_return(value, &carried);
+                    self.release(&carried);
+                }
+                self.state.unreachable = true;
+                Vec::new()
+            }
+            // the arguments are borrowed
+            ExprKind::Format { args, .. } => {
+                for arg in args {
+                    match self.eval_place(arg) {
+                        Some(place) => {
+                            self.read(place, arg.ty, arg.span);
+                        }
+                        None => {
+                            let unused = self.expr(arg);
+                            self.release(&unused);
+                        }
+                    }
+                }
+                Vec::new()
+            }
+        }
+    }
+
+    /// evaluates the operands in order, the borrows of the earlier ones
+    /// live while the later ones are evaluated
+    fn operands<'e>(
+        &mut self, exprs: impl IntoIterator<Item = &'e Expr>,
+    ) -> Vec<usize> {
+        let depth = self.in_flight.len();
+        let mut carried = Vec::new();
+        for expr in exprs {
+            let found = self.expr(expr);
+            self.in_flight.extend(&found);
+            carried.extend(found);
+        }
+        self.in_flight.truncate(depth);
+        carried
+    }
+
+    fn call(&mut self, callee: &Expr, args: &[Expr], ty: Ty) -> Vec<usize> {
+        let unused = self.expr(callee);
+        self.release(&unused);
+        // `a.f(a.len())` borrows `a` mutably after the other arguments, but
+        // `f(&mut a, a)` does not
+        let two_phase = matches!(
+            args.first().map(|arg| &arg.kind),
+            Some(ExprKind::Ref { mutable: true, expr, autoref: true })
+                if is_place(expr)
+        );
+        let mut order: Vec<&Expr> = args.iter().collect();
+        if two_phase {
+            order.rotate_left(1);
+        }
+        let depth = self.in_flight.len();
+        let mut carried = Vec::new();
+        for arg in order {
+            // a `&mut` reference is reborrowed, not moved
+            let found = match self.tcx.kind(arg.ty) {
+                TyKind::Ref { mutable: true, .. } if is_place(arg) => {
+                    let place = self.eval_place(arg).unwrap();
+                    self.read(place, arg.ty, arg.span)
+                }
+                _ => self.expr(arg),
+            };
+            self.in_flight.extend(&found);
+            for id in &found {
+                self.callees.insert(*id, callee.span);
+            }
+            carried.extend(found);
+        }
+        self.in_flight.truncate(depth);
+        self.keep(carried, ty)
+    }
+
+    /// `..rest` of a struct expression moves the fields not in `mentioned`
+    fn rest(&mut self, rest: &Expr, mentioned: &[usize]) -> Vec<usize> {
+        let Some(place) = self.eval_place(rest) else {
+            return self.expr(rest);
+        };
+        let TyKind::Adt(id, _) = *self.tcx.kind(rest.ty) else {
+            return Vec::new();
+        };
+        let count = self.tcx.adt(id).unwrap().variants[0].fields.len();
+        let mut carried = Vec::new();
+        for index in (0..count).filter(|index| !mentioned.contains(index)) {
+            let ty = self.tcx.field_ty(rest.ty, 0, index);
+            let field = place.project(Proj::Field(0, index), ty);
+            carried.extend(self.consume(field, ty, rest.span));
+        }
+        carried
+    }
+
+    fn check_match(
+        &mut self, scrutinee: &Expr, arms: &[hir::Arm],
+    ) -> Vec<usize> {
+        let place = self.eval_place(scrutinee);
+        let carried = match &place {
+            // inspected, only the bindings move out of it
+            Some(place) => {
+                self.read(place.clone(), scrutinee.ty, scrutinee.span);
+                Vec::new()
+            }
+            None => self.expr(scrutinee),
+        };
+        let before = self.state.clone();
+        let mut merged = State::unreachable();
+        let mut result = Vec::new();
+        for arm in arms {
+            self.state = before.clone();
+            self.scopes.push(Vec::new());
+            self.bind(&arm.pat, place.clone(), &carried);
+            if let Some(guard) = &arm.guard {
+                let unused = self.expr(guard);
+                self.release(&unused);
+            }
+            result.extend(self.expr(&arm.body));
+            let locals = self.scopes.pop().unwrap();
+            self.end_scope(&locals, arm.span.hi(), &result);
+            merged = merged.merge(mem::take(&mut self.state));
+        }
+        self.state = merged;
+        let unused: Vec<_> =
+            carried.into_iter().filter(|id| !result.contains(id)).collect();
+        self.release(&unused);
+        result
+    }
+
+    /// checks a loop body twice, the second time with the moves and the
+    /// borrows of the first iteration
+    fn check_loop(
+        &mut self, id: LoopId, may_skip: bool, body: &mut dyn FnMut(&mut Self),
+    ) {
+        let entry = self.state.clone();
+        self.loops.push(LoopCx {
+            id,
+            breaks: Vec::new(),
+            continues: Vec::new(),
+        });
+        body(self);
+        let continues =
+            mem::take(&mut self.loops.last_mut().unwrap().continues);
+        let mut back = mem::take(&mut self.state);
+        for state in continues {
+            back = back.merge(state);
+        }
+        self.state = entry.clone().merge(back);
+        body(self);
+        let cx = self.loops.pop().unwrap();
+        let mut exit = match may_skip {
+            true => {
+                let mut end = mem::take(&mut self.state);
+                for state in cx.continues {
+                    end = end.merge(state);
+                }
+                entry.merge(end)
+            }
+            false => State::unreachable(),
+        };
+        for state in cx.breaks {
+            exit = exit.merge(state);
+        }
+        self.state = exit;
+    }
+
+    fn loop_cx(&mut self, id: LoopId) -> &mut LoopCx {
+        self.loops.iter_mut().rev().find(|cx| cx.id == id).unwrap()
+    }
+
+    /// binds the locals of `pat` to `place`, or to a temporary holding the
+    /// borrows `carried`
+    fn bind(&mut self, pat: &Pat, place: Option<Place>, carried: &[usize]) {
+        match &pat.kind {
+            PatKind::Wild
+            | PatKind::Literal(_)
+            | PatKind::Const(_)
+            | PatKind::Range { .. } => {}
+            PatKind::Binding { local, by_ref, sub } => {
+                let held = match &place {
+                    Some(place) if *by_ref => {
+                        let local_ty = self.body.local(*local).ty;
+                        let mutable =
+                            matches!(self.tcx.kind(local_ty), TyKind::Ref {
+                                mutable: true,
+                                ..
+                            });
+                        self.borrow(place.clone(), mutable, pat.span)
+                    }
+                    Some(place) => {
+                        self.consume(place.clone(), pat.ty, pat.span)
+                    }
+                    None => carried.to_vec(),
+                };
+                self.declare(*local, true);
+                self.hold(*local, &held);
+                if let Some(sub) = sub {
+                    self.bind(sub, place, carried);
+                }
+            }
+            PatKind::Adt { variant, fields, .. } => {
+                for (index, field) in fields {
+                    let place = place.as_ref().map(|place| {
+                        place.project(Proj::Field(*variant, *index), field.ty)
+                    });
+                    self.bind(field, place, carried);
+                }
+            }
+            PatKind::Tuple(elems) => {
+                for (index, elem) in elems.iter().enumerate() {
+                    let place = place.as_ref().map(|place| {
+                        place.project(Proj::Field(0, index), elem.ty)
+                    });
+                    self.bind(elem, place, carried);
+                }
+            }
+            PatKind::Slice { prefix, suffix, .. } => {
+                for elem in prefix.iter().chain(suffix) {
+                    let place = place
+                        .as_ref()
+                        .map(|place| place.project(Proj::Index, elem.ty));
+                    self.bind(elem, place, carried);
+                }
+            }
+            PatKind::Ref(inner) => {
+                let place = place
+                    .as_ref()
+                    .map(|place| place.project(Proj::Deref, inner.ty));
+                self.bind(inner, place, carried);
+            }
+            // the alternatives bind the same locals
+            PatKind::Or(alternatives) => {
+                if let Some(first) = alternatives.first() {
+                    self.bind(first, place, carried);
+                }
+            }
+        }
+    }
+
+    /// `let a;`, the locals of `pat` without a value
+    fn declare_uninit(&mut self, pat: &Pat) {
+        match &pat.kind {
+            PatKind::Binding { local, .. } => {
+                self.declare(*local, false);
+                self.state.moves.push(Move {
+                    place:  Place::new(*local),
+                    ty:     pat.ty,
+                    span:   pat.span,
+                    uninit: true,
+                });
+            }
+            PatKind::Tuple(elems) => {
+                for elem in elems {
+                    self.declare_uninit(elem);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    fn declare(&mut self, local: LocalId, initialized: bool) {
+        self.scopes.last_mut().unwrap().push(local);
+        self.state.moves.retain(|moved| moved.place.local != local);
+        for borrow in &mut self.state.borrows {
+            borrow.holders.retain(|holder| *holder != local);
+        }
+        self.state.assigned.retain(|(assigned, _)| *assigned != local);
+        let decl = self.body.local(local);
+        if initialized && !decl.mutable {
+            self.state.assigned.push((local, decl.span));
+        }
+    }
+
+    /// the locals of a block go out of scope, a borrow of them must not be
+    /// used after `end`
+    fn end_scope(
+        &mut self, locals: &[LocalId], end: usize, escaping: &[usize],
+    ) {
+        let borrows = self.state.borrows.clone();
+        for borrow in &borrows {
+            if self.state.unreachable
+                || !locals.contains(&borrow.place.local)
+                || self.through_ref(&borrow.place)
+            {
+                continue;
+            }
+            let later = borrow
+                .holders
+                .iter()
+                .filter(|holder| !locals.contains(holder))
+                .find_map(|holder| self.next_use(*holder, end));
+            if later.is_none() && !escaping.contains(&borrow.id) {
+                continue;
+            }
+            let decl = self.body.local(borrow.place.local);
+            let name = &decl.name;
+            let mut diagnostic = Diagnostic::error(format!(
+                "`{name}` does not live long enough"
+            ))
+            .with_code("E0597")
+            .with_primary(
+                borrow.span,
+                "borrowed value does not live long enough",
+            )
+            .with_secondary(
+                decl.span,
+                format!("binding `{name}` declared here"),
+            );
+            if let Some(later) = later {
+                diagnostic =
+                    diagnostic.with_secondary(later, "borrow later used here");
+            }
+            self.error(diagnostic.with_note(format!(
+                "`{name}` is dropped at the end of its block while still \
+                 borrowed"
+            )));
+        }
+        let in_flight = self.in_flight.clone();
+        self.state.borrows.retain_mut(|borrow| {
+            if locals.contains(&borrow.place.local) {
+                return false;
+            }
+            let held = !borrow.holders.is_empty();
+            borrow.holders.retain(|holder| !locals.contains(holder));
+            // a borrow only stored in the locals ends with them
+            !held
+                || !borrow.holders.is_empty()
+                || escaping.contains(&borrow.id)
+                || in_flight.contains(&borrow.id)
+        });
+        self.state.moves.retain(|moved| !locals.contains(&moved.place.local));
+        self.state.assigned.retain(|(local, _)| !locals.contains(local));
+    }
+
+    /// a reference to data owned by the body must not be returned
+    fn check_return(&mut self, value: &Expr, carried: &[usize]) {
+        if !self.may_hold_refs(value.ty) {
+            return;
+        }
+        let borrow = self.state.borrows.iter().find(|borrow| {
+            carried.contains(&borrow.id) && !self.through_ref(&borrow.place)
+        });
+        let Some(borrow) = borrow.cloned() else { return };
+        let name = &self.body.local(borrow.place.local).name;
+        let kind = match self.params.contains(&borrow.place.local) {
+            true => "function parameter",
+            false => "local variable",
+        };
+        let mut tail = value;
+        while let ExprKind::Block(Block { expr: Some(expr), .. }) = &tail.kind {
+            tail = expr;
+        }
+        let diagnostic = match tail.kind {
+            ExprKind::Ref { .. } => Diagnostic::error(format!(
+                "cannot return reference to {kind} `{name}`"
+            ))
+            .with_code("E0515")
+            .with_primary(
+                value.span,
+                "returns a reference to data owned by the current function",
+            ),
+            _ => Diagnostic::error(format!(
+                "cannot return value referencing {kind} `{name}`"
+            ))
+            .with_code("E0515")
+            .with_primary(
+                value.span,
+                "returns a value referencing data owned by the current \
+                 function",
+            )
+            .with_secondary(borrow.span, format!("`{name}` is borrowed here")),
+        };
+        self.error(diagnostic);
+    }
+
+    /// the place `expr` names after evaluating the indices in it, e.g.
+    /// `a.b[i]`
+    fn eval_place(&mut self, expr: &Expr) -> Option<Place> {
+        if !is_place(expr) {
+            return None;
+        }
+        Some(match &expr.kind {
+            ExprKind::Local(local) => Place::new(*local),
+            ExprKind::Field(base, index) => {
+                let base = self.eval_place(base)?;
+                base.project(Proj::Field(0, *index), expr.ty)
+            }
+            ExprKind::Index(base, index) => {
+                let base = self.eval_place(base)?;
+                let unused = self.expr(index);
+                self.release(&unused);
+                base.project(Proj::Index, expr.ty)
+            }
+            ExprKind::Unary(UnOp::Deref, base) => {
+                self.eval_place(base)?.project(Proj::Deref, expr.ty)
+            }
+            _ => unreachable!(),
+        })
+    }
+
+    /// a place used by value, moved unless its type is `Copy`
+    fn consume(&mut self, place: Place, ty: Ty, span: Span) -> Vec<usize> {
+        if self.tcx.is_copy(ty) {
+            return self.read(place, ty, span);
+        }
+        if !self.check_init(&place, span, Access::Use) {
+            return Vec::new();
+        }
+        if let Some(diagnostic) = self.move_out_error(&place, ty, span) {
+            self.error(diagnostic);
+            return Vec::new();
+        }
+        if let Some(borrow) = self.conflict(&place, span.lo(), false) {
+            let name = self.describe(&place);
+            let borrowed = self.describe(&borrow.place);
+            let mut diagnostic = Diagnostic::error(format!(
+                "cannot move out of `{name}` because it is borrowed"
+            ))
+            .with_code("E0505")
+            .with_primary(span, format!("move out of `{name}` occurs here"))
+            .with_secondary(
+                borrow.span,
+                format!("borrow of `{borrowed}` occurs here"),
+            );
+            if let Some((later, at)) = self.later_use(&borrow, span.lo()) {
+                diagnostic = diagnostic
+                    .with_secondary(later, format!("borrow later used {at}"));
+            }
+            self.error(diagnostic);
+        }
+        let carried = self.held_by(place.local, ty);
+        self.state.moves.push(Move { place, ty, span, uninit: false });
+        carried
+    }
+
+    /// a place used without moving it, e.g. copied or printed
+    fn read(&mut self, place: Place, ty: Ty, span: Span) -> Vec<usize> {
+        if !self.check_init(&place, span, Access::Use) {
+            return Vec::new();
+        }
+        if let Some(borrow) = self.conflict(&place, span.lo(), true) {
+            let name = self.describe(&place);
+            let borrowed = self.describe(&borrow.place);
+            let mut diagnostic = Diagnostic::error(format!(
+                "cannot use `{name}` because it was mutably borrowed"
+            ))
+            .with_code("E0503")
+            .with_primary(span, format!("use of borrowed `{borrowed}`"))
+            .with_secondary(
+                borrow.span,
+                format!("`{borrowed}` is borrowed here"),
+            );
+            if let Some((later, at)) = self.later_use(&borrow, span.lo()) {
+                diagnostic = diagnostic
+                    .with_secondary(later, format!("borrow later used {at}"));
+            }
+            self.error(diagnostic);
+        }
+        self.held_by(place.local, ty)
+    }
+
+    fn borrow(
+        &mut self, place: Place, mutable: bool, span: Span,
+    ) -> Vec<usize> {
+        self.check_init(&place, span, Access::Borrow);
+        if mutable && let Some(immutable) = self.immutability(&place) {
+            let name = self.describe(&place);
+            let diagnostic = match immutable {
+                Immutable::Local => Diagnostic::error(format!(
+                    "cannot borrow `{name}` as mutable, as `{}` is not \
+                     declared as mutable",
+                    self.body.local(place.local).name
+                ))
+                .with_code("E0596")
+                .with_primary(span, "cannot borrow as mutable")
+                .with_help(format!(
+                    "consider changing this to be mutable: `mut {}`",
+                    self.body.local(place.local).name
+                )),
+                Immutable::Ref(base) => Diagnostic::error(format!(
+                    "cannot borrow `{name}` as mutable, as it is behind a `&` \
+                     reference"
+                ))
+                .with_code("E0596")
+                .with_primary(
+                    span,
+                    format!(
+                        "`{}` is a `&` reference, so the data it refers to \
+                         cannot be borrowed as mutable",
+                        self.describe(&base)
+                    ),
+                ),
+                Immutable::Ptr(base) => Diagnostic::error(format!(
+                    "cannot borrow `{name}` as mutable, as it is behind a \
+                     `*const` pointer"
+                ))
+                .with_code("E0596")
+                .with_primary(
+                    span,
+                    format!(
+                        "`{}` is a `*const` pointer, so the data it refers to \
+                         cannot be borrowed as mutable",
+                        self.describe(&base)
+                    ),
+                ),
+            };
+            self.error(diagnostic);
+        }
+        if let Some(other) = self.conflict(&place, span.lo(), !mutable) {
+            let name = self.describe(&place);
+            let (message, label, other_label, later_label) =
+                match (mutable, other.mutable) {
+                    (true, true) => (
+                        format!(
+                            "cannot borrow `{name}` as mutable more than once \
+                             at a time"
+                        ),
+                        "second mutable borrow occurs here",
+                        "first mutable borrow occurs here",
+                        "first borrow later used",
+                    ),
+                    (true, false) => (
+                        format!(
+                            "cannot borrow `{name}` as mutable because it is \
+                             also borrowed as immutable"
+                        ),
+                        "mutable borrow occurs here",
+                        "immutable borrow occurs here",
+                        "immutable borrow later used",
+                    ),
+                    (false, _) => (
+                        format!(
+                            "cannot borrow `{name}` as immutable because it \
+                             is also borrowed as mutable"
+                        ),
+                        "immutable borrow occurs here",
+                        "mutable borrow occurs here",
+                        "mutable borrow later used",
+                    ),
+                };
+            let code = match mutable && other.mutable {
+                true => "E0499",
+                false => "E0502",
+            };
+            let mut diagnostic = Diagnostic::error(message)
+                .with_code(code)
+                .with_primary(span, label)
+                .with_secondary(other.span, other_label);
+            if let Some((later, at)) = self.later_use(&other, span.lo()) {
+                diagnostic = diagnostic
+                    .with_secondary(later, format!("{later_label} {at}"));
+            }
+            self.error(diagnostic);
+        }
+        let id = self.next_borrow;
+        self.next_borrow += 1;
+        let mut carried = vec![id];
+        // a reborrow through a reference lives no longer than the reference
+        if place.projs.iter().any(|(proj, _)| *proj == Proj::Deref) {
+            carried.extend(self.held_by(place.local, None));
+        }
+        self.state.borrows.push(Borrow {
+            id,
+            place,
+            mutable,
+            span,
+            holders: Vec::new(),
+        });
+        carried
+    }
+
+    /// `place = value`, or `place += value` if `compound`
+    fn assign(
+        &mut self, place: Place, span: Span, carried: Vec<usize>,
+        compound: bool,
+    ) {
+        if compound {
+            let ty = self.ty_of(&place);
+            self.read(place.clone(), ty, span);
+        } else if !place.projs.is_empty() {
+            self.check_init(&place, span, Access::Assign);
+        }
+        let name = self.describe(&place);
+        match self.immutability(&place) {
+            Some(Immutable::Local) if place.projs.is_empty() => {
+                let local = place.local;
+                let first = self
+                    .state
+                    .assigned
+                    .iter()
+                    .find(|(assigned, _)| *assigned == local)
+                    .map(|(_, span)| *span);
+                match first {
+                    Some(first) => self.error(
+                        Diagnostic::error(format!(
+                            "cannot assign twice to immutable variable \
+                             `{name}`"
+                        ))
+                        .with_code("E0384")
+                        .with_primary(
+                            span,
+                            "cannot assign twice to immutable variable",
+                        )
+                        .with_secondary(
+                            first,
+                            format!("first assignment to `{name}`"),
+                        )
+                        .with_help(format!(
+                            "consider making this binding mutable: `mut \
+                             {name}`"
+                        )),
+                    ),
+                    None => self.state.assigned.push((local, span)),
+                }
+            }
+            Some(Immutable::Local) => {
+                let local = &self.body.local(place.local).name;
+                self.error(
+                    Diagnostic::error(format!(
+                        "cannot assign to `{name}`, as `{local}` is not \
+                         declared as mutable"
+                    ))
+                    .with_code("E0594")
+                    .with_primary(span, "cannot assign")
+                    .with_help(format!(
+                        "consider changing this to be mutable: `mut {local}`"
+                    )),
+                );
+            }
+            Some(Immutable::Ref(base)) => {
+                let base = self.describe(&base);
+                self.error(
+                    Diagnostic::error(format!(
+                        "cannot assign to `{name}`, which is behind a `&` \
+                         reference"
+                    ))
+                    .with_code("E0594")
+                    .with_primary(
+                        span,
+                        format!(
+                            "`{base}` is a `&` reference, so the data it \
+                             refers to cannot be written"
+                        ),
+                    ),
+                );
+            }
+            Some(Immutable::Ptr(base)) => {
+                let base = self.describe(&base);
+                self.error(
+                    Diagnostic::error(format!(
+                        "cannot assign to `{name}`, which is behind a \
+                         `*const` pointer"
+                    ))
+                    .with_code("E0594")
+                    .with_primary(
+                        span,
+                        format!(
+                            "`{base}` is a `*const` pointer, so the data it \
+                             refers to cannot be written"
+                        ),
+                    ),
+                );
+            }
+            None => {}
+        }
+        if let Some(borrow) = self.conflict(&place, span.lo(), false) {
+            let borrowed = self.describe(&borrow.place);
+            let mut diagnostic = Diagnostic::error(format!(
+                "cannot assign to `{name}` because it is borrowed"
+            ))
+            .with_code("E0506")
+            .with_primary(
+                span,
+                format!(
+                    "`{name}` is assigned to here but it was already borrowed"
+                ),
+            )
+            .with_secondary(
+                borrow.span,
+                format!("`{borrowed}` is borrowed here"),
+            );
+            if let Some((later, at)) = self.later_use(&borrow, span.lo()) {
+                diagnostic = diagnostic
+                    .with_secondary(later, format!("borrow later used {at}"));
+            }
+            self.error(diagnostic);
+        }
+        if compound {
+            return;
+        }
+        // the place has a value again
+        self.state.moves.retain(|moved| !place.contains(&moved.place));
+        if place.projs.is_empty() {
+            for borrow in &mut self.state.borrows {
+                borrow.holders.retain(|holder| *holder != place.local);
+            }
+        }
+        self.hold(place.local, &carried);
+        self.release(&carried);
+    }
+
+    /// reports a use of `place` after it or a part of it is moved, or
+    /// before it has a value
+    fn check_init(
+        &mut self, place: &Place, span: Span, access: Access,
+    ) -> bool {
+        let found = self.state.moves.iter().find(|moved| match access {
+            // a part of a moved place may be assigned again
+            Access::Assign => {
+                moved.place.contains(place) && moved.place != *place
+            }
+            Access::Use | Access::Borrow => moved.place.overlaps(place),
+        });
+        let Some(moved) = found.cloned() else { return true };
+        let name = self.describe(&moved.place);
+        let verb = match access {
+            Access::Use => "used",
+            Access::Borrow => "borrowed",
+            Access::Assign => "partially assigned",
+        };
+        if moved.uninit {
+            let message = match access {
+                Access::Assign => {
+                    format!(
+                        "partially assigned binding `{name}` isn't fully \
+                         initialized"
+                    )
+                }
+                _ => format!("used binding `{name}` isn't initialized"),
+            };
+            self.error(
+                Diagnostic::error(message)
+                    .with_primary(
+                        span,
+                        format!(
+                            "`{name}` {verb} here but it isn't initialized"
+                        ),
+                    )
+                    .with_secondary(
+                        moved.span,
+                        "binding declared here but left uninitialized",
+                    ),
+            );
+            return false;
+        }
+        let partial = !moved.place.contains(place);
+        let noun = match access {
+            Access::Use => "use",
+            Access::Borrow => "borrow",
+            Access::Assign => "assign to part",
+        };
+        let ty = self.tcx.display(moved.ty);
+        let mut moved_label = match partial {
+            true => "value partially moved here".to_string(),
+            false => "value moved here".to_string(),
+        };
+        if moved.span.lo() >= span.lo() {
+            moved_label.push_str(", in previous iteration of loop");
+        }
+        let diagnostic = match partial {
+            true => Diagnostic::error(format!(
+                "{noun} of partially moved value: `{}`",
+                self.describe(place)
+            ))
+            .with_code("E0382")
+            .with_primary(span, format!("value {verb} here after partial move"))
+            .with_note(format!(
+                "partial move occurs because `{name}` has type `{ty}`, which \
+                 does not implement the `Copy` trait"
+            )),
+            false => {
+                Diagnostic::error(format!("{noun} of moved value: `{name}`"))
+                    .with_code("E0382")
+                    .with_primary(span, format!("value {verb} here after move"))
+                    .with_note(format!(
+                        "move occurs because `{name}` has type `{ty}`, which \
+                         does not implement the `Copy` trait"
+                    ))
+            }
+        };
+        self.error(diagnostic.with_secondary(moved.span, moved_label));
+        false
+    }
+
+    /// moving out of a reference, a pointer or an element is not allowed
+    fn move_out_error(
+        &self, place: &Place, ty: Ty, span: Span,
+    ) -> Option<Diagnostic> {
+        let name = self.describe(place);
+        let found = self.tcx.display(ty);
+        let label = format!(
+            "move occurs because `{name}` has type `{found}`, which does not \
+             implement the `Copy` trait"
+        );
+        for (index, (proj, _)) in place.projs.iter().enumerate() {
+            let base = self.base_ty(place, index);
+            let behind = match (proj, self.tcx.kind(base)) {
+                (Proj::Deref, TyKind::Ref { mutable: false, .. }) => {
+                    "a shared reference"
+                }
+                (Proj::Deref, TyKind::Ref { mutable: true, .. }) => {
+                    "a mutable reference"
+                }
+                (Proj::Deref, TyKind::Ptr { .. }) => "a raw pointer",
+                (Proj::Index, _) => {
+                    let base = self.tcx.display(base);
+                    return Some(
+                        Diagnostic::error(format!(
+                            "cannot move out of index of `{base}`"
+                        ))
+                        .with_code("E0508")
+                        .with_primary(
+                            span,
+                            format!(
+                                "move occurs because value has type \
+                                 `{found}`, which does not implement the \
+                                 `Copy` trait"
+                            ),
+                        ),
+                    );
+                }
+                _ => continue,
+            };
+            return Some(
+                Diagnostic::error(format!(
+                    "cannot move out of `{name}` which is behind {behind}"
+                ))
+                .with_code("E0507")
+                .with_primary(span, label),
+            );
+        }
+        None
+    }
+
+    /// why `place` cannot be written
+    fn immutability(&self, place: &Place) -> Option<Immutable> {
+        let mutable = self.body.local(place.local).mutable;
+        let mut found = (!mutable).then_some(Immutable::Local);
+        for (index, (proj, _)) in place.projs.iter().enumerate() {
+            if *proj != Proj::Deref {
+                continue;
+            }
+            match *self.tcx.kind(self.base_ty(place, index)) {
+                TyKind::Ref { mutable, .. } => {
+                    found =
+                        (!mutable).then(|| Immutable::Ref(place.prefix(index)))
+                }
+                TyKind::Ptr { mutable, .. } => {
+                    found =
+                        (!mutable).then(|| Immutable::Ptr(place.prefix(index)))
+                }
+                _ => {}
+            }
+        }
+        found
+    }
+
+    /// a live borrow overlapping `place` at `pos`, only a mutable one if
+    /// `mutable_only`
+    fn conflict(
+        &self, place: &Place, pos: usize, mutable_only: bool,
+    ) -> Option<Borrow> {
+        self.state
+            .borrows
+            .iter()
+            .filter(|borrow| borrow.mutable || !mutable_only)
+            .filter(|borrow| borrow.place.overlaps(place))
+            .find(|borrow| self.is_live(borrow, pos))
+            .cloned()
+    }
+
+    fn is_live(&self, borrow: &Borrow, pos: usize) -> bool {
+        borrow.holders.is_empty()
+            || self.in_flight.contains(&borrow.id)
+            || self.later_use(borrow, pos).is_some()
+    }
+
+    /// the call the borrow is an argument of, or the first use after `pos`
+    /// of a local the borrow is stored in, with `by call` or `here`
+    fn later_use(
+        &self, borrow: &Borrow, pos: usize,
+    ) -> Option<(Span, &'static str)> {
+        if self.in_flight.contains(&borrow.id)
+            && let Some(callee) = self.callees.get(&borrow.id)
+        {
+            return Some((*callee, "by call"));
+        }
+        borrow
+            .holders
+            .iter()
+            .filter_map(|holder| {
+                let uses = self.uses.get(holder)?;
+                uses.iter().find(|(at, _)| *at > pos)
+            })
+            .min_by_key(|(at, _)| *at)
+            .map(|(_, span)| (*span, "here"))
+    }
+
+    fn next_use(&self, local: LocalId, pos: usize) -> Option<Span> {
+        let uses = self.uses.get(&local)?;
+        uses.iter().find(|(at, _)| *at > pos).map(|(_, span)| *span)
+    }
+
+    /// the borrows stored in `local`, if a value of type `ty` may hold them
+    fn held_by(&self, local: LocalId, ty: impl Into<Option<Ty>>) -> Vec<usize> {
+        if ty.into().is_some_and(|ty| !self.may_hold_refs(ty)) {
+            return Vec::new();
+        }
+        self.state
+            .borrows
+            .iter()
+            .filter(|borrow| borrow.holders.contains(&local))
+            .map(|borrow| borrow.id)
+            .collect()
+    }
+
+    /// stores the borrows in `local`
+    fn hold(&mut self, local: LocalId, carried: &[usize]) {
+        if !self.may_hold_refs(self.body.local(local).ty) {
+            return;
+        }
+        for borrow in &mut self.state.borrows {
+            if carried.contains(&borrow.id) && !borrow.holders.contains(&local)
+            {
+                borrow.holders.push(local);
+            }
+        }
+    }
+
+    /// the borrows of a value of type `ty`, none if it cannot hold them
+    fn keep(&mut self, carried: Vec<usize>, ty: Ty) -> Vec<usize> {
+        if self.may_hold_refs(ty) {
+            return carried;
+        }
+        self.release(&carried);
+        Vec::new()
+    }
+
+    /// ends the temporary borrows in `carried`
+    fn release(&mut self, carried: &[usize]) {
+        let in_flight = &self.in_flight;
+        self.state.borrows.retain(|borrow| {
+            !carried.contains(&borrow.id)
+                || !borrow.holders.is_empty()
+                || in_flight.contains(&borrow.id)
+        });
+    }
+
+    /// a value of type `ty` may hold a reference, a generic parameter may
+    /// be one
+    fn may_hold_refs(&self, ty: Ty) -> bool {
+        self.tcx.any(ty, &mut |ty| {
+            matches!(
+                self.tcx.kind(ty),
+                TyKind::Ref { .. }
+                    | TyKind::Param(_)
+                    | TyKind::Projection { .. }
+            )
+        })
+    }
+
+    /// the place contains a dereference of a reference or a pointer, so it
+    /// is not owned by the body
+    fn through_ref(&self, place: &Place) -> bool {
+        place.projs.iter().enumerate().any(|(index, (proj, _))| {
+            *proj == Proj::Deref
+                && matches!(
+                    self.tcx.kind(self.base_ty(place, index)),
+                    TyKind::Ref { .. } | TyKind::Ptr { .. }
+                )
+        })
+    }
+
+    /// the type of the place the projection `index` of `place` applies to
+    fn base_ty(&self, place: &Place, index: usize) -> Ty {
+        match index {
+            0 => self.body.local(place.local).ty,
+            _ => place.projs[index - 1].1,
+        }
+    }
+
+    fn ty_of(&self, place: &Place) -> Ty {
+        match place.projs.last() {
+            Some((_, ty)) => *ty,
+            None => self.body.local(place.local).ty,
+        }
+    }
+
+    /// e.g. `a`, `a.b`, `*a`, `a[_]`, a field through a reference without
+    /// the `*`
+    fn describe(&self, place: &Place) -> String {
+        let mut text = self.body.local(place.local).name.clone();
+        for (index, (proj, _)) in place.projs.iter().enumerate() {
+            match proj {
+                Proj::Deref => {
+                    let next = place.projs.get(index + 1);
+                    if !matches!(next, Some((Proj::Field(..), _))) {
+                        text = format!("*{text}");
+                    }
+                }
+                Proj::Field(variant, field) => {
+                    let base = self.base_ty(place, index);
+                    let name = match self.tcx.kind(base) {
+                        TyKind::Adt(id, _) => {
+                            self.tcx.adt(*id).unwrap().variants[*variant].fields
+                                [*field]
+                                .name
+                                .clone()
+                        }
+                        _ => field.to_string(),
+                    };
+                    text = format!("{text}.{name}");
+                }
+                Proj::Index => text.push_str("[_]"),
+            }
+        }
+        text
+    }
+
+    fn error(&mut self, diagnostic: Diagnostic) {
+        let span = diagnostic.primary_span().unwrap();
+        let key = (diagnostic.message().to_string(), span);
+        if self.reported.insert(key) {
+            self.diagnostics.push(diagnostic);
+        }
+    }
+}
+/// a local, or a field, an element or a dereference of a place
+fn is_place(expr: &Expr) -> bool {
+    match &expr.kind {
+        ExprKind::Local(_) => true,
+        ExprKind::Field(base, _)
+        | ExprKind::Index(base, _)
+        | ExprKind::Unary(UnOp::Deref, base) => is_place(base),
+        _ => false,
+    }
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::semantic::tests::{Checked, check_clean};
+    /// the errors of the borrow checker on `source`, each with its labels
+    /// and the text they point at, the primary one first
+    fn errors(source: &str) -> Vec<String> {
+        let Checked { source_map, mut tcx, bodies, .. } = check_clean(source);
+        let mut borrowck = BorrowChecker::new(&mut tcx);
+        borrowck.check_crate(&bodies);
+        borrowck
+            .take_diagnostics()
+            .iter()
+            .map(|diagnostic| {
+                let mut labels: Vec<_> = diagnostic.labels().iter().collect();
+                labels.sort_by_key(|label| !label.is_primary());
+                let labels: Vec<_> = labels
+                    .iter()
+                    .map(|label| {
+                        let snippet = source_map.snippet(label.span());
+                        format!("{} `{snippet}`", label.message())
+                    })
+                    .collect();
+                format!("{}: {}", diagnostic.message(), labels.join("; "))
+            })
+            .collect()
+    }
+    #[test]
+    fn moves() {
+        let errors = errors(
+            "struct Point { x: i32, y: i32 }
+            struct Line { a: Point, b: Point }
+            fn take(p: Point) {}
+            fn main() {
+                let a = Point { x: 1, y: 2 };
+                take(a);
+                take(a);
+                let n = 1;
+                let m = n;
+                let k = n + m;
+                let l = Line { a: Point { x: 1, y: 2 }, b: Point { x: 3, y: 4 \
+             } };
+                let c = l.a;
+                let d = l.b;
+                let e = &l;
+                let f = Point { x: 1, y: 2 };
+                let g = (&f, f.x);
+                loop { take(f); }
+            }
+            fn cond(b: bool) {
+                let a = Point { x: 1, y: 2 };
+                if b { take(a); } else { let c = 1; }
+                let d = a;
+                let e = Point { x: 1, y: 2 };
+                if b { take(e); return; }
+                let f = e;
+                let mut g = Point { x: 1, y: 2 };
+                take(g);
+                g = Point { x: 3, y: 4 };
+                let h = g;
+                let i: Point;
+                let j = i;
+                let r = &h;
+                let s = *r;
+            }
+            #[derive(Copy)] struct Meters(i32);
+            #[derive(Copy)] struct Wrap<T> { t: T }
+            fn copies() {
+                let m = Meters(1);
+                let n = (m, m);
+                let w = Wrap { t: 1 };
+                let x = (w, w);
+                let p = Wrap { t: Point { x: 1, y: 2 } };
+                let q = (p, p);
+            }",
+        );
+        assert_eq!(errors, [
+            "use of moved value: `a`: value used here after move `a`; value \
+             moved here `a`",
+            "borrow of partially moved value: `l`: value borrowed here after \
+             partial move `&l`; value partially moved here `l.a`",
+            "use of moved value: `f`: value used here after move `f`; value \
+             moved here, in previous iteration of loop `f`",
+            "use of moved value: `a`: value used here after move `a`; value \
+             moved here `a`",
+            "used binding `i` isn't initialized: `i` used here but it isn't \
+             initialized `i`; binding declared here but left uninitialized `i`",
+            "cannot move out of `*r` which is behind a shared reference: move \
+             occurs because `*r` has type `Point`, which does not implement \
+             the `Copy` trait `*r`",
+            "use of moved value: `p`: value used here after move `p`; value \
+             moved here `p`",
+        ]);
+    }
+    #[test]
+    fn borrows() {
+        let errors = errors(
+            "struct Point { x: i32, y: i32 }
+            fn push(v: &mut i32, n: i32) { *v += n; }
+            fn both(a: &mut i32, b: &mut i32) {}
+            fn read(a: &mut i32, b: &i32) {}
+            struct Counter { n: i32 }
+            impl Counter {
+                fn add(&mut self, n: i32) { self.n += n; }
+                fn get(&self) -> i32 { self.n }
+            }
+            fn main() {
+                let mut v = 1;
+                let r = &mut v;
+                let s = &mut v;
+                *r = 2;
+                let a = &v;
+                let b = &mut v;
+                let c = *a;
+                let t = &mut v;
+                let u = v;
+                *t = 3;
+                let w = &v;
+                v = 4;
+                let x = *w;
+                let y = &mut v;
+                push(y, 1);
+                push(y, 2);
+                push(&mut v, v);
+                both(&mut v, &mut v);
+                read(&mut v, &v);
+                let mut c = Counter { n: 1 };
+                c.add(c.get());
+                let mut p = Point { x: 1, y: 2 };
+                let px = &mut p.x;
+                let py = &mut p.y;
+                *px = *py;
+                let n = 1;
+                n = 2;
+                let q = Point { x: 1, y: 2 };
+                q.x = 3;
+                let qr = &mut q;
+                let o;
+                o = 1;
+            }",
+        );
+        assert_eq!(errors, [
+            "cannot borrow `v` as mutable more than once at a time: second \
+             mutable borrow occurs here `&mut v`; first mutable borrow occurs \
+             here `&mut v`; first borrow later used here `r`",
+            "cannot borrow `v` as mutable because it is also borrowed as \
+             immutable: mutable borrow occurs here `&mut v`; immutable borrow \
+             occurs here `&v`; immutable borrow later used here `a`",
+            "cannot use `v` because it was mutably borrowed: use of borrowed \
+             `v` `v`; `v` is borrowed here `&mut v`; borrow later used here \
+             `t`",
+            "cannot assign to `v` because it is borrowed: `v` is assigned to \
+             here but it was already borrowed `v`; `v` is borrowed here `&v`; \
+             borrow later used here `w`",
+            "cannot use `v` because it was mutably borrowed: use of borrowed \
+             `v` `v`; `v` is borrowed here `&mut v`; borrow later used by \
+             call `push`",
+            "cannot borrow `v` as mutable more than once at a time: second \
+             mutable borrow occurs here `&mut v`; first mutable borrow occurs \
+             here `&mut v`; first borrow later used by call `both`",
+            "cannot borrow `v` as immutable because it is also borrowed as \
+             mutable: immutable borrow occurs here `&v`; mutable borrow \
+             occurs here `&mut v`; mutable borrow later used by call `read`",
+            "cannot assign twice to immutable variable `n`: cannot assign \
+             twice to immutable variable `n`; first assignment to `n` `n`",
+            "cannot assign to `q.x`, as `q` is not declared as mutable: \
+             cannot assign `q.x`",
+            "cannot borrow `q` as mutable, as `q` is not declared as mutable: \
+             cannot borrow as mutable `&mut q`",
+        ]);
+    }
+    #[test]
+    fn lifetimes() {
+        let errors = errors(
+            "fn local() -> &i32 { let x = 1; &x }
+            fn param(x: i32) -> &i32 { &x }
+            fn through(x: &i32) -> &i32 { &*x }
+            fn tuple() -> (&i32, i32) { let x = 1; (&x, 2) }
+            fn main() {
+                let q;
+                {
+                    let y = 1;
+                    q = &y;
+                }
+                let z = *q;
+                let ok;
+                {
+                    let y = 1;
+                    ok = &y;
+                    let w = *ok;
+                }
+                let s = {
+                    let y = 1;
+                    &y
+                };
+            }",
+        );
+        assert_eq!(errors, [
+            "cannot return reference to local variable `x`: returns a \
+             reference to data owned by the current function `&x`",
+            "cannot return reference to function parameter `x`: returns a \
+             reference to data owned by the current function `&x`",
+            "cannot return value referencing local variable `x`: returns a \
+             value referencing data owned by the current function `(&x, 2)`; \
+             `x` is borrowed here `&x`",
+            "`y` does not live long enough: borrowed value does not live long \
+             enough `&y`; binding `y` declared here `y`; borrow later used \
+             here `q`",
+            "`y` does not live long enough: borrowed value does not live long \
+             enough `&y`; binding `y` declared here `y`",
+        ]);
+    }
+}
@@ -0,0 +1,1724 @@
+// Copyright (c) 2025 air (https://yuanair.github.io).
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published by
+// the Free Software Foundation, version 3 of the License only.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+use std::collections::{HashMap, HashSet};
+
+use crate::{
+    attr::{self, BuiltinAttr, Repr},
+    lexer::token::Literal,
+    parser::{
+        ast::{
+            Attribute,
+            BinOp,
+            Expr,
+            ExprKind,
+            FieldDef,
+            FnSig,
+            Generics,
+            Item,
+            ItemKind,
+            Module,
+            Path,
+            Type,
+            TypeKind,
+            UnOp,
+            VariantData,
+            Visibility,
+        },
+        visit::{self, MutVisitor},
+    },
+    semantic::resolve::{
+        CtorKind,
+        DefId,
+        DefKind,
+        PartialRes,
+        PrimTy,
+        Res,
+        Resolutions,
+        Vis,
+    },
+    types::{
+        self,
+        AdtDef,
+        AdtKind,
+        IntTy,
+        Predicate,
+        ReprOptions,
+        Ty,
+        TyCtxt,
+        TyKind,
+        UintTy,
+        VariantDef,
+        layout::{LayoutError, Target},
+    },
+    utils::{diagnostic::Diagnostic, span::Span},
+};
+/// lowers the structs, enums, unions, type aliases and the signatures of
+/// the functions of a resolved crate into a [TyCtxt], see
+/// [Collector::collect_crate]
+///
+/// the impls of traits are checked against their traits and against each
+/// other, e.g. `impl A for i32 {}` twice
+///
+/// the discriminants of enums and the lengths of arrays are evaluated from
+/// integer literals, arithmetic and constants
+#[derive(Debug)]
+pub struct Collector<'a> {
+    res:         &'a Resolutions,
+    tcx:         &'a mut TyCtxt,
+    /// the structs, enums and unions
+    adts:        HashMap<DefId, Item>,
+    /// the type of every type alias, and the self type of every impl
+    types:       HashMap<DefId, Type>,
+    /// the signature of every function and method
+    fns:         HashMap<DefId, FnSig>,
+    /// the generic parameters of every function, method, struct, enum,
+    /// union and impl
+    generics:    HashMap<DefId, Generics>,
+    /// the functions with bodies, for the defaults of the items of traits
+    bodies:      HashSet<DefId>,
+    /// the type of every constant and static
+    item_tys:    HashMap<DefId, Type>,
+    /// the value of every constant
+    values:      HashMap<DefId, Expr>,
+    /// the impls without a trait
+    impls:       Vec<DefId>,
+    /// the impls of traits, with their traits
+    trait_impls: Vec<(DefId, DefId)>,
+    evaluated:   HashMap<DefId, Option<i128>>,
+    /// the type aliases and the constants being lowered, to detect cycles
+    stack:       Vec<DefId>,
+    diagnostics: Vec<Diagnostic>,
+}
+impl<'a> Collector<'a> {
+    /// lowers into `tcx`, a collector created after
+    /// [Collector::collect_crate] lowers types and evaluates constants of
+    /// the bodies of functions
+    pub fn new(res: &'a Resolutions, tcx: &'a mut TyCtxt) -> Self {
+        Self {
+            res,
+            tcx,
+            adts: HashMap::new(),
+            types: HashMap::new(),
+            fns: HashMap::new(),
+            generics: HashMap::new(),
+            bodies: HashSet::new(),
+            item_tys: HashMap::new(),
+            values: HashMap::new(),
+            impls: Vec::new(),
+            trait_impls: Vec::new(),
+            evaluated: HashMap::new(),
+            stack: Vec::new(),
+            diagnostics: Vec::new(),
+        }
+    }
+
+    /// the [AdtDef] of every struct, enum and union of `module`, which is
+    /// resolved to [Collector::new], the types of its items and the values
+    /// of its constants of integer types
+    pub fn collect_crate(&mut self, module: &mut Module) {
+        self.visit_module(module);
+        for (id, def) in self.res.defs() {
+            if matches!(
+                def.kind,
+                DefKind::Fn
+                    | DefKind::AssocFn
+                    | DefKind::Trait
+                    | DefKind::AssocTy
+                    | DefKind::TyParam
+            ) {
+                self.tcx.add_name(id, def.name.clone());
+            }
+        }
+        let mut generics: Vec<_> =
+            std::mem::take(&mut self.generics).into_iter().collect();
+        generics.sort_by_key(|(id, _)| *id);
+        self.lower_params(&generics);
+        let mut item_tys: Vec<_> =
+            std::mem::take(&mut self.item_tys).into_iter().collect();
+        item_tys.sort_by_key(|(id, _)| *id);
+        for (id, ty) in item_tys {
+            let ty = self.lower_ty(&ty);
+            self.tcx.set_type_of(id, ty);
+        }
+        let mut adts: Vec<_> =
+            std::mem::take(&mut self.adts).into_iter().collect();
+        adts.sort_by_key(|(id, _)| *id);
+        for (id, item) in &adts {
+            let def = self.lower_adt(*id, item);
+            self.tcx.add_adt(*id, def);
+            self.add_ctor_sigs(*id);
+        }
+        // the fields may be of ADTs declared later
+        for (id, item) in &adts {
+            self.check_copy(*id, item);
+        }
+        let mut aliases: Vec<_> = self.types.keys().copied().collect();
+        aliases.sort();
+        for id in aliases {
+            self.lower_named(id);
+        }
+        for id in std::mem::take(&mut self.impls) {
+            self.tcx.add_inherent_impl(id);
+        }
+        self.trait_impls.sort();
+        for (impl_, trait_) in self.trait_impls.clone() {
+            self.lower_named(impl_);
+            self.tcx.add_trait_impl(trait_, impl_);
+        }
+        self.lower_predicates(&generics);
+        let mut fns: Vec<_> =
+            std::mem::take(&mut self.fns).into_iter().collect();
+        fns.sort_by_key(|(id, _)| *id);
+        for (id, sig) in &fns {
+            let sig = self.lower_sig(*id, sig);
+            self.tcx.add_fn_sig(*id, sig);
+        }
+        let mut consts: Vec<_> = self.values.keys().copied().collect();
+        consts.sort();
+        for id in consts {
+            if let Some(ty) = self.tcx.type_of(id)
+                && self.tcx.is_integral(ty)
+                && let Some(value) = self.eval_const(id, self.res.def(id).span)
+            {
+                self.tcx.add_const_value(id, value);
+            }
+        }
+        for (id, _) in &adts {
+            // the parameters do not change whether a type contains itself
+            let unit = self.tcx.unit();
+            let args = vec![unit; self.tcx.generics(*id).len()];
+            let ty = self.tcx.intern(TyKind::Adt(*id, args));
+            if let Err(LayoutError::Recursive(cycle)) =
+                self.tcx.layout_of(ty, Target::host())
+                && cycle == *id
+            {
+                let def = self.res.def(*id);
+                self.diagnostics.push(
+                    Diagnostic::error(format!(
+                        "recursive type `{}` has infinite size",
+                        def.name
+                    ))
+                    .with_code("E0072")
+                    .with_primary(def.span, "recursive without indirection")
+                    .with_help(
+                        "insert some indirection (e.g., a `T.Box` or `&T`) to \
+                         break the cycle",
+                    ),
+                );
+            }
+        }
+        let fns: HashMap<_, _> = fns.into_iter().collect();
+        self.check_trait_impls(&fns);
+        self.check_coherence();
+    }
+
+    pub fn take_diagnostics(&mut self) -> Vec<Diagnostic> {
+        std::mem::take(&mut self.diagnostics)
+    }
+
+    /// the [Ty] of a written type, e.g. of a field
+    pub fn lower_ty(&mut self, ty: &Type) -> Ty {
+        match &ty.kind {
+            TypeKind::Path(path) => self.lower_path(path),
+            TypeKind::Tuple(tys) => {
+                let tys = tys.iter().map(|ty| self.lower_ty(ty)).collect();
+                self.tcx.intern(TyKind::Tuple(tys))
+            }
+            TypeKind::Ptr { mutable, ty } => {
+                let ty = self.lower_ty(ty);
+                self.tcx.intern(TyKind::Ptr { mutable: *mutable, ty })
+            }
+            TypeKind::Ref { mutable, ty } => {
+                let ty = self.lower_ty(ty);
+                self.tcx.intern(TyKind::Ref { mutable: *mutable, ty })
+            }
+            TypeKind::Slice(ty) => {
+                let ty = self.lower_ty(ty);
+                self.tcx.intern(TyKind::Slice(ty))
+            }
+            TypeKind::Array(elem, len) => {
+                let elem = self.lower_ty(elem);
+                let Some(value) = self.eval(len) else {
+                    return self.tcx.error();
+                };
+                match u64::try_from(value) {
+                    Ok(len) => self.tcx.intern(TyKind::Array(elem, len)),
+                    Err(_) => {
+                        self.diagnostics.push(
+                            Diagnostic::error(format!(
+                                "array length `{value}` is not a `usize`"
+                            ))
+                            .with_code("E0308")
+                            .with_primary(len.span, "negative or too large"),
+                        );
+                        self.tcx.error()
+                    }
+                }
+            }
+            TypeKind::TraitObject(paths) => {
+                let mut traits = Vec::new();
+                for path in paths {
+                    match self.res.path(path.id).and_then(|p| p.full_res()) {
+                        Some(Res::Def(DefKind::Trait, id)) => traits.push(id),
+                        _ => return self.tcx.error(),
+                    }
+                }
+                self.tcx.intern(TyKind::Dynamic(traits))
+            }
+            TypeKind::ImplTrait(_) => {
+                self.diagnostics.push(
+                    Diagnostic::error("`impl Trait` is not allowed here")
+                        .with_code("E0562")
+                        .with_primary(ty.span, ""),
+                );
+                self.tcx.error()
+            }
+            TypeKind::Box(ty) => {
+                let ty = self.lower_ty(ty);
+                self.tcx.intern(TyKind::Box(ty))
+            }
+            TypeKind::Infer => {
+                self.diagnostics.push(
+                    Diagnostic::error(
+                        "the placeholder `_` is not allowed within the types \
+                         of items",
+                    )
+                    .with_code("E0121")
+                    .with_primary(ty.span, "not allowed in type signatures"),
+                );
+                self.tcx.error()
+            }
+        }
+    }
+
+    fn lower_path(&mut self, path: &Path) -> Ty {
+        // unresolved paths are already reported
+        let Some(partial) = self.res.path(path.id) else {
+            return self.tcx.error();
+        };
+        if partial.unresolved == 1 {
+            return self.lower_assoc_ty(partial.base, path);
+        }
+        if partial.unresolved > 0 {
+            self.diagnostics.push(
+                Diagnostic::error("ambiguous associated type")
+                    .with_code("E0223")
+                    .with_primary(path.span, ""),
+            );
+            return self.tcx.error();
+        }
+        match partial.base {
+            Res::Def(DefKind::TyParam, id) => {
+                self.no_generic_args(path, 0);
+                self.tcx.intern(TyKind::Param(id))
+            }
+            base => self.lower_res(base, path),
+        }
+    }
+
+    /// reports the generic arguments of the segments of `path` from
+    /// `start`, which do not take any
+    fn no_generic_args(&mut self, path: &Path, start: usize) {
+        let segments = path.segments.iter().skip(start);
+        if let Some(args) = segments.filter_map(|s| s.args.as_ref()).next() {
+            self.diagnostics.push(
+                Diagnostic::error(
+                    "type arguments are not allowed on this type",
+                )
+                .with_code("E0109")
+                .with_primary(args.span, "type argument not allowed"),
+            );
+        }
+    }
+
+    /// the ADT `id` with the generic arguments of the last segment of
+    /// `path`, which must be as many as its parameters
+    fn lower_adt_path(&mut self, id: DefId, path: &Path) -> Ty {
+        let (last, init) = path.segments.split_last().unwrap();
+        let init = Path { segments: init.to_vec(), ..path.clone() };
+        self.no_generic_args(&init, 0);
+        let params = self.tcx.generics(id).len();
+        let def = self.res.def(id);
+        let (descr, name) = (def.kind.descr(), def.name.clone());
+        let args = match &last.args {
+            Some(args) => {
+                let tys: Vec<_> =
+                    args.args.iter().map(|ty| self.lower_ty(ty)).collect();
+                if tys.len() == params {
+                    tys
+                } else {
+                    self.diagnostics.push(
+                        Diagnostic::error(format!(
+                            "{descr} takes {params} generic argument{} but {} \
+                             generic argument{} supplied",
+                            plural(params),
+                            tys.len(),
+                            if tys.len() == 1 { " was" } else { "s were" }
+                        ))
+                        .with_code("E0107")
+                        .with_primary(
+                            args.span,
+                            format!(
+                                "expected {params} generic argument{}",
+                                plural(params)
+                            ),
+                        ),
+                    );
+                    let error = self.tcx.error();
+                    vec![error; params]
+                }
+            }
+            None if params > 0 => {
+                self.diagnostics.push(
+                    Diagnostic::error(format!(
+                        "missing generics for {descr} `{name}`"
+                    ))
+                    .with_code("E0107")
+                    .with_primary(
+                        last.ident.span,
+                        format!(
+                            "expected {params} generic argument{}",
+                            plural(params)
+                        ),
+                    ),
+                );
+                let error = self.tcx.error();
+                vec![error; params]
+            }
+            None => Vec::new(),
+        };
+        self.tcx.intern(TyKind::Adt(id, args))
+    }
+
+    /// the ADT `id` inside of its definition, with its parameters as its
+    /// arguments
+    fn self_adt(&mut self, id: DefId) -> Ty {
+        let params = self.tcx.generics(id).to_vec();
+        let args =
+            params.iter().map(|p| self.tcx.intern(TyKind::Param(*p))).collect();
+        self.tcx.intern(TyKind::Adt(id, args))
+    }
+
+    /// e.g. `Self::Item` in a trait or an impl, `T::Item` with `T: A` or
+    /// `B::Item` with `impl A for B`
+    fn lower_assoc_ty(&mut self, base: Res, path: &Path) -> Ty {
+        let name = path.segments.last().unwrap().ident.node.as_str();
+        // the type of an impl of a trait
+        if let Res::SelfTy(id) = base
+            && self.res.def(id).kind == DefKind::Impl
+            && let Some(item) = self.assoc_item(id, name, DefKind::AssocTy)
+        {
+            return self.lower_named(item);
+        }
+        let base_path = Path {
+            segments: path.segments[..path.segments.len() - 1].to_vec(),
+            ..path.clone()
+        };
+        let self_ty = match base {
+            Res::Def(DefKind::TyParam, id) => {
+                self.tcx.intern(TyKind::Param(id))
+            }
+            Res::PrimTy(_)
+            | Res::Def(
+                DefKind::Struct
+                | DefKind::Enum
+                | DefKind::Union
+                | DefKind::TypeAlias
+                | DefKind::AssocTy,
+                _,
+            )
+            | Res::SelfTy(_) => self.lower_res(base, &base_path),
+            _ => {
+                self.diagnostics.push(
+                    Diagnostic::error("ambiguous associated type")
+                        .with_code("E0223")
+                        .with_primary(path.span, ""),
+                );
+                self.tcx.error()
+            }
+        };
+        if self.tcx.references_error(self_ty) {
+            return self.tcx.error();
+        }
+        let traits: Vec<_> = match *self.tcx.kind(self_ty) {
+            TyKind::Param(id) if self.res.def(id).kind == DefKind::Trait => {
+                vec![id]
+            }
+            TyKind::Param(id) => {
+                let owner = self.res.def(id).parent.unwrap();
+                self.tcx
+                    .predicates(owner)
+                    .iter()
+                    .filter(|predicate| predicate.ty == self_ty)
+                    .map(|predicate| predicate.trait_)
+                    .collect()
+            }
+            _ => {
+                let mut traits = Vec::new();
+                for (impl_, trait_) in self.trait_impls.clone() {
+                    self.lower_named(impl_);
+                    if self.tcx.match_impl(impl_, self_ty).is_some() {
+                        traits.push(trait_);
+                    }
+                }
+                traits
+            }
+        };
+        let mut found: Vec<_> = traits
+            .iter()
+            .filter_map(|trait_| {
+                let item = self.assoc_item(*trait_, name, DefKind::AssocTy)?;
+                Some((*trait_, item))
+            })
+            .collect();
+        found.dedup();
+        let display = self.tcx.display(self_ty);
+        let (trait_, item) = match found[..] {
+            [found] => found,
+            [] => {
+                self.diagnostics.push(
+                    Diagnostic::error(format!(
+                        "associated type `{name}` not found for `{display}`"
+                    ))
+                    .with_code("E0220")
+                    .with_primary(path.span, "associated type not found"),
+                );
+                return self.tcx.error();
+            }
+            _ => {
+                self.diagnostics.push(
+                    Diagnostic::error(format!(
+                        "ambiguous associated type `{name}` in bounds of \
+                         `{display}`"
+                    ))
+                    .with_code("E0221")
+                    .with_primary(
+                        path.span,
+                        format!("ambiguous associated type `{name}`"),
+                    ),
+                );
+                return self.tcx.error();
+            }
+        };
+        if let TyKind::Param(_) = self.tcx.kind(self_ty) {
+            return self.tcx.intern(TyKind::Projection {
+                ty: self_ty,
+                trait_,
+                item,
+            });
+        }
+        for (impl_, other) in self.trait_impls.clone() {
+            if other != trait_ {
+                continue;
+            }
+            self.lower_named(impl_);
+            if let Some(args) = self.tcx.match_impl(impl_, self_ty) {
+                let found = self.assoc_item(impl_, name, DefKind::AssocTy);
+                let ty = match found {
+                    Some(found) => {
+                        let ty = self.lower_named(found);
+                        self.tcx.instantiate(ty, impl_, &args)
+                    }
+                    // the default of the trait, e.g. `type A = Self;`
+                    None => self.lower_named(item),
+                };
+                return self.tcx.subst(ty, &[trait_], &[self_ty]);
+            }
+        }
+        self.tcx.error()
+    }
+
+    /// the type a path without associated segments resolves to
+    fn lower_res(&mut self, base: Res, path: &Path) -> Ty {
+        if let Res::Def(DefKind::Struct | DefKind::Enum | DefKind::Union, id) =
+            base
+        {
+            return self.lower_adt_path(id, path);
+        }
+        self.no_generic_args(path, 0);
+        match base {
+            Res::PrimTy(prim) => self.tcx.prim(prim),
+            Res::Def(DefKind::TypeAlias | DefKind::AssocTy, id) => {
+                self.lower_named(id)
+            }
+            Res::SelfTy(id) => match self.res.def(id).kind {
+                DefKind::Struct | DefKind::Enum | DefKind::Union => {
+                    self.self_adt(id)
+                }
+                DefKind::Impl => self.lower_named(id),
+                DefKind::Trait => self.tcx.intern(TyKind::Param(id)),
+                _ => self.tcx.error(),
+            },
+            _ => self.tcx.error(),
+        }
+    }
+
+    /// the item named `name` of a trait or an impl
+    fn assoc_item(
+        &self, id: DefId, name: &str, kind: DefKind,
+    ) -> Option<DefId> {
+        self.res.def(id).children.iter().copied().find(|child| {
+            let def = self.res.def(*child);
+            def.kind == kind && def.name == name
+        })
+    }
+
+    /// the trait a path of a bound resolves to, reported by the resolver
+    /// otherwise
+    fn bound_trait(&self, path: &Path) -> Option<DefId> {
+        match self.res.path(path.id).and_then(|p| p.full_res()) {
+            Some(Res::Def(DefKind::Trait, id)) => Some(id),
+            _ => None,
+        }
+    }
+
+    /// the generic parameters of every item, see [TyCtxt::generics], before
+    /// the types that refer to them
+    fn lower_params(&mut self, generics: &[(DefId, Generics)]) {
+        // an impl before its methods
+        for (id, generics) in generics {
+            let mut params = Vec::new();
+            let parent = self.res.def(*id).parent.unwrap();
+            match self.res.def(parent).kind {
+                DefKind::Trait => params.push(parent),
+                DefKind::Impl => {
+                    params.extend_from_slice(self.tcx.generics(parent))
+                }
+                _ => {}
+            }
+            let own = generics.params.iter();
+            params.extend(own.filter_map(|param| self.res.item(param.id)));
+            self.tcx.set_generics(*id, params);
+        }
+    }
+
+    /// the predicates of every generic item, see [TyCtxt::predicates]
+    fn lower_predicates(&mut self, generics: &[(DefId, Generics)]) {
+        // the bounds of the parameters first, for `T::Item` in a `where`
+        // clause
+        for (id, generics) in generics {
+            let mut predicates = Vec::new();
+            let parent = self.res.def(*id).parent.unwrap();
+            match self.res.def(parent).kind {
+                DefKind::Trait => {
+                    let ty = self.tcx.intern(TyKind::Param(parent));
+                    predicates.push(Predicate { ty, trait_: parent });
+                }
+                DefKind::Impl => {
+                    predicates.extend_from_slice(self.tcx.predicates(parent))
+                }
+                _ => {}
+            }
+            for param in &generics.params {
+                let Some(param_id) = self.res.item(param.id) else {
+                    continue;
+                };
+                let ty = self.tcx.intern(TyKind::Param(param_id));
+                for bound in &param.bounds {
+                    if let Some(trait_) = self.bound_trait(bound) {
+                        predicates.push(Predicate { ty, trait_ });
+                    }
+                }
+            }
+            for predicate in &generics.where_clause {
+                if let TypeKind::Path(path) = &predicate.ty.kind
+                    && let Some(PartialRes {
+                        base: Res::Def(DefKind::TyParam, param),
+                        unresolved: 0,
+                    }) = self.res.path(path.id)
+                {
+                    let ty = self.tcx.intern(TyKind::Param(param));
+                    for bound in &predicate.bounds {
+                        if let Some(trait_) = self.bound_trait(bound) {
+                            predicates.push(Predicate { ty, trait_ });
+                        }
+                    }
+                }
+            }
+            self.tcx.set_predicates(*id, predicates);
+        }
+        // e.g. `where T::Item: A`
+        for (id, generics) in generics {
+            let mut predicates = self.tcx.predicates(*id).to_vec();
+            for predicate in &generics.where_clause {
+                if let TypeKind::Path(path) = &predicate.ty.kind
+                    && let Some(PartialRes {
+                        base: Res::Def(DefKind::TyParam, _),
+                        unresolved: 0,
+                    }) = self.res.path(path.id)
+                {
+                    continue;
+                }
+                let ty = self.lower_ty(&predicate.ty);
+                for bound in &predicate.bounds {
+                    if let Some(trait_) = self.bound_trait(bound) {
+                        predicates.push(Predicate { ty, trait_ });
+                    }
+                }
+            }
+            self.tcx.set_predicates(*id, predicates);
+        }
+    }
+
+    /// the type of a type alias, or the self type of an impl
+    fn lower_named(&mut self, id: DefId) -> Ty {
+        if let Some(ty) = self.tcx.type_of(id) {
+            return ty;
+        }
+        // e.g. `type A;` in a trait
+        let Some(ty) = self.types.get(&id).cloned() else {
+            return self.tcx.error();
+        };
+        if self.stack.contains(&id) {
+            let def = self.res.def(id);
+            self.diagnostics.push(
+                Diagnostic::error(format!(
+                    "cycle detected when expanding {} `{}`",
+                    def.kind.descr(),
+                    def.name
+                ))
+                .with_code("E0391")
+                .with_primary(def.span, "refers to itself"),
+            );
+            let error = self.tcx.error();
+            self.tcx.set_type_of(id, error);
+            return error;
+        }
+        self.stack.push(id);
+        let lowered = self.lower_ty(&ty);
+        self.stack.pop();
+        match self.tcx.type_of(id) {
+            Some(ty) => ty,
+            None => {
+                self.tcx.set_type_of(id, lowered);
+                lowered
+            }
+        }
+    }
+
+    /// `self` is the self type of the impl of a method, or `Self` of its
+    /// trait
+    fn lower_sig(&mut self, id: DefId, sig: &FnSig) -> types::FnSig {
+        let mut params = Vec::new();
+        if let Some(param) = sig.self_param {
+            let parent = self.res.def(id).parent.unwrap();
+            let self_ty = match self.res.def(parent).kind {
+                DefKind::Impl => self.lower_named(parent),
+                DefKind::Trait => self.tcx.intern(TyKind::Param(parent)),
+                _ => self.tcx.error(),
+            };
+            params.push(match param.by_ref {
+                true => self.tcx.intern(TyKind::Ref {
+                    mutable: param.mutable,
+                    ty:      self_ty,
+                }),
+                false => self_ty,
+            });
+        }
+        for param in &sig.params {
+            params.push(self.lower_ty(&param.ty));
+        }
+        let ret = match &sig.ret {
+            Some(ret) => self.lower_ty(ret),
+            None => self.tcx.unit(),
+        };
+        types::FnSig { params, ret, has_self: sig.self_param.is_some() }
+    }
+
+    /// the signatures of the constructors of a tuple struct and of the
+    /// tuple variants of an enum, see [CtorKind::Fn]
+    fn add_ctor_sigs(&mut self, id: DefId) {
+        let ret = self.self_adt(id);
+        let generics = self.tcx.generics(id).to_vec();
+        let def = self.tcx.adt(id).unwrap();
+        let ctors: Vec<_> = match def.kind {
+            AdtKind::Struct => vec![(id, 0)],
+            AdtKind::Enum => {
+                self.res.def(id).children.iter().copied().zip(0..).collect()
+            }
+            AdtKind::Union => Vec::new(),
+        };
+        for (ctor, index) in ctors {
+            if self.res.def(ctor).ctor == Some(CtorKind::Fn) {
+                let params = self.tcx.adt(id).unwrap().variants[index]
+                    .fields
+                    .iter()
+                    .map(|field| field.ty)
+                    .collect();
+                let sig = types::FnSig { params, ret, has_self: false };
+                self.tcx.add_fn_sig(ctor, sig);
+                self.tcx.set_generics(ctor, generics.clone());
+            }
+        }
+    }
+
+    fn lower_adt(&mut self, id: DefId, item: &Item) -> AdtDef {
+        let repr = self.repr(&item.attrs);
+        let module = self.res.module(id);
+        let fields = |this: &mut Self, data: &[FieldDef], public: bool| {
+            let mut defs = Vec::new();
+            for (index, field) in data.iter().enumerate() {
+                let ty = this.lower_ty(&field.ty);
+                if !this.tcx.is_sized(ty) {
+                    this.diagnostics.push(
+                        Diagnostic::error(format!(
+                            "the size for values of type `{}` cannot be known \
+                             at compilation time",
+                            this.tcx.display(ty)
+                        ))
+                        .with_code("E0277")
+                        .with_primary(field.ty.span, "unsized field")
+                        .with_help("borrow it or put it in a `T.Box`"),
+                    );
+                }
+                defs.push(types::FieldDef {
+                    name: match &field.name {
+                        Some(name) => name.node.to_string(),
+                        None => index.to_string(),
+                    },
+                    ty,
+                    vis: match (public, field.vis) {
+                        (true, _) | (_, Visibility::Public(_)) => Vis::Public,
+                        _ => Vis::Restricted(module),
+                    },
+                    deprecated: attr::deprecation(&field.attrs),
+                });
+            }
+            defs
+        };
+        let name = self.res.def(id).name.clone();
+        let (kind, variants) = match &item.kind {
+            ItemKind::Struct { data, .. } => {
+                let fields = fields(self, variant_fields(data), false);
+                let variant =
+                    VariantDef { name: name.clone(), fields, discr: 0 };
+                (AdtKind::Struct, vec![variant])
+            }
+            ItemKind::Union { fields: data, .. } => {
+                let fields = fields(self, data, false);
+                let variant =
+                    VariantDef { name: name.clone(), fields, discr: 0 };
+                (AdtKind::Union, vec![variant])
+            }
+            ItemKind::Enum { variants: data, .. } => {
+                let mut variants = Vec::<VariantDef>::new();
+                let mut spans = Vec::<Span>::new();
+                for variant in data {
+                    let fields =
+                        fields(self, variant_fields(&variant.data), true);
+                    let discr = match &variant.discriminant {
+                        Some(expr) => self.eval(expr).unwrap_or(0),
+                        None => match variants.last() {
+                            Some(last) => last.discr.wrapping_add(1),
+                            None => 0,
+                        },
+                    };
+                    let span = match &variant.discriminant {
+                        Some(expr) => expr.span,
+                        None => variant.name.span,
+                    };
+                    if let Some(int) = repr.int
+                        && !fits(self.tcx.kind(int), discr)
+                    {
+                        self.diagnostics.push(
+                            Diagnostic::error(format!(
+                                "discriminant value `{discr}` does not fit in \
+                                 `{}`",
+                                self.tcx.display(int)
+                            ))
+                            .with_primary(span, "out of range"),
+                        );
+                    }
+                    if let Some(index) =
+                        variants.iter().position(|v| v.discr == discr)
+                    {
+                        self.diagnostics.push(
+                            Diagnostic::error(format!(
+                                "discriminant value `{discr}` assigned more \
+                                 than once"
+                            ))
+                            .with_code("E0081")
+                            .with_primary(
+                                span,
+                                format!("`{discr}` assigned here"),
+                            )
+                            .with_secondary(
+                                spans[index],
+                                "first assigned here",
+                            ),
+                        );
+                    }
+                    variants.push(VariantDef {
+                        name: variant.name.node.to_string(),
+                        fields,
+                        discr,
+                    });
+                    spans.push(span);
+                }
+                (AdtKind::Enum, variants)
+            }
+            _ => unreachable!(),
+        };
+        let copy = attr::derive(&item.attrs, "Copy").is_some();
+        AdtDef { kind, name, variants, repr, copy }
+    }
+
+    /// every field of an ADT with `#[derive(Copy)]` is copied, its
+    /// parameters are assumed to be
+    fn check_copy(&mut self, id: DefId, item: &Item) {
+        let Some(derive) = attr::derive(&item.attrs, "Copy") else { return };
+        let fields: Vec<&FieldDef> = match &item.kind {
+            ItemKind::Struct { data, .. } => {
+                variant_fields(data).iter().collect()
+            }
+            ItemKind::Union { fields, .. } => fields.iter().collect(),
+            ItemKind::Enum { variants, .. } => variants
+                .iter()
+                .flat_map(|variant| variant_fields(&variant.data))
+                .collect(),
+            _ => unreachable!(),
+        };
+        let tys: Vec<_> = self
+            .tcx
+            .adt(id)
+            .unwrap()
+            .variants
+            .iter()
+            .flat_map(|variant| &variant.fields)
+            .map(|field| field.ty)
+            .collect();
+        let params = self.tcx.generics(id).to_vec();
+        let unit = self.tcx.unit();
+        let units = vec![unit; params.len()];
+        let mut diagnostic = None;
+        for (field, ty) in fields.into_iter().zip(tys) {
+            let ty = self.tcx.subst(ty, &params, &units);
+            if self.tcx.is_copy(ty) {
+                continue;
+            }
+            diagnostic = Some(
+                diagnostic
+                    .unwrap_or_else(|| {
+                        Diagnostic::error(
+                            "the trait `Copy` cannot be implemented for this \
+                             type",
+                        )
+                        .with_code("E0204")
+                        .with_primary(derive, "")
+                    })
+                    .with_secondary(
+                        field.span,
+                        "this field does not implement `Copy`",
+                    ),
+            );
+        }
+        self.diagnostics.extend(diagnostic);
+    }
+
+    /// see `#[repr(...)]`, already checked by [attr](crate::attr)
+    fn repr(&mut self, attrs: &[Attribute]) -> ReprOptions {
+        let mut repr = ReprOptions::default();
+        for attr in attrs {
+            let Some(Ok(BuiltinAttr::Repr(reprs))) = BuiltinAttr::parse(attr)
+            else {
+                continue;
+            };
+            for spanned in reprs {
+                match spanned.node {
+                    Repr::C => repr.c = true,
+                    Repr::Transparent => repr.transparent = true,
+                    Repr::Packed => repr.packed = true,
+                    Repr::Int(int) => {
+                        repr.int = PrimTy::from_name(int)
+                            .map(|prim| self.tcx.prim(prim))
+                    }
+                }
+            }
+        }
+        repr
+    }
+
+    /// the items of every impl of a trait against the items of the trait,
+    /// see [TyCtxt::impl_item]
+    fn check_trait_impls(&mut self, fns: &HashMap<DefId, FnSig>) {
+        for (impl_, trait_) in self.trait_impls.clone() {
+            let self_ty = self.lower_named(impl_);
+            let trait_name = self.res.def(trait_).name.clone();
+            for item in self.res.def(impl_).children.clone() {
+                let def = self.res.def(item);
+                if def.kind == DefKind::TyParam {
+                    continue;
+                }
+                let name = def.name.clone();
+                let found = self
+                    .res
+                    .def(trait_)
+                    .children
+                    .iter()
+                    .copied()
+                    .find(|child| self.res.def(*child).name == name);
+                let Some(found) = found else {
+                    let (descr, code) = match def.kind {
+                        DefKind::AssocFn => ("method", "E0407"),
+                        DefKind::AssocConst => ("const", "E0438"),
+                        _ => ("type", "E0437"),
+                    };
+                    self.diagnostics.push(
+                        Diagnostic::error(format!(
+                            "{descr} `{name}` is not a member of trait \
+                             `{trait_name}`"
+                        ))
+                        .with_code(code)
+                        .with_primary(
+                            def.span,
+                            format!("not a member of trait `{trait_name}`"),
+                        ),
+                    );
+                    continue;
+                };
+                let expected = self.res.def(found);
+                if expected.kind != def.kind {
+                    self.diagnostics.push(
+                        Diagnostic::error(format!(
+                            "item `{name}` is an {}, which doesn't match its \
+                             trait `{trait_name}`",
+                            def.kind.descr()
+                        ))
+                        .with_primary(def.span, "does not match trait")
+                        .with_secondary(
+                            expected.span,
+                            format!(
+                                "item in trait is an {}",
+                                expected.kind.descr()
+                            ),
+                        ),
+                    );
+                    continue;
+                }
+                self.tcx.add_impl_item(impl_, found, item);
+                match def.kind {
+                    DefKind::AssocFn => {
+                        self.compare_fn(item, found, self_ty, &trait_name, fns)
+                    }
+                    DefKind::AssocConst => {
+                        let (Some(ty), Some(expected)) =
+                            (self.tcx.type_of(item), self.tcx.type_of(found))
+                        else {
+                            continue;
+                        };
+                        let expected =
+                            self.tcx.subst(expected, &[trait_], &[self_ty]);
+                        let expected = self.tcx.normalize(expected);
+                        if expected != ty
+                            && !self.tcx.references_error(expected)
+                            && !self.tcx.references_error(ty)
+                        {
+                            let (expected, ty) = (
+                                self.tcx.display(expected),
+                                self.tcx.display(ty),
+                            );
+                            self.diagnostics.push(
+                                Diagnostic::error(format!(
+                                    "implemented const `{name}` has an \
+                                     incompatible type for trait"
+                                ))
+                                .with_code("E0326")
+                                .with_primary(
+                                    def.span,
+                                    format!(
+                                        "expected `{expected}`, found `{ty}`"
+                                    ),
+                                ),
+                            );
+                        }
+                    }
+                    _ => {}
+                }
+            }
+            let mut missing = Vec::new();
+            for item in self.res.def(trait_).children.clone() {
+                if self.tcx.impl_item(impl_, item).is_some() {
+                    continue;
+                }
+                match self.bodies.contains(&item) {
+                    true => self.tcx.add_impl_item(impl_, item, item),
+                    false => {
+                        missing.push(format!("`{}`", self.res.def(item).name))
+                    }
+                }
+            }
+            if !missing.is_empty() {
+                let missing = missing.join(", ");
+                self.diagnostics.push(
+                    Diagnostic::error(format!(
+                        "not all trait items implemented, missing: {missing}"
+                    ))
+                    .with_code("E0046")
+                    .with_primary(
+                        self.res.def(impl_).span,
+                        format!("missing {missing} in implementation"),
+                    ),
+                );
+            }
+        }
+    }
+
+    /// the signature of the method `id` of an impl against `trait_fn` of its
+    /// trait
+    fn compare_fn(
+        &mut self, id: DefId, trait_fn: DefId, self_ty: Ty, trait_name: &str,
+        fns: &HashMap<DefId, FnSig>,
+    ) {
+        let (Some(sig), Some(trait_sig)) =
+            (self.tcx.fn_sig(id).cloned(), self.tcx.fn_sig(trait_fn).cloned())
+        else {
+            return;
+        };
+        let def = self.res.def(id);
+        let (name, span) = (def.name.clone(), def.span);
+        if sig.has_self != trait_sig.has_self {
+            let (place, label) = match trait_sig.has_self {
+                true => {
+                    ("trait, but not in the impl", "expected `self` in impl")
+                }
+                false => ("impl, but not in the trait", "`self` used in impl"),
+            };
+            self.diagnostics.push(
+                Diagnostic::error(format!(
+                    "method `{name}` has a `self` declaration in the {place}"
+                ))
+                .with_primary(span, label),
+            );
+            return;
+        }
+        if sig.params.len() != trait_sig.params.len() {
+            let (found, count) = (sig.params.len(), trait_sig.params.len());
+            self.diagnostics.push(
+                Diagnostic::error(format!(
+                    "method `{name}` has {found} parameter{} but the \
+                     declaration in trait `{trait_name}::{name}` has {count}",
+                    plural(found)
+                ))
+                .with_code("E0050")
+                .with_primary(
+                    span,
+                    format!(
+                        "expected {count} parameter{}, found {found}",
+                        plural(count)
+                    ),
+                ),
+            );
+            return;
+        }
+        let impl_ = self.res.def(id).parent.unwrap();
+        let params =
+            self.tcx.generics(id)[self.tcx.generics(impl_).len()..].to_vec();
+        let trait_params = self.tcx.generics(trait_fn).to_vec();
+        if params.len() + 1 != trait_params.len() {
+            let (found, count) = (params.len(), trait_params.len() - 1);
+            self.diagnostics.push(
+                Diagnostic::error(format!(
+                    "method `{name}` has {found} type parameter{} but its \
+                     trait declaration has {count} type parameter{}",
+                    plural(found),
+                    plural(count)
+                ))
+                .with_code("E0049")
+                .with_primary(
+                    span,
+                    format!("found {found} type parameter{}", plural(found)),
+                ),
+            );
+            return;
+        }
+        let mut args = vec![self_ty];
+        args.extend(
+            params.iter().map(|param| self.tcx.intern(TyKind::Param(*param))),
+        );
+        let pairs = sig
+            .params
+            .iter()
+            .chain([&sig.ret])
+            .zip(trait_sig.params.iter().chain([&trait_sig.ret]));
+        for (index, (found, expected)) in pairs.enumerate() {
+            let expected = self.tcx.subst(*expected, &trait_params, &args);
+            let expected = self.tcx.normalize(expected);
+            let found = self.tcx.normalize(*found);
+            if expected == found
+                || self.tcx.references_error(expected)
+                || self.tcx.references_error(found)
+            {
+                continue;
+            }
+            let span = fns.get(&id).map_or(span, |sig| sig_span(sig, index));
+            let (expected, found) =
+                (self.tcx.display(expected), self.tcx.display(found));
+            let mut diagnostic = Diagnostic::error(format!(
+                "method `{name}` has an incompatible type for trait"
+            ))
+            .with_code("E0053")
+            .with_primary(
+                span,
+                format!("expected `{expected}`, found `{found}`"),
+            );
+            if let Some(sig) = fns.get(&trait_fn) {
+                diagnostic = diagnostic
+                    .with_secondary(sig_span(sig, index), "type in trait");
+            }
+            self.diagnostics.push(diagnostic);
+            return;
+        }
+    }
+
+    /// reports overlapping impls of a trait, and items of the inherent
+    /// impls of a type with the same name
+    fn check_coherence(&mut self) {
+        let mut traits: Vec<_> =
+            self.tcx.traits().map(|(id, impls)| (id, impls.to_vec())).collect();
+        traits.sort();
+        for (trait_, impls) in traits {
+            for (index, impl_) in impls.iter().enumerate() {
+                let ty = self.tcx.type_of(*impl_).unwrap();
+                if self.tcx.references_error(ty) {
+                    continue;
+                }
+                let first = impls[..index].iter().find(|other| {
+                    let other = self.tcx.type_of(**other).unwrap();
+                    self.tcx.may_overlap(other, ty)
+                        && !self.tcx.references_error(other)
+                });
+                if let Some(first) = first {
+                    let ty = self.tcx.display(ty);
+                    self.diagnostics.push(
+                        Diagnostic::error(format!(
+                            "conflicting implementations of trait `{}` for \
+                             type `{ty}`",
+                            self.res.def(trait_).name
+                        ))
+                        .with_code("E0119")
+                        .with_primary(
+                            self.res.def(*impl_).span,
+                            format!("conflicting implementation for `{ty}`"),
+                        )
+                        .with_secondary(
+                            self.res.def(*first).span,
+                            "first implementation here",
+                        ),
+                    );
+                }
+            }
+        }
+        let impls = self.tcx.inherent_impls().to_vec();
+        for (index, impl_) in impls.iter().enumerate() {
+            let ty = self.tcx.type_of(*impl_).unwrap();
+            for other in &impls[..index] {
+                let other_ty = self.tcx.type_of(*other).unwrap();
+                if !self.tcx.may_overlap(other_ty, ty)
+                    || self.tcx.references_error(other_ty)
+                    || self.tcx.references_error(ty)
+                {
+                    continue;
+                }
+                for item in &self.res.def(*impl_).children {
+                    let def = self.res.def(*item);
+                    if def.kind == DefKind::TyParam {
+                        continue;
+                    }
+                    let Some(first) =
+                        self.res.def(*other).children.iter().find(|first| {
+                            self.res.def(**first).name == def.name
+                        })
+                    else {
+                        continue;
+                    };
+                    self.diagnostics.push(
+                        Diagnostic::error(format!(
+                            "duplicate definitions with name `{}`",
+                            def.name
+                        ))
+                        .with_code("E0201")
+                        .with_primary(
+                            def.span,
+                            format!("duplicate definitions for `{}`", def.name),
+                        )
+                        .with_secondary(
+                            self.res.def(*first).span,
+                            format!("other definition for `{}`", def.name),
+                        ),
+                    );
+                }
+            }
+        }
+    }
+
+    /// the value of a constant integer expression, [None] after an error
+    pub fn eval(&mut self, expr: &Expr) -> Option<i128> {
+        let overflow = |span: Span, message: &str| {
+            Diagnostic::error("evaluation of constant value failed")
+                .with_code("E0080")
+                .with_primary(span, message.to_string())
+        };
+        match &expr.kind {
+            ExprKind::Literal(literal) => {
+                let Literal::Int(lit) = literal else {
+                    return self.not_constant(expr.span);
+                };
+                let value = i128::try_from(lit.value)
+                    .ok()
+                    .map(|value| if lit.negative { -value } else { value });
+                if value.is_none() {
+                    self.diagnostics
+                        .push(overflow(expr.span, "the value is too large"));
+                }
+                value
+            }
+            ExprKind::Paren(inner) => self.eval(inner),
+            ExprKind::Unary(UnOp::Neg, operand) => {
+                let value = self.eval(operand)?.checked_neg();
+                if value.is_none() {
+                    self.diagnostics.push(overflow(
+                        expr.span,
+                        "attempt to negate with overflow",
+                    ));
+                }
+                value
+            }
+            ExprKind::Binary(op, lhs, rhs) => {
+                let (lhs, rhs) = (self.eval(lhs)?, self.eval(rhs)?);
+                let value = match op.node {
+                    BinOp::Add => lhs.checked_add(rhs),
+                    BinOp::Sub => lhs.checked_sub(rhs),
+                    BinOp::Mul => lhs.checked_mul(rhs),
+                    BinOp::Div | BinOp::Rem if rhs == 0 => {
+                        self.diagnostics.push(overflow(
+                            expr.span,
+                            "attempt to divide by zero",
+                        ));
+                        return None;
+                    }
+                    BinOp::Div => lhs.checked_div(rhs),
+                    BinOp::Rem => lhs.checked_rem(rhs),
+                    BinOp::BitAnd => Some(lhs & rhs),
+                    BinOp::BitOr => Some(lhs | rhs),
+                    BinOp::BitXor => Some(lhs ^ rhs),
+                    BinOp::Shl => u32::try_from(rhs)
+                        .ok()
+                        .and_then(|rhs| lhs.checked_shl(rhs)),
+                    BinOp::Shr => u32::try_from(rhs)
+                        .ok()
+                        .and_then(|rhs| lhs.checked_shr(rhs)),
+                    _ => return self.not_constant(expr.span),
+                };
+                if value.is_none() {
+                    self.diagnostics.push(overflow(
+                        expr.span,
+                        &format!(
+                            "attempt to compute `{}` with overflow",
+                            op.node.as_str()
+                        ),
+                    ));
+                }
+                value
+            }
+            ExprKind::Path(path) => {
+                match self.res.path(path.id).and_then(|p| p.full_res()) {
+                    Some(Res::Def(
+                        DefKind::Const | DefKind::AssocConst,
+                        id,
+                    )) => self.eval_const(id, path.span),
+                    // already reported
+                    None | Some(Res::Err) => None,
+                    Some(_) => {
+                        self.diagnostics.push(
+                            Diagnostic::error(
+                                "attempt to use a non-constant value in a \
+                                 constant",
+                            )
+                            .with_code("E0435")
+                            .with_primary(path.span, "non-constant value"),
+                        );
+                        None
+                    }
+                }
+            }
+            ExprKind::Err => None,
+            _ => self.not_constant(expr.span),
+        }
+    }
+
+    fn eval_const(&mut self, id: DefId, span: Span) -> Option<i128> {
+        if let Some(value) = self.tcx.const_value(id) {
+            return Some(value);
+        }
+        if let Some(value) = self.evaluated.get(&id) {
+            return *value;
+        }
+        let def = self.res.def(id);
+        if self.stack.contains(&id) {
+            self.diagnostics.push(
+                Diagnostic::error(format!(
+                    "cycle detected when evaluating constant `{}`",
+                    def.name
+                ))
+                .with_code("E0391")
+                .with_primary(def.span, "refers to itself"),
+            );
+            self.evaluated.insert(id, None);
+            return None;
+        }
+        // every constant of an integer type with a value is evaluated by
+        // [Collector::collect_crate]
+        let Some(value) = self.values.get(&id).cloned() else {
+            let integral =
+                self.tcx.type_of(id).is_some_and(|ty| self.tcx.is_integral(ty));
+            let in_trait = def.parent.is_some_and(|parent| {
+                self.res.def(parent).kind == DefKind::Trait
+            });
+            if !integral {
+                return self.not_constant(span);
+            }
+            if !in_trait {
+                return None;
+            }
+            self.diagnostics.push(
+                Diagnostic::error(format!(
+                    "constant `{}` has no value",
+                    def.name
+                ))
+                .with_primary(span, "")
+                .with_secondary(def.span, "declared here"),
+            );
+            return None;
+        };
+        self.stack.push(id);
+        let evaluated = self.eval(&value);
+        self.stack.pop();
+        *self.evaluated.entry(id).or_insert(evaluated)
+    }
+
+    fn not_constant(&mut self, span: Span) -> Option<i128> {
+        self.diagnostics.push(
+            Diagnostic::error("expected a constant integer expression")
+                .with_primary(span, "not a constant integer"),
+        );
+        None
+    }
+}
+impl MutVisitor for Collector<'_> {
+    fn visit_item(&mut self, item: &mut Item) {
+        if let Some(id) = self.res.item(item.id) {
+            if let Some(generics) = item.generics() {
+                self.generics.insert(id, generics.clone());
+            }
+            match &item.kind {
+                ItemKind::Struct { .. }
+                | ItemKind::Enum { .. }
+                | ItemKind::Union { .. } => {
+                    self.adts.insert(id, item.clone());
+                }
+                ItemKind::TypeAlias { ty: Some(ty), .. } => {
+                    self.types.insert(id, ty.clone());
+                    self.bodies.insert(id);
+                }
+                ItemKind::Trait { .. } => self.tcx.add_trait(id),
+                ItemKind::Impl { of_trait, self_ty, .. } => {
+                    self.types.insert(id, self_ty.clone());
+                    match of_trait.as_ref().map(|path| self.bound_trait(path)) {
+                        None => self.impls.push(id),
+                        Some(Some(trait_)) => {
+                            self.trait_impls.push((id, trait_))
+                        }
+                        // already reported
+                        Some(None) => {}
+                    }
+                }
+                ItemKind::Fn(def) => {
+                    self.fns.insert(id, def.sig.clone());
+                    if def.body.is_some() {
+                        self.bodies.insert(id);
+                    }
+                }
+                ItemKind::Const { ty, value, .. } => {
+                    self.item_tys.insert(id, ty.clone());
+                    if let Some(value) = value {
+                        self.values.insert(id, value.clone());
+                        self.bodies.insert(id);
+                    }
+                }
+                ItemKind::Static { ty, .. } => {
+                    self.item_tys.insert(id, ty.clone());
+                }
+                _ => {}
+            }
+        }
+        visit::walk_item(self, item);
+    }
+}
+/// the span of the parameter or the return type at `index` of `sig`
+fn sig_span(sig: &FnSig, index: usize) -> Span {
+    let index = match sig.self_param {
+        Some(param) if index == 0 => return param.span,
+        Some(_) => index - 1,
+        None => index,
+    };
+    match (sig.params.get(index), &sig.ret) {
+        (Some(param), _) => param.ty.span,
+        (None, Some(ret)) => ret.span,
+        (None, None) => sig.span,
+    }
+}
+fn plural(count: usize) -> &'static str { if count == 1 { "" } else { "s" } }
+fn variant_fields(data: &VariantData) -> &[FieldDef] {
+    match data {
+        VariantData::Struct(fields) | VariantData::Tuple(fields) => fields,
+        VariantData::Unit => &[],
+    }
+}
+/// `value` is in the range of the integer type `kind`, `isize` and `usize`
+/// as on a 64-bit target
+fn fits(kind: &TyKind, value: i128) -> bool {
+    let (min, max) = match kind {
+        TyKind::Int(int) => {
+            let bits = match int {
+                IntTy::I8 => 8,
+                IntTy::I16 => 16,
+                IntTy::I32 => 32,
+                IntTy::I64 | IntTy::Isize => 64,
+                IntTy::I128 => return true,
+            };
+            (-(1 << (bits - 1)), (1 << (bits - 1)) - 1)
+        }
+        TyKind::Uint(uint) => {
+            let bits = match uint {
+                UintTy::U8 => 8,
+                UintTy::U16 => 16,
+                UintTy::U32 => 32,
+                UintTy::U64 | UintTy::Usize => 64,
+                UintTy::U128 => return value >= 0,
+            };
+            (0, (1 << bits) - 1)
+        }
+        _ => return true,
+    };
+    (min..=max).contains(&value)
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::semantic::tests::{Checked, check};
+    /// the types of `source`, and the errors of the collector
+    fn collect(source: &str) -> (Resolutions, TyCtxt, Vec<String>) {
+        let Checked { res, tcx, parse, resolve, collect, .. } = check(source);
+        assert_eq!(parse, []);
+        assert_eq!(resolve, []);
+        let errors = collect
+            .iter()
+            .map(|diagnostic| diagnostic.message().to_string())
+            .collect();
+        (res, tcx, errors)
+    }
+    /// every struct, enum and union with its fields and its size on a
+    /// 64-bit target
+    fn layouts(source: &str) -> Vec<String> {
+        let (res, mut tcx, errors) = collect(source);
+        assert_eq!(errors, Vec::<String>::new());
+        let mut adts: Vec<_> = tcx.adts().map(|(id, _)| id).collect();
+        adts.sort();
+        adts.into_iter()
+            .map(|id| {
+                let def = tcx.adt(id).unwrap().clone();
+                let variants: Vec<_> = def
+                    .variants
+                    .iter()
+                    .map(|variant| {
+                        let fields: Vec<_> = variant
+                            .fields
+                            .iter()
+                            .map(|field| {
+                                format!(
+                                    "{}: {}",
+                                    field.name,
+                                    tcx.display(field.ty)
+                                )
+                            })
+                            .collect();
+                        format!(
+                            "{} = {} {{ {} }}",
+                            variant.name,
+                            variant.discr,
+                            fields.join(", ")
+                        )
+                    })
+                    .collect();
+                let ty = tcx.intern(TyKind::Adt(id, Vec::new()));
+                let layout = tcx.layout_of(ty, Target::BITS_64).unwrap();
+                format!(
+                    "{} ({}, {}): {}",
+                    res.def_path(id),
+                    layout.size,
+                    layout.align,
+                    variants.join("; ")
+                )
+            })
+            .collect()
+    }
+    #[test]
+    fn structs_and_enums() {
+        assert_eq!(
+            layouts(
+                "const N: usize = 2 * 3 + 1;
+                type Pair = (u8, u32);
+                struct Point { x: f64, pub y: f64 }
+                struct Wrapper(Pair, [i16; N], &str);
+                #[repr(u8)]
+                enum Kind { A = 1, B, C = 10 }
+                enum Shape { Circle(Point, f32), Square { side: u64.Box } }
+                union Bits { f: f32, u: u32 }
+                mod inner { pub struct List { next: *const Self } }"
+            ),
+            [
+                "crate::Point (16, 8): Point = 0 { x: f64, y: f64 }",
+                "crate::Wrapper (40, 8): Wrapper = 0 { 0: (u8, u32), 1: [i16; \
+                 7], 2: &str }",
+                "crate::Kind (1, 1): A = 1 {  }; B = 2 {  }; C = 10 {  }",
+                "crate::Shape (24, 8): Circle = 0 { 0: Point, 1: f32 }; \
+                 Square = 1 { side: u64.Box }",
+                "crate::Bits (4, 4): Bits = 0 { f: f32, u: u32 }",
+                "crate::inner::List (8, 8): List = 0 { next: *const List }",
+            ]
+        );
+    }
+    #[test]
+    fn collect_errors() {
+        let (_, _, errors) = collect(
+            "struct A { b: B }
+            struct B { a: A, s: str }
+            type C = D;
+            type D = C;
+            const E: i32 = 1 / 0;
+            const F: i32 = G;
+            const G: i32 = F;
+            #[repr(u8)]
+            enum H { I = 255, J, K = 255 }
+            struct L([i32; -1], [u8; E], [u8; F]);
+            struct M(_);
+            #[derive(Copy)] struct N<T>(T, *const i32, (u8, M));",
+        );
+        assert_eq!(errors, [
+            "the size for values of type `str` cannot be known at compilation \
+             time",
+            "discriminant value `256` does not fit in `u8`",
+            "discriminant value `255` assigned more than once",
+            "array length `-1` is not a `usize`",
+            "evaluation of constant value failed",
+            "cycle detected when evaluating constant `F`",
+            "the placeholder `_` is not allowed within the types of items",
+            "the trait `Copy` cannot be implemented for this type",
+            "cycle detected when expanding type alias `C`",
+            "recursive type `A` has infinite size",
+            "recursive type `B` has infinite size",
+        ]);
+    }
+    #[test]
+    fn impl_errors() {
+        let (_, _, errors) = collect(
+            "trait A {
+                type T;
+                const N: u8;
+                fn f(&self, a: u8) -> Self::T;
+                fn g() {}
+            }
+            struct B;
+            impl A for B {
+                type T = i32;
+                const N: u16 = 1;
+                fn f(&self, a: u16) -> i32 { 1 }
+                fn h() {}
+            }
+            impl A for B {
+                fn f(self) -> () {}
+                fn g<U>() {}
+            }
+            impl A for i32 {
+                type T = ();
+                fn f(&self, a: u8, b: u8) {}
+            }
+            impl B { fn new() {} }
+            impl B { fn new() {} }
+            fn k<U: A>() -> U::X {}",
+        );
+        assert_eq!(errors, [
+            "associated type `X` not found for `U`",
+            "implemented const `N` has an incompatible type for trait",
+            "method `f` has an incompatible type for trait",
+            "method `h` is not a member of trait `A`",
+            "method `f` has 1 parameter but the declaration in trait `A::f` \
+             has 2",
+            "method `g` has 1 type parameter but its trait declaration has 0 \
+             type parameters",
+            "not all trait items implemented, missing: `T`, `N`",
+            "method `f` has 3 parameters but the declaration in trait `A::f` \
+             has 2",
+            "not all trait items implemented, missing: `N`",
+            "conflicting implementations of trait `A` for type `B`",
+            "duplicate definitions with name `new`",
+        ]);
+    }
+    #[test]
+    fn generics() {
+        let (res, mut tcx, errors) = collect(
+            "struct Pair<A, B> { a: A, b: B }
+            enum Option<T> { None, Some(T) }
+            struct List<T> { head: T, tail: Option<List<T>.Box> }
+            fn f(a: Pair<u8, u32>, b: Pair<u64, u8>, c: Option<Pair<u8, u8>>,
+                d: List<i32>) {}",
+        );
+        assert_eq!(errors, Vec::<String>::new());
+        let f = res.defs().find(|(_, def)| def.name == "f").unwrap().0;
+        let params = tcx.fn_sig(f).unwrap().params.clone();
+        let sizes: Vec<_> = params
+            .into_iter()
+            .map(|ty| {
+                let layout = tcx.layout_of(ty, Target::BITS_64).unwrap();
+                format!("{}: {}", tcx.display(ty), layout.size)
+            })
+            .collect();
+        assert_eq!(sizes, [
+            "Pair<u8, u32>: 8",
+            "Pair<u64, u8>: 16",
+            "Option<Pair<u8, u8>>: 3",
+            "List<i32>: 24",
+        ]);
+    }
+    #[test]
+    fn generic_errors() {
+        let (_, _, errors) = collect(
+            "struct A<T> { t: T }
+            struct B { a: A }
+            struct C { a: A<i32, u8>, b: i32<u8> }
+            impl<T> A<T> { fn g() {} }
+            impl A<i32> { fn g() {} }
+            impl A<u8> { fn h() {} }
+            impl A<u16> { fn h() {} }
+            fn f<T>(t: T<u8>) {}",
+        );
+        assert_eq!(errors, [
+            "missing generics for struct `A`",
+            "struct takes 1 generic argument but 2 generic arguments were \
+             supplied",
+            "type arguments are not allowed on this type",
+            "type arguments are not allowed on this type",
+            "duplicate definitions with name `g`",
+        ]);
+    }
+}
@@ -0,0 +1,396 @@
+// Copyright (c) 2025 air (https://yuanair.github.io).
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published by
+// the Free Software Foundation, version 3 of the License only.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+use crate::{
+    lexer::token::Literal,
+    parser::ast::{BinOp, FormatMacro, FormatSpec, UnOp},
+    semantic::resolve::DefId,
+    types::Ty,
+    utils::span::Span,
+};
+/// index of a [LocalDecl] in [Body::locals]
+#[derive(Debug, Clone, Copy, Hash, Ord, PartialOrd, PartialEq, Eq)]
+pub struct LocalId(u32);
+/// identifies a loop of a [Body], the target of `break` and `continue`
+#[derive(Debug, Clone, Copy, Hash, Ord, PartialOrd, PartialEq, Eq)]
+pub struct LoopId(u32);
+/// the typed body of a function, a constant or a static, see
+/// [TypeChecker](crate::semantic::typeck::TypeChecker)
+#[derive(Debug, Clone, PartialEq)]
+pub struct Body {
+    pub owner:  DefId,
+    /// `self` first for a method, then the bindings in the order of the
+    /// source
+    pub locals: Vec<LocalDecl>,
+    /// the patterns of the parameters, `self` is a [PatKind::Binding]
+    pub params: Vec<Pat>,
+    pub value:  Expr,
+}
+/// a variable, e.g. `a` of `let mut a = 1;`
+#[derive(Debug, Clone, PartialEq)]
+pub struct LocalDecl {
+    pub name:    String,
+    pub mutable: bool,
+    pub ty:      Ty,
+    pub span:    Span,
+}
+#[derive(Debug, Clone, PartialEq)]
+pub struct Expr {
+    pub kind: ExprKind,
+    pub ty:   Ty,
+    pub span: Span,
+}
+/// see [Expr], `a.f(b)` is a [ExprKind::Call] and `while` is a
+/// [ExprKind::Loop]
+#[derive(Debug, Clone, PartialEq)]
+pub enum ExprKind {
+    Literal(Literal),
+    Local(LocalId),
+    /// a function, a method, a constant or a static, e.g. `f`, `A::new`
+    Def(DefId),
+    /// a constant of a trait for a type only known after monomorphization,
+    /// e.g. `T::N` with `T: A`
+    AssocConst {
+        id:      DefId,
+        self_ty: Ty,
+    },
+    Tuple(Vec<Expr>),
+    Array(Vec<Expr>),
+    /// e.g. `[a; 3]`
+    Repeat(Box<Expr>, u64),
+    /// e.g. `A { a: 1, ..b }`, `A(1)`, `B::C`, the fields by their index in
+    /// the variant
+    Adt {
+        adt:     DefId,
+        variant: usize,
+        fields:  Vec<(usize, Expr)>,
+        rest:    Option<Box<Expr>>,
+    },
+    /// e.g. `-a`, `!a`, `*a`
+    Unary(UnOp, Box<Expr>),
+    /// e.g. `&a`, `&mut a`
+    Ref {
+        mutable: bool,
+        expr:    Box<Expr>,
+        /// the receiver `a` of `a.f()` borrowed for `&self` or `&mut self`,
+        /// which is borrowed after the other arguments are evaluated
+        autoref: bool,
+    },
+    /// e.g. `box a`
+    Box(Box<Expr>),
+    Binary(BinOp, Box<Expr>, Box<Expr>),
+    Assign(Box<Expr>, Box<Expr>),
+    AssignOp(BinOp, Box<Expr>, Box<Expr>),
+    /// e.g. `a as i64`, to [Expr::ty]
+    Cast(Box<Expr>),
+    /// e.g. `f(a)`, and `a.f(b)` as `A::f(&a, b)` with the receiver
+    /// adjusted to the type of `self`, see the `autoref` of
+    /// [ExprKind::Ref]
+    Call(Box<Expr>, Vec<Expr>),
+    /// e.g. `a.b`, `a.0`, by the index of the field
+    Field(Box<Expr>, usize),
+    /// e.g. `a[b]`
+    Index(Box<Expr>, Box<Expr>),
+    /// e.g. `a..b`, only the iterator of a [ExprKind::For], of the type of
+    /// its ends
+    Range {
+        start:     Box<Expr>,
+        end:       Box<Expr>,
+        inclusive: bool,
+    },
+    Block(Block),
+    If {
+        cond:        Box<Expr>,
+        then_branch: Block,
+        else_branch: Option<Box<Expr>>,
+    },
+    Match {
+        scrutinee: Box<Expr>,
+        arms:      Vec<Arm>,
+    },
+    /// `loop`, or `while a { b }` as `loop { if a { b } else { break } }`
+    Loop {
+        id:   LoopId,
+        body: Block,
+    },
+    /// e.g. `for a in 0..3 {}`, `for a in &array {}`
+    For {
+        id:   LoopId,
+        pat:  Pat,
+        iter: Box<Expr>,
+        body: Block,
+    },
+    Break {
+        target: LoopId,
+        value:  Option<Box<Expr>>,
+    },
+    Continue {
+        target: LoopId,
+    },
+    Return(Option<Box<Expr>>),
+    /// e.g. `println!("{a} {}", b)`, see [FormatPiece]
+    Format {
+        mac:    FormatMacro,
+        pieces: Vec<FormatPiece>,
+        /// the arguments after the format string, then the expressions
+        /// inside the placeholders
+        args:   Vec<Expr>,
+    },
+    /// an expression with an error, which is already reported
+    Err,
+}
+/// see [ExprKind::Format]
+#[derive(Debug, Clone, PartialEq)]
+pub enum FormatPiece {
+    Literal(String),
+    /// by the index of the argument
+    Placeholder {
+        arg:  usize,
+        spec: FormatSpec,
+    },
+}
+/// e.g. `{ a; b }`
+#[derive(Debug, Clone, PartialEq)]
+pub struct Block {
+    pub stmts: Vec<Stmt>,
+    /// the value of the block, `()` if [None]
+    pub expr:  Option<Box<Expr>>,
+    pub span:  Span,
+}
+#[derive(Debug, Clone, PartialEq)]
+pub struct Stmt {
+    pub kind: StmtKind,
+    pub span: Span,
+}
+/// see [Stmt], items are checked as bodies of their own
+#[derive(Debug, Clone, PartialEq)]
+pub enum StmtKind {
+    /// e.g. `let a = 1;`, `let Some(a) = b else { return };`
+    Let { pat: Pat, init: Option<Expr>, els: Option<Block> },
+    /// e.g. `a;`
+    Expr(Expr),
+}
+/// e.g. `a if c => b`
+#[derive(Debug, Clone, PartialEq)]
+pub struct Arm {
+    pub pat:   Pat,
+    pub guard: Option<Expr>,
+    pub body:  Expr,
+    pub span:  Span,
+}
+#[derive(Debug, Clone, PartialEq)]
+pub struct Pat {
+    pub kind: PatKind,
+    pub ty:   Ty,
+    pub span: Span,
+}
+/// see [Pat]
+#[derive(Debug, Clone, PartialEq)]
+pub enum PatKind {
+    /// `_`, or a pattern with an error
+    Wild,
+    /// e.g. `a`, `ref mut a`, `a @ 1..=5`
+    Binding {
+        local:  LocalId,
+        by_ref: bool,
+        sub:    Option<Box<Pat>>,
+    },
+    Literal(Literal),
+    /// a constant, e.g. `MAX`
+    Const(DefId),
+    /// e.g. `None`, `Some(a)`, `Point { x, .. }`, the fields by their index
+    /// in the variant
+    Adt {
+        adt:     DefId,
+        variant: usize,
+        fields:  Vec<(usize, Pat)>,
+    },
+    /// every element, `..` is expanded to `_`
+    Tuple(Vec<Pat>),
+    /// e.g. `[a, .., b]`
+    Slice {
+        prefix: Vec<Pat>,
+        /// has `..`
+        rest:   bool,
+        suffix: Vec<Pat>,
+    },
+    /// e.g. `1..=5`, the ends are [PatKind::Literal] or [PatKind::Const]
+    Range {
+        start:     Option<Box<Pat>>,
+        end:       Option<Box<Pat>>,
+        inclusive: bool,
+    },
+    /// e.g. `&a`, `&mut a`
+    Ref(Box<Pat>),
+    Or(Vec<Pat>),
+}
+/// visits the typed HIR in the order of the source, e.g. to replace the
+/// inference variables of its types
+pub trait MutVisitor {
+    fn visit_body(&mut self, body: &mut Body) { walk_body(self, body) }
+    fn visit_expr(&mut self, expr: &mut Expr) { walk_expr(self, expr) }
+    fn visit_block(&mut self, block: &mut Block) { walk_block(self, block) }
+    fn visit_stmt(&mut self, stmt: &mut Stmt) { walk_stmt(self, stmt) }
+    fn visit_pat(&mut self, pat: &mut Pat) { walk_pat(self, pat) }
+    /// the type of every local, expression and pattern
+    fn visit_ty(&mut self, _ty: &mut Ty) {}
+}
+pub fn walk_body<V: MutVisitor + ?Sized>(v: &mut V, body: &mut Body) {
+    for local in &mut body.locals {
+        v.visit_ty(&mut local.ty);
+    }
+    for param in &mut body.params {
+        v.visit_pat(param);
+    }
+    v.visit_expr(&mut body.value);
+}
+pub fn walk_expr<V: MutVisitor + ?Sized>(v: &mut V, expr: &mut Expr) {
+    v.visit_ty(&mut expr.ty);
+    match &mut expr.kind {
+        ExprKind::Literal(_)
+        | ExprKind::Local(_)
+        | ExprKind::Def(_)
+        | ExprKind::Continue { .. }
+        | ExprKind::Err => {}
+        ExprKind::AssocConst { self_ty, .. } => v.visit_ty(self_ty),
+        ExprKind::Tuple(exprs)
+        | ExprKind::Array(exprs)
+        | ExprKind::Format { args: exprs, .. } => {
+            for expr in exprs {
+                v.visit_expr(expr);
+            }
+        }
+        ExprKind::Repeat(expr, _)
+        | ExprKind::Unary(_, expr)
+        | ExprKind::Ref { expr, .. }
+        | ExprKind::Box(expr)
+        | ExprKind::Cast(expr)
+        | ExprKind::Field(expr, _) => v.visit_expr(expr),
+        ExprKind::Adt { fields, rest, .. } => {
+            for (_, expr) in fields {
+                v.visit_expr(expr);
+            }
+            if let Some(rest) = rest {
+                v.visit_expr(rest);
+            }
+        }
+        ExprKind::Binary(_, lhs, rhs)
+        | ExprKind::Assign(lhs, rhs)
+        | ExprKind::AssignOp(_, lhs, rhs)
+        | ExprKind::Index(lhs, rhs)
+        | ExprKind::Range { start: lhs, end: rhs, .. } => {
+            v.visit_expr(lhs);
+            v.visit_expr(rhs);
+        }
+        ExprKind::Call(callee, args) => {
+            v.visit_expr(callee);
+            for arg in args {
+                v.visit_expr(arg);
+            }
+        }
+        ExprKind::Block(block) | ExprKind::Loop { body: block, .. } => {
+            v.visit_block(block)
+        }
+        ExprKind::If { cond, then_branch, else_branch } => {
+            v.visit_expr(cond);
+            v.visit_block(then_branch);
+            if let Some(else_branch) = else_branch {
+                v.visit_expr(else_branch);
+            }
+        }
+        ExprKind::Match { scrutinee, arms } => {
+            v.visit_expr(scrutinee);
+            for arm in arms {
+                v.visit_pat(&mut arm.pat);
+                if let Some(guard) = &mut arm.guard {
+                    v.visit_expr(guard);
+                }
+                v.visit_expr(&mut arm.body);
+            }
+        }
+        ExprKind::For { pat, iter, body, .. } => {
+            v.visit_pat(pat);
+            v.visit_expr(iter);
+            v.visit_block(body);
+        }
+        ExprKind::Break { value, .. } | ExprKind::Return(value) => {
+            if let Some(value) = value {
+                v.visit_expr(value);
+            }
+        }
+    }
+}
+pub fn walk_block<V: MutVisitor + ?Sized>(v: &mut V, block: &mut Block) {
+    for stmt in &mut block.stmts {
+        v.visit_stmt(stmt);
+    }
+    if let Some(expr) = &mut block.expr {
+        v.visit_expr(expr);
+    }
+}
+pub fn walk_stmt<V: MutVisitor + ?Sized>(v: &mut V, stmt: &mut Stmt) {
+    match &mut stmt.kind {
+        StmtKind::Let { pat, init, els } => {
+            v.visit_pat(pat);
+            if let Some(init) = init {
+                v.visit_expr(init);
+            }
+            if let Some(els) = els {
+                v.visit_block(els);
+            }
+        }
+        StmtKind::Expr(expr) => v.visit_expr(expr),
+    }
+}
+pub fn walk_pat<V: MutVisitor + ?Sized>(v: &mut V, pat: &mut Pat) {
+    v.visit_ty(&mut pat.ty);
+    match &mut pat.kind {
+        PatKind::Wild | PatKind::Literal(_) | PatKind::Const(_) => {}
+        PatKind::Binding { sub, .. } => {
+            if let Some(sub) = sub {
+                v.visit_pat(sub);
+            }
+        }
+        PatKind::Adt { fields, .. } => {
+            for (_, pat) in fields {
+                v.visit_pat(pat);
+            }
+        }
+        PatKind::Tuple(pats) | PatKind::Or(pats) => {
+            for pat in pats {
+                v.visit_pat(pat);
+            }
+        }
+        PatKind::Slice { prefix, suffix, .. } => {
+            for pat in prefix.iter_mut().chain(suffix) {
+                v.visit_pat(pat);
+            }
+        }
+        PatKind::Range { start, end, .. } => {
+            for end in [start, end].into_iter().flatten() {
+                v.visit_pat(end);
+            }
+        }
+        PatKind::Ref(pat) => v.visit_pat(pat),
+    }
+}
+impl LocalId {
+    pub fn new(index: usize) -> Self { Self(index as u32) }
+
+    pub fn index(&self) -> usize { self.0 as usize }
+}
+impl LoopId {
+    pub fn new(index: usize) -> Self { Self(index as u32) }
+}
+impl Body {
+    pub fn local(&self, id: LocalId) -> &LocalDecl { &self.locals[id.index()] }
+}
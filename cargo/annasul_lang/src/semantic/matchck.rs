@@ -0,0 +1,970 @@
+// Copyright (c) 2025 air (https://yuanair.github.io).
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published by
+// the Free Software Foundation, version 3 of the License only.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+use std::mem;
+
+use crate::{
+    lexer::token::Literal,
+    semantic::hir::{
+        self,
+        Body,
+        Expr,
+        ExprKind,
+        MutVisitor,
+        Pat,
+        PatKind,
+        Stmt,
+        StmtKind,
+    },
+    types::{AdtKind, CharTy, Ty, TyCtxt, TyKind, layout::Target},
+    utils::{diagnostic::Diagnostic, span::Span},
+};
+/// checks that the arms of a `match` cover every value of the scrutinee and
+/// that every arm can be reached, and that the patterns of `let`, of the
+/// parameters and of `for` always match unless a `let` has an `else`
+///
+/// a missing value is reported as a pattern, e.g. `Option::Some(false)` or
+/// `i32::MIN..=-1`
+pub struct MatchChecker<'a> {
+    tcx:         &'a mut TyCtxt,
+    target:      Target,
+    diagnostics: Vec<Diagnostic>,
+}
+/// a pattern as a constructor and the patterns of its fields, or a value
+/// not covered by a match
+#[derive(Debug, Clone)]
+struct DeconPat {
+    ctor:   Ctor,
+    /// the alternatives of [Ctor::Or]
+    fields: Vec<DeconPat>,
+    ty:     Ty,
+}
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Ctor {
+    /// `_`, or a binding
+    Wild,
+    Or,
+    Bool(bool),
+    /// the integers or the characters from the first to the second
+    /// inclusive, offset so the order of the signed ones is the one of
+    /// `u128`, see [IntInfo]
+    Range(u128, u128),
+    /// of an enum
+    Variant(usize),
+    /// a struct, a union or a tuple
+    Single,
+    Ref,
+    Slice(SliceKind),
+    /// e.g. a float, a string or a constant, only known to match itself
+    Opaque,
+    /// the values of a type that only `_` covers, e.g. `f64`
+    NonExhaustive,
+}
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum SliceKind {
+    /// e.g. `[a, b]`
+    Fixed(usize),
+    /// e.g. `[a, .., b]`, by the length of the prefix and the suffix
+    Var(usize, usize),
+}
+/// the width of an integer or a character type, a signed value `v` is
+/// stored as `v + 2^(bits - 1)`
+#[derive(Debug, Clone, Copy)]
+struct IntInfo {
+    bits:   u32,
+    signed: bool,
+}
+/// the patterns of an arm or a value, one per column
+type Row = Vec<DeconPat>;
+impl<'a> MatchChecker<'a> {
+    pub fn new(tcx: &'a mut TyCtxt) -> Self {
+        Self { tcx, target: Target::host(), diagnostics: Vec::new() }
+    }
+
+    /// the host by default, for the range of `usize` and `isize`
+    pub fn with_target(mut self, target: Target) -> Self {
+        self.target = target;
+        self
+    }
+
+    pub fn check_crate(&mut self, bodies: &[Body]) {
+        for body in bodies {
+            self.check_body(body);
+        }
+    }
+
+    pub fn check_body(&mut self, body: &Body) {
+        for param in &body.params {
+            self.check_irrefutable(param, "function argument");
+        }
+        self.visit_expr(&mut body.value.clone());
+    }
+
+    pub fn take_diagnostics(&mut self) -> Vec<Diagnostic> {
+        mem::take(&mut self.diagnostics)
+    }
+
+    fn check_match(&mut self, scrutinee: &Expr, arms: &[hir::Arm]) {
+        let mut rows = Vec::new();
+        for arm in arms {
+            let pat = self.lower(&arm.pat);
+            let alternatives = match pat.ctor {
+                Ctor::Or => pat.fields.clone(),
+                _ => vec![pat.clone()],
+            };
+            let PatKind::Or(spans) = &arm.pat.kind else {
+                if !self.is_useful(&rows, &pat) {
+                    self.unreachable(arm.pat.span);
+                }
+                if arm.guard.is_none() {
+                    push_row(&mut rows, vec![pat]);
+                }
+                continue;
+            };
+            // an alternative may be unreachable because of an earlier one
+            let mut seen = rows.clone();
+            let mut useless = Vec::new();
+            for (alternative, pat) in alternatives.into_iter().zip(spans) {
+                if !self.is_useful(&seen, &alternative) {
+                    useless.push(pat.span);
+                }
+                push_row(&mut seen, vec![alternative]);
+            }
+            match useless.len() == spans.len() {
+                true => self.unreachable(arm.pat.span),
+                false => {
+                    for span in useless {
+                        self.unreachable(span);
+                    }
+                }
+            }
+            if arm.guard.is_none() {
+                rows = seen;
+            }
+        }
+        let wild = DeconPat::wild(scrutinee.ty);
+        let witnesses = self.witnesses(&rows, &wild);
+        if witnesses.is_empty() {
+            return;
+        }
+        let (listed, label) = self.list(&witnesses);
+        let ty = self.tcx.display(scrutinee.ty);
+        self.diagnostics.push(
+            Diagnostic::error(format!("non-exhaustive patterns: {listed}"))
+                .with_code("E0004")
+                .with_primary(scrutinee.span, label)
+                .with_note(format!("the matched value is of type `{ty}`"))
+                .with_help(
+                    "ensure that all possible cases are being handled by \
+                     adding a match arm with a wildcard pattern, or an arm \
+                     for every pattern not covered",
+                ),
+        );
+    }
+
+    fn check_let(&mut self, pat: &Pat, has_else: bool) {
+        if !has_else {
+            self.check_irrefutable(pat, "local binding");
+            return;
+        }
+        if !self.is_refutable(pat) {
+            self.diagnostics.push(
+                Diagnostic::warning("irrefutable `let...else` pattern")
+                    .with_lint("irrefutable_let_patterns")
+                    .with_primary(
+                        pat.span,
+                        "this pattern always matches, so the `else` clause is \
+                         useless",
+                    )
+                    .with_help("consider removing the `else` clause"),
+            );
+        }
+    }
+
+    /// reports the values `pat` does not match, in a `let` without `else`,
+    /// a parameter or a `for`
+    fn check_irrefutable(&mut self, pat: &Pat, context: &str) {
+        let witnesses = self.not_matched(pat);
+        if witnesses.is_empty() {
+            return;
+        }
+        let (_, label) = self.list(&witnesses);
+        let mut diagnostic =
+            Diagnostic::error(format!("refutable pattern in {context}"))
+                .with_code("E0005")
+                .with_primary(pat.span, label);
+        if context == "local binding" {
+            diagnostic = diagnostic
+                .with_note(
+                    "`let` bindings require an irrefutable pattern, like a \
+                     `struct` or an `enum` with only one variant",
+                )
+                .with_help(
+                    "you might want to use `let...else` to handle the values \
+                     that are not matched",
+                );
+        }
+        self.diagnostics.push(diagnostic);
+    }
+
+    fn unreachable(&mut self, span: Span) {
+        self.diagnostics.push(
+            Diagnostic::warning("unreachable pattern")
+                .with_lint("unreachable_patterns")
+                .with_primary(span, "no value can reach this"),
+        );
+    }
+
+    /// e.g. ``"`A`, `B` and 2 more not covered"`` and ``"patterns `A`, `B`,
+    /// `C` and 1 more not covered"``
+    fn list(&mut self, witnesses: &[DeconPat]) -> (String, String) {
+        let mut shown: Vec<_> = witnesses
+            .iter()
+            .take(if witnesses.len() > 4 { 3 } else { 4 })
+            .map(|witness| format!("`{}`", self.display(witness)))
+            .collect();
+        let more = witnesses.len() - shown.len();
+        let last = match more {
+            0 => shown.pop().unwrap(),
+            _ => format!("{more} more"),
+        };
+        let listed = match shown.is_empty() {
+            true => last,
+            false => format!("{} and {last}", shown.join(", ")),
+        };
+        let noun = if witnesses.len() == 1 { "pattern" } else { "patterns" };
+        (
+            format!("{listed} not covered"),
+            format!("{noun} {listed} not covered"),
+        )
+    }
+}
+impl MatchChecker<'_> {
+    /// the values `pat` does not match
+    fn not_matched(&mut self, pat: &Pat) -> Vec<DeconPat> {
+        let mut rows = Vec::new();
+        let lowered = self.lower(pat);
+        push_row(&mut rows, vec![lowered]);
+        self.witnesses(&rows, &DeconPat::wild(pat.ty))
+    }
+
+    fn is_refutable(&mut self, pat: &Pat) -> bool {
+        !self.not_matched(pat).is_empty()
+    }
+
+    /// `pat` matches a value none of `rows` does
+    fn is_useful(&mut self, rows: &[Row], pat: &DeconPat) -> bool {
+        !self.useful(rows, vec![pat.clone()]).is_empty()
+    }
+
+    /// the values `pat` matches and none of `rows` does
+    fn witnesses(&mut self, rows: &[Row], pat: &DeconPat) -> Vec<DeconPat> {
+        let found = self.useful(rows, vec![pat.clone()]);
+        found.into_iter().map(|mut row| row.pop().unwrap()).collect()
+    }
+
+    /// the values `row` matches and none of `rows` does, empty if `row` is
+    /// useless
+    ///
+    /// the first column is split into constructors that every pattern in
+    /// it either covers or does not, then the rows are specialized to each
+    /// of them, see "Warnings for pattern matching" by Luc Maranget
+    fn useful(&mut self, rows: &[Row], row: Row) -> Vec<Row> {
+        let Some(head) = row.first() else {
+            return match rows.is_empty() {
+                true => vec![Vec::new()],
+                false => Vec::new(),
+            };
+        };
+        if head.ctor == Ctor::Or {
+            let mut found = Vec::new();
+            for alternative in &head.fields {
+                let mut row = row.clone();
+                row[0] = alternative.clone();
+                found.extend(self.useful(rows, row));
+            }
+            return found;
+        }
+        let ty = head.ty;
+        // only known to match itself, so only the wildcards cover it
+        if head.ctor == Ctor::Opaque {
+            let found = self.useful(&default_rows(rows), row[1..].to_vec());
+            return found
+                .into_iter()
+                .map(|rest| [vec![row[0].clone()], rest].concat())
+                .collect();
+        }
+        let column: Vec<_> = rows
+            .iter()
+            .map(|row| row[0].ctor)
+            .filter(|ctor| *ctor != Ctor::Wild)
+            .collect();
+        let split = match head.ctor {
+            Ctor::Wild => {
+                let all = self.all_ctors(ty);
+                let split = self.split(&all, &column, ty);
+                let missing: Vec<_> = split
+                    .iter()
+                    .copied()
+                    .filter(|ctor| !column.iter().any(|h| covers(*h, *ctor)))
+                    .collect();
+                if !missing.is_empty() {
+                    return self.useful_default(rows, &row, &column, &missing);
+                }
+                split
+            }
+            ctor => self.split(&[ctor], &column, ty),
+        };
+        let mut witnesses = Vec::new();
+        for ctor in split {
+            let tys = self.fields(ctor, ty);
+            let mut specialized = Vec::new();
+            for row in rows {
+                if let Some(row) = specialize(row, ctor, &tys) {
+                    push_row(&mut specialized, row);
+                }
+            }
+            let Some(row) = specialize(&row, ctor, &tys) else { continue };
+            for mut found in self.useful(&specialized, row) {
+                let rest = found.split_off(tys.len());
+                let mut witness = vec![DeconPat { ctor, fields: found, ty }];
+                witness.extend(rest);
+                witnesses.push(witness);
+            }
+        }
+        witnesses
+    }
+
+    /// a wildcard in the first column of `row` matches the constructors
+    /// `missing` that no row has, only the rows starting with a wildcard
+    /// apply to the other columns
+    fn useful_default(
+        &mut self, rows: &[Row], row: &Row, column: &[Ctor], missing: &[Ctor],
+    ) -> Vec<Row> {
+        let ty = row[0].ty;
+        let mut witnesses = Vec::new();
+        for rest in self.useful(&default_rows(rows), row[1..].to_vec()) {
+            // `_` if no row names a constructor, e.g. for a binding
+            let heads = match column.is_empty() {
+                true => vec![DeconPat::wild(ty)],
+                false => missing
+                    .iter()
+                    .map(|ctor| {
+                        let fields = self.fields(*ctor, ty);
+                        let fields = fields.into_iter().map(DeconPat::wild);
+                        DeconPat { ctor: *ctor, fields: fields.collect(), ty }
+                    })
+                    .collect(),
+            };
+            for head in heads {
+                let mut witness = vec![head];
+                witness.extend(rest.iter().cloned());
+                witnesses.push(witness);
+            }
+        }
+        witnesses
+    }
+
+    /// every constructor of `ty`
+    fn all_ctors(&self, ty: Ty) -> Vec<Ctor> {
+        match self.tcx.kind(ty) {
+            TyKind::Bool => vec![Ctor::Bool(false), Ctor::Bool(true)],
+            // without the surrogates
+            TyKind::Char(CharTy::C32) => {
+                vec![Ctor::Range(0, 0xD7FF), Ctor::Range(0xE000, 0x10FFFF)]
+            }
+            TyKind::Int(_) | TyKind::Uint(_) | TyKind::Char(_) => {
+                vec![Ctor::Range(0, self.int_info(ty).unwrap().max())]
+            }
+            TyKind::Adt(id, _) => {
+                let adt = self.tcx.adt(*id).unwrap();
+                match adt.kind {
+                    AdtKind::Enum => {
+                        (0..adt.variants.len()).map(Ctor::Variant).collect()
+                    }
+                    AdtKind::Struct | AdtKind::Union => vec![Ctor::Single],
+                }
+            }
+            TyKind::Tuple(_) => vec![Ctor::Single],
+            TyKind::Ref { .. } => vec![Ctor::Ref],
+            TyKind::Array(_, len) => {
+                vec![Ctor::Slice(SliceKind::Fixed(*len as usize))]
+            }
+            TyKind::Slice(_) => vec![Ctor::Slice(SliceKind::Var(0, 0))],
+            _ => vec![Ctor::NonExhaustive],
+        }
+    }
+
+    /// splits `ctors` so each of the pieces is either covered or not by
+    /// each of `column`, e.g. `0..=255` by `5..=9` into `0..=4`, `5..=9`
+    /// and `10..=255`
+    fn split(&self, ctors: &[Ctor], column: &[Ctor], ty: Ty) -> Vec<Ctor> {
+        let mut split = Vec::new();
+        for ctor in ctors {
+            match *ctor {
+                Ctor::Range(lo, hi) => {
+                    let mut borders: Vec<_> = column
+                        .iter()
+                        .filter_map(|ctor| match ctor {
+                            Ctor::Range(lo, hi) => {
+                                Some([Some(*lo), hi.checked_add(1)])
+                            }
+                            _ => None,
+                        })
+                        .flatten()
+                        .flatten()
+                        .filter(|border| lo < *border && *border <= hi)
+                        .collect();
+                    borders.sort_unstable();
+                    borders.dedup();
+                    let mut start = lo;
+                    for border in borders {
+                        split.push(Ctor::Range(start, border - 1));
+                        start = border;
+                    }
+                    split.push(Ctor::Range(start, hi));
+                }
+                // the lengths up to the longest one in the column, and the
+                // longer ones together
+                Ctor::Slice(kind)
+                    if matches!(self.tcx.kind(ty), TyKind::Slice(_)) =>
+                {
+                    let kinds = column
+                        .iter()
+                        .filter_map(|ctor| match ctor {
+                            Ctor::Slice(kind) => Some(*kind),
+                            _ => None,
+                        })
+                        .chain([kind]);
+                    let (mut fixed, mut prefix, mut suffix) = (0, 0, 0);
+                    for kind in kinds {
+                        match kind {
+                            SliceKind::Fixed(len) => fixed = fixed.max(len + 1),
+                            SliceKind::Var(p, s) => {
+                                prefix = prefix.max(p);
+                                suffix = suffix.max(s);
+                            }
+                        }
+                    }
+                    let len = fixed.max(prefix + suffix);
+                    match kind {
+                        SliceKind::Fixed(_) => split.push(*ctor),
+                        SliceKind::Var(p, s) => {
+                            for len in p + s..len {
+                                split.push(Ctor::Slice(SliceKind::Fixed(len)));
+                            }
+                            let var = SliceKind::Var(prefix, len - prefix);
+                            split.push(Ctor::Slice(var));
+                        }
+                    }
+                }
+                ctor => split.push(ctor),
+            }
+        }
+        split
+    }
+
+    /// the types of the fields of `ctor`
+    fn fields(&mut self, ctor: Ctor, ty: Ty) -> Vec<Ty> {
+        let variant = match ctor {
+            Ctor::Variant(variant) => variant,
+            Ctor::Single => 0,
+            Ctor::Ref => match self.tcx.kind(ty) {
+                TyKind::Ref { ty, .. } => return vec![*ty],
+                _ => return Vec::new(),
+            },
+            Ctor::Slice(kind) => {
+                let elem = match self.tcx.kind(ty) {
+                    TyKind::Array(elem, _) | TyKind::Slice(elem) => *elem,
+                    _ => return Vec::new(),
+                };
+                let len = match kind {
+                    SliceKind::Fixed(len) => len,
+                    SliceKind::Var(prefix, suffix) => prefix + suffix,
+                };
+                return vec![elem; len];
+            }
+            _ => return Vec::new(),
+        };
+        match self.tcx.kind(ty) {
+            TyKind::Tuple(elems) => elems.clone(),
+            TyKind::Adt(id, _) => {
+                let adt = self.tcx.adt(*id).unwrap();
+                let count = adt.variants[variant].fields.len();
+                (0..count)
+                    .map(|index| self.tcx.field_ty(ty, variant, index))
+                    .collect()
+            }
+            _ => Vec::new(),
+        }
+    }
+
+    fn lower(&mut self, pat: &Pat) -> DeconPat {
+        let ty = pat.ty;
+        let (ctor, fields) = match &pat.kind {
+            PatKind::Wild | PatKind::Binding { sub: None, .. } => {
+                (Ctor::Wild, Vec::new())
+            }
+            PatKind::Binding { sub: Some(sub), .. } => return self.lower(sub),
+            PatKind::Literal(literal) => {
+                (self.literal_ctor(literal, ty), Vec::new())
+            }
+            PatKind::Const(_) => (Ctor::Opaque, Vec::new()),
+            PatKind::Range { start, end, inclusive } => {
+                let ctor = self.range_ctor(
+                    start.as_deref(),
+                    end.as_deref(),
+                    *inclusive,
+                    ty,
+                );
+                (ctor, Vec::new())
+            }
+            PatKind::Adt { variant, fields, .. } => {
+                let ctor = match self.all_ctors(ty).as_slice() {
+                    [Ctor::Single] => Ctor::Single,
+                    _ => Ctor::Variant(*variant),
+                };
+                let tys = self.fields(ctor, ty);
+                let mut lowered: Vec<_> =
+                    tys.into_iter().map(DeconPat::wild).collect();
+                for (index, field) in fields {
+                    lowered[*index] = self.lower(field);
+                }
+                (ctor, lowered)
+            }
+            PatKind::Tuple(elems) => (
+                Ctor::Single,
+                elems.iter().map(|elem| self.lower(elem)).collect(),
+            ),
+            PatKind::Slice { prefix, rest, suffix } => {
+                let mut fields: Vec<_> =
+                    prefix.iter().map(|elem| self.lower(elem)).collect();
+                let kind = match (rest, self.tcx.kind(ty).clone()) {
+                    (false, _) => SliceKind::Fixed(prefix.len() + suffix.len()),
+                    // `[a, .., b]` of an array is `[a, _, ..., _, b]`
+                    (true, TyKind::Array(elem, len)) => {
+                        let len = len as usize;
+                        let wild =
+                            len.saturating_sub(prefix.len() + suffix.len());
+                        fields.extend((0..wild).map(|_| DeconPat::wild(elem)));
+                        SliceKind::Fixed(len)
+                    }
+                    (true, _) => SliceKind::Var(prefix.len(), suffix.len()),
+                };
+                fields.extend(suffix.iter().map(|elem| self.lower(elem)));
+                (Ctor::Slice(kind), fields)
+            }
+            PatKind::Ref(inner) => (Ctor::Ref, vec![self.lower(inner)]),
+            PatKind::Or(alternatives) => {
+                let lowered = alternatives
+                    .iter()
+                    .map(|alternative| self.lower(alternative));
+                (Ctor::Or, lowered.collect())
+            }
+        };
+        DeconPat { ctor, fields, ty }
+    }
+
+    fn literal_ctor(&self, literal: &Literal, ty: Ty) -> Ctor {
+        let Some(info) = self.int_info(ty) else {
+            return match literal {
+                Literal::Bool(value) => Ctor::Bool(*value),
+                _ => Ctor::Opaque,
+            };
+        };
+        let value = match literal {
+            Literal::Int(lit) if lit.negative => lit.value.wrapping_neg(),
+            Literal::Int(lit) => lit.value,
+            Literal::Char(value) => *value as u128,
+            Literal::CChar8(value) => *value as u128,
+            Literal::CChar16(value) => *value as u128,
+            _ => return Ctor::Opaque,
+        };
+        let value = info.bias(value);
+        Ctor::Range(value, value)
+    }
+
+    /// e.g. `1..5`, `..=5`, `'a'..`, a constant end is opaque
+    fn range_ctor(
+        &self, start: Option<&Pat>, end: Option<&Pat>, inclusive: bool, ty: Ty,
+    ) -> Ctor {
+        let Some(info) = self.int_info(ty) else { return Ctor::Opaque };
+        let value = |pat: &Pat| match &pat.kind {
+            PatKind::Literal(literal) => match self.literal_ctor(literal, ty) {
+                Ctor::Range(value, _) => Some(value),
+                _ => None,
+            },
+            _ => None,
+        };
+        let lo = match start.map(value) {
+            Some(Some(lo)) => lo,
+            Some(None) => return Ctor::Opaque,
+            None => 0,
+        };
+        let hi = match end.map(value) {
+            Some(Some(hi)) if inclusive => hi,
+            Some(Some(hi)) if hi > 0 => hi - 1,
+            Some(_) => return Ctor::Opaque,
+            None => info.max(),
+        };
+        // an empty range matches nothing
+        match lo <= hi {
+            true => Ctor::Range(lo, hi),
+            false => Ctor::Opaque,
+        }
+    }
+
+    fn int_info(&self, ty: Ty) -> Option<IntInfo> {
+        let (bits, signed) = self.tcx.int_width(ty, self.target)?;
+        Some(IntInfo { bits, signed })
+    }
+
+    /// a value not covered as a pattern, e.g. `Option::Some(false)`,
+    /// `Point { x: 0, .. }`, `[_, ..]` or `5..=i32::MAX`
+    fn display(&self, pat: &DeconPat) -> String {
+        let fields: Vec<_> =
+            pat.fields.iter().map(|field| self.display(field)).collect();
+        match pat.ctor {
+            Ctor::Wild | Ctor::Or | Ctor::Opaque | Ctor::NonExhaustive => {
+                "_".to_string()
+            }
+            Ctor::Bool(value) => value.to_string(),
+            Ctor::Range(lo, hi) => self.display_range(lo, hi, pat.ty),
+            Ctor::Variant(_) | Ctor::Single => {
+                let TyKind::Adt(id, _) = self.tcx.kind(pat.ty) else {
+                    return match fields.len() {
+                        1 => format!("({},)", fields[0]),
+                        _ => format!("({})", fields.join(", ")),
+                    };
+                };
+                let adt = self.tcx.adt(*id).unwrap();
+                let (variant, name) = match pat.ctor {
+                    Ctor::Variant(index) => {
+                        let variant = &adt.variants[index];
+                        (variant, format!("{}::{}", adt.name, variant.name))
+                    }
+                    _ => (&adt.variants[0], adt.name.clone()),
+                };
+                match variant.fields.first() {
+                    None => name,
+                    Some(first) if first.name.parse::<usize>().is_ok() => {
+                        format!("{name}({})", fields.join(", "))
+                    }
+                    Some(_) => {
+                        let mut named: Vec<_> = variant
+                            .fields
+                            .iter()
+                            .zip(&fields)
+                            .filter(|(_, field)| *field != "_")
+                            .map(|(def, field)| {
+                                format!("{}: {field}", def.name)
+                            })
+                            .collect();
+                        if named.len() < fields.len() {
+                            named.push("..".to_string());
+                        }
+                        format!("{name} {{ {} }}", named.join(", "))
+                    }
+                }
+            }
+            Ctor::Ref => match self.tcx.kind(pat.ty) {
+                TyKind::Ref { mutable: true, .. } => {
+                    format!("&mut {}", fields[0])
+                }
+                _ => format!("&{}", fields[0]),
+            },
+            Ctor::Slice(SliceKind::Fixed(_)) => {
+                format!("[{}]", fields.join(", "))
+            }
+            Ctor::Slice(SliceKind::Var(prefix, _)) => {
+                let mut elems = fields;
+                elems.insert(prefix, "..".to_string());
+                format!("[{}]", elems.join(", "))
+            }
+        }
+    }
+
+    /// e.g. `5`, `'a'..='z'`, `i32::MIN..=-1`
+    fn display_range(&self, lo: u128, hi: u128, ty: Ty) -> String {
+        let info = self.int_info(ty).unwrap();
+        let name = self.tcx.display(ty);
+        let value = |value: u128| match self.tcx.kind(ty) {
+            TyKind::Char(CharTy::C8) => {
+                Literal::CChar8(value as u8).to_string()
+            }
+            TyKind::Char(CharTy::C16) => {
+                Literal::CChar16(value as u16).to_string()
+            }
+            TyKind::Char(_) => {
+                let value = char::from_u32(value as u32).unwrap_or_default();
+                Literal::Char(value).to_string()
+            }
+            _ => match info.signed {
+                true => (info.unbias(value) as i128).to_string(),
+                false => value.to_string(),
+            },
+        };
+        let is_char = matches!(self.tcx.kind(ty), TyKind::Char(_));
+        match (lo, hi) {
+            _ if lo == hi => value(lo),
+            (0, _) if hi == info.max() => "_".to_string(),
+            (0, _) if !is_char => format!("{name}::MIN..={}", value(hi)),
+            _ if hi == info.max() && !is_char => {
+                format!("{}..={name}::MAX", value(lo))
+            }
+            _ => format!("{}..={}", value(lo), value(hi)),
+        }
+    }
+}
+impl MutVisitor for MatchChecker<'_> {
+    fn visit_expr(&mut self, expr: &mut Expr) {
+        match &expr.kind {
+            ExprKind::Match { scrutinee, arms } => {
+                self.check_match(scrutinee, arms)
+            }
+            ExprKind::For { pat, .. } => {
+                self.check_irrefutable(pat, "`for` loop binding")
+            }
+            _ => {}
+        }
+        hir::walk_expr(self, expr);
+    }
+
+    fn visit_stmt(&mut self, stmt: &mut Stmt) {
+        if let StmtKind::Let { pat, els, .. } = &stmt.kind {
+            self.check_let(pat, els.is_some());
+        }
+        hir::walk_stmt(self, stmt);
+    }
+}
+impl DeconPat {
+    fn wild(ty: Ty) -> Self {
+        Self { ctor: Ctor::Wild, fields: Vec::new(), ty }
+    }
+}
+impl IntInfo {
+    fn max(&self) -> u128 { u128::MAX >> (128 - self.bits) }
+
+    fn bias(&self, value: u128) -> u128 { value.wrapping_add(self.offset()) }
+
+    fn unbias(&self, value: u128) -> u128 { value.wrapping_sub(self.offset()) }
+
+    fn offset(&self) -> u128 {
+        match self.signed {
+            true => 1 << (self.bits - 1),
+            false => 0,
+        }
+    }
+}
+/// the values of `ctor` are all values of `head`
+fn covers(head: Ctor, ctor: Ctor) -> bool {
+    match (head, ctor) {
+        (Ctor::Range(lo, hi), Ctor::Range(start, end)) => {
+            lo <= start && end <= hi
+        }
+        (
+            Ctor::Slice(SliceKind::Var(prefix, suffix)),
+            Ctor::Slice(SliceKind::Fixed(len)),
+        ) => prefix + suffix <= len,
+        (
+            Ctor::Slice(SliceKind::Var(prefix, suffix)),
+            Ctor::Slice(SliceKind::Var(start, end)),
+        ) => prefix <= start && suffix <= end,
+        (Ctor::Opaque | Ctor::NonExhaustive, _) => false,
+        _ => head == ctor,
+    }
+}
+/// the fields of the first pattern of `row` if it covers `ctor`, followed
+/// by the other patterns
+fn specialize(row: &Row, ctor: Ctor, tys: &[Ty]) -> Option<Row> {
+    let head = &row[0];
+    let wild = |count: usize| (0..count).map(|_| DeconPat::wild(tys[0]));
+    let mut fields: Row = match (head.ctor, ctor) {
+        (Ctor::Wild, _) => tys.iter().map(|ty| DeconPat::wild(*ty)).collect(),
+        (head_ctor, _) if !covers(head_ctor, ctor) => return None,
+        // the elements between the prefix and the suffix are `_`
+        (Ctor::Slice(SliceKind::Var(prefix, suffix)), Ctor::Slice(kind)) => {
+            let middle = match kind {
+                SliceKind::Fixed(len) => len - prefix - suffix,
+                SliceKind::Var(start, end) => start - prefix + end - suffix,
+            };
+            let mut fields = head.fields[..prefix].to_vec();
+            fields.extend(wild(middle));
+            fields.extend(head.fields[prefix..].iter().cloned());
+            fields
+        }
+        _ => head.fields.clone(),
+    };
+    fields.extend(row[1..].iter().cloned());
+    Some(fields)
+}
+/// the other patterns of the rows starting with a wildcard
+fn default_rows(rows: &[Row]) -> Vec<Row> {
+    let mut default = Vec::new();
+    for row in rows.iter().filter(|row| row[0].ctor == Ctor::Wild) {
+        push_row(&mut default, row[1..].to_vec());
+    }
+    default
+}
+/// adds `row`, once for each alternative of an or-pattern first in it
+fn push_row(rows: &mut Vec<Row>, row: Row) {
+    match row.first() {
+        Some(head) if head.ctor == Ctor::Or => {
+            for alternative in &head.fields {
+                let mut row = row.clone();
+                row[0] = alternative.clone();
+                push_row(rows, row);
+            }
+        }
+        _ => rows.push(row),
+    }
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::semantic::tests::{Checked, check_clean};
+    /// the diagnostics of the match checker on `source` with the text their
+    /// primary label points at
+    fn diagnostics(source: &str) -> Vec<String> {
+        let Checked { source_map, mut tcx, bodies, .. } = check_clean(source);
+        let mut matchck = MatchChecker::new(&mut tcx);
+        matchck.check_crate(&bodies);
+        matchck
+            .take_diagnostics()
+            .iter()
+            .map(|diagnostic| {
+                let span = diagnostic.primary_span().unwrap();
+                let snippet = source_map.snippet(span);
+                let level = match diagnostic.code() {
+                    Some(code) => format!("{}[{code}]", diagnostic.level()),
+                    None => diagnostic.level().to_string(),
+                };
+                format!("{level}: {} `{snippet}`", diagnostic.message())
+            })
+            .collect()
+    }
+    #[test]
+    fn exhaustiveness() {
+        let diagnostics = diagnostics(
+            "enum Option<T> { None, Some(T) }
+            enum Color { Red, Green, Blue }
+            struct Point { x: i32, y: bool }
+            fn main() {
+                let o = Option::Some(true);
+                match o { Option::Some(true) => {} Option::None => {} }
+                match o { Option::Some(_) => {} Option::None => {} }
+                let n = 5;
+                match n { 0 => {} 1..=9 => {} }
+                match n { ..0 => {} 0 => {} 1.. => {} }
+                let b = 1u8;
+                match b { 0..=127 => {} 128..=255 => {} }
+                match b { 0..128 => {} 129.. => {} }
+                let t = (true, Color::Red);
+                match t {
+                    (true, _) => {}
+                    (false, Color::Red | Color::Green) => {}
+                }
+                let a = [1u8, 2, 3];
+                let s: &[u8] = &a;
+                match s { &[] => {} &[x, .., y] => {} }
+                match s { &[] => {} &[_] => {} &[_, _, ..] => {} }
+                match a { [0, ..] => {} [_, _, 1] => {} }
+                let p = Point { x: 1, y: true };
+                match p { Point { x: 0, y } => {} Point { y: false, .. } => {} \
+             }
+                let c = 'a';
+                match c { 'a'..='z' => {} }
+                let f = 1.5;
+                match f { 1.5 => {} }
+                match &o { &Option::None => {} }
+                match (o, Color::Red) {}
+            }",
+        );
+        assert_eq!(diagnostics, [
+            "error[E0004]: non-exhaustive patterns: `Option::Some(false)` not \
+             covered `o`",
+            "error[E0004]: non-exhaustive patterns: `i32::MIN..=-1` and \
+             `10..=i32::MAX` not covered `n`",
+            "error[E0004]: non-exhaustive patterns: `128` not covered `b`",
+            "error[E0004]: non-exhaustive patterns: `(false, Color::Blue)` \
+             not covered `t`",
+            "error[E0004]: non-exhaustive patterns: `&[_]` not covered `s`",
+            "error[E0004]: non-exhaustive patterns: `[1..=u8::MAX, _, 0]` and \
+             `[1..=u8::MAX, _, 2..=u8::MAX]` not covered `a`",
+            "error[E0004]: non-exhaustive patterns: `Point { x: \
+             i32::MIN..=-1, y: true }` and `Point { x: 1..=i32::MAX, y: true \
+             }` not covered `p`",
+            "error[E0004]: non-exhaustive patterns: `'\\0'..='`'`, \
+             `'{'..='\\u{d7ff}'` and `'\\u{e000}'..='\\u{10ffff}'` not \
+             covered `c`",
+            "error[E0004]: non-exhaustive patterns: `_` not covered `f`",
+            "error[E0004]: non-exhaustive patterns: `&Option::Some(_)` not \
+             covered `&o`",
+            "error[E0004]: non-exhaustive patterns: `_` not covered `(o, \
+             Color::Red)`",
+        ]);
+    }
+    #[test]
+    fn reachability() {
+        let diagnostics = diagnostics(
+            "enum Option<T> { None, Some(T) }
+            fn main() {
+                let o = Option::Some(1u8);
+                match o {
+                    Option::Some(0..=9) => {}
+                    Option::Some(5) => {}
+                    Option::Some(n) if n > 100 => {}
+                    Option::Some(10..) => {}
+                    Option::None | Option::Some(_) => {}
+                    _ => {}
+                }
+                let t = (1, true);
+                match t {
+                    (_, true) => {}
+                    (1, false) | (_, true) | (_, false) => {}
+                }
+            }",
+        );
+        assert_eq!(diagnostics, [
+            "warning: unreachable pattern `Option::Some(5)`",
+            "warning: unreachable pattern `Option::Some(_)`",
+            "warning: unreachable pattern `_`",
+            "warning: unreachable pattern `(_, true)`",
+        ]);
+    }
+    #[test]
+    fn let_patterns() {
+        let diagnostics = diagnostics(
+            "enum Option<T> { None, Some(T) }
+            struct Wrapper(i32);
+            fn first((a, b): (i32, bool)) {}
+            fn unwrap(Option::Some(a): Option<i32>) {}
+            fn main() {
+                let o = Option::Some(1);
+                let Option::Some(a) = o;
+                let Option::Some(b) = o else { return; };
+                let Wrapper(c) = Wrapper(1);
+                let Wrapper(d) = Wrapper(1) else { return; };
+                let (1 | _, e) = (1, 2);
+                for 0 in 0..1 {}
+            }",
+        );
+        assert_eq!(diagnostics, [
+            "error[E0005]: refutable pattern in function argument \
+             `Option::Some(a)`",
+            "error[E0005]: refutable pattern in local binding \
+             `Option::Some(a)`",
+            "warning: irrefutable `let...else` pattern `Wrapper(d)`",
+            "error[E0005]: refutable pattern in `for` loop binding `0`",
+        ]);
+    }
+}
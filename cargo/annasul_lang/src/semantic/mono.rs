@@ -0,0 +1,330 @@
+// Copyright (c) 2025 air (https://yuanair.github.io).
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published by
+// the Free Software Foundation, version 3 of the License only.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+use std::collections::HashMap;
+
+use crate::{
+    semantic::{
+        hir::{self, Body, Expr, ExprKind, MutVisitor},
+        resolve::{DefId, DefKind, Resolutions},
+    },
+    types::{Ty, TyCtxt, TyKind},
+    utils::{diagnostic::Diagnostic, span::Span},
+};
+/// how many generic functions may instantiate each other in a chain, e.g.
+/// `fn f<T>() { f::<(T,)>() }` never ends
+pub const RECURSION_LIMIT: usize = 64;
+/// a function, a constant or a static with its generic arguments, by
+/// [TyCtxt::generics]
+#[derive(Debug, Clone, Hash, PartialEq, Eq)]
+pub struct Instance {
+    pub def:  DefId,
+    pub args: Vec<Ty>,
+}
+/// a body with its generic parameters replaced by the arguments of its
+/// instance, and the trait items it uses replaced by those of the impls
+#[derive(Debug, Clone, PartialEq)]
+pub struct MonoItem {
+    pub instance: Instance,
+    pub body:     Body,
+}
+/// collects the instances of the typed bodies reachable from the bodies
+/// without generic parameters, see [MonoItem]
+///
+/// every instance is generated once, however often it is used
+pub struct Monomorphizer<'a> {
+    res:         &'a Resolutions,
+    tcx:         &'a mut TyCtxt,
+    /// the typed bodies by their owners, generic or not
+    bodies:      HashMap<DefId, &'a Body>,
+    /// the index of every instance in `items`
+    cache:       HashMap<Instance, usize>,
+    items:       Vec<MonoItem>,
+    /// the number of instances that led to each item, see
+    /// [RECURSION_LIMIT]
+    depths:      Vec<usize>,
+    diagnostics: Vec<Diagnostic>,
+}
+/// substitutes the generic arguments of an instance into its body, and
+/// collects the instances it uses
+struct Substitutor<'a, 'b> {
+    res:    &'a Resolutions,
+    tcx:    &'b mut TyCtxt,
+    params: Vec<DefId>,
+    args:   &'b [Ty],
+    /// the instances with bodies, by the spans of their uses
+    used:   Vec<(Instance, Span)>,
+    bodies: &'b HashMap<DefId, &'a Body>,
+}
+impl Instance {
+    /// e.g. `crate::f::<i32, bool>`
+    pub fn display(&self, res: &Resolutions, tcx: &TyCtxt) -> String {
+        let path = res.def_path(self.def);
+        if self.args.is_empty() {
+            return path;
+        }
+        let args: Vec<String> =
+            self.args.iter().map(|ty| tcx.display(*ty)).collect();
+        format!("{path}::<{}>", args.join(", "))
+    }
+}
+impl<'a> Monomorphizer<'a> {
+    pub fn new(
+        res: &'a Resolutions, tcx: &'a mut TyCtxt, bodies: &'a [Body],
+    ) -> Self {
+        Self {
+            res,
+            tcx,
+            bodies: bodies.iter().map(|body| (body.owner, body)).collect(),
+            cache: HashMap::new(),
+            items: Vec::new(),
+            depths: Vec::new(),
+            diagnostics: Vec::new(),
+        }
+    }
+
+    /// the instances of every body without generic parameters and of the
+    /// bodies they use, in the order they are found
+    pub fn collect_crate(&mut self) -> Vec<MonoItem> {
+        let mut roots: Vec<DefId> = self
+            .bodies
+            .keys()
+            .copied()
+            .filter(|id| generics_of(self.res, self.tcx, *id).is_empty())
+            .collect();
+        roots.sort();
+        for def in roots {
+            self.push(Instance { def, args: Vec::new() }, 0);
+        }
+        let mut next = 0;
+        while next < self.items.len() {
+            self.monomorphize(next);
+            next += 1;
+        }
+        std::mem::take(&mut self.items)
+    }
+
+    pub fn take_diagnostics(&mut self) -> Vec<Diagnostic> {
+        std::mem::take(&mut self.diagnostics)
+    }
+
+    /// adds `instance` unless it is already cached
+    fn push(&mut self, instance: Instance, depth: usize) {
+        if self.cache.contains_key(&instance) {
+            return;
+        }
+        let body = self.bodies[&instance.def].clone();
+        self.cache.insert(instance.clone(), self.items.len());
+        self.items.push(MonoItem { instance, body });
+        self.depths.push(depth);
+    }
+
+    /// substitutes the arguments into the body of the item `index` and
+    /// pushes the instances it uses
+    fn monomorphize(&mut self, index: usize) {
+        let item = &mut self.items[index];
+        let params = generics_of(self.res, self.tcx, item.instance.def);
+        let mut substitutor = Substitutor {
+            res: self.res,
+            tcx: self.tcx,
+            params,
+            args: &item.instance.args,
+            used: Vec::new(),
+            bodies: &self.bodies,
+        };
+        substitutor.visit_body(&mut item.body);
+        let used = substitutor.used;
+        let depth = self.depths[index] + 1;
+        for (instance, span) in used {
+            if depth > RECURSION_LIMIT && !self.cache.contains_key(&instance) {
+                let name = instance.display(self.res, self.tcx);
+                self.diagnostics.push(
+                    Diagnostic::error(format!(
+                        "reached the recursion limit while instantiating \
+                         `{name}`"
+                    ))
+                    .with_primary(span, "instantiated here")
+                    .with_note(format!(
+                        "generic functions may instantiate each other at most \
+                         {RECURSION_LIMIT} times in a chain"
+                    )),
+                );
+                // the chain stops here, the other uses are of the same
+                // depth
+                return;
+            }
+            self.push(instance, depth);
+        }
+    }
+}
+impl Substitutor<'_, '_> {
+    /// the function or constant of the impl for a trait item `id` with
+    /// the arguments `args`, `Self` first, or `id` itself for a default of
+    /// the trait or a type only known at runtime
+    fn resolve(&mut self, id: DefId, args: &[Ty]) -> Option<Instance> {
+        let parent = self.res.def(id).parent;
+        let Some(trait_) = parent
+            .filter(|parent| self.res.def(*parent).kind == DefKind::Trait)
+        else {
+            return Some(Instance { def: id, args: args.to_vec() });
+        };
+        let self_ty = *args.first()?;
+        if let TyKind::Dynamic(_) = self.tcx.kind(self_ty) {
+            return None;
+        }
+        let (impl_, impl_args) = self.tcx.find_impl(trait_, self_ty)?;
+        match self.tcx.impl_item(impl_, id) {
+            Some(item) if item != id => {
+                let mut item_args = impl_args;
+                item_args.extend_from_slice(&args[1..]);
+                Some(Instance { def: item, args: item_args })
+            }
+            _ => Some(Instance { def: id, args: args.to_vec() }),
+        }
+    }
+
+    fn use_instance(&mut self, instance: Instance, span: Span) {
+        if self.bodies.contains_key(&instance.def) {
+            self.used.push((instance, span));
+        }
+    }
+}
+impl MutVisitor for Substitutor<'_, '_> {
+    fn visit_ty(&mut self, ty: &mut Ty) {
+        let instantiated = self.tcx.subst(*ty, &self.params, self.args);
+        *ty = self.tcx.normalize(instantiated);
+    }
+
+    fn visit_expr(&mut self, expr: &mut Expr) {
+        hir::walk_expr(self, expr);
+        match &expr.kind {
+            ExprKind::Def(id) => {
+                let TyKind::FnDef(_, args) = self.tcx.kind(expr.ty).clone()
+                else {
+                    // a constant or a static
+                    if generics_of(self.res, self.tcx, *id).is_empty() {
+                        let instance = Instance { def: *id, args: Vec::new() };
+                        self.use_instance(instance, expr.span);
+                    }
+                    return;
+                };
+                let Some(instance) = self.resolve(*id, &args) else { return };
+                expr.kind = ExprKind::Def(instance.def);
+                expr.ty = self
+                    .tcx
+                    .intern(TyKind::FnDef(instance.def, instance.args.clone()));
+                self.use_instance(instance, expr.span);
+            }
+            ExprKind::AssocConst { id, self_ty } => {
+                let Some(instance) = self.resolve(*id, &[*self_ty]) else {
+                    return;
+                };
+                if instance.def != *id {
+                    expr.kind = ExprKind::Def(instance.def);
+                }
+                self.use_instance(instance, expr.span);
+            }
+            _ => {}
+        }
+    }
+}
+/// [TyCtxt::generics] of a body, `Self` for a constant of a trait and the
+/// parameters of the impl for a constant of an impl
+fn generics_of(res: &Resolutions, tcx: &TyCtxt, id: DefId) -> Vec<DefId> {
+    let def = res.def(id);
+    if def.kind != DefKind::AssocConst {
+        return tcx.generics(id).to_vec();
+    }
+    match def.parent {
+        Some(parent) if res.def(parent).kind == DefKind::Trait => {
+            vec![parent]
+        }
+        Some(parent) => tcx.generics(parent).to_vec(),
+        None => Vec::new(),
+    }
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::semantic::tests::{Checked, check_clean};
+    /// every instance of `source` with the types of its locals, and the
+    /// errors of the monomorphizer
+    fn instances(source: &str) -> (Vec<String>, Vec<String>) {
+        let Checked { res, mut tcx, bodies, .. } = check_clean(source);
+        let mut monomorphizer = Monomorphizer::new(&res, &mut tcx, &bodies);
+        let items = monomorphizer.collect_crate();
+        let errors = monomorphizer
+            .take_diagnostics()
+            .iter()
+            .map(|diagnostic| diagnostic.message().to_string())
+            .collect();
+        let instances = items
+            .iter()
+            .map(|item| {
+                let locals: Vec<_> = item
+                    .body
+                    .locals
+                    .iter()
+                    .map(|local| {
+                        format!("{}: {}", local.name, tcx.display(local.ty))
+                    })
+                    .collect();
+                let name = item.instance.display(&res, &tcx);
+                format!("{name} ({})", locals.join(", "))
+            })
+            .collect();
+        (instances, errors)
+    }
+    #[test]
+    fn instantiation() {
+        let (instances, errors) = instances(
+            "trait Show { fn show(&self) -> i32; fn twice(&self) -> i32 { \
+             self.show() * 2 } }
+            impl Show for i32 { fn show(&self) -> i32 { *self } }
+            struct Wrapper<T> { t: T }
+            impl<T: Show> Show for Wrapper<T> {
+                fn show(&self) -> i32 { self.t.show() }
+            }
+            fn id<T>(t: T) -> T { t }
+            fn total<T: Show>(t: T) -> i32 { let n = t.twice(); n }
+            fn unused<T>(t: T) {}
+            fn main() {
+                let a = id(1);
+                let b = id(2);
+                let c = id(true);
+                let d = total(Wrapper { t: a });
+            }",
+        );
+        assert_eq!(errors, Vec::<String>::new());
+        assert_eq!(instances, [
+            "crate::{impl}::show (self: &i32)",
+            "crate::main (a: i32, b: i32, c: bool, d: i32)",
+            "crate::id::<i32> (t: i32)",
+            "crate::id::<bool> (t: bool)",
+            "crate::total::<Wrapper<i32>> (t: Wrapper<i32>, n: i32)",
+            "crate::Show::twice::<Wrapper<i32>> (self: &Wrapper<i32>)",
+            "crate::{impl}::show::<i32> (self: &Wrapper<i32>)",
+        ]);
+    }
+    #[test]
+    fn recursion_limit() {
+        let (_, errors) = instances(
+            "fn f<T>(t: T) { f((t,)); }
+            fn main() { f(1); }",
+        );
+        // one error for the chain, by the innermost instance
+        assert_eq!(errors.len(), 1);
+        assert!(errors[0].starts_with(
+            "reached the recursion limit while instantiating `crate::f::<((("
+        ));
+    }
+}
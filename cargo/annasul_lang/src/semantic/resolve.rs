@@ -0,0 +1,2064 @@
+// Copyright (c) 2025 air (https://yuanair.github.io).
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published by
+// the Free Software Foundation, version 3 of the License only.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+use std::collections::{HashMap, hash_map::Entry};
+
+use crate::{
+    attr::{self, Deprecation},
+    lexer::token::Identifier,
+    parser::{
+        ast::{
+            Block,
+            Expr,
+            ExprKind,
+            Generics,
+            Ident,
+            Item,
+            ItemKind,
+            Label,
+            Module,
+            NodeId,
+            Pat,
+            PatKind,
+            Path,
+            Stmt,
+            StmtKind,
+            Type,
+            TypeKind,
+            UseTree,
+            UseTreeKind,
+            VariantData,
+            Visibility,
+        },
+        visit::{self, MutVisitor},
+    },
+    utils::{diagnostic::Diagnostic, span::Span},
+};
+/// index of a [Def] in [Resolutions]
+#[derive(Debug, Clone, Copy, Hash, Ord, PartialOrd, PartialEq, Eq)]
+pub struct DefId(u32);
+/// a module, an item, an associated item or an enum variant of a crate
+#[derive(Debug, Clone)]
+pub struct Def {
+    pub kind:       DefKind,
+    /// `crate` for the root module
+    pub name:       String,
+    /// the enclosing module, block, enum, trait or impl, [None] for the
+    /// root module
+    pub parent:     Option<DefId>,
+    pub vis:        Vis,
+    /// how a struct or a variant is constructed, [None] for `A { .. }`
+    pub ctor:       Option<CtorKind>,
+    /// the items of a module, the variants of an enum, the associated
+    /// items of a trait or an impl
+    pub children:   Vec<DefId>,
+    /// the name, or the whole item for impls, blocks and the root module
+    pub span:       Span,
+    /// see [attr::deprecation]
+    pub deprecated: Option<Deprecation>,
+}
+#[derive(Debug, Clone, Copy, Hash, PartialEq, Eq)]
+pub enum DefKind {
+    Mod,
+    Fn,
+    Struct,
+    Enum,
+    Variant,
+    Union,
+    Trait,
+    Impl,
+    TypeAlias,
+    Const,
+    Static,
+    /// e.g. `fn a()` in a trait or an impl
+    AssocFn,
+    AssocConst,
+    AssocTy,
+    /// a block with items, e.g. `{ fn a() {} a() }`
+    Block,
+    /// e.g. `T` of `fn a<T>()`
+    TyParam,
+}
+/// e.g. `A(i32)` or `A`, the constructor of a struct or a variant
+#[derive(Debug, Clone, Copy, Hash, PartialEq, Eq)]
+pub enum CtorKind {
+    /// `A(1)`
+    Fn,
+    /// `A`
+    Const,
+}
+/// where a [Def] can be named
+#[derive(Debug, Clone, Copy, Hash, PartialEq, Eq)]
+pub enum Vis {
+    /// `pub`
+    Public,
+    /// inside the module and its descendants, e.g. no visibility or `priv`
+    Restricted(DefId),
+}
+/// a module has one item of a name in each namespace, e.g. a function and
+/// a module named `a`
+#[derive(Debug, Clone, Copy, Hash, PartialEq, Eq)]
+pub enum Namespace {
+    /// modules, types and traits
+    Type,
+    /// functions, constants, statics, locals and constructors
+    Value,
+}
+/// see the type table in `lib.md`
+#[derive(Debug, Clone, Copy, Hash, PartialEq, Eq)]
+pub enum PrimTy {
+    I8,
+    I16,
+    I32,
+    I64,
+    I128,
+    Isize,
+    U8,
+    U16,
+    U32,
+    U64,
+    U128,
+    Usize,
+    F16,
+    F32,
+    F64,
+    F128,
+    Bool,
+    /// `char` or `c_char32`
+    Char,
+    CChar8,
+    CChar16,
+    Str,
+}
+/// what a path refers to
+#[derive(Debug, Clone, Copy, Hash, PartialEq, Eq)]
+pub enum Res {
+    Def(DefKind, DefId),
+    PrimTy(PrimTy),
+    /// `Self` in the impl, trait, struct, enum or union
+    SelfTy(DefId),
+    /// `self` in the method
+    SelfParam(DefId),
+    /// the binding of a [PatKind::Ident], by the id of the pattern
+    Local(NodeId),
+    /// an error, which is already reported
+    Err,
+}
+/// e.g. `Vec::new` resolves `Vec` and leaves `new` to the type checker
+#[derive(Debug, Clone, Copy, Hash, PartialEq, Eq)]
+pub struct PartialRes {
+    pub base:       Res,
+    /// the number of trailing segments that name associated items
+    pub unresolved: usize,
+}
+/// the output of [Resolver::resolve_crate]
+#[derive(Debug, Default, Clone)]
+pub struct Resolutions {
+    defs:  Vec<Def>,
+    /// by [Item::id]
+    items: HashMap<NodeId, DefId>,
+    /// by [Path::id], and by [Pat::id] for an identifier pattern that
+    /// names a constant or a unit struct or variant, or that binds the
+    /// same name as the first alternative of an `|` pattern
+    paths: HashMap<NodeId, PartialRes>,
+}
+/// builds the module tree of a crate, resolves its `use` items and every
+/// path in it, see [Resolutions]
+///
+/// locals are compared with their [Identifier::mark], items by name
+#[derive(Debug, Default)]
+pub struct Resolver {
+    res:         Resolutions,
+    /// the names of every module, enum and block with items
+    scopes:      HashMap<DefId, Scope>,
+    imports:     Vec<Import>,
+    /// the innermost last
+    ribs:        Vec<Rib>,
+    /// the labels of the enclosing loops of the current function
+    labels:      Vec<Label>,
+    /// see [Res::SelfTy]
+    self_ty:     Option<DefId>,
+    /// the method with a `self` parameter the current body belongs to
+    self_param:  Option<DefId>,
+    diagnostics: Vec<Diagnostic>,
+}
+#[derive(Debug, Default)]
+struct Scope {
+    bindings: HashMap<(Namespace, String), Binding>,
+}
+/// a name in a [Scope]
+#[derive(Debug, Clone)]
+struct Binding {
+    res:       Res,
+    vis:       Vis,
+    span:      Span,
+    /// from `use a::*`, shadowed by any other binding
+    glob:      bool,
+    /// another glob import of the same name, which makes using the name
+    /// an error
+    ambiguity: Option<Box<Binding>>,
+}
+#[derive(Debug)]
+struct Import {
+    /// the module or block of the `use` item
+    scope: DefId,
+    path:  Vec<Ident>,
+    kind:  ImportKind,
+    vis:   Vis,
+    span:  Span,
+}
+#[derive(Debug)]
+enum ImportKind {
+    /// e.g. `a::b`, `a::b as c`, or `a::{self}` which only imports the
+    /// module
+    Single { name: Ident, type_only: bool },
+    /// `a::*`
+    Glob,
+}
+/// the bindings of a pattern, with the id and the name of the identifier
+/// pattern that binds them
+type Bindings = HashMap<Identifier, (NodeId, Span)>;
+#[derive(Debug, Default)]
+struct Rib {
+    locals: HashMap<Identifier, NodeId>,
+    /// a module or a block with items
+    scope:  Option<DefId>,
+    /// the start of an item, locals outside of it are out of reach
+    item:   bool,
+    /// the generic parameters of the item, e.g. `T` of `fn a<T>()`
+    params: HashMap<String, DefId>,
+}
+/// why the segment at the index failed to resolve
+#[derive(Debug, Clone)]
+enum PathError {
+    NotFound(usize),
+    Private(usize, Binding),
+    /// the previous segment is not a module, e.g. `f::a`
+    NotAModule(usize, Res),
+    TooManySupers(usize),
+    NoSelfTy(usize),
+    NoSelfParam,
+    Ambiguous(usize, Binding),
+}
+/// where a path is resolved, to check what it refers to
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum PathSource {
+    Type,
+    Trait,
+    Expr,
+    /// e.g. `A { a: 1 }`
+    Struct,
+    /// e.g. `A` in a pattern
+    Pat,
+    /// e.g. `A(a)` in a pattern
+    TupleStruct,
+}
+impl DefId {
+    pub fn new(index: usize) -> Self { Self(index as u32) }
+
+    pub fn index(&self) -> usize { self.0 as usize }
+}
+impl DefKind {
+    /// e.g. `function`
+    pub fn descr(&self) -> &'static str {
+        match self {
+            DefKind::Mod => "module",
+            DefKind::Fn => "function",
+            DefKind::Struct => "struct",
+            DefKind::Enum => "enum",
+            DefKind::Variant => "variant",
+            DefKind::Union => "union",
+            DefKind::Trait => "trait",
+            DefKind::Impl => "impl",
+            DefKind::TypeAlias => "type alias",
+            DefKind::Const => "constant",
+            DefKind::Static => "static",
+            DefKind::AssocFn => "associated function",
+            DefKind::AssocConst => "associated constant",
+            DefKind::AssocTy => "associated type",
+            DefKind::Block => "block",
+            DefKind::TyParam => "type parameter",
+        }
+    }
+}
+impl Namespace {
+    pub fn descr(&self) -> &'static str {
+        match self {
+            Namespace::Type => "type",
+            Namespace::Value => "value",
+        }
+    }
+
+    fn other(&self) -> Namespace {
+        match self {
+            Namespace::Type => Namespace::Value,
+            Namespace::Value => Namespace::Type,
+        }
+    }
+}
+impl PrimTy {
+    pub const ALL: [PrimTy; 21] = [
+        PrimTy::I8,
+        PrimTy::I16,
+        PrimTy::I32,
+        PrimTy::I64,
+        PrimTy::I128,
+        PrimTy::Isize,
+        PrimTy::U8,
+        PrimTy::U16,
+        PrimTy::U32,
+        PrimTy::U64,
+        PrimTy::U128,
+        PrimTy::Usize,
+        PrimTy::F16,
+        PrimTy::F32,
+        PrimTy::F64,
+        PrimTy::F128,
+        PrimTy::Bool,
+        PrimTy::Char,
+        PrimTy::CChar8,
+        PrimTy::CChar16,
+        PrimTy::Str,
+    ];
+
+    /// e.g. `i32`, and `c_char32` for [PrimTy::Char]
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "c_char32" => Some(PrimTy::Char),
+            _ => Self::ALL.into_iter().find(|ty| ty.as_str() == name),
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            PrimTy::I8 => "i8",
+            PrimTy::I16 => "i16",
+            PrimTy::I32 => "i32",
+            PrimTy::I64 => "i64",
+            PrimTy::I128 => "i128",
+            PrimTy::Isize => "isize",
+            PrimTy::U8 => "u8",
+            PrimTy::U16 => "u16",
+            PrimTy::U32 => "u32",
+            PrimTy::U64 => "u64",
+            PrimTy::U128 => "u128",
+            PrimTy::Usize => "usize",
+            PrimTy::F16 => "f16",
+            PrimTy::F32 => "f32",
+            PrimTy::F64 => "f64",
+            PrimTy::F128 => "f128",
+            PrimTy::Bool => "bool",
+            PrimTy::Char => "char",
+            PrimTy::CChar8 => "c_char8",
+            PrimTy::CChar16 => "c_char16",
+            PrimTy::Str => "str",
+        }
+    }
+}
+impl Res {
+    /// e.g. `function`
+    pub fn descr(&self) -> &'static str {
+        match self {
+            Res::Def(kind, _) => kind.descr(),
+            Res::PrimTy(_) => "builtin type",
+            Res::SelfTy(_) => "self type",
+            Res::SelfParam(_) => "self value",
+            Res::Local(_) => "local variable",
+            Res::Err => "unresolved item",
+        }
+    }
+
+    pub fn def_id(&self) -> Option<DefId> {
+        match self {
+            Res::Def(_, id) => Some(*id),
+            _ => None,
+        }
+    }
+}
+impl PartialRes {
+    pub fn new(base: Res) -> Self { Self { base, unresolved: 0 } }
+
+    /// the resolution of the whole path, [None] for associated items
+    pub fn full_res(&self) -> Option<Res> {
+        (self.unresolved == 0).then_some(self.base)
+    }
+}
+impl Resolutions {
+    /// the root module of the crate
+    pub fn root(&self) -> DefId { DefId(0) }
+
+    pub fn def(&self, id: DefId) -> &Def { &self.defs[id.0 as usize] }
+
+    pub fn defs(&self) -> impl Iterator<Item = (DefId, &Def)> {
+        self.defs.iter().enumerate().map(|(i, def)| (DefId(i as u32), def))
+    }
+
+    /// the definition of an item, including associated items
+    pub fn item(&self, id: NodeId) -> Option<DefId> {
+        self.items.get(&id).copied()
+    }
+
+    /// see [Resolutions::paths]
+    pub fn path(&self, id: NodeId) -> Option<PartialRes> {
+        self.paths.get(&id).copied()
+    }
+
+    /// e.g. `crate::a::B`, with `{impl}` and `{block}` for the anonymous
+    /// parents
+    pub fn def_path(&self, id: DefId) -> String {
+        let mut names = Vec::new();
+        let mut next = Some(id);
+        while let Some(id) = next {
+            let def = self.def(id);
+            names.push(match def.kind {
+                DefKind::Impl => "{impl}",
+                DefKind::Block => "{block}",
+                _ => def.name.as_str(),
+            });
+            next = def.parent;
+        }
+        names.reverse();
+        names.join("::")
+    }
+
+    /// the module `id` is in, itself for a module
+    pub fn module(&self, mut id: DefId) -> DefId {
+        while self.def(id).kind != DefKind::Mod {
+            id = self.def(id).parent.unwrap();
+        }
+        id
+    }
+
+    /// `vis` allows naming the item from the module or block `from`
+    pub fn is_accessible(&self, vis: Vis, from: DefId) -> bool {
+        let Vis::Restricted(module) = vis else { return true };
+        let mut next = Some(self.module(from));
+        while let Some(id) = next {
+            if id == module {
+                return true;
+            }
+            next = self.def(id).parent;
+        }
+        false
+    }
+}
+impl Resolver {
+    pub fn new() -> Self { Self::default() }
+
+    /// resolves `module`, the root of a crate after
+    /// [assign_node_ids](crate::semantic::assign_node_ids), which is not
+    /// changed
+    pub fn resolve_crate(&mut self, module: &mut Module) -> Resolutions {
+        let root = self.define_def(
+            DefKind::Mod,
+            "crate".to_string(),
+            None,
+            Vis::Public,
+            module.span,
+        );
+        self.scopes.insert(root, Scope::default());
+        self.collect_items(&module.items, root);
+        self.resolve_imports(0);
+        self.ribs.push(Rib { scope: Some(root), item: true, ..Rib::default() });
+        self.visit_module(module);
+        self.ribs.pop();
+        std::mem::take(&mut self.res)
+    }
+
+    /// unresolved, ambiguous and private names, and duplicate definitions
+    pub fn take_diagnostics(&mut self) -> Vec<Diagnostic> {
+        std::mem::take(&mut self.diagnostics)
+    }
+
+    fn define_def(
+        &mut self, kind: DefKind, name: String, parent: Option<DefId>,
+        vis: Vis, span: Span,
+    ) -> DefId {
+        let id = DefId(self.res.defs.len() as u32);
+        self.res.defs.push(Def {
+            kind,
+            name,
+            parent,
+            vis,
+            ctor: None,
+            children: Vec::new(),
+            span,
+            deprecated: None,
+        });
+        if let Some(parent) = parent {
+            self.res.defs[parent.0 as usize].children.push(id);
+        }
+        id
+    }
+
+    fn def_mut(&mut self, id: DefId) -> &mut Def {
+        &mut self.res.defs[id.0 as usize]
+    }
+
+    /// binds `name` in `scope`, reporting a duplicate unless one of them is
+    /// a glob import, which is shadowed
+    fn define(
+        &mut self, scope: DefId, ns: Namespace, name: &Ident, binding: Binding,
+    ) {
+        let key = (ns, name.node.as_str().to_string());
+        let bindings = &mut self.scopes.get_mut(&scope).unwrap().bindings;
+        match bindings.get(&key) {
+            Some(previous) if !previous.glob && !binding.glob => {
+                let previous = previous.span;
+                let name = &key.1;
+                self.diagnostics.push(
+                    Diagnostic::error(format!(
+                        "the name `{name}` is defined multiple times"
+                    ))
+                    .with_code("E0428")
+                    .with_primary(
+                        binding.span,
+                        format!("`{name}` redefined here"),
+                    )
+                    .with_secondary(
+                        previous,
+                        format!("previous definition of `{name}` here"),
+                    )
+                    .with_note(format!(
+                        "`{name}` must be defined only once in the {} \
+                         namespace of this module",
+                        ns.descr()
+                    )),
+                );
+            }
+            Some(previous) if !previous.glob => {}
+            _ => {
+                bindings.insert(key, binding);
+            }
+        }
+    }
+
+    /// the visibility of an item in `scope`
+    fn vis(&self, vis: &Visibility, scope: DefId) -> Vis {
+        match vis {
+            Visibility::Public(_) => Vis::Public,
+            Visibility::Inherited | Visibility::Private(_) => {
+                Vis::Restricted(self.res.module(scope))
+            }
+        }
+    }
+
+    /// defines the items of a module or a block, and collects their
+    /// imports
+    fn collect_items<'a>(
+        &mut self, items: impl IntoIterator<Item = &'a Item>, scope: DefId,
+    ) {
+        for item in items {
+            self.collect_item(item, scope);
+        }
+    }
+
+    fn collect_item(&mut self, item: &Item, scope: DefId) {
+        let vis = self.vis(&item.vis, scope);
+        let (kind, name) = match &item.kind {
+            ItemKind::Fn(def) => (DefKind::Fn, &def.name),
+            ItemKind::Struct { name, .. } => (DefKind::Struct, name),
+            ItemKind::Enum { name, .. } => (DefKind::Enum, name),
+            ItemKind::Union { name, .. } => (DefKind::Union, name),
+            ItemKind::Trait { name, .. } => (DefKind::Trait, name),
+            ItemKind::Mod { name, .. } => (DefKind::Mod, name),
+            ItemKind::Const { name, .. } => (DefKind::Const, name),
+            ItemKind::Static { name, .. } => (DefKind::Static, name),
+            ItemKind::TypeAlias { name, .. } => (DefKind::TypeAlias, name),
+            ItemKind::Impl { items, .. } => {
+                let id = self.define_def(
+                    DefKind::Impl,
+                    "{impl}".to_string(),
+                    Some(scope),
+                    vis,
+                    item.span,
+                );
+                self.res.items.insert(item.id, id);
+                self.collect_assoc_items(items, id, None);
+                self.collect_generics(item, id);
+                return;
+            }
+            ItemKind::Use(tree) => {
+                self.collect_use(tree, Vec::new(), scope, vis);
+                return;
+            }
+            ItemKind::Extern { items, .. } => {
+                self.collect_items(items, scope);
+                return;
+            }
+            // expanded before resolution
+            ItemKind::Marco(_) | ItemKind::MacCall(_) | ItemKind::Err => {
+                return;
+            }
+        };
+        let id = self.define_def(
+            kind,
+            name.node.as_str().to_string(),
+            Some(scope),
+            vis,
+            name.span,
+        );
+        self.res.items.insert(item.id, id);
+        self.def_mut(id).deprecated = attr::deprecation(&item.attrs);
+        let binding = Binding::new(Res::Def(kind, id), vis, name.span);
+        let ns = match kind {
+            DefKind::Fn | DefKind::Const | DefKind::Static => Namespace::Value,
+            _ => Namespace::Type,
+        };
+        self.define(scope, ns, name, binding.clone());
+        match &item.kind {
+            ItemKind::Struct { data, .. } => {
+                if let Some(ctor) = ctor_kind(data) {
+                    self.def_mut(id).ctor = Some(ctor);
+                    self.define(scope, Namespace::Value, name, binding);
+                }
+            }
+            ItemKind::Enum { variants, .. } => {
+                self.scopes.insert(id, Scope::default());
+                for variant in variants {
+                    let variant_id = self.define_def(
+                        DefKind::Variant,
+                        variant.name.node.as_str().to_string(),
+                        Some(id),
+                        Vis::Public,
+                        variant.name.span,
+                    );
+                    let ctor = ctor_kind(&variant.data);
+                    self.def_mut(variant_id).ctor = ctor;
+                    self.def_mut(variant_id).deprecated =
+                        attr::deprecation(&variant.attrs);
+                    let binding = Binding::new(
+                        Res::Def(DefKind::Variant, variant_id),
+                        Vis::Public,
+                        variant.name.span,
+                    );
+                    if ctor.is_some() {
+                        let name = &variant.name;
+                        self.define(
+                            id,
+                            Namespace::Value,
+                            name,
+                            binding.clone(),
+                        );
+                    }
+                    self.define(id, Namespace::Type, &variant.name, binding);
+                }
+            }
+            ItemKind::Trait { items, .. } => {
+                self.collect_assoc_items(items, id, Some(Vis::Public));
+            }
+            ItemKind::Mod { module, .. } => {
+                self.scopes.insert(id, Scope::default());
+                if let Some(module) = module {
+                    self.collect_items(&module.items, id);
+                }
+            }
+            _ => {}
+        }
+        self.collect_generics(item, id);
+    }
+
+    /// the items of a trait, which are public, or of an impl
+    fn collect_assoc_items(
+        &mut self, items: &[Item], parent: DefId, vis: Option<Vis>,
+    ) {
+        for item in items {
+            let (kind, name) = match &item.kind {
+                ItemKind::Fn(def) => (DefKind::AssocFn, &def.name),
+                ItemKind::Const { name, .. } => (DefKind::AssocConst, name),
+                ItemKind::TypeAlias { name, .. } => (DefKind::AssocTy, name),
+                _ => continue,
+            };
+            let vis = vis.unwrap_or_else(|| self.vis(&item.vis, parent));
+            let id = self.define_def(
+                kind,
+                name.node.as_str().to_string(),
+                Some(parent),
+                vis,
+                name.span,
+            );
+            self.res.items.insert(item.id, id);
+            self.def_mut(id).deprecated = attr::deprecation(&item.attrs);
+            self.collect_generics(item, id);
+        }
+    }
+
+    /// defines the generic parameters of `item` as children of `id`, after
+    /// its variants and associated items
+    fn collect_generics(&mut self, item: &Item, id: DefId) {
+        let Some(generics) = item.generics() else { return };
+        let mut seen = HashMap::<&str, Span>::new();
+        for param in &generics.params {
+            let name = param.name.node.as_str();
+            if let Some(first) = seen.insert(name, param.name.span) {
+                self.diagnostics.push(
+                    Diagnostic::error(format!(
+                        "the name `{name}` is already used for a generic \
+                         parameter in this item's generic parameters"
+                    ))
+                    .with_code("E0403")
+                    .with_primary(param.name.span, "already used")
+                    .with_secondary(first, format!("first use of `{name}`")),
+                );
+            }
+            let param_id = self.define_def(
+                DefKind::TyParam,
+                name.to_string(),
+                Some(id),
+                Vis::Public,
+                param.name.span,
+            );
+            self.res.items.insert(param.id, param_id);
+        }
+    }
+
+    /// flattens `tree` into [Import]s, e.g. `a::{b, c::*}` into `a::b` and
+    /// `a::c::*`
+    fn collect_use(
+        &mut self, tree: &UseTree, mut path: Vec<Ident>, scope: DefId, vis: Vis,
+    ) {
+        let nested = !path.is_empty();
+        path.extend(tree.prefix.segments.iter().map(|s| s.ident.clone()));
+        let kind = match &tree.kind {
+            UseTreeKind::Nested(trees) => {
+                for tree in trees {
+                    self.collect_use(tree, path.clone(), scope, vis);
+                }
+                return;
+            }
+            UseTreeKind::Glob => ImportKind::Glob,
+            UseTreeKind::Simple(rename) => {
+                let last = path.last().unwrap().clone();
+                let type_only = is_keyword(&last, "self");
+                if type_only {
+                    path.pop();
+                    if !nested || path.is_empty() {
+                        self.diagnostics.push(
+                            Diagnostic::error(
+                                "`self` imports are only allowed within a { } \
+                                 list",
+                            )
+                            .with_code("E0429")
+                            .with_primary(last.span, ""),
+                        );
+                        return;
+                    }
+                }
+                let name = rename.as_ref().unwrap_or(path.last().unwrap());
+                ImportKind::Single { name: name.clone(), type_only }
+            }
+        };
+        self.imports.push(Import { scope, path, kind, vis, span: tree.span });
+    }
+
+    /// resolves the imports from `start` on until none makes progress,
+    /// then reports the unresolved ones
+    fn resolve_imports(&mut self, start: usize) {
+        let mut pending: Vec<_> = (start..self.imports.len()).collect();
+        let mut globs = Vec::new();
+        loop {
+            let mut progress = false;
+            let mut unresolved = Vec::new();
+            for index in pending {
+                match self.resolve_import(index) {
+                    Ok(Some(target)) => {
+                        globs.push((index, target));
+                        progress = true;
+                    }
+                    Ok(None) => progress = true,
+                    Err(_) => unresolved.push(index),
+                }
+            }
+            pending = unresolved;
+            for &(index, target) in &globs {
+                progress |= self.apply_glob(index, target);
+            }
+            if !progress {
+                break;
+            }
+        }
+        for index in pending {
+            let Err(error) = self.resolve_import(index) else { continue };
+            let import = &self.imports[index];
+            let (scope, path, vis) =
+                (import.scope, import.path.clone(), import.vis);
+            // the name is defined anyway, so its uses are not reported
+            if let ImportKind::Single { name, type_only } = &import.kind {
+                let (name, type_only) = (name.clone(), *type_only);
+                let binding = Binding::new(Res::Err, vis, name.span);
+                self.define(scope, Namespace::Type, &name, binding.clone());
+                if !type_only {
+                    self.define(scope, Namespace::Value, &name, binding);
+                }
+            }
+            self.report(&path, Namespace::Type, error, Some(&path));
+        }
+    }
+
+    /// defines the names of a single import, or returns the module of a
+    /// glob import
+    fn resolve_import(
+        &mut self, index: usize,
+    ) -> Result<Option<DefId>, PathError> {
+        let import = &self.imports[index];
+        let (scope, vis, path) =
+            (import.scope, import.vis, import.path.clone());
+        match &import.kind {
+            ImportKind::Glob => {
+                let target = self.resolve_module_path(scope, &path)?;
+                if self.scopes.contains_key(&target) {
+                    return Ok(Some(target));
+                }
+                let res = Res::Def(self.res.def(target).kind, target);
+                Err(PathError::NotAModule(path.len(), res))
+            }
+            &ImportKind::Single { ref name, type_only } => {
+                let name = name.clone();
+                let (module, last) = match type_only {
+                    true => (path.len(), path.len() - 1),
+                    false => (path.len() - 1, path.len() - 1),
+                };
+                let namespaces: &[Namespace] = match type_only {
+                    true => &[Namespace::Type],
+                    false => &[Namespace::Type, Namespace::Value],
+                };
+                let found: Vec<_> = if type_only {
+                    let target = self.resolve_module_path(scope, &path)?;
+                    let def = self.res.def(target);
+                    let binding = Binding::new(
+                        Res::Def(def.kind, target),
+                        def.vis,
+                        def.span,
+                    );
+                    vec![(Namespace::Type, binding)]
+                } else if module == 0 {
+                    namespaces
+                        .iter()
+                        .filter_map(|ns| {
+                            let binding =
+                                self.lookup_scopes(scope, &path[last], *ns)?;
+                            Some((*ns, binding))
+                        })
+                        .collect()
+                } else {
+                    let target =
+                        self.resolve_module_path(scope, &path[..module])?;
+                    let mut found = Vec::new();
+                    for &ns in namespaces {
+                        let Some(binding) =
+                            self.lookup_in(target, &path[last], ns)
+                        else {
+                            continue;
+                        };
+                        if !self.res.is_accessible(binding.vis, scope) {
+                            return Err(PathError::Private(last, binding));
+                        }
+                        found.push((ns, binding));
+                    }
+                    found
+                };
+                if found.is_empty() {
+                    return Err(PathError::NotFound(last));
+                }
+                for (ns, binding) in found {
+                    let span = name.span;
+                    self.define(scope, ns, &name, Binding {
+                        vis,
+                        span,
+                        glob: false,
+                        ..binding
+                    });
+                }
+                Ok(None)
+            }
+        }
+    }
+
+    /// imports the accessible names of `target` that are not defined yet,
+    /// `true` if there are new ones
+    fn apply_glob(&mut self, index: usize, target: DefId) -> bool {
+        let (scope, vis, span) = {
+            let import = &self.imports[index];
+            (import.scope, import.vis, import.span)
+        };
+        let names: Vec<_> = self.scopes[&target]
+            .bindings
+            .iter()
+            .filter(|(_, binding)| self.res.is_accessible(binding.vis, scope))
+            .map(|(key, binding)| (key.clone(), binding.res))
+            .collect();
+        let mut progress = false;
+        for (key, res) in names {
+            let binding =
+                Binding { glob: true, ..Binding::new(res, vis, span) };
+            let bindings = &mut self.scopes.get_mut(&scope).unwrap().bindings;
+            match bindings.get_mut(&key) {
+                None => {
+                    bindings.insert(key, binding);
+                    progress = true;
+                }
+                Some(previous)
+                    if previous.glob
+                        && previous.res != res
+                        && previous.ambiguity.is_none() =>
+                {
+                    previous.ambiguity = Some(Box::new(binding));
+                    progress = true;
+                }
+                Some(_) => {}
+            }
+        }
+        progress
+    }
+
+    /// `name` in `scope` itself
+    fn lookup_in(
+        &self, scope: DefId, name: &Ident, ns: Namespace,
+    ) -> Option<Binding> {
+        let key = (ns, name.node.as_str().to_string());
+        self.scopes.get(&scope)?.bindings.get(&key).cloned()
+    }
+
+    /// `name` in `scope` and its enclosing blocks, up to the module
+    fn lookup_scopes(
+        &self, mut scope: DefId, name: &Ident, ns: Namespace,
+    ) -> Option<Binding> {
+        loop {
+            if let Some(binding) = self.lookup_in(scope, name, ns) {
+                return Some(binding);
+            }
+            if self.res.def(scope).kind == DefKind::Mod {
+                return None;
+            }
+            scope = self.res.def(scope).parent?;
+        }
+    }
+
+    /// the module or enum `path` names, relative to `scope`
+    fn resolve_module_path(
+        &self, scope: DefId, path: &[Ident],
+    ) -> Result<DefId, PathError> {
+        let mut module = scope;
+        for (index, segment) in path.iter().enumerate() {
+            let binding = match segment.node.as_str() {
+                "crate" if index == 0 && !segment.node.is_raw() => {
+                    module = self.res.root();
+                    continue;
+                }
+                "self" if index == 0 && !segment.node.is_raw() => {
+                    module = self.res.module(scope);
+                    continue;
+                }
+                "super"
+                    if !segment.node.is_raw()
+                        && path[..index]
+                            .iter()
+                            .all(|s| is_keyword(s, "super")) =>
+                {
+                    let parent = self.res.def(self.res.module(module)).parent;
+                    match parent {
+                        Some(parent) => module = self.res.module(parent),
+                        None => return Err(PathError::TooManySupers(index)),
+                    }
+                    continue;
+                }
+                _ if index == 0 => {
+                    self.lookup_scopes(scope, segment, Namespace::Type)
+                }
+                _ => {
+                    let binding =
+                        self.lookup_in(module, segment, Namespace::Type);
+                    if let Some(binding) = &binding
+                        && !self.res.is_accessible(binding.vis, scope)
+                    {
+                        return Err(PathError::Private(index, binding.clone()));
+                    }
+                    binding
+                }
+            };
+            let binding = binding.ok_or(PathError::NotFound(index))?;
+            if binding.ambiguity.is_some() {
+                return Err(PathError::Ambiguous(index, binding));
+            }
+            module = match binding.res {
+                Res::Def(DefKind::Mod | DefKind::Enum, id) => id,
+                res => return Err(PathError::NotAModule(index + 1, res)),
+            };
+        }
+        Ok(module)
+    }
+
+    /// the innermost module or block
+    fn current_scope(&self) -> DefId {
+        self.ribs.iter().rev().find_map(|rib| rib.scope).unwrap()
+    }
+
+    /// `name` from the innermost rib on up to the module, locals before
+    /// items, then the builtin types
+    fn lookup_lexical(&self, name: &Ident, ns: Namespace) -> Option<Binding> {
+        let mut locals = ns == Namespace::Value;
+        // the generic parameters of the items around the block of an item
+        // are out of reach
+        let mut params = ns == Namespace::Type;
+        let mut in_item = false;
+        for rib in self.ribs.iter().rev() {
+            if locals && let Some(&id) = rib.locals.get(&local_key(name)) {
+                return Some(Binding::new(
+                    Res::Local(id),
+                    Vis::Public,
+                    name.span,
+                ));
+            }
+            if params && let Some(&id) = rib.params.get(name.node.as_str()) {
+                let res = Res::Def(DefKind::TyParam, id);
+                return Some(Binding::new(res, Vis::Public, name.span));
+            }
+            if let Some(scope) = rib.scope {
+                if let Some(binding) = self.lookup_in(scope, name, ns) {
+                    return Some(binding);
+                }
+                match self.res.def(scope).kind {
+                    DefKind::Mod => break,
+                    _ => params &= !in_item,
+                }
+            }
+            locals &= !rib.item;
+            in_item |= rib.item;
+        }
+        let ty = PrimTy::from_name(name.node.as_str())
+            .filter(|_| ns == Namespace::Type)?;
+        Some(Binding::new(Res::PrimTy(ty), Vis::Public, name.span))
+    }
+
+    /// the binding of the segment at `index`, unless it is ambiguous
+    fn expect_binding(
+        &self, binding: Option<Binding>, index: usize,
+    ) -> Result<Binding, PathError> {
+        match binding {
+            None => Err(PathError::NotFound(index)),
+            Some(binding) if binding.ambiguity.is_some() => {
+                Err(PathError::Ambiguous(index, binding))
+            }
+            Some(binding) => Ok(binding),
+        }
+    }
+
+    /// resolves `path` from the current scope, the segments after a type
+    /// or a trait are left to the type checker, e.g. `new` of `Vec::new`
+    fn resolve_segments(
+        &self, path: &[Ident], ns: Namespace,
+    ) -> Result<PartialRes, PathError> {
+        let scope = self.current_scope();
+        let mut res = None;
+        for (index, segment) in path.iter().enumerate() {
+            let partial = PartialRes {
+                base:       Res::Err,
+                unresolved: path.len() - index,
+            };
+            let ns = if index + 1 == path.len() { ns } else { Namespace::Type };
+            let keyword =
+                (!segment.node.is_raw()).then(|| segment.node.as_str());
+            let next = match (res, keyword) {
+                (None, Some("crate")) => {
+                    Res::Def(DefKind::Mod, self.res.root())
+                }
+                (None, Some("self"))
+                    if path.len() == 1 && ns == Namespace::Value =>
+                {
+                    Res::SelfParam(
+                        self.self_param.ok_or(PathError::NoSelfParam)?,
+                    )
+                }
+                (None, Some("self")) => {
+                    Res::Def(DefKind::Mod, self.res.module(scope))
+                }
+                (None, Some("Self")) => {
+                    Res::SelfTy(self.self_ty.ok_or(PathError::NoSelfTy(index))?)
+                }
+                (None | Some(Res::Def(DefKind::Mod, _)), Some("super"))
+                    if path[..index].iter().all(|s| is_keyword(s, "super")) =>
+                {
+                    let module = match res {
+                        Some(Res::Def(_, module)) => module,
+                        _ => self.res.module(scope),
+                    };
+                    let parent = self.res.def(module).parent;
+                    let parent =
+                        parent.ok_or(PathError::TooManySupers(index))?;
+                    Res::Def(DefKind::Mod, self.res.module(parent))
+                }
+                // a name of the other namespace is reported as unexpected
+                (None, _) => {
+                    let binding = self
+                        .lookup_lexical(segment, ns)
+                        .or_else(|| self.lookup_lexical(segment, ns.other()));
+                    self.expect_binding(binding, index)?.res
+                }
+                (
+                    Some(base @ Res::Def(DefKind::Mod | DefKind::Enum, module)),
+                    _,
+                ) => {
+                    let binding =
+                        self.lookup_in(module, segment, ns).or_else(|| {
+                            self.lookup_in(module, segment, ns.other())
+                        });
+                    match binding {
+                        Some(binding)
+                            if !self.res.is_accessible(binding.vis, scope) =>
+                        {
+                            return Err(PathError::Private(index, binding));
+                        }
+                        Some(binding) => {
+                            self.expect_binding(Some(binding), index)?.res
+                        }
+                        // e.g. `E::new`
+                        None if self.res.def(module).kind == DefKind::Enum => {
+                            return Ok(PartialRes { base, ..partial });
+                        }
+                        None => return Err(PathError::NotFound(index)),
+                    }
+                }
+                (
+                    Some(
+                        base @ (Res::Def(
+                            DefKind::Struct
+                            | DefKind::Union
+                            | DefKind::Trait
+                            | DefKind::TypeAlias
+                            | DefKind::AssocTy
+                            | DefKind::TyParam,
+                            _,
+                        )
+                        | Res::PrimTy(_)
+                        | Res::SelfTy(_)),
+                    ),
+                    _,
+                ) => return Ok(PartialRes { base, ..partial }),
+                (Some(res), _) => {
+                    return Err(PathError::NotAModule(index, res));
+                }
+            };
+            res = Some(next);
+        }
+        Ok(PartialRes::new(res.unwrap()))
+    }
+
+    /// `res` is what `source` expects, e.g. a type for [PathSource::Type]
+    fn is_expected(&self, source: PathSource, res: Res) -> bool {
+        let ctor = res.def_id().and_then(|id| self.res.def(id).ctor);
+        match (source, res) {
+            (_, Res::Err) => true,
+            (PathSource::Type, Res::Def(kind, _)) => matches!(
+                kind,
+                DefKind::Struct
+                    | DefKind::Enum
+                    | DefKind::Union
+                    | DefKind::TypeAlias
+                    | DefKind::AssocTy
+                    | DefKind::TyParam
+            ),
+            (PathSource::Type, res) => {
+                matches!(res, Res::PrimTy(_) | Res::SelfTy(_))
+            }
+            (PathSource::Trait, res) => {
+                matches!(res, Res::Def(DefKind::Trait, _))
+            }
+            (PathSource::Expr, Res::Def(kind, _)) => {
+                ctor.is_some()
+                    || matches!(
+                        kind,
+                        DefKind::Fn
+                            | DefKind::Const
+                            | DefKind::Static
+                            | DefKind::AssocFn
+                            | DefKind::AssocConst
+                    )
+            }
+            (PathSource::Expr, res) => matches!(
+                res,
+                Res::Local(_) | Res::SelfParam(_) | Res::SelfTy(_)
+            ),
+            (PathSource::Struct, res) => matches!(
+                res,
+                Res::Def(
+                    DefKind::Struct
+                        | DefKind::Variant
+                        | DefKind::Union
+                        | DefKind::TypeAlias
+                        | DefKind::AssocTy,
+                    _
+                ) | Res::SelfTy(_)
+            ),
+            (PathSource::Pat, Res::Def(kind, _)) => {
+                ctor == Some(CtorKind::Const)
+                    || matches!(kind, DefKind::Const | DefKind::AssocConst)
+            }
+            (PathSource::TupleStruct, Res::Def(..)) => {
+                ctor == Some(CtorKind::Fn)
+            }
+            (PathSource::Pat | PathSource::TupleStruct, res) => {
+                matches!(res, Res::SelfTy(_))
+            }
+        }
+    }
+
+    /// the generic parameters of `id` by their names
+    fn generic_params(&self, id: DefId) -> HashMap<String, DefId> {
+        let children = self.res.def(id).children.iter().copied();
+        children
+            .filter(|child| self.res.def(*child).kind == DefKind::TyParam)
+            .map(|child| (self.res.def(child).name.clone(), child))
+            .collect()
+    }
+
+    /// records what `path` refers to, [Res::Err] after reporting why it
+    /// cannot be resolved
+    fn resolve_path(&mut self, path: &mut Path, source: PathSource) {
+        let segments: Vec<_> =
+            path.segments.iter().map(|s| s.ident.clone()).collect();
+        let ns = source.namespace();
+        let res = match self.resolve_segments(&segments, ns) {
+            Ok(res)
+                if res.unresolved > 0 || self.is_expected(source, res.base) =>
+            {
+                res
+            }
+            Ok(res) => {
+                let name = path_to_string(&segments);
+                let mut diagnostic = Diagnostic::error(format!(
+                    "expected {}, found {} `{name}`",
+                    source.descr(),
+                    res.base.descr()
+                ))
+                .with_primary(path.span, format!("not a {}", source.descr()));
+                if let Some(id) = res.base.def_id() {
+                    diagnostic = diagnostic.with_secondary(
+                        self.res.def(id).span,
+                        format!("`{name}` defined here"),
+                    );
+                }
+                self.diagnostics.push(diagnostic);
+                PartialRes::new(Res::Err)
+            }
+            Err(error) => {
+                self.report(&segments, ns, error, None);
+                PartialRes::new(Res::Err)
+            }
+        };
+        if let Some(id) = res.full_res().and_then(|res| res.def_id())
+            && let Some(deprecation) = &self.res.def(id).deprecated
+        {
+            let name = path_to_string(&segments);
+            let descr = self.res.def(id).kind.descr();
+            self.diagnostics.push(deprecation.warning(descr, &name, path.span));
+        }
+        self.res.paths.insert(path.id, res);
+        visit::walk_path(self, path);
+    }
+
+    /// reports why `path` cannot be resolved, `import` is the path of the
+    /// `use` item it comes from
+    fn report(
+        &mut self, path: &[Ident], ns: Namespace, error: PathError,
+        import: Option<&[Ident]>,
+    ) {
+        let place = |index: usize| match index {
+            0 => "this scope".to_string(),
+            _ => format!("`{}`", path_to_string(&path[..index])),
+        };
+        let diagnostic = match error {
+            PathError::NotFound(index) => {
+                let name = &path[index].node;
+                let (message, code) = match import {
+                    Some(import) => (
+                        format!(
+                            "unresolved import `{}`",
+                            path_to_string(import)
+                        ),
+                        "E0432",
+                    ),
+                    None if index + 1 == path.len() => (
+                        format!(
+                            "cannot find {} `{name}` in {}",
+                            ns.descr(),
+                            place(index)
+                        ),
+                        match ns {
+                            Namespace::Type => "E0412",
+                            Namespace::Value => "E0425",
+                        },
+                    ),
+                    None => (
+                        format!(
+                            "failed to resolve: could not find `{name}` in {}",
+                            place(index)
+                        ),
+                        "E0433",
+                    ),
+                };
+                Diagnostic::error(message).with_code(code).with_primary(
+                    path[index].span,
+                    format!("no `{name}` in {}", place(index)),
+                )
+            }
+            PathError::Private(index, binding) => {
+                let name = &path[index].node;
+                let descr = binding.res.descr();
+                Diagnostic::error(format!("{descr} `{name}` is private"))
+                    .with_code("E0603")
+                    .with_primary(path[index].span, format!("private {descr}"))
+                    .with_secondary(
+                        binding.span,
+                        format!("the {descr} `{name}` is defined here"),
+                    )
+            }
+            PathError::NotAModule(len, res) => Diagnostic::error(format!(
+                "expected module, found {} `{}`",
+                res.descr(),
+                path_to_string(&path[..len])
+            ))
+            .with_primary(path[len - 1].span, "not a module"),
+            PathError::TooManySupers(index) => {
+                Diagnostic::error("too many leading `super` keywords")
+                    .with_code("E0433")
+                    .with_primary(
+                        path[index].span,
+                        "there are too many leading `super` keywords",
+                    )
+            }
+            PathError::NoSelfTy(index) => Diagnostic::error(
+                "failed to resolve: `Self` is only available in impls, \
+                 traits, and type definitions",
+            )
+            .with_code("E0433")
+            .with_primary(path[index].span, "`Self` is only available here"),
+            PathError::NoSelfParam => {
+                Diagnostic::error("expected value, found module `self`")
+                    .with_code("E0424")
+                    .with_primary(path[0].span, "not a value")
+                    .with_note(
+                        "`self` value is a keyword only available in methods \
+                         with a `self` parameter",
+                    )
+            }
+            PathError::Ambiguous(index, binding) => {
+                let name = &path[index].node;
+                let mut diagnostic =
+                    Diagnostic::error(format!("`{name}` is ambiguous"))
+                        .with_code("E0659")
+                        .with_primary(path[index].span, "ambiguous name")
+                        .with_secondary(
+                            binding.span,
+                            format!(
+                                "`{name}` could refer to the {} imported here",
+                                binding.res.descr()
+                            ),
+                        );
+                if let Some(other) = binding.ambiguity {
+                    diagnostic = diagnostic.with_secondary(
+                        other.span,
+                        format!(
+                            "`{name}` could also refer to the {} imported here",
+                            other.res.descr()
+                        ),
+                    );
+                }
+                diagnostic.with_help(format!(
+                    "import `{name}` explicitly or use a qualified path to \
+                     disambiguate"
+                ))
+            }
+        };
+        self.diagnostics.push(diagnostic);
+    }
+
+    /// resolves the paths of `pat` and collects its bindings, `first` are
+    /// the bindings of the first alternative of an enclosing `|` pattern,
+    /// which the other alternatives bind again
+    fn resolve_pat(
+        &mut self, pat: &mut Pat, bindings: &mut Bindings,
+        first: Option<&Bindings>,
+    ) {
+        match &mut pat.kind {
+            PatKind::Wild | PatKind::Rest | PatKind::Literal(_) => {}
+            PatKind::Ident { by_ref, mutable, name, sub } => {
+                // e.g. `None` or a constant, which is not a binding
+                if !*by_ref
+                    && !*mutable
+                    && sub.is_none()
+                    && let Some(binding) =
+                        self.lookup_lexical(name, Namespace::Value)
+                    && binding.res != Res::Err
+                    && self.is_expected(PathSource::Pat, binding.res)
+                {
+                    self.res.paths.insert(pat.id, PartialRes::new(binding.res));
+                    return;
+                }
+                let key = local_key(name);
+                let rebound = first.and_then(|first| first.get(&key)).copied();
+                match bindings.entry(key) {
+                    Entry::Occupied(_) => self.diagnostics.push(
+                        Diagnostic::error(format!(
+                            "identifier `{}` is bound more than once in the \
+                             same pattern",
+                            name.node
+                        ))
+                        .with_code("E0416")
+                        .with_primary(
+                            name.span,
+                            "used in a pattern more than once",
+                        ),
+                    ),
+                    Entry::Vacant(entry) => {
+                        let id = match rebound {
+                            Some((id, _)) => {
+                                let res = PartialRes::new(Res::Local(id));
+                                self.res.paths.insert(pat.id, res);
+                                id
+                            }
+                            None => pat.id,
+                        };
+                        entry.insert((id, name.span));
+                    }
+                }
+                if let Some(sub) = sub {
+                    self.resolve_pat(sub, bindings, first);
+                }
+            }
+            PatKind::Path(path) => self.resolve_path(path, PathSource::Pat),
+            PatKind::TupleStruct(path, pats) => {
+                self.resolve_path(path, PathSource::TupleStruct);
+                for pat in pats {
+                    self.resolve_pat(pat, bindings, first);
+                }
+            }
+            PatKind::Struct { path, fields, .. } => {
+                self.resolve_path(path, PathSource::Struct);
+                for field in fields {
+                    self.resolve_pat(&mut field.pat, bindings, first);
+                }
+            }
+            PatKind::Tuple(pats) | PatKind::Slice(pats) => {
+                for pat in pats {
+                    self.resolve_pat(pat, bindings, first);
+                }
+            }
+            PatKind::Range { start, end, .. } => {
+                for end in [start, end].into_iter().flatten() {
+                    self.resolve_pat(end, bindings, first);
+                }
+            }
+            PatKind::Ref { pat, .. } => self.resolve_pat(pat, bindings, first),
+            PatKind::Or(pats) => {
+                let before = bindings.clone();
+                let mut first_alt: Option<(Bindings, Span)> = None;
+                for alt in pats {
+                    let mut alt_bindings = before.clone();
+                    let outer = first_alt.as_ref().map(|(first, _)| first);
+                    self.resolve_pat(alt, &mut alt_bindings, outer.or(first));
+                    alt_bindings.retain(|key, _| !before.contains_key(key));
+                    let Some((first_alt, first_span)) = &first_alt else {
+                        first_alt = Some((alt_bindings, alt.span));
+                        continue;
+                    };
+                    // the binding and the alternative without it
+                    let mut missing: Vec<_> = first_alt
+                        .iter()
+                        .filter(|(key, _)| !alt_bindings.contains_key(key))
+                        .map(|(key, &(_, span))| (key, span, alt.span))
+                        .chain(
+                            alt_bindings
+                                .iter()
+                                .filter(|(key, _)| !first_alt.contains_key(key))
+                                .map(|(key, &(_, span))| {
+                                    (key, span, *first_span)
+                                }),
+                        )
+                        .collect();
+                    missing.sort_by_key(|(key, ..)| key.as_str().to_string());
+                    for (key, span, alt) in missing {
+                        let name = key.as_str();
+                        self.diagnostics.push(
+                            Diagnostic::error(format!(
+                                "variable `{name}` is not bound in all \
+                                 patterns"
+                            ))
+                            .with_code("E0408")
+                            .with_primary(
+                                alt,
+                                format!("pattern doesn't bind `{name}`"),
+                            )
+                            .with_secondary(
+                                span,
+                                "variable not in all patterns",
+                            ),
+                        );
+                    }
+                }
+                bindings.extend(
+                    first_alt.map(|(first, _)| first).unwrap_or_default(),
+                );
+            }
+        }
+    }
+
+    /// binds the names of `pat` in the innermost rib
+    fn bind_pat(&mut self, pat: &mut Pat) {
+        let mut bindings = Bindings::new();
+        self.resolve_pat(pat, &mut bindings, None);
+        self.bind(bindings);
+    }
+
+    fn bind(&mut self, bindings: Bindings) {
+        let rib = self.ribs.last_mut().unwrap();
+        rib.locals.extend(bindings.into_iter().map(|(key, (id, _))| (key, id)));
+    }
+
+    /// `f` inside a loop labeled `label`
+    fn with_label(&mut self, label: &Option<Label>, f: impl FnOnce(&mut Self)) {
+        let len = self.labels.len();
+        self.labels.extend(label.clone());
+        f(self);
+        self.labels.truncate(len);
+    }
+
+    /// reports `label` of a `break` or a `continue` outside of its loop
+    fn check_label(&mut self, label: &Option<Label>) {
+        let Some(label) = label else { return };
+        if !self.labels.iter().any(|outer| outer.node == label.node) {
+            let name = &label.node;
+            self.diagnostics.push(
+                Diagnostic::error(format!("use of undeclared label `'{name}`"))
+                    .with_code("E0426")
+                    .with_primary(
+                        label.span,
+                        format!("undeclared label `'{name}`"),
+                    ),
+            );
+        }
+    }
+}
+impl MutVisitor for Resolver {
+    fn visit_item(&mut self, item: &mut Item) {
+        let id = self.res.item(item.id);
+        let self_ty = self.self_ty;
+        let self_param = self.self_param.take();
+        let labels = std::mem::take(&mut self.labels);
+        let params = id.map(|id| self.generic_params(id)).unwrap_or_default();
+        self.ribs.push(Rib { item: true, params, ..Rib::default() });
+        match &mut item.kind {
+            ItemKind::Fn(def) => {
+                let assoc = id.is_some_and(|id| {
+                    self.res.def(id).kind == DefKind::AssocFn
+                });
+                match def.sig.self_param {
+                    Some(_) if assoc => self.self_param = id,
+                    Some(param) => self.diagnostics.push(
+                        Diagnostic::error(
+                            "`self` parameter is only allowed in associated \
+                             functions",
+                        )
+                        .with_primary(
+                            param.span,
+                            "not semantically valid as function parameter",
+                        )
+                        .with_note(
+                            "associated functions are those in `impl` or \
+                             `trait` definitions",
+                        ),
+                    ),
+                    None => {}
+                }
+                self.visit_generics(&mut def.generics);
+                let mut bindings = Bindings::new();
+                for param in &mut def.sig.params {
+                    self.visit_ty(&mut param.ty);
+                    self.resolve_pat(&mut param.pat, &mut bindings, None);
+                }
+                self.bind(bindings);
+                if let Some(ret) = &mut def.sig.ret {
+                    self.visit_ty(ret);
+                }
+                if let Some(body) = &mut def.body {
+                    self.visit_block(body);
+                }
+            }
+            ItemKind::Mod { module, .. } => {
+                self.ribs.last_mut().unwrap().scope = id;
+                if let Some(module) = module {
+                    self.visit_module(module);
+                }
+            }
+            ItemKind::Impl { generics, of_trait, self_ty, items } => {
+                self.visit_generics(generics);
+                self.visit_ty(self_ty);
+                if let Some(of_trait) = of_trait {
+                    self.resolve_path(of_trait, PathSource::Trait);
+                }
+                self.self_ty = id;
+                self.visit_items(items);
+            }
+            ItemKind::Struct { .. }
+            | ItemKind::Enum { .. }
+            | ItemKind::Union { .. }
+            | ItemKind::Trait { .. } => {
+                self.self_ty = id;
+                visit::walk_item(self, item);
+            }
+            // resolved by `resolve_imports`, or expanded
+            ItemKind::Use(_) | ItemKind::Marco(_) | ItemKind::MacCall(_) => {}
+            _ => visit::walk_item(self, item),
+        }
+        self.ribs.pop();
+        self.self_ty = self_ty;
+        self.self_param = self_param;
+        self.labels = labels;
+    }
+
+    fn visit_block(&mut self, block: &mut Block) {
+        let items: Vec<_> = block
+            .stmts
+            .iter()
+            .filter_map(|stmt| match &stmt.kind {
+                StmtKind::Item(item) => Some(&**item),
+                _ => None,
+            })
+            .collect();
+        let mut scope = None;
+        if !items.is_empty() {
+            let parent = self.current_scope();
+            let vis = Vis::Restricted(self.res.module(parent));
+            let id = self.define_def(
+                DefKind::Block,
+                "{block}".to_string(),
+                Some(parent),
+                vis,
+                block.span,
+            );
+            self.scopes.insert(id, Scope::default());
+            let start = self.imports.len();
+            self.collect_items(items, id);
+            self.resolve_imports(start);
+            scope = Some(id);
+        }
+        self.ribs.push(Rib { scope, ..Rib::default() });
+        visit::walk_block(self, block);
+        self.ribs.pop();
+    }
+
+    fn visit_stmt(&mut self, stmt: &mut Stmt) {
+        let StmtKind::Let(local) = &mut stmt.kind else {
+            return visit::walk_stmt(self, stmt);
+        };
+        if let Some(ty) = &mut local.ty {
+            self.visit_ty(ty);
+        }
+        if let Some(init) = &mut local.init {
+            self.visit_expr(init);
+        }
+        if let Some(els) = &mut local.els {
+            self.visit_block(els);
+        }
+        self.bind_pat(&mut local.pat);
+    }
+
+    fn visit_expr(&mut self, expr: &mut Expr) {
+        match &mut expr.kind {
+            ExprKind::Path(path) => self.resolve_path(path, PathSource::Expr),
+            ExprKind::Struct { path, fields, rest } => {
+                self.resolve_path(path, PathSource::Struct);
+                for field in fields {
+                    self.visit_expr(&mut field.expr);
+                }
+                if let Some(rest) = rest {
+                    self.visit_expr(rest);
+                }
+            }
+            ExprKind::Match { scrutinee, arms } => {
+                self.visit_expr(scrutinee);
+                for arm in arms {
+                    self.ribs.push(Rib::default());
+                    self.bind_pat(&mut arm.pat);
+                    if let Some(guard) = &mut arm.guard {
+                        self.visit_expr(guard);
+                    }
+                    self.visit_expr(&mut arm.body);
+                    self.ribs.pop();
+                }
+            }
+            ExprKind::Loop { label, body } => {
+                self.with_label(label, |this| this.visit_block(body));
+            }
+            ExprKind::While { label, cond, body } => {
+                self.with_label(label, |this| {
+                    this.visit_expr(cond);
+                    this.visit_block(body);
+                });
+            }
+            ExprKind::For { label, pat, iter, body } => {
+                self.visit_expr(iter);
+                self.ribs.push(Rib::default());
+                self.bind_pat(pat);
+                self.with_label(label, |this| this.visit_block(body));
+                self.ribs.pop();
+            }
+            ExprKind::Break { label, value } => {
+                self.check_label(label);
+                if let Some(value) = value {
+                    self.visit_expr(value);
+                }
+            }
+            ExprKind::Continue { label } => self.check_label(label),
+            _ => visit::walk_expr(self, expr),
+        }
+    }
+
+    fn visit_generics(&mut self, generics: &mut Generics) {
+        for param in &mut generics.params {
+            for bound in &mut param.bounds {
+                self.resolve_path(bound, PathSource::Trait);
+            }
+        }
+        for predicate in &mut generics.where_clause {
+            self.visit_ty(&mut predicate.ty);
+            for bound in &mut predicate.bounds {
+                self.resolve_path(bound, PathSource::Trait);
+            }
+        }
+    }
+
+    /// a pattern outside of a `let`, a parameter, an arm or a `for` loop
+    fn visit_pat(&mut self, pat: &mut Pat) { self.bind_pat(pat) }
+
+    fn visit_ty(&mut self, ty: &mut Type) {
+        match &mut ty.kind {
+            TypeKind::Path(path) => self.resolve_path(path, PathSource::Type),
+            TypeKind::TraitObject(paths) | TypeKind::ImplTrait(paths) => {
+                for path in paths {
+                    self.resolve_path(path, PathSource::Trait);
+                }
+            }
+            _ => visit::walk_ty(self, ty),
+        }
+    }
+}
+impl Binding {
+    fn new(res: Res, vis: Vis, span: Span) -> Self {
+        Self { res, vis, span, glob: false, ambiguity: None }
+    }
+}
+impl PathSource {
+    fn namespace(&self) -> Namespace {
+        match self {
+            PathSource::Type | PathSource::Trait | PathSource::Struct => {
+                Namespace::Type
+            }
+            PathSource::Expr | PathSource::Pat | PathSource::TupleStruct => {
+                Namespace::Value
+            }
+        }
+    }
+
+    /// what is expected, e.g. `type`
+    fn descr(&self) -> &'static str {
+        match self {
+            PathSource::Type => "type",
+            PathSource::Trait => "trait",
+            PathSource::Expr => "value",
+            PathSource::Struct => "struct, variant or union type",
+            PathSource::Pat => "unit struct, unit variant or constant",
+            PathSource::TupleStruct => "tuple struct or tuple variant",
+        }
+    }
+}
+/// the constructor of a struct or a variant with `data`
+fn ctor_kind(data: &VariantData) -> Option<CtorKind> {
+    match data {
+        VariantData::Struct(_) => None,
+        VariantData::Tuple(_) => Some(CtorKind::Fn),
+        VariantData::Unit => Some(CtorKind::Const),
+    }
+}
+/// `ident` is the keyword `keyword`, e.g. `self` but not `r#self`
+fn is_keyword(ident: &Ident, keyword: &str) -> bool {
+    !ident.node.is_raw() && ident.node.as_str() == keyword
+}
+/// `r#a` and `a` are the same local, unless their marks differ
+fn local_key(name: &Ident) -> Identifier {
+    Identifier::new(name.node.as_str()).with_mark(name.node.mark())
+}
+/// e.g. `a::b`
+fn path_to_string(path: &[Ident]) -> String {
+    let names: Vec<_> =
+        path.iter().map(|ident| ident.node.to_string()).collect();
+    names.join("::")
+}
+#[cfg(test)]
+mod tests {
+    use std::path::Path as FilePath;
+
+    use super::*;
+    use crate::{
+        attr::cfg::Cfg,
+        semantic::{parse_crate, tests::MemoryLoader},
+        utils::span::SourceMap,
+    };
+    /// the paths outside of `use` items and the identifier patterns, in
+    /// the order of the source
+    #[derive(Default)]
+    struct Nodes {
+        names: Vec<(String, NodeId)>,
+        pats:  Vec<NodeId>,
+    }
+    impl MutVisitor for Nodes {
+        fn visit_item(&mut self, item: &mut Item) {
+            if !matches!(item.kind, ItemKind::Use(_)) {
+                visit::walk_item(self, item);
+            }
+        }
+
+        fn visit_pat(&mut self, pat: &mut Pat) {
+            if let PatKind::Ident { name, .. } = &pat.kind {
+                self.names.push((name.node.to_string(), pat.id));
+                self.pats.push(pat.id);
+            }
+            visit::walk_pat(self, pat);
+        }
+
+        fn visit_path(&mut self, path: &mut Path) {
+            let segments: Vec<_> =
+                path.segments.iter().map(|s| s.ident.clone()).collect();
+            self.names.push((path_to_string(&segments), path.id));
+            visit::walk_path(self, path);
+        }
+    }
+    /// the crate of `files`, the first is the root
+    fn resolve(
+        files: &[(&str, &str)],
+    ) -> (Module, Resolutions, Vec<Diagnostic>) {
+        let loader = MemoryLoader::new(files);
+        let mut source_map = SourceMap::new();
+        let root = FilePath::new(files[0].0);
+        let (module, diagnostics) =
+            parse_crate(root, &loader, &mut source_map, &Cfg::new());
+        assert_eq!(diagnostics, []);
+        let mut module = module.unwrap();
+        let mut resolver = Resolver::new();
+        let res = resolver.resolve_crate(&mut module);
+        (module, res, resolver.take_diagnostics())
+    }
+    /// every path, and every identifier pattern that is not a new binding,
+    /// with what it refers to, e.g. `a -> crate::a`, locals are numbered
+    /// by their identifier pattern
+    fn paths(files: &[(&str, &str)]) -> Vec<String> {
+        let (mut module, res, diagnostics) = resolve(files);
+        assert_eq!(diagnostics, []);
+        let mut nodes = Nodes::default();
+        nodes.visit_module(&mut module);
+        let mut paths = Vec::new();
+        for (name, id) in &nodes.names {
+            let Some(partial) = res.path(*id) else { continue };
+            let mut target = match partial.base {
+                Res::Def(_, id) => res.def_path(id),
+                Res::PrimTy(ty) => ty.as_str().to_string(),
+                Res::SelfTy(id) => format!("Self of {}", res.def_path(id)),
+                Res::SelfParam(_) => "self".to_string(),
+                Res::Local(id) => {
+                    let index = nodes.pats.iter().position(|pat| *pat == id);
+                    format!("local #{}", index.unwrap())
+                }
+                Res::Err => "error".to_string(),
+            };
+            if partial.unresolved > 0 {
+                target += &format!(" + {}", partial.unresolved);
+            }
+            paths.push(format!("{name} -> {target}"));
+        }
+        paths
+    }
+    fn errors(files: &[(&str, &str)]) -> Vec<String> {
+        let (_, _, diagnostics) = resolve(files);
+        diagnostics
+            .iter()
+            .map(|diagnostic| {
+                let label = diagnostic
+                    .labels()
+                    .iter()
+                    .find(|label| label.is_primary())
+                    .map(|label| label.message())
+                    .unwrap_or_default();
+                format!("{}: {label}", diagnostic.message())
+            })
+            .collect()
+    }
+    #[test]
+    fn modules_and_imports() {
+        assert_eq!(
+            paths(&[
+                (
+                    "main.aau",
+                    "mod shapes;
+                    mod util {
+                        pub fn area() -> i32 { super::shapes::AREA }
+                    }
+                    use shapes::{self as sh, Circle as C, kinds::*};
+                    use util::area;
+                    fn main() {
+                        let c: C = C { r: 1 };
+                        let s = Square;
+                        area();
+                        crate::util::area();
+                        util::area();
+                        self::main();
+                        let k = Kind::Big;
+                        let n = i32::MAX + sh::AREA;
+                    }",
+                ),
+                (
+                    "shapes.aau",
+                    "pub struct Circle { pub r: i32 }
+                    pub const AREA: i32 = 1;
+                    pub mod kinds;",
+                ),
+                (
+                    "shapes/kinds.aau",
+                    "pub struct Square;
+                    pub enum Kind { Big, Small(i32) }
+                    impl Kind { fn new() -> Self { Self::Big } }",
+                ),
+            ]),
+            [
+                "i32 -> i32",
+                "i32 -> i32",
+                "i32 -> i32",
+                "Kind -> crate::shapes::kinds::Kind",
+                "Self -> Self of crate::shapes::kinds::{impl}",
+                "Self::Big -> Self of crate::shapes::kinds::{impl} + 1",
+                "i32 -> i32",
+                "super::shapes::AREA -> crate::shapes::AREA",
+                "C -> crate::shapes::Circle",
+                "C -> crate::shapes::Circle",
+                "Square -> crate::shapes::kinds::Square",
+                "area -> crate::util::area",
+                "crate::util::area -> crate::util::area",
+                "util::area -> crate::util::area",
+                "self::main -> crate::main",
+                "Kind::Big -> crate::shapes::kinds::Kind::Big",
+                "i32::MAX -> i32 + 1",
+                "sh::AREA -> crate::shapes::AREA",
+            ]
+        );
+    }
+    #[test]
+    fn locals_and_patterns() {
+        assert_eq!(
+            paths(&[(
+                "main.aau",
+                "enum E { A, B(i32), C { c: i32 } }
+                use E::*;
+                const K: i32 = 1;
+                struct S { v: i32 }
+                impl S { fn get(&self) -> i32 { self.v } }
+                marco twice { ($e:expr) => {{ let x = 2; $e * x }} }
+                fn f(x: i32, (y, z): (i32, i32)) -> i32 {
+                    let x = match B(y) {
+                        A => x,
+                        B(a) | C { c: a } => a,
+                        K => z,
+                        k => k,
+                    };
+                    fn inner() -> i32 { K }
+                    'outer: loop { break 'outer inner(); }
+                    twice!(x)
+                }",
+            )]),
+            [
+                "i32 -> i32",
+                "i32 -> i32",
+                "i32 -> i32",
+                "i32 -> i32",
+                "S -> crate::S",
+                "i32 -> i32",
+                "self -> self",
+                "i32 -> i32",
+                "i32 -> i32",
+                "i32 -> i32",
+                "i32 -> i32",
+                "B -> crate::E::B",
+                "y -> local #1",
+                "A -> crate::E::A",
+                "x -> local #0",
+                "B -> crate::E::B",
+                "C -> crate::E::C",
+                "a -> local #5",
+                "a -> local #5",
+                "K -> crate::K",
+                "z -> local #2",
+                "k -> local #8",
+                "i32 -> i32",
+                "K -> crate::K",
+                "inner -> crate::{block}::inner",
+                // the argument of `twice!` is the outer `x`
+                "x -> local #3",
+                "x -> local #9",
+            ]
+        );
+    }
+    #[test]
+    fn resolution_errors() {
+        assert_eq!(
+            errors(&[(
+                "main.aau",
+                "mod a {
+                    fn hidden() {}
+                    pub mod b { pub fn f() {} }
+                    pub mod c { pub fn f() {} }
+                }
+                mod d { pub use super::a::b::*; pub use super::a::c::*; }
+                use a::*;
+                use missing::x;
+                fn f() {}
+                fn main() {
+                    a::hidden();
+                    d::f();
+                    f::g();
+                    super::main();
+                    let s: Self;
+                    self;
+                    y;
+                    break 'none;
+                    match 1 { 1 | x => {} }
+                    let (p, p) = (1, 2);
+                    let t: f = 1;
+                }",
+            )]),
+            [
+                "unresolved import `missing::x`: no `missing` in this scope",
+                "function `hidden` is private: private function",
+                "`f` is ambiguous: ambiguous name",
+                "expected module, found function `f`: not a module",
+                "too many leading `super` keywords: there are too many \
+                 leading `super` keywords",
+                "failed to resolve: `Self` is only available in impls, \
+                 traits, and type definitions: `Self` is only available here",
+                "expected value, found module `self`: not a value",
+                "cannot find value `y` in this scope: no `y` in this scope",
+                "use of undeclared label `'none`: undeclared label `'none`",
+                "variable `x` is not bound in all patterns: pattern doesn't \
+                 bind `x`",
+                "identifier `p` is bound more than once in the same pattern: \
+                 used in a pattern more than once",
+                "expected type, found function `f`: not a type",
+            ]
+        );
+    }
+}
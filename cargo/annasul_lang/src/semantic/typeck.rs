@@ -0,0 +1,3879 @@
+// Copyright (c) 2025 air (https://yuanair.github.io).
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published by
+// the Free Software Foundation, version 3 of the License only.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+use std::collections::{HashMap, HashSet};
+
+use crate::{
+    lexer::token::{FloatLit, FloatSuffix, IntLit, IntSuffix, Literal},
+    parser::{
+        ast::{
+            self,
+            BinOp,
+            ExprKind,
+            FnDef,
+            FormatArg,
+            FormatMacro,
+            FormatTrait,
+            Item,
+            ItemKind,
+            Label,
+            Module,
+            NodeId,
+            PatKind,
+            StmtKind,
+            TypeKind,
+            UnOp,
+        },
+        visit::{self, MutVisitor},
+    },
+    semantic::{
+        collect::Collector,
+        hir::{self, Body, LocalDecl, LocalId, LoopId},
+        resolve::{
+            CtorKind,
+            DefId,
+            DefKind,
+            PartialRes,
+            PrimTy,
+            Res,
+            Resolutions,
+        },
+    },
+    types::{
+        AdtKind,
+        CharTy,
+        FieldDef,
+        FloatTy,
+        InferTy,
+        Ty,
+        TyCtxt,
+        TyKind,
+        UintTy,
+        layout::Target,
+    },
+    utils::{diagnostic::Diagnostic, span::Span},
+};
+/// infers the types of the locals and checks the bodies of a resolved and
+/// collected crate, see [TypeChecker::check_crate]
+///
+/// integer and float literals without a suffix are `i32` and `f64` unless
+/// their types are inferred, e.g. `let a: u8 = 1;`, and must fit into them
+#[derive(Debug)]
+pub struct TypeChecker<'a> {
+    res:         &'a Resolutions,
+    tcx:         &'a mut TyCtxt,
+    /// for the range of `usize` and `isize`
+    target:      Target,
+    bodies:      Vec<Body>,
+    diagnostics: Vec<Diagnostic>,
+}
+/// checks one [Body]
+struct FnCtxt<'a, 'b> {
+    res:         &'a Resolutions,
+    tcx:         &'b mut TyCtxt,
+    diagnostics: &'b mut Vec<Diagnostic>,
+    target:      Target,
+    owner:       DefId,
+    /// the value of every inference variable, see [InferTy]
+    vars:        Vec<(InferTy, Option<Ty>)>,
+    /// the variables of diverging expressions, e.g. `return`, which are
+    /// `()` unless inferred
+    diverging:   Vec<Ty>,
+    locals:      Vec<LocalDecl>,
+    /// by the id of the identifier pattern
+    bindings:    HashMap<NodeId, LocalId>,
+    self_local:  Option<LocalId>,
+    /// the innermost last
+    loops:       Vec<LoopCx>,
+    next_loop:   usize,
+    /// the return type
+    ret:         Ty,
+    /// the current expression never finishes, e.g. after `return`
+    diverges:    bool,
+    /// the trait bounds and projections to prove, see [FnCtxt::select]
+    obligations: Vec<Obligation>,
+}
+/// a requirement of the body on the types, e.g. `i32: A` for `f(1)` with
+/// `fn f<T: A>(t: T)`
+#[derive(Debug, Clone, Copy)]
+enum Obligation {
+    Trait {
+        ty:     Ty,
+        trait_: DefId,
+        span:   Span,
+    },
+    /// the inference variable `var` is `projection` once normalized, e.g.
+    /// `<_ as A>::B`
+    Projection {
+        projection: Ty,
+        var:        Ty,
+        span:       Span,
+    },
+}
+/// a loop the current expression is in
+struct LoopCx {
+    id:        LoopId,
+    label:     Option<Label>,
+    /// the type of `break a`, [None] in `while` and `for`
+    break_ty:  Option<Ty>,
+    may_break: bool,
+}
+/// the struct or the variant of a constructor, a struct expression or a
+/// struct pattern
+#[derive(Debug, Clone, Copy)]
+struct Variant {
+    adt:   DefId,
+    index: usize,
+    /// the struct or the variant
+    def:   DefId,
+    ctor:  Option<CtorKind>,
+    /// the ADT with its generic arguments, see [TyKind::Adt]
+    ty:    Ty,
+}
+/// replaces the inference variables of a [Body], see [FnCtxt::finish]
+struct Finisher<'f, 'a, 'b> {
+    fcx:      &'f mut FnCtxt<'a, 'b>,
+    reported: HashSet<u32>,
+}
+impl<'a> TypeChecker<'a> {
+    /// `tcx` is collected by [Collector::collect_crate]
+    pub fn new(res: &'a Resolutions, tcx: &'a mut TyCtxt) -> Self {
+        Self {
+            res,
+            tcx,
+            target: Target::host(),
+            bodies: Vec::new(),
+            diagnostics: Vec::new(),
+        }
+    }
+
+    /// the host by default
+    pub fn with_target(mut self, target: Target) -> Self {
+        self.target = target;
+        self
+    }
+
+    /// the typed body of every function, constant and static of `module`,
+    /// ordered by their [DefId]
+    pub fn check_crate(&mut self, module: &mut Module) -> Vec<Body> {
+        self.visit_module(module);
+        let mut bodies = std::mem::take(&mut self.bodies);
+        bodies.sort_by_key(|body| body.owner);
+        bodies
+    }
+
+    pub fn take_diagnostics(&mut self) -> Vec<Diagnostic> {
+        std::mem::take(&mut self.diagnostics)
+    }
+}
+impl MutVisitor for TypeChecker<'_> {
+    fn visit_item(&mut self, item: &mut Item) {
+        if let Some(id) = self.res.item(item.id) {
+            let body = match &item.kind {
+                ItemKind::Fn(FnDef { sig, body: Some(body), .. }) => {
+                    Some(FnCtxt::new(self, id).check_fn(sig, body))
+                }
+                ItemKind::Const { value: Some(value), .. }
+                | ItemKind::Static { value: Some(value), .. } => {
+                    Some(FnCtxt::new(self, id).check_const(value))
+                }
+                _ => None,
+            };
+            self.bodies.extend(body);
+        }
+        // the items in the bodies
+        visit::walk_item(self, item);
+    }
+}
+impl<'a, 'b> FnCtxt<'a, 'b> {
+    fn new(tc: &'b mut TypeChecker<'a>, owner: DefId) -> Self {
+        let ret = tc.tcx.unit();
+        Self {
+            res: tc.res,
+            tcx: tc.tcx,
+            diagnostics: &mut tc.diagnostics,
+            target: tc.target,
+            owner,
+            vars: Vec::new(),
+            diverging: Vec::new(),
+            locals: Vec::new(),
+            bindings: HashMap::new(),
+            self_local: None,
+            loops: Vec::new(),
+            next_loop: 0,
+            ret,
+            diverges: false,
+            obligations: Vec::new(),
+        }
+    }
+
+    fn check_fn(mut self, sig: &ast::FnSig, body: &ast::Block) -> Body {
+        let fn_sig = self.tcx.fn_sig(self.owner).unwrap().clone();
+        self.ret = fn_sig.ret;
+        let mut tys = fn_sig.params.iter().copied();
+        let mut params = Vec::new();
+        if let Some(param) = sig.self_param {
+            let ty = tys.next().unwrap();
+            let mutable = param.mutable && !param.by_ref;
+            let local = self.new_local("self", mutable, ty, param.span);
+            self.self_local = Some(local);
+            params.push(hir::Pat {
+                kind: hir::PatKind::Binding { local, by_ref: false, sub: None },
+                ty,
+                span: param.span,
+            });
+        }
+        for (param, ty) in sig.params.iter().zip(tys) {
+            params.push(self.check_pat(&param.pat, ty));
+        }
+        let value = self.check_block_with(body, Some(self.ret), true);
+        self.finish(params, value)
+    }
+
+    fn check_const(mut self, value: &ast::Expr) -> Body {
+        self.ret = match self.tcx.type_of(self.owner) {
+            Some(ty) => ty,
+            None => self.tcx.error(),
+        };
+        let value = self.check_expr_coerce(value, self.ret);
+        self.finish(Vec::new(), value)
+    }
+
+    /// proves the obligations, defaults the unknown numbers and reports the
+    /// other unknown types
+    fn finish(mut self, params: Vec<hir::Pat>, value: hir::Expr) -> Body {
+        self.select(false);
+        let unit = self.tcx.unit();
+        for ty in std::mem::take(&mut self.diverging) {
+            if let TyKind::Infer(InferTy::Var(var)) =
+                *self.tcx.kind(self.resolve(ty))
+            {
+                self.vars[var as usize].1 = Some(unit);
+            }
+        }
+        for index in 0..self.vars.len() {
+            let default = match self.vars[index] {
+                (InferTy::Int(_), None) => PrimTy::I32,
+                (InferTy::Float(_), None) => PrimTy::F64,
+                _ => continue,
+            };
+            self.vars[index].1 = Some(self.tcx.prim(default));
+        }
+        self.select(true);
+        let mut body = Body {
+            owner: self.owner,
+            locals: std::mem::take(&mut self.locals),
+            params,
+            value,
+        };
+        let mut finisher =
+            Finisher { fcx: &mut self, reported: HashSet::new() };
+        hir::MutVisitor::visit_body(&mut finisher, &mut body);
+        body
+    }
+
+    fn check_expr_coerce(&mut self, expr: &ast::Expr, ty: Ty) -> hir::Expr {
+        let checked = self.check_expr(expr, Some(ty));
+        self.coerce(checked, ty)
+    }
+
+    /// `expected` is a hint, e.g. for `1` in `let a: u8 = 1;`
+    fn check_expr(
+        &mut self, expr: &ast::Expr, expected: Option<Ty>,
+    ) -> hir::Expr {
+        let span = expr.span;
+        let typed = |kind, ty| hir::Expr { kind, ty, span };
+        match &expr.kind {
+            ExprKind::Literal(literal) => {
+                let ty = self.literal_ty(literal);
+                typed(hir::ExprKind::Literal(literal.clone()), ty)
+            }
+            ExprKind::Path(path) => self.check_path(path),
+            ExprKind::Tuple(exprs) => {
+                let hints = match expected.map(|ty| self.resolve(ty)) {
+                    Some(ty) => match self.tcx.kind(ty) {
+                        TyKind::Tuple(tys) if tys.len() == exprs.len() => {
+                            tys.iter().copied().map(Some).collect()
+                        }
+                        _ => vec![None; exprs.len()],
+                    },
+                    None => vec![None; exprs.len()],
+                };
+                let exprs: Vec<_> = exprs
+                    .iter()
+                    .zip(hints)
+                    .map(|(expr, hint)| self.check_expr(expr, hint))
+                    .collect();
+                let tys = exprs.iter().map(|expr| expr.ty).collect();
+                let ty = self.tcx.intern(TyKind::Tuple(tys));
+                typed(hir::ExprKind::Tuple(exprs), ty)
+            }
+            ExprKind::Paren(inner) => self.check_expr(inner, expected),
+            ExprKind::Array(exprs) => {
+                let elem = match expected.map(|ty| self.resolve(ty)) {
+                    Some(ty) => match self.tcx.kind(ty) {
+                        TyKind::Array(elem, _) | TyKind::Slice(elem) => *elem,
+                        _ => self.next_var(InferTy::Var),
+                    },
+                    None => self.next_var(InferTy::Var),
+                };
+                let exprs: Vec<_> = exprs
+                    .iter()
+                    .map(|expr| self.check_expr_coerce(expr, elem))
+                    .collect();
+                let len = exprs.len() as u64;
+                let ty = self.tcx.intern(TyKind::Array(elem, len));
+                typed(hir::ExprKind::Array(exprs), ty)
+            }
+            ExprKind::Repeat(elem, len) => {
+                let hint = expected.and_then(|ty| {
+                    match self.tcx.kind(self.resolve(ty)) {
+                        TyKind::Array(elem, _) => Some(*elem),
+                        _ => None,
+                    }
+                });
+                let elem = self.check_expr(elem, hint);
+                let mut collector = Collector::new(self.res, self.tcx);
+                let value = collector.eval(len);
+                self.diagnostics.extend(collector.take_diagnostics());
+                let Some(value) = value else { return self.error_expr(span) };
+                let Ok(len) = u64::try_from(value) else {
+                    self.diagnostics.push(
+                        Diagnostic::error(format!(
+                            "array length `{value}` is not a `usize`"
+                        ))
+                        .with_code("E0308")
+                        .with_primary(len.span, "negative or too large"),
+                    );
+                    return self.error_expr(span);
+                };
+                let ty = self.tcx.intern(TyKind::Array(elem.ty, len));
+                typed(hir::ExprKind::Repeat(Box::new(elem), len), ty)
+            }
+            ExprKind::Struct { path, fields, rest } => {
+                self.check_struct(path, fields, rest.as_deref(), span)
+            }
+            ExprKind::Unary(op, operand) => {
+                self.check_unary(*op, operand, expected, span)
+            }
+            ExprKind::Ref { mutable, expr: inner } => {
+                let hint = expected.and_then(|ty| {
+                    match self.tcx.kind(self.resolve(ty)) {
+                        TyKind::Ref { ty, .. } => Some(*ty),
+                        _ => None,
+                    }
+                });
+                let inner = self.check_expr(inner, hint);
+                let ty = self.tcx.intern(TyKind::Ref {
+                    mutable: *mutable,
+                    ty:      inner.ty,
+                });
+                let kind = hir::ExprKind::Ref {
+                    mutable: *mutable,
+                    expr:    Box::new(inner),
+                    autoref: false,
+                };
+                typed(kind, ty)
+            }
+            ExprKind::Box(inner) => {
+                let hint = expected.and_then(|ty| {
+                    match self.tcx.kind(self.resolve(ty)) {
+                        TyKind::Box(ty) => Some(*ty),
+                        _ => None,
+                    }
+                });
+                let inner = self.check_expr(inner, hint);
+                let ty = self.tcx.intern(TyKind::Box(inner.ty));
+                typed(hir::ExprKind::Box(Box::new(inner)), ty)
+            }
+            ExprKind::Binary(op, lhs, rhs) => {
+                let (lhs, rhs, ty) =
+                    self.check_binary(op.node, lhs, rhs, expected, op.span);
+                let kind = hir::ExprKind::Binary(
+                    op.node,
+                    Box::new(lhs),
+                    Box::new(rhs),
+                );
+                typed(kind, ty)
+            }
+            ExprKind::Assign(lhs, rhs) => {
+                let lhs = self.check_expr(lhs, None);
+                self.check_place(&lhs);
+                let rhs = self.check_expr_coerce(rhs, lhs.ty);
+                let kind = hir::ExprKind::Assign(Box::new(lhs), Box::new(rhs));
+                let unit = self.tcx.unit();
+                typed(kind, unit)
+            }
+            ExprKind::AssignOp(op, lhs, rhs) => {
+                let (lhs, rhs, _) =
+                    self.check_binary(op.node, lhs, rhs, None, op.span);
+                self.check_place(&lhs);
+                let kind = hir::ExprKind::AssignOp(
+                    op.node,
+                    Box::new(lhs),
+                    Box::new(rhs),
+                );
+                let unit = self.tcx.unit();
+                typed(kind, unit)
+            }
+            ExprKind::Cast(inner, ty) => {
+                let target = self.lower_ty(ty);
+                let inner = self.check_expr(inner, None);
+                if !self.can_cast(inner.ty, target, span) {
+                    let (source, target) =
+                        (self.display(inner.ty), self.display(target));
+                    self.diagnostics.push(
+                        Diagnostic::error(format!(
+                            "non-primitive cast: `{source}` as `{target}`"
+                        ))
+                        .with_code("E0605")
+                        .with_primary(
+                            span,
+                            "an `as` expression can only be used to convert \
+                             between primitive types",
+                        ),
+                    );
+                }
+                typed(hir::ExprKind::Cast(Box::new(inner)), target)
+            }
+            ExprKind::Call(callee, args) => self.check_call(callee, args, span),
+            ExprKind::MethodCall { receiver, method, args } => {
+                self.check_method_call(receiver, method, args, span)
+            }
+            ExprKind::Field(base, name) => {
+                let base = self.check_expr(base, None);
+                self.check_field(base, name, span)
+            }
+            ExprKind::Index(base, index) => {
+                let base = self.check_expr(base, None);
+                let usize = self.tcx.prim(PrimTy::Usize);
+                let index = self.check_expr_coerce(index, usize);
+                let (base, ty) =
+                    self.autoderef(base, |fcx, ty| match fcx.tcx.kind(ty) {
+                        TyKind::Array(elem, _) | TyKind::Slice(elem) => {
+                            Some(*elem)
+                        }
+                        _ => None,
+                    });
+                let Some(ty) = ty else {
+                    if !self.has_error(base.ty) {
+                        let found = self.display(base.ty);
+                        self.diagnostics.push(
+                            Diagnostic::error(format!(
+                                "cannot index into a value of type `{found}`"
+                            ))
+                            .with_code("E0608")
+                            .with_primary(base.span, ""),
+                        );
+                    }
+                    return self.error_expr(span);
+                };
+                let kind =
+                    hir::ExprKind::Index(Box::new(base), Box::new(index));
+                typed(kind, ty)
+            }
+            ExprKind::Try(_) => {
+                self.diagnostics.push(
+                    Diagnostic::error("the `?` operator is not supported yet")
+                        .with_primary(span, ""),
+                );
+                self.error_expr(span)
+            }
+            ExprKind::Range { .. } => {
+                self.diagnostics.push(
+                    Diagnostic::error(
+                        "ranges are only supported as the iterator of a `for` \
+                         loop",
+                    )
+                    .with_primary(span, ""),
+                );
+                self.error_expr(span)
+            }
+            ExprKind::Block(block) => {
+                self.check_block_with(block, expected, false)
+            }
+            ExprKind::If { cond, then_branch, else_branch } => self.check_if(
+                cond,
+                then_branch,
+                else_branch.as_deref(),
+                expected,
+                span,
+            ),
+            ExprKind::Match { scrutinee, arms } => {
+                self.check_match(scrutinee, arms, expected, span)
+            }
+            ExprKind::Loop { label, body } => {
+                let diverges = std::mem::take(&mut self.diverges);
+                let break_ty = self.next_var(InferTy::Var);
+                let id = self.enter_loop(label.clone(), Some(break_ty));
+                let unit = self.tcx.unit();
+                let body = self.check_block_with(body, Some(unit), true);
+                let cx = self.loops.pop().unwrap();
+                let ty = match cx.may_break {
+                    true => break_ty,
+                    false => self.next_diverging(),
+                };
+                self.diverges = diverges || !cx.may_break;
+                typed(hir::ExprKind::Loop { id, body: block_of(body) }, ty)
+            }
+            ExprKind::While { label, cond, body } => {
+                let bool = self.tcx.intern(TyKind::Bool);
+                let unit = self.tcx.unit();
+                let cond = self.check_expr_coerce(cond, bool);
+                let diverges = self.diverges;
+                let id = self.enter_loop(label.clone(), None);
+                let body = self.check_block_with(body, Some(unit), true);
+                self.loops.pop();
+                self.diverges = diverges;
+                // `loop { if cond { body } else { break } }`
+                let exit = hir::Expr {
+                    kind: hir::ExprKind::Break { target: id, value: None },
+                    ty:   unit,
+                    span: cond.span,
+                };
+                let cond_span = cond.span;
+                let branch = hir::Expr {
+                    kind: hir::ExprKind::If {
+                        cond:        Box::new(cond),
+                        then_branch: block_of(body),
+                        else_branch: Some(Box::new(exit)),
+                    },
+                    ty:   unit,
+                    span: cond_span,
+                };
+                let body = hir::Block {
+                    stmts: Vec::new(),
+                    expr: Some(Box::new(branch)),
+                    span,
+                };
+                typed(hir::ExprKind::Loop { id, body }, unit)
+            }
+            ExprKind::For { label, pat, iter, body } => {
+                let (iter, elem) = self.check_iter(iter);
+                let pat = self.check_pat(pat, elem);
+                let diverges = self.diverges;
+                let id = self.enter_loop(label.clone(), None);
+                let unit = self.tcx.unit();
+                let body = self.check_block_with(body, Some(unit), true);
+                self.loops.pop();
+                self.diverges = diverges;
+                let kind = hir::ExprKind::For {
+                    id,
+                    pat,
+                    iter: Box::new(iter),
+                    body: block_of(body),
+                };
+                typed(kind, unit)
+            }
+            ExprKind::Break { label, value } => {
+                let target = self.find_loop(label, span, "break");
+                let value = match target {
+                    Some(index) => match (self.loops[index].break_ty, value) {
+                        (Some(ty), Some(value)) => {
+                            Some(self.check_expr_coerce(value, ty))
+                        }
+                        (Some(ty), None) => {
+                            let unit = self.tcx.unit();
+                            if !self.unify(ty, unit) {
+                                self.mismatch(span, ty, unit);
+                            }
+                            None
+                        }
+                        (None, Some(value)) => {
+                            self.diagnostics.push(
+                                Diagnostic::error(
+                                    "`break` with value from a `while` or a \
+                                     `for` loop",
+                                )
+                                .with_code("E0571")
+                                .with_primary(
+                                    span,
+                                    "can only break with a value inside `loop`",
+                                ),
+                            );
+                            Some(self.check_expr(value, None))
+                        }
+                        (None, None) => None,
+                    },
+                    None => {
+                        value.as_ref().map(|value| self.check_expr(value, None))
+                    }
+                };
+                let ty = self.next_diverging();
+                self.diverges = true;
+                let Some(index) = target else { return self.error_expr(span) };
+                self.loops[index].may_break = true;
+                let target = self.loops[index].id;
+                let value = value.map(Box::new);
+                typed(hir::ExprKind::Break { target, value }, ty)
+            }
+            ExprKind::Continue { label } => {
+                let target = self.find_loop(label, span, "continue");
+                let ty = self.next_diverging();
+                self.diverges = true;
+                let Some(index) = target else { return self.error_expr(span) };
+                let target = self.loops[index].id;
+                typed(hir::ExprKind::Continue { target }, ty)
+            }
+            ExprKind::Return(value) => {
+                let value = match value {
+                    Some(value) => {
+                        Some(self.check_expr_coerce(value, self.ret))
+                    }
+                    None => {
+                        let unit = self.tcx.unit();
+                        if !self.unify(self.ret, unit) {
+                            let ret = self.display(self.ret);
+                            self.diagnostics.push(
+                                Diagnostic::error(
+                                    "`return;` in a function whose return \
+                                     type is not `()`",
+                                )
+                                .with_code("E0069")
+                                .with_primary(
+                                    span,
+                                    format!("expected `{ret}`, found `()`"),
+                                ),
+                            );
+                        }
+                        None
+                    }
+                };
+                let ty = self.next_diverging();
+                self.diverges = true;
+                typed(hir::ExprKind::Return(value.map(Box::new)), ty)
+            }
+            ExprKind::Format(args) => self.check_format(args, span),
+            ExprKind::MacCall(_) | ExprKind::Err => self.error_expr(span),
+        }
+    }
+
+    /// `coerce` the last expression to `expected` instead of using it as a
+    /// hint
+    fn check_block_with(
+        &mut self, block: &ast::Block, expected: Option<Ty>, coerce: bool,
+    ) -> hir::Expr {
+        let (tail, init) = match block.stmts.split_last() {
+            Some((ast::Stmt { kind: StmtKind::Expr(tail), .. }, init)) => {
+                (Some(tail), init)
+            }
+            _ => (None, block.stmts.as_slice()),
+        };
+        let stmts =
+            init.iter().filter_map(|stmt| self.check_stmt(stmt)).collect();
+        let (expr, ty) = match (tail, expected) {
+            (Some(tail), Some(expected)) if coerce => {
+                (Some(self.check_expr_coerce(tail, expected)), expected)
+            }
+            (Some(tail), _) => {
+                let tail = self.check_expr(tail, expected);
+                let ty = tail.ty;
+                (Some(tail), ty)
+            }
+            (None, _) if self.diverges => (None, self.next_diverging()),
+            (None, expected) => {
+                let unit = self.tcx.unit();
+                if let Some(expected) = expected
+                    && coerce
+                    && !self.unify(unit, expected)
+                {
+                    self.mismatch(block.span, expected, unit);
+                }
+                (None, unit)
+            }
+        };
+        hir::Expr {
+            kind: hir::ExprKind::Block(hir::Block {
+                stmts,
+                expr: expr.map(Box::new),
+                span: block.span,
+            }),
+            ty,
+            span: block.span,
+        }
+    }
+
+    fn check_stmt(&mut self, stmt: &ast::Stmt) -> Option<hir::Stmt> {
+        let kind = match &stmt.kind {
+            // e.g. `if a {}` in the middle of a block
+            StmtKind::Expr(expr) => {
+                let unit = self.tcx.unit();
+                hir::StmtKind::Expr(self.check_expr_coerce(expr, unit))
+            }
+            StmtKind::Semi(expr) => {
+                hir::StmtKind::Expr(self.check_expr(expr, None))
+            }
+            StmtKind::Let(local) => {
+                let ty = local.ty.as_ref().map(|ty| self.lower_ty(ty));
+                let init = match (&local.init, ty) {
+                    (Some(init), Some(ty)) => {
+                        Some(self.check_expr_coerce(init, ty))
+                    }
+                    (Some(init), None) => Some(self.check_expr(init, None)),
+                    (None, _) => None,
+                };
+                let ty = match (ty, &init) {
+                    (Some(ty), _) => ty,
+                    (None, Some(init)) => init.ty,
+                    (None, None) => self.next_var(InferTy::Var),
+                };
+                let els = local.els.as_ref().map(|els| {
+                    let diverges = std::mem::take(&mut self.diverges);
+                    let checked = self.check_block_with(els, None, false);
+                    if !self.diverges {
+                        let found = self.display(checked.ty);
+                        self.diagnostics.push(
+                            Diagnostic::error(
+                                "`else` clause of `let...else` does not \
+                                 diverge",
+                            )
+                            .with_code("E0308")
+                            .with_primary(
+                                els.span,
+                                format!("expected `!`, found `{found}`"),
+                            )
+                            .with_help(
+                                "try adding a diverging expression, such as \
+                                 `return` or `break`",
+                            ),
+                        );
+                    }
+                    self.diverges = diverges;
+                    block_of(checked)
+                });
+                let pat = self.check_pat(&local.pat, ty);
+                hir::StmtKind::Let { pat, init, els }
+            }
+            StmtKind::Empty | StmtKind::Item(_) => return None,
+        };
+        Some(hir::Stmt { kind, span: stmt.span })
+    }
+
+    fn check_path(&mut self, path: &ast::Path) -> hir::Expr {
+        let span = path.span;
+        let Some(partial) = self.res.path(path.id) else {
+            return self.error_expr(span);
+        };
+        let last = path.segments.last().unwrap();
+        if partial.unresolved == 0 {
+            if let Some(variant) = self.variant_of(partial.base) {
+                self.variant_args(variant, path);
+                return self.check_ctor(variant, span);
+            }
+            let expr = self.check_res(partial.base, span);
+            self.fn_args(&expr, last);
+            return expr;
+        }
+        let name = last.ident.node.as_str();
+        // e.g. `A::f` of a trait `A`
+        if let Res::Def(DefKind::Trait, trait_) = partial.base
+            && partial.unresolved == 1
+        {
+            let item =
+                self.res.def(trait_).children.iter().copied().find(|item| {
+                    let def = self.res.def(*item);
+                    matches!(def.kind, DefKind::AssocFn | DefKind::AssocConst)
+                        && def.name == name
+                });
+            if let Some(item) = item {
+                let self_ty = self.next_var(InferTy::Var);
+                let expr = self.check_trait_item(item, self_ty, span);
+                self.fn_args(&expr, last);
+                return expr;
+            }
+            let trait_name = &self.res.def(trait_).name;
+            self.diagnostics.push(
+                Diagnostic::error(format!(
+                    "cannot find method or associated constant `{name}` in \
+                     trait `{trait_name}`"
+                ))
+                .with_primary(span, "not found in this trait"),
+            );
+            return self.error_expr(span);
+        }
+        let self_ty = self.self_ty_of(partial, path);
+        if partial.unresolved > 1 || self.has_error(self_ty) {
+            if !self.has_error(self_ty) {
+                self.diagnostics.push(
+                    Diagnostic::error("ambiguous associated item")
+                        .with_primary(span, ""),
+                );
+            }
+            return self.error_expr(span);
+        }
+        if let Some(id) = self.lookup_assoc(self_ty, name, span) {
+            let args = self.instantiate_assoc(id, self_ty, span);
+            let expr = match self.res.def(id).kind {
+                DefKind::AssocFn => {
+                    let ty = self.tcx.intern(TyKind::FnDef(id, args));
+                    hir::Expr { kind: hir::ExprKind::Def(id), ty, span }
+                }
+                _ => {
+                    let Some(ty) = self.tcx.type_of(id) else {
+                        return self.error_expr(span);
+                    };
+                    let impl_ = self.res.def(id).parent.unwrap();
+                    let ty = self.tcx.instantiate(ty, impl_, &args);
+                    let ty = self.normalize(ty, span);
+                    hir::Expr { kind: hir::ExprKind::Def(id), ty, span }
+                }
+            };
+            self.fn_args(&expr, last);
+            return expr;
+        }
+        if let Some(item) = self.lookup_trait_item(self_ty, name, false, span) {
+            let expr = self.check_trait_item(item, self_ty, span);
+            self.fn_args(&expr, last);
+            return expr;
+        }
+        if let TyKind::Adt(adt, _) = *self.tcx.kind(self_ty)
+            && let Some(def) = self.res.def(adt).children.iter().find(|v| {
+                let def = self.res.def(**v);
+                def.kind == DefKind::Variant && def.name == name
+            })
+            && let Some(mut variant) =
+                self.variant_of(Res::Def(DefKind::Variant, *def))
+        {
+            variant.ty = self_ty;
+            self.no_args(last);
+            return self.check_ctor(variant, span);
+        }
+        let found = self.display(self_ty);
+        self.diagnostics.push(
+            Diagnostic::error(format!(
+                "no function or associated item named `{name}` found for \
+                 `{found}`"
+            ))
+            .with_code("E0599")
+            .with_primary(span, "associated item not found"),
+        );
+        self.error_expr(span)
+    }
+
+    /// a unit struct or variant, or the constructor of a tuple struct or
+    /// variant
+    fn check_ctor(&mut self, variant: Variant, span: Span) -> hir::Expr {
+        let typed = |kind, ty| hir::Expr { kind, ty, span };
+        match variant.ctor {
+            Some(CtorKind::Const) => {
+                let kind = hir::ExprKind::Adt {
+                    adt:     variant.adt,
+                    variant: variant.index,
+                    fields:  Vec::new(),
+                    rest:    None,
+                };
+                typed(kind, variant.ty)
+            }
+            Some(CtorKind::Fn) => {
+                let TyKind::Adt(_, args) = self.tcx.kind(variant.ty).clone()
+                else {
+                    unreachable!()
+                };
+                let ty = self.tcx.intern(TyKind::FnDef(variant.def, args));
+                typed(hir::ExprKind::Def(variant.def), ty)
+            }
+            None => {
+                let name = &self.res.def(variant.def).name;
+                self.diagnostics.push(
+                    Diagnostic::error(format!(
+                        "expected value, found struct `{name}`"
+                    ))
+                    .with_code("E0423")
+                    .with_primary(span, "")
+                    .with_help(format!(
+                        "use struct literal syntax instead: `{name} {{ .. }}`"
+                    )),
+                );
+                self.error_expr(span)
+            }
+        }
+    }
+
+    /// a value path, e.g. a local, a function or a unit struct
+    fn check_res(&mut self, res: Res, span: Span) -> hir::Expr {
+        let typed = |kind, ty| hir::Expr { kind, ty, span };
+        if let Some(variant) = self.variant_of(res) {
+            return self.check_ctor(variant, span);
+        }
+        match res {
+            Res::Local(id) => match self.bindings.get(&id) {
+                Some(local) => {
+                    let ty = self.locals[local.index()].ty;
+                    typed(hir::ExprKind::Local(*local), ty)
+                }
+                None => self.error_expr(span),
+            },
+            Res::SelfParam(_) => match self.self_local {
+                Some(local) => {
+                    let ty = self.locals[local.index()].ty;
+                    typed(hir::ExprKind::Local(local), ty)
+                }
+                None => self.error_expr(span),
+            },
+            Res::Def(DefKind::AssocFn | DefKind::AssocConst, id)
+                if self.is_trait_item(id) =>
+            {
+                let self_ty = self.next_var(InferTy::Var);
+                self.check_trait_item(id, self_ty, span)
+            }
+            Res::Def(DefKind::Fn | DefKind::AssocFn, id) => {
+                let args = self.instantiate(id, span);
+                let ty = self.tcx.intern(TyKind::FnDef(id, args));
+                typed(hir::ExprKind::Def(id), ty)
+            }
+            Res::Def(
+                DefKind::Const | DefKind::AssocConst | DefKind::Static,
+                id,
+            ) => match self.tcx.type_of(id) {
+                Some(ty) => typed(hir::ExprKind::Def(id), ty),
+                None => self.error_expr(span),
+            },
+            _ => self.error_expr(span),
+        }
+    }
+
+    /// an item of a trait for `self_ty`, e.g. `A::f` with `impl B for A`,
+    /// `T::N` with `T: B`
+    fn check_trait_item(
+        &mut self, id: DefId, self_ty: Ty, span: Span,
+    ) -> hir::Expr {
+        let trait_ = self.res.def(id).parent.unwrap();
+        if self.res.def(id).kind == DefKind::AssocFn {
+            let args = self.instantiate(id, span);
+            self.unify(args[0], self_ty);
+            let ty = self.tcx.intern(TyKind::FnDef(id, args));
+            return hir::Expr { kind: hir::ExprKind::Def(id), ty, span };
+        }
+        self.obligations.push(Obligation::Trait { ty: self_ty, trait_, span });
+        let Some(ty) = self.tcx.type_of(id) else {
+            return self.error_expr(span);
+        };
+        let ty = self.tcx.subst(ty, &[trait_], &[self_ty]);
+        let ty = self.normalize(ty, span);
+        let resolved = self.resolve_deep(self_ty, &mut |_, ty| ty);
+        // the constant of the impl if it is already known
+        if let Some((impl_, args)) = self.tcx.find_impl(trait_, resolved)
+            && args.is_empty()
+            && let Some(item) = self.tcx.impl_item(impl_, id)
+            && item != id
+        {
+            return hir::Expr { kind: hir::ExprKind::Def(item), ty, span };
+        }
+        let kind = hir::ExprKind::AssocConst { id, self_ty };
+        hir::Expr { kind, ty, span }
+    }
+
+    /// e.g. `A { a: 1 }`, `B::C { ..d }`
+    fn check_struct(
+        &mut self, path: &ast::Path, fields: &[ast::ExprField],
+        rest: Option<&ast::Expr>, span: Span,
+    ) -> hir::Expr {
+        let Some(variant) = self.variant_of_path(path) else {
+            return self.error_expr(span);
+        };
+        self.variant_args(variant, path);
+        let adt_ty = variant.ty;
+        let def = self.tcx.adt(variant.adt).unwrap();
+        let kind = def.kind;
+        let adt_name = def.variants[variant.index].name.clone();
+        let defs = def.variants[variant.index].fields.clone();
+        let tys: Vec<_> = (0..defs.len())
+            .map(|index| self.tcx.field_ty(adt_ty, variant.index, index))
+            .collect();
+        let mut checked = Vec::<(usize, hir::Expr)>::new();
+        for field in fields {
+            let name = field.name.node.as_str();
+            let Some(index) = defs.iter().position(|def| def.name == name)
+            else {
+                self.diagnostics.push(
+                    Diagnostic::error(format!(
+                        "struct `{adt_name}` has no field named `{name}`"
+                    ))
+                    .with_code("E0560")
+                    .with_primary(field.name.span, "unknown field"),
+                );
+                self.check_expr(&field.expr, None);
+                continue;
+            };
+            self.check_field_vis(&defs[index], &adt_name, field.name.span);
+            if checked.iter().any(|(other, _)| *other == index) {
+                self.diagnostics.push(
+                    Diagnostic::error(format!(
+                        "field `{name}` specified more than once"
+                    ))
+                    .with_code("E0062")
+                    .with_primary(field.name.span, "used more than once"),
+                );
+            }
+            let expr = self.check_expr_coerce(&field.expr, tys[index]);
+            checked.push((index, expr));
+        }
+        let rest =
+            rest.map(|rest| Box::new(self.check_expr_coerce(rest, adt_ty)));
+        if kind == AdtKind::Union && (checked.len() != 1 || rest.is_some()) {
+            self.diagnostics.push(
+                Diagnostic::error(
+                    "union expressions should have exactly one field",
+                )
+                .with_code("E0784")
+                .with_primary(span, ""),
+            );
+        } else if kind != AdtKind::Union && rest.is_none() {
+            let missing: Vec<_> = defs
+                .iter()
+                .enumerate()
+                .filter(|(index, _)| {
+                    checked.iter().all(|(other, _)| other != index)
+                })
+                .map(|(_, def)| format!("`{}`", def.name))
+                .collect();
+            if !missing.is_empty() {
+                self.diagnostics.push(
+                    Diagnostic::error(format!(
+                        "missing field{} {} in initializer of `{adt_name}`",
+                        plural(missing.len()),
+                        missing.join(", ")
+                    ))
+                    .with_code("E0063")
+                    .with_primary(path.span, "missing fields"),
+                );
+            }
+        }
+        hir::Expr {
+            kind: hir::ExprKind::Adt {
+                adt: variant.adt,
+                variant: variant.index,
+                fields: checked,
+                rest,
+            },
+            ty: adt_ty,
+            span,
+        }
+    }
+
+    fn check_unary(
+        &mut self, op: UnOp, operand: &ast::Expr, expected: Option<Ty>,
+        span: Span,
+    ) -> hir::Expr {
+        let operand = match op {
+            UnOp::Deref => self.check_expr(operand, None),
+            UnOp::Neg | UnOp::Not => self.check_expr(operand, expected),
+        };
+        let ty = self.structurally_resolve(operand.ty, operand.span);
+        let result = match (op, self.tcx.kind(ty)) {
+            (_, TyKind::Error) => Some(ty),
+            (
+                UnOp::Deref,
+                TyKind::Ref { ty, .. }
+                | TyKind::Ptr { ty, .. }
+                | TyKind::Box(ty),
+            ) => Some(*ty),
+            (UnOp::Neg, TyKind::Uint(_)) => None,
+            (UnOp::Neg, _) if self.tcx.is_numeric(ty) => Some(ty),
+            (UnOp::Not, TyKind::Bool) => Some(ty),
+            (UnOp::Not, _) if self.tcx.is_integral(ty) => Some(ty),
+            _ => None,
+        };
+        let Some(result) = result else {
+            let found = self.display(ty);
+            let message = match op {
+                UnOp::Deref => format!("type `{found}` cannot be dereferenced"),
+                _ => format!(
+                    "cannot apply unary operator `{}` to type `{found}`",
+                    op.as_str()
+                ),
+            };
+            self.diagnostics
+                .push(Diagnostic::error(message).with_primary(span, ""));
+            return self.error_expr(span);
+        };
+        hir::Expr {
+            kind: hir::ExprKind::Unary(op, Box::new(operand)),
+            ty: result,
+            span,
+        }
+    }
+
+    /// the operands and the type of `lhs op rhs`
+    fn check_binary(
+        &mut self, op: BinOp, lhs: &ast::Expr, rhs: &ast::Expr,
+        expected: Option<Ty>, span: Span,
+    ) -> (hir::Expr, hir::Expr, Ty) {
+        let bool = self.tcx.intern(TyKind::Bool);
+        if matches!(op, BinOp::And | BinOp::Or) {
+            let lhs = self.check_expr_coerce(lhs, bool);
+            let rhs = self.check_expr_coerce(rhs, bool);
+            return (lhs, rhs, bool);
+        }
+        let hint = if op.is_comparison() { None } else { expected };
+        let lhs = self.check_expr(lhs, hint);
+        let rhs = match op {
+            BinOp::Shl | BinOp::Shr => self.check_expr(rhs, None),
+            _ => self.check_expr_coerce(rhs, lhs.ty),
+        };
+        let ty = self.structurally_resolve(lhs.ty, lhs.span);
+        let valid = match op {
+            _ if self.has_error(ty) => true,
+            BinOp::Add | BinOp::Sub | BinOp::Mul | BinOp::Div | BinOp::Rem => {
+                self.tcx.is_numeric(ty)
+            }
+            BinOp::BitAnd | BinOp::BitOr | BinOp::BitXor => {
+                self.tcx.is_integral(ty) || *self.tcx.kind(ty) == TyKind::Bool
+            }
+            BinOp::Shl | BinOp::Shr => {
+                let rhs = self.structurally_resolve(rhs.ty, rhs.span);
+                self.tcx.is_integral(ty)
+                    && (self.tcx.is_integral(rhs) || self.has_error(rhs))
+            }
+            BinOp::Eq
+            | BinOp::Ne
+            | BinOp::Lt
+            | BinOp::Le
+            | BinOp::Gt
+            | BinOp::Ge => self.tcx.is_scalar(ty) || ty == self.tcx.unit(),
+            BinOp::And | BinOp::Or => unreachable!(),
+        };
+        if !valid {
+            let found = self.display(ty);
+            self.diagnostics.push(
+                Diagnostic::error(format!(
+                    "binary operation `{}` cannot be applied to type `{found}`",
+                    op.as_str()
+                ))
+                .with_code("E0369")
+                .with_primary(span, "")
+                .with_secondary(lhs.span, found),
+            );
+        }
+        let ty = if op.is_comparison() { bool } else { lhs.ty };
+        (lhs, rhs, ty)
+    }
+
+    /// reports a left-hand side of an assignment that is not a place, e.g.
+    /// `1 = a`
+    fn check_place(&mut self, expr: &hir::Expr) {
+        fn is_place(res: &Resolutions, expr: &hir::Expr) -> bool {
+            match &expr.kind {
+                hir::ExprKind::Local(_)
+                | hir::ExprKind::Unary(UnOp::Deref, _)
+                | hir::ExprKind::Err => true,
+                hir::ExprKind::Def(id) => res.def(*id).kind == DefKind::Static,
+                hir::ExprKind::Field(base, _)
+                | hir::ExprKind::Index(base, _) => is_place(res, base),
+                _ => false,
+            }
+        }
+        if !is_place(self.res, expr) {
+            self.diagnostics.push(
+                Diagnostic::error("invalid left-hand side of assignment")
+                    .with_code("E0070")
+                    .with_primary(
+                        expr.span,
+                        "cannot assign to this expression",
+                    ),
+            );
+        }
+    }
+
+    fn check_call(
+        &mut self, callee: &ast::Expr, args: &[ast::Expr], span: Span,
+    ) -> hir::Expr {
+        // e.g. `Some(1)`
+        if let ExprKind::Path(path) = &callee.kind
+            && let Some(variant) = self.variant_of_path_quiet(path)
+            && variant.ctor == Some(CtorKind::Fn)
+        {
+            self.variant_args(variant, path);
+            let ty = variant.ty;
+            let fields = self.field_tys(variant);
+            let args = self.check_args(&fields, args, span);
+            let kind = hir::ExprKind::Adt {
+                adt:     variant.adt,
+                variant: variant.index,
+                fields:  args.into_iter().enumerate().collect(),
+                rest:    None,
+            };
+            return hir::Expr { kind, ty, span };
+        }
+        let callee = self.check_expr(callee, None);
+        let ty = self.structurally_resolve(callee.ty, callee.span);
+        let (params, ret) = match self.tcx.kind(ty).clone() {
+            TyKind::FnDef(id, args) => self.instantiate_sig(id, &args, span),
+            TyKind::FnPtr { params, ret } => (params, ret),
+            kind => {
+                if kind != TyKind::Error {
+                    let found = self.display(ty);
+                    self.diagnostics.push(
+                        Diagnostic::error(format!(
+                            "expected function, found `{found}`"
+                        ))
+                        .with_code("E0618")
+                        .with_primary(
+                            callee.span,
+                            "call expression requires function",
+                        ),
+                    );
+                }
+                for arg in args {
+                    self.check_expr(arg, None);
+                }
+                return self.error_expr(span);
+            }
+        };
+        let args = self.check_args(&params, args, span);
+        hir::Expr {
+            kind: hir::ExprKind::Call(Box::new(callee), args),
+            ty: ret,
+            span,
+        }
+    }
+
+    fn check_args(
+        &mut self, params: &[Ty], args: &[ast::Expr], span: Span,
+    ) -> Vec<hir::Expr> {
+        if params.len() != args.len() {
+            let (expected, found) = (params.len(), args.len());
+            self.diagnostics.push(
+                Diagnostic::error(format!(
+                    "this function takes {expected} argument{} but {found} \
+                     argument{} {} supplied",
+                    plural(expected),
+                    plural(found),
+                    if found == 1 { "was" } else { "were" }
+                ))
+                .with_code("E0061")
+                .with_primary(span, ""),
+            );
+        }
+        args.iter()
+            .enumerate()
+            .map(|(index, arg)| match params.get(index) {
+                Some(param) => self.check_expr_coerce(arg, *param),
+                None => self.check_expr(arg, None),
+            })
+            .collect()
+    }
+
+    /// `a.f(b)` as `A::f(&a, b)`, the methods of the type of `a` first, then
+    /// of the types it dereferences to
+    fn check_method_call(
+        &mut self, receiver: &ast::Expr, method: &ast::Ident,
+        args: &[ast::Expr], span: Span,
+    ) -> hir::Expr {
+        let receiver = self.check_expr(receiver, None);
+        let receiver_ty = receiver.ty;
+        let name = method.node.as_str();
+        let (receiver, found) = self.autoderef(receiver, |fcx, ty| {
+            Some((fcx.lookup_method(ty, name, method.span)?, ty))
+        });
+        let Some((id, self_ty)) = found else {
+            if !self.has_error(receiver_ty) {
+                let found = self.display(receiver_ty);
+                self.diagnostics.push(
+                    Diagnostic::error(format!(
+                        "no method named `{name}` found for type `{found}` in \
+                         the current scope"
+                    ))
+                    .with_code("E0599")
+                    .with_primary(method.span, "method not found"),
+                );
+            }
+            for arg in args {
+                self.check_expr(arg, None);
+            }
+            return self.error_expr(span);
+        };
+        let has_self = self.tcx.fn_sig(id).unwrap().has_self;
+        self.check_vis(id, method.span);
+        if !has_self {
+            let self_ty = self.display(receiver.ty);
+            self.diagnostics.push(
+                Diagnostic::error(format!(
+                    "`{name}` is an associated function, not a method"
+                ))
+                .with_code("E0599")
+                .with_primary(method.span, "this is an associated function")
+                .with_help(format!("use `{self_ty}::{name}(...)` instead")),
+            );
+            for arg in args {
+                self.check_expr(arg, None);
+            }
+            return self.error_expr(span);
+        }
+        let fn_args = self.instantiate_assoc(id, self_ty, method.span);
+        let (params, ret) = self.instantiate_sig(id, &fn_args, method.span);
+        // `&self` and `&mut self` borrow the receiver
+        let receiver = match self.tcx.kind(params[0]).clone() {
+            TyKind::Ref { mutable, ty }
+                if self.resolve_deep(ty, &mut |_, ty| ty)
+                    == self.resolve_deep(receiver.ty, &mut |_, ty| ty) =>
+            {
+                hir::Expr {
+                    span: receiver.span,
+                    ty:   params[0],
+                    kind: hir::ExprKind::Ref {
+                        mutable,
+                        expr: Box::new(receiver),
+                        autoref: true,
+                    },
+                }
+            }
+            _ => self.coerce(receiver, params[0]),
+        };
+        let mut checked = vec![receiver];
+        checked.extend(self.check_args(&params[1..], args, span));
+        let callee = hir::Expr {
+            kind: hir::ExprKind::Def(id),
+            ty:   self.tcx.intern(TyKind::FnDef(id, fn_args)),
+            span: method.span,
+        };
+        hir::Expr {
+            kind: hir::ExprKind::Call(Box::new(callee), checked),
+            ty: ret,
+            span,
+        }
+    }
+
+    /// e.g. `a.b`, `a.0`, through references and boxes
+    fn check_field(
+        &mut self, base: hir::Expr, name: &ast::Ident, span: Span,
+    ) -> hir::Expr {
+        let base_ty = base.ty;
+        let name = name.node.as_str();
+        let (base, found) =
+            self.autoderef(base, |fcx, ty| match fcx.tcx.kind(ty) {
+                TyKind::Adt(id, _) => {
+                    let def = fcx.tcx.adt(*id)?;
+                    if def.kind == AdtKind::Enum {
+                        return None;
+                    }
+                    let fields = &def.variants[0].fields;
+                    let index =
+                        fields.iter().position(|field| field.name == name)?;
+                    let mut field = fields[index].clone();
+                    let adt_name = def.name.clone();
+                    field.ty = fcx.tcx.field_ty(ty, 0, index);
+                    Some((index, Some((field, adt_name))))
+                }
+                TyKind::Tuple(tys) => {
+                    let index = name
+                        .parse::<usize>()
+                        .ok()
+                        .filter(|i| *i < tys.len())?;
+                    let ty = tys[index];
+                    let def = FieldDef {
+                        name: name.to_string(),
+                        ty,
+                        vis: crate::semantic::resolve::Vis::Public,
+                        deprecated: None,
+                    };
+                    Some((index, Some((def, String::new()))))
+                }
+                _ => None,
+            });
+        let Some((index, Some((def, adt_name)))) = found else {
+            if !self.has_error(base_ty) {
+                let found = self.display(base_ty);
+                self.diagnostics.push(
+                    Diagnostic::error(format!(
+                        "no field `{name}` on type `{found}`"
+                    ))
+                    .with_code("E0609")
+                    .with_primary(span, "unknown field"),
+                );
+            }
+            return self.error_expr(span);
+        };
+        self.check_field_vis(&def, &adt_name, span);
+        hir::Expr {
+            kind: hir::ExprKind::Field(Box::new(base), index),
+            ty: def.ty,
+            span,
+        }
+    }
+
+    fn check_if(
+        &mut self, cond: &ast::Expr, then_branch: &ast::Block,
+        else_branch: Option<&ast::Expr>, expected: Option<Ty>, span: Span,
+    ) -> hir::Expr {
+        let bool = self.tcx.intern(TyKind::Bool);
+        let cond = self.check_expr_coerce(cond, bool);
+        let diverges = std::mem::take(&mut self.diverges);
+        let then = self.check_block_with(then_branch, expected, false);
+        let then_diverges = std::mem::take(&mut self.diverges);
+        let (ty, else_branch) = match else_branch {
+            Some(else_branch) => {
+                let checked = self.check_expr(else_branch, Some(then.ty));
+                let checked = match self.try_coerce(checked, then.ty) {
+                    Ok(checked) => checked,
+                    Err(checked) => {
+                        let checked = *checked;
+                        let (expected, found) =
+                            (self.display(then.ty), self.display(checked.ty));
+                        self.diagnostics.push(
+                            Diagnostic::error(
+                                "`if` and `else` have incompatible types",
+                            )
+                            .with_code("E0308")
+                            .with_primary(
+                                checked.span,
+                                format!(
+                                    "expected `{expected}`, found `{found}`"
+                                ),
+                            )
+                            .with_secondary(
+                                then.span,
+                                "expected because of this",
+                            ),
+                        );
+                        checked
+                    }
+                };
+                self.diverges = diverges || (then_diverges && self.diverges);
+                (then.ty, Some(Box::new(checked)))
+            }
+            None => {
+                let unit = self.tcx.unit();
+                if !self.unify(then.ty, unit) {
+                    let found = self.display(then.ty);
+                    self.diagnostics.push(
+                        Diagnostic::error(
+                            "`if` may be missing an `else` clause",
+                        )
+                        .with_code("E0317")
+                        .with_primary(
+                            then.span,
+                            format!("expected `()`, found `{found}`"),
+                        ),
+                    );
+                }
+                self.diverges = diverges;
+                (unit, None)
+            }
+        };
+        hir::Expr {
+            kind: hir::ExprKind::If {
+                cond: Box::new(cond),
+                then_branch: block_of(then),
+                else_branch,
+            },
+            ty,
+            span,
+        }
+    }
+
+    fn check_match(
+        &mut self, scrutinee: &ast::Expr, arms: &[ast::Arm],
+        expected: Option<Ty>, span: Span,
+    ) -> hir::Expr {
+        let scrutinee = self.check_expr(scrutinee, None);
+        let diverges = self.diverges;
+        let bool = self.tcx.intern(TyKind::Bool);
+        let mut ty = None::<Ty>;
+        let mut all_diverge = true;
+        let mut checked = Vec::new();
+        for arm in arms {
+            let pat = self.check_pat(&arm.pat, scrutinee.ty);
+            let guard = arm
+                .guard
+                .as_ref()
+                .map(|guard| self.check_expr_coerce(guard, bool));
+            self.diverges = false;
+            let body = self.check_expr(&arm.body, ty.or(expected));
+            all_diverge &= self.diverges;
+            let body = match ty {
+                None => {
+                    ty = Some(body.ty);
+                    body
+                }
+                Some(ty) => match self.try_coerce(body, ty) {
+                    Ok(body) => body,
+                    Err(body) => {
+                        let body = *body;
+                        let (expected, found) =
+                            (self.display(ty), self.display(body.ty));
+                        self.diagnostics.push(
+                            Diagnostic::error(
+                                "`match` arms have incompatible types",
+                            )
+                            .with_code("E0308")
+                            .with_primary(
+                                body.span,
+                                format!(
+                                    "expected `{expected}`, found `{found}`"
+                                ),
+                            )
+                            .with_secondary(
+                                checked
+                                    .first()
+                                    .map_or(span, |arm: &hir::Arm| {
+                                        arm.body.span
+                                    }),
+                                "this is found to be of the first type",
+                            ),
+                        );
+                        body
+                    }
+                },
+            };
+            checked.push(hir::Arm { pat, guard, body, span: arm.span });
+        }
+        self.diverges = diverges || all_diverge;
+        let ty = match ty {
+            Some(ty) => ty,
+            None => self.next_diverging(),
+        };
+        hir::Expr {
+            kind: hir::ExprKind::Match {
+                scrutinee: Box::new(scrutinee),
+                arms:      checked,
+            },
+            ty,
+            span,
+        }
+    }
+
+    /// the iterator of a `for` loop and the type of its items, an integer
+    /// range, an array or a reference to an array or a slice
+    fn check_iter(&mut self, iter: &ast::Expr) -> (hir::Expr, Ty) {
+        if let ExprKind::Range {
+            start: Some(start),
+            end: Some(end),
+            inclusive,
+        } = &iter.kind
+        {
+            let start = self.check_expr(start, None);
+            let end = self.check_expr_coerce(end, start.ty);
+            let ty = self.structurally_resolve(start.ty, start.span);
+            if !self.tcx.is_integral(ty) && !self.has_error(ty) {
+                let found = self.display(ty);
+                self.diagnostics.push(
+                    Diagnostic::error(format!("`{found}` is not an integer"))
+                        .with_primary(
+                            iter.span,
+                            "only integer ranges can be iterated",
+                        ),
+                );
+            }
+            let kind = hir::ExprKind::Range {
+                start:     Box::new(start),
+                end:       Box::new(end),
+                inclusive: *inclusive,
+            };
+            return (hir::Expr { kind, ty, span: iter.span }, ty);
+        }
+        let checked = self.check_expr(iter, None);
+        let ty = self.structurally_resolve(checked.ty, checked.span);
+        let elem = match self.tcx.kind(ty).clone() {
+            TyKind::Array(elem, _) => Some(elem),
+            TyKind::Ref { mutable, ty } => {
+                match *self.tcx.kind(self.resolve(ty)) {
+                    TyKind::Array(elem, _) | TyKind::Slice(elem) => {
+                        Some(self.tcx.intern(TyKind::Ref { mutable, ty: elem }))
+                    }
+                    _ => None,
+                }
+            }
+            TyKind::Error => Some(ty),
+            _ => None,
+        };
+        let Some(elem) = elem else {
+            let found = self.display(ty);
+            self.diagnostics.push(
+                Diagnostic::error(format!("`{found}` is not an iterator"))
+                    .with_code("E0277")
+                    .with_primary(iter.span, "")
+                    .with_note(
+                        "integer ranges, arrays and references to arrays and \
+                         slices can be iterated",
+                    ),
+            );
+            let error = self.tcx.error();
+            return (checked, error);
+        };
+        (checked, elem)
+    }
+
+    /// e.g. `println!("{a}")`
+    fn check_format(
+        &mut self, args: &ast::FormatArgs, span: Span,
+    ) -> hir::Expr {
+        let mut checked: Vec<_> =
+            args.args.iter().map(|arg| self.check_expr(arg, None)).collect();
+        let mut pieces = Vec::new();
+        let mut uses = Vec::new();
+        for piece in &args.pieces {
+            match piece {
+                ast::FormatPiece::Literal(text) => {
+                    pieces.push(hir::FormatPiece::Literal(text.clone()))
+                }
+                ast::FormatPiece::Placeholder(placeholder) => {
+                    let arg = match &placeholder.arg {
+                        FormatArg::Index(index) => *index,
+                        FormatArg::Expr(expr) => {
+                            checked.push(self.check_expr(expr, None));
+                            checked.len() - 1
+                        }
+                    };
+                    uses.push((
+                        arg,
+                        placeholder.spec.format_trait,
+                        placeholder.span,
+                    ));
+                    pieces.push(hir::FormatPiece::Placeholder {
+                        arg,
+                        spec: placeholder.spec,
+                    });
+                }
+            }
+        }
+        for (arg, format_trait, span) in uses {
+            let Some(arg) = checked.get(arg) else { continue };
+            if !self.formattable(arg.ty, format_trait) {
+                let found = self.display(arg.ty);
+                self.diagnostics.push(
+                    Diagnostic::error(format!(
+                        "`{found}` doesn't implement `{}`",
+                        format_trait.name()
+                    ))
+                    .with_code("E0277")
+                    .with_primary(arg.span, "")
+                    .with_secondary(span, "required by this placeholder"),
+                );
+            }
+        }
+        let ty = match args.mac {
+            FormatMacro::Format => {
+                let str = self.tcx.prim(PrimTy::Str);
+                self.tcx.intern(TyKind::Box(str))
+            }
+            _ => self.tcx.unit(),
+        };
+        hir::Expr {
+            kind: hir::ExprKind::Format {
+                mac: args.mac,
+                pieces,
+                args: checked,
+            },
+            ty,
+            span,
+        }
+    }
+
+    /// `ty` can be formatted with `format_trait`, e.g. a number with `{}`
+    fn formattable(&mut self, ty: Ty, format_trait: FormatTrait) -> bool {
+        let ty = self.resolve(ty);
+        match self.tcx.kind(ty).clone() {
+            TyKind::Error | TyKind::Infer(InferTy::Var(_)) => true,
+            TyKind::Ref { ty, .. } | TyKind::Box(ty) => {
+                self.formattable(ty, format_trait)
+            }
+            kind => match format_trait {
+                FormatTrait::Display => {
+                    self.tcx.is_numeric(ty)
+                        || matches!(
+                            kind,
+                            TyKind::Bool | TyKind::Char(_) | TyKind::Str
+                        )
+                }
+                FormatTrait::Debug => match kind {
+                    TyKind::Tuple(tys) => tys
+                        .into_iter()
+                        .all(|ty| self.formattable(ty, format_trait)),
+                    TyKind::Array(ty, _) | TyKind::Slice(ty) => {
+                        self.formattable(ty, format_trait)
+                    }
+                    TyKind::FnDef(..)
+                    | TyKind::Dynamic(_)
+                    | TyKind::Param(_)
+                    | TyKind::Projection { .. } => false,
+                    _ => true,
+                },
+                FormatTrait::LowerHex
+                | FormatTrait::UpperHex
+                | FormatTrait::Binary
+                | FormatTrait::Octal => self.tcx.is_integral(ty),
+                FormatTrait::LowerExp | FormatTrait::UpperExp => {
+                    self.tcx.is_numeric(ty)
+                }
+            },
+        }
+    }
+
+    fn check_pat(&mut self, pat: &ast::Pat, expected: Ty) -> hir::Pat {
+        let span = pat.span;
+        let typed = |kind, ty| hir::Pat { kind, ty, span };
+        match &pat.kind {
+            PatKind::Wild => typed(hir::PatKind::Wild, expected),
+            PatKind::Rest => {
+                self.diagnostics.push(
+                    Diagnostic::error("`..` patterns are not allowed here")
+                        .with_primary(span, ""),
+                );
+                typed(hir::PatKind::Wild, expected)
+            }
+            PatKind::Ident { by_ref, mutable, name, sub } => {
+                match self.res.path(pat.id).and_then(|res| res.full_res()) {
+                    // a later alternative of an `|` pattern
+                    Some(Res::Local(first)) => {
+                        let local = self.bindings[&first];
+                        self.bindings.insert(pat.id, local);
+                        let ty = self.locals[local.index()].ty;
+                        let ty = match by_ref {
+                            true => self.resolve_ref_target(ty),
+                            false => ty,
+                        };
+                        self.demand_pat(span, expected, ty);
+                        let sub = sub
+                            .as_ref()
+                            .map(|sub| Box::new(self.check_pat(sub, expected)));
+                        let kind = hir::PatKind::Binding {
+                            local,
+                            by_ref: *by_ref,
+                            sub,
+                        };
+                        typed(kind, expected)
+                    }
+                    Some(res) => self.check_path_pat(res, expected, span),
+                    None => {
+                        let ty = match by_ref {
+                            true => self.tcx.intern(TyKind::Ref {
+                                mutable: *mutable,
+                                ty:      expected,
+                            }),
+                            false => expected,
+                        };
+                        let local = self.new_local(
+                            name.node.as_str(),
+                            *mutable && !by_ref,
+                            ty,
+                            name.span,
+                        );
+                        self.bindings.insert(pat.id, local);
+                        let sub = sub
+                            .as_ref()
+                            .map(|sub| Box::new(self.check_pat(sub, expected)));
+                        let kind = hir::PatKind::Binding {
+                            local,
+                            by_ref: *by_ref,
+                            sub,
+                        };
+                        typed(kind, expected)
+                    }
+                }
+            }
+            PatKind::Literal(literal) => {
+                let ty = self.literal_ty(literal);
+                self.demand_pat(span, expected, ty);
+                typed(hir::PatKind::Literal(literal.clone()), expected)
+            }
+            PatKind::Path(path) => match self.res.path(path.id) {
+                Some(partial) if partial.unresolved == 0 => {
+                    self.check_path_pat(partial.base, expected, span)
+                }
+                Some(partial) => {
+                    let Some(variant) = self.variant_of_assoc(partial, path)
+                    else {
+                        return typed(hir::PatKind::Wild, expected);
+                    };
+                    self.check_path_pat(
+                        Res::Def(DefKind::Variant, variant),
+                        expected,
+                        span,
+                    )
+                }
+                None => typed(hir::PatKind::Wild, expected),
+            },
+            PatKind::Tuple(pats) => {
+                let (prefix, suffix) = self.split_rest(pats);
+                let resolved = self.resolve(expected);
+                let tys = match self.tcx.kind(resolved).clone() {
+                    TyKind::Tuple(tys) => tys,
+                    TyKind::Infer(InferTy::Var(_)) if suffix.is_none() => {
+                        let tys: Vec<_> = pats
+                            .iter()
+                            .map(|_| self.next_var(InferTy::Var))
+                            .collect();
+                        let tuple = self.tcx.intern(TyKind::Tuple(tys.clone()));
+                        self.unify(expected, tuple);
+                        tys
+                    }
+                    TyKind::Error => {
+                        return typed(hir::PatKind::Wild, expected);
+                    }
+                    _ => {
+                        let found =
+                            format!("({})", vec!["_"; pats.len()].join(", "));
+                        let expected_ty = self.display(expected);
+                        self.diagnostics.push(
+                            Diagnostic::error("mismatched types")
+                                .with_code("E0308")
+                                .with_primary(
+                                    span,
+                                    format!(
+                                        "expected `{expected_ty}`, found \
+                                         `{found}`"
+                                    ),
+                                ),
+                        );
+                        return typed(hir::PatKind::Wild, expected);
+                    }
+                };
+                let Some(fields) =
+                    self.expand_rest(prefix, suffix, &tys, span, "tuple")
+                else {
+                    return typed(hir::PatKind::Wild, expected);
+                };
+                let pats = fields.into_iter().map(|(_, pat)| pat).collect();
+                typed(hir::PatKind::Tuple(pats), expected)
+            }
+            PatKind::TupleStruct(path, pats) => {
+                let Some(variant) = self.variant_of_path(path) else {
+                    return typed(hir::PatKind::Wild, expected);
+                };
+                if variant.ctor != Some(CtorKind::Fn) {
+                    let name = self.res.def(variant.def).name.clone();
+                    self.diagnostics.push(
+                        Diagnostic::error(format!(
+                            "expected tuple struct or tuple variant, found \
+                             `{name}`"
+                        ))
+                        .with_code("E0532")
+                        .with_primary(path.span, ""),
+                    );
+                    return typed(hir::PatKind::Wild, expected);
+                }
+                self.variant_args(variant, path);
+                self.demand_pat(span, expected, variant.ty);
+                let tys = self.field_tys(variant);
+                let (prefix, suffix) = self.split_rest(pats);
+                let Some(fields) = self
+                    .expand_rest(prefix, suffix, &tys, span, "tuple variant")
+                else {
+                    return typed(hir::PatKind::Wild, expected);
+                };
+                let kind = hir::PatKind::Adt {
+                    adt: variant.adt,
+                    variant: variant.index,
+                    fields,
+                };
+                typed(kind, expected)
+            }
+            PatKind::Struct { path, fields, rest } => {
+                let Some(variant) = self.variant_of_path(path) else {
+                    return typed(hir::PatKind::Wild, expected);
+                };
+                self.variant_args(variant, path);
+                self.demand_pat(span, expected, variant.ty);
+                let def =
+                    &self.tcx.adt(variant.adt).unwrap().variants[variant.index];
+                let adt_name = def.name.clone();
+                let defs = def.fields.clone();
+                let tys = self.field_tys(variant);
+                let mut checked = Vec::<(usize, hir::Pat)>::new();
+                for field in fields {
+                    let name = field.name.node.as_str();
+                    let Some(index) =
+                        defs.iter().position(|def| def.name == name)
+                    else {
+                        self.diagnostics.push(
+                            Diagnostic::error(format!(
+                                "`{adt_name}` does not have a field named \
+                                 `{name}`"
+                            ))
+                            .with_code("E0026")
+                            .with_primary(field.name.span, ""),
+                        );
+                        continue;
+                    };
+                    self.check_field_vis(
+                        &defs[index], &adt_name, field.name.span,
+                    );
+                    let pat = self.check_pat(&field.pat, tys[index]);
+                    checked.push((index, pat));
+                }
+                if !rest {
+                    let missing: Vec<_> = defs
+                        .iter()
+                        .enumerate()
+                        .filter(|(index, _)| {
+                            checked.iter().all(|(other, _)| other != index)
+                        })
+                        .map(|(_, def)| format!("`{}`", def.name))
+                        .collect();
+                    if !missing.is_empty() {
+                        self.diagnostics.push(
+                            Diagnostic::error(format!(
+                                "pattern does not mention field{} {}",
+                                plural(missing.len()),
+                                missing.join(", ")
+                            ))
+                            .with_code("E0027")
+                            .with_primary(span, "missing fields")
+                            .with_help("ignore the other fields with `..`"),
+                        );
+                    }
+                }
+                checked.sort_by_key(|(index, _)| *index);
+                let kind = hir::PatKind::Adt {
+                    adt:     variant.adt,
+                    variant: variant.index,
+                    fields:  checked,
+                };
+                typed(kind, expected)
+            }
+            PatKind::Slice(pats) => {
+                let resolved = self.structurally_resolve(expected, span);
+                let (elem, len) = match *self.tcx.kind(resolved) {
+                    TyKind::Array(elem, len) => (elem, Some(len)),
+                    TyKind::Slice(elem) => (elem, None),
+                    TyKind::Error => {
+                        return typed(hir::PatKind::Wild, expected);
+                    }
+                    _ => {
+                        let expected_ty = self.display(expected);
+                        self.diagnostics.push(
+                            Diagnostic::error(format!(
+                                "expected an array or slice, found \
+                                 `{expected_ty}`"
+                            ))
+                            .with_code("E0529")
+                            .with_primary(
+                                span,
+                                "pattern cannot match with input type",
+                            ),
+                        );
+                        return typed(hir::PatKind::Wild, expected);
+                    }
+                };
+                let (prefix, suffix) = self.split_rest(pats);
+                let count =
+                    prefix.len() + suffix.map_or(0, |suffix| suffix.len());
+                if let Some(len) = len
+                    && (count as u64 > len
+                        || (suffix.is_none() && (count as u64) < len))
+                {
+                    self.diagnostics.push(
+                        Diagnostic::error(format!(
+                            "pattern requires {}{count} element{} but array \
+                             has {len}",
+                            if suffix.is_some() { "at least " } else { "" },
+                            plural(count),
+                        ))
+                        .with_code(match suffix {
+                            Some(_) => "E0528",
+                            None => "E0527",
+                        })
+                        .with_primary(span, ""),
+                    );
+                }
+                if len.is_none() && suffix.is_none() {
+                    // a slice of any length unless it has `..`
+                }
+                let prefix = prefix
+                    .iter()
+                    .map(|pat| self.check_pat(pat, elem))
+                    .collect();
+                let (rest, suffix) = match suffix {
+                    Some(suffix) => (
+                        true,
+                        suffix
+                            .iter()
+                            .map(|pat| self.check_pat(pat, elem))
+                            .collect(),
+                    ),
+                    None => (false, Vec::new()),
+                };
+                typed(hir::PatKind::Slice { prefix, rest, suffix }, expected)
+            }
+            PatKind::Range { start, end, inclusive } => {
+                let start = start
+                    .as_ref()
+                    .map(|start| Box::new(self.check_pat(start, expected)));
+                let end = end
+                    .as_ref()
+                    .map(|end| Box::new(self.check_pat(end, expected)));
+                let resolved = self.resolve(expected);
+                let valid = self.tcx.is_integral(resolved)
+                    || matches!(
+                        self.tcx.kind(resolved),
+                        TyKind::Char(_) | TyKind::Error
+                    );
+                if !valid {
+                    self.diagnostics.push(
+                        Diagnostic::error(
+                            "only `char` and integer types are allowed in \
+                             range patterns",
+                        )
+                        .with_code("E0029")
+                        .with_primary(span, ""),
+                    );
+                }
+                let kind =
+                    hir::PatKind::Range { start, end, inclusive: *inclusive };
+                typed(kind, expected)
+            }
+            PatKind::Ref { mutable, pat: inner } => {
+                let resolved = self.resolve(expected);
+                let inner_ty = match *self.tcx.kind(resolved) {
+                    TyKind::Ref { mutable: m, ty } if m == *mutable => ty,
+                    TyKind::Error => resolved,
+                    _ => {
+                        let ty = self.next_var(InferTy::Var);
+                        let reference = self
+                            .tcx
+                            .intern(TyKind::Ref { mutable: *mutable, ty });
+                        self.demand_pat(span, expected, reference);
+                        ty
+                    }
+                };
+                let inner = self.check_pat(inner, inner_ty);
+                typed(hir::PatKind::Ref(Box::new(inner)), expected)
+            }
+            PatKind::Or(pats) => {
+                let pats = pats
+                    .iter()
+                    .map(|pat| self.check_pat(pat, expected))
+                    .collect();
+                typed(hir::PatKind::Or(pats), expected)
+            }
+        }
+    }
+
+    /// a constant, a unit struct or a unit variant
+    fn check_path_pat(
+        &mut self, res: Res, expected: Ty, span: Span,
+    ) -> hir::Pat {
+        let typed = |kind, ty| hir::Pat { kind, ty, span };
+        if let Some(variant) = self.variant_of(res) {
+            if variant.ctor != Some(CtorKind::Const) {
+                let name = self.res.def(variant.def).name.clone();
+                let ctor = match variant.ctor {
+                    Some(_) => format!("`{name}(..)`"),
+                    None => format!("`{name} {{ .. }}`"),
+                };
+                self.diagnostics.push(
+                    Diagnostic::error(format!(
+                        "expected unit struct, unit variant or constant, \
+                         found `{name}`"
+                    ))
+                    .with_code("E0532")
+                    .with_primary(span, format!("did you mean {ctor}?")),
+                );
+                return typed(hir::PatKind::Wild, expected);
+            }
+            self.demand_pat(span, expected, variant.ty);
+            let kind = hir::PatKind::Adt {
+                adt:     variant.adt,
+                variant: variant.index,
+                fields:  Vec::new(),
+            };
+            return typed(kind, expected);
+        }
+        match res {
+            Res::Def(DefKind::Const | DefKind::AssocConst, id) => {
+                if let Some(ty) = self.tcx.type_of(id) {
+                    self.demand_pat(span, expected, ty);
+                }
+                typed(hir::PatKind::Const(id), expected)
+            }
+            _ => typed(hir::PatKind::Wild, expected),
+        }
+    }
+
+    /// the patterns before `..` and after it, [None] without `..`
+    fn split_rest<'p>(
+        &mut self, pats: &'p [ast::Pat],
+    ) -> (&'p [ast::Pat], Option<&'p [ast::Pat]>) {
+        let mut rests = pats
+            .iter()
+            .enumerate()
+            .filter(|(_, pat)| pat.kind == PatKind::Rest);
+        let Some((index, _)) = rests.next() else { return (pats, None) };
+        for (_, pat) in rests {
+            self.diagnostics.push(
+                Diagnostic::error("`..` can only be used once per pattern")
+                    .with_primary(
+                        pat.span,
+                        "can only be used once per pattern",
+                    ),
+            );
+        }
+        let suffix =
+            pats[index + 1..].iter().filter(|pat| pat.kind != PatKind::Rest);
+        let suffix = &pats[pats.len() - suffix.count()..];
+        (&pats[..index], Some(suffix))
+    }
+
+    /// the patterns of the fields of `tys` by their index, [None] after an
+    /// error
+    fn expand_rest(
+        &mut self, prefix: &[ast::Pat], suffix: Option<&[ast::Pat]>,
+        tys: &[Ty], span: Span, descr: &str,
+    ) -> Option<Vec<(usize, hir::Pat)>> {
+        let count = prefix.len() + suffix.map_or(0, |suffix| suffix.len());
+        if count > tys.len() || (suffix.is_none() && count < tys.len()) {
+            self.diagnostics.push(
+                Diagnostic::error(format!(
+                    "this pattern has {count} field{}, but the corresponding \
+                     {descr} has {} field{}",
+                    plural(count),
+                    tys.len(),
+                    plural(tys.len())
+                ))
+                .with_code("E0023")
+                .with_primary(
+                    span,
+                    format!(
+                        "expected {} field{}",
+                        tys.len(),
+                        plural(tys.len())
+                    ),
+                ),
+            );
+            return None;
+        }
+        let mut fields = Vec::new();
+        for (index, pat) in prefix.iter().enumerate() {
+            fields.push((index, self.check_pat(pat, tys[index])));
+        }
+        let suffix = suffix.unwrap_or_default();
+        let start = tys.len() - suffix.len();
+        for (index, ty) in tys.iter().enumerate().take(start).skip(prefix.len())
+        {
+            let wild = hir::Pat { kind: hir::PatKind::Wild, ty: *ty, span };
+            fields.push((index, wild));
+        }
+        for (offset, pat) in suffix.iter().enumerate() {
+            fields.push((
+                start + offset,
+                self.check_pat(pat, tys[start + offset]),
+            ));
+        }
+        Some(fields)
+    }
+
+    /// reports a pattern of type `found` for a value of type `expected`
+    fn demand_pat(&mut self, span: Span, expected: Ty, found: Ty) {
+        if !self.unify(expected, found) {
+            self.mismatch(span, expected, found);
+        }
+    }
+
+    /// `ty` is the type of a `ref a` binding
+    fn resolve_ref_target(&mut self, ty: Ty) -> Ty {
+        match *self.tcx.kind(self.resolve(ty)) {
+            TyKind::Ref { ty, .. } => ty,
+            _ => ty,
+        }
+    }
+
+    /// the struct or the variant `res` constructs, with fresh inference
+    /// variables for the generic arguments of its ADT
+    fn variant_of(&mut self, res: Res) -> Option<Variant> {
+        let struct_of = |fcx: &mut Self, ty: Ty| match *fcx.tcx.kind(ty) {
+            TyKind::Adt(adt, _) if fcx.res.def(adt).kind == DefKind::Struct => {
+                Some(Variant {
+                    adt,
+                    index: 0,
+                    def: adt,
+                    ctor: fcx.res.def(adt).ctor,
+                    ty,
+                })
+            }
+            _ => None,
+        };
+        match res {
+            Res::Def(DefKind::Struct | DefKind::Union, adt) => Some(Variant {
+                adt,
+                index: 0,
+                def: adt,
+                ctor: self.res.def(adt).ctor,
+                ty: self.fresh_adt(adt),
+            }),
+            Res::Def(DefKind::Variant, def) => {
+                let adt = self.res.def(def).parent.unwrap();
+                let index = self
+                    .res
+                    .def(adt)
+                    .children
+                    .iter()
+                    .position(|v| *v == def)?;
+                let ctor = self.res.def(def).ctor;
+                Some(Variant { adt, index, def, ctor, ty: self.fresh_adt(adt) })
+            }
+            Res::SelfTy(id) => match self.res.def(id).kind {
+                DefKind::Struct | DefKind::Union => {
+                    self.variant_of(Res::Def(DefKind::Struct, id))
+                }
+                DefKind::Impl => {
+                    let ty = self.tcx.type_of(id)?;
+                    struct_of(self, ty)
+                }
+                _ => None,
+            },
+            Res::Def(DefKind::TypeAlias, id) => {
+                let ty = self.tcx.type_of(id)?;
+                struct_of(self, ty)
+            }
+            _ => None,
+        }
+    }
+
+    /// the variant of `Self::A` or `Alias::A`
+    fn variant_of_assoc(
+        &mut self, partial: PartialRes, path: &ast::Path,
+    ) -> Option<DefId> {
+        let self_ty = self.self_ty_of(partial, path);
+        let name = path.segments.last()?.ident.node.as_str();
+        let variant = match *self.tcx.kind(self_ty) {
+            TyKind::Adt(adt, _) if partial.unresolved == 1 => {
+                self.res.def(adt).children.iter().copied().find(|v| {
+                    let def = self.res.def(*v);
+                    def.kind == DefKind::Variant && def.name == name
+                })
+            }
+            _ => None,
+        };
+        if variant.is_none() && !self.has_error(self_ty) {
+            let found = self.display(self_ty);
+            self.diagnostics.push(
+                Diagnostic::error(format!(
+                    "no variant named `{name}` found for `{found}`"
+                ))
+                .with_code("E0599")
+                .with_primary(path.span, "variant not found"),
+            );
+        }
+        variant
+    }
+
+    /// the struct or the variant of a struct expression or pattern
+    fn variant_of_path(&mut self, path: &ast::Path) -> Option<Variant> {
+        let partial = self.res.path(path.id)?;
+        let res = match partial.unresolved {
+            0 => partial.base,
+            _ => Res::Def(
+                DefKind::Variant,
+                self.variant_of_assoc(partial, path)?,
+            ),
+        };
+        let variant = self.variant_of(res);
+        if variant.is_none() && res != Res::Err {
+            self.diagnostics.push(
+                Diagnostic::error(format!(
+                    "expected struct, variant or union type, found {}",
+                    res.descr()
+                ))
+                .with_code("E0071")
+                .with_primary(path.span, "not a struct, variant or union type"),
+            );
+        }
+        variant
+    }
+
+    /// [FnCtxt::variant_of_path] without reporting other paths
+    fn variant_of_path_quiet(&mut self, path: &ast::Path) -> Option<Variant> {
+        let partial = self.res.path(path.id)?;
+        match partial.unresolved {
+            0 => self.variant_of(partial.base),
+            _ => {
+                let self_ty = self.self_ty_of(partial, path);
+                let name = path.segments.last()?.ident.node.as_str();
+                if partial.unresolved > 1
+                    || self.lookup_assoc_quiet(self_ty, name).is_some()
+                {
+                    return None;
+                }
+                let TyKind::Adt(adt, _) = *self.tcx.kind(self_ty) else {
+                    return None;
+                };
+                let variant =
+                    self.res.def(adt).children.iter().copied().find(|v| {
+                        let def = self.res.def(*v);
+                        def.kind == DefKind::Variant && def.name == name
+                    })?;
+                let mut variant =
+                    self.variant_of(Res::Def(DefKind::Variant, variant))?;
+                variant.ty = self_ty;
+                Some(variant)
+            }
+        }
+    }
+
+    /// the type before the associated segments of a path, e.g. `A` of
+    /// `A::new`, and `A<_>` for a generic ADT without arguments
+    fn self_ty_of(&mut self, partial: PartialRes, path: &ast::Path) -> Ty {
+        let base = path.segments.len() - 1 - partial.unresolved;
+        let segment = &path.segments[base];
+        let ty = match partial.base {
+            Res::PrimTy(prim) => Some(self.tcx.prim(prim)),
+            Res::Def(DefKind::Struct | DefKind::Enum | DefKind::Union, id) => {
+                let ty = self.fresh_adt(id);
+                if let Some(args) = self.lower_args(segment) {
+                    self.apply_args(ty, id, 0, args, segment);
+                }
+                Some(ty)
+            }
+            Res::Def(DefKind::TypeAlias, id) => self.tcx.type_of(id),
+            Res::Def(DefKind::TyParam, id) => {
+                Some(self.tcx.intern(TyKind::Param(id)))
+            }
+            Res::SelfTy(id) => match self.res.def(id).kind {
+                DefKind::Impl => self.tcx.type_of(id),
+                DefKind::Struct | DefKind::Enum | DefKind::Union => {
+                    let params = self.tcx.generics(id).to_vec();
+                    let args = params
+                        .iter()
+                        .map(|param| self.tcx.intern(TyKind::Param(*param)))
+                        .collect();
+                    Some(self.tcx.intern(TyKind::Adt(id, args)))
+                }
+                DefKind::Trait => Some(self.tcx.intern(TyKind::Param(id))),
+                _ => None,
+            },
+            _ => None,
+        };
+        if !matches!(
+            partial.base,
+            Res::Def(DefKind::Struct | DefKind::Enum | DefKind::Union, _)
+        ) {
+            self.no_args(segment);
+        }
+        match ty {
+            Some(ty) => ty,
+            None => self.tcx.error(),
+        }
+    }
+
+    /// an associated function or constant of an inherent impl of `self_ty`
+    fn lookup_assoc(
+        &mut self, self_ty: Ty, name: &str, span: Span,
+    ) -> Option<DefId> {
+        let id = self.lookup_assoc_quiet(self_ty, name)?;
+        self.check_vis(id, span);
+        Some(id)
+    }
+
+    fn lookup_assoc_quiet(&mut self, self_ty: Ty, name: &str) -> Option<DefId> {
+        let impls = self.tcx.inherent_impls().to_vec();
+        let impls: Vec<_> = impls
+            .into_iter()
+            .filter(|impl_| self.probe_impl(*impl_, self_ty))
+            .collect();
+        let res = self.res;
+        impls
+            .into_iter()
+            .flat_map(|impl_| res.def(impl_).children.iter().copied())
+            .find(|id| {
+                let def = self.res.def(*id);
+                matches!(def.kind, DefKind::AssocFn | DefKind::AssocConst)
+                    && def.name == name
+            })
+    }
+
+    /// the self type of `impl_` unifies with `ty` for some generic
+    /// arguments of the impl, without unifying them
+    fn probe_impl(&mut self, impl_: DefId, ty: Ty) -> bool {
+        let snapshot = self.vars.clone();
+        let impl_ty = self.fresh_impl(impl_);
+        let unified = self.unify(impl_ty, ty);
+        self.vars = snapshot;
+        unified
+    }
+
+    /// the self type of `impl_` with fresh inference variables for its
+    /// generic arguments
+    fn fresh_impl(&mut self, impl_: DefId) -> Ty {
+        let count = self.tcx.generics(impl_).len();
+        let args: Vec<_> =
+            (0..count).map(|_| self.next_var(InferTy::Var)).collect();
+        let impl_ty = self.tcx.type_of(impl_).unwrap();
+        self.tcx.instantiate(impl_ty, impl_, &args)
+    }
+
+    /// the ADT `id` with fresh inference variables for its generic
+    /// arguments
+    fn fresh_adt(&mut self, id: DefId) -> Ty {
+        let count = self.tcx.generics(id).len();
+        let args = (0..count).map(|_| self.next_var(InferTy::Var)).collect();
+        self.tcx.intern(TyKind::Adt(id, args))
+    }
+
+    /// the types of the fields of `variant` with its generic arguments
+    fn field_tys(&mut self, variant: Variant) -> Vec<Ty> {
+        let def = &self.tcx.adt(variant.adt).unwrap().variants[variant.index];
+        (0..def.fields.len())
+            .map(|index| self.tcx.field_ty(variant.ty, variant.index, index))
+            .collect()
+    }
+
+    /// the types of the generic arguments of `segment`, e.g. `[i32]` of
+    /// `f::<i32>`
+    fn lower_args(&mut self, segment: &ast::PathSegment) -> Option<Vec<Ty>> {
+        let args = segment.args.as_ref()?;
+        Some(args.args.iter().map(|ty| self.lower_ty(ty)).collect())
+    }
+
+    /// unifies the generic arguments of the type `ty` of `id` from `start`
+    /// with the written `args` of `segment`, which must be as many
+    fn apply_args(
+        &mut self, ty: Ty, id: DefId, start: usize, args: Vec<Ty>,
+        segment: &ast::PathSegment,
+    ) {
+        let (TyKind::Adt(_, params) | TyKind::FnDef(_, params)) =
+            self.tcx.kind(ty).clone()
+        else {
+            return;
+        };
+        let params = &params[start.min(params.len())..];
+        if params.len() != args.len() {
+            let def = self.res.def(id);
+            let (expected, found) = (params.len(), args.len());
+            let span = segment.args.as_ref().unwrap().span;
+            self.diagnostics.push(
+                Diagnostic::error(format!(
+                    "{} takes {expected} generic argument{} but {found} \
+                     generic argument{} supplied",
+                    def.kind.descr(),
+                    plural(expected),
+                    if found == 1 { " was" } else { "s were" }
+                ))
+                .with_code("E0107")
+                .with_primary(
+                    span,
+                    format!(
+                        "expected {expected} generic argument{}",
+                        plural(expected)
+                    ),
+                ),
+            );
+            return;
+        }
+        for (param, arg) in params.iter().zip(args) {
+            self.unify(*param, arg);
+        }
+    }
+
+    /// the written generic arguments of a function, e.g. `f::<i32>` or
+    /// `A::new::<i32>`, after the parameters of its impl or trait
+    fn fn_args(&mut self, expr: &hir::Expr, segment: &ast::PathSegment) {
+        let Some(args) = self.lower_args(segment) else { return };
+        let TyKind::FnDef(id, _) = *self.tcx.kind(expr.ty) else {
+            if expr.ty != self.tcx.error() {
+                self.no_args(segment);
+            }
+            return;
+        };
+        let parent = self.res.def(id).parent;
+        let start = match parent.map(|parent| self.res.def(parent).kind) {
+            Some(DefKind::Impl | DefKind::Trait) => {
+                self.tcx.generics(parent.unwrap()).len()
+                    + self.is_trait_item(id) as usize
+            }
+            _ => 0,
+        };
+        self.apply_args(expr.ty, id, start, args, segment);
+    }
+
+    /// the written generic arguments of the ADT of a struct or a variant,
+    /// e.g. `A::<i32> { .. }`, `B::<i32>::C(1)` or `B::C::<i32>(1)`
+    fn variant_args(&mut self, variant: Variant, path: &ast::Path) {
+        let segments = match variant.def == variant.adt {
+            true => &path.segments[path.segments.len() - 1..],
+            false => &path.segments[path.segments.len().saturating_sub(2)..],
+        };
+        for segment in segments {
+            if let Some(args) = self.lower_args(segment) {
+                self.apply_args(variant.ty, variant.adt, 0, args, segment);
+            }
+        }
+    }
+
+    /// reports the generic arguments of `segment`, which takes none
+    fn no_args(&mut self, segment: &ast::PathSegment) {
+        if let Some(args) = &segment.args {
+            self.diagnostics.push(
+                Diagnostic::error(
+                    "type arguments are not allowed on this type",
+                )
+                .with_code("E0109")
+                .with_primary(args.span, "type argument not allowed"),
+            );
+        }
+    }
+
+    /// a method of an inherent impl of `self_ty`, or of a trait it
+    /// implements
+    fn lookup_method(
+        &mut self, self_ty: Ty, name: &str, span: Span,
+    ) -> Option<DefId> {
+        let inherent = self
+            .lookup_assoc_quiet(self_ty, name)
+            .filter(|id| self.res.def(*id).kind == DefKind::AssocFn);
+        inherent.or_else(|| self.lookup_trait_item(self_ty, name, true, span))
+    }
+
+    /// a method, or an associated constant unless `method`, of a trait
+    /// `self_ty` may implement, see [FnCtxt::may_implement]
+    fn lookup_trait_item(
+        &mut self, self_ty: Ty, name: &str, method: bool, span: Span,
+    ) -> Option<DefId> {
+        let mut traits: Vec<_> = self.tcx.traits().map(|(id, _)| id).collect();
+        traits.sort();
+        let mut found = Vec::new();
+        for trait_ in traits {
+            let item =
+                self.res.def(trait_).children.iter().copied().find(|item| {
+                    let def = self.res.def(*item);
+                    let kind = match method {
+                        true => def.kind == DefKind::AssocFn,
+                        false => matches!(
+                            def.kind,
+                            DefKind::AssocFn | DefKind::AssocConst
+                        ),
+                    };
+                    kind && def.name == name
+                });
+            if let Some(item) = item
+                && self.may_implement(self_ty, trait_)
+            {
+                found.push(item);
+            }
+        }
+        if found.len() > 1 {
+            let mut diagnostic =
+                Diagnostic::error("multiple applicable items in scope")
+                    .with_code("E0034")
+                    .with_primary(span, format!("multiple `{name}` found"));
+            for item in &found {
+                let trait_ = self.res.def(*item).parent.unwrap();
+                diagnostic = diagnostic.with_secondary(
+                    self.res.def(*item).span,
+                    format!(
+                        "candidate is defined in the trait `{}`",
+                        self.res.def(trait_).name
+                    ),
+                );
+            }
+            self.diagnostics.push(diagnostic);
+        }
+        found.first().copied()
+    }
+
+    /// `ty` implements `trait_` by a bound or as a trait object, or may by
+    /// an impl once its inference variables are known
+    fn may_implement(&mut self, ty: Ty, trait_: DefId) -> bool {
+        let ty = self.resolve_deep(ty, &mut |_, ty| ty);
+        if let TyKind::Dynamic(traits) = self.tcx.kind(ty)
+            && traits.contains(&trait_)
+        {
+            return true;
+        }
+        let bounds = self.tcx.predicates(self.owner);
+        if bounds.iter().any(|bound| bound.ty == ty && bound.trait_ == trait_) {
+            return true;
+        }
+        self.impls_for(ty, trait_) > 0
+    }
+
+    /// the number of impls of `trait_` whose types unify with `ty`
+    fn impls_for(&mut self, ty: Ty, trait_: DefId) -> usize {
+        let impls = self.tcx.trait_impls(trait_).to_vec();
+        impls.into_iter().filter(|impl_| self.probe_impl(*impl_, ty)).count()
+    }
+
+    /// `id` is an item of a trait, e.g. `f` of `trait A { fn f(); }`
+    fn is_trait_item(&self, id: DefId) -> bool {
+        let parent = self.res.def(id).parent;
+        parent.is_some_and(|parent| self.res.def(parent).kind == DefKind::Trait)
+    }
+
+    /// [FnCtxt::instantiate] for an associated function or constant `id`
+    /// of `self_ty`, with the generic arguments of its impl or `Self` of
+    /// its trait unified with `self_ty`
+    fn instantiate_assoc(
+        &mut self, id: DefId, self_ty: Ty, span: Span,
+    ) -> Vec<Ty> {
+        let parent = self.res.def(id).parent.unwrap();
+        let args = match self.res.def(id).kind {
+            DefKind::AssocFn => self.instantiate(id, span),
+            _ => self.instantiate(parent, span),
+        };
+        match self.res.def(parent).kind {
+            DefKind::Trait => {
+                self.unify(args[0], self_ty);
+            }
+            _ => {
+                let impl_ty = self.tcx.type_of(parent).unwrap();
+                // the parameters of the impl come first
+                let impl_ty = self.tcx.instantiate(impl_ty, parent, &args);
+                self.unify(impl_ty, self_ty);
+            }
+        }
+        args
+    }
+
+    /// fresh inference variables for the generic parameters of `id`, and
+    /// the obligations of its predicates
+    fn instantiate(&mut self, id: DefId, span: Span) -> Vec<Ty> {
+        let params = self.tcx.generics(id).to_vec();
+        let args: Vec<_> =
+            params.iter().map(|_| self.next_var(InferTy::Var)).collect();
+        for predicate in self.tcx.predicates(id).to_vec() {
+            let ty = self.tcx.subst(predicate.ty, &params, &args);
+            let ty = self.normalize(ty, span);
+            self.obligations.push(Obligation::Trait {
+                ty,
+                trait_: predicate.trait_,
+                span,
+            });
+        }
+        args
+    }
+
+    /// the parameters and the return type of the function `id` with the
+    /// generic arguments `args`
+    fn instantiate_sig(
+        &mut self, id: DefId, args: &[Ty], span: Span,
+    ) -> (Vec<Ty>, Ty) {
+        let sig = self.tcx.fn_sig(id).unwrap().clone();
+        let params = self.tcx.generics(id).to_vec();
+        let subst = |fcx: &mut Self, ty| {
+            let ty = fcx.tcx.subst(ty, &params, args);
+            fcx.normalize(ty, span)
+        };
+        let tys = sig.params.iter().map(|ty| subst(self, *ty)).collect();
+        (tys, subst(self, sig.ret))
+    }
+
+    /// `ty` with its projections normalized, and the projections of
+    /// types not known yet replaced by inference variables, see
+    /// [Obligation::Projection]
+    fn normalize(&mut self, ty: Ty, span: Span) -> Ty {
+        let ty = self.resolve_deep(ty, &mut |_, ty| ty);
+        let ty = self.tcx.normalize(ty);
+        let mut pending = Vec::new();
+        self.tcx.any(ty, &mut |ty| {
+            if let TyKind::Projection { ty: self_ty, .. } = self.tcx.kind(ty)
+                && self.tcx.has_params(*self_ty)
+                && self.tcx.any(*self_ty, &mut |ty| {
+                    matches!(self.tcx.kind(ty), TyKind::Infer(_))
+                })
+            {
+                pending.push(ty);
+            }
+            false
+        });
+        if pending.is_empty() {
+            return ty;
+        }
+        let vars: Vec<_> =
+            pending.iter().map(|_| self.next_var(InferTy::Var)).collect();
+        for (projection, var) in pending.iter().zip(&vars) {
+            self.obligations.push(Obligation::Projection {
+                projection: *projection,
+                var: *var,
+                span,
+            });
+        }
+        self.tcx.fold(ty, &mut |_, ty| {
+            let index = pending.iter().position(|pending| *pending == ty)?;
+            Some(vars[index])
+        })
+    }
+
+    /// proves the obligations whose types are known, and reports the
+    /// others if `last`
+    fn select(&mut self, last: bool) {
+        loop {
+            let obligations = std::mem::take(&mut self.obligations);
+            let count = obligations.len();
+            for obligation in obligations {
+                if !self.select_one(obligation, last) {
+                    self.obligations.push(obligation);
+                }
+            }
+            if self.obligations.is_empty() || self.obligations.len() == count {
+                break;
+            }
+        }
+    }
+
+    /// proves `obligation`, `false` if it depends on unknown types
+    fn select_one(&mut self, obligation: Obligation, last: bool) -> bool {
+        match obligation {
+            Obligation::Trait { ty, trait_, span } => {
+                let ty = self.resolve_deep(ty, &mut |_, ty| ty);
+                if self.has_error(ty) {
+                    return true;
+                }
+                if let TyKind::Infer(InferTy::Var(_)) = self.tcx.kind(ty) {
+                    // reported as an unknown type
+                    return last;
+                }
+                let bounds = self.tcx.predicates(self.owner);
+                let bound = bounds
+                    .iter()
+                    .any(|bound| bound.ty == ty && bound.trait_ == trait_);
+                let dynamic = matches!(
+                    self.tcx.kind(ty),
+                    TyKind::Dynamic(traits) if traits.contains(&trait_)
+                );
+                if bound || dynamic {
+                    return true;
+                }
+                let impls = self.tcx.trait_impls(trait_).to_vec();
+                let mut found = impls
+                    .iter()
+                    .copied()
+                    .filter(|impl_| self.probe_impl(*impl_, ty));
+                match (found.next(), found.next()) {
+                    (Some(impl_), None) => {
+                        // the bounds of the impl, e.g. `T: A` of
+                        // `impl<T: A> A for B<T>`
+                        let args = self.instantiate(impl_, span);
+                        let impl_ty = self.tcx.type_of(impl_).unwrap();
+                        let impl_ty =
+                            self.tcx.instantiate(impl_ty, impl_, &args);
+                        self.unify(impl_ty, ty);
+                        true
+                    }
+                    (None, _) => {
+                        self.unsatisfied(ty, trait_, span);
+                        true
+                    }
+                    (Some(_), Some(_)) if last => {
+                        let (ty, name) =
+                            (self.display(ty), self.tcx.name(trait_));
+                        self.diagnostics.push(
+                            Diagnostic::error("type annotations needed")
+                                .with_code("E0282")
+                                .with_primary(
+                                    span,
+                                    format!("cannot satisfy `{ty}: {name}`"),
+                                )
+                                .with_note(format!(
+                                    "multiple impls of trait `{name}` apply"
+                                )),
+                        );
+                        true
+                    }
+                    (Some(_), Some(_)) => false,
+                }
+            }
+            Obligation::Projection { projection, var, span } => {
+                let projection = self.resolve_deep(projection, &mut |_, ty| ty);
+                let normalized = self.tcx.normalize(projection);
+                if let TyKind::Projection { ty, .. } =
+                    *self.tcx.kind(normalized)
+                    && !last
+                    && self.tcx.any(ty, &mut |ty| {
+                        matches!(self.tcx.kind(ty), TyKind::Infer(_))
+                    })
+                {
+                    return false;
+                }
+                if !self.unify(var, normalized) {
+                    self.mismatch(span, normalized, var);
+                }
+                true
+            }
+        }
+    }
+
+    /// reports `ty: trait_` without an impl
+    fn unsatisfied(&mut self, ty: Ty, trait_: DefId, span: Span) {
+        let (found, name) =
+            (self.display(ty), self.tcx.name(trait_).to_string());
+        let mut diagnostic = Diagnostic::error(format!(
+            "the trait bound `{found}: {name}` is not satisfied"
+        ))
+        .with_code("E0277")
+        .with_primary(
+            span,
+            format!("the trait `{name}` is not implemented for `{found}`"),
+        );
+        let mut impls: Vec<_> = self
+            .tcx
+            .trait_impls(trait_)
+            .iter()
+            .map(|impl_| {
+                format!(
+                    "`{}`",
+                    self.tcx.display(self.tcx.type_of(*impl_).unwrap())
+                )
+            })
+            .collect();
+        if let TyKind::Param(_) = self.tcx.kind(ty) {
+            diagnostic = diagnostic.with_help(format!(
+                "consider restricting type parameter `{found}` with trait \
+                 `{name}`"
+            ));
+        } else if !impls.is_empty() {
+            impls.dedup();
+            diagnostic = diagnostic.with_help(format!(
+                "the trait `{name}` is implemented for {}",
+                impls.join(", ")
+            ));
+        }
+        self.diagnostics.push(diagnostic);
+    }
+
+    /// reports an item that is private from the body, and warns on a
+    /// deprecated one
+    fn check_vis(&mut self, id: DefId, span: Span) {
+        let def = self.res.def(id);
+        if let Some(deprecation) = &def.deprecated {
+            let warning =
+                deprecation.warning(def.kind.descr(), &def.name, span);
+            self.diagnostics.push(warning);
+        }
+        if !self.res.is_accessible(def.vis, self.owner) {
+            self.diagnostics.push(
+                Diagnostic::error(format!(
+                    "{} `{}` is private",
+                    def.kind.descr(),
+                    def.name
+                ))
+                .with_code("E0603")
+                .with_primary(span, format!("private {}", def.kind.descr())),
+            );
+        }
+    }
+
+    /// see [FnCtxt::check_vis]
+    fn check_field_vis(
+        &mut self, field: &FieldDef, adt_name: &str, span: Span,
+    ) {
+        if let Some(deprecation) = &field.deprecated {
+            let name = format!("{adt_name}::{}", field.name);
+            let warning = deprecation.warning("field", &name, span);
+            self.diagnostics.push(warning);
+        }
+        if !self.res.is_accessible(field.vis, self.owner) {
+            self.diagnostics.push(
+                Diagnostic::error(format!(
+                    "field `{}` of `{adt_name}` is private",
+                    field.name
+                ))
+                .with_code("E0616")
+                .with_primary(span, "private field"),
+            );
+        }
+    }
+
+    /// dereferences `expr` through references and boxes until `found`
+    /// finds something for its type
+    fn autoderef<T>(
+        &mut self, mut expr: hir::Expr,
+        mut found: impl FnMut(&mut Self, Ty) -> Option<T>,
+    ) -> (hir::Expr, Option<T>) {
+        loop {
+            let ty = self.structurally_resolve(expr.ty, expr.span);
+            if let Some(found) = found(self, ty) {
+                return (expr, Some(found));
+            }
+            let (TyKind::Ref { ty: inner, .. } | TyKind::Box(inner)) =
+                *self.tcx.kind(ty)
+            else {
+                return (expr, None);
+            };
+            expr = hir::Expr {
+                span: expr.span,
+                ty:   inner,
+                kind: hir::ExprKind::Unary(UnOp::Deref, Box::new(expr)),
+            };
+        }
+    }
+
+    /// the loop a `break` or a `continue` with `label` exits
+    fn find_loop(
+        &mut self, label: &Option<Label>, span: Span, keyword: &str,
+    ) -> Option<usize> {
+        match label {
+            // an undeclared label is already reported
+            Some(label) => self.loops.iter().rposition(|cx| {
+                cx.label.as_ref().is_some_and(|outer| outer.node == label.node)
+            }),
+            None if self.loops.is_empty() => {
+                self.diagnostics.push(
+                    Diagnostic::error(format!("`{keyword}` outside of a loop"))
+                        .with_code("E0268")
+                        .with_primary(
+                            span,
+                            format!("cannot `{keyword}` outside of a loop"),
+                        ),
+                );
+                None
+            }
+            None => Some(self.loops.len() - 1),
+        }
+    }
+
+    fn enter_loop(
+        &mut self, label: Option<Label>, break_ty: Option<Ty>,
+    ) -> LoopId {
+        let id = LoopId::new(self.next_loop);
+        self.next_loop += 1;
+        self.loops.push(LoopCx { id, label, break_ty, may_break: false });
+        id
+    }
+
+    fn lower_ty(&mut self, ty: &ast::Type) -> Ty {
+        if !has_infer(ty) {
+            let mut collector = Collector::new(self.res, self.tcx);
+            let lowered = collector.lower_ty(ty);
+            self.diagnostics.extend(collector.take_diagnostics());
+            return lowered;
+        }
+        let kind = match &ty.kind {
+            TypeKind::Infer => return self.next_var(InferTy::Var),
+            TypeKind::Tuple(tys) => {
+                TyKind::Tuple(tys.iter().map(|ty| self.lower_ty(ty)).collect())
+            }
+            TypeKind::Ptr { mutable, ty } => {
+                TyKind::Ptr { mutable: *mutable, ty: self.lower_ty(ty) }
+            }
+            TypeKind::Ref { mutable, ty } => {
+                TyKind::Ref { mutable: *mutable, ty: self.lower_ty(ty) }
+            }
+            TypeKind::Slice(ty) => TyKind::Slice(self.lower_ty(ty)),
+            TypeKind::Box(ty) => TyKind::Box(self.lower_ty(ty)),
+            TypeKind::Array(elem, len) => {
+                let elem = self.lower_ty(elem);
+                let mut collector = Collector::new(self.res, self.tcx);
+                let len =
+                    collector.eval(len).and_then(|len| u64::try_from(len).ok());
+                self.diagnostics.extend(collector.take_diagnostics());
+                match len {
+                    Some(len) => TyKind::Array(elem, len),
+                    None => TyKind::Error,
+                }
+            }
+            TypeKind::Path(_)
+            | TypeKind::TraitObject(_)
+            | TypeKind::ImplTrait(_) => {
+                unreachable!()
+            }
+        };
+        self.tcx.intern(kind)
+    }
+
+    /// the suffix or an inference variable, the range is checked by
+    /// [Finisher::check_literal]
+    fn literal_ty(&mut self, literal: &Literal) -> Ty {
+        let prim = match literal {
+            Literal::Int(IntLit { suffix: None, .. }) => {
+                return self.next_var(InferTy::Int);
+            }
+            Literal::Int(IntLit { suffix: Some(suffix), .. }) => match suffix {
+                IntSuffix::U8 => PrimTy::U8,
+                IntSuffix::U16 => PrimTy::U16,
+                IntSuffix::U32 => PrimTy::U32,
+                IntSuffix::U64 => PrimTy::U64,
+                IntSuffix::U128 => PrimTy::U128,
+                IntSuffix::Usize => PrimTy::Usize,
+                IntSuffix::I8 => PrimTy::I8,
+                IntSuffix::I16 => PrimTy::I16,
+                IntSuffix::I32 => PrimTy::I32,
+                IntSuffix::I64 => PrimTy::I64,
+                IntSuffix::I128 => PrimTy::I128,
+                IntSuffix::Isize => PrimTy::Isize,
+            },
+            Literal::Float(FloatLit { suffix: None, .. }) => {
+                return self.next_var(InferTy::Float);
+            }
+            Literal::Float(FloatLit { suffix: Some(suffix), .. }) => {
+                match suffix {
+                    FloatSuffix::F16 => PrimTy::F16,
+                    FloatSuffix::F32 => PrimTy::F32,
+                    FloatSuffix::F64 => PrimTy::F64,
+                    FloatSuffix::F128 => PrimTy::F128,
+                }
+            }
+            Literal::Bool(_) => PrimTy::Bool,
+            Literal::Char(_) => PrimTy::Char,
+            Literal::CChar8(_) => PrimTy::CChar8,
+            Literal::CChar16(_) => PrimTy::CChar16,
+            Literal::String(_) => {
+                let str = self.tcx.prim(PrimTy::Str);
+                return self
+                    .tcx
+                    .intern(TyKind::Ref { mutable: false, ty: str });
+            }
+            Literal::CChar8Str(chars) => {
+                let char = self.tcx.prim(PrimTy::CChar8);
+                let array =
+                    self.tcx.intern(TyKind::Array(char, chars.len() as u64));
+                return self
+                    .tcx
+                    .intern(TyKind::Ref { mutable: false, ty: array });
+            }
+            Literal::CChar16Str(chars) => {
+                let char = self.tcx.prim(PrimTy::CChar16);
+                let array =
+                    self.tcx.intern(TyKind::Array(char, chars.len() as u64));
+                return self
+                    .tcx
+                    .intern(TyKind::Ref { mutable: false, ty: array });
+            }
+        };
+        self.tcx.prim(prim)
+    }
+
+    /// `expr` as a value of type `target`, e.g. `&mut a` as `&a`, `&[1, 2]`
+    /// as `&[i32]` or a function as a function pointer, `Err` if the types
+    /// differ
+    fn try_coerce(
+        &mut self, expr: hir::Expr, target: Ty,
+    ) -> Result<hir::Expr, Box<hir::Expr>> {
+        let source = self.resolve(expr.ty);
+        let resolved = self.resolve(target);
+        let span = expr.span;
+        let cast = |expr: hir::Expr| hir::Expr {
+            span: expr.span,
+            kind: hir::ExprKind::Cast(Box::new(expr)),
+            ty:   target,
+        };
+        match (self.tcx.kind(source).clone(), self.tcx.kind(resolved).clone()) {
+            (
+                TyKind::Ref { mutable: true, ty: a },
+                TyKind::Ref { mutable: false, ty: b },
+            ) => {
+                if self.unsize(a, b, span) {
+                    return Ok(cast(expr));
+                }
+                if self.unify(a, b) {
+                    // `&*a`
+                    let place = hir::Expr {
+                        kind: hir::ExprKind::Unary(UnOp::Deref, Box::new(expr)),
+                        ty: a,
+                        span,
+                    };
+                    return Ok(hir::Expr {
+                        kind: hir::ExprKind::Ref {
+                            mutable: false,
+                            expr:    Box::new(place),
+                            autoref: false,
+                        },
+                        ty: target,
+                        span,
+                    });
+                }
+            }
+            (
+                TyKind::Ref { mutable: m1, ty: a },
+                TyKind::Ref { mutable: m2, ty: b },
+            ) if m1 == m2 && self.unsize(a, b, span) => {
+                return Ok(cast(expr));
+            }
+            (
+                TyKind::Ref { mutable: m1, ty: a },
+                TyKind::Ptr { mutable: m2, ty: b },
+            ) if m1 || !m2 => {
+                if self.unsize(a, b, span) || self.unify(a, b) {
+                    return Ok(cast(expr));
+                }
+            }
+            (TyKind::Box(a), TyKind::Box(b)) if self.unsize(a, b, span) => {
+                return Ok(cast(expr));
+            }
+            (TyKind::FnDef(id, args), TyKind::FnPtr { params, ret }) => {
+                let (sig_params, sig_ret) =
+                    self.instantiate_sig(id, &args, span);
+                let same = sig_params.len() == params.len()
+                    && sig_params
+                        .iter()
+                        .zip(&params)
+                        .all(|(a, b)| self.unify(*a, *b))
+                    && self.unify(sig_ret, ret);
+                if same {
+                    return Ok(cast(expr));
+                }
+            }
+            _ => {}
+        }
+        match self.unify(expr.ty, target) {
+            true => Ok(expr),
+            false => Err(Box::new(expr)),
+        }
+    }
+
+    fn coerce(&mut self, expr: hir::Expr, target: Ty) -> hir::Expr {
+        match self.try_coerce(expr, target) {
+            Ok(expr) => expr,
+            Err(expr) => {
+                let expr = *expr;
+                self.mismatch(expr.span, target, expr.ty);
+                expr
+            }
+        }
+    }
+
+    /// `[T; N]` to `[T]`, or a sized type to `dyn A` with the obligation
+    /// that it implements `A`
+    fn unsize(&mut self, source: Ty, target: Ty, span: Span) -> bool {
+        let (source, target) = (self.resolve(source), self.resolve(target));
+        match (self.tcx.kind(source).clone(), self.tcx.kind(target).clone()) {
+            (TyKind::Array(a, _), TyKind::Slice(b)) => self.unify(a, b),
+            (
+                TyKind::Infer(InferTy::Var(_)) | TyKind::Dynamic(_),
+                TyKind::Dynamic(_),
+            ) => false,
+            (_, TyKind::Dynamic(traits)) if self.tcx.is_sized(source) => {
+                for trait_ in traits {
+                    self.obligations.push(Obligation::Trait {
+                        ty: source,
+                        trait_,
+                        span,
+                    });
+                }
+                true
+            }
+            _ => false,
+        }
+    }
+
+    /// a value of type `source` can be cast to `target` with `as`
+    fn can_cast(&mut self, source: Ty, target: Ty, span: Span) -> bool {
+        let (source, target) = (self.resolve(source), self.resolve(target));
+        let integral = |fcx: &Self, ty| fcx.tcx.is_integral(ty);
+        match (self.tcx.kind(source).clone(), self.tcx.kind(target).clone()) {
+            (TyKind::Error, _) | (_, TyKind::Error) => true,
+            _ if self.tcx.is_numeric(source) && self.tcx.is_numeric(target) => {
+                true
+            }
+            (TyKind::Bool | TyKind::Char(_), _) if integral(self, target) => {
+                true
+            }
+            (_, TyKind::Char(CharTy::C32)) => {
+                matches!(
+                    self.tcx.kind(source),
+                    TyKind::Uint(UintTy::U8) | TyKind::Infer(InferTy::Int(_))
+                )
+            }
+            (_, TyKind::Char(_)) => integral(self, source),
+            (TyKind::Ptr { .. }, TyKind::Ptr { .. }) => true,
+            (
+                TyKind::Ptr { .. } | TyKind::FnDef(..) | TyKind::FnPtr { .. },
+                _,
+            ) if integral(self, target) => true,
+            (_, TyKind::Ptr { .. }) if integral(self, source) => true,
+            (
+                TyKind::Ref { mutable: m1, ty: a },
+                TyKind::Ptr { mutable: m2, ty: b },
+            ) => (m1 || !m2) && (self.unify(a, b) || self.unsize(a, b, span)),
+            (TyKind::FnDef(..), TyKind::FnPtr { .. } | TyKind::Ptr { .. }) => {
+                let expr =
+                    hir::Expr { kind: hir::ExprKind::Err, ty: source, span };
+                self.try_coerce(expr, target).is_ok()
+                    || matches!(self.tcx.kind(target), TyKind::Ptr { .. })
+            }
+            _ => self.unify(source, target),
+        }
+    }
+
+    fn unify(&mut self, a: Ty, b: Ty) -> bool {
+        let (a, b) = (self.resolve(a), self.resolve(b));
+        if a == b {
+            return true;
+        }
+        match (self.tcx.kind(a).clone(), self.tcx.kind(b).clone()) {
+            (TyKind::Error, _) | (_, TyKind::Error) => true,
+            (TyKind::Infer(InferTy::Var(var)), _) => self.bind(var, b),
+            (_, TyKind::Infer(InferTy::Var(var))) => self.bind(var, a),
+            (TyKind::Infer(InferTy::Int(var)), kind)
+                if self.tcx.is_integral(b)
+                    || matches!(kind, TyKind::Infer(InferTy::Int(_))) =>
+            {
+                self.bind(var, b)
+            }
+            (_, TyKind::Infer(InferTy::Int(var)))
+                if self.tcx.is_integral(a) =>
+            {
+                self.bind(var, a)
+            }
+            (TyKind::Infer(InferTy::Float(var)), _) if self.tcx.is_float(b) => {
+                self.bind(var, b)
+            }
+            (_, TyKind::Infer(InferTy::Float(var))) if self.tcx.is_float(a) => {
+                self.bind(var, a)
+            }
+            (
+                TyKind::Ptr { mutable: m1, ty: a },
+                TyKind::Ptr { mutable: m2, ty: b },
+            )
+            | (
+                TyKind::Ref { mutable: m1, ty: a },
+                TyKind::Ref { mutable: m2, ty: b },
+            ) => m1 == m2 && self.unify(a, b),
+            (TyKind::Box(a), TyKind::Box(b))
+            | (TyKind::Slice(a), TyKind::Slice(b)) => self.unify(a, b),
+            (TyKind::Array(a, n), TyKind::Array(b, m)) => {
+                n == m && self.unify(a, b)
+            }
+            (TyKind::Tuple(a), TyKind::Tuple(b)) => {
+                a.len() == b.len()
+                    && a.iter().zip(&b).all(|(a, b)| self.unify(*a, *b))
+            }
+            (
+                TyKind::FnPtr { params: p1, ret: r1 },
+                TyKind::FnPtr { params: p2, ret: r2 },
+            ) => {
+                p1.len() == p2.len()
+                    && p1.iter().zip(&p2).all(|(a, b)| self.unify(*a, *b))
+                    && self.unify(r1, r2)
+            }
+            (TyKind::Adt(f1, a1), TyKind::Adt(f2, a2))
+            | (TyKind::FnDef(f1, a1), TyKind::FnDef(f2, a2)) => {
+                f1 == f2 && a1.iter().zip(&a2).all(|(a, b)| self.unify(*a, *b))
+            }
+            (
+                TyKind::Projection { ty: a, trait_: t1, item: i1 },
+                TyKind::Projection { ty: b, trait_: t2, item: i2 },
+            ) => t1 == t2 && i1 == i2 && self.unify(a, b),
+            _ => false,
+        }
+    }
+
+    /// sets inference variable `var` to `ty`, unless `ty` contains it
+    fn bind(&mut self, var: u32, ty: Ty) -> bool {
+        if self.occurs(var, ty) {
+            return false;
+        }
+        self.vars[var as usize].1 = Some(ty);
+        true
+    }
+
+    fn occurs(&self, var: u32, ty: Ty) -> bool {
+        let ty = self.resolve(ty);
+        match self.tcx.kind(ty) {
+            TyKind::Infer(InferTy::Var(other)) => *other == var,
+            TyKind::Ptr { ty, .. }
+            | TyKind::Ref { ty, .. }
+            | TyKind::Box(ty)
+            | TyKind::Slice(ty)
+            | TyKind::Array(ty, _)
+            | TyKind::Projection { ty, .. } => self.occurs(var, *ty),
+            TyKind::Tuple(tys)
+            | TyKind::Adt(_, tys)
+            | TyKind::FnDef(_, tys) => {
+                tys.iter().any(|ty| self.occurs(var, *ty))
+            }
+            TyKind::FnPtr { params, ret } => {
+                params.iter().any(|ty| self.occurs(var, *ty))
+                    || self.occurs(var, *ret)
+            }
+            _ => false,
+        }
+    }
+
+    /// follows the inference variables of `ty` that are known, but not of
+    /// its components
+    fn resolve(&self, mut ty: Ty) -> Ty {
+        while let TyKind::Infer(var) = self.tcx.kind(ty) {
+            let (InferTy::Var(index)
+            | InferTy::Int(index)
+            | InferTy::Float(index)) = *var;
+            match self.vars[index as usize].1 {
+                Some(value) => ty = value,
+                None => break,
+            }
+        }
+        ty
+    }
+
+    /// `ty` with every known inference variable replaced, with `fallback`
+    /// for the unknown ones
+    fn resolve_deep(
+        &mut self, ty: Ty, fallback: &mut dyn FnMut(&mut Self, Ty) -> Ty,
+    ) -> Ty {
+        let ty = self.resolve(ty);
+        let kind = match self.tcx.kind(ty).clone() {
+            TyKind::Infer(_) => return fallback(self, ty),
+            TyKind::Ptr { mutable, ty } => {
+                TyKind::Ptr { mutable, ty: self.resolve_deep(ty, fallback) }
+            }
+            TyKind::Ref { mutable, ty } => {
+                TyKind::Ref { mutable, ty: self.resolve_deep(ty, fallback) }
+            }
+            TyKind::Box(ty) => TyKind::Box(self.resolve_deep(ty, fallback)),
+            TyKind::Slice(ty) => TyKind::Slice(self.resolve_deep(ty, fallback)),
+            TyKind::Array(ty, len) => {
+                TyKind::Array(self.resolve_deep(ty, fallback), len)
+            }
+            TyKind::Tuple(tys) => TyKind::Tuple(
+                tys.into_iter()
+                    .map(|ty| self.resolve_deep(ty, fallback))
+                    .collect(),
+            ),
+            TyKind::Adt(id, args) => TyKind::Adt(
+                id,
+                args.into_iter()
+                    .map(|ty| self.resolve_deep(ty, fallback))
+                    .collect(),
+            ),
+            TyKind::FnDef(id, args) => TyKind::FnDef(
+                id,
+                args.into_iter()
+                    .map(|ty| self.resolve_deep(ty, fallback))
+                    .collect(),
+            ),
+            TyKind::Projection { ty, trait_, item } => TyKind::Projection {
+                ty: self.resolve_deep(ty, fallback),
+                trait_,
+                item,
+            },
+            TyKind::FnPtr { params, ret } => TyKind::FnPtr {
+                params: params
+                    .into_iter()
+                    .map(|ty| self.resolve_deep(ty, fallback))
+                    .collect(),
+                ret:    self.resolve_deep(ret, fallback),
+            },
+            _ => return ty,
+        };
+        self.tcx.intern(kind)
+    }
+
+    /// `ty` must be known here, e.g. the type of `a` in `a.b`
+    fn structurally_resolve(&mut self, ty: Ty, span: Span) -> Ty {
+        let mut ty = self.resolve(ty);
+        if let TyKind::Infer(InferTy::Var(_)) = self.tcx.kind(ty) {
+            self.select(false);
+            ty = self.resolve(ty);
+        }
+        let TyKind::Infer(InferTy::Var(var)) = *self.tcx.kind(ty) else {
+            return ty;
+        };
+        self.diagnostics.push(
+            Diagnostic::error("type annotations needed")
+                .with_code("E0282")
+                .with_primary(span, "type must be known at this point"),
+        );
+        let error = self.tcx.error();
+        self.vars[var as usize].1 = Some(error);
+        error
+    }
+
+    /// `ty` contains [TyKind::Error], so errors about it are redundant
+    fn has_error(&self, ty: Ty) -> bool {
+        let ty = self.resolve(ty);
+        match self.tcx.kind(ty) {
+            TyKind::Error => true,
+            TyKind::Ptr { ty, .. }
+            | TyKind::Ref { ty, .. }
+            | TyKind::Box(ty)
+            | TyKind::Slice(ty)
+            | TyKind::Array(ty, _)
+            | TyKind::Projection { ty, .. } => self.has_error(*ty),
+            TyKind::Tuple(tys)
+            | TyKind::Adt(_, tys)
+            | TyKind::FnDef(_, tys) => tys.iter().any(|ty| self.has_error(*ty)),
+            TyKind::FnPtr { params, ret } => {
+                params.iter().any(|ty| self.has_error(*ty))
+                    || self.has_error(*ret)
+            }
+            _ => false,
+        }
+    }
+
+    /// e.g. `&{integer}` in a diagnostic
+    fn display(&mut self, ty: Ty) -> String {
+        let ty = self.resolve_deep(ty, &mut |_, ty| ty);
+        self.tcx.display(ty)
+    }
+
+    fn mismatch(&mut self, span: Span, expected: Ty, found: Ty) {
+        if self.has_error(expected) || self.has_error(found) {
+            return;
+        }
+        let (expected, found) = (self.display(expected), self.display(found));
+        self.diagnostics.push(
+            Diagnostic::error("mismatched types")
+                .with_code("E0308")
+                .with_primary(
+                    span,
+                    format!("expected `{expected}`, found `{found}`"),
+                ),
+        );
+    }
+
+    fn next_var(&mut self, kind: fn(u32) -> InferTy) -> Ty {
+        let infer = kind(self.vars.len() as u32);
+        self.vars.push((infer, None));
+        self.tcx.intern(TyKind::Infer(infer))
+    }
+
+    /// the type of an expression that never finishes, see
+    /// [FnCtxt::diverging]
+    fn next_diverging(&mut self) -> Ty {
+        let ty = self.next_var(InferTy::Var);
+        self.diverging.push(ty);
+        ty
+    }
+
+    fn new_local(
+        &mut self, name: &str, mutable: bool, ty: Ty, span: Span,
+    ) -> LocalId {
+        let id = LocalId::new(self.locals.len());
+        self.locals.push(LocalDecl {
+            name: name.to_string(),
+            mutable,
+            ty,
+            span,
+        });
+        id
+    }
+
+    fn error_expr(&mut self, span: Span) -> hir::Expr {
+        let ty = self.tcx.error();
+        hir::Expr { kind: hir::ExprKind::Err, ty, span }
+    }
+}
+impl Finisher<'_, '_, '_> {
+    /// reports the unknown type variables of `ty` at `span`
+    fn finish(&mut self, ty: Ty, span: Option<Span>) -> Ty {
+        let mut unknown = Vec::new();
+        let ty = self.fcx.resolve_deep(ty, &mut |fcx, ty| {
+            if let TyKind::Infer(InferTy::Var(var)) = *fcx.tcx.kind(ty) {
+                unknown.push(var);
+            }
+            fcx.tcx.error()
+        });
+        if let Some(span) = span
+            && let Some(var) = unknown.first()
+            && self.reported.insert(*var)
+        {
+            self.reported.extend(unknown.iter().copied());
+            self.fcx.diagnostics.push(
+                Diagnostic::error("type annotations needed")
+                    .with_code("E0282")
+                    .with_primary(span, "cannot infer type"),
+            );
+        }
+        ty
+    }
+}
+impl Finisher<'_, '_, '_> {
+    /// reports a number that does not fit into its type, `negated` by `-`
+    fn check_literal(
+        &mut self, literal: &Literal, negated: bool, ty: Ty, span: Span,
+    ) {
+        let fcx = &mut *self.fcx;
+        let range = match (literal, fcx.tcx.kind(ty)) {
+            (Literal::Int(lit), kind @ (TyKind::Int(_) | TyKind::Uint(_))) => {
+                let signed = matches!(kind, TyKind::Int(_));
+                let Ok(size) = fcx.tcx.size_of(ty, fcx.target) else { return };
+                let max = u128::MAX >> (128 - size * 8 + signed as u64);
+                let fits = match (signed, lit.negative != negated) {
+                    (true, true) => lit.value <= max + 1,
+                    (_, false) => lit.value <= max,
+                    // `-1u8` is already reported as an invalid negation
+                    (false, true) => negated || lit.value == 0,
+                };
+                if fits {
+                    return;
+                }
+                let min =
+                    if signed { format!("-{}", max + 1) } else { "0".into() };
+                format!(" whose range is `{min}..={max}`")
+            }
+            (Literal::Float(lit), TyKind::Float(float)) => {
+                let max = match float {
+                    FloatTy::F16 => 65504.0,
+                    FloatTy::F32 => f32::MAX as f64,
+                    // `f128` can not be parsed yet
+                    FloatTy::F64 | FloatTy::F128 => f64::MAX,
+                };
+                let value = lit.text.parse::<f64>().unwrap_or(f64::INFINITY);
+                if value.abs() <= max {
+                    return;
+                }
+                String::new()
+            }
+            _ => return,
+        };
+        let name = fcx.tcx.display(ty);
+        let sign = if negated { "-" } else { "" };
+        fcx.diagnostics.push(
+            Diagnostic::error(format!("literal out of range for `{name}`"))
+                .with_primary(
+                    span,
+                    format!(
+                        "the literal `{sign}{literal}` does not fit into the \
+                         type `{name}`{range}"
+                    ),
+                ),
+        );
+    }
+}
+impl hir::MutVisitor for Finisher<'_, '_, '_> {
+    fn visit_body(&mut self, body: &mut Body) {
+        for local in &mut body.locals {
+            local.ty = self.finish(local.ty, Some(local.span));
+        }
+        hir::walk_body(self, body);
+    }
+
+    fn visit_expr(&mut self, expr: &mut hir::Expr) {
+        expr.ty = self.finish(expr.ty, Some(expr.span));
+        match &mut expr.kind {
+            hir::ExprKind::Literal(literal) => {
+                self.check_literal(literal, false, expr.ty, expr.span);
+            }
+            // e.g. `-128i8`
+            hir::ExprKind::Unary(UnOp::Neg, inner)
+                if matches!(inner.kind, hir::ExprKind::Literal(_)) =>
+            {
+                inner.ty = self.finish(inner.ty, Some(inner.span));
+                if let hir::ExprKind::Literal(literal) = &inner.kind {
+                    self.check_literal(literal, true, inner.ty, expr.span);
+                }
+                return;
+            }
+            _ => {}
+        }
+        hir::walk_expr(self, expr);
+    }
+
+    fn visit_pat(&mut self, pat: &mut hir::Pat) {
+        pat.ty = self.finish(pat.ty, Some(pat.span));
+        if let hir::PatKind::Literal(literal) = &pat.kind {
+            self.check_literal(literal, false, pat.ty, pat.span);
+        }
+        hir::walk_pat(self, pat);
+    }
+
+    fn visit_ty(&mut self, ty: &mut Ty) { *ty = self.finish(*ty, None); }
+}
+/// the block of a [hir::ExprKind::Block]
+fn block_of(expr: hir::Expr) -> hir::Block {
+    match expr.kind {
+        hir::ExprKind::Block(block) => block,
+        _ => unreachable!(),
+    }
+}
+/// `ty` contains `_`
+fn has_infer(ty: &ast::Type) -> bool {
+    match &ty.kind {
+        TypeKind::Infer => true,
+        TypeKind::Tuple(tys) => tys.iter().any(has_infer),
+        TypeKind::Ptr { ty, .. }
+        | TypeKind::Ref { ty, .. }
+        | TypeKind::Slice(ty)
+        | TypeKind::Array(ty, _)
+        | TypeKind::Box(ty) => has_infer(ty),
+        TypeKind::Path(_)
+        | TypeKind::TraitObject(_)
+        | TypeKind::ImplTrait(_) => false,
+    }
+}
+/// `s` for anything but one
+fn plural(count: usize) -> &'static str { if count == 1 { "" } else { "s" } }
+#[cfg(test)]
+mod tests {
+    use crate::semantic::{self, tests::Checked};
+    /// the locals of every body of `source` with their types, and the
+    /// errors of the type checker with their primary labels
+    fn check(source: &str) -> (Vec<String>, Vec<String>) {
+        let Checked { tcx, bodies, parse, resolve, collect, typeck, .. } =
+            semantic::tests::check(source);
+        assert_eq!(parse, []);
+        assert_eq!(resolve, []);
+        assert_eq!(collect, []);
+        let errors = typeck
+            .iter()
+            .map(|diagnostic| {
+                let label = diagnostic
+                    .labels()
+                    .iter()
+                    .find(|label| label.is_primary())
+                    .map_or("", |label| label.message());
+                format!("{}: {label}", diagnostic.message())
+            })
+            .collect();
+        let locals = bodies
+            .iter()
+            .flat_map(|body| &body.locals)
+            .map(|local| format!("{}: {}", local.name, tcx.display(local.ty)))
+            .collect();
+        (locals, errors)
+    }
+    #[test]
+    fn inference() {
+        let (locals, errors) = check(
+            r#"
+            struct Point { x: i32, y: i32 }
+            impl Point {
+                fn new(x: i32) -> Self { Point { x, y: 0 } }
+                fn sum(&self) -> i32 { self.x + self.y }
+            }
+            fn main() {
+                let number = box 1;
+                println!("{*number}");
+                let a = 1;
+                let b: u8 = a;
+                let c = 2.0;
+                let d = [c; 3];
+                let e = (number, &b);
+                let mut f = 0;
+                f = 3u64;
+                let g = if b > 1 { 1 } else { 2 };
+                let h = loop { break 7i16; };
+                let i = box Point::new(1);
+                let j = i.sum();
+                let k: [_; 2] = [1u16, 2];
+                for l in &k {}
+                let m = format!("{j}");
+                let (n, _) = (k[0], e.1);
+            }
+            "#,
+        );
+        assert_eq!(errors, Vec::<String>::new());
+        assert_eq!(locals, [
+            "x: i32", "self: &Point", "number: i32.Box", "a: u8", "b: u8",
+            "c: f64", "d: [f64; 3]", "e: (i32.Box, &u8)", "f: u64", "g: i32",
+            "h: i16", "i: Point.Box", "j: i32", "k: [u16; 2]", "l: &u16",
+            "m: str.Box", "n: u16",
+        ]);
+    }
+    #[test]
+    fn literals() {
+        let (locals, errors) = check(
+            r#"
+            const A: u8 = 256;
+            fn main() {
+                let a: u64 = 5_000_000_000;
+                let b = 5_000_000_000;
+                let c = -128i8;
+                let d: i8 = -129;
+                let e = 1e40f32;
+                let f: usize = 0xFFFF_FFFF_FFFF_FFFF;
+                let g = 0x1_00u8;
+                let h: f16 = 1e5;
+                let i = 1.5f128;
+                match 1u8 { 0..=255 => {}, -1 => {}, 256 => {} }
+            }
+            "#,
+        );
+        assert_eq!(locals, [
+            "a: u64", "b: i32", "c: i8", "d: i8", "e: f32", "f: usize",
+            "g: u8", "h: f16", "i: f128",
+        ]);
+        assert_eq!(errors, [
+            "literal out of range for `u8`: the literal `256` does not fit \
+             into the type `u8` whose range is `0..=255`",
+            "literal out of range for `i32`: the literal `5_000_000_000` does \
+             not fit into the type `i32` whose range is \
+             `-2147483648..=2147483647`",
+            "literal out of range for `i8`: the literal `-129` does not fit \
+             into the type `i8` whose range is `-128..=127`",
+            "literal out of range for `f32`: the literal `1e40f32` does not \
+             fit into the type `f32`",
+            "literal out of range for `u8`: the literal `0x1_00u8` does not \
+             fit into the type `u8` whose range is `0..=255`",
+            "literal out of range for `f16`: the literal `1e5` does not fit \
+             into the type `f16`",
+            "literal out of range for `u8`: the literal `-1` does not fit \
+             into the type `u8` whose range is `0..=255`",
+            "literal out of range for `u8`: the literal `256` does not fit \
+             into the type `u8` whose range is `0..=255`",
+        ]);
+    }
+    #[test]
+    fn type_errors() {
+        let (_, errors) = check(
+            r#"
+            struct A { a: u8 }
+            fn f(a: u8) -> bool { a }
+            fn main() {
+                let a: u8 = true;
+                let b = if true { 1 } else { 'b' };
+                let c = match 1 { 1 => 2u8, _ => 3i8 };
+                f(1, 2);
+                A { a: 1 }.b;
+                A {};
+                1u8 + 1i64;
+                !1.0;
+                1u8.g();
+                let d = [];
+                break;
+                let e = 1 else { 2 };
+                if 1 {}
+            }
+            "#,
+        );
+        assert_eq!(errors, [
+            "mismatched types: expected `bool`, found `u8`",
+            "mismatched types: expected `u8`, found `bool`",
+            "`if` and `else` have incompatible types: expected `{integer}`, \
+             found `char`",
+            "`match` arms have incompatible types: expected `u8`, found `i8`",
+            "this function takes 1 argument but 2 arguments were supplied: ",
+            "no field `b` on type `A`: unknown field",
+            "missing field `a` in initializer of `A`: missing fields",
+            "mismatched types: expected `u8`, found `i64`",
+            "cannot apply unary operator `!` to type `{float}`: ",
+            "no method named `g` found for type `u8` in the current scope: \
+             method not found",
+            "`break` outside of a loop: cannot `break` outside of a loop",
+            "`else` clause of `let...else` does not diverge: expected `!`, \
+             found `{integer}`",
+            "mismatched types: expected `bool`, found `{integer}`",
+            "type annotations needed: cannot infer type",
+        ]);
+    }
+    #[test]
+    fn traits() {
+        let (locals, errors) = check(
+            r#"
+            trait Shape {
+                type Unit;
+                const SIDES: u8;
+                fn area(&self) -> Self::Unit;
+                fn sides(&self) -> u8 { Self::SIDES }
+            }
+            struct Square(u32);
+            impl Shape for Square {
+                type Unit = u32;
+                const SIDES: u8 = 4;
+                fn area(&self) -> u32 { self.0 * self.0 }
+            }
+            trait Named { fn name() -> &str; }
+            impl Named for u8 { fn name() -> &str { "u8" } }
+            fn total<T: Shape>(shape: &T) -> T::Unit where T: Shape {
+                shape.area()
+            }
+            fn main() {
+                let a = Square(2);
+                let b = a.area();
+                let c = (&&a).sides();
+                let d = total(&a);
+                let e = Square::SIDES;
+                let f: &dyn Shape = &a;
+                let g = u8::name();
+            }
+            "#,
+        );
+        assert_eq!(errors, Vec::<String>::new());
+        assert_eq!(locals, [
+            "self: &Self", "self: &Square", "shape: &T", "a: Square", "b: u32",
+            "c: u8", "d: u32", "e: u8", "f: &dyn Shape", "g: &str",
+        ]);
+    }
+    #[test]
+    fn trait_errors() {
+        let (_, errors) = check(
+            r#"
+            trait Show { fn show(&self) -> u8; }
+            trait Print { fn show(&self) -> u8; }
+            impl Show for i32 { fn show(&self) -> u8 { 1 } }
+            impl Print for i32 { fn show(&self) -> u8 { 2 } }
+            impl Show for bool { fn show(&self) -> u8 { 3 } }
+            fn f<T: Show>(t: T) -> u8 { t.show() }
+            fn g<T>(t: T) -> u8 { f(t) }
+            fn main() {
+                f(1.0);
+                f('a');
+                1i32.show();
+                let a: &dyn Show = &'b';
+                let b = Show::show;
+            }
+            "#,
+        );
+        assert_eq!(errors, [
+            "the trait bound `T: Show` is not satisfied: the trait `Show` is \
+             not implemented for `T`",
+            "multiple applicable items in scope: multiple `show` found",
+            "the trait bound `{float}: Show` is not satisfied: the trait \
+             `Show` is not implemented for `{float}`",
+            "the trait bound `char: Show` is not satisfied: the trait `Show` \
+             is not implemented for `char`",
+            "the trait bound `char: Show` is not satisfied: the trait `Show` \
+             is not implemented for `char`",
+            "type annotations needed: cannot infer type",
+        ]);
+    }
+    #[test]
+    fn duplicate_bounds() {
+        let (_, errors) = check(
+            "trait Show {}
+            fn call<T: Show>(t: &T) where T: Show {}
+            fn main() { call(&5); }",
+        );
+        assert_eq!(errors, ["the trait bound `{integer}: Show` is not \
+                             satisfied: the trait `Show` is not implemented \
+                             for `{integer}`",]);
+    }
+    #[test]
+    fn generics() {
+        let (locals, errors) = check(
+            r#"
+            enum Option<T> { None, Some(T) }
+            struct Pair<A, B> { a: A, b: B }
+            impl<A, B> Pair<A, B> {
+                fn new(a: A, b: B) -> Self { Pair { a, b } }
+                fn first(&self) -> &A { &self.a }
+            }
+            fn id<T>(t: T) -> T { t }
+            fn main() {
+                let a = id(1u8);
+                let p = Pair::new(a, id(true));
+                let f = p.first();
+                let q = Pair::<i32, _>::new(1, 'c');
+                let o = Option::Some(q);
+                let n: Option<u16> = Option::None;
+                let g = id::<i64>;
+            }
+            "#,
+        );
+        assert_eq!(errors, Vec::<String>::new());
+        assert_eq!(locals, [
+            "a: A",
+            "b: B",
+            "self: &Pair<A, B>",
+            "t: T",
+            "a: u8",
+            "p: Pair<u8, bool>",
+            "f: &u8",
+            "q: Pair<i32, char>",
+            "o: Option<Pair<i32, char>>",
+            "n: Option<u16>",
+            "g: fn id",
+        ]);
+    }
+    #[test]
+    fn generic_errors() {
+        let (_, errors) = check(
+            r#"
+            struct Wrapper<T> { t: T }
+            fn id<T>(t: T) -> T { t }
+            fn main() {
+                let a = id::<u8, u8>(1);
+                let b: Wrapper<u8> = Wrapper { t: true };
+                let c = Wrapper::<u8, u8> { t: 1 };
+                let d = id;
+            }
+            "#,
+        );
+        assert_eq!(errors, [
+            "function takes 1 generic argument but 2 generic arguments were \
+             supplied: expected 1 generic argument",
+            "mismatched types: expected `Wrapper<u8>`, found `Wrapper<bool>`",
+            "struct takes 1 generic argument but 2 generic arguments were \
+             supplied: expected 1 generic argument",
+            "type annotations needed: cannot infer type",
+        ]);
+    }
+}
@@ -10,3 +10,819 @@
 //
 // You should have received a copy of the GNU Affero General Public License
 // along with this program. If not, see <https://www.gnu.org/licenses/>.
+pub mod layout;
+use std::collections::{HashMap, HashSet};
+
+use crate::{
+    attr::Deprecation,
+    semantic::resolve::{DefId, PrimTy, Vis},
+    types::layout::Target,
+};
+/// an interned type, two types are the same if their [Ty]s are equal, see
+/// [TyCtxt::intern]
+#[derive(Debug, Clone, Copy, Hash, Ord, PartialOrd, PartialEq, Eq)]
+pub struct Ty(u32);
+/// see [Ty], and the type table in `lib.md`
+#[derive(Debug, Clone, Hash, PartialEq, Eq)]
+pub enum TyKind {
+    Int(IntTy),
+    Uint(UintTy),
+    Float(FloatTy),
+    Bool,
+    Char(CharTy),
+    /// `str`, unsized
+    Str,
+    /// e.g. `*const i32`, `*mut i32`
+    Ptr {
+        mutable: bool,
+        ty:      Ty,
+    },
+    /// e.g. `&i32`, `&mut i32`
+    Ref {
+        mutable: bool,
+        ty:      Ty,
+    },
+    /// e.g. `i32.Box`
+    Box(Ty),
+    /// e.g. `[i32]`, unsized
+    Slice(Ty),
+    /// e.g. `[i32; 3]`
+    Array(Ty, u64),
+    /// e.g. `(i32, bool)`, and `()` for the unit type
+    Tuple(Vec<Ty>),
+    /// a struct, an enum or a union with its generic arguments, see
+    /// [AdtDef] and [TyCtxt::field_ty]
+    Adt(DefId, Vec<Ty>),
+    /// the zero-sized type of a function item with its generic arguments,
+    /// see [TyCtxt::generics], e.g. of `a` in `let b = a;`
+    FnDef(DefId, Vec<Ty>),
+    /// e.g. the type of a function item after coercion
+    FnPtr {
+        params: Vec<Ty>,
+        ret:    Ty,
+    },
+    /// e.g. `dyn A + B`, by the traits, unsized
+    Dynamic(Vec<DefId>),
+    /// a generic parameter, e.g. `T` of `fn a<T>()`, or `Self` in a trait by
+    /// the trait
+    Param(DefId),
+    /// e.g. `T::Item`, the associated type `item` of the impl of `trait_`
+    /// for `ty`, see [TyCtxt::normalize]
+    Projection {
+        ty:     Ty,
+        trait_: DefId,
+        item:   DefId,
+    },
+    /// an unknown type during type checking, see [InferTy]
+    Infer(InferTy),
+    /// a type with an error, which is already reported
+    Error,
+}
+/// see [TyKind::Infer], numbered by the type checker of each body
+#[derive(Debug, Clone, Copy, Hash, Ord, PartialOrd, PartialEq, Eq)]
+pub enum InferTy {
+    /// any type, e.g. of `a` in `let a;`
+    Var(u32),
+    /// an integer type, e.g. of `1`, `i32` by default
+    Int(u32),
+    /// a float type, e.g. of `1.0`, `f64` by default
+    Float(u32),
+}
+#[derive(Debug, Clone, Copy, Hash, Ord, PartialOrd, PartialEq, Eq)]
+pub enum IntTy {
+    I8,
+    I16,
+    I32,
+    I64,
+    I128,
+    Isize,
+}
+#[derive(Debug, Clone, Copy, Hash, Ord, PartialOrd, PartialEq, Eq)]
+pub enum UintTy {
+    U8,
+    U16,
+    U32,
+    U64,
+    U128,
+    Usize,
+}
+#[derive(Debug, Clone, Copy, Hash, Ord, PartialOrd, PartialEq, Eq)]
+pub enum FloatTy {
+    F16,
+    F32,
+    F64,
+    F128,
+}
+#[derive(Debug, Clone, Copy, Hash, Ord, PartialOrd, PartialEq, Eq)]
+pub enum CharTy {
+    /// `c_char8`
+    C8,
+    /// `c_char16`
+    C16,
+    /// `char` or `c_char32`
+    C32,
+}
+/// interns the types of a crate, and holds the definitions of its structs,
+/// enums and unions and the signatures of its functions
+#[derive(Debug, Default, Clone)]
+pub struct TyCtxt {
+    kinds:          Vec<TyKind>,
+    interned:       HashMap<TyKind, Ty>,
+    adts:           HashMap<DefId, AdtDef>,
+    /// the names of functions and traits, see [TyCtxt::display]
+    names:          HashMap<DefId, String>,
+    fn_sigs:        HashMap<DefId, FnSig>,
+    /// see [TyCtxt::type_of]
+    types:          HashMap<DefId, Ty>,
+    /// the values of the constants of integer types
+    consts:         HashMap<DefId, i128>,
+    /// the impls without a trait, e.g. `impl A {}`
+    inherent_impls: Vec<DefId>,
+    /// the impls of every trait
+    traits:         HashMap<DefId, Vec<DefId>>,
+    /// the trait of every impl of a trait
+    impl_traits:    HashMap<DefId, DefId>,
+    /// the item of an impl of a trait for each item of the trait, the item
+    /// of the trait if its default is used
+    impl_items:     HashMap<(DefId, DefId), DefId>,
+    /// see [TyCtxt::generics]
+    generics:       HashMap<DefId, Vec<DefId>>,
+    /// see [TyCtxt::predicates]
+    predicates:     HashMap<DefId, Vec<Predicate>>,
+}
+/// a struct, an enum or a union
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AdtDef {
+    pub kind:     AdtKind,
+    /// e.g. `Point`
+    pub name:     String,
+    /// exactly one for a struct or a union
+    pub variants: Vec<VariantDef>,
+    pub repr:     ReprOptions,
+    /// `#[derive(Copy)]`
+    pub copy:     bool,
+}
+#[derive(Debug, Clone, Copy, Hash, PartialEq, Eq)]
+pub enum AdtKind {
+    Struct,
+    Enum,
+    Union,
+}
+/// a variant of an enum, or the fields of a struct or a union
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct VariantDef {
+    /// the name of the struct or the union itself if it is not an enum
+    pub name:   String,
+    pub fields: Vec<FieldDef>,
+    /// e.g. `1` of `A = 1`, the previous one plus one by default
+    pub discr:  i128,
+}
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FieldDef {
+    /// `0`, `1`, ... in a tuple struct
+    pub name:       String,
+    pub ty:         Ty,
+    pub vis:        Vis,
+    /// see [deprecation](crate::attr::deprecation)
+    pub deprecated: Option<Deprecation>,
+}
+/// the parameters and the return type of a function, or of the constructor
+/// of a tuple struct or variant
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FnSig {
+    /// the type of `self` first for a method
+    pub params:   Vec<Ty>,
+    pub ret:      Ty,
+    /// e.g. `fn a(&self)`
+    pub has_self: bool,
+}
+/// e.g. `T: A`, a bound of a generic parameter or of a `where` clause
+#[derive(Debug, Clone, Copy, Hash, PartialEq, Eq)]
+pub struct Predicate {
+    pub ty:     Ty,
+    pub trait_: DefId,
+}
+/// see `#[repr(...)]` in [attr](crate::attr)
+#[derive(Debug, Default, Clone, Copy, Hash, PartialEq, Eq)]
+pub struct ReprOptions {
+    /// `C`, the fields in declaration order
+    pub c:           bool,
+    /// `packed`, no padding
+    pub packed:      bool,
+    /// `transparent`
+    pub transparent: bool,
+    /// e.g. `u8`, the type of the discriminant of an enum
+    pub int:         Option<Ty>,
+}
+impl TyCtxt {
+    pub fn new() -> Self { Self::default() }
+
+    /// the same [Ty] for equal kinds
+    pub fn intern(&mut self, kind: TyKind) -> Ty {
+        if let Some(ty) = self.interned.get(&kind) {
+            return *ty;
+        }
+        let ty = Ty(self.kinds.len() as u32);
+        self.kinds.push(kind.clone());
+        self.interned.insert(kind, ty);
+        ty
+    }
+
+    pub fn kind(&self, ty: Ty) -> &TyKind { &self.kinds[ty.0 as usize] }
+
+    /// `()`
+    pub fn unit(&mut self) -> Ty { self.intern(TyKind::Tuple(Vec::new())) }
+
+    pub fn error(&mut self) -> Ty { self.intern(TyKind::Error) }
+
+    pub fn prim(&mut self, prim: PrimTy) -> Ty {
+        let kind = match prim {
+            PrimTy::I8 => TyKind::Int(IntTy::I8),
+            PrimTy::I16 => TyKind::Int(IntTy::I16),
+            PrimTy::I32 => TyKind::Int(IntTy::I32),
+            PrimTy::I64 => TyKind::Int(IntTy::I64),
+            PrimTy::I128 => TyKind::Int(IntTy::I128),
+            PrimTy::Isize => TyKind::Int(IntTy::Isize),
+            PrimTy::U8 => TyKind::Uint(UintTy::U8),
+            PrimTy::U16 => TyKind::Uint(UintTy::U16),
+            PrimTy::U32 => TyKind::Uint(UintTy::U32),
+            PrimTy::U64 => TyKind::Uint(UintTy::U64),
+            PrimTy::U128 => TyKind::Uint(UintTy::U128),
+            PrimTy::Usize => TyKind::Uint(UintTy::Usize),
+            PrimTy::F16 => TyKind::Float(FloatTy::F16),
+            PrimTy::F32 => TyKind::Float(FloatTy::F32),
+            PrimTy::F64 => TyKind::Float(FloatTy::F64),
+            PrimTy::F128 => TyKind::Float(FloatTy::F128),
+            PrimTy::Bool => TyKind::Bool,
+            PrimTy::Char => TyKind::Char(CharTy::C32),
+            PrimTy::CChar8 => TyKind::Char(CharTy::C8),
+            PrimTy::CChar16 => TyKind::Char(CharTy::C16),
+            PrimTy::Str => TyKind::Str,
+        };
+        self.intern(kind)
+    }
+
+    pub fn add_adt(&mut self, id: DefId, def: AdtDef) {
+        self.adts.insert(id, def);
+    }
+
+    pub fn adt(&self, id: DefId) -> Option<&AdtDef> { self.adts.get(&id) }
+
+    pub fn adts(&self) -> impl Iterator<Item = (DefId, &AdtDef)> {
+        self.adts.iter().map(|(id, def)| (*id, def))
+    }
+
+    /// the name of a function, a trait, an associated type or a generic
+    /// parameter, to display [TyKind::FnDef], [TyKind::Dynamic],
+    /// [TyKind::Param] and [TyKind::Projection]
+    pub fn add_name(&mut self, id: DefId, name: impl Into<String>) {
+        self.names.insert(id, name.into());
+    }
+
+    pub fn name(&self, id: DefId) -> &str {
+        self.names.get(&id).map_or("{unknown}", |name| name.as_str())
+    }
+
+    pub fn add_fn_sig(&mut self, id: DefId, sig: FnSig) {
+        self.fn_sigs.insert(id, sig);
+    }
+
+    /// of a function, a method or a tuple constructor
+    pub fn fn_sig(&self, id: DefId) -> Option<&FnSig> { self.fn_sigs.get(&id) }
+
+    pub fn set_type_of(&mut self, id: DefId, ty: Ty) {
+        self.types.insert(id, ty);
+    }
+
+    /// the type of a constant or a static, the aliased type of a type
+    /// alias, or the self type of an impl
+    pub fn type_of(&self, id: DefId) -> Option<Ty> {
+        self.types.get(&id).copied()
+    }
+
+    pub fn add_const_value(&mut self, id: DefId, value: i128) {
+        self.consts.insert(id, value);
+    }
+
+    /// the value of a constant of an integer type
+    pub fn const_value(&self, id: DefId) -> Option<i128> {
+        self.consts.get(&id).copied()
+    }
+
+    pub fn add_inherent_impl(&mut self, id: DefId) {
+        self.inherent_impls.push(id);
+    }
+
+    /// e.g. `impl A {}`, see [TyCtxt::type_of] for their self types
+    pub fn inherent_impls(&self) -> &[DefId] { &self.inherent_impls }
+
+    pub fn add_trait(&mut self, id: DefId) {
+        self.traits.entry(id).or_default();
+    }
+
+    pub fn is_trait(&self, id: DefId) -> bool { self.traits.contains_key(&id) }
+
+    /// every trait with its impls
+    pub fn traits(&self) -> impl Iterator<Item = (DefId, &[DefId])> {
+        self.traits.iter().map(|(id, impls)| (*id, impls.as_slice()))
+    }
+
+    /// `impl_` is an impl of `trait_`
+    pub fn add_trait_impl(&mut self, trait_: DefId, impl_: DefId) {
+        self.traits.entry(trait_).or_default().push(impl_);
+        self.impl_traits.insert(impl_, trait_);
+    }
+
+    /// e.g. `impl A for B {}`, see [TyCtxt::type_of] for their self types
+    pub fn trait_impls(&self, trait_: DefId) -> &[DefId] {
+        self.traits.get(&trait_).map_or(&[], |impls| impls.as_slice())
+    }
+
+    /// the trait of an impl, [None] for an inherent impl
+    pub fn impl_trait(&self, impl_: DefId) -> Option<DefId> {
+        self.impl_traits.get(&impl_).copied()
+    }
+
+    pub fn add_impl_item(
+        &mut self, impl_: DefId, trait_item: DefId, item: DefId,
+    ) {
+        self.impl_items.insert((impl_, trait_item), item);
+    }
+
+    /// the item of `impl_` for `trait_item`, or `trait_item` itself for a
+    /// default
+    pub fn impl_item(&self, impl_: DefId, trait_item: DefId) -> Option<DefId> {
+        self.impl_items.get(&(impl_, trait_item)).copied()
+    }
+
+    pub fn set_generics(&mut self, id: DefId, params: Vec<DefId>) {
+        self.generics.insert(id, params);
+    }
+
+    /// the generic parameters of a function, a struct, an enum, a union or
+    /// an impl, see [TyKind::Param]
+    ///
+    /// `Self` by the trait comes first for the methods of a trait, and the
+    /// parameters of an impl for its items, the constructors of a struct or
+    /// its variants have the parameters of the type
+    pub fn generics(&self, id: DefId) -> &[DefId] {
+        self.generics.get(&id).map_or(&[], |params| params.as_slice())
+    }
+
+    /// a bound written both inline and in a `where` clause is kept once
+    pub fn set_predicates(
+        &mut self, id: DefId, mut predicates: Vec<Predicate>,
+    ) {
+        let mut seen = HashSet::new();
+        predicates.retain(|predicate| seen.insert(*predicate));
+        self.predicates.insert(id, predicates);
+    }
+
+    /// the bounds of the generic parameters of a function or an impl and
+    /// its `where` clause, also those of the impl for its methods, and
+    /// `Self: A` for the methods of a trait `A`
+    pub fn predicates(&self, id: DefId) -> &[Predicate] {
+        self.predicates.get(&id).map_or(&[], |predicates| predicates.as_slice())
+    }
+
+    /// `ty` with every component replaced by `f` where it returns a type,
+    /// from the outside in
+    pub fn fold(
+        &mut self, ty: Ty, f: &mut dyn FnMut(&mut Self, Ty) -> Option<Ty>,
+    ) -> Ty {
+        if let Some(ty) = f(self, ty) {
+            return ty;
+        }
+        let kind = match self.kind(ty).clone() {
+            TyKind::Ptr { mutable, ty } => {
+                TyKind::Ptr { mutable, ty: self.fold(ty, f) }
+            }
+            TyKind::Ref { mutable, ty } => {
+                TyKind::Ref { mutable, ty: self.fold(ty, f) }
+            }
+            TyKind::Box(ty) => TyKind::Box(self.fold(ty, f)),
+            TyKind::Slice(ty) => TyKind::Slice(self.fold(ty, f)),
+            TyKind::Array(ty, len) => TyKind::Array(self.fold(ty, f), len),
+            TyKind::Tuple(tys) => TyKind::Tuple(
+                tys.into_iter().map(|ty| self.fold(ty, f)).collect(),
+            ),
+            TyKind::Adt(id, args) => TyKind::Adt(
+                id,
+                args.into_iter().map(|ty| self.fold(ty, f)).collect(),
+            ),
+            TyKind::FnDef(id, args) => TyKind::FnDef(
+                id,
+                args.into_iter().map(|ty| self.fold(ty, f)).collect(),
+            ),
+            TyKind::FnPtr { params, ret } => TyKind::FnPtr {
+                params: params.into_iter().map(|ty| self.fold(ty, f)).collect(),
+                ret:    self.fold(ret, f),
+            },
+            TyKind::Projection { ty, trait_, item } => {
+                TyKind::Projection { ty: self.fold(ty, f), trait_, item }
+            }
+            _ => return ty,
+        };
+        self.intern(kind)
+    }
+
+    /// `f` finds something in `ty` or in one of its components
+    pub fn any(&self, ty: Ty, f: &mut dyn FnMut(Ty) -> bool) -> bool {
+        if f(ty) {
+            return true;
+        }
+        match self.kind(ty) {
+            TyKind::Ptr { ty, .. }
+            | TyKind::Ref { ty, .. }
+            | TyKind::Box(ty)
+            | TyKind::Slice(ty)
+            | TyKind::Array(ty, _)
+            | TyKind::Projection { ty, .. } => self.any(*ty, f),
+            TyKind::Tuple(tys)
+            | TyKind::Adt(_, tys)
+            | TyKind::FnDef(_, tys) => tys.iter().any(|ty| self.any(*ty, f)),
+            TyKind::FnPtr { params, ret } => {
+                params.iter().any(|ty| self.any(*ty, f)) || self.any(*ret, f)
+            }
+            _ => false,
+        }
+    }
+
+    /// `ty` with the generic parameters `params` replaced by `args`
+    pub fn subst(&mut self, ty: Ty, params: &[DefId], args: &[Ty]) -> Ty {
+        self.fold(ty, &mut |tcx, ty| match tcx.kind(ty) {
+            TyKind::Param(id) => {
+                let index = params.iter().position(|param| param == id)?;
+                args.get(index).copied()
+            }
+            _ => None,
+        })
+    }
+
+    /// `ty` with the generic parameters of `id` replaced by `args`, e.g.
+    /// of a function or an impl
+    pub fn instantiate(&mut self, ty: Ty, id: DefId, args: &[Ty]) -> Ty {
+        let params = self.generics(id).to_vec();
+        self.subst(ty, &params, args)
+    }
+
+    /// the type of the field `index` of the variant `variant` of the ADT
+    /// `ty`, with its generic arguments
+    pub fn field_ty(&mut self, ty: Ty, variant: usize, index: usize) -> Ty {
+        let TyKind::Adt(id, args) = self.kind(ty).clone() else {
+            return self.error();
+        };
+        let field = self.adt(id).unwrap().variants[variant].fields[index].ty;
+        self.instantiate(field, id, &args)
+    }
+
+    /// `ty` with its projections replaced by the associated types of the
+    /// impls, unless their types are generic or unknown
+    pub fn normalize(&mut self, ty: Ty) -> Ty {
+        self.fold(ty, &mut |tcx, ty| {
+            let TyKind::Projection { ty: self_ty, trait_, item } =
+                *tcx.kind(ty)
+            else {
+                return None;
+            };
+            let self_ty = tcx.normalize(self_ty);
+            let normalized = tcx.project(self_ty, trait_, item);
+            Some(normalized.unwrap_or_else(|| {
+                tcx.intern(TyKind::Projection { ty: self_ty, trait_, item })
+            }))
+        })
+    }
+
+    /// the associated type `item` of the impl of `trait_` for `ty`
+    pub fn project(
+        &mut self, ty: Ty, trait_: DefId, item: DefId,
+    ) -> Option<Ty> {
+        let (impl_, args) = self.find_impl(trait_, ty)?;
+        let found = self.impl_item(impl_, item)?;
+        let projected = self.type_of(found)?;
+        let projected = self.instantiate(projected, impl_, &args);
+        // a default of the trait, e.g. `type A = Self;`
+        let projected = self.subst(projected, &[trait_], &[ty]);
+        Some(self.normalize(projected))
+    }
+
+    /// the impl of `trait_` for `ty` with its generic arguments, unless
+    /// another impl may apply once the generic or unknown parts of `ty` are
+    /// known
+    pub fn find_impl(&self, trait_: DefId, ty: Ty) -> Option<(DefId, Vec<Ty>)> {
+        let impls = self.trait_impls(trait_);
+        if self.has_params(ty) {
+            let mut overlapping = impls.iter().filter(|impl_| {
+                self.type_of(**impl_)
+                    .is_some_and(|impl_ty| self.may_overlap(impl_ty, ty))
+            });
+            if overlapping.next().is_none() || overlapping.next().is_some() {
+                return None;
+            }
+        }
+        impls
+            .iter()
+            .find_map(|impl_| Some((*impl_, self.match_impl(*impl_, ty)?)))
+    }
+
+    /// the generic arguments of `impl_` for which its self type is `ty`,
+    /// e.g. `[i32]` for `impl<T> A for B<T>` and `B<i32>`
+    pub fn match_impl(&self, impl_: DefId, ty: Ty) -> Option<Vec<Ty>> {
+        fn matches(
+            tcx: &TyCtxt, pattern: Ty, ty: Ty, params: &[DefId],
+            args: &mut [Option<Ty>],
+        ) -> bool {
+            if let TyKind::Param(id) = tcx.kind(pattern)
+                && let Some(index) = params.iter().position(|p| p == id)
+            {
+                return *args[index].get_or_insert(ty) == ty;
+            }
+            tcx.components(pattern, ty).is_some_and(|pairs| {
+                pairs.into_iter().all(|(pattern, ty)| {
+                    matches(tcx, pattern, ty, params, args)
+                })
+            })
+        }
+        let params = self.generics(impl_);
+        let mut args = vec![None; params.len()];
+        if !matches(self, self.type_of(impl_)?, ty, params, &mut args) {
+            return None;
+        }
+        // a parameter only in the bounds is not constrained by `ty`
+        args.into_iter().collect()
+    }
+
+    /// `ty` contains a [TyKind::Param], an inference variable or a
+    /// projection
+    pub fn has_params(&self, ty: Ty) -> bool {
+        self.any(ty, &mut |ty| {
+            matches!(
+                self.kind(ty),
+                TyKind::Param(_) | TyKind::Infer(_) | TyKind::Projection { .. }
+            )
+        })
+    }
+
+    /// `ty` contains [TyKind::Error], so errors about it are redundant
+    pub fn references_error(&self, ty: Ty) -> bool {
+        self.any(ty, &mut |ty| *self.kind(ty) == TyKind::Error)
+    }
+
+    /// values of `a` may be values of `b`, with the generic parameters and
+    /// the unknown types as any type, e.g. for overlapping impls
+    pub fn may_overlap(&self, a: Ty, b: Ty) -> bool {
+        let any = |ty: Ty| {
+            matches!(
+                self.kind(ty),
+                TyKind::Param(_)
+                    | TyKind::Projection { .. }
+                    | TyKind::Infer(InferTy::Var(_))
+                    | TyKind::Error
+            )
+        };
+        if any(a) || any(b) {
+            return true;
+        }
+        self.components(a, b).is_some_and(|pairs| {
+            pairs.into_iter().all(|(a, b)| self.may_overlap(a, b))
+        })
+    }
+
+    /// the pairs of the components of `a` and `b` if they are the same kind
+    /// of type otherwise, e.g. `[(i32, u8)]` for `&i32` and `&u8`, and
+    /// nothing for equal types
+    pub fn components(&self, a: Ty, b: Ty) -> Option<Vec<(Ty, Ty)>> {
+        let zip = |a: &[Ty], b: &[Ty]| {
+            (a.len() == b.len())
+                .then(|| a.iter().copied().zip(b.iter().copied()).collect())
+        };
+        if a == b {
+            return Some(Vec::new());
+        }
+        match (self.kind(a), self.kind(b)) {
+            (
+                TyKind::Ptr { mutable: m1, ty: a },
+                TyKind::Ptr { mutable: m2, ty: b },
+            )
+            | (
+                TyKind::Ref { mutable: m1, ty: a },
+                TyKind::Ref { mutable: m2, ty: b },
+            ) if m1 == m2 => Some(vec![(*a, *b)]),
+            (TyKind::Box(a), TyKind::Box(b))
+            | (TyKind::Slice(a), TyKind::Slice(b)) => Some(vec![(*a, *b)]),
+            (TyKind::Array(a, n), TyKind::Array(b, m)) if n == m => {
+                Some(vec![(*a, *b)])
+            }
+            (TyKind::Tuple(a), TyKind::Tuple(b)) => zip(a, b),
+            (TyKind::Adt(x, a), TyKind::Adt(y, b))
+            | (TyKind::FnDef(x, a), TyKind::FnDef(y, b))
+                if x == y =>
+            {
+                zip(a, b)
+            }
+            (
+                TyKind::FnPtr { params: p1, ret: r1 },
+                TyKind::FnPtr { params: p2, ret: r2 },
+            ) => {
+                let mut pairs = zip(p1, p2)?;
+                pairs.push((*r1, *r2));
+                Some(pairs)
+            }
+            (
+                TyKind::Projection { ty: a, trait_: t1, item: i1 },
+                TyKind::Projection { ty: b, trait_: t2, item: i2 },
+            ) if t1 == t2 && i1 == i2 => Some(vec![(*a, *b)]),
+            _ => None,
+        }
+    }
+
+    /// e.g. `i32`, `usize` or the type of `1`
+    pub fn is_integral(&self, ty: Ty) -> bool {
+        matches!(
+            self.kind(ty),
+            TyKind::Int(_) | TyKind::Uint(_) | TyKind::Infer(InferTy::Int(_))
+        )
+    }
+
+    /// e.g. `f32` or the type of `1.0`
+    pub fn is_float(&self, ty: Ty) -> bool {
+        matches!(
+            self.kind(ty),
+            TyKind::Float(_) | TyKind::Infer(InferTy::Float(_))
+        )
+    }
+
+    pub fn is_numeric(&self, ty: Ty) -> bool {
+        self.is_integral(ty) || self.is_float(ty)
+    }
+
+    /// a number, a `bool`, a character or a raw pointer
+    pub fn is_scalar(&self, ty: Ty) -> bool {
+        self.is_numeric(ty)
+            || matches!(
+                self.kind(ty),
+                TyKind::Bool | TyKind::Char(_) | TyKind::Ptr { .. }
+            )
+    }
+
+    /// `ty` has a size known at compile time, unlike e.g. `[i32]` or `str`
+    pub fn is_sized(&self, ty: Ty) -> bool {
+        !matches!(
+            self.kind(ty),
+            TyKind::Str | TyKind::Slice(_) | TyKind::Dynamic(_)
+        )
+    }
+
+    /// `ty` is copied instead of moved: a primitive, a shared reference, a
+    /// pointer, a function, a tuple or an array of them, or an ADT with
+    /// `#[derive(Copy)]` whose arguments are copied
+    pub fn is_copy(&self, ty: Ty) -> bool {
+        match self.kind(ty) {
+            TyKind::Int(_)
+            | TyKind::Uint(_)
+            | TyKind::Float(_)
+            | TyKind::Bool
+            | TyKind::Char(_)
+            | TyKind::Ptr { .. }
+            | TyKind::Ref { mutable: false, .. }
+            | TyKind::FnDef(..)
+            | TyKind::FnPtr { .. }
+            | TyKind::Infer(InferTy::Int(_) | InferTy::Float(_))
+            | TyKind::Error => true,
+            TyKind::Array(elem, _) => self.is_copy(*elem),
+            TyKind::Tuple(elems) => {
+                elems.iter().all(|elem| self.is_copy(*elem))
+            }
+            TyKind::Adt(id, args) => {
+                self.adt(*id).is_some_and(|def| def.copy)
+                    && args.iter().all(|arg| self.is_copy(*arg))
+            }
+            _ => false,
+        }
+    }
+
+    /// the width in bits of an integer or a character type, and whether it
+    /// is signed
+    pub fn int_width(&self, ty: Ty, target: Target) -> Option<(u32, bool)> {
+        let pointer = target.pointer_size() as u32 * 8;
+        Some(match self.kind(ty) {
+            TyKind::Int(IntTy::I8) => (8, true),
+            TyKind::Int(IntTy::I16) => (16, true),
+            TyKind::Int(IntTy::I32) => (32, true),
+            TyKind::Int(IntTy::I64) => (64, true),
+            TyKind::Int(IntTy::I128) => (128, true),
+            TyKind::Int(IntTy::Isize) => (pointer, true),
+            TyKind::Uint(UintTy::U8) | TyKind::Char(CharTy::C8) => (8, false),
+            TyKind::Uint(UintTy::U16) | TyKind::Char(CharTy::C16) => {
+                (16, false)
+            }
+            TyKind::Uint(UintTy::U32) | TyKind::Char(CharTy::C32) => {
+                (32, false)
+            }
+            TyKind::Uint(UintTy::U64) => (64, false),
+            TyKind::Uint(UintTy::U128) => (128, false),
+            TyKind::Uint(UintTy::Usize) => (pointer, false),
+            _ => return None,
+        })
+    }
+
+    /// e.g. `&mut [i32; 3]`, in the syntax of the language
+    pub fn display(&self, ty: Ty) -> String {
+        let join = |tys: &[Ty]| {
+            let tys: Vec<_> = tys.iter().map(|ty| self.display(*ty)).collect();
+            tys.join(", ")
+        };
+        match self.kind(ty) {
+            TyKind::Int(ty) => ty.as_str().to_string(),
+            TyKind::Uint(ty) => ty.as_str().to_string(),
+            TyKind::Float(ty) => ty.as_str().to_string(),
+            TyKind::Bool => "bool".to_string(),
+            TyKind::Char(ty) => ty.as_str().to_string(),
+            TyKind::Str => "str".to_string(),
+            TyKind::Ptr { mutable, ty } => {
+                let kind = if *mutable { "mut" } else { "const" };
+                format!("*{kind} {}", self.display(*ty))
+            }
+            TyKind::Ref { mutable, ty } => {
+                let kind = if *mutable { "mut " } else { "" };
+                format!("&{kind}{}", self.display(*ty))
+            }
+            TyKind::Box(ty) => format!("{}.Box", self.display(*ty)),
+            TyKind::Slice(ty) => format!("[{}]", self.display(*ty)),
+            TyKind::Array(ty, len) => format!("[{}; {len}]", self.display(*ty)),
+            TyKind::Tuple(tys) if tys.len() == 1 => format!("({},)", join(tys)),
+            TyKind::Tuple(tys) => format!("({})", join(tys)),
+            TyKind::Adt(id, args) => {
+                let name = self.adt(*id).map_or("{unknown}", |def| &def.name);
+                match args.is_empty() {
+                    true => name.to_string(),
+                    false => format!("{name}<{}>", join(args)),
+                }
+            }
+            TyKind::FnDef(id, _) => format!("fn {}", self.name(*id)),
+            TyKind::FnPtr { params, ret } => {
+                format!("fn({}) -> {}", join(params), self.display(*ret))
+            }
+            TyKind::Dynamic(traits) => {
+                let traits: Vec<_> =
+                    traits.iter().map(|id| self.name(*id)).collect();
+                format!("dyn {}", traits.join(" + "))
+            }
+            TyKind::Param(id) if self.is_trait(*id) => "Self".to_string(),
+            TyKind::Param(id) => self.name(*id).to_string(),
+            TyKind::Projection { ty, trait_, item } => format!(
+                "<{} as {}>::{}",
+                self.display(*ty),
+                self.name(*trait_),
+                self.name(*item)
+            ),
+            TyKind::Infer(InferTy::Var(_)) => "_".to_string(),
+            TyKind::Infer(InferTy::Int(_)) => "{integer}".to_string(),
+            TyKind::Infer(InferTy::Float(_)) => "{float}".to_string(),
+            TyKind::Error => "{error}".to_string(),
+        }
+    }
+}
+impl IntTy {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            IntTy::I8 => "i8",
+            IntTy::I16 => "i16",
+            IntTy::I32 => "i32",
+            IntTy::I64 => "i64",
+            IntTy::I128 => "i128",
+            IntTy::Isize => "isize",
+        }
+    }
+}
+impl UintTy {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            UintTy::U8 => "u8",
+            UintTy::U16 => "u16",
+            UintTy::U32 => "u32",
+            UintTy::U64 => "u64",
+            UintTy::U128 => "u128",
+            UintTy::Usize => "usize",
+        }
+    }
+}
+impl FloatTy {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            FloatTy::F16 => "f16",
+            FloatTy::F32 => "f32",
+            FloatTy::F64 => "f64",
+            FloatTy::F128 => "f128",
+        }
+    }
+}
+impl CharTy {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            CharTy::C8 => "c_char8",
+            CharTy::C16 => "c_char16",
+            CharTy::C32 => "char",
+        }
+    }
+}
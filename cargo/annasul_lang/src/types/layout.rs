@@ -0,0 +1,539 @@
+// Copyright (c) 2025 air (https://yuanair.github.io).
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published by
+// the Free Software Foundation, version 3 of the License only.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+use std::collections::HashMap;
+
+use crate::{
+    semantic::resolve::DefId,
+    types::{
+        AdtKind,
+        CharTy,
+        FloatTy,
+        IntTy,
+        ReprOptions,
+        Ty,
+        TyCtxt,
+        TyKind,
+        UintTy,
+    },
+};
+/// the most ADTs laid out inside each other, see [LayoutError::Recursive]
+const MAX_DEPTH: usize = 64;
+/// the machine the code is compiled for, see [TyCtxt::layout_of]
+#[derive(Debug, Clone, Copy, Hash, PartialEq, Eq)]
+pub struct Target {
+    /// `32` or `64`
+    pointer_width: u32,
+}
+/// the size and the alignment of a type in bytes, and where its fields are
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Layout {
+    pub size:     u64,
+    /// a power of two, the size is a multiple of it
+    pub align:    u64,
+    /// the offset of every field of a struct, a union or a tuple, in
+    /// declaration order
+    pub offsets:  Vec<u64>,
+    /// see [TagLayout], [None] unless an enum has more than one variant or
+    /// a `#[repr(...)]` of its discriminant
+    pub tag:      Option<TagLayout>,
+    /// the offsets of the fields of every variant of an enum, after the tag
+    pub variants: Vec<Vec<u64>>,
+}
+/// the discriminant of an enum, at offset `0`
+#[derive(Debug, Clone, Copy, Hash, PartialEq, Eq)]
+pub struct TagLayout {
+    pub size:   u64,
+    pub signed: bool,
+}
+/// why a type has no [Layout]
+#[derive(Debug, Clone, Copy, Hash, PartialEq, Eq)]
+pub enum LayoutError {
+    /// e.g. `str` or `[i32]`, or a struct with such a field
+    Unsized(Ty),
+    /// a struct or an enum that contains itself without indirection
+    Recursive(DefId),
+    /// larger than `isize::MAX` of the target
+    TooLarge(Ty),
+    /// e.g. [TyKind::Error], which is already reported
+    Unknown(Ty),
+}
+/// computes layouts, the ADTs in progress detect recursive types
+struct LayoutCx<'a> {
+    tcx:    &'a mut TyCtxt,
+    target: Target,
+    stack:  Vec<Ty>,
+    cache:  HashMap<Ty, Layout>,
+}
+impl Target {
+    pub const BITS_32: Target = Target { pointer_width: 32 };
+    pub const BITS_64: Target = Target { pointer_width: 64 };
+
+    /// [None] unless `pointer_width` is `32` or `64`
+    pub fn new(pointer_width: u32) -> Option<Self> {
+        matches!(pointer_width, 32 | 64).then_some(Self { pointer_width })
+    }
+
+    /// the machine the compiler runs on
+    pub fn host() -> Self {
+        match cfg!(target_pointer_width = "32") {
+            true => Self::BITS_32,
+            false => Self::BITS_64,
+        }
+    }
+
+    pub fn pointer_width(&self) -> u32 { self.pointer_width }
+
+    /// the size of `usize`, `isize` and thin pointers in bytes
+    pub fn pointer_size(&self) -> u64 { self.pointer_width as u64 / 8 }
+
+    /// `isize::MAX`, the size of the largest object
+    pub fn max_size(&self) -> u64 { (1 << (self.pointer_width - 1)) - 1 }
+}
+impl Default for Target {
+    fn default() -> Self { Self::host() }
+}
+impl Layout {
+    /// e.g. `i32`, aligned to its size
+    fn scalar(size: u64) -> Self {
+        Self {
+            size,
+            align: size,
+            offsets: Vec::new(),
+            tag: None,
+            variants: Vec::new(),
+        }
+    }
+}
+impl LayoutError {
+    /// e.g. for a diagnostic
+    pub fn message(&self, tcx: &TyCtxt) -> String {
+        match self {
+            LayoutError::Unsized(ty) => format!(
+                "the size for values of type `{}` cannot be known at \
+                 compilation time",
+                tcx.display(*ty)
+            ),
+            LayoutError::Recursive(id) => {
+                let name = tcx.adt(*id).map_or("{unknown}", |def| &def.name);
+                format!("recursive type `{name}` has infinite size")
+            }
+            LayoutError::TooLarge(ty) => format!(
+                "values of the type `{}` are too big for the target \
+                 architecture",
+                tcx.display(*ty)
+            ),
+            LayoutError::Unknown(ty) => {
+                format!("the layout of `{}` is unknown", tcx.display(*ty))
+            }
+        }
+    }
+}
+impl TyCtxt {
+    /// the size, the alignment and the field offsets of `ty` on `target`
+    ///
+    /// primitives are aligned to their size, e.g. `i128` to 16 bytes, the
+    /// fields of a struct or a tuple are ordered by decreasing alignment
+    /// unless it is `#[repr(C)]`, and an enum is its tag followed by the
+    /// fields of a variant, with the generic arguments of a generic ADT
+    pub fn layout_of(
+        &mut self, ty: Ty, target: Target,
+    ) -> Result<Layout, LayoutError> {
+        let mut cx = LayoutCx {
+            tcx: self,
+            target,
+            stack: Vec::new(),
+            cache: HashMap::new(),
+        };
+        cx.layout_of(ty)
+    }
+
+    /// `size_of::<T>()`
+    pub fn size_of(
+        &mut self, ty: Ty, target: Target,
+    ) -> Result<u64, LayoutError> {
+        Ok(self.layout_of(ty, target)?.size)
+    }
+
+    /// `align_of::<T>()`
+    pub fn align_of(
+        &mut self, ty: Ty, target: Target,
+    ) -> Result<u64, LayoutError> {
+        Ok(self.layout_of(ty, target)?.align)
+    }
+}
+impl LayoutCx<'_> {
+    fn layout_of(&mut self, ty: Ty) -> Result<Layout, LayoutError> {
+        if let Some(layout) = self.cache.get(&ty) {
+            return Ok(layout.clone());
+        }
+        let pointer = self.target.pointer_size();
+        let layout = match self.tcx.kind(ty).clone() {
+            TyKind::Int(int) => Layout::scalar(match int {
+                IntTy::I8 => 1,
+                IntTy::I16 => 2,
+                IntTy::I32 => 4,
+                IntTy::I64 => 8,
+                IntTy::I128 => 16,
+                IntTy::Isize => pointer,
+            }),
+            TyKind::Uint(uint) => Layout::scalar(match uint {
+                UintTy::U8 => 1,
+                UintTy::U16 => 2,
+                UintTy::U32 => 4,
+                UintTy::U64 => 8,
+                UintTy::U128 => 16,
+                UintTy::Usize => pointer,
+            }),
+            TyKind::Float(float) => Layout::scalar(match float {
+                FloatTy::F16 => 2,
+                FloatTy::F32 => 4,
+                FloatTy::F64 => 8,
+                FloatTy::F128 => 16,
+            }),
+            TyKind::Bool => Layout::scalar(1),
+            TyKind::Char(char) => Layout::scalar(match char {
+                CharTy::C8 => 1,
+                CharTy::C16 => 2,
+                CharTy::C32 => 4,
+            }),
+            TyKind::Str | TyKind::Slice(_) | TyKind::Dynamic(_) => {
+                return Err(LayoutError::Unsized(ty));
+            }
+            // a pointer to an unsized type also holds its length or vtable
+            TyKind::Ptr { ty: pointee, .. }
+            | TyKind::Ref { ty: pointee, .. }
+            | TyKind::Box(pointee) => match self.tcx.is_sized(pointee) {
+                true => Layout::scalar(pointer),
+                false => {
+                    Layout { size: 2 * pointer, ..Layout::scalar(pointer) }
+                }
+            },
+            TyKind::FnPtr { .. } => Layout::scalar(pointer),
+            TyKind::FnDef(..) => Layout { align: 1, ..Layout::scalar(0) },
+            TyKind::Array(elem, len) => {
+                let elem = self.layout_of(elem)?;
+                let size = elem.size.checked_mul(len);
+                let size = size.ok_or(LayoutError::TooLarge(ty))?;
+                Layout { size, align: elem.align, ..Layout::scalar(0) }
+            }
+            TyKind::Tuple(tys) => {
+                let (offsets, size, align) =
+                    self.univariant(&tys, ReprOptions::default(), 0, 1)?;
+                let size = size.next_multiple_of(align);
+                Layout { size, align, offsets, ..Layout::scalar(0) }
+            }
+            TyKind::Adt(id, args) => {
+                // also for generic arguments growing without end, e.g.
+                // `struct A<T>(A<(T,)>);`
+                if self.stack.contains(&ty) || self.stack.len() > MAX_DEPTH {
+                    return Err(LayoutError::Recursive(id));
+                }
+                self.stack.push(ty);
+                let layout = self.adt(ty, id, &args);
+                self.stack.pop();
+                layout?
+            }
+            // generic types are laid out after monomorphization
+            TyKind::Param(_)
+            | TyKind::Projection { .. }
+            | TyKind::Infer(_)
+            | TyKind::Error => {
+                return Err(LayoutError::Unknown(ty));
+            }
+        };
+        if layout.size > self.target.max_size() {
+            return Err(LayoutError::TooLarge(ty));
+        }
+        self.cache.insert(ty, layout.clone());
+        Ok(layout)
+    }
+
+    fn adt(
+        &mut self, ty: Ty, id: DefId, args: &[Ty],
+    ) -> Result<Layout, LayoutError> {
+        let def = self.tcx.adt(id).ok_or(LayoutError::Unknown(ty))?.clone();
+        let repr = def.repr;
+        let fields = |cx: &mut Self, index: usize| -> Vec<Ty> {
+            let fields = def.variants[index].fields.iter();
+            fields.map(|field| cx.tcx.instantiate(field.ty, id, args)).collect()
+        };
+        match def.kind {
+            AdtKind::Struct => {
+                let fields = fields(self, 0);
+                let (offsets, size, align) =
+                    self.univariant(&fields, repr, 0, 1)?;
+                let size = size.next_multiple_of(align);
+                Ok(Layout { size, align, offsets, ..Layout::scalar(0) })
+            }
+            AdtKind::Union => {
+                let mut layout = Layout { align: 1, ..Layout::scalar(0) };
+                for field in fields(self, 0) {
+                    let field = self.sized_layout_of(field)?;
+                    layout.size = layout.size.max(field.size);
+                    layout.align = layout.align.max(field.align);
+                    layout.offsets.push(0);
+                }
+                if repr.packed {
+                    layout.align = 1;
+                }
+                layout.size = layout.size.next_multiple_of(layout.align);
+                Ok(layout)
+            }
+            AdtKind::Enum => {
+                let tag = self.tag(id)?;
+                let (start, mut align) = match tag {
+                    Some(tag) => (tag.size, tag.size),
+                    None => (0, 1),
+                };
+                let mut end = start;
+                let mut variants = Vec::new();
+                for index in 0..def.variants.len() {
+                    let fields = fields(self, index);
+                    let (offsets, size, variant_align) =
+                        self.univariant(&fields, repr, start, align)?;
+                    end = end.max(size);
+                    align = align.max(variant_align);
+                    variants.push(offsets);
+                }
+                let size = end.next_multiple_of(align);
+                Ok(Layout { size, align, tag, variants, ..Layout::scalar(0) })
+            }
+        }
+    }
+
+    /// the integer of the discriminant of an enum, see [Layout::tag]
+    fn tag(&mut self, id: DefId) -> Result<Option<TagLayout>, LayoutError> {
+        let def = self.tcx.adt(id).unwrap().clone();
+        if let Some(int) = def.repr.int {
+            let signed = matches!(self.tcx.kind(int), TyKind::Int(_));
+            let size = self.layout_of(int)?.size;
+            return Ok(Some(TagLayout { size, signed }));
+        }
+        if def.repr.c {
+            // a C `int`
+            return Ok(Some(TagLayout { size: 4, signed: true }));
+        }
+        if def.variants.len() <= 1 {
+            return Ok(None);
+        }
+        let discrs = def.variants.iter().map(|variant| variant.discr);
+        let (min, max) = (discrs.clone().min().unwrap(), discrs.max().unwrap());
+        let signed = min < 0;
+        let size = [1, 2, 4, 8, 16]
+            .into_iter()
+            .find(|size: &u32| {
+                let bits = size * 8;
+                match signed {
+                    true => {
+                        min >= -(1 << (bits - 1)) && max < (1 << (bits - 1))
+                    }
+                    false => bits == 128 || max < (1 << bits),
+                }
+            })
+            .unwrap_or(16);
+        Ok(Some(TagLayout { size: size as u64, signed }))
+    }
+
+    fn sized_layout_of(&mut self, ty: Ty) -> Result<Layout, LayoutError> {
+        match self.tcx.is_sized(ty) {
+            true => self.layout_of(ty),
+            false => Err(LayoutError::Unsized(ty)),
+        }
+    }
+
+    /// the offsets, the end of the last field and the alignment of
+    /// `fields` placed after `start` bytes aligned to `align`, e.g. a tag
+    ///
+    /// the fields are ordered by decreasing alignment to need less
+    /// padding, or by increasing alignment after a tag so the small ones
+    /// fill the space next to it
+    fn univariant(
+        &mut self, fields: &[Ty], repr: ReprOptions, start: u64, align: u64,
+    ) -> Result<(Vec<u64>, u64, u64), LayoutError> {
+        let layouts = fields
+            .iter()
+            .map(|field| self.sized_layout_of(*field))
+            .collect::<Result<Vec<_>, _>>()?;
+        let mut order: Vec<_> = (0..fields.len()).collect();
+        if !repr.c && !repr.packed {
+            match start {
+                0 => {
+                    order.sort_by_key(|&i| std::cmp::Reverse(layouts[i].align))
+                }
+                _ => order.sort_by_key(|&i| layouts[i].align),
+            }
+        }
+        let mut offsets = vec![0; fields.len()];
+        let (mut offset, mut align) = (start, align);
+        for i in order {
+            let field_align = if repr.packed { 1 } else { layouts[i].align };
+            offset = offset.next_multiple_of(field_align);
+            offsets[i] = offset;
+            offset = offset
+                .checked_add(layouts[i].size)
+                .ok_or(LayoutError::TooLarge(fields[i]))?;
+            align = align.max(field_align);
+        }
+        Ok((offsets, offset, align))
+    }
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        semantic::resolve::PrimTy,
+        types::{AdtDef, FieldDef, VariantDef},
+    };
+    fn adt(
+        tcx: &mut TyCtxt, id: u32, kind: AdtKind, variants: &[&[Ty]],
+        repr: ReprOptions,
+    ) -> Ty {
+        let id = DefId::new(id as usize);
+        let variants = variants
+            .iter()
+            .enumerate()
+            .map(|(index, fields)| VariantDef {
+                name:   format!("V{index}"),
+                fields: fields
+                    .iter()
+                    .enumerate()
+                    .map(|(index, ty)| FieldDef {
+                        name:       index.to_string(),
+                        ty:         *ty,
+                        vis:        crate::semantic::resolve::Vis::Public,
+                        deprecated: None,
+                    })
+                    .collect(),
+                discr:  index as i128,
+            })
+            .collect();
+        let name = format!("A{}", id.index());
+        tcx.add_adt(id, AdtDef { kind, name, variants, repr, copy: false });
+        tcx.intern(TyKind::Adt(id, Vec::new()))
+    }
+    /// `(size, align)` on a 32-bit and a 64-bit target
+    fn sizes(tcx: &mut TyCtxt, ty: Ty) -> [(u64, u64); 2] {
+        [Target::BITS_32, Target::BITS_64].map(|target| {
+            let layout = tcx.layout_of(ty, target).unwrap();
+            (layout.size, layout.align)
+        })
+    }
+    #[test]
+    fn primitives_and_pointers() {
+        let mut tcx = TyCtxt::new();
+        let expected = [
+            (PrimTy::I8, [(1, 1), (1, 1)]),
+            (PrimTy::U16, [(2, 2), (2, 2)]),
+            (PrimTy::F16, [(2, 2), (2, 2)]),
+            (PrimTy::Char, [(4, 4), (4, 4)]),
+            (PrimTy::CChar16, [(2, 2), (2, 2)]),
+            (PrimTy::I64, [(8, 8), (8, 8)]),
+            (PrimTy::F128, [(16, 16), (16, 16)]),
+            (PrimTy::Isize, [(4, 4), (8, 8)]),
+            (PrimTy::Usize, [(4, 4), (8, 8)]),
+            (PrimTy::Bool, [(1, 1), (1, 1)]),
+        ];
+        for (prim, sizes_) in expected {
+            let ty = tcx.prim(prim);
+            assert_eq!(sizes(&mut tcx, ty), sizes_, "{}", prim.as_str());
+        }
+        let i32 = tcx.prim(PrimTy::I32);
+        let str = tcx.prim(PrimTy::Str);
+        let slice = tcx.intern(TyKind::Slice(i32));
+        let thin = tcx.intern(TyKind::Ref { mutable: false, ty: i32 });
+        let wide = tcx.intern(TyKind::Ptr { mutable: true, ty: slice });
+        let boxed = tcx.intern(TyKind::Box(str));
+        assert_eq!(sizes(&mut tcx, thin), [(4, 4), (8, 8)]);
+        assert_eq!(sizes(&mut tcx, wide), [(8, 4), (16, 8)]);
+        assert_eq!(sizes(&mut tcx, boxed), [(8, 4), (16, 8)]);
+        assert_eq!(
+            tcx.layout_of(str, Target::BITS_64),
+            Err(LayoutError::Unsized(str))
+        );
+        let array = tcx.intern(TyKind::Array(i32, 3));
+        assert_eq!(sizes(&mut tcx, array), [(12, 4), (12, 4)]);
+        let huge = tcx.intern(TyKind::Array(i32, 1 << 30));
+        assert_eq!(
+            tcx.layout_of(huge, Target::BITS_32),
+            Err(LayoutError::TooLarge(huge))
+        );
+        assert_eq!(tcx.size_of(huge, Target::BITS_64), Ok(1 << 32));
+        let unit = tcx.unit();
+        assert_eq!(sizes(&mut tcx, unit), [(0, 1), (0, 1)]);
+    }
+    #[test]
+    fn field_order_and_padding() {
+        let mut tcx = TyCtxt::new();
+        let u8 = tcx.prim(PrimTy::U8);
+        let u16 = tcx.prim(PrimTy::U16);
+        let u64 = tcx.prim(PrimTy::U64);
+        let tuple = tcx.intern(TyKind::Tuple(vec![u8, u64, u16]));
+        let layout = tcx.layout_of(tuple, Target::BITS_64).unwrap();
+        assert_eq!((layout.size, layout.align), (16, 8));
+        assert_eq!(layout.offsets, [10, 0, 8]);
+        let c = ReprOptions { c: true, ..ReprOptions::default() };
+        let c = adt(&mut tcx, 1, AdtKind::Struct, &[&[u8, u64, u16]], c);
+        let layout = tcx.layout_of(c, Target::BITS_64).unwrap();
+        assert_eq!((layout.size, layout.align), (24, 8));
+        assert_eq!(layout.offsets, [0, 8, 16]);
+        let packed = ReprOptions { packed: true, ..ReprOptions::default() };
+        let packed =
+            adt(&mut tcx, 2, AdtKind::Struct, &[&[u8, u64, u16]], packed);
+        let layout = tcx.layout_of(packed, Target::BITS_64).unwrap();
+        assert_eq!((layout.size, layout.align), (11, 1));
+        assert_eq!(layout.offsets, [0, 1, 9]);
+        let union =
+            adt(&mut tcx, 3, AdtKind::Union, &[&[u8, u16, tuple]], c_repr());
+        assert_eq!(sizes(&mut tcx, union), [(16, 8), (16, 8)]);
+    }
+    #[test]
+    fn enums() {
+        let mut tcx = TyCtxt::new();
+        let u8 = tcx.prim(PrimTy::U8);
+        let u32 = tcx.prim(PrimTy::U32);
+        let default = ReprOptions::default();
+        let empty = adt(&mut tcx, 1, AdtKind::Enum, &[], default);
+        assert_eq!(sizes(&mut tcx, empty), [(0, 1), (0, 1)]);
+        let single = adt(&mut tcx, 2, AdtKind::Enum, &[&[u32]], default);
+        assert_eq!(sizes(&mut tcx, single), [(4, 4), (4, 4)]);
+        let option =
+            adt(&mut tcx, 3, AdtKind::Enum, &[&[], &[u8, u32]], default);
+        let layout = tcx.layout_of(option, Target::BITS_64).unwrap();
+        assert_eq!((layout.size, layout.align), (8, 4));
+        assert_eq!(layout.tag, Some(TagLayout { size: 1, signed: false }));
+        assert_eq!(layout.variants, [vec![], vec![1, 4]]);
+        let repr = ReprOptions { int: Some(u32), ..default };
+        let tagged = adt(&mut tcx, 4, AdtKind::Enum, &[&[], &[u8]], repr);
+        assert_eq!(sizes(&mut tcx, tagged), [(8, 4), (8, 4)]);
+        let c = adt(&mut tcx, 5, AdtKind::Enum, &[&[]], c_repr());
+        assert_eq!(sizes(&mut tcx, c), [(4, 4), (4, 4)]);
+    }
+    #[test]
+    fn recursive_types() {
+        let mut tcx = TyCtxt::new();
+        let list = tcx.intern(TyKind::Adt(DefId::new(1), Vec::new()));
+        let boxed = tcx.intern(TyKind::Box(list));
+        adt(&mut tcx, 1, AdtKind::Struct, &[&[boxed]], c_repr());
+        assert_eq!(sizes(&mut tcx, list), [(4, 4), (8, 8)]);
+        let node = tcx.intern(TyKind::Adt(DefId::new(2), Vec::new()));
+        let wrapper = adt(&mut tcx, 3, AdtKind::Struct, &[&[node]], c_repr());
+        adt(&mut tcx, 2, AdtKind::Struct, &[&[list, wrapper]], c_repr());
+        assert_eq!(
+            tcx.layout_of(node, Target::BITS_64),
+            Err(LayoutError::Recursive(DefId::new(2)))
+        );
+    }
+    fn c_repr() -> ReprOptions {
+        ReprOptions { c: true, ..ReprOptions::default() }
+    }
+}
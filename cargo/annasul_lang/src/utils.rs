@@ -10,4 +10,6 @@
 //
 // You should have received a copy of the GNU Affero General Public License
 // along with this program. If not, see <https://www.gnu.org/licenses/>.
+pub mod diagnostic;
 pub mod error;
+pub mod span;
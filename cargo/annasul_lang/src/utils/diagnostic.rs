@@ -0,0 +1,751 @@
+// Copyright (c) 2025 air (https://yuanair.github.io).
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published by
+// the Free Software Foundation, version 3 of the License only.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+use std::{collections::BTreeMap, fmt::Display, io::Write};
+
+use colored::{Color, Colorize};
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+use crate::utils::{
+    error::Error,
+    span::{Expansion, FileId, SourceFile, SourceMap, Span},
+};
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Debug, Clone, Copy, Hash, Ord, PartialOrd, PartialEq, Eq)]
+pub enum Level {
+    Error,
+    Warning,
+    Note,
+    Help,
+}
+/// a message about the source code, e.g. an error with the spans it is
+/// about
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Debug, Clone, Hash, PartialEq, Eq)]
+pub struct Diagnostic {
+    level:   Level,
+    /// e.g. `E0001`
+    code:    Option<String>,
+    /// e.g. `unreachable_patterns`, see [LINTS](crate::attr::LINTS)
+    lint:    Option<String>,
+    message: String,
+    labels:  Vec<Label>,
+    notes:   Vec<String>,
+    helps:   Vec<String>,
+}
+/// a span underlined in the source code, with an optional message
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Debug, Clone, Hash, PartialEq, Eq)]
+pub struct Label {
+    span:    Span,
+    message: String,
+    /// underlined with `^` instead of `-`
+    primary: bool,
+}
+/// the diagnostics of every compiler stage
+#[derive(Default, Debug, Clone)]
+pub struct Diagnostics {
+    diagnostics: Vec<Diagnostic>,
+}
+/// renders [Diagnostic]s like rustc does
+#[derive(Default, Debug, Clone)]
+pub struct Emitter {
+    color: bool,
+}
+impl Diagnostic {
+    pub fn new(level: Level, message: impl Into<String>) -> Self {
+        Self {
+            level,
+            code: None,
+            lint: None,
+            message: message.into(),
+            labels: Vec::new(),
+            notes: Vec::new(),
+            helps: Vec::new(),
+        }
+    }
+
+    pub fn error(message: impl Into<String>) -> Self {
+        Self::new(Level::Error, message)
+    }
+
+    pub fn warning(message: impl Into<String>) -> Self {
+        Self::new(Level::Warning, message)
+    }
+
+    pub fn with_code(mut self, code: impl Into<String>) -> Self {
+        self.code = Some(code.into());
+        self
+    }
+
+    /// a warning that `#[allow(...)]` and `#[deny(...)]` change, see
+    /// [LintLevels](crate::attr::LintLevels)
+    pub fn with_lint(mut self, lint: impl Into<String>) -> Self {
+        self.lint = Some(lint.into());
+        self
+    }
+
+    pub fn with_level(mut self, level: Level) -> Self {
+        self.level = level;
+        self
+    }
+
+    /// underlines `span` with `^`
+    pub fn with_primary(
+        mut self, span: Span, message: impl Into<String>,
+    ) -> Self {
+        self.labels.push(Label {
+            span,
+            message: message.into(),
+            primary: true,
+        });
+        self
+    }
+
+    /// underlines `span` with `-`
+    pub fn with_secondary(
+        mut self, span: Span, message: impl Into<String>,
+    ) -> Self {
+        self.labels.push(Label {
+            span,
+            message: message.into(),
+            primary: false,
+        });
+        self
+    }
+
+    /// e.g. `= note: ...`
+    pub fn with_note(mut self, note: impl Into<String>) -> Self {
+        self.notes.push(note.into());
+        self
+    }
+
+    /// e.g. `= help: ...`
+    pub fn with_help(mut self, help: impl Into<String>) -> Self {
+        self.helps.push(help.into());
+        self
+    }
+
+    /// labels the call sites of the expansions `backtrace`, the innermost
+    /// first, that the diagnostic originates in, a call site expanded
+    /// repeatedly by a recursive macro is labeled once
+    pub fn with_backtrace(mut self, backtrace: &[&Expansion]) -> Self {
+        let Some(innermost) = backtrace.first() else {
+            return self;
+        };
+        // by the first expansion of each call site
+        let mut frames: Vec<(&Expansion, usize)> = Vec::new();
+        for expansion in backtrace {
+            let same = frames.iter_mut().find(|(other, _)| {
+                other.name == expansion.name
+                    && other.call_site.same_source(expansion.call_site)
+            });
+            match same {
+                Some((_, count)) => *count += 1,
+                None => frames.push((expansion, 1)),
+            }
+        }
+        for (expansion, count) in frames {
+            let mut label =
+                format!("in this expansion of `{}!`", expansion.name);
+            if count > 1 {
+                label += &format!(" (×{count})");
+            }
+            self = self.with_secondary(expansion.call_site, label);
+        }
+        let level = match self.level {
+            Level::Warning => "warning",
+            _ => "error",
+        };
+        self.with_note(format!(
+            "this {level} originates in the macro `{}`",
+            innermost.name
+        ))
+    }
+
+    /// [Diagnostic::with_backtrace] of the expansions the primary span
+    /// originates in, for the stages after expansion
+    pub fn with_expansions(self, source_map: &SourceMap) -> Self {
+        match self.primary_span() {
+            Some(span) => self.with_backtrace(&source_map.backtrace(span)),
+            None => self,
+        }
+    }
+
+    pub fn level(&self) -> Level { self.level }
+
+    pub fn code(&self) -> Option<&str> { self.code.as_deref() }
+
+    pub fn lint(&self) -> Option<&str> { self.lint.as_deref() }
+
+    pub fn message(&self) -> &str { &self.message }
+
+    pub fn labels(&self) -> &[Label] { &self.labels }
+
+    pub fn notes(&self) -> &[String] { &self.notes }
+
+    pub fn helps(&self) -> &[String] { &self.helps }
+
+    /// the span of the first primary label
+    pub fn primary_span(&self) -> Option<Span> {
+        self.labels.iter().find(|label| label.primary).map(|label| label.span)
+    }
+}
+impl Label {
+    pub fn span(&self) -> Span { self.span }
+
+    pub fn message(&self) -> &str { &self.message }
+
+    pub fn is_primary(&self) -> bool { self.primary }
+}
+impl Diagnostics {
+    pub fn new() -> Self { Self::default() }
+
+    pub fn push(&mut self, diagnostic: impl Into<Diagnostic>) {
+        self.diagnostics.push(diagnostic.into());
+    }
+
+    pub fn has_errors(&self) -> bool { self.error_count() > 0 }
+
+    pub fn error_count(&self) -> usize {
+        self.diagnostics
+            .iter()
+            .filter(|diagnostic| diagnostic.level == Level::Error)
+            .count()
+    }
+
+    pub fn is_empty(&self) -> bool { self.diagnostics.is_empty() }
+
+    pub fn len(&self) -> usize { self.diagnostics.len() }
+
+    pub fn iter(&self) -> std::slice::Iter<'_, Diagnostic> {
+        self.diagnostics.iter()
+    }
+}
+impl Emitter {
+    pub fn new(color: bool) -> Self { Self { color } }
+
+    /// writes every diagnostic, followed by an error count summary
+    pub fn emit(
+        &self, source_map: &SourceMap, diagnostics: &Diagnostics,
+        out: &mut impl Write,
+    ) -> std::io::Result<()> {
+        for diagnostic in diagnostics.iter() {
+            writeln!(out, "{}", self.render(source_map, diagnostic))?;
+        }
+        match diagnostics.error_count() {
+            0 => Ok(()),
+            1 => writeln!(
+                out,
+                "{}",
+                self.paint(
+                    "error: aborting due to 1 previous error",
+                    Color::Red
+                )
+            ),
+            count => writeln!(
+                out,
+                "{}",
+                self.paint(
+                    &format!("error: aborting due to {count} previous errors"),
+                    Color::Red,
+                )
+            ),
+        }
+    }
+
+    /// e.g.
+    ///
+    /// ```text
+    /// error[E0001]: unknown character `¤`
+    ///  --> test.aau:1:11
+    ///   |
+    /// 1 | let x = 1 ¤ 2;
+    ///   |           ^ not a token
+    ///   |
+    ///   = help: remove it
+    /// ```
+    pub fn render(
+        &self, source_map: &SourceMap, diagnostic: &Diagnostic,
+    ) -> String {
+        let level_color = diagnostic.level.color();
+        let mut out = String::new();
+        let header = match &diagnostic.code {
+            Some(code) => format!("{}[{code}]", diagnostic.level),
+            None => diagnostic.level.to_string(),
+        };
+        out.push_str(&self.paint_bold(&header, level_color));
+        out.push_str(
+            &self
+                .paint_bold(&format!(": {}", diagnostic.message), Color::White),
+        );
+        // labels grouped by file then line, the primary file first
+        let primary_file = diagnostic.primary_span().map(|span| span.file());
+        let mut files: BTreeMap<(bool, FileId), FileLabels> = BTreeMap::new();
+        for label in &diagnostic.labels {
+            let file = label.span.file();
+            let source_file = source_map.file(file);
+            let labels =
+                files.entry((Some(file) != primary_file, file)).or_default();
+            let (end_line, end_col) = end_line_col(source_file, label.span);
+            let line = label.span.line();
+            if end_line == line {
+                labels.lines.entry(line).or_default().push(label);
+                continue;
+            }
+            labels.lines.entry(line).or_default();
+            labels.lines.entry(end_line).or_default();
+            // the lines between are elided if there are many
+            if end_line - line <= MAX_MULTILINE_LINES {
+                for inner in line + 1..end_line {
+                    labels.lines.entry(inner).or_default();
+                }
+            }
+            labels.multiline.push((label, end_line, end_col));
+        }
+        let width = files
+            .values()
+            .flat_map(|labels| labels.lines.keys())
+            .max()
+            .map_or(1, |line| line.to_string().len());
+        let gutter = self.paint_bold(&format!("{:width$} |", ""), Color::Blue);
+        for (index, ((_, file), labels)) in files.iter().enumerate() {
+            let source_file = source_map.file(*file);
+            // the primary span, or the first label of another file
+            let first = diagnostic
+                .primary_span()
+                .filter(|span| span.file() == *file)
+                .or_else(|| {
+                    let line = labels.lines.keys().next()?;
+                    diagnostic.labels.iter().map(|label| label.span).find(
+                        |span| span.file() == *file && span.line() == *line,
+                    )
+                });
+            let location = match first {
+                Some(span) => format!(
+                    "{}:{}:{}",
+                    source_file.name().display(),
+                    span.line(),
+                    span.col()
+                ),
+                None => source_file.name().display().to_string(),
+            };
+            let arrow = if index == 0 { "-->" } else { ":::" };
+            out.push_str(&format!(
+                "\n{:width$}{} {location}",
+                "",
+                self.paint_bold(arrow, Color::Blue)
+            ));
+            out.push_str(&format!("\n{gutter}"));
+            // a multi-line label is drawn in a margin left of the source
+            let margin = |line: usize| {
+                let inside = labels.multiline.iter().any(|(label, end, _)| {
+                    label.span.line() < line && line <= *end
+                });
+                match (labels.multiline.is_empty(), inside) {
+                    (true, _) => String::new(),
+                    (false, true) => {
+                        format!("{} ", self.paint_bold("|", Color::Blue))
+                    }
+                    (false, false) => "  ".to_string(),
+                }
+            };
+            let mut previous = None;
+            for (&line, line_labels) in &labels.lines {
+                if previous.is_some_and(|previous| line > previous + 1) {
+                    out.push_str(&self.paint_bold("\n...", Color::Blue));
+                }
+                previous = Some(line);
+                let text = source_file.line(line);
+                let number =
+                    self.paint_bold(&format!("{line:>width$} |"), Color::Blue);
+                let margin = margin(line);
+                out.push_str(&format!(
+                    "\n{number} {margin}{}",
+                    expand_tabs(text)
+                ));
+                for row in self.label_rows(text, line_labels, level_color) {
+                    out.push_str(&format!("\n{gutter} {margin}{row}"));
+                }
+                for (label, end_line, end_col) in &labels.multiline {
+                    let color =
+                        if label.primary { level_color } else { Color::Blue };
+                    let marker = if label.primary { "^" } else { "-" };
+                    if label.span.line() == line {
+                        let (start, _) = underline_range(text, label.span);
+                        let row = "_".repeat(start + 1) + marker;
+                        out.push_str(&format!(
+                            "\n{gutter}  {}",
+                            self.paint_bold(&row, color)
+                        ));
+                    }
+                    if *end_line == line {
+                        let end: usize = text
+                            .chars()
+                            .take(end_col - 1)
+                            .map(char_width)
+                            .sum();
+                        let mut row =
+                            format!("|{}{marker}", "_".repeat(end + 1));
+                        if !label.message.is_empty() {
+                            row = format!("{row} {}", label.message);
+                        }
+                        out.push_str(&format!(
+                            "\n{gutter} {}",
+                            self.paint_bold(&row, color)
+                        ));
+                    }
+                }
+            }
+        }
+        let notes = diagnostic
+            .notes
+            .iter()
+            .map(|note| ("note", note))
+            .chain(diagnostic.helps.iter().map(|help| ("help", help)));
+        for (index, (kind, message)) in notes.enumerate() {
+            if index == 0 && !diagnostic.labels.is_empty() {
+                out.push_str(&format!("\n{gutter}"));
+            }
+            out.push_str(&format!(
+                "\n{:width$} {} {message}",
+                "",
+                self.paint_bold(&format!("= {kind}:"), Color::Blue)
+            ));
+        }
+        out
+    }
+
+    /// the underline rows below a source line, the rightmost message is
+    /// on the underline row, the others hang below it
+    fn label_rows(
+        &self, text: &str, labels: &[&Label], level_color: Color,
+    ) -> Vec<String> {
+        let mut labels: Vec<_> = labels
+            .iter()
+            .map(|label| {
+                let (start, len) = underline_range(text, label.span);
+                (start, len, *label)
+            })
+            .collect();
+        labels.sort_by_key(|(start, ..)| *start);
+        let color = |label: &Label| {
+            if label.primary { level_color } else { Color::Blue }
+        };
+        // overlapping underlines are drawn over each other, the primary
+        // ones on top
+        let mut cells: Vec<Option<&Label>> = Vec::new();
+        for (start, len, label) in &labels {
+            if cells.len() < start + len {
+                cells.resize(start + len, None);
+            }
+            for cell in &mut cells[*start..start + len] {
+                if !cell.is_some_and(|other| other.primary) {
+                    *cell = Some(label);
+                }
+            }
+        }
+        let mut underline = String::new();
+        let primary = |cell: &Option<&Label>| cell.map(|label| label.primary);
+        for run in cells.chunk_by(|a, b| primary(a) == primary(b)) {
+            let Some(label) = run[0] else {
+                underline.push_str(&" ".repeat(run.len()));
+                continue;
+            };
+            let marker = if label.primary { "^" } else { "-" };
+            underline.push_str(
+                &self.paint_bold(&marker.repeat(run.len()), color(label)),
+            );
+        }
+        let mut rows = Vec::new();
+        let Some((_, _, last)) = labels.pop() else {
+            return rows;
+        };
+        if !last.message.is_empty() {
+            underline.push(' ');
+            underline.push_str(&self.paint_bold(&last.message, color(last)));
+        }
+        rows.push(underline);
+        // the other messages hang below the underlines, right to left
+        labels.retain(|(_, _, label)| !label.message.is_empty());
+        let connectors = |starts: &[usize]| {
+            let mut row = String::new();
+            for start in starts {
+                row.push_str(&" ".repeat(start.saturating_sub(row.len())));
+                row.push('|');
+            }
+            row
+        };
+        let mut starts: Vec<_> =
+            labels.iter().map(|(start, ..)| *start).collect();
+        for (start, _, label) in labels.iter().rev() {
+            rows.push(self.paint_bold(&connectors(&starts), Color::Blue));
+            starts.pop();
+            let row = connectors(&starts);
+            let padding = " ".repeat(start.saturating_sub(row.len()));
+            rows.push(format!(
+                "{}{padding}{}",
+                self.paint_bold(&row, Color::Blue),
+                self.paint_bold(&label.message, color(label))
+            ));
+        }
+        rows
+    }
+
+    fn paint(&self, text: &str, color: Color) -> String {
+        if self.color {
+            text.color(color).to_string()
+        } else {
+            text.to_string()
+        }
+    }
+
+    fn paint_bold(&self, text: &str, color: Color) -> String {
+        if self.color {
+            text.color(color).bold().to_string()
+        } else {
+            text.to_string()
+        }
+    }
+}
+impl Level {
+    fn color(&self) -> Color {
+        match self {
+            Level::Error => Color::Red,
+            Level::Warning => Color::Yellow,
+            Level::Note => Color::Green,
+            Level::Help => Color::Cyan,
+        }
+    }
+}
+/// the labels of a [Diagnostic] in one file
+#[derive(Default)]
+struct FileLabels<'a> {
+    /// every line to show with its single-line labels
+    lines:     BTreeMap<usize, Vec<&'a Label>>,
+    /// the labels spanning multiple lines, with the line and column of
+    /// their last char
+    multiline: Vec<(&'a Label, usize, usize)>,
+}
+/// the lines of a multi-line label shown in full, longer ones are elided
+/// after the first line
+const MAX_MULTILINE_LINES: usize = 4;
+/// tabs are rendered as 4 spaces
+fn expand_tabs(text: &str) -> String { text.replace('\t', "    ") }
+/// the display column and width of the underline of `span` on its first
+/// line `text`, at least one column wide
+fn underline_range(text: &str, span: Span) -> (usize, usize) {
+    let width = char_width;
+    let mut chars = text.chars().skip(span.col() - 1);
+    let start: usize = text.chars().take(span.col() - 1).map(width).sum();
+    let mut len = 0;
+    let mut bytes = 0;
+    while bytes < span.len() {
+        let Some(c) = chars.next() else {
+            break;
+        };
+        bytes += c.len_utf8();
+        len += width(c);
+    }
+    (start, len.max(1))
+}
+/// the display width of `c`, see [expand_tabs]
+fn char_width(c: char) -> usize { if c == '\t' { 4 } else { 1 } }
+/// the line and column of the last char of `span`
+fn end_line_col(file: &SourceFile, span: Span) -> (usize, usize) {
+    let last = file.source()[..span.hi()]
+        .char_indices()
+        .next_back()
+        .map_or(span.lo(), |(offset, _)| offset.max(span.lo()));
+    file.line_col(last)
+}
+impl From<Error> for Diagnostic {
+    fn from(error: Error) -> Self {
+        Diagnostic::error(error.msg()).with_primary(error.span(), "")
+    }
+}
+impl Display for Level {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            Level::Error => write!(f, "error"),
+            Level::Warning => write!(f, "warning"),
+            Level::Note => write!(f, "note"),
+            Level::Help => write!(f, "help"),
+        }
+    }
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+    #[test]
+    fn render_without_color() {
+        let mut source_map = SourceMap::new();
+        let source = "fn main() {\n\tlet x: i32 = \"a\";\n\n\n    x\n}\n";
+        let file = source_map.add_file("main.aau", source);
+        let other = source_map.add_file("other.aau", "fn f() {}");
+        let span = |lo: usize, len: usize| {
+            let (line, col) = source_map.file(file).line_col(lo);
+            Span::new(file, lo, lo + len, line, col)
+        };
+        let diagnostic = Diagnostic::error("mismatched types")
+            .with_code("E0308")
+            .with_primary(span(26, 3), "expected `i32`, found `str`")
+            .with_secondary(span(20, 3), "expected due to this")
+            .with_secondary(span(17, 1), "")
+            .with_secondary(span(37, 1), "used here")
+            .with_secondary(Span::new(other, 3, 4, 1, 4), "defined here")
+            .with_note("`str` is not `i32`")
+            .with_help("remove the quotes");
+        let rendered = Emitter::new(false).render(&source_map, &diagnostic);
+        assert_eq!(
+            rendered,
+            "\
+error[E0308]: mismatched types
+ --> main.aau:2:15
+  |
+2 |     let x: i32 = \"a\";
+  |         -  ---   ^^^ expected `i32`, found `str`
+  |            |
+  |            expected due to this
+...
+5 |     x
+  |     - used here
+ ::: other.aau:1:4
+  |
+1 | fn f() {}
+  |    - defined here
+  |
+  = note: `str` is not `i32`
+  = help: remove the quotes"
+        );
+        let mut diagnostics = Diagnostics::new();
+        diagnostics.push(Error::new(span(0, 2), "unexpected `fn`"));
+        diagnostics
+            .push(Diagnostic::warning("unused").with_primary(span(3, 4), ""));
+        let mut out = Vec::new();
+        Emitter::new(false).emit(&source_map, &diagnostics, &mut out).unwrap();
+        assert_eq!(
+            String::from_utf8(out).unwrap(),
+            "\
+error: unexpected `fn`
+ --> main.aau:1:1
+  |
+1 | fn main() {
+  | ^^
+warning: unused
+ --> main.aau:1:4
+  |
+1 | fn main() {
+  |    ^^^^
+error: aborting due to 1 previous error
+"
+        );
+    }
+    #[test]
+    fn render_multiline_spans() {
+        let mut source_map = SourceMap::new();
+        let source = "fn main() {\n    let a = {\n        1\n    };\n    let \
+                      b = a\n        + 1\n        + 2\n        + 3\n        + \
+                      4\n        + 5;\n}\n";
+        let file = source_map.add_file("main.aau", source);
+        let span = |text: &str| {
+            let lo = source.find(text).unwrap();
+            let (line, col) = source_map.file(file).line_col(lo);
+            Span::new(file, lo, lo + text.len(), line, col)
+        };
+        let render = |diagnostic: &Diagnostic| {
+            Emitter::new(false).render(&source_map, diagnostic)
+        };
+        assert_eq!(
+            render(
+                &Diagnostic::error("mismatched types")
+                    .with_primary(span("{\n        1\n    }"), "expected `()`")
+                    .with_secondary(span("a = "), "")
+            ),
+            "\
+error: mismatched types
+ --> main.aau:2:13
+  |
+2 |       let a = {
+  |           ----
+  |  _____________^
+3 | |         1
+4 | |     };
+  | |_____^ expected `()`"
+        );
+        // the lines inside a long span are elided
+        assert_eq!(
+            render(
+                &Diagnostic::error("mismatched types").with_primary(
+                    span(
+                        "a\n        + 1\n        + 2\n        + 3\n        + \
+                         4\n        + 5"
+                    ),
+                    ""
+                )
+            ),
+            "\
+error: mismatched types
+  --> main.aau:5:13
+   |
+ 5 |       let b = a
+   |  _____________^
+...
+10 | |         + 5;
+   | |___________^"
+        );
+    }
+    #[test]
+    fn render_labels_on_other_lines_and_notes() {
+        let mut source_map = SourceMap::new();
+        let source =
+            "fn main() {\n    let b = a\n        + 1\n        + 2;\n}\n";
+        let file = source_map.add_file("main.aau", source);
+        let span = |text: &str| {
+            let lo = source.find(text).unwrap();
+            let (line, col) = source_map.file(file).line_col(lo);
+            Span::new(file, lo, lo + text.len(), line, col)
+        };
+        let diagnostic = Diagnostic::warning("long expression")
+            .with_secondary(span("a\n        + 1"), "starts here")
+            .with_primary(span("+ 2"), "the last operand")
+            .with_secondary(span("main"), "in this function")
+            .with_note("a note")
+            .with_help("a help");
+        assert_eq!(
+            Emitter::new(false).render(&source_map, &diagnostic),
+            "\
+warning: long expression
+ --> main.aau:4:9
+  |
+1 |   fn main() {
+  |      ---- in this function
+2 |       let b = a
+  |  _____________-
+3 | |         + 1
+  | |___________- starts here
+4 |           + 2;
+  |           ^^^ the last operand
+  |
+  = note: a note
+  = help: a help"
+        );
+        let diagnostic = Diagnostic::error("no labels")
+            .with_note("a note")
+            .with_help("a help");
+        assert_eq!(
+            Emitter::new(false).render(&source_map, &diagnostic),
+            "error: no labels\n  = note: a note\n  = help: a help"
+        );
+    }
+}
@@ -10,13 +10,48 @@
 //
 // You should have received a copy of the GNU Affero General Public License
 // along with this program. If not, see <https://www.gnu.org/licenses/>.
-#[derive(Debug)]
+use std::fmt::Display;
+
+use crate::utils::span::Span;
+pub type Result<T> = std::result::Result<T, Error>;
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub struct Error {
-    pos: ErrorPosition,
-    msg: String,
+    pos:  ErrorPosition,
+    span: Span,
+    msg:  String,
 }
-#[derive(Debug)]
+#[derive(Debug, Clone, Copy, Hash, Ord, PartialOrd, PartialEq, Eq)]
 pub struct ErrorPosition {
     col:  usize,
     line: usize,
 }
+impl From<Span> for ErrorPosition {
+    fn from(span: Span) -> Self { Self { col: span.col(), line: span.line() } }
+}
+impl Error {
+    pub fn new(span: Span, msg: impl Into<String>) -> Self {
+        Self { pos: span.into(), span, msg: msg.into() }
+    }
+
+    pub fn pos(&self) -> ErrorPosition { self.pos }
+
+    pub fn span(&self) -> Span { self.span }
+
+    pub fn msg(&self) -> &str { &self.msg }
+}
+impl ErrorPosition {
+    pub fn line(&self) -> usize { self.line }
+
+    pub fn col(&self) -> usize { self.col }
+}
+impl Display for Error {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "{} at {}", self.msg, self.pos)
+    }
+}
+impl Display for ErrorPosition {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "{}:{}", self.line, self.col)
+    }
+}
+impl std::error::Error for Error {}
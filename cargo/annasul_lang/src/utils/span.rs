@@ -0,0 +1,229 @@
+// Copyright (c) 2025 air (https://yuanair.github.io).
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published by
+// the Free Software Foundation, version 3 of the License only.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+use std::{
+    fmt::Display,
+    path::{Path, PathBuf},
+};
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+/// index of a file in a [SourceMap]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Default, Debug, Clone, Copy, Hash, Ord, PartialOrd, PartialEq, Eq)]
+pub struct FileId(u32);
+/// a byte range in a file, plus the line and column where it starts
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Default, Debug, Clone, Copy, Hash, Ord, PartialOrd, PartialEq, Eq)]
+pub struct Span {
+    file:      FileId,
+    /// byte offset of the first byte
+    lo:        usize,
+    /// byte offset after the last byte
+    hi:        usize,
+    /// 1-based line of `lo`
+    line:      usize,
+    /// 1-based column of `lo`, in chars
+    col:       usize,
+    /// the macro expansion the span was produced by, 0 if it is written in
+    /// the source, see [SourceMap::expansion]
+    expansion: u32,
+}
+/// e.g. a token or an AST node together with its [Span]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Debug, Clone, Hash, Ord, PartialOrd, PartialEq, Eq)]
+pub struct Spanned<T> {
+    pub node: T,
+    pub span: Span,
+}
+/// every file of a compilation, so [Span]s can be resolved to source text
+#[derive(Default, Debug, Clone)]
+pub struct SourceMap {
+    files:      Vec<SourceFile>,
+    /// by [Span::expansion] minus one
+    expansions: Vec<Expansion>,
+}
+/// a macro call that was expanded, see [Span::expansion]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Expansion {
+    /// the name of the macro, without the `!`
+    pub name:      String,
+    pub call_site: Span,
+}
+#[derive(Debug, Clone)]
+pub struct SourceFile {
+    name:        PathBuf,
+    source:      String,
+    /// byte offset of the start of every line
+    line_starts: Vec<usize>,
+}
+impl Span {
+    pub fn new(
+        file: FileId, lo: usize, hi: usize, line: usize, col: usize,
+    ) -> Self {
+        Self { file, lo, hi, line, col, expansion: 0 }
+    }
+
+    /// `self` as produced by the expansion `expansion`
+    pub fn with_expansion(self, expansion: u32) -> Self {
+        Self { expansion, ..self }
+    }
+
+    pub fn file(&self) -> FileId { self.file }
+
+    pub fn lo(&self) -> usize { self.lo }
+
+    pub fn hi(&self) -> usize { self.hi }
+
+    pub fn line(&self) -> usize { self.line }
+
+    pub fn col(&self) -> usize { self.col }
+
+    pub fn expansion(&self) -> u32 { self.expansion }
+
+    pub fn len(&self) -> usize { self.hi - self.lo }
+
+    pub fn is_empty(&self) -> bool { self.lo == self.hi }
+
+    /// from the start of `self` to the end of `other`
+    pub fn to(&self, other: Span) -> Span {
+        Span { hi: other.hi.max(self.hi), ..*self }
+    }
+
+    /// `self` and `other` cover the same source text, maybe in different
+    /// expansions
+    pub fn same_source(&self, other: Span) -> bool {
+        (self.file, self.lo, self.hi) == (other.file, other.lo, other.hi)
+    }
+
+    /// `other` is inside `self`, e.g. a token inside a macro definition
+    pub fn contains(&self, other: Span) -> bool {
+        self.file == other.file && self.lo <= other.lo && other.hi <= self.hi
+    }
+
+    /// the empty span at the start of `self`
+    pub fn shrink_to_lo(&self) -> Span { Span { hi: self.lo, ..*self } }
+
+    /// the empty span at the end of `self`, the column counts the bytes of
+    /// `self` as chars, so `self` must be ASCII on one line, e.g. a
+    /// delimiter or a keyword
+    pub fn shrink_to_hi(&self) -> Span {
+        let len = self.len();
+        Span { lo: self.hi, col: self.col + len, ..*self }
+    }
+
+    /// like [Span::shrink_to_hi], with the exact line and column in
+    /// `source`, the text of the file of `self`
+    pub fn shrink_to_hi_in(&self, source: &str) -> Span {
+        let Some(text) = source.get(self.lo..self.hi) else {
+            return self.shrink_to_hi();
+        };
+        let (line, col) = match text.rfind('\n') {
+            Some(newline) => (
+                self.line + text.matches('\n').count(),
+                text[newline + 1..].chars().count() + 1,
+            ),
+            None => (self.line, self.col + text.chars().count()),
+        };
+        Span { lo: self.hi, line, col, ..*self }
+    }
+}
+impl<T> Spanned<T> {
+    pub fn new(node: T, span: Span) -> Self { Self { node, span } }
+
+    pub fn map<U>(self, f: impl FnOnce(T) -> U) -> Spanned<U> {
+        Spanned { node: f(self.node), span: self.span }
+    }
+
+    pub fn as_ref(&self) -> Spanned<&T> {
+        Spanned { node: &self.node, span: self.span }
+    }
+}
+impl SourceMap {
+    pub fn new() -> Self { Self::default() }
+
+    pub fn add_file(
+        &mut self, name: impl Into<PathBuf>, source: impl Into<String>,
+    ) -> FileId {
+        let source = source.into();
+        let line_starts = std::iter::once(0)
+            .chain(source.match_indices('\n').map(|(i, _)| i + 1))
+            .collect();
+        self.files.push(SourceFile { name: name.into(), source, line_starts });
+        FileId(self.files.len() as u32 - 1)
+    }
+
+    pub fn file(&self, file: FileId) -> &SourceFile {
+        &self.files[file.0 as usize]
+    }
+
+    pub fn files(&self) -> impl Iterator<Item = (FileId, &SourceFile)> {
+        self.files.iter().enumerate().map(|(i, file)| (FileId(i as u32), file))
+    }
+
+    /// records a macro call that was expanded, returns its
+    /// [Span::expansion], see [SourceMap::expansion_count]
+    pub fn add_expansion(&mut self, expansion: Expansion) -> u32 {
+        self.expansions.push(expansion);
+        self.expansions.len() as u32
+    }
+
+    /// the expansions added so far, the next is one more
+    pub fn expansion_count(&self) -> u32 { self.expansions.len() as u32 }
+
+    /// the expansion that produced `span`, [None] if it is written in the
+    /// source
+    pub fn expansion(&self, span: Span) -> Option<&Expansion> {
+        let index = span.expansion.checked_sub(1)?;
+        self.expansions.get(index as usize)
+    }
+
+    /// the expansions `span` originates in, the innermost first
+    pub fn backtrace(&self, span: Span) -> Vec<&Expansion> {
+        std::iter::successors(self.expansion(span), |expansion| {
+            self.expansion(expansion.call_site)
+        })
+        .collect()
+    }
+
+    /// the source text covered by `span`
+    pub fn snippet(&self, span: Span) -> &str {
+        &self.file(span.file).source[span.lo..span.hi]
+    }
+}
+impl SourceFile {
+    pub fn name(&self) -> &Path { &self.name }
+
+    pub fn source(&self) -> &str { &self.source }
+
+    pub fn line_count(&self) -> usize { self.line_starts.len() }
+
+    /// the 1-based `line` without its line break
+    pub fn line(&self, line: usize) -> &str {
+        let lo = self.line_starts[line - 1];
+        let hi =
+            self.line_starts.get(line).copied().unwrap_or(self.source.len());
+        self.source[lo..hi].trim_end_matches(['\n', '\r'])
+    }
+
+    /// the 1-based line and column (in chars) of a byte `offset`
+    pub fn line_col(&self, offset: usize) -> (usize, usize) {
+        let line = self.line_starts.partition_point(|&start| start <= offset);
+        let lo = self.line_starts[line - 1];
+        (line, self.source[lo..offset].chars().count() + 1)
+    }
+}
+impl Display for Span {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "{}:{}", self.line, self.col)
+    }
+}